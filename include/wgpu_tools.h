@@ -0,0 +1,49 @@
+#ifndef WGPU_TOOLS_H
+#define WGPU_TOOLS_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct WgpuToolsContext WgpuToolsContext;
+typedef struct WgpuToolsTexture WgpuToolsTexture;
+
+enum {
+    WGPU_TOOLS_FORMAT_RGBA8_UNORM = 0,
+    WGPU_TOOLS_FORMAT_RGBA8_UNORM_SRGB = 1,
+    WGPU_TOOLS_FORMAT_BGRA8_UNORM = 2,
+    WGPU_TOOLS_FORMAT_BGRA8_UNORM_SRGB = 3,
+    WGPU_TOOLS_FORMAT_RGBA16_FLOAT = 4,
+    WGPU_TOOLS_FORMAT_RGBA32_FLOAT = 5,
+};
+
+const char *wgpu_tools_last_error(void);
+
+WgpuToolsContext *wgpu_tools_context_create(void);
+void wgpu_tools_context_destroy(WgpuToolsContext *context);
+
+WgpuToolsTexture *wgpu_tools_texture_create(const WgpuToolsContext *context, const uint8_t *data,
+                                            size_t len, uint32_t width, uint32_t height,
+                                            uint32_t format);
+/* Only available when built with the `image` feature. */
+WgpuToolsTexture *wgpu_tools_texture_load(const WgpuToolsContext *context, const char *path,
+                                          bool srgb);
+WgpuToolsTexture *wgpu_tools_render_target_create(const WgpuToolsContext *context, uint32_t width,
+                                                  uint32_t height, uint32_t format);
+
+uint32_t wgpu_tools_texture_width(const WgpuToolsTexture *texture);
+uint32_t wgpu_tools_texture_height(const WgpuToolsTexture *texture);
+size_t wgpu_tools_texture_byte_size(const WgpuToolsTexture *texture);
+int64_t wgpu_tools_texture_read(const WgpuToolsContext *context, const WgpuToolsTexture *texture,
+                                uint8_t *out, size_t len);
+void wgpu_tools_texture_destroy(WgpuToolsTexture *texture);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
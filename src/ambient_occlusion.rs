@@ -0,0 +1,256 @@
+use super::bvh::Bvh;
+use super::context::Context;
+use super::diagnostics::{describe_buffer, PassRecord};
+use super::error::Error;
+use super::mesh::Mesh;
+use super::progress::Progress;
+use super::texel_bake::{TexelBake, TexelPost};
+use super::texture::Texture;
+use wgpu::util::DeviceExt;
+
+const COSINE_WEIGHTED: u32 = 1;
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    size: [u32; 2],
+    sample_index: u32,
+    flags: u32,
+    distance: f32,
+    bias: f32,
+    _padding: [u32; 2],
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AmbientOcclusionSettings {
+    /// Width and height of the texture in texels.
+    pub resolution: u32,
+    /// Rays cast per texel.
+    pub samples: u32,
+    /// Distance beyond which geometry no longer occludes, `None` for any.
+    pub distance: Option<f32>,
+    /// Weights occluders by the cosine to the normal, as diffuse lighting
+    /// does, instead of counting the whole hemisphere evenly.
+    pub cosine_weighted: bool,
+    /// Texels between charts when unwrapping.
+    pub padding: u32,
+    /// Texels the charts are grown by to hide seams when filtering.
+    pub dilation: u32,
+}
+
+impl Default for AmbientOcclusionSettings {
+    fn default() -> Self {
+        Self {
+            resolution: 256,
+            samples: 64,
+            distance: None,
+            cosine_weighted: true,
+            padding: 2,
+            dilation: 2,
+        }
+    }
+}
+
+/// Ambient occlusion baking by casting rays from each texel of a mesh's
+/// `uv2` space against a BVH of the scene on the GPU.
+pub struct AmbientOcclusionBaker {
+    layout: wgpu::BindGroupLayout,
+    bvh_layout: wgpu::BindGroupLayout,
+    occlusion: wgpu::ComputePipeline,
+    post: TexelPost,
+}
+
+impl AmbientOcclusionBaker {
+    pub fn new(context: &Context) -> Self {
+        let device = &context.device;
+        let source = include_str!("shaders/raytracing.wgsl").to_string()
+            + include_str!("shaders/ambient_occlusion.wgsl");
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("ambient_occlusion.wgsl"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let entry = |binding, ty| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty,
+            count: None,
+        };
+        let storage = |read_only| wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        };
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Ambient Occlusion Bind Group Layout"),
+            entries: &[
+                entry(
+                    0,
+                    wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                ),
+                entry(1, storage(true)),
+                entry(2, storage(true)),
+                entry(3, storage(false)),
+            ],
+        });
+        let bvh_layout = Bvh::bind_group_layout(context);
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Ambient Occlusion Pipeline Layout"),
+            bind_group_layouts: &[&layout, &bvh_layout],
+            push_constant_ranges: &[],
+        });
+
+        Self {
+            occlusion: device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("occlusion"),
+                layout: Some(&pipeline_layout),
+                module: &module,
+                entry_point: "occlusion",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            post: TexelPost::new(context),
+            layout,
+            bvh_layout,
+        }
+    }
+
+    /// Bakes the ambient occlusion of `mesh` into an `Rgba16Float` texture
+    /// over its `uv2`, which is generated with `Mesh::unwrap_lightmap_uvs`
+    /// if the mesh has none. `occluders` block rays along with the mesh
+    /// itself, all in the same world space. The color channels hold the
+    /// unoccluded fraction of the hemisphere, 1 in the open, and alpha is 1
+    /// where the mesh covers the texture.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(resolution = settings.resolution, samples = settings.samples),
+            err
+        )
+    )]
+    pub fn bake(
+        &self,
+        context: &Context,
+        mesh: &mut Mesh,
+        occluders: &[&Mesh],
+        settings: &AmbientOcclusionSettings,
+    ) -> Result<Texture, Error> {
+        self.bake_with_progress(context, mesh, occluders, settings, &mut Progress::new())
+    }
+
+    /// Like `bake`, reporting the "bvh" build and the "ambient occlusion"
+    /// samples finished on the GPU to `progress`, and stopping with
+    /// `Error::Cancelled` once its token is cancelled.
+    pub fn bake_with_progress(
+        &self,
+        context: &Context,
+        mesh: &mut Mesh,
+        occluders: &[&Mesh],
+        settings: &AmbientOcclusionSettings,
+        progress: &mut Progress,
+    ) -> Result<Texture, Error> {
+        let bake = TexelBake::new(
+            context,
+            &self.bvh_layout,
+            mesh,
+            occluders,
+            settings.resolution,
+            settings.padding,
+            progress,
+        )?;
+        let device = &context.device;
+        let samples = settings.samples.max(1);
+        let distance = settings.distance.filter(|d| *d > 0.0).unwrap_or(f32::MAX);
+        let workgroups = bake.workgroups();
+
+        context.diagnostics.record_pass(|| {
+            PassRecord::new(
+                "Ambient Occlusion Bake",
+                "occlusion",
+                vec![describe_buffer("accumulation", &bake.accumulation)],
+            )
+        });
+        // One submission per sample keeps each one short, and waiting for
+        // the previous one before recording the next keeps progress honest
+        // and cancellation prompt.
+        let mut previous = None;
+        for sample_index in 0..samples {
+            let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Ambient Occlusion Params"),
+                contents: bytemuck::bytes_of(&Params {
+                    size: [bake.resolution; 2],
+                    sample_index,
+                    flags: match settings.cosine_weighted {
+                        true => COSINE_WEIGHTED,
+                        false => 0,
+                    },
+                    distance,
+                    bias: bake.bias,
+                    _padding: [0; 2],
+                }),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Ambient Occlusion Bind Group"),
+                layout: &self.layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: params.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: bake.positions.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: bake.normals.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: bake.accumulation.as_entire_binding(),
+                    },
+                ],
+            });
+            let mut command_encoder =
+                device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Ambient Occlusion Encoder"),
+                });
+            {
+                let mut pass = command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some("Ambient Occlusion"),
+                    timestamp_writes: None,
+                });
+                pass.set_pipeline(&self.occlusion);
+                pass.set_bind_group(0, &bind_group, &[]);
+                pass.set_bind_group(1, &bake.bvh_group, &[]);
+                pass.dispatch_workgroups(workgroups, workgroups, 1);
+            }
+            let index = context.submit(command_encoder);
+            if let Some(previous) = previous.replace(index) {
+                context
+                    .device
+                    .poll(wgpu::Maintain::WaitForSubmissionIndex(previous));
+            }
+            progress.report("ambient occlusion", sample_index as u64, samples as u64)?;
+        }
+        if let Some(previous) = previous {
+            context
+                .device
+                .poll(wgpu::Maintain::WaitForSubmissionIndex(previous));
+        }
+        progress.report("ambient occlusion", samples as u64, samples as u64)?;
+
+        Ok(self.post.finish(
+            context,
+            &bake,
+            1.0 / samples as f32,
+            settings.dilation,
+            "Ambient Occlusion",
+        ))
+    }
+}
@@ -0,0 +1,252 @@
+use super::clock::Clock;
+use super::context::Context;
+use super::error::Error;
+use super::texture::Texture;
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+use image::{AnimationDecoder, Delay, Frame};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::time::Duration;
+
+/// Frames without a delay are shown this long, as browsers do.
+const DEFAULT_FRAME_DURATION: Duration = Duration::from_millis(100);
+
+/// Frames of an animation in the layers of one array texture, with a view
+/// of the current frame to bind like a 2D texture.
+#[derive(Debug)]
+pub struct AnimatedTexture {
+    /// All frames, viewed as a 2D array.
+    pub texture: Texture,
+    frame_views: Vec<wgpu::TextureView>,
+    durations: Vec<Duration>,
+    frame: usize,
+    /// Restart after the last frame instead of holding it.
+    pub looping: bool,
+}
+
+impl AnimatedTexture {
+    pub fn frame_count(&self) -> usize {
+        self.durations.len()
+    }
+
+    pub fn frame_durations(&self) -> &[Duration] {
+        &self.durations
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.durations.iter().sum()
+    }
+
+    /// The current frame.
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    pub fn set_frame(&mut self, frame: usize) {
+        self.frame = frame.min(self.frame_count() - 1);
+    }
+
+    /// The frame shown `elapsed` after the start.
+    pub fn frame_at(&self, elapsed: Duration) -> usize {
+        let total = self.duration();
+        let mut time = match self.looping {
+            true if !total.is_zero() => {
+                Duration::from_nanos((elapsed.as_nanos() % total.as_nanos()) as u64)
+            }
+            _ => elapsed,
+        };
+        for (frame, duration) in self.durations.iter().enumerate() {
+            if time < *duration {
+                return frame;
+            }
+            time -= *duration;
+        }
+        self.frame_count() - 1
+    }
+
+    /// Moves to the frame at the clock's elapsed time and returns whether
+    /// it changed.
+    pub fn update(&mut self, clock: &Clock) -> bool {
+        let frame = self.frame_at(clock.elapsed());
+        let changed = frame != self.frame;
+        self.frame = frame;
+        changed
+    }
+
+    /// A 2D view of the current frame.
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.frame_views[self.frame]
+    }
+
+    pub fn frame_view(&self, frame: usize) -> Option<&wgpu::TextureView> {
+        self.frame_views.get(frame)
+    }
+
+    pub fn sampler(&self) -> &wgpu::Sampler {
+        &self.texture.sampler
+    }
+}
+
+impl<'a> From<&'a AnimatedTexture> for wgpu::BindingResource<'a> {
+    fn from(texture: &'a AnimatedTexture) -> Self {
+        wgpu::BindingResource::TextureView(texture.view())
+    }
+}
+
+impl Context {
+    /// Loads an animated GIF or PNG, or a still image as a single frame.
+    pub fn load_animated_texture(
+        &self,
+        path: impl AsRef<Path>,
+        texture_format: &wgpu::TextureFormat,
+        label: Option<&str>,
+    ) -> Result<AnimatedTexture, Error> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+        let frames = match extension.as_deref() {
+            Some("gif") => GifDecoder::new(BufReader::new(File::open(path)?))?
+                .into_frames()
+                .collect_frames()?,
+            Some("png" | "apng") => {
+                let decoder = PngDecoder::new(BufReader::new(File::open(path)?))?;
+                match decoder.is_apng()? {
+                    true => decoder.apng()?.into_frames().collect_frames()?,
+                    false => vec![Frame::new(
+                        image::DynamicImage::from_decoder(decoder)?.into_rgba8(),
+                    )],
+                }
+            }
+            _ => vec![Frame::new(image::open(path)?.into_rgba8())],
+        };
+        self.animated_texture_from_frames(&frames, texture_format, label)
+    }
+
+    /// Loads one frame per image, each shown for `frame_duration`.
+    pub fn load_image_sequence(
+        &self,
+        paths: &[impl AsRef<Path>],
+        frame_duration: Duration,
+        texture_format: &wgpu::TextureFormat,
+        label: Option<&str>,
+    ) -> Result<AnimatedTexture, Error> {
+        let frames = paths
+            .iter()
+            .map(|path| {
+                Ok(Frame::from_parts(
+                    image::open(path)?.into_rgba8(),
+                    0,
+                    0,
+                    Delay::from_saturating_duration(frame_duration),
+                ))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        self.animated_texture_from_frames(&frames, texture_format, label)
+    }
+
+    /// Uploads full-size frames into the layers of an `Rgba8Unorm` or
+    /// `Rgba8UnormSrgb` array texture.
+    pub fn animated_texture_from_frames(
+        &self,
+        frames: &[Frame],
+        texture_format: &wgpu::TextureFormat,
+        label: Option<&str>,
+    ) -> Result<AnimatedTexture, Error> {
+        let first = frames
+            .first()
+            .ok_or_else(|| Error::InvalidAnimation("no frames".to_string()))?;
+        let (width, height) = first.buffer().dimensions();
+        if let Some(frame) = frames.iter().find(|frame| {
+            frame.buffer().dimensions() != (width, height) || frame.left() != 0 || frame.top() != 0
+        }) {
+            let (frame_width, frame_height) = frame.buffer().dimensions();
+            return Err(Error::InvalidAnimation(format!(
+                "frame of {frame_width}x{frame_height} at ({}, {}) in a {width}x{height} animation",
+                frame.left(),
+                frame.top()
+            )));
+        }
+        if !matches!(
+            texture_format,
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb
+        ) {
+            return Err(Error::UnsupportedTextureFormat(*texture_format));
+        }
+
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: frames.len() as u32,
+        };
+        self.check_texture_size(&size, wgpu::TextureDimension::D2)?;
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: *texture_format,
+            view_formats: &[*texture_format],
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
+        });
+        for (layer, frame) in frames.iter().enumerate() {
+            self.queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    aspect: wgpu::TextureAspect::All,
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: layer as u32,
+                    },
+                },
+                frame.buffer(),
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * width),
+                    rows_per_image: Some(height),
+                },
+                wgpu::Extent3d {
+                    depth_or_array_layers: 1,
+                    ..size
+                },
+            );
+        }
+
+        let frame_views = (0..size.depth_or_array_layers)
+            .map(|layer| {
+                texture.create_view(&wgpu::TextureViewDescriptor {
+                    dimension: Some(wgpu::TextureViewDimension::D2),
+                    base_array_layer: layer,
+                    array_layer_count: Some(1),
+                    ..Default::default()
+                })
+            })
+            .collect();
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+        let durations = frames
+            .iter()
+            .map(|frame| match Duration::from(frame.delay()) {
+                duration if duration.is_zero() => DEFAULT_FRAME_DURATION,
+                duration => duration,
+            })
+            .collect();
+        Ok(AnimatedTexture {
+            texture: Texture::from_raw(texture, view, self.texture_sampler())
+                .with_view_dimension(wgpu::TextureViewDimension::D2Array),
+            frame_views,
+            durations,
+            frame: 0,
+            looping: true,
+        })
+    }
+}
@@ -0,0 +1,487 @@
+use super::clock::Clock;
+use super::error::Error;
+use super::math::{lerp, slerp};
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Interpolation {
+    /// Hold each keyframe until the next.
+    Step,
+    #[default]
+    Linear,
+}
+
+/// The animated property of a target, such as a node, a joint or a mesh
+/// with morph targets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Property {
+    Translation,
+    Rotation,
+    Scale,
+    Weights,
+}
+
+/// Keyframe values of a channel, one per keyframe time. Rotations are unit
+/// quaternions `[x, y, z, w]`, weights hold the same number of morph target
+/// weights for every keyframe.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Keyframes {
+    Translation(Vec<[f32; 3]>),
+    Rotation(Vec<[f32; 4]>),
+    Scale(Vec<[f32; 3]>),
+    Weights(Vec<f32>),
+}
+
+impl Keyframes {
+    pub fn property(&self) -> Property {
+        match self {
+            Keyframes::Translation(_) => Property::Translation,
+            Keyframes::Rotation(_) => Property::Rotation,
+            Keyframes::Scale(_) => Property::Scale,
+            Keyframes::Weights(_) => Property::Weights,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Keyframes::Translation(values) | Keyframes::Scale(values) => values.len(),
+            Keyframes::Rotation(values) => values.len(),
+            Keyframes::Weights(values) => values.len(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChannelValue {
+    Translation([f32; 3]),
+    Rotation([f32; 4]),
+    Scale([f32; 3]),
+    Weights(Vec<f32>),
+}
+
+impl ChannelValue {
+    pub fn property(&self) -> Property {
+        match self {
+            ChannelValue::Translation(_) => Property::Translation,
+            ChannelValue::Rotation(_) => Property::Rotation,
+            ChannelValue::Scale(_) => Property::Scale,
+            ChannelValue::Weights(_) => Property::Weights,
+        }
+    }
+
+    /// `self` at 0 and `other` at 1, spherically for rotations. Values of
+    /// different properties don't mix, `self` is returned.
+    pub fn interpolate(&self, other: &ChannelValue, t: f32) -> ChannelValue {
+        match (self, other) {
+            (ChannelValue::Translation(a), ChannelValue::Translation(b)) => {
+                ChannelValue::Translation(lerp(*a, *b, t))
+            }
+            (ChannelValue::Rotation(a), ChannelValue::Rotation(b)) => {
+                ChannelValue::Rotation(slerp(*a, *b, t))
+            }
+            (ChannelValue::Scale(a), ChannelValue::Scale(b)) => {
+                ChannelValue::Scale(lerp(*a, *b, t))
+            }
+            (ChannelValue::Weights(a), ChannelValue::Weights(b)) => {
+                ChannelValue::Weights(a.iter().zip(b).map(|(a, b)| a + (b - a) * t).collect())
+            }
+            _ => self.clone(),
+        }
+    }
+}
+
+/// Keyframes of one property of one target.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Channel {
+    target: String,
+    times: Vec<f32>,
+    keyframes: Keyframes,
+    pub interpolation: Interpolation,
+}
+
+impl Channel {
+    /// `times` are in seconds and must be ascending, with one keyframe (or
+    /// one set of weights) per time.
+    pub fn new(target: &str, times: Vec<f32>, keyframes: Keyframes) -> Result<Self, Error> {
+        if times.is_empty() {
+            return Err(Error::InvalidAnimation("channel has no keyframes".into()));
+        }
+        if times.iter().any(|time| !time.is_finite()) || times.windows(2).any(|w| w[0] > w[1]) {
+            return Err(Error::InvalidAnimation(
+                "keyframe times are not ascending".into(),
+            ));
+        }
+        let count = keyframes.len();
+        let matches = match keyframes {
+            Keyframes::Weights(_) => count > 0 && count.is_multiple_of(times.len()),
+            _ => count == times.len(),
+        };
+        if !matches {
+            return Err(Error::InvalidAnimation(format!(
+                "{count} keyframe values for {} times",
+                times.len()
+            )));
+        }
+        Ok(Self {
+            target: target.to_string(),
+            times,
+            keyframes,
+            interpolation: Interpolation::Linear,
+        })
+    }
+
+    pub fn with_interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    pub fn times(&self) -> &[f32] {
+        &self.times
+    }
+
+    pub fn keyframes(&self) -> &Keyframes {
+        &self.keyframes
+    }
+
+    pub fn property(&self) -> Property {
+        self.keyframes.property()
+    }
+
+    fn value(&self, index: usize) -> ChannelValue {
+        match &self.keyframes {
+            Keyframes::Translation(values) => ChannelValue::Translation(values[index]),
+            Keyframes::Rotation(values) => ChannelValue::Rotation(values[index]),
+            Keyframes::Scale(values) => ChannelValue::Scale(values[index]),
+            Keyframes::Weights(values) => {
+                let stride = values.len() / self.times.len();
+                ChannelValue::Weights(values[index * stride..(index + 1) * stride].to_vec())
+            }
+        }
+    }
+
+    /// The value at `time`, holding the first and last keyframes outside
+    /// their range.
+    pub fn sample(&self, time: f32) -> ChannelValue {
+        let next = self.times.partition_point(|&t| t <= time);
+        if next == 0 {
+            return self.value(0);
+        }
+        if next == self.times.len() || self.interpolation == Interpolation::Step {
+            return self.value(next - 1);
+        }
+        let (start, end) = (self.times[next - 1], self.times[next]);
+        let t = (time - start) / (end - start);
+        self.value(next - 1).interpolate(&self.value(next), t)
+    }
+}
+
+/// A named point in a clip, reported by `AnimationPlayer::advance` when
+/// playback passes it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimationEvent {
+    /// Seconds.
+    pub time: f32,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AnimationClip {
+    pub name: String,
+    pub channels: Vec<Channel>,
+    pub events: Vec<AnimationEvent>,
+}
+
+impl AnimationClip {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_channel(mut self, channel: Channel) -> Self {
+        self.channels.push(channel);
+        self
+    }
+
+    pub fn with_event(mut self, time: f32, name: &str) -> Self {
+        self.events.push(AnimationEvent {
+            time,
+            name: name.to_string(),
+        });
+        self
+    }
+
+    /// Seconds up to the last keyframe or event.
+    pub fn duration(&self) -> f32 {
+        let keyframes = self.channels.iter().filter_map(|c| c.times.last().copied());
+        let events = self.events.iter().map(|event| event.time);
+        keyframes.chain(events).fold(0.0, f32::max)
+    }
+
+    pub fn sample(&self, time: f32) -> Pose {
+        let values = self
+            .channels
+            .iter()
+            .map(|channel| {
+                let key = (channel.target.clone(), channel.property());
+                (key, channel.sample(time))
+            })
+            .collect();
+        Pose { values }
+    }
+}
+
+/// Channel values by target and property, to apply to node transforms,
+/// joints or morph target weights.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Pose {
+    pub values: HashMap<(String, Property), ChannelValue>,
+}
+
+impl Pose {
+    pub fn get(&self, target: &str, property: Property) -> Option<&ChannelValue> {
+        self.values.get(&(target.to_string(), property))
+    }
+
+    /// `self` at 0 and `other` at 1. Values in only one of the poses are
+    /// kept as they are.
+    pub fn blend(&self, other: &Pose, weight: f32) -> Pose {
+        let mut values = self.values.clone();
+        for (key, value) in &other.values {
+            let blended = match self.values.get(key) {
+                Some(own) => own.interpolate(value, weight),
+                None => value.clone(),
+            };
+            values.insert(key.clone(), blended);
+        }
+        Pose { values }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PlaybackMode {
+    /// Stop at the end.
+    Once,
+    #[default]
+    Loop,
+    /// Alternate between playing forwards and backwards.
+    PingPong,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Playback {
+    clip: usize,
+    time: f32,
+    /// False while a ping-pong cycle plays back.
+    forward: bool,
+    /// Whether events at `time` are still to be reported, after a start or
+    /// a wrap.
+    at_start: bool,
+}
+
+/// Plays clips with looping, ping-pong, speed and crossfades between two
+/// clips, reporting the events passed.
+#[derive(Clone, Debug)]
+pub struct AnimationPlayer {
+    clips: Vec<AnimationClip>,
+    current: Option<Playback>,
+    previous: Option<Playback>,
+    fade: f32,
+    fade_elapsed: f32,
+    finished: bool,
+    pub mode: PlaybackMode,
+    /// Playback rate, negative to play backwards.
+    pub speed: f32,
+}
+
+impl AnimationPlayer {
+    pub fn new(clips: Vec<AnimationClip>) -> Self {
+        Self {
+            clips,
+            current: None,
+            previous: None,
+            fade: 0.0,
+            fade_elapsed: 0.0,
+            finished: false,
+            mode: PlaybackMode::default(),
+            speed: 1.0,
+        }
+    }
+
+    pub fn clips(&self) -> &[AnimationClip] {
+        &self.clips
+    }
+
+    pub fn clip_index(&self, name: &str) -> Option<usize> {
+        self.clips.iter().position(|clip| clip.name == name)
+    }
+
+    /// Starts `clip` from its beginning, or from its end when the speed is
+    /// negative, crossfading from the playing clip over `fade`.
+    pub fn play(&mut self, clip: usize, fade: Duration) -> Result<(), Error> {
+        let duration = self
+            .clips
+            .get(clip)
+            .ok_or_else(|| Error::InvalidAnimation(format!("no clip {clip}")))?
+            .duration();
+        self.previous = match fade.is_zero() {
+            true => None,
+            false => self.current,
+        };
+        self.fade = fade.as_secs_f32();
+        self.fade_elapsed = 0.0;
+        self.finished = false;
+        self.current = Some(Playback {
+            clip,
+            time: if self.speed < 0.0 { duration } else { 0.0 },
+            forward: true,
+            at_start: true,
+        });
+        Ok(())
+    }
+
+    pub fn stop(&mut self) {
+        self.current = None;
+        self.previous = None;
+    }
+
+    pub fn current_clip(&self) -> Option<usize> {
+        self.current.map(|playback| playback.clip)
+    }
+
+    /// Seconds into the current clip.
+    pub fn time(&self) -> f32 {
+        self.current.map_or(0.0, |playback| playback.time)
+    }
+
+    pub fn seek(&mut self, time: f32) {
+        if let Some(playback) = &mut self.current {
+            let duration = self.clips[playback.clip].duration();
+            playback.time = time.clamp(0.0, duration);
+            playback.at_start = true;
+            self.finished = false;
+        }
+    }
+
+    /// Whether a `Once` clip reached its end.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn is_fading(&self) -> bool {
+        self.previous.is_some()
+    }
+
+    /// Advances by `delta` and returns the events of the current clip that
+    /// playback passed, in order.
+    pub fn advance(&mut self, delta: Duration) -> Vec<AnimationEvent> {
+        let delta = delta.as_secs_f32();
+        let mut events = Vec::new();
+        if let Some(mut previous) = self.previous {
+            self.fade_elapsed += delta;
+            if self.fade_elapsed >= self.fade {
+                self.previous = None;
+            } else {
+                self.step(&mut previous, delta, &mut Vec::new());
+                self.previous = Some(previous);
+            }
+        }
+        if let Some(mut current) = self.current {
+            self.finished = self.step(&mut current, delta, &mut events);
+            self.current = Some(current);
+        }
+        events
+    }
+
+    /// Advances by the clock's frame delta.
+    pub fn update(&mut self, clock: &Clock) -> Vec<AnimationEvent> {
+        self.advance(clock.delta())
+    }
+
+    /// The current clip's pose, blended with the previous clip's during a
+    /// crossfade.
+    pub fn pose(&self) -> Pose {
+        let sample = |playback: &Playback| self.clips[playback.clip].sample(playback.time);
+        let Some(current) = &self.current else {
+            return Pose::default();
+        };
+        match &self.previous {
+            Some(previous) => {
+                let weight = (self.fade_elapsed / self.fade).clamp(0.0, 1.0);
+                sample(previous).blend(&sample(current), weight)
+            }
+            None => sample(current),
+        }
+    }
+
+    /// Moves `playback` by `delta` seconds at the player's speed, collecting
+    /// passed events, and returns whether a `Once` clip reached its end.
+    fn step(&self, playback: &mut Playback, delta: f32, events: &mut Vec<AnimationEvent>) -> bool {
+        let clip = &self.clips[playback.clip];
+        let duration = clip.duration();
+        let mut remaining = (delta * self.speed).abs();
+        if duration <= 0.0 {
+            playback.time = 0.0;
+            return self.mode == PlaybackMode::Once;
+        }
+        // Whole cycles end where they started.
+        let cycle = match self.mode {
+            PlaybackMode::Once => f32::INFINITY,
+            PlaybackMode::Loop => duration,
+            PlaybackMode::PingPong => 2.0 * duration,
+        };
+        if remaining > cycle {
+            remaining %= cycle;
+        }
+
+        loop {
+            let increasing = (self.speed >= 0.0) == playback.forward;
+            let boundary = if increasing { duration } else { 0.0 };
+            let span = (boundary - playback.time).abs();
+            let from = playback.time;
+            let reached = remaining >= span;
+            playback.time = match reached {
+                true => boundary,
+                false if increasing => from + remaining,
+                false => from - remaining,
+            };
+            let to = playback.time;
+            let (low, high) = (from.min(to), from.max(to));
+            let mut passed: Vec<&AnimationEvent> = clip
+                .events
+                .iter()
+                .filter(|event| {
+                    let time = event.time;
+                    (time == from && playback.at_start)
+                        || (time == to && to != from)
+                        || (low < time && time < high)
+                })
+                .collect();
+            passed.sort_by(|a, b| match increasing {
+                true => a.time.total_cmp(&b.time),
+                false => b.time.total_cmp(&a.time),
+            });
+            events.extend(passed.into_iter().cloned());
+            playback.at_start = false;
+            if !reached {
+                return false;
+            }
+            remaining -= span;
+            match self.mode {
+                PlaybackMode::Once => return true,
+                _ if remaining <= 0.0 => return false,
+                PlaybackMode::Loop => {
+                    playback.time = duration - boundary;
+                    playback.at_start = true;
+                }
+                PlaybackMode::PingPong => playback.forward = !playback.forward,
+            }
+        }
+    }
+}
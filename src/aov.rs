@@ -0,0 +1,124 @@
+use super::context::Context;
+use super::error::Error;
+use super::limits::check_limit;
+use super::texture::Texture;
+
+/// An arbitrary output variable: a pass rendered alongside the beauty for
+/// compositing, denoising or debugging.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Aov {
+    /// Linear base color of the surfaces' own materials.
+    Albedo,
+    /// World-space normals, signed.
+    Normal,
+    /// View depth along the camera axis, 0 where there is no surface.
+    Depth,
+    /// Index of the scene object plus one, 0 where there is no surface.
+    ObjectId,
+    /// Screen-space motion since the last frame, in UV units, as
+    /// `TemporalResources::motion` and `DenoiseHistory::motion` take.
+    Motion,
+}
+
+impl Aov {
+    pub const ALL: [Aov; 5] = [
+        Aov::Albedo,
+        Aov::Normal,
+        Aov::Depth,
+        Aov::ObjectId,
+        Aov::Motion,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Aov::Albedo => "albedo",
+            Aov::Normal => "normal",
+            Aov::Depth => "depth",
+            Aov::ObjectId => "object id",
+            Aov::Motion => "motion",
+        }
+    }
+
+    pub fn format(self) -> wgpu::TextureFormat {
+        match self {
+            Aov::Albedo => wgpu::TextureFormat::Rgba8Unorm,
+            Aov::Normal => wgpu::TextureFormat::Rgba16Float,
+            Aov::Depth | Aov::ObjectId => wgpu::TextureFormat::R32Float,
+            Aov::Motion => wgpu::TextureFormat::Rg16Float,
+        }
+    }
+
+    /// The color attachment the renderers write the AOV to, after the
+    /// beauty at 0.
+    pub(crate) fn location(self) -> usize {
+        1 + Aov::ALL.iter().position(|&aov| aov == self).unwrap_or(0)
+    }
+}
+
+/// Render targets of the requested AOVs, which can be sampled, copied and
+/// captured with `FrameCapture::capture_aovs`.
+pub struct AovTargets {
+    targets: Vec<(Aov, Texture)>,
+}
+
+impl AovTargets {
+    /// Targets of `aovs` for a `width` x `height` frame.
+    pub fn new(context: &Context, width: &u32, height: &u32, aovs: &[Aov]) -> Result<Self, Error> {
+        let mut targets: Vec<(Aov, Texture)> = Vec::new();
+        for &aov in aovs {
+            if targets.iter().any(|(existing, _)| *existing == aov) {
+                continue;
+            }
+            let label = format!("AOV {}", aov.name());
+            let target = context.render_target(width, height, &aov.format(), Some(&label))?;
+            targets.push((aov, target));
+        }
+        targets.sort_by_key(|(aov, _)| aov.location());
+        Ok(Self { targets })
+    }
+
+    pub fn get(&self, aov: Aov) -> Option<&Texture> {
+        self.targets
+            .iter()
+            .find(|(existing, _)| *existing == aov)
+            .map(|(_, target)| target)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Aov, &Texture)> {
+        self.targets.iter().map(|(aov, target)| (*aov, target))
+    }
+
+    /// The beauty `target`, at location 0, and the AOV targets at their
+    /// locations, split into groups of attachments that fit the device's
+    /// limit on color bytes per sample, one render pass each.
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn attachment_groups<'a>(
+        &'a self,
+        context: &Context,
+        target: &'a Texture,
+    ) -> Result<Vec<Vec<(usize, &'a Texture)>>, Error> {
+        let limit = context
+            .device
+            .limits()
+            .max_color_attachment_bytes_per_sample;
+        let mut groups: Vec<Vec<(usize, &Texture)>> = vec![Vec::new()];
+        let mut bytes = 0u32;
+        let attachments = std::iter::once((0, target))
+            .chain(self.iter().map(|(aov, texture)| (aov.location(), texture)));
+        for (location, texture) in attachments {
+            let format = texture.format();
+            let cost = format.target_pixel_byte_cost().unwrap_or(0);
+            let alignment = format.target_component_alignment().unwrap_or(1);
+            check_limit("max_color_attachment_bytes_per_sample", limit, cost)?;
+            bytes = bytes.next_multiple_of(alignment) + cost;
+            if bytes > limit {
+                groups.push(Vec::new());
+                bytes = cost;
+            }
+            if let Some(group) = groups.last_mut() {
+                group.push((location, texture));
+            }
+        }
+        Ok(groups)
+    }
+}
@@ -0,0 +1,328 @@
+use super::context::Context;
+use super::error::Error;
+use std::ops::Range;
+
+/// A range of one of a `BufferArena`'s buffers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ArenaAllocation {
+    block: usize,
+    offset: u64,
+    size: u64,
+}
+
+impl ArenaAllocation {
+    /// Index of the arena buffer holding the allocation.
+    pub fn block(&self) -> usize {
+        self.block
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Size in bytes, rounded up to the arena's alignment.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+struct ArenaBlock {
+    buffer: wgpu::Buffer,
+    /// Free ranges sorted by offset, never adjacent.
+    free: Vec<Range<u64>>,
+}
+
+/// Packs many small allocations (vertices, indices, uniforms) into a few
+/// large buffers. Allocations are placed first-fit, freed ranges are merged
+/// with their neighbours and reused, and a new buffer is created when no
+/// existing one has room.
+pub struct BufferArena {
+    usage: wgpu::BufferUsages,
+    block_size: u64,
+    alignment: u64,
+    label: Option<String>,
+    blocks: Vec<ArenaBlock>,
+}
+
+impl BufferArena {
+    /// Offsets are aligned to the device's uniform or storage offset
+    /// alignment if `usage` contains those usages. `COPY_DST` is added so
+    /// allocations can be written through the queue.
+    pub fn new(
+        context: &Context,
+        block_size: u64,
+        usage: wgpu::BufferUsages,
+        label: Option<&str>,
+    ) -> Result<Self, Error> {
+        context.check_buffer_size(block_size)?;
+        let limits = context.device.limits();
+        let mut alignment = wgpu::COPY_BUFFER_ALIGNMENT;
+        if usage.contains(wgpu::BufferUsages::UNIFORM) {
+            alignment = alignment.max(limits.min_uniform_buffer_offset_alignment as u64);
+        }
+        if usage.contains(wgpu::BufferUsages::STORAGE) {
+            alignment = alignment.max(limits.min_storage_buffer_offset_alignment as u64);
+        }
+
+        Ok(Self {
+            usage: usage | wgpu::BufferUsages::COPY_DST,
+            block_size: block_size.next_multiple_of(alignment),
+            alignment,
+            label: label.map(str::to_string),
+            blocks: Vec::new(),
+        })
+    }
+
+    pub fn alignment(&self) -> u64 {
+        self.alignment
+    }
+
+    /// Allocations larger than the block size get a buffer of their own.
+    pub fn alloc(&mut self, context: &Context, size: u64) -> Result<ArenaAllocation, Error> {
+        let size = size.max(1).next_multiple_of(self.alignment);
+        for (block, arena_block) in self.blocks.iter_mut().enumerate() {
+            if let Some(offset) = take_range(&mut arena_block.free, size) {
+                return Ok(ArenaAllocation {
+                    block,
+                    offset,
+                    size,
+                });
+            }
+        }
+
+        let block_size = self.block_size.max(size);
+        context.check_buffer_size(block_size)?;
+        let buffer = context.device.create_buffer(&wgpu::BufferDescriptor {
+            label: self.label.as_deref(),
+            size: block_size,
+            usage: self.usage,
+            mapped_at_creation: false,
+        });
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            block = self.blocks.len(),
+            block_size,
+            "created arena buffer"
+        );
+        let mut free = Vec::new();
+        if size < block_size {
+            free.push(size..block_size);
+        }
+        self.blocks.push(ArenaBlock { buffer, free });
+        Ok(ArenaAllocation {
+            block: self.blocks.len() - 1,
+            offset: 0,
+            size,
+        })
+    }
+
+    pub fn alloc_with_data(
+        &mut self,
+        context: &Context,
+        data: &[u8],
+    ) -> Result<ArenaAllocation, Error> {
+        let allocation = self.alloc(context, data.len() as u64)?;
+        self.write(context, &allocation, 0, data)?;
+        Ok(allocation)
+    }
+
+    /// Writes `data` at `offset` within the allocation, a multiple of
+    /// `COPY_BUFFER_ALIGNMENT`. Unaligned tails are padded with zeroes, which
+    /// stay inside the allocation as its size is aligned.
+    pub fn write(
+        &self,
+        context: &Context,
+        allocation: &ArenaAllocation,
+        offset: u64,
+        data: &[u8],
+    ) -> Result<(), Error> {
+        if !offset.is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT) {
+            return Err(Error::UnalignedBufferOffset {
+                offset,
+                alignment: wgpu::COPY_BUFFER_ALIGNMENT,
+            });
+        }
+        let too_small = |required| Error::BufferTooSmall {
+            size: allocation.size,
+            required,
+        };
+        let required = offset
+            .checked_add(data.len() as u64)
+            .ok_or(too_small(u64::MAX))?;
+        if required > allocation.size {
+            return Err(too_small(required));
+        }
+        context.write_buffer_padded(self.buffer(allocation), allocation.offset + offset, data);
+        Ok(())
+    }
+
+    /// Returns the range to the arena. The allocation must not be used by
+    /// pending submissions and must not be freed twice.
+    pub fn free(&mut self, allocation: ArenaAllocation) {
+        let range = allocation.offset..allocation.offset + allocation.size;
+        return_range(&mut self.blocks[allocation.block].free, range);
+    }
+
+    pub fn buffer(&self, allocation: &ArenaAllocation) -> &wgpu::Buffer {
+        &self.blocks[allocation.block].buffer
+    }
+
+    pub fn slice(&self, allocation: &ArenaAllocation) -> wgpu::BufferSlice<'_> {
+        self.buffer(allocation)
+            .slice(allocation.offset..allocation.offset + allocation.size)
+    }
+
+    pub fn binding(&self, allocation: &ArenaAllocation) -> wgpu::BufferBinding<'_> {
+        wgpu::BufferBinding {
+            buffer: self.buffer(allocation),
+            offset: allocation.offset,
+            size: wgpu::BufferSize::new(allocation.size),
+        }
+    }
+
+    pub fn block_count(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Total size of the arena's buffers.
+    pub fn capacity(&self) -> u64 {
+        self.blocks.iter().map(|block| block.buffer.size()).sum()
+    }
+
+    pub fn free_bytes(&self) -> u64 {
+        self.blocks
+            .iter()
+            .flat_map(|block| &block.free)
+            .map(|range| range.end - range.start)
+            .sum()
+    }
+}
+
+/// Takes `size` bytes from the first free range large enough.
+fn take_range(free: &mut Vec<Range<u64>>, size: u64) -> Option<u64> {
+    let index = free.iter().position(|r| r.end - r.start >= size)?;
+    let offset = free[index].start;
+    free[index].start += size;
+    if free[index].is_empty() {
+        free.remove(index);
+    }
+    Some(offset)
+}
+
+/// Inserts `range` in order, merging it with adjacent free ranges.
+fn return_range(free: &mut Vec<Range<u64>>, range: Range<u64>) {
+    let index = free.partition_point(|r| r.start < range.start);
+    free.insert(index, range);
+    if index + 1 < free.len() && free[index].end == free[index + 1].start {
+        free[index].end = free.remove(index + 1).end;
+    }
+    if index > 0 && free[index - 1].end == free[index].start {
+        free[index - 1].end = free.remove(index).end;
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn take_range_is_first_fit() {
+        let mut free = vec![0..4, 8..24, 32..64];
+        assert_eq!(take_range(&mut free, 8), Some(8));
+        assert_eq!(free, [0..4, 16..24, 32..64]);
+        assert_eq!(take_range(&mut free, 4), Some(0));
+        assert_eq!(free, [16..24, 32..64]);
+        assert_eq!(take_range(&mut free, 16), Some(32));
+        assert_eq!(take_range(&mut free, 8), Some(16));
+        assert_eq!(free, [48..64]);
+        assert_eq!(take_range(&mut free, 32), None);
+        assert_eq!(free, [48..64]);
+    }
+
+    #[test]
+    fn return_range_merges_neighbours() {
+        let mut free = vec![0..4, 16..24];
+        return_range(&mut free, 8..12);
+        assert_eq!(free, [0..4, 8..12, 16..24]);
+        // Left neighbour only.
+        return_range(&mut free, 24..28);
+        assert_eq!(free, [0..4, 8..12, 16..28]);
+        // Right neighbour only.
+        return_range(&mut free, 12..14);
+        assert_eq!(free, [0..4, 8..14, 16..28]);
+        // Both neighbours.
+        return_range(&mut free, 4..8);
+        assert_eq!(free, [0..14, 16..28]);
+        return_range(&mut free, 14..16);
+        assert_eq!(free, [0..28]);
+    }
+
+    #[test]
+    fn freed_ranges_are_reused() {
+        let mut free = vec![0..64];
+        let offsets: Vec<_> = (0..4).map(|_| take_range(&mut free, 16).unwrap()).collect();
+        assert_eq!(offsets, [0, 16, 32, 48]);
+        assert!(free.is_empty());
+        return_range(&mut free, 16..32);
+        return_range(&mut free, 32..48);
+        assert_eq!(free, [16..48]);
+        assert_eq!(take_range(&mut free, 24), Some(16));
+        assert_eq!(free, [40..48]);
+    }
+
+    #[test]
+    fn oversized_allocations_get_their_own_block() {
+        let Ok(context) = pollster::block_on(Context::default()) else {
+            return;
+        };
+        let usage = wgpu::BufferUsages::VERTEX;
+        let mut arena = BufferArena::new(&context, 256, usage, None).unwrap();
+        let small = arena.alloc(&context, 100).unwrap();
+        let large = arena.alloc(&context, 1000).unwrap();
+        assert_eq!((large.block(), large.offset(), large.size()), (1, 0, 1000));
+        assert_eq!(arena.block_count(), 2);
+        assert_eq!(arena.capacity(), 256 + 1000);
+        assert_eq!(arena.free_bytes(), 256 - 100);
+
+        // Small allocations still go to the first block with room.
+        let next = arena.alloc(&context, 100).unwrap();
+        assert_eq!((next.block(), next.offset()), (0, 100));
+        arena.free(large);
+        assert_eq!(arena.free_bytes(), 56 + 1000);
+        arena.free(small);
+        arena.free(next);
+        assert_eq!(arena.free_bytes(), 256 + 1000);
+        assert_eq!(arena.block_count(), 2);
+    }
+
+    #[test]
+    fn writes_reject_unaligned_and_overflowing_offsets() {
+        let Ok(context) = pollster::block_on(Context::default()) else {
+            return;
+        };
+        let usage = wgpu::BufferUsages::VERTEX;
+        let mut arena = BufferArena::new(&context, 256, usage, None).unwrap();
+        let allocation = arena.alloc_with_data(&context, &[1; 7]).unwrap();
+        assert_eq!(allocation.size(), 8);
+        arena.write(&context, &allocation, 4, &[2; 3]).unwrap();
+        assert!(matches!(
+            arena.write(&context, &allocation, 2, &[2; 2]),
+            Err(Error::UnalignedBufferOffset { offset: 2, .. })
+        ));
+        assert!(matches!(
+            arena.write(&context, &allocation, 4, &[2; 5]),
+            Err(Error::BufferTooSmall {
+                size: 8,
+                required: 9
+            })
+        ));
+        assert!(matches!(
+            arena.write(&context, &allocation, u64::MAX - 3, &[2; 8]),
+            Err(Error::BufferTooSmall {
+                required: u64::MAX,
+                ..
+            })
+        ));
+    }
+}
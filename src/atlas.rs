@@ -0,0 +1,275 @@
+use super::context::Context;
+use super::error::Error;
+use super::texture::Texture;
+#[cfg(feature = "image")]
+use image::DynamicImage;
+
+/// Where an image was packed into a `TextureAtlas`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AtlasRegion {
+    /// Texel rectangle of the image, without its padding.
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Texture coordinates of the rectangle's top-left and bottom-right
+    /// corners.
+    pub uv_min: [f32; 2],
+    pub uv_max: [f32; 2],
+}
+
+/// A row of the atlas, filled from the left.
+#[derive(Clone, Copy, Debug)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    x: u32,
+}
+
+/// Many small images, such as sprites or glyphs, packed into the shelves
+/// of one texture. Images can be inserted at any time, and each insertion
+/// writes only its own region of the texture. Every image is surrounded by
+/// `padding` texels copied from its edges, so that filtering and mipmaps
+/// do not bleed neighbouring images into it.
+#[derive(Debug)]
+pub struct TextureAtlas {
+    pub texture: Texture,
+    padding: u32,
+    shelves: Vec<Shelf>,
+    regions: Vec<AtlasRegion>,
+}
+
+impl TextureAtlas {
+    /// An empty `width` by `height` atlas of an uncompressed `format`.
+    pub fn new(
+        context: &Context,
+        width: &u32,
+        height: &u32,
+        texture_format: &wgpu::TextureFormat,
+        padding: u32,
+        label: Option<&str>,
+    ) -> Result<Self, Error> {
+        if texture_format.block_dimensions() != (1, 1)
+            || texture_format.block_copy_size(None).is_none()
+        {
+            return Err(Error::UnsupportedTextureFormat(*texture_format));
+        }
+        let size = wgpu::Extent3d {
+            width: *width,
+            height: *height,
+            depth_or_array_layers: 1,
+        };
+        context.check_texture_size(&size, wgpu::TextureDimension::D2)?;
+        let texture = context.device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: *texture_format,
+            view_formats: &[*texture_format],
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
+        });
+        Ok(Self {
+            texture: Texture::from_wgpu(context, texture),
+            padding,
+            shelves: Vec::new(),
+            regions: Vec::new(),
+        })
+    }
+
+    /// The region of the image returned by an insertion.
+    pub fn region(&self, id: usize) -> Option<&AtlasRegion> {
+        self.regions.get(id)
+    }
+
+    /// The regions of all images, indexed by their ids.
+    pub fn regions(&self) -> &[AtlasRegion] {
+        &self.regions
+    }
+
+    /// Forgets all images so that the atlas can be refilled. The texture
+    /// keeps its contents until they are overwritten.
+    pub fn clear(&mut self) {
+        self.shelves.clear();
+        self.regions.clear();
+    }
+
+    /// Packs a `width` by `height` image of tightly packed texels in the
+    /// atlas's format and uploads it, returning its id.
+    pub fn insert(
+        &mut self,
+        context: &Context,
+        data: &[u8],
+        width: &u32,
+        height: &u32,
+    ) -> Result<usize, Error> {
+        self.check_data(data, *width, *height)?;
+        let (x, y) = self.allocate(*width, *height)?;
+        let region = self.region_at(x, y, *width, *height);
+        self.write(context, &region, data);
+        self.regions.push(region);
+        Ok(self.regions.len() - 1)
+    }
+
+    /// Inserts several images, tallest first, which packs the shelves
+    /// tighter than inserting them in any order. Returns the ids in the
+    /// order of `images`. If one does not fit, none are inserted.
+    pub fn insert_all(
+        &mut self,
+        context: &Context,
+        images: &[(&[u8], u32, u32)],
+    ) -> Result<Vec<usize>, Error> {
+        for &(data, width, height) in images {
+            self.check_data(data, width, height)?;
+        }
+        let mut order: Vec<usize> = (0..images.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse((images[i].2, images[i].1)));
+
+        let shelves = self.shelves.clone();
+        let mut positions = vec![(0, 0); images.len()];
+        for &i in &order {
+            let (_, width, height) = images[i];
+            match self.allocate(width, height) {
+                Ok(position) => positions[i] = position,
+                Err(error) => {
+                    self.shelves = shelves;
+                    return Err(error);
+                }
+            }
+        }
+        Ok(images
+            .iter()
+            .zip(positions)
+            .map(|(&(data, width, height), (x, y))| {
+                let region = self.region_at(x, y, width, height);
+                self.write(context, &region, data);
+                self.regions.push(region);
+                self.regions.len() - 1
+            })
+            .collect())
+    }
+
+    /// Packs and uploads `image`, converted to the atlas's format as by
+    /// `Context::texture_from_image`.
+    #[cfg(feature = "image")]
+    pub fn insert_image(
+        &mut self,
+        context: &Context,
+        image: &DynamicImage,
+    ) -> Result<usize, Error> {
+        let data = super::context::image_data(image, &self.texture.format());
+        self.insert(context, &data, &image.width(), &image.height())
+    }
+
+    /// Overwrites the texels of image `id` with `data` of the same size,
+    /// e.g. to re-render a cached glyph.
+    pub fn update(&self, context: &Context, id: usize, data: &[u8]) -> Result<(), Error> {
+        let region = *self.regions.get(id).ok_or(Error::UnknownAtlasEntry(id))?;
+        self.check_data(data, region.width, region.height)?;
+        self.write(context, &region, data);
+        Ok(())
+    }
+
+    fn bytes_per_texel(&self) -> usize {
+        self.texture.format().block_copy_size(None).unwrap_or(0) as usize
+    }
+
+    fn check_data(&self, data: &[u8], width: u32, height: u32) -> Result<(), Error> {
+        let required = width as usize * height as usize * self.bytes_per_texel();
+        if width == 0 || height == 0 || data.len() < required {
+            return Err(Error::TextureCreationFailed);
+        }
+        Ok(())
+    }
+
+    /// Finds room for an image and its padding on the shortest shelf it
+    /// fits on, or on a new shelf, returning the position of the image itself.
+    fn allocate(&mut self, width: u32, height: u32) -> Result<(u32, u32), Error> {
+        let size = self.texture.size();
+        let (padded_width, padded_height) = (width + 2 * self.padding, height + 2 * self.padding);
+        let best = self
+            .shelves
+            .iter_mut()
+            .filter(|shelf| shelf.height >= padded_height && size.width - shelf.x >= padded_width)
+            .min_by_key(|shelf| shelf.height);
+        let shelf = match best {
+            Some(shelf) => shelf,
+            None => {
+                let y = self
+                    .shelves
+                    .last()
+                    .map_or(0, |shelf| shelf.y + shelf.height);
+                if padded_width > size.width || padded_height > size.height - y {
+                    return Err(Error::AtlasFull { width, height });
+                }
+                self.shelves.push(Shelf {
+                    y,
+                    height: padded_height,
+                    x: 0,
+                });
+                self.shelves.last_mut().unwrap()
+            }
+        };
+        let position = (shelf.x + self.padding, shelf.y + self.padding);
+        shelf.x += padded_width;
+        Ok(position)
+    }
+
+    fn region_at(&self, x: u32, y: u32, width: u32, height: u32) -> AtlasRegion {
+        let size = self.texture.size();
+        let (atlas_width, atlas_height) = (size.width as f32, size.height as f32);
+        AtlasRegion {
+            x,
+            y,
+            width,
+            height,
+            uv_min: [x as f32 / atlas_width, y as f32 / atlas_height],
+            uv_max: [
+                (x + width) as f32 / atlas_width,
+                (y + height) as f32 / atlas_height,
+            ],
+        }
+    }
+
+    /// Writes `data` with its edges extended over the padding.
+    fn write(&self, context: &Context, region: &AtlasRegion, data: &[u8]) {
+        let texel = self.bytes_per_texel();
+        let padding = self.padding;
+        let (width, height) = (region.width + 2 * padding, region.height + 2 * padding);
+        let mut padded = Vec::with_capacity(width as usize * height as usize * texel);
+        for y in 0..height {
+            let source_y = y.saturating_sub(padding).min(region.height - 1) as usize;
+            for x in 0..width {
+                let source_x = x.saturating_sub(padding).min(region.width - 1) as usize;
+                let offset = (source_y * region.width as usize + source_x) * texel;
+                padded.extend_from_slice(&data[offset..offset + texel]);
+            }
+        }
+        context.queue.write_texture(
+            wgpu::ImageCopyTexture {
+                aspect: wgpu::TextureAspect::All,
+                texture: &self.texture.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: region.x - padding,
+                    y: region.y - padding,
+                    z: 0,
+                },
+            },
+            &padded,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(width * texel as u32),
+                rows_per_image: Some(height),
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+}
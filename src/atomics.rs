@@ -0,0 +1,45 @@
+use super::context::Context;
+
+impl Context {
+    pub fn supports_shader_int64(&self) -> bool {
+        self.device
+            .features()
+            .contains(wgpu::Features::SHADER_INT64)
+    }
+}
+
+/// WGSL declaring `struct AtomicU64`, a 64-bit unsigned counter emulated
+/// with a pair of 32-bit atomics, which works on every adapter. Shaders
+/// include it once, followed by `atomic_u64_wgsl` for each array.
+pub const ATOMIC_U64_WGSL: &str = "struct AtomicU64 {
+    lo: atomic<u32>,
+    hi: atomic<u32>,
+}
+";
+
+/// WGSL for the storage array of `AtomicU64` named `array`: the functions
+/// `<array>_add(index, value)` and `<array>_load(index) -> vec2<u32>` (low
+/// word, high word). Loads are only consistent once all writers have
+/// finished.
+pub fn atomic_u64_wgsl(array: &str) -> String {
+    format!(
+        "fn {array}_add(index: u32, value: u32) {{
+    let old = atomicAdd(&{array}[index].lo, value);
+    if (old + value < old) {{
+        atomicAdd(&{array}[index].hi, 1u);
+    }}
+}}
+
+fn {array}_load(index: u32) -> vec2<u32> {{
+    return vec2<u32>(atomicLoad(&{array}[index].lo), atomicLoad(&{array}[index].hi));
+}}
+"
+    )
+}
+
+pub fn read_atomic_u64(words: &[u32]) -> Vec<u64> {
+    words
+        .chunks_exact(2)
+        .map(|pair| pair[0] as u64 | (pair[1] as u64) << 32)
+        .collect()
+}
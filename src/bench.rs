@@ -0,0 +1,242 @@
+use super::context::Context;
+use super::error::Error;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimingSource {
+    /// Timestamp queries written around the workload.
+    GpuTimestamps,
+    /// Wall clock time of submitting and waiting for the workload, used when
+    /// the device lacks `TIMESTAMP_QUERY_INSIDE_ENCODERS`.
+    CpuWallClock,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BenchReport {
+    pub source: TimingSource,
+    /// One sample per measured iteration, warmup excluded.
+    pub samples: Vec<Duration>,
+}
+
+impl BenchReport {
+    pub fn min(&self) -> Duration {
+        self.samples.iter().min().copied().unwrap_or_default()
+    }
+
+    pub fn max(&self) -> Duration {
+        self.samples.iter().max().copied().unwrap_or_default()
+    }
+
+    pub fn mean(&self) -> Duration {
+        match self.samples.len() {
+            0 => Duration::ZERO,
+            len => self.samples.iter().sum::<Duration>() / len as u32,
+        }
+    }
+
+    pub fn median(&self) -> Duration {
+        self.percentile(50.0)
+    }
+
+    /// Nearest-rank percentile, `percentile` in 0..=100.
+    pub fn percentile(&self, percentile: f64) -> Duration {
+        if self.samples.is_empty() {
+            return Duration::ZERO;
+        }
+        let mut sorted = self.samples.clone();
+        sorted.sort();
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0 * sorted.len() as f64).ceil() as usize;
+        sorted[rank.saturating_sub(1)]
+    }
+
+    pub fn std_dev(&self) -> Duration {
+        if self.samples.len() < 2 {
+            return Duration::ZERO;
+        }
+        let mean = self.mean().as_secs_f64();
+        let variance = self
+            .samples
+            .iter()
+            .map(|sample| (sample.as_secs_f64() - mean).powi(2))
+            .sum::<f64>()
+            / (self.samples.len() - 1) as f64;
+        Duration::from_secs_f64(variance.sqrt())
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} samples ({:?}): mean {:?} ± {:?}, median {:?}, min {:?}, max {:?}, p95 {:?}",
+            self.samples.len(),
+            self.source,
+            self.mean(),
+            self.std_dev(),
+            self.median(),
+            self.min(),
+            self.max(),
+            self.percentile(95.0),
+        )
+    }
+}
+
+/// Runs a GPU workload repeatedly and collects per-iteration timings.
+pub struct GpuBench {
+    warmup: u32,
+    iterations: u32,
+}
+
+impl GpuBench {
+    pub fn new(iterations: u32) -> Self {
+        Self {
+            warmup: 3,
+            iterations: iterations.max(1),
+        }
+    }
+
+    pub fn warmup(mut self, warmup: u32) -> Self {
+        self.warmup = warmup;
+        self
+    }
+
+    pub fn timing_source(context: &Context) -> TimingSource {
+        let required =
+            wgpu::Features::TIMESTAMP_QUERY | wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS;
+        match context.device.features().contains(required) {
+            true => TimingSource::GpuTimestamps,
+            false => TimingSource::CpuWallClock,
+        }
+    }
+
+    /// Records `workload` into a fresh encoder per iteration and submits it,
+    /// timing each iteration after the warmup ones.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(iterations = self.iterations, warmup = self.warmup),
+            err
+        )
+    )]
+    pub fn run<F>(&self, context: &Context, mut workload: F) -> Result<BenchReport, Error>
+    where
+        F: FnMut(&mut wgpu::CommandEncoder),
+    {
+        for _ in 0..self.warmup {
+            submit_and_wait(context, &mut workload, None);
+        }
+
+        let source = Self::timing_source(context);
+        let samples = match source {
+            TimingSource::GpuTimestamps => gpu_samples(context, self.iterations, &mut workload)?,
+            TimingSource::CpuWallClock => (0..self.iterations)
+                .map(|_| {
+                    let start = Instant::now();
+                    submit_and_wait(context, &mut workload, None);
+                    start.elapsed()
+                })
+                .collect(),
+        };
+        Ok(BenchReport { source, samples })
+    }
+
+    /// Total time of `iterations` runs, for criterion's `iter_custom`:
+    /// `bencher.iter_custom(|iters| bench.total(&context, iters, |encoder| ..))`.
+    pub fn total<F>(&self, context: &Context, iterations: u64, workload: F) -> Duration
+    where
+        F: FnMut(&mut wgpu::CommandEncoder),
+    {
+        let bench = GpuBench {
+            warmup: 0,
+            iterations: iterations.clamp(1, u32::MAX as u64) as u32,
+        };
+        bench
+            .run(context, workload)
+            .map(|report| report.samples.iter().sum())
+            .unwrap_or_default()
+    }
+}
+
+fn submit_and_wait<F>(
+    context: &Context,
+    workload: &mut F,
+    timestamps: Option<(&wgpu::QuerySet, u32)>,
+) where
+    F: FnMut(&mut wgpu::CommandEncoder),
+{
+    let mut command_encoder =
+        context
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Bench Encoder"),
+            });
+    if let Some((query_set, index)) = timestamps {
+        command_encoder.write_timestamp(query_set, index);
+    }
+    workload(&mut command_encoder);
+    if let Some((query_set, index)) = timestamps {
+        command_encoder.write_timestamp(query_set, index + 1);
+    }
+    let index = context.submit(command_encoder);
+    context
+        .device
+        .poll(wgpu::Maintain::WaitForSubmissionIndex(index));
+}
+
+fn gpu_samples<F>(
+    context: &Context,
+    iterations: u32,
+    workload: &mut F,
+) -> Result<Vec<Duration>, Error>
+where
+    F: FnMut(&mut wgpu::CommandEncoder),
+{
+    let period = context.queue.get_timestamp_period() as f64;
+    let batch = wgpu::QUERY_SET_MAX_QUERIES / 2;
+    let mut samples = Vec::with_capacity(iterations as usize);
+
+    let mut remaining = iterations;
+    while remaining > 0 {
+        let count = remaining.min(batch);
+        remaining -= count;
+
+        let query_set = context.device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("Bench Timestamps"),
+            ty: wgpu::QueryType::Timestamp,
+            count: count * 2,
+        });
+        for iteration in 0..count {
+            submit_and_wait(context, workload, Some((&query_set, iteration * 2)));
+        }
+
+        let size = count as u64 * 2 * wgpu::QUERY_SIZE as u64;
+        let resolve = context.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Bench Resolve Buffer"),
+            size,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let staging = context.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Bench Readback Buffer"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        context.schedule(|encoder| {
+            encoder.resolve_query_set(&query_set, 0..count * 2, &resolve, 0);
+            encoder.copy_buffer_to_buffer(&resolve, 0, &staging, 0, size);
+        });
+
+        let ticks: Vec<u64> = bytemuck::pod_collect_to_vec(&context.map_read(&staging)?);
+        samples.extend(ticks.chunks(2).map(|pair| {
+            let elapsed = pair[1].saturating_sub(pair[0]) as f64 * period;
+            Duration::from_nanos(elapsed as u64)
+        }));
+    }
+    Ok(samples)
+}
@@ -0,0 +1,210 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use wgpu_tools::{image, wgpu, Blitter, Context, Error, Texture};
+
+const USAGE: &str = "usage: wgpu-tools-convert <input> <output> [--resize <width>x<height>]
+                           [--format <format>] [--mipmaps] [--srgb]
+
+Converts textures on the GPU, picking the file formats by extension: the
+formats of the `image` crate, KTX2 and DDS as input, and the formats of the
+`image` crate and DDS as output.
+
+  --resize   resamples the base level to <width>x<height>
+  --format   converts to one of rgba8unorm, rgba8unorm-srgb, bgra8unorm,
+             bgra8unorm-srgb, r8unorm, r16unorm, rgba16unorm, r32float,
+             rgba16float and rgba32float (rgba8unorm by default, or the
+             input's format for KTX2 or DDS input written as DDS)
+  --mipmaps  generates a full mip chain, written to DDS output
+  --srgb     is short for --format rgba8unorm-srgb
+
+KTX2 output and block compression are not supported; block-compressed
+KTX2 and DDS input is kept compressed when written as DDS, and decoded
+otherwise.";
+
+const FORMATS: [(&str, wgpu::TextureFormat); 10] = [
+    ("rgba8unorm", wgpu::TextureFormat::Rgba8Unorm),
+    ("rgba8unorm-srgb", wgpu::TextureFormat::Rgba8UnormSrgb),
+    ("bgra8unorm", wgpu::TextureFormat::Bgra8Unorm),
+    ("bgra8unorm-srgb", wgpu::TextureFormat::Bgra8UnormSrgb),
+    ("r8unorm", wgpu::TextureFormat::R8Unorm),
+    ("r16unorm", wgpu::TextureFormat::R16Unorm),
+    ("rgba16unorm", wgpu::TextureFormat::Rgba16Unorm),
+    ("r32float", wgpu::TextureFormat::R32Float),
+    ("rgba16float", wgpu::TextureFormat::Rgba16Float),
+    ("rgba32float", wgpu::TextureFormat::Rgba32Float),
+];
+
+struct Options {
+    input: PathBuf,
+    output: PathBuf,
+    resize: Option<(u32, u32)>,
+    format: Option<wgpu::TextureFormat>,
+    mipmaps: bool,
+}
+
+fn parse(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut paths = Vec::new();
+    let mut resize = None;
+    let mut format = None;
+    let mut mipmaps = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--resize" => {
+                let size = args.next().ok_or("--resize needs a size")?;
+                let (width, height) = size
+                    .split_once('x')
+                    .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+                    .ok_or_else(|| format!("invalid size {size:?}"))?;
+                resize = Some((width, height));
+            }
+            "--format" => {
+                let name = args.next().ok_or("--format needs a format")?;
+                let (_, texture_format) = FORMATS
+                    .iter()
+                    .find(|(format_name, _)| name.eq_ignore_ascii_case(format_name))
+                    .ok_or_else(|| format!("unsupported format {name:?}"))?;
+                format = Some(*texture_format);
+            }
+            "--srgb" => format = Some(wgpu::TextureFormat::Rgba8UnormSrgb),
+            "--mipmaps" => mipmaps = true,
+            "-h" | "--help" => return Err(String::new()),
+            _ if arg.starts_with("--") => return Err(format!("unknown option {arg}")),
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+    let [input, output]: [PathBuf; 2] = paths
+        .try_into()
+        .map_err(|_| "expected an input and an output path".to_string())?;
+    match extension(&output).as_str() {
+        "ktx2" => return Err("writing KTX2 is not supported, write DDS instead".to_string()),
+        "dds" => {}
+        _ if mipmaps => return Err("--mipmaps needs a DDS output".to_string()),
+        _ => {}
+    }
+    Ok(Options {
+        input,
+        output,
+        resize,
+        format,
+        mipmaps,
+    })
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default()
+}
+
+/// Draws `texture` into a new `width` x `height` target
+/// of `format`, which decodes block-compressed and converts other formats.
+fn blit(
+    context: &Context,
+    texture: &Texture,
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+) -> Result<Texture, Error> {
+    let target = context.render_target(&width, &height, &format, Some("Converted"))?;
+    let mut encoder = context
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Convert Encoder"),
+        });
+    Blitter::new(context).blit(context, &mut encoder, texture, &target)?;
+    context.queue.submit([encoder.finish()]);
+    Ok(target)
+}
+
+/// A context with the texture compression features the adapter has, so
+/// that block-compressed input can be read.
+async fn context() -> Result<Context, Error> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+    let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions::default())
+        .await
+        .ok_or(Error::RequestingAdapterFailed)?;
+    let compression = wgpu::Features::TEXTURE_COMPRESSION_BC
+        | wgpu::Features::TEXTURE_COMPRESSION_ETC2
+        | wgpu::Features::TEXTURE_COMPRESSION_ASTC;
+    let device_descriptor = wgpu::DeviceDescriptor {
+        required_features: adapter.features() & compression,
+        ..Default::default()
+    };
+    Context::from_adapter(instance, adapter, &device_descriptor, None).await
+}
+
+fn convert(options: &Options) -> Result<(), Error> {
+    let context = pollster::block_on(context())?;
+    let dds_output = extension(&options.output) == "dds";
+
+    let mut texture = match extension(&options.input).as_str() {
+        "ktx2" => context.load_ktx2(&options.input, Some("Input"))?,
+        "dds" => context.load_dds(&options.input, Some("Input"))?,
+        _ => {
+            let input = image::open(&options.input)?;
+            let format = options.format.unwrap_or(wgpu::TextureFormat::Rgba8Unorm);
+            context.texture_from_image(&input, &format, Some("Input"))?
+        }
+    };
+    let format = match options.format {
+        Some(format) => format,
+        None if dds_output && !options.mipmaps && options.resize.is_none() => texture.format(),
+        None if texture.format().is_srgb() => wgpu::TextureFormat::Rgba8UnormSrgb,
+        None => wgpu::TextureFormat::Rgba8Unorm,
+    };
+    if format != texture.format() {
+        texture = blit(
+            &context,
+            &texture,
+            texture.width(),
+            texture.height(),
+            format,
+        )?;
+    }
+    if let Some((width, height)) = options.resize {
+        texture = blit(&context, &texture, width, height, format)?;
+    }
+    if options.mipmaps {
+        let data = context.read_texture(&texture)?;
+        texture = context.texture_with_data_mipmapped(
+            &data,
+            &texture.width(),
+            &texture.height(),
+            &format,
+            Some("Mipmapped"),
+        )?;
+    }
+
+    if dds_output {
+        return context.save_dds(&texture, &options.output);
+    }
+    let output = texture.read_to_image(&context)?;
+    // JPEG has no alpha channel.
+    let output = match image::ImageFormat::from_path(&options.output) {
+        Ok(image::ImageFormat::Jpeg) => image::DynamicImage::ImageRgb8(output.to_rgb8()),
+        _ => output,
+    };
+    output.save(&options.output)?;
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let options = match parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            if !message.is_empty() {
+                eprintln!("error: {message}");
+            }
+            eprintln!("{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+    match convert(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
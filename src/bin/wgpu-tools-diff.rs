@@ -0,0 +1,78 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+use wgpu_tools::{Error, FrameCapture};
+
+const USAGE: &str =
+    "usage: wgpu-tools-diff <capture-a> <capture-b> [--threshold <value>] [--output <directory>]
+
+Compares two directories written by `FrameCapture::save`, printing per-pass
+error statistics and optionally writing diff images and a report. Exits
+with 1 if any pass differs by more than the threshold (default 0).";
+
+struct Options {
+    captures: [PathBuf; 2],
+    threshold: f32,
+    output: Option<PathBuf>,
+}
+
+fn parse(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut paths = Vec::new();
+    let mut threshold = 0.0;
+    let mut output = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--threshold" => {
+                let value = args.next().ok_or("--threshold needs a value")?;
+                threshold = value
+                    .parse()
+                    .map_err(|_| format!("invalid threshold {value:?}"))?;
+            }
+            "--output" => output = Some(PathBuf::from(args.next().ok_or("--output needs a path")?)),
+            "-h" | "--help" => return Err(String::new()),
+            _ if arg.starts_with("--") => return Err(format!("unknown option {arg}")),
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+    let captures = paths
+        .try_into()
+        .map_err(|_| "expected two capture directories".to_string())?;
+    Ok(Options {
+        captures,
+        threshold,
+        output,
+    })
+}
+
+fn diff(options: &Options) -> Result<bool, Error> {
+    let [a, b] = &options.captures;
+    let diff = FrameCapture::load(a)?.diff(&FrameCapture::load(b)?, options.threshold);
+    print!("{diff}");
+    if let Some(pass) = diff.first_difference() {
+        println!("First difference: {}", pass.pass);
+    }
+    if let Some(output) = &options.output {
+        diff.write_report(output)?;
+    }
+    Ok(diff.is_match())
+}
+
+fn main() -> ExitCode {
+    let options = match parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            if !message.is_empty() {
+                eprintln!("error: {message}");
+            }
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    match diff(&options) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::from(2)
+        }
+    }
+}
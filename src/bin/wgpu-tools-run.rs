@@ -0,0 +1,502 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use wgpu_tools::wgpu::naga;
+use wgpu_tools::{image, wgpu, Context, Texture};
+
+const USAGE: &str = "usage: wgpu-tools-run <shader.wgsl> [options]
+
+Runs a WGSL compute or fragment entry point headlessly. Resources in bind
+group 0 are bound in binding order:
+  uniform buffers     the values of --uniforms, zero padded
+  sampled textures    the images of --input, in order
+  samplers            a linear clamping sampler
+  storage buffers     the contents of --buffer, in order
+  storage textures    blank rgba8unorm/rgba32float textures of --size
+
+A fragment entry point is drawn over a fullscreen triangle into an rgba8unorm
+image of --size. Afterwards the color target (or the storage textures) are
+written to --output, and writable storage buffers to <output>.<binding>.bin.
+
+options:
+  --entry <name>          entry point (default: the first in the shader)
+  --size <width>x<height> output size (default: 512x512)
+  --uniforms <f32,...>    uniform buffer values
+  --input <image>         sampled texture, repeatable
+  --buffer <file|bytes>   storage buffer from a file or zeroed, repeatable
+  --dispatch <x,y,z>      compute workgroups (default: size / workgroup size)
+  --output <path>         output image (default: out.png)";
+
+const FULLSCREEN_VERTEX: &str = "
+@vertex
+fn wgpu_tools_fullscreen(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+}
+";
+
+struct Options {
+    shader: PathBuf,
+    entry: Option<String>,
+    size: (u32, u32),
+    uniforms: Vec<f32>,
+    inputs: Vec<PathBuf>,
+    buffers: Vec<String>,
+    dispatch: Option<[u32; 3]>,
+    output: PathBuf,
+}
+
+fn parse(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        shader: PathBuf::new(),
+        entry: None,
+        size: (512, 512),
+        uniforms: Vec::new(),
+        inputs: Vec::new(),
+        buffers: Vec::new(),
+        dispatch: None,
+        output: PathBuf::from("out.png"),
+    };
+    let mut shader = None;
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{arg} needs a value"));
+        match arg.as_str() {
+            "--entry" => options.entry = Some(value()?),
+            "--size" => {
+                let size = value()?;
+                options.size = size
+                    .split_once('x')
+                    .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+                    .ok_or_else(|| format!("invalid size {size:?}"))?;
+            }
+            "--uniforms" => {
+                options.uniforms = value()?
+                    .split(',')
+                    .map(|v| {
+                        v.trim()
+                            .parse()
+                            .map_err(|_| format!("invalid uniform {v:?}"))
+                    })
+                    .collect::<Result<_, _>>()?;
+            }
+            "--input" => options.inputs.push(PathBuf::from(value()?)),
+            "--buffer" => options.buffers.push(value()?),
+            "--dispatch" => {
+                let dispatch = value()?;
+                let counts: Vec<u32> = dispatch
+                    .split(',')
+                    .map(|v| v.trim().parse())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| format!("invalid dispatch {dispatch:?}"))?;
+                let mut workgroups = [1; 3];
+                if counts.is_empty() || counts.len() > 3 {
+                    return Err(format!("invalid dispatch {dispatch:?}"));
+                }
+                workgroups[..counts.len()].copy_from_slice(&counts);
+                options.dispatch = Some(workgroups);
+            }
+            "--output" => options.output = PathBuf::from(value()?),
+            "-h" | "--help" => return Err(String::new()),
+            _ if arg.starts_with("--") => return Err(format!("unknown option {arg}")),
+            _ if shader.is_none() => shader = Some(PathBuf::from(arg)),
+            _ => return Err(format!("unexpected argument {arg}")),
+        }
+    }
+    options.shader = shader.ok_or("expected a shader path")?;
+    Ok(options)
+}
+
+/// What gets bound to a global of the shader.
+enum Resource {
+    Uniform(wgpu::Buffer),
+    Storage {
+        buffer: wgpu::Buffer,
+        writable: bool,
+    },
+    Texture(Texture),
+    StorageTexture(Texture),
+    Sampler(wgpu::Sampler),
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let user_source = std::fs::read_to_string(&options.shader)
+        .map_err(|error| format!("{}: {error}", options.shader.display()))?;
+    // Parse the shader on its own first so errors point at the user's code.
+    naga::front::wgsl::parse_str(&user_source).map_err(|error| {
+        let message = error.emit_to_string_with_path(&user_source, &options.shader);
+        message.trim_start_matches("error: ").to_string()
+    })?;
+    let source = format!("{user_source}\n{FULLSCREEN_VERTEX}");
+    let module = naga::front::wgsl::parse_str(&source).map_err(|error| error.to_string())?;
+
+    let entry = module
+        .entry_points
+        .iter()
+        .filter(|entry| entry.name != "wgpu_tools_fullscreen")
+        .find(|entry| {
+            options
+                .entry
+                .as_ref()
+                .is_none_or(|name| &entry.name == name)
+        })
+        .ok_or("no matching entry point")?;
+    if entry.stage == naga::ShaderStage::Vertex {
+        return Err("vertex entry points can't be run".into());
+    }
+
+    let context = pollster::block_on(Context::default()).map_err(|error| error.to_string())?;
+    let resources = create_resources(&context, &module, options)?;
+
+    context
+        .device
+        .push_error_scope(wgpu::ErrorFilter::Validation);
+    let shader = context
+        .device
+        .create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shader"),
+            source: wgpu::ShaderSource::Wgsl(source.as_str().into()),
+        });
+    let target = match entry.stage {
+        naga::ShaderStage::Fragment => {
+            Some(draw(&context, &shader, &entry.name, &resources, options)?)
+        }
+        _ => {
+            let [x, y, _] = entry.workgroup_size;
+            let workgroups = options.dispatch.unwrap_or([
+                options.size.0.div_ceil(x),
+                options.size.1.div_ceil(y),
+                1,
+            ]);
+            dispatch(&context, &shader, &entry.name, &resources, workgroups)?;
+            None
+        }
+    };
+    if let Some(error) = pollster::block_on(context.device.pop_error_scope()) {
+        return Err(error.to_string());
+    }
+
+    let mut images = target.iter().collect::<Vec<_>>();
+    images.extend(resources.iter().filter_map(|(_, resource)| match resource {
+        Resource::StorageTexture(texture) => Some(texture),
+        _ => None,
+    }));
+    for (index, texture) in images.iter().enumerate() {
+        let path = match index {
+            0 => options.output.clone(),
+            _ => suffixed(&options.output, &index.to_string()),
+        };
+        save_image(&context, texture, &path)?;
+    }
+    for (binding, resource) in &resources {
+        if let Resource::Storage {
+            buffer,
+            writable: true,
+        } = resource
+        {
+            let data = context
+                .read_buffer(buffer)
+                .map_err(|error| error.to_string())?;
+            let path = suffixed(&options.output, &format!("{binding}.bin"));
+            std::fs::write(&path, data).map_err(|error| format!("{}: {error}", path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+fn create_resources(
+    context: &Context,
+    module: &naga::Module,
+    options: &Options,
+) -> Result<Vec<(u32, Resource)>, String> {
+    let mut layouter = naga::proc::Layouter::default();
+    layouter
+        .update(module.to_ctx())
+        .map_err(|error| error.to_string())?;
+
+    let mut globals: Vec<_> = module
+        .global_variables
+        .iter()
+        .filter_map(|(_, global)| Some((global.binding.clone()?, global)))
+        .collect();
+    if let Some((binding, _)) = globals.iter().find(|(binding, _)| binding.group != 0) {
+        return Err(format!(
+            "only bind group 0 is supported, found group {}",
+            binding.group
+        ));
+    }
+    globals.sort_by_key(|(binding, _)| binding.binding);
+
+    let (mut inputs, mut buffers) = (options.inputs.iter(), options.buffers.iter());
+    let mut resources = Vec::new();
+    for (binding, global) in globals {
+        let size = layouter[global.ty].size as usize;
+        let resource = match (global.space, &module.types[global.ty].inner) {
+            (naga::AddressSpace::Uniform, _) => {
+                let mut data: Vec<u8> = bytemuck::cast_slice(&options.uniforms).to_vec();
+                data.resize(data.len().max(size).next_multiple_of(16), 0);
+                Resource::Uniform(buffer(context, &data, wgpu::BufferUsages::UNIFORM)?)
+            }
+            (naga::AddressSpace::Storage { access }, _) => {
+                let contents = buffers
+                    .next()
+                    .ok_or(format!("no --buffer for binding {}", binding.binding))?;
+                let mut data = match contents.parse::<usize>() {
+                    Ok(len) => vec![0; len],
+                    Err(_) => {
+                        std::fs::read(contents).map_err(|error| format!("{contents}: {error}"))?
+                    }
+                };
+                data.resize(data.len().max(size).next_multiple_of(4), 0);
+                Resource::Storage {
+                    buffer: buffer(context, &data, wgpu::BufferUsages::STORAGE)?,
+                    writable: access.contains(naga::StorageAccess::STORE),
+                }
+            }
+            (_, naga::TypeInner::Sampler { .. }) => {
+                Resource::Sampler(context.device.create_sampler(&wgpu::SamplerDescriptor {
+                    mag_filter: wgpu::FilterMode::Linear,
+                    min_filter: wgpu::FilterMode::Linear,
+                    ..Default::default()
+                }))
+            }
+            (
+                _,
+                naga::TypeInner::Image {
+                    class: naga::ImageClass::Storage { format, .. },
+                    ..
+                },
+            ) => {
+                let format = match format {
+                    naga::StorageFormat::Rgba8Unorm => wgpu::TextureFormat::Rgba8Unorm,
+                    naga::StorageFormat::Rgba32Float => wgpu::TextureFormat::Rgba32Float,
+                    _ => return Err(format!("unsupported storage texture format {format:?}")),
+                };
+                let texture = context.device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some("Storage Texture"),
+                    size: wgpu::Extent3d {
+                        width: options.size.0,
+                        height: options.size.1,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::COPY_SRC,
+                    view_formats: &[],
+                });
+                Resource::StorageTexture(Texture::from_wgpu(context, texture))
+            }
+            (_, naga::TypeInner::Image { .. }) => {
+                let path = inputs
+                    .next()
+                    .ok_or(format!("no --input for binding {}", binding.binding))?;
+                let image =
+                    image::open(path).map_err(|error| format!("{}: {error}", path.display()))?;
+                Resource::Texture(
+                    context
+                        .texture_from_image(&image, &wgpu::TextureFormat::Rgba8Unorm, None)
+                        .map_err(|error| error.to_string())?,
+                )
+            }
+            _ => {
+                return Err(format!(
+                    "unsupported resource at binding {}",
+                    binding.binding
+                ))
+            }
+        };
+        resources.push((binding.binding, resource));
+    }
+    Ok(resources)
+}
+
+fn buffer(
+    context: &Context,
+    data: &[u8],
+    usage: wgpu::BufferUsages,
+) -> Result<wgpu::Buffer, String> {
+    context
+        .buffer_with_data(data, usage | wgpu::BufferUsages::COPY_SRC, None)
+        .map_err(|error| error.to_string())
+}
+
+fn bind_group(
+    context: &Context,
+    layout: &wgpu::BindGroupLayout,
+    resources: &[(u32, Resource)],
+) -> wgpu::BindGroup {
+    let entries: Vec<_> = resources
+        .iter()
+        .map(|(binding, resource)| wgpu::BindGroupEntry {
+            binding: *binding,
+            resource: match resource {
+                Resource::Uniform(buffer) | Resource::Storage { buffer, .. } => {
+                    buffer.as_entire_binding()
+                }
+                Resource::Texture(texture) | Resource::StorageTexture(texture) => {
+                    wgpu::BindingResource::TextureView(&texture.view)
+                }
+                Resource::Sampler(sampler) => wgpu::BindingResource::Sampler(sampler),
+            },
+        })
+        .collect();
+    context
+        .device
+        .create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Bind Group"),
+            layout,
+            entries: &entries,
+        })
+}
+
+fn draw(
+    context: &Context,
+    shader: &wgpu::ShaderModule,
+    entry_point: &str,
+    resources: &[(u32, Resource)],
+    options: &Options,
+) -> Result<Texture, String> {
+    let format = wgpu::TextureFormat::Rgba8Unorm;
+    let target = context.device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Color Target"),
+        size: wgpu::Extent3d {
+            width: options.size.0,
+            height: options.size.1,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+    let target = Texture::from_wgpu(context, target);
+
+    let pipeline = context
+        .device
+        .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: None,
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: "wgpu_tools_fullscreen",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point,
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                targets: &[Some(format.into())],
+            }),
+            multiview: None,
+        });
+    let bind_group = (!resources.is_empty())
+        .then(|| bind_group(context, &pipeline.get_bind_group_layout(0), resources));
+
+    context.schedule(|encoder| {
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &target.view,
+                resolve_target: None,
+                ops: wgpu::Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(&pipeline);
+        if let Some(bind_group) = &bind_group {
+            pass.set_bind_group(0, bind_group, &[]);
+        }
+        pass.draw(0..3, 0..1);
+    });
+    Ok(target)
+}
+
+fn dispatch(
+    context: &Context,
+    shader: &wgpu::ShaderModule,
+    entry_point: &str,
+    resources: &[(u32, Resource)],
+    workgroups: [u32; 3],
+) -> Result<(), String> {
+    let pipeline = context
+        .device
+        .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Compute Pipeline"),
+            layout: None,
+            module: shader,
+            entry_point,
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        });
+    let bind_group = (!resources.is_empty())
+        .then(|| bind_group(context, &pipeline.get_bind_group_layout(0), resources));
+
+    context.schedule(|encoder| {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Compute Pass"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&pipeline);
+        if let Some(bind_group) = &bind_group {
+            pass.set_bind_group(0, bind_group, &[]);
+        }
+        pass.dispatch_workgroups(workgroups[0], workgroups[1], workgroups[2]);
+    });
+    Ok(())
+}
+
+fn save_image(context: &Context, texture: &Texture, path: &Path) -> Result<(), String> {
+    let data = context
+        .read_texture(texture)
+        .map_err(|error| error.to_string())?;
+    let data = match texture.format() {
+        wgpu::TextureFormat::Rgba32Float => bytemuck::pod_collect_to_vec::<u8, f32>(&data)
+            .into_iter()
+            .map(|value| (value.clamp(0.0, 1.0) * 255.0).round() as u8)
+            .collect(),
+        _ => data,
+    };
+    let image = image::RgbaImage::from_raw(texture.width(), texture.height(), data)
+        .ok_or("invalid image data")?;
+    image
+        .save(path)
+        .map_err(|error| format!("{}: {error}", path.display()))
+}
+
+fn suffixed(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) if !suffix.ends_with(".bin") => {
+            format!("{stem}.{suffix}.{}", extension.to_string_lossy())
+        }
+        _ => format!("{stem}.{suffix}"),
+    };
+    path.with_file_name(name)
+}
+
+fn main() -> ExitCode {
+    let options = match parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            if !message.is_empty() {
+                eprintln!("error: {message}");
+            }
+            eprintln!("{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+    match run(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use wgpu_tools::{image, wgpu, Context, Error, ShaderToy, ShaderToyInputs, ShaderToySource};
+
+const USAGE: &str = "usage: wgpu-tools-shadertoy <shader.glsl|shader.wgsl> [options]
+
+Renders a Shadertoy-style shader headlessly and writes the frames as images.
+GLSL shaders define mainImage as on Shadertoy, WGSL shaders define
+fn main_image(frag_coord: vec2<f32>) -> vec4<f32>.
+
+options:
+  --size <width>x<height> frame size (default: 640x360)
+  --time <seconds>        time of the first frame (default: 0)
+  --frames <count>        number of frames (default: 1)
+  --fps <rate>            frame rate (default: 60)
+  --channel <image>       input channel, up to four
+  --output <path>         output image, numbered when rendering several
+                          frames (default: frame.png)";
+
+struct Options {
+    shader: PathBuf,
+    size: (u32, u32),
+    time: f32,
+    frames: u32,
+    fps: f32,
+    channels: Vec<PathBuf>,
+    output: PathBuf,
+}
+
+fn parse(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        shader: PathBuf::new(),
+        size: (640, 360),
+        time: 0.0,
+        frames: 1,
+        fps: 60.0,
+        channels: Vec::new(),
+        output: PathBuf::from("frame.png"),
+    };
+    let mut shader = None;
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{arg} needs a value"));
+        match arg.as_str() {
+            "--size" => {
+                let size = value()?;
+                options.size = size
+                    .split_once('x')
+                    .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+                    .ok_or_else(|| format!("invalid size {size:?}"))?;
+            }
+            "--time" => options.time = value()?.parse().map_err(|_| "invalid time")?,
+            "--frames" => options.frames = value()?.parse().map_err(|_| "invalid frame count")?,
+            "--fps" => options.fps = value()?.parse().map_err(|_| "invalid frame rate")?,
+            "--channel" if options.channels.len() == 4 => {
+                return Err("at most four channels are supported".into())
+            }
+            "--channel" => options.channels.push(PathBuf::from(value()?)),
+            "--output" => options.output = PathBuf::from(value()?),
+            "-h" | "--help" => return Err(String::new()),
+            _ if arg.starts_with("--") => return Err(format!("unknown option {arg}")),
+            _ if shader.is_none() => shader = Some(PathBuf::from(arg)),
+            _ => return Err(format!("unexpected argument {arg}")),
+        }
+    }
+    options.shader = shader.ok_or("expected a shader path")?;
+    Ok(options)
+}
+
+fn render(options: &Options) -> Result<(), Error> {
+    let code = std::fs::read_to_string(&options.shader)
+        .map_err(|error| Error::ShaderCompilationFailed(error.to_string()))?;
+    let source = match options.shader.extension().and_then(|e| e.to_str()) {
+        Some("wgsl") => ShaderToySource::Wgsl(&code),
+        _ => ShaderToySource::Glsl(&code),
+    };
+
+    let context = pollster::block_on(Context::default())?;
+    let format = wgpu::TextureFormat::Rgba8UnormSrgb;
+    let shadertoy = ShaderToy::new(&context, source, &format)?;
+    let channels = options
+        .channels
+        .iter()
+        .map(|path| {
+            let image = image::open(path)?;
+            context.texture_from_image(&image, &wgpu::TextureFormat::Rgba8UnormSrgb, None)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let channels = [0, 1, 2, 3].map(|index| channels.get(index));
+
+    let (width, height) = options.size;
+    let target = context.device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Frame"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+    let target = wgpu_tools::Texture::from_wgpu(&context, target);
+
+    let time_delta = 1.0 / options.fps;
+    for frame in 0..options.frames {
+        let inputs = ShaderToyInputs {
+            time: options.time + frame as f32 * time_delta,
+            time_delta,
+            frame: frame as i32,
+            ..Default::default()
+        };
+        let mut encoder = context
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("ShaderToy Encoder"),
+            });
+        shadertoy.render(
+            &context,
+            &mut encoder,
+            &target.view,
+            &width,
+            &height,
+            &inputs,
+            channels,
+        );
+        context.queue.submit(std::iter::once(encoder.finish()));
+
+        let data = context.read_texture(&target)?;
+        let image =
+            image::RgbaImage::from_raw(width, height, data).ok_or(Error::TextureCreationFailed)?;
+        let path = match options.frames {
+            1 => options.output.clone(),
+            _ => numbered(&options.output, frame),
+        };
+        image.save(path)?;
+    }
+    Ok(())
+}
+
+fn numbered(path: &Path, frame: u32) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{stem}.{frame:04}.{extension}"))
+}
+
+fn main() -> ExitCode {
+    let options = match parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            if !message.is_empty() {
+                eprintln!("error: {message}");
+            }
+            eprintln!("{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+    match render(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
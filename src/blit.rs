@@ -0,0 +1,307 @@
+use super::context::Context;
+use super::diagnostics::PassRecord;
+use super::error::Error;
+use super::fixed_resolution::ScalingMode;
+use super::texture::Texture;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Fullscreen-triangle copy of a filterable 2D texture into a render
+/// target, with one pipeline per target format. `unfilterable` pipelines
+/// copy unfilterable float textures with a non-filtering sampler.
+pub(crate) struct BlitPipeline {
+    module: wgpu::ShaderModule,
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline_layout: wgpu::PipelineLayout,
+    pipelines: Mutex<HashMap<wgpu::TextureFormat, wgpu::RenderPipeline>>,
+}
+
+impl BlitPipeline {
+    pub(crate) fn new(context: &Context) -> Self {
+        Self::with_filtering(context, true)
+    }
+
+    pub(crate) fn unfilterable(context: &Context) -> Self {
+        Self::with_filtering(context, false)
+    }
+
+    fn with_filtering(context: &Context, filterable: bool) -> Self {
+        let device = &context.device;
+        let module = device.create_shader_module(wgpu::include_wgsl!("shaders/blit.wgsl"));
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Blit Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(match filterable {
+                        true => wgpu::SamplerBindingType::Filtering,
+                        false => wgpu::SamplerBindingType::NonFiltering,
+                    }),
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Blit Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        Self {
+            module,
+            bind_group_layout,
+            pipeline_layout,
+            pipelines: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Draws `source` into `viewport` (x, y, width, height in target
+    /// pixels, the whole target if `None`), clearing the target first if
+    /// `clear` is set.
+    #[allow(clippy::too_many_arguments)]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip_all, fields(format = ?target_format, ?viewport))
+    )]
+    pub(crate) fn draw(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        source: &wgpu::TextureView,
+        sampler: &wgpu::Sampler,
+        target: &wgpu::TextureView,
+        target_format: wgpu::TextureFormat,
+        viewport: Option<[f32; 4]>,
+        clear: Option<wgpu::Color>,
+    ) {
+        let bind_group = context
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Blit Bind Group"),
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(source),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(sampler),
+                    },
+                ],
+            });
+
+        context.diagnostics.record_pass(|| {
+            PassRecord::new(
+                "Blit Pass",
+                "Blit Pipeline",
+                vec![format!("target: {target_format:?}")],
+            )
+        });
+
+        let mut pipelines = self
+            .pipelines
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let pipeline = pipelines
+            .entry(target_format)
+            .or_insert_with(|| self.create_pipeline(context, target_format));
+
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Blit Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: clear.map_or(wgpu::LoadOp::Load, wgpu::LoadOp::Clear),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        if let Some([x, y, width, height]) = viewport {
+            pass.set_viewport(x, y, width, height, 0.0, 1.0);
+        }
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+
+    fn create_pipeline(
+        &self,
+        context: &Context,
+        target_format: wgpu::TextureFormat,
+    ) -> wgpu::RenderPipeline {
+        context
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Blit Pipeline"),
+                layout: Some(&self.pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &self.module,
+                    entry_point: "vs_main",
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    buffers: &[],
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &self.module,
+                    entry_point: "fs_main",
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    targets: &[Some(target_format.into())],
+                }),
+                multiview: None,
+            })
+    }
+}
+
+/// Copies textures into render targets of other sizes and formats with a
+/// fullscreen pass, for downscaling, letterboxing and presenting offscreen
+/// targets. Colors are converted through the shader, so sRGB sources and
+/// targets are decoded and encoded as usual.
+pub struct Blitter {
+    filterable: BlitPipeline,
+    unfilterable: BlitPipeline,
+    linear: wgpu::Sampler,
+    nearest: wgpu::Sampler,
+    /// How the source is fitted into the target, stretched by default.
+    pub mode: ScalingMode,
+    /// Filter when scaling filterable sources. Unfilterable float sources,
+    /// such as `Rgba32Float` on most devices, are always sampled nearest.
+    pub filter: wgpu::FilterMode,
+    /// Color the target is cleared to first, filling the letterbox, or
+    /// `None` to draw over its contents.
+    pub clear_color: Option<wgpu::Color>,
+}
+
+impl Blitter {
+    pub fn new(context: &Context) -> Self {
+        Self {
+            filterable: BlitPipeline::new(context),
+            unfilterable: BlitPipeline::unfilterable(context),
+            linear: context.linear_sampler(),
+            nearest: context.device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("Blit Nearest Sampler"),
+                ..Default::default()
+            }),
+            mode: ScalingMode::Stretch,
+            filter: wgpu::FilterMode::Linear,
+            clear_color: Some(wgpu::Color::BLACK),
+        }
+    }
+
+    /// Draws `source`, a single-sampled 2D float texture, into `target`,
+    /// placed by `mode`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "trace",
+            skip_all,
+            fields(source = ?source.format(), target = ?target.format())
+        )
+    )]
+    pub fn blit(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        source: &Texture,
+        target: &Texture,
+    ) -> Result<(), Error> {
+        if source.view_dimension() != wgpu::TextureViewDimension::D2
+            || source.sample_count() != 1
+            || !source
+                .usage()
+                .contains(wgpu::TextureUsages::TEXTURE_BINDING)
+            || target.sample_count() != 1
+            || !target
+                .usage()
+                .contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+        {
+            return Err(Error::TextureCreationFailed);
+        }
+        let filterable = match source
+            .format()
+            .sample_type(None, Some(context.device.features()))
+        {
+            Some(wgpu::TextureSampleType::Float { filterable }) => filterable,
+            _ => return Err(Error::UnsupportedTextureFormat(source.format())),
+        };
+        let (pipeline, sampler) = match (filterable, self.filter) {
+            (true, wgpu::FilterMode::Linear) => (&self.filterable, &self.linear),
+            (true, wgpu::FilterMode::Nearest) => (&self.filterable, &self.nearest),
+            (false, _) => (&self.unfilterable, &self.nearest),
+        };
+        pipeline.draw(
+            context,
+            encoder,
+            &source.view,
+            sampler,
+            &target.view,
+            target.format(),
+            Some(self.mode.viewport(
+                source.width(),
+                source.height(),
+                target.width(),
+                target.height(),
+            )),
+            self.clear_color,
+        );
+        Ok(())
+    }
+}
+
+impl Context {
+    /// Resamples `texture` into a new render target of the same format with
+    /// linear filtering. The format must be renderable and filterable.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, texture),
+            fields(width = *width, height = *height),
+            err
+        )
+    )]
+    pub fn resize_texture(
+        &self,
+        texture: &Texture,
+        width: &u32,
+        height: &u32,
+        label: Option<&str>,
+    ) -> Result<Texture, Error> {
+        let format = texture.format();
+        let resized = self.render_target(width, height, &format, label)?;
+        let mut command_encoder =
+            self.device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Resize Encoder"),
+                });
+        BlitPipeline::new(self).draw(
+            self,
+            &mut command_encoder,
+            &texture.view,
+            &self.linear_sampler(),
+            &resized.view,
+            format,
+            None,
+            None,
+        );
+        self.submit(command_encoder);
+        Ok(resized)
+    }
+}
@@ -0,0 +1,223 @@
+use super::context::Context;
+use super::error::Error;
+use super::screen::ResizeRegistry;
+use super::texture::Texture;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Downgrade {
+    /// `levels` top mips of `streaming[index]` were dropped.
+    DroppedMips {
+        index: usize,
+        levels: u32,
+        freed: u64,
+    },
+    /// The render targets' resolution scale was lowered to `scale`.
+    ScaledTargets { scale: f32, freed: u64 },
+}
+
+impl Downgrade {
+    pub fn freed(&self) -> u64 {
+        match self {
+            Downgrade::DroppedMips { freed, .. } | Downgrade::ScaledTargets { freed, .. } => *freed,
+        }
+    }
+}
+
+type DowngradeCallback = Box<dyn FnMut(&Downgrade) + Send>;
+
+/// Keeps streaming textures and screen-sized targets within a memory
+/// budget by dropping top mips of the largest streaming texture first and
+/// halving the targets' resolution once no texture can lose another mip.
+///
+/// Downgraded textures are recreated, so bind groups referencing them have
+/// to be rebuilt; the callbacks registered with `on_downgrade` are the place
+/// to do that.
+pub struct BudgetPolicy {
+    budget: u64,
+    min_mip_size: u32,
+    min_target_scale: f32,
+    callbacks: Vec<DowngradeCallback>,
+}
+
+impl BudgetPolicy {
+    pub fn new(budget: u64) -> Self {
+        Self {
+            budget,
+            min_mip_size: 64,
+            min_target_scale: 0.25,
+            callbacks: Vec::new(),
+        }
+    }
+
+    pub fn budget(&self) -> u64 {
+        self.budget
+    }
+
+    pub fn set_budget(&mut self, budget: u64) {
+        self.budget = budget;
+    }
+
+    /// Streaming textures are not shrunk below this width or height.
+    pub fn min_mip_size(mut self, size: u32) -> Self {
+        self.min_mip_size = size;
+        self
+    }
+
+    /// Targets are not scaled below this resolution scale.
+    pub fn min_target_scale(mut self, scale: f32) -> Self {
+        self.min_target_scale = scale;
+        self
+    }
+
+    pub fn on_downgrade<F>(&mut self, callback: F)
+    where
+        F: FnMut(&Downgrade) + Send + 'static,
+    {
+        self.callbacks.push(Box::new(callback));
+    }
+
+    /// Downgrades resources until `other_usage` (memory the application
+    /// tracks itself) plus the streaming textures and targets fit the
+    /// budget, or nothing more can be downgraded. Streaming textures need
+    /// `COPY_SRC` usage.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self, context, streaming, targets), err)
+    )]
+    pub fn enforce(
+        &mut self,
+        context: &Context,
+        streaming: &mut [&mut Texture],
+        mut targets: Option<&mut ResizeRegistry>,
+        other_usage: u64,
+    ) -> Result<Vec<Downgrade>, Error> {
+        let mut downgrades = Vec::new();
+        loop {
+            let streaming_usage: u64 = streaming.iter().map(|t| t.memory_size()).sum();
+            let target_usage = targets.as_ref().map_or(0, |t| t.memory_size());
+            if other_usage + streaming_usage + target_usage <= self.budget {
+                break;
+            }
+
+            let largest = streaming
+                .iter()
+                .enumerate()
+                .filter(|(_, texture)| self.can_drop_mip(texture))
+                .max_by_key(|(_, texture)| texture.memory_size())
+                .map(|(index, _)| index);
+            let downgrade = if let Some(index) = largest {
+                let before = streaming[index].memory_size();
+                context.drop_top_mips(streaming[index], 1)?;
+                Downgrade::DroppedMips {
+                    index,
+                    levels: 1,
+                    freed: before - streaming[index].memory_size(),
+                }
+            } else if let Some(targets) = targets
+                .as_deref_mut()
+                .filter(|t| t.resolution_scale() * 0.5 >= self.min_target_scale)
+            {
+                let before = targets.memory_size();
+                let scale = targets.resolution_scale() * 0.5;
+                targets.set_resolution_scale(context, scale)?;
+                Downgrade::ScaledTargets {
+                    scale,
+                    freed: before.saturating_sub(targets.memory_size()),
+                }
+            } else {
+                break;
+            };
+
+            #[cfg(feature = "tracing")]
+            tracing::info!(?downgrade, "memory budget exceeded");
+            for callback in &mut self.callbacks {
+                callback(&downgrade);
+            }
+            downgrades.push(downgrade);
+        }
+        Ok(downgrades)
+    }
+
+    fn can_drop_mip(&self, texture: &Texture) -> bool {
+        let (block_width, block_height) = texture.format().block_dimensions();
+        let (width, height) = (texture.width() / 2, texture.height() / 2);
+        texture.mip_level_count() > 1
+            && texture.usage().contains(wgpu::TextureUsages::COPY_SRC)
+            && width >= self.min_mip_size
+            && height >= self.min_mip_size
+            && width.is_multiple_of(block_width)
+            && height.is_multiple_of(block_height)
+    }
+}
+
+impl Context {
+    /// Replaces `texture` with a copy that lacks its `levels` largest mips,
+    /// keeping the sampler and view dimension. The texture needs
+    /// `COPY_SRC` usage, and the new top level of a block-compressed
+    /// texture has to be whole blocks.
+    pub fn drop_top_mips(&self, texture: &mut Texture, levels: u32) -> Result<(), Error> {
+        if levels == 0 {
+            return Ok(());
+        }
+        if levels >= texture.mip_level_count() {
+            return Err(Error::InvalidTexture(format!(
+                "cannot drop {levels} of {} mip levels",
+                texture.mip_level_count()
+            )));
+        }
+        if !texture.usage().contains(wgpu::TextureUsages::COPY_SRC) {
+            return Err(Error::InvalidTexture(
+                "dropping mips needs COPY_SRC usage".into(),
+            ));
+        }
+
+        let (format, dimension) = (texture.format(), texture.dimension());
+        let size = texture.size().mip_level_size(levels, dimension);
+        let (block_width, block_height) = format.block_dimensions();
+        if !size.width.is_multiple_of(block_width) || !size.height.is_multiple_of(block_height) {
+            return Err(Error::InvalidTexture(format!(
+                "mip level {levels} of {}x{} is not a multiple of the {block_width}x{block_height} blocks of {format:?}",
+                size.width, size.height
+            )));
+        }
+        let mip_level_count = texture.mip_level_count() - levels;
+        let label = format!("Downgraded Texture ({levels} mips dropped)");
+        let downgraded = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(&label),
+            size,
+            mip_level_count,
+            sample_count: texture.sample_count(),
+            dimension,
+            format,
+            view_formats: &[],
+            usage: texture.usage() | wgpu::TextureUsages::COPY_DST,
+        });
+
+        let mut command_encoder =
+            self.device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Mip Drop Encoder"),
+                });
+        for level in 0..mip_level_count {
+            command_encoder.copy_texture_to_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &texture.texture,
+                    mip_level: level + levels,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                wgpu::ImageCopyTexture {
+                    texture: &downgraded,
+                    mip_level: level,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                size.mip_level_size(level, dimension).physical_size(format),
+            );
+        }
+        self.submit(command_encoder);
+
+        texture.replace(downgraded);
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+use super::context::Context;
+use super::error::Error;
+use wgpu::util::DeviceExt;
+
+impl Context {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self, data), fields(bytes = data.len()), err)
+    )]
+    pub fn buffer_with_data(
+        &self,
+        data: &[u8],
+        usage: wgpu::BufferUsages,
+        label: Option<&str>,
+    ) -> Result<wgpu::Buffer, Error> {
+        self.check_buffer_size(data.len() as u64)?;
+        let buffer = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label,
+                contents: data,
+                usage,
+            });
+        #[cfg(feature = "tracing")]
+        tracing::debug!("created buffer");
+        Ok(buffer)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(bytes = buffer.size()), err)
+    )]
+    pub fn read_buffer(&self, buffer: &wgpu::Buffer) -> Result<Vec<u8>, Error> {
+        let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Readback Buffer"),
+            size: buffer.size(),
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut command_encoder =
+            self.device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Readback Encoder"),
+                });
+        command_encoder.copy_buffer_to_buffer(buffer, 0, &staging, 0, buffer.size());
+        self.submit(command_encoder);
+
+        self.map_read(&staging)
+    }
+
+    pub(crate) fn map_read(&self, staging: &wgpu::Buffer) -> Result<Vec<u8>, Error> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let slice = staging.slice(..);
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        receiver
+            .recv()
+            .map_err(|_| Error::BufferMappingFailed(wgpu::BufferAsyncError))??;
+
+        let data = slice.get_mapped_range().to_vec();
+        staging.unmap();
+        Ok(data)
+    }
+}
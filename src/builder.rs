@@ -0,0 +1,324 @@
+use super::context::Context;
+use super::error::Error;
+use super::poller::PollingStrategy;
+
+pub struct ContextBuilder<'a> {
+    backends: wgpu::Backends,
+    flags: wgpu::InstanceFlags,
+    dx12_shader_compiler: wgpu::Dx12Compiler,
+    gles_minor_version: wgpu::Gles3MinorVersion,
+    power_preference: wgpu::PowerPreference,
+    force_fallback_adapter: bool,
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    adapter_name: Option<String>,
+    required_features: wgpu::Features,
+    required_limits: wgpu::Limits,
+    adapter_limits: bool,
+    label: Option<&'a str>,
+    trace_path: Option<&'a std::path::Path>,
+    env_overrides: bool,
+    reverse_z: bool,
+    polling_strategy: PollingStrategy,
+    texture_anisotropy: u16,
+}
+
+impl<'a> ContextBuilder<'a> {
+    pub fn new() -> Self {
+        Self {
+            backends: wgpu::Backends::all(),
+            flags: wgpu::InstanceFlags::from_build_config(),
+            dx12_shader_compiler: wgpu::Dx12Compiler::default(),
+            gles_minor_version: wgpu::Gles3MinorVersion::default(),
+            power_preference: wgpu::PowerPreference::default(),
+            force_fallback_adapter: false,
+            adapter_name: None,
+            required_features: wgpu::Features::empty(),
+            required_limits: wgpu::Limits::default(),
+            adapter_limits: false,
+            label: None,
+            trace_path: None,
+            env_overrides: true,
+            reverse_z: false,
+            polling_strategy: PollingStrategy::Blocking,
+            texture_anisotropy: 1,
+        }
+    }
+
+    pub fn backends(mut self, backends: wgpu::Backends) -> Self {
+        self.backends = backends;
+        self
+    }
+
+    pub fn instance_flags(mut self, flags: wgpu::InstanceFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    pub fn debug(mut self, enabled: bool) -> Self {
+        self.flags.set(wgpu::InstanceFlags::DEBUG, enabled);
+        self
+    }
+
+    pub fn validation(mut self, enabled: bool) -> Self {
+        self.flags.set(wgpu::InstanceFlags::VALIDATION, enabled);
+        self
+    }
+
+    pub fn dx12_shader_compiler(mut self, compiler: wgpu::Dx12Compiler) -> Self {
+        self.dx12_shader_compiler = compiler;
+        self
+    }
+
+    pub fn gles_minor_version(mut self, version: wgpu::Gles3MinorVersion) -> Self {
+        self.gles_minor_version = version;
+        self
+    }
+
+    pub fn power_preference(mut self, power_preference: wgpu::PowerPreference) -> Self {
+        self.power_preference = power_preference;
+        self
+    }
+
+    pub fn force_fallback_adapter(mut self, force: bool) -> Self {
+        self.force_fallback_adapter = force;
+        self
+    }
+
+    /// Picks the first adapter whose name contains `name` (case-insensitive).
+    /// Ignored on wasm32, where adapters cannot be enumerated.
+    pub fn adapter_name(mut self, name: &str) -> Self {
+        self.adapter_name = Some(name.to_lowercase());
+        self
+    }
+
+    pub fn features(mut self, features: wgpu::Features) -> Self {
+        self.required_features = features;
+        self
+    }
+
+    pub fn limits(mut self, limits: wgpu::Limits) -> Self {
+        self.required_limits = limits;
+        self.adapter_limits = false;
+        self
+    }
+
+    /// Requests the full limits of the selected adapter instead of the
+    /// defaults.
+    pub fn adapter_limits(mut self) -> Self {
+        self.adapter_limits = true;
+        self
+    }
+
+    pub fn label(mut self, label: &'a str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    pub fn trace_path(mut self, path: &'a std::path::Path) -> Self {
+        self.trace_path = Some(path);
+        self
+    }
+
+    /// Whether `WGPU_BACKEND`, `WGPU_POWER_PREF`, `WGPU_ADAPTER_NAME`,
+    /// `WGPU_DX12_COMPILER`, `WGPU_GLES_MINOR_VERSION` and the instance flag
+    /// variables (`WGPU_VALIDATION`, `WGPU_DEBUG`, ...) override the builder.
+    /// Enabled by default.
+    pub fn env_overrides(mut self, enabled: bool) -> Self {
+        self.env_overrides = enabled;
+        self
+    }
+
+    /// Clear depth to 0.0 and pass nearer fragments with `Greater`, for
+    /// projections mapping the near plane to 1.0. See
+    /// `Context::set_reverse_z`.
+    pub fn reverse_z(mut self, enabled: bool) -> Self {
+        self.reverse_z = enabled;
+        self
+    }
+
+    /// See `Context::set_polling_strategy`.
+    pub fn polling_strategy(mut self, strategy: PollingStrategy) -> Self {
+        self.polling_strategy = strategy;
+        self
+    }
+
+    /// Anisotropy of the samplers of the textures the context creates,
+    /// clamped to `Context::max_anisotropy` on adapters that support less.
+    /// See `Context::set_texture_anisotropy`.
+    pub fn texture_anisotropy(mut self, clamp: u16) -> Self {
+        self.texture_anisotropy = clamp;
+        self
+    }
+
+    /// The backends, overridden by `WGPU_BACKEND` with `env_overrides`.
+    fn effective_backends(&self) -> wgpu::Backends {
+        match self.env_overrides {
+            true => wgpu::util::backend_bits_from_env().unwrap_or(self.backends),
+            false => self.backends,
+        }
+    }
+
+    pub fn instance(&self) -> wgpu::Instance {
+        let mut descriptor = wgpu::InstanceDescriptor {
+            backends: self.effective_backends(),
+            flags: self.flags,
+            dx12_shader_compiler: self.dx12_shader_compiler.clone(),
+            gles_minor_version: self.gles_minor_version,
+        };
+        if self.env_overrides {
+            descriptor.flags = descriptor.flags.with_env();
+            descriptor.dx12_shader_compiler = wgpu::util::dx12_shader_compiler_from_env()
+                .unwrap_or(descriptor.dx12_shader_compiler);
+            descriptor.gles_minor_version =
+                wgpu::util::gles_minor_version_from_env().unwrap_or(descriptor.gles_minor_version);
+        }
+        wgpu::Instance::new(descriptor)
+    }
+
+    pub async fn build(self) -> Result<Context, Error> {
+        let instance = self.instance();
+        self.build_with_instance(instance, None).await
+    }
+
+    /// Builds on an instance created by `instance()`, so that surfaces can
+    /// be created before the adapter is chosen.
+    pub async fn build_with_instance(
+        self,
+        instance: wgpu::Instance,
+        compatible_surface: Option<&wgpu::Surface<'_>>,
+    ) -> Result<Context, Error> {
+        let adapter = self.adapter(&instance, compatible_surface).await?;
+
+        let required_limits = if self.adapter_limits {
+            adapter.limits()
+        } else {
+            self.required_limits.clone()
+        };
+        let device_descriptor = wgpu::DeviceDescriptor {
+            label: self.label,
+            required_features: self.required_features,
+            required_limits,
+        };
+
+        let mut context =
+            Context::from_adapter(instance, adapter, &device_descriptor, self.trace_path).await?;
+        context.set_reverse_z(self.reverse_z);
+        context.set_polling_strategy(self.polling_strategy);
+        context.texture_anisotropy = self.texture_anisotropy.clamp(1, context.max_anisotropy());
+        Ok(context)
+    }
+
+    /// The one context of `build`, as adapters cannot be enumerated on
+    /// wasm32.
+    #[cfg(target_arch = "wasm32")]
+    pub async fn build_all(self) -> Result<Vec<Context>, Error> {
+        Ok(vec![self.build().await?])
+    }
+
+    /// Creates one context per adapter matching the backends, the adapter
+    /// name filter and `force_fallback_adapter`, as `build` would choose
+    /// them, each with its own instance. Adapters that fail to create a
+    /// device are skipped. On wasm32, where adapters cannot be enumerated,
+    /// this is the one context of `build`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn build_all(self) -> Result<Vec<Context>, Error> {
+        let backends = self.effective_backends();
+        let name_filter = self.name_filter();
+        let count = self.instance().enumerate_adapters(backends).len();
+        let mut contexts = Vec::with_capacity(count);
+        for index in 0..count {
+            let instance = self.instance();
+            let Some(adapter) = instance.enumerate_adapters(backends).into_iter().nth(index) else {
+                continue;
+            };
+            if !self.matches(&adapter, name_filter.as_deref()) {
+                continue;
+            }
+
+            let required_limits = if self.adapter_limits {
+                adapter.limits()
+            } else {
+                self.required_limits.clone()
+            };
+            let device_descriptor = wgpu::DeviceDescriptor {
+                label: self.label,
+                required_features: self.required_features,
+                required_limits,
+            };
+            if let Ok(mut context) =
+                Context::from_adapter(instance, adapter, &device_descriptor, self.trace_path).await
+            {
+                context.set_reverse_z(self.reverse_z);
+                context.set_polling_strategy(self.polling_strategy);
+                context.texture_anisotropy =
+                    self.texture_anisotropy.clamp(1, context.max_anisotropy());
+                contexts.push(context);
+            }
+        }
+
+        if contexts.is_empty() {
+            return Err(Error::RequestingAdapterFailed);
+        }
+        Ok(contexts)
+    }
+
+    /// The adapter name filter, from `WGPU_ADAPTER_NAME` if the
+    /// environment overrides the builder.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn name_filter(&self) -> Option<String> {
+        std::env::var("WGPU_ADAPTER_NAME")
+            .ok()
+            .filter(|_| self.env_overrides)
+            .map(|name| name.to_lowercase())
+            .or_else(|| self.adapter_name.clone())
+    }
+
+    /// Whether an enumerated adapter passes the name filter and, if a
+    /// fallback adapter is forced, is a software one, so that `build` and
+    /// `build_all` choose from the same adapters.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn matches(&self, adapter: &wgpu::Adapter, name_filter: Option<&str>) -> bool {
+        let info = adapter.get_info();
+        name_filter.is_none_or(|name| info.name.to_lowercase().contains(name))
+            && (!self.force_fallback_adapter || info.device_type == wgpu::DeviceType::Cpu)
+    }
+
+    async fn adapter(
+        &self,
+        instance: &wgpu::Instance,
+        compatible_surface: Option<&wgpu::Surface<'_>>,
+    ) -> Result<wgpu::Adapter, Error> {
+        // Adapters cannot be enumerated on wasm32, which picks one by power
+        // preference only.
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(name) = self.name_filter() {
+            return instance
+                .enumerate_adapters(self.effective_backends())
+                .into_iter()
+                .filter(|adapter| {
+                    compatible_surface.is_none_or(|surface| adapter.is_surface_supported(surface))
+                })
+                .find(|adapter| self.matches(adapter, Some(&name)))
+                .ok_or(Error::RequestingAdapterFailed);
+        }
+
+        let power_preference = match self.env_overrides {
+            true => wgpu::util::power_preference_from_env().unwrap_or(self.power_preference),
+            false => self.power_preference,
+        };
+        instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference,
+                force_fallback_adapter: self.force_fallback_adapter,
+                compatible_surface,
+            })
+            .await
+            .ok_or(Error::RequestingAdapterFailed)
+    }
+}
+
+impl Default for ContextBuilder<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,172 @@
+use super::context::Context;
+use super::error::Error;
+use super::mesh::Mesh;
+use super::progress::Progress;
+
+/// Triangles per leaf, at most.
+const LEAF_SIZE: usize = 4;
+
+/// A node of `Bvh`, with the layout of `BvhNode` in raytracing.wgsl.
+#[repr(C)]
+#[derive(Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct BvhNode {
+    min: [f32; 3],
+    /// Index of the left child, followed by the right one, or of the first
+    /// triangle of a leaf.
+    first: u32,
+    max: [f32; 3],
+    /// Triangles of a leaf, 0 for inner nodes.
+    count: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct BvhTriangle {
+    vertices: [[f32; 4]; 3],
+}
+
+/// A bounding volume hierarchy over triangles for the WGSL ray traversal
+/// in raytracing.wgsl, split at the median centroid along the longest
+/// axis.
+pub(crate) struct Bvh {
+    pub(crate) nodes: Vec<BvhNode>,
+    pub(crate) triangles: Vec<BvhTriangle>,
+}
+
+impl Bvh {
+    /// Reports the triangles placed in leaves as the "bvh" stage of
+    /// `progress`, in steps of a percent.
+    pub(crate) fn new<'a>(
+        meshes: impl IntoIterator<Item = &'a Mesh>,
+        progress: &mut Progress,
+    ) -> Result<Self, Error> {
+        let triangles: Vec<[[f32; 3]; 3]> = meshes
+            .into_iter()
+            .flat_map(|mesh| {
+                mesh.triangles()
+                    .map(|triangle| triangle.map(|i| mesh.vertices[i as usize].position))
+            })
+            .collect();
+        let centroid = |triangle: &[[f32; 3]; 3]| {
+            [0, 1, 2].map(|i| (triangle[0][i] + triangle[1][i] + triangle[2][i]) / 3.0)
+        };
+        let mut order: Vec<usize> = (0..triangles.len()).collect();
+        let mut nodes = vec![BvhNode::default()];
+        let mut stack = vec![(0, 0, triangles.len())];
+        let total = triangles.len() as u64;
+        let mut placed = 0;
+        progress.report("bvh", 0, total)?;
+        let percent = |placed| placed * 100 / total.max(1);
+        while let Some((node, start, end)) = stack.pop() {
+            let (mut min, mut max) = ([f32::MAX; 3], [f32::MIN; 3]);
+            let (mut centroid_min, mut centroid_max) = ([f32::MAX; 3], [f32::MIN; 3]);
+            for &triangle in &order[start..end] {
+                for vertex in triangles[triangle] {
+                    min = [0, 1, 2].map(|i| min[i].min(vertex[i]));
+                    max = [0, 1, 2].map(|i| max[i].max(vertex[i]));
+                }
+                let c = centroid(&triangles[triangle]);
+                centroid_min = [0, 1, 2].map(|i| centroid_min[i].min(c[i]));
+                centroid_max = [0, 1, 2].map(|i| centroid_max[i].max(c[i]));
+            }
+            let extent = [0, 1, 2].map(|i| centroid_max[i] - centroid_min[i]);
+            let axis = match extent {
+                [x, y, z] if x >= y && x >= z => 0,
+                [_, y, z] if y >= z => 1,
+                _ => 2,
+            };
+            if end - start <= LEAF_SIZE || extent[axis] <= 0.0 {
+                nodes[node] = BvhNode {
+                    min,
+                    first: start as u32,
+                    max,
+                    count: (end - start) as u32,
+                };
+                let previous = placed;
+                placed += (end - start) as u64;
+                if percent(placed) != percent(previous) {
+                    progress.report("bvh", placed, total)?;
+                }
+                continue;
+            }
+            let middle = (start + end) / 2;
+            order[start..end].select_nth_unstable_by(middle - start, |&a, &b| {
+                centroid(&triangles[a])[axis].total_cmp(&centroid(&triangles[b])[axis])
+            });
+            let left = nodes.len();
+            nodes.extend([BvhNode::default(); 2]);
+            nodes[node] = BvhNode {
+                min,
+                first: left as u32,
+                max,
+                count: 0,
+            };
+            stack.push((left, start, middle));
+            stack.push((left + 1, middle, end));
+        }
+        let triangles = order
+            .iter()
+            .map(|&triangle| BvhTriangle {
+                vertices: triangles[triangle].map(|[x, y, z]| [x, y, z, 0.0]),
+            })
+            .collect();
+        Ok(Self { nodes, triangles })
+    }
+
+    pub(crate) fn bind_group_layout(context: &Context) -> wgpu::BindGroupLayout {
+        let entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        context
+            .device
+            .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("BVH Bind Group Layout"),
+                entries: &[entry(0), entry(1)],
+            })
+    }
+
+    /// Uploads the hierarchy for group 1 of raytracing.wgsl.
+    pub(crate) fn bind_group(
+        &self,
+        context: &Context,
+        layout: &wgpu::BindGroupLayout,
+    ) -> Result<wgpu::BindGroup, Error> {
+        if self.triangles.is_empty() {
+            return Err(Error::InvalidMesh("no triangles to trace".to_string()));
+        }
+        context.check_binding_size(std::mem::size_of_val(&self.triangles[..]) as u64, true)?;
+        let nodes = context.buffer_with_data(
+            bytemuck::cast_slice(&self.nodes),
+            wgpu::BufferUsages::STORAGE,
+            Some("BVH Nodes"),
+        )?;
+        let triangles = context.buffer_with_data(
+            bytemuck::cast_slice(&self.triangles),
+            wgpu::BufferUsages::STORAGE,
+            Some("BVH Triangles"),
+        )?;
+        Ok(context
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("BVH Bind Group"),
+                layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: nodes.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: triangles.as_entire_binding(),
+                    },
+                ],
+            }))
+    }
+}
@@ -0,0 +1,168 @@
+#[cfg(feature = "image")]
+use super::capture::FrameCapture;
+#[cfg(feature = "image")]
+use super::context::Context;
+#[cfg(feature = "image")]
+use super::error::Error;
+use super::math::{normalize, sub};
+use super::scene::Camera;
+#[cfg(feature = "image")]
+use super::texture::Texture;
+
+/// Scripted camera motion for showcase renders and regression sequences,
+/// sampled at a normalized time in [0, 1]. The field of view, clip planes
+/// and projection flags come from a base camera.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CameraPath {
+    /// Circles `center` at `radius` and `height` above it, looking at it,
+    /// starting on its +Z side and turning counter-clockwise seen from +Y.
+    Turntable {
+        center: [f32; 3],
+        radius: f32,
+        height: f32,
+        start_angle_degrees: f32,
+        revolutions: f32,
+    },
+    /// Flies through `positions` on a Catmull-Rom spline, with the same
+    /// time per segment. The camera looks at `targets` on a spline of their
+    /// own, at the only target if there is one, or along the path if there
+    /// are none.
+    Spline {
+        positions: Vec<[f32; 3]>,
+        targets: Vec<[f32; 3]>,
+    },
+}
+
+impl CameraPath {
+    /// One revolution around `center`.
+    pub fn turntable(center: [f32; 3], radius: f32, height: f32) -> Self {
+        CameraPath::Turntable {
+            center,
+            radius,
+            height,
+            start_angle_degrees: 0.0,
+            revolutions: 1.0,
+        }
+    }
+
+    /// A flythrough looking along the path.
+    pub fn spline(positions: Vec<[f32; 3]>) -> Self {
+        CameraPath::Spline {
+            positions,
+            targets: Vec::new(),
+        }
+    }
+
+    /// Whether the end meets the start, so that sequences leave out the
+    /// last frame to loop seamlessly.
+    pub fn is_closed(&self) -> bool {
+        match self {
+            CameraPath::Turntable { revolutions, .. } => {
+                *revolutions != 0.0 && revolutions.fract() == 0.0
+            }
+            CameraPath::Spline { .. } => false,
+        }
+    }
+
+    pub fn camera_at(&self, base: &Camera, t: f32) -> Camera {
+        let t = t.clamp(0.0, 1.0);
+        let (position, target) = match self {
+            CameraPath::Turntable {
+                center,
+                radius,
+                height,
+                start_angle_degrees,
+                revolutions,
+            } => {
+                let angle =
+                    start_angle_degrees.to_radians() + t * revolutions * std::f32::consts::TAU;
+                let position = [
+                    center[0] + radius * angle.sin(),
+                    center[1] + height,
+                    center[2] + radius * angle.cos(),
+                ];
+                (position, *center)
+            }
+            CameraPath::Spline { positions, targets } => {
+                let position = catmull_rom(positions, t);
+                let target = match targets.len() {
+                    0 => {
+                        // Look a little ahead, or behind at the very end.
+                        let ahead = catmull_rom(positions, (t + 1e-3).min(1.0));
+                        let behind = catmull_rom(positions, (t - 1e-3).max(0.0));
+                        let direction = normalize(sub(ahead, behind));
+                        [0, 1, 2].map(|i| position[i] + direction[i])
+                    }
+                    _ => catmull_rom(targets, t),
+                };
+                (position, target)
+            }
+        };
+        Camera {
+            position,
+            target,
+            ..base.clone()
+        }
+    }
+
+    /// `frames` cameras evenly spread over the path, without the end of a
+    /// closed path.
+    pub fn cameras(&self, base: &Camera, frames: usize) -> Vec<Camera> {
+        let steps = match self.is_closed() {
+            true => frames,
+            false => frames.saturating_sub(1).max(1),
+        };
+        (0..frames)
+            .map(|frame| self.camera_at(base, frame as f32 / steps as f32))
+            .collect()
+    }
+}
+
+/// The uniform Catmull-Rom spline through `points` at `t` in [0, 1], with
+/// the end points repeated as tangent controls.
+fn catmull_rom(points: &[[f32; 3]], t: f32) -> [f32; 3] {
+    match points.len() {
+        0 => return [0.0; 3],
+        1 => return points[0],
+        _ => {}
+    }
+    let segments = points.len() - 1;
+    let position = t * segments as f32;
+    let segment = (position.floor() as usize).min(segments - 1);
+    let s = position - segment as f32;
+    let point = |i: isize| points[i.clamp(0, segments as isize) as usize];
+    let i = segment as isize;
+    let (p0, p1, p2, p3) = (point(i - 1), point(i), point(i + 1), point(i + 2));
+    let (s2, s3) = (s * s, s * s * s);
+    std::array::from_fn(|k| {
+        0.5 * (2.0 * p1[k]
+            + (p2[k] - p0[k]) * s
+            + (2.0 * p0[k] - 5.0 * p1[k] + 4.0 * p2[k] - p3[k]) * s2
+            + (3.0 * p1[k] - p0[k] - 3.0 * p2[k] + p3[k]) * s3)
+    })
+}
+
+#[cfg(feature = "image")]
+impl FrameCapture {
+    /// Renders and captures `frames` cameras of `path` as passes named
+    /// `<name> 0000`, `<name> 0001`, ... `render` draws the camera's view
+    /// into `target`, which needs `COPY_SRC` usage. Saving the capture and
+    /// diffing it against an earlier run checks a whole sequence.
+    #[allow(clippy::too_many_arguments)]
+    pub fn capture_camera_path(
+        &mut self,
+        context: &Context,
+        name: &str,
+        path: &CameraPath,
+        base: &Camera,
+        frames: usize,
+        target: &Texture,
+        mut render: impl FnMut(&Camera, &Texture) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        for (frame, camera) in path.cameras(base, frames).iter().enumerate() {
+            render(camera, target)?;
+            self.capture(context, &format!("{name} {frame:04}"), target)?;
+        }
+        Ok(())
+    }
+}
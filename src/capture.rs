@@ -0,0 +1,293 @@
+use super::aov::AovTargets;
+use super::context::Context;
+use super::error::Error;
+use super::texture::Texture;
+use half::f16;
+use image::{Rgba, Rgba32FImage, RgbaImage};
+use std::fmt;
+use std::path::Path;
+
+/// Output textures of the passes of one frame, read back in the order they
+/// were captured. Save the captures of two runs (e.g. two commits) and
+/// `diff` them to find the first pass whose output changed.
+#[derive(Clone, Debug, Default)]
+pub struct FrameCapture {
+    passes: Vec<(String, Rgba32FImage)>,
+}
+
+impl FrameCapture {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads back `texture` (which needs `COPY_SRC` usage) as the output of
+    /// `pass`, replacing an earlier capture with the same name. Supports
+    /// 8-bit unorm, 16-bit float and 32-bit float color formats, including
+    /// the two-channel float formats of motion vectors.
+    pub fn capture(
+        &mut self,
+        context: &Context,
+        pass: &str,
+        texture: &Texture,
+    ) -> Result<(), Error> {
+        let data = context.read_texture(texture)?;
+        let image = to_rgba32f(&data, texture.width(), texture.height(), texture.format())?;
+        match self.passes.iter_mut().find(|(name, _)| name == pass) {
+            Some((_, captured)) => *captured = image,
+            None => self.passes.push((pass.to_string(), image)),
+        }
+        Ok(())
+    }
+
+    /// Captures the beauty `target` as `<name> beauty` and each of `aovs`
+    /// as `<name> <aov name>`, so that `save` writes every pass to its own
+    /// file for compositing.
+    pub fn capture_aovs(
+        &mut self,
+        context: &Context,
+        name: &str,
+        target: &Texture,
+        aovs: &AovTargets,
+    ) -> Result<(), Error> {
+        self.capture(context, &format!("{name} beauty"), target)?;
+        for (aov, texture) in aovs.iter() {
+            self.capture(context, &format!("{name} {}", aov.name()), texture)?;
+        }
+        Ok(())
+    }
+
+    pub fn passes(&self) -> impl Iterator<Item = &str> {
+        self.passes.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn get(&self, pass: &str) -> Option<&Rgba32FImage> {
+        self.passes
+            .iter()
+            .find(|(name, _)| name == pass)
+            .map(|(_, image)| image)
+    }
+
+    /// Writes one OpenEXR file per pass to `directory`, named
+    /// `<index>-<pass>.exr` so `load` restores the order. Characters other
+    /// than letters, digits, spaces, `-`, `_` and `.` become `_`.
+    pub fn save(&self, directory: impl AsRef<Path>) -> Result<(), Error> {
+        let directory = directory.as_ref();
+        std::fs::create_dir_all(directory)?;
+        for (index, (name, image)) in self.passes.iter().enumerate() {
+            image.save(directory.join(format!("{index:03}-{}.exr", file_name(name))))?;
+        }
+        Ok(())
+    }
+
+    pub fn load(directory: impl AsRef<Path>) -> Result<Self, Error> {
+        let mut files: Vec<_> = std::fs::read_dir(directory)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|e| e == "exr"))
+            .collect();
+        files.sort();
+
+        let mut passes = Vec::new();
+        for path in files {
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            let name = stem.split_once('-').map_or(&*stem, |(_, name)| name);
+            passes.push((name.to_string(), image::open(&path)?.into_rgba32f()));
+        }
+        Ok(Self { passes })
+    }
+
+    /// Compares every pass of `self` and `other`. Pixels are counted as
+    /// different when a channel differs by more than `threshold`.
+    pub fn diff(&self, other: &FrameCapture, threshold: f32) -> CaptureDiff {
+        let mut passes: Vec<_> = self
+            .passes
+            .iter()
+            .map(|(name, image)| PassDiff::new(name, Some(image), other.get(name), threshold))
+            .collect();
+        passes.extend(
+            other
+                .passes
+                .iter()
+                .filter(|(name, _)| self.get(name).is_none())
+                .map(|(name, image)| PassDiff::new(name, None, Some(image), threshold)),
+        );
+        CaptureDiff { threshold, passes }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PassDiff {
+    pub pass: String,
+    /// Size in the first and second capture, `None` if the pass is missing.
+    pub size: [Option<(u32, u32)>; 2],
+    pub max_error: f32,
+    pub mean_error: f32,
+    pub differing_pixels: u64,
+    /// Per-pixel maximum channel error scaled so `threshold` maps to mid
+    /// gray, with differing pixels tinted red. Only set if both captures
+    /// have the pass at the same size.
+    pub image: Option<RgbaImage>,
+}
+
+impl PassDiff {
+    fn new(pass: &str, a: Option<&Rgba32FImage>, b: Option<&Rgba32FImage>, threshold: f32) -> Self {
+        let mut diff = Self {
+            pass: pass.to_string(),
+            size: [a.map(|a| a.dimensions()), b.map(|b| b.dimensions())],
+            max_error: 0.0,
+            mean_error: 0.0,
+            differing_pixels: 0,
+            image: None,
+        };
+        let (Some(a), Some(b)) = (a, b) else {
+            return diff;
+        };
+        if a.dimensions() != b.dimensions() {
+            return diff;
+        }
+
+        let scale = 0.5 / threshold.max(f32::EPSILON);
+        let mut image = RgbaImage::new(a.width(), a.height());
+        let mut total = 0.0;
+        for ((pa, pb), out) in a.pixels().zip(b.pixels()).zip(image.pixels_mut()) {
+            let error =
+                pa.0.iter()
+                    .zip(pb.0)
+                    .map(|(x, y)| channel_error(*x, y))
+                    .fold(0.0, f32::max);
+            diff.max_error = diff.max_error.max(error);
+            total += error.min(f32::MAX) as f64;
+            let gray = ((error * scale).min(1.0) * 255.0) as u8;
+            *out = if error > threshold {
+                diff.differing_pixels += 1;
+                Rgba([255, gray / 2, gray / 2, 255])
+            } else {
+                Rgba([gray, gray, gray, 255])
+            };
+        }
+        diff.mean_error = (total / (a.width() as f64 * a.height() as f64).max(1.0)) as f32;
+        diff.image = Some(image);
+        diff
+    }
+
+    pub fn is_match(&self) -> bool {
+        self.image.is_some() && self.differing_pixels == 0
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CaptureDiff {
+    pub threshold: f32,
+    pub passes: Vec<PassDiff>,
+}
+
+impl CaptureDiff {
+    pub fn is_match(&self) -> bool {
+        self.passes.iter().all(PassDiff::is_match)
+    }
+
+    /// The first pass in capture order whose output differs.
+    pub fn first_difference(&self) -> Option<&PassDiff> {
+        self.passes.iter().find(|pass| !pass.is_match())
+    }
+
+    /// Writes `report.txt` and a `<index>-<pass>.png` diff image per pass
+    /// with matching sizes to `directory`.
+    pub fn write_report(&self, directory: impl AsRef<Path>) -> Result<(), Error> {
+        let directory = directory.as_ref();
+        std::fs::create_dir_all(directory)?;
+        for (index, pass) in self.passes.iter().enumerate() {
+            if let Some(image) = &pass.image {
+                image.save(directory.join(format!("{index:03}-{}.png", file_name(&pass.pass))))?;
+            }
+        }
+        std::fs::write(directory.join("report.txt"), self.to_string())?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for CaptureDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Threshold: {}", self.threshold)?;
+        for pass in &self.passes {
+            match pass.size {
+                [Some(a), Some(b)] if a != b => writeln!(
+                    f,
+                    "{}: size changed from {}x{} to {}x{}",
+                    pass.pass, a.0, a.1, b.0, b.1
+                )?,
+                [Some(_), Some(_)] => writeln!(
+                    f,
+                    "{}: {} differing pixels, max error {}, mean error {}",
+                    pass.pass, pass.differing_pixels, pass.max_error, pass.mean_error
+                )?,
+                [None, _] => writeln!(f, "{}: only in the second capture", pass.pass)?,
+                [_, None] => writeln!(f, "{}: only in the first capture", pass.pass)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// NaN in only one capture counts as an infinite error.
+fn channel_error(a: f32, b: f32) -> f32 {
+    match (a.is_nan(), b.is_nan()) {
+        (true, true) => 0.0,
+        (false, false) if a == b => 0.0,
+        (false, false) => (a - b).abs(),
+        _ => f32::INFINITY,
+    }
+}
+
+fn file_name(pass: &str) -> String {
+    pass.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-' | '.' | ' ' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+fn to_rgba32f(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+) -> Result<Rgba32FImage, Error> {
+    use wgpu::TextureFormat as F;
+    let unorm = |v: &u8| *v as f32 / 255.0;
+    let pixels: Vec<f32> = match format {
+        F::Rgba8Unorm | F::Rgba8UnormSrgb => data.iter().map(unorm).collect(),
+        F::Bgra8Unorm | F::Bgra8UnormSrgb => data
+            .chunks_exact(4)
+            .flat_map(|p| [p[2], p[1], p[0], p[3]])
+            .map(|v| unorm(&v))
+            .collect(),
+        F::R8Unorm => data
+            .iter()
+            .flat_map(|v| [unorm(v), 0.0, 0.0, 1.0])
+            .collect(),
+        F::Rgba16Float => bytemuck::pod_collect_to_vec::<u8, f16>(data)
+            .into_iter()
+            .map(f16::to_f32)
+            .collect(),
+        F::R16Float => bytemuck::pod_collect_to_vec::<u8, f16>(data)
+            .into_iter()
+            .flat_map(|v| [v.to_f32(), 0.0, 0.0, 1.0])
+            .collect(),
+        F::Rg16Float => bytemuck::pod_collect_to_vec::<u8, f16>(data)
+            .chunks_exact(2)
+            .flat_map(|p| [p[0].to_f32(), p[1].to_f32(), 0.0, 1.0])
+            .collect(),
+        F::Rgba32Float => bytemuck::pod_collect_to_vec(data),
+        F::R32Float => bytemuck::pod_collect_to_vec::<u8, f32>(data)
+            .into_iter()
+            .flat_map(|v| [v, 0.0, 0.0, 1.0])
+            .collect(),
+        F::Rg32Float => bytemuck::pod_collect_to_vec::<u8, f32>(data)
+            .chunks_exact(2)
+            .flat_map(|p| [p[0], p[1], 0.0, 1.0])
+            .collect(),
+        _ => return Err(Error::UnsupportedTextureFormat(format)),
+    };
+    Rgba32FImage::from_raw(width, height, pixels).ok_or(Error::TextureCreationFailed)
+}
@@ -0,0 +1,309 @@
+use super::context::Context;
+use super::error::Error;
+use super::limits::check_limit;
+use super::progress::Progress;
+
+const STAGING_CHUNK_SIZE: u64 = 64 << 20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DispatchChunk {
+    pub first_workgroup: u64,
+    pub workgroup_count: u64,
+    pub workgroups: (u32, u32, u32),
+}
+
+pub struct BufferChunk {
+    pub buffer: wgpu::Buffer,
+    pub offset: u64,
+    pub size: u64,
+}
+
+/// A logical buffer split into several `wgpu::Buffer`s so that each chunk
+/// fits both `max_buffer_size` and the storage binding limit.
+pub struct ChunkedBuffer {
+    chunks: Vec<BufferChunk>,
+    size: u64,
+}
+
+impl ChunkedBuffer {
+    pub fn new(
+        context: &Context,
+        size: u64,
+        element_size: u64,
+        usage: wgpu::BufferUsages,
+        label: Option<&str>,
+    ) -> Result<Self, Error> {
+        context.check_buffer_size(element_size)?;
+        let chunk_size = context.max_chunk_size(element_size);
+        let mut chunks = Vec::new();
+        let mut offset = 0;
+        while offset < size {
+            let chunk = chunk_size.min(size - offset);
+            let buffer = context.device.create_buffer(&wgpu::BufferDescriptor {
+                label,
+                size: chunk.next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT),
+                usage: usage | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            });
+            chunks.push(BufferChunk {
+                buffer,
+                offset,
+                size: chunk,
+            });
+            offset += chunk;
+        }
+        Ok(Self { chunks, size })
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn chunks(&self) -> &[BufferChunk] {
+        &self.chunks
+    }
+
+    /// Writes `data` at `offset`, a multiple of `COPY_BUFFER_ALIGNMENT`. The
+    /// write has to end on a multiple too, unless it ends the buffer.
+    pub fn write(&self, context: &Context, offset: u64, data: &[u8]) -> Result<(), Error> {
+        self.write_with_progress(context, offset, data, &mut Progress::new())
+    }
+
+    /// Like `write`, reporting the bytes handed to the queue as the
+    /// "upload" stage of `progress`, in pieces of the staging chunk size,
+    /// and stopping with `Error::Cancelled` once its token is cancelled.
+    pub fn write_with_progress(
+        &self,
+        context: &Context,
+        offset: u64,
+        data: &[u8],
+        progress: &mut Progress,
+    ) -> Result<(), Error> {
+        check_alignment(offset)?;
+        let end = offset.saturating_add(data.len() as u64);
+        if end > self.size {
+            return Err(Error::BufferTooSmall {
+                size: self.size,
+                required: end,
+            });
+        }
+        // Only the end of the buffer may be unaligned, where the padding of
+        // the last chunk takes the zeroes of the tail.
+        if end < self.size && !end.is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT) {
+            return Err(Error::UnalignedBufferSize {
+                size: data.len() as u64,
+                alignment: wgpu::COPY_BUFFER_ALIGNMENT,
+            });
+        }
+
+        for chunk in &self.chunks {
+            let start = offset.max(chunk.offset);
+            let stop = end.min(chunk.offset + chunk.size);
+            if start >= stop {
+                continue;
+            }
+            let bytes = &data[(start - offset) as usize..(stop - offset) as usize];
+            for (index, piece) in bytes.chunks(STAGING_CHUNK_SIZE as usize).enumerate() {
+                let piece_start = start + index as u64 * STAGING_CHUNK_SIZE;
+                progress.report("upload", piece_start - offset, data.len() as u64)?;
+                context.write_buffer_padded(&chunk.buffer, piece_start - chunk.offset, piece);
+            }
+        }
+        progress.report("upload", data.len() as u64, data.len() as u64)
+    }
+
+    pub fn read(&self, context: &Context) -> Result<Vec<u8>, Error> {
+        let mut data = Vec::with_capacity(self.size as usize);
+        for chunk in &self.chunks {
+            let bytes = context.read_buffer_range(&chunk.buffer, 0, chunk.size)?;
+            data.extend_from_slice(&bytes);
+        }
+        Ok(data)
+    }
+}
+
+impl Context {
+    pub fn check_binding_size(&self, size: u64, storage: bool) -> Result<(), Error> {
+        let limits = self.device.limits();
+        if storage {
+            check_limit(
+                "max_storage_buffer_binding_size",
+                limits.max_storage_buffer_binding_size as u64,
+                size,
+            )
+        } else {
+            check_limit(
+                "max_uniform_buffer_binding_size",
+                limits.max_uniform_buffer_binding_size as u64,
+                size,
+            )
+        }
+    }
+
+    /// Largest chunk that can be created as one buffer and bound whole as
+    /// storage, rounded down to a multiple of `element_size` and the
+    /// storage offset alignment.
+    pub fn max_chunk_size(&self, element_size: u64) -> u64 {
+        let limits = self.device.limits();
+        let max = limits
+            .max_buffer_size
+            .min(limits.max_storage_buffer_binding_size as u64);
+        let alignment = lcm(
+            element_size.max(1),
+            lcm(
+                limits.min_storage_buffer_offset_alignment as u64,
+                wgpu::COPY_BUFFER_ALIGNMENT,
+            ),
+        );
+        (max / alignment * alignment).max(alignment)
+    }
+
+    /// Writes `data` in bounded pieces so large uploads don't require a
+    /// single staging allocation of the full size. `offset` and the length
+    /// of `data` have to be multiples of `COPY_BUFFER_ALIGNMENT`, as the
+    /// queue cannot write parts of a word without overwriting the rest.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self, buffer, data), fields(bytes = data.len()), err)
+    )]
+    pub fn write_buffer_chunked(
+        &self,
+        buffer: &wgpu::Buffer,
+        offset: u64,
+        data: &[u8],
+    ) -> Result<(), Error> {
+        check_alignment(offset)?;
+        let size = data.len() as u64;
+        if !size.is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT) {
+            return Err(Error::UnalignedBufferSize {
+                size,
+                alignment: wgpu::COPY_BUFFER_ALIGNMENT,
+            });
+        }
+        let end = offset.saturating_add(size);
+        if end > buffer.size() {
+            return Err(Error::BufferTooSmall {
+                size: buffer.size(),
+                required: end,
+            });
+        }
+        self.write_buffer_padded(buffer, offset, data);
+        Ok(())
+    }
+
+    /// Like `write_buffer_chunked`, padding an unaligned tail with zeroes,
+    /// which overwrites up to 3 bytes after `data`. Only for callers that
+    /// own those bytes and have checked the offset and bounds.
+    pub(crate) fn write_buffer_padded(&self, buffer: &wgpu::Buffer, offset: u64, data: &[u8]) {
+        for (index, chunk) in data.chunks(STAGING_CHUNK_SIZE as usize).enumerate() {
+            let chunk_offset = offset + index as u64 * STAGING_CHUNK_SIZE;
+            let aligned =
+                chunk.len() as u64 / wgpu::COPY_BUFFER_ALIGNMENT * wgpu::COPY_BUFFER_ALIGNMENT;
+            if aligned > 0 {
+                self.queue
+                    .write_buffer(buffer, chunk_offset, &chunk[..aligned as usize]);
+            }
+            if aligned < chunk.len() as u64 {
+                // Pad the unaligned tail with zeroes.
+                let mut tail = [0u8; wgpu::COPY_BUFFER_ALIGNMENT as usize];
+                tail[..chunk.len() - aligned as usize].copy_from_slice(&chunk[aligned as usize..]);
+                self.queue
+                    .write_buffer(buffer, chunk_offset + aligned, &tail);
+            }
+        }
+    }
+
+    /// Reads `size` bytes of `buffer` at `offset`, a multiple of
+    /// `COPY_BUFFER_ALIGNMENT`, through staging buffers of at most 64 MiB.
+    /// The range rounded up to whole words has to lie inside the buffer.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self, buffer), err)
+    )]
+    pub fn read_buffer_range(
+        &self,
+        buffer: &wgpu::Buffer,
+        offset: u64,
+        size: u64,
+    ) -> Result<Vec<u8>, Error> {
+        check_alignment(offset)?;
+        // The copies are whole words, so an unaligned end reads up to the
+        // next one.
+        let end = offset
+            .saturating_add(size)
+            .next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT);
+        if end > buffer.size() {
+            return Err(Error::BufferTooSmall {
+                size: buffer.size(),
+                required: end,
+            });
+        }
+        let mut data = Vec::with_capacity(size as usize);
+        let mut position = offset;
+        while position < offset + size {
+            let chunk = STAGING_CHUNK_SIZE.min(offset + size - position);
+            let padded = chunk.next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT);
+            let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Chunked Readback Buffer"),
+                size: padded,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+            let mut command_encoder =
+                self.device
+                    .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                        label: Some("Chunked Readback Encoder"),
+                    });
+            command_encoder.copy_buffer_to_buffer(buffer, position, &staging, 0, padded);
+            self.submit(command_encoder);
+
+            let bytes = self.map_read(&staging)?;
+            data.extend_from_slice(&bytes[..chunk as usize]);
+            position += chunk;
+        }
+        Ok(data)
+    }
+
+    /// Splits a 1D dispatch of `total_workgroups` into dispatches that fit
+    /// `max_compute_workgroups_per_dimension`, folding each into x/y. Kernels
+    /// reconstruct the linear workgroup index as
+    /// `first_workgroup + id.x + id.y * num_workgroups.x` and must skip
+    /// indices past `workgroup_count`.
+    pub fn split_dispatch(&self, total_workgroups: u64) -> Vec<DispatchChunk> {
+        let max = self.device.limits().max_compute_workgroups_per_dimension as u64;
+        let per_dispatch = max * max;
+        let mut chunks = Vec::new();
+        let mut first_workgroup = 0;
+        while first_workgroup < total_workgroups {
+            let workgroup_count = per_dispatch.min(total_workgroups - first_workgroup);
+            let x = workgroup_count.min(max);
+            let y = workgroup_count.div_ceil(x);
+            chunks.push(DispatchChunk {
+                first_workgroup,
+                workgroup_count,
+                workgroups: (x as u32, y as u32, 1),
+            });
+            first_workgroup += workgroup_count;
+        }
+        chunks
+    }
+}
+
+fn lcm(a: u64, b: u64) -> u64 {
+    let (mut x, mut y) = (a, b);
+    while y != 0 {
+        (x, y) = (y, x % y);
+    }
+    a / x * b
+}
+
+fn check_alignment(offset: u64) -> Result<(), Error> {
+    match offset.is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT) {
+        true => Ok(()),
+        false => Err(Error::UnalignedBufferOffset {
+            offset,
+            alignment: wgpu::COPY_BUFFER_ALIGNMENT,
+        }),
+    }
+}
@@ -0,0 +1,77 @@
+use std::time::{Duration, Instant};
+
+/// Frame timing: `tick` once per frame, then read the time since the
+/// start and since the previous frame. Time stops while paused.
+#[derive(Clone, Debug)]
+pub struct Clock {
+    last: Instant,
+    elapsed: Duration,
+    delta: Duration,
+    frame: u64,
+    paused: bool,
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock {
+    pub fn new() -> Self {
+        Self {
+            last: Instant::now(),
+            elapsed: Duration::ZERO,
+            delta: Duration::ZERO,
+            frame: 0,
+            paused: false,
+        }
+    }
+
+    /// Advances by the wall time since the previous tick.
+    pub fn tick(&mut self) -> Duration {
+        let now = Instant::now();
+        let delta = now - self.last;
+        self.last = now;
+        self.advance(delta)
+    }
+
+    /// Advances by a fixed `delta`, for offline rendering.
+    pub fn advance(&mut self, delta: Duration) -> Duration {
+        self.delta = match self.paused {
+            true => Duration::ZERO,
+            false => delta,
+        };
+        self.elapsed += self.delta;
+        self.frame += 1;
+        self.delta
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
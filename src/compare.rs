@@ -0,0 +1,388 @@
+use super::atomics::{atomic_u64_wgsl, read_atomic_u64, ATOMIC_U64_WGSL};
+use super::context::Context;
+use super::diagnostics::{describe_texture, PassRecord};
+use super::error::Error;
+use super::texture::Texture;
+use super::texture_builder::TextureBuilder;
+use std::fmt;
+use wgpu::util::DeviceExt;
+
+const WORKGROUP_SIZE: u32 = 8;
+const PERCEPTUAL: u32 = 1;
+const DIFFERENCE_IMAGE: u32 = 2;
+const ERROR_SCALE: f64 = 65536.0;
+const DELTA_E_SCALE: f64 = 256.0;
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    size: [u32; 2],
+    threshold: f32,
+    difference_gain: f32,
+    flags: u32,
+    _padding: [u32; 3],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct Stats {
+    max_error: f32,
+    max_delta_e: f32,
+    differing_pixels: u32,
+    _padding: u32,
+}
+
+/// Result of `TextureComparer::compare`. Errors are absolute differences of
+/// the channels as sampled, so sRGB textures are compared in linear space.
+#[derive(Debug)]
+pub struct ComparisonReport {
+    pub width: u32,
+    pub height: u32,
+    /// Largest channel error of any pixel.
+    pub max_error: f32,
+    /// Mean channel error over all pixels and channels.
+    pub mean_error: f32,
+    /// Root mean square channel error.
+    pub rmse: f32,
+    /// Pixels with a channel error above `threshold`.
+    pub differing_pixels: u64,
+    pub threshold: f32,
+    /// Mean and largest CIE76 delta E of the colors, with the textures
+    /// taken as linear sRGB, if `TextureComparer::perceptual` is set. A
+    /// delta E around 2.3 is just noticeable.
+    pub mean_delta_e: Option<f32>,
+    pub max_delta_e: Option<f32>,
+    /// `Rgba8Unorm` image of the absolute differences, if
+    /// `TextureComparer::difference_image` is set.
+    pub difference: Option<Texture>,
+}
+
+impl ComparisonReport {
+    /// Whether no pixel differs by more than the threshold.
+    pub fn is_match(&self) -> bool {
+        self.differing_pixels == 0
+    }
+
+    /// Peak signal-to-noise ratio in decibels for a peak of 1, infinite for
+    /// identical textures.
+    pub fn psnr(&self) -> f32 {
+        -20.0 * self.rmse.log10()
+    }
+}
+
+impl fmt::Display for ComparisonReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {}x{} pixels differ by more than {}: max error {}, mean error {}, RMSE {}",
+            self.differing_pixels,
+            self.width,
+            self.height,
+            self.threshold,
+            self.max_error,
+            self.mean_error,
+            self.rmse
+        )?;
+        if let (Some(mean), Some(max)) = (self.mean_delta_e, self.max_delta_e) {
+            write!(f, ", mean delta E {mean}, max delta E {max}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares textures on the GPU for golden-image tests of renderers,
+/// reporting error statistics and optionally a difference image.
+pub struct TextureComparer {
+    layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::ComputePipeline,
+    /// Channel error above which a pixel counts as differing, 0 by default.
+    pub threshold: f32,
+    /// Also computes delta E statistics.
+    pub perceptual: bool,
+    /// Also writes a difference image.
+    pub difference_image: bool,
+    /// Factor of the differences in the difference image, to make small
+    /// differences visible.
+    pub difference_gain: f32,
+}
+
+impl TextureComparer {
+    pub fn new(context: &Context) -> Self {
+        let device = &context.device;
+        let source = ATOMIC_U64_WGSL.to_owned()
+            + &atomic_u64_wgsl("sums")
+            + include_str!("shaders/compare.wgsl");
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("compare.wgsl"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let entry = |binding, ty| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty,
+            count: None,
+        };
+        let texture = wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+        };
+        let storage = wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only: false },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        };
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Compare Bind Group Layout"),
+            entries: &[
+                entry(
+                    0,
+                    wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                ),
+                entry(1, texture),
+                entry(2, texture),
+                entry(3, storage),
+                entry(4, storage),
+                entry(
+                    5,
+                    wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: wgpu::TextureFormat::Rgba8Unorm,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                ),
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Compare Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Compare Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &module,
+            entry_point: "compare",
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        });
+
+        Self {
+            layout,
+            pipeline,
+            threshold: 0.0,
+            perceptual: false,
+            difference_image: false,
+            difference_gain: 1.0,
+        }
+    }
+
+    /// Compares two single-sampled 2D float textures of the same size,
+    /// waiting for the result.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, context, first, second),
+            fields(width = first.width(), height = first.height()),
+            err
+        )
+    )]
+    pub fn compare(
+        &self,
+        context: &Context,
+        first: &Texture,
+        second: &Texture,
+    ) -> Result<ComparisonReport, Error> {
+        for texture in [first, second] {
+            if !matches!(
+                texture.format().sample_type(None, None),
+                Some(wgpu::TextureSampleType::Float { .. })
+            ) || texture.view_dimension() != wgpu::TextureViewDimension::D2
+                || texture.sample_count() != 1
+            {
+                return Err(Error::UnsupportedTextureFormat(texture.format()));
+            }
+            if !texture
+                .usage()
+                .contains(wgpu::TextureUsages::TEXTURE_BINDING)
+            {
+                return Err(Error::TextureCreationFailed);
+            }
+        }
+        if first.size() != second.size() {
+            return Err(Error::TextureSizeMismatch {
+                expected: first.size(),
+                actual: second.size(),
+            });
+        }
+
+        let device = &context.device;
+        let (width, height) = (first.width(), first.height());
+        let mut flags = 0;
+        if self.perceptual {
+            flags |= PERCEPTUAL;
+        }
+        if self.difference_image {
+            flags |= DIFFERENCE_IMAGE;
+        }
+        let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Compare Params"),
+            contents: bytemuck::bytes_of(&Params {
+                size: [width, height],
+                threshold: self.threshold,
+                difference_gain: self.difference_gain,
+                flags,
+                ..Default::default()
+            }),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let storage = |label, size| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents: &vec![0; size],
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            })
+        };
+        let stats = storage("Compare Stats", std::mem::size_of::<Stats>());
+        let sums = storage("Compare Sums", 3 * 8);
+        // The difference image is always bound, so stand in a texel for it.
+        let (difference_width, difference_height) = match self.difference_image {
+            true => (width, height),
+            false => (1, 1),
+        };
+        let difference = TextureBuilder::new(
+            difference_width,
+            difference_height,
+            wgpu::TextureFormat::Rgba8Unorm,
+        )
+        .label("Difference Image")
+        .usage(
+            wgpu::TextureUsages::STORAGE_BINDING
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+        )
+        .build(context)?;
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Compare Bind Group"),
+            layout: &self.layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&first.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&second.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: stats.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: sums.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: wgpu::BindingResource::TextureView(&difference.view),
+                },
+            ],
+        });
+
+        context.diagnostics.record_pass(|| {
+            PassRecord::new(
+                "Compare",
+                "compare",
+                vec![
+                    describe_texture("first", &first.texture),
+                    describe_texture("second", &second.texture),
+                ],
+            )
+        });
+        let mut command_encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Compare Encoder"),
+        });
+        {
+            let mut pass = command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Compare"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(
+                width.div_ceil(WORKGROUP_SIZE),
+                height.div_ceil(WORKGROUP_SIZE),
+                1,
+            );
+        }
+        context.submit(command_encoder);
+
+        let stats: Stats = bytemuck::pod_read_unaligned(&context.read_buffer(&stats)?);
+        let sums = read_atomic_u64(&bytemuck::pod_collect_to_vec::<u8, u32>(
+            &context.read_buffer(&sums)?,
+        ));
+        let pixels = (width as f64 * height as f64).max(1.0);
+        let channels = pixels * 4.0;
+        Ok(ComparisonReport {
+            width,
+            height,
+            max_error: stats.max_error,
+            mean_error: (sums[0] as f64 / ERROR_SCALE / channels) as f32,
+            rmse: (sums[1] as f64 / ERROR_SCALE / channels).sqrt() as f32,
+            differing_pixels: stats.differing_pixels as u64,
+            threshold: self.threshold,
+            mean_delta_e: self
+                .perceptual
+                .then(|| (sums[2] as f64 / DELTA_E_SCALE / pixels) as f32),
+            max_delta_e: self.perceptual.then_some(stats.max_delta_e),
+            difference: self.difference_image.then_some(difference),
+        })
+    }
+
+    /// Compares `texture` with a reference image, such as a golden PNG,
+    /// uploaded as 8-bit RGBA in the texture's sRGB-ness for 8-bit
+    /// textures, and as `Rgba32Float` for others.
+    #[cfg(feature = "image")]
+    pub fn compare_image(
+        &self,
+        context: &Context,
+        texture: &Texture,
+        reference: &image::DynamicImage,
+    ) -> Result<ComparisonReport, Error> {
+        use wgpu::TextureFormat as F;
+
+        let (width, height) = (reference.width(), reference.height());
+        let reference = match texture.format() {
+            F::Rgba8Unorm | F::Bgra8Unorm => context.texture_with_data(
+                &reference.to_rgba8(),
+                &width,
+                &height,
+                &F::Rgba8Unorm,
+                Some("Reference Image"),
+            )?,
+            F::Rgba8UnormSrgb | F::Bgra8UnormSrgb => context.texture_with_data(
+                &reference.to_rgba8(),
+                &width,
+                &height,
+                &F::Rgba8UnormSrgb,
+                Some("Reference Image"),
+            )?,
+            _ => context.texture_with_data(
+                bytemuck::cast_slice(&reference.to_rgba32f()),
+                &width,
+                &height,
+                &F::Rgba32Float,
+                Some("Reference Image"),
+            )?,
+        };
+        self.compare(context, texture, &reference)
+    }
+}
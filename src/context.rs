@@ -1,8 +1,18 @@
+use super::diagnostics::Diagnostics;
 use super::error::Error;
+#[cfg(feature = "image")]
+use super::format::ColorSpace;
+use super::format::{texel_from_color, ImageLayout};
+use super::mipmap::full_mip_level_count;
+use super::poller::PollingStrategy;
+use super::render_target::RenderTargetBuilder;
 use super::texture::Texture;
+use super::texture_pool::TexturePool;
+#[cfg(feature = "image")]
 use image::{DynamicImage, GenericImageView};
+#[cfg(feature = "nalgebra")]
 use nalgebra::SVector;
-use wgpu;
+use std::sync::Mutex;
 
 pub struct ContextDescriptor<'a, 'b> {
     request_adapter_options: wgpu::RequestAdapterOptions<'a, 'b>,
@@ -10,11 +20,20 @@ pub struct ContextDescriptor<'a, 'b> {
     trace_path: Option<&'a std::path::Path>,
 }
 
+/// The wgpu instance, adapter, device and queue the crate works with.
+/// Outside of wasm32 it is `Send` and `Sync`, like the textures, buffers
+/// and renderers created from it: share it through an `Arc` to create
+/// resources on worker threads while another thread renders.
 pub struct Context {
     pub instance: wgpu::Instance,
     pub adapter: wgpu::Adapter,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
+    pub(crate) diagnostics: Diagnostics,
+    pub(crate) reverse_z: bool,
+    pub(crate) texture_anisotropy: u16,
+    pub(crate) texture_pool: TexturePool,
+    pub(crate) polling_strategy: Mutex<PollingStrategy>,
 }
 
 impl Context {
@@ -26,15 +45,47 @@ impl Context {
             .request_adapter(&descriptor.request_adapter_options)
             .await
             .ok_or(Error::RequestingAdapterFailed)?;
+
+        Self::from_adapter(
+            instance,
+            adapter,
+            &descriptor.device_descriptor,
+            descriptor.trace_path,
+        )
+        .await
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "info",
+            skip_all,
+            fields(adapter = %adapter.get_info().name),
+            err
+        )
+    )]
+    pub async fn from_adapter(
+        instance: wgpu::Instance,
+        adapter: wgpu::Adapter,
+        device_descriptor: &wgpu::DeviceDescriptor<'_>,
+        trace_path: Option<&std::path::Path>,
+    ) -> Result<Self, Error> {
         let (device, queue) = adapter
-            .request_device(&descriptor.device_descriptor, descriptor.trace_path)
+            .request_device(device_descriptor, trace_path)
             .await?;
+        #[cfg(feature = "tracing")]
+        tracing::info!(backend = ?adapter.get_info().backend, "created device");
 
         Ok(Self {
             instance,
             adapter,
             device,
             queue,
+            diagnostics: Diagnostics::default(),
+            reverse_z: false,
+            texture_anisotropy: 1,
+            texture_pool: TexturePool::default(),
+            polling_strategy: Mutex::new(PollingStrategy::default()),
         })
     }
 
@@ -64,9 +115,9 @@ impl Context {
         Self::default_with_surface(instance, None).await
     }
 
-    pub fn schedule<O>(&self, operations: O)
+    pub fn schedule<O>(&self, operations: O) -> wgpu::SubmissionIndex
     where
-        O: Fn(&mut wgpu::CommandEncoder) -> (),
+        O: Fn(&mut wgpu::CommandEncoder),
     {
         let mut command_encoder =
             self.device
@@ -76,47 +127,124 @@ impl Context {
 
         operations(&mut command_encoder);
 
-        let command_buffer = command_encoder.finish();
-        self.queue.submit(std::iter::once(command_buffer));
+        self.submit(command_encoder)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub(crate) fn submit(&self, command_encoder: wgpu::CommandEncoder) -> wgpu::SubmissionIndex {
+        let index = self.queue.submit(std::iter::once(command_encoder.finish()));
+        self.diagnostics.submitted();
+        #[cfg(feature = "tracing")]
+        tracing::debug!("submitted command buffer");
+        index
     }
 
-    pub fn depth_texture(&self, width: &u32, height: &u32, label: &str) -> Texture {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self),
+            fields(width = *width, height = *height),
+            err
+        )
+    )]
+    pub fn depth_texture(&self, width: &u32, height: &u32, label: &str) -> Result<Texture, Error> {
+        self.depth_texture_with_format(width, height, &Texture::DEPTH_FORMAT, 1, true, label)
+    }
+
+    /// Like `depth_texture`, in any depth or depth stencil format the device
+    /// supports, such as `Depth24PlusStencil8` or `Depth16Unorm`, with
+    /// `sample_count` samples per pixel. With `comparison` the sampler is a
+    /// linear comparison sampler for shadow lookups, otherwise a nearest
+    /// one for reading depth values. `Texture::stencil_view` views the
+    /// stencil aspect of formats that have one.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self),
+            fields(width = *width, height = *height),
+            err
+        )
+    )]
+    pub fn depth_texture_with_format(
+        &self,
+        width: &u32,
+        height: &u32,
+        texture_format: &wgpu::TextureFormat,
+        sample_count: u32,
+        comparison: bool,
+        label: &str,
+    ) -> Result<Texture, Error> {
+        let format = *texture_format;
+        if !format.is_depth_stencil_format()
+            || !self.device.features().contains(format.required_features())
+        {
+            return Err(Error::UnsupportedTextureFormat(format));
+        }
+        if sample_count != 1 && !self.supported_sample_counts(format).contains(&sample_count) {
+            return Err(Error::UnsupportedSampleCount {
+                format,
+                sample_count,
+            });
+        }
         let size = wgpu::Extent3d {
             width: *width,
             height: *height,
             depth_or_array_layers: 1,
         };
+        if size.width == 0 || size.height == 0 {
+            return Err(Error::TextureCreationFailed);
+        }
+        self.check_texture_size(&size, wgpu::TextureDimension::D2)?;
+
         let texture = self.device.create_texture(&wgpu::TextureDescriptor {
             label: Some(label),
             size,
             mip_level_count: 1,
-            sample_count: 1,
+            sample_count,
             dimension: wgpu::TextureDimension::D2,
-            format: Texture::DEPTH_FORMAT,
-            view_formats: &[wgpu::TextureFormat::Depth32Float],
+            format,
+            view_formats: &[format],
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
         });
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let filter = match comparison {
+            true => wgpu::FilterMode::Linear,
+            false => wgpu::FilterMode::Nearest,
+        };
         let sampler = self.device.create_sampler(&wgpu::SamplerDescriptor {
             address_mode_u: wgpu::AddressMode::ClampToEdge,
             address_mode_v: wgpu::AddressMode::ClampToEdge,
             address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
+            mag_filter: filter,
+            min_filter: filter,
             mipmap_filter: wgpu::FilterMode::Nearest,
-            compare: Some(wgpu::CompareFunction::LessEqual),
+            compare: comparison.then(|| self.depth_compare_function()),
             lod_min_clamp: 0.0,
             lod_max_clamp: 100.0,
             ..Default::default()
         });
+        #[cfg(feature = "tracing")]
+        tracing::debug!("created texture");
 
-        Texture {
-            texture,
-            view,
-            sampler,
-        }
+        Ok(Texture::from_raw(texture, view, sampler)
+            .with_view_dimension(wgpu::TextureViewDimension::D2))
     }
 
+    /// Creates a texture from the tightly packed rows of `data`. Rows of
+    /// block-compressed formats (BCn, ETC2, ASTC) are rows of blocks, so
+    /// `width` and `height` must be whole blocks, and the device needs the
+    /// format's compression feature.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, data),
+            fields(width = *width, height = *height, bytes = data.len()),
+            err
+        )
+    )]
     pub fn texture_with_data(
         &self,
         data: &[u8],
@@ -124,28 +252,155 @@ impl Context {
         height: &u32,
         texture_format: &wgpu::TextureFormat,
         label: Option<&str>,
+    ) -> Result<Texture, Error> {
+        self.create_texture_with_data(data, width, height, texture_format, false, label)
+    }
+
+    /// Like `texture_with_data`, with a full mip chain filled by
+    /// `Texture::generate_mipmaps`. The format must be renderable and
+    /// filterable.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, data),
+            fields(width = *width, height = *height, bytes = data.len()),
+            err
+        )
+    )]
+    pub fn texture_with_data_mipmapped(
+        &self,
+        data: &[u8],
+        width: &u32,
+        height: &u32,
+        texture_format: &wgpu::TextureFormat,
+        label: Option<&str>,
+    ) -> Result<Texture, Error> {
+        self.check_mipmap_format(*texture_format)?;
+        let texture =
+            self.create_texture_with_data(data, width, height, texture_format, true, label)?;
+        texture.generate_mipmaps(self)?;
+        Ok(texture)
+    }
+
+    /// Like `texture_with_data`, with mip levels baked offline: one slice
+    /// per level, from the `width` x `height` base level down, each in the
+    /// level's `ImageLayout`. Levels of block-compressed formats smaller
+    /// than a block take a whole block.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, levels),
+            fields(width = *width, height = *height, levels = levels.len()),
+            err
+        )
+    )]
+    pub fn texture_with_mip_data(
+        &self,
+        levels: &[&[u8]],
+        width: &u32,
+        height: &u32,
+        texture_format: &wgpu::TextureFormat,
+        label: Option<&str>,
     ) -> Result<Texture, Error> {
         let size = wgpu::Extent3d {
             width: *width,
             height: *height,
             depth_or_array_layers: 1,
         };
-        let bytes_per_row = 4 * size.width;
-        let bytes_per_image = bytes_per_row * size.width;
-
-        if bytes_per_row == 0 || data.len() < bytes_per_image as usize {
+        let dimension = wgpu::TextureDimension::D2;
+        let mip_level_count = levels.len() as u32;
+        if mip_level_count == 0 || mip_level_count > size.max_mips(dimension) {
             return Err(Error::TextureCreationFailed);
         }
+        self.image_layout(*texture_format, size)?;
+        let layouts = (0..mip_level_count)
+            .zip(levels)
+            .map(|(level, data)| {
+                let level_size = size
+                    .mip_level_size(level, dimension)
+                    .physical_size(*texture_format);
+                let layout = ImageLayout::new(*texture_format, level_size)?;
+                layout.check_data(data, true)?;
+                Ok((level_size, layout))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        self.check_texture_size(&size, dimension)?;
 
         let texture = self.device.create_texture(&wgpu::TextureDescriptor {
             label,
             size,
-            mip_level_count: 1,
+            mip_level_count,
+            sample_count: 1,
+            dimension,
+            format: *texture_format,
+            view_formats: &[*texture_format],
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
+        });
+        for (level, ((level_size, layout), data)) in layouts.iter().zip(levels).enumerate() {
+            self.queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    aspect: wgpu::TextureAspect::All,
+                    texture: &texture,
+                    mip_level: level as u32,
+                    origin: wgpu::Origin3d::ZERO,
+                },
+                data,
+                layout.data_layout(0),
+                *level_size,
+            );
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = self.texture_sampler();
+        #[cfg(feature = "tracing")]
+        tracing::debug!("created texture");
+
+        Ok(Texture::from_raw(texture, view, sampler)
+            .with_view_dimension(wgpu::TextureViewDimension::D2))
+    }
+
+    fn create_texture_with_data(
+        &self,
+        data: &[u8],
+        width: &u32,
+        height: &u32,
+        texture_format: &wgpu::TextureFormat,
+        mipmapped: bool,
+        label: Option<&str>,
+    ) -> Result<Texture, Error> {
+        let size = wgpu::Extent3d {
+            width: *width,
+            height: *height,
+            depth_or_array_layers: 1,
+        };
+        let layout = self.image_layout(*texture_format, size)?;
+        layout.check_data(data, true)?;
+        self.check_texture_size(&size, wgpu::TextureDimension::D2)?;
+
+        let usage = wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::COPY_DST
+            | wgpu::TextureUsages::COPY_SRC;
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size,
+            mip_level_count: if mipmapped {
+                full_mip_level_count(size.width, size.height)
+            } else {
+                1
+            },
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format: *texture_format,
             view_formats: &[*texture_format],
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            usage: if mipmapped {
+                usage | wgpu::TextureUsages::RENDER_ATTACHMENT
+            } else {
+                usage
+            },
         });
 
         self.queue.write_texture(
@@ -156,32 +411,250 @@ impl Context {
                 origin: wgpu::Origin3d::ZERO,
             },
             data,
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(bytes_per_row),
-                rows_per_image: Some(size.height),
+            layout.data_layout(0),
+            size,
+        );
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = self.texture_sampler();
+        #[cfg(feature = "tracing")]
+        tracing::debug!("created texture");
+
+        Ok(Texture::from_raw(texture, view, sampler)
+            .with_view_dimension(wgpu::TextureViewDimension::D2))
+    }
+
+    /// A volume texture for volume data or 3D LUTs, with `depth` slices of
+    /// `width` x `height` texels in `data`, viewed in 3D and sampled with
+    /// clamped linear filtering.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, data),
+            fields(width = *width, height = *height, depth = *depth, bytes = data.len()),
+            err
+        )
+    )]
+    pub fn texture_3d_with_data(
+        &self,
+        data: &[u8],
+        width: &u32,
+        height: &u32,
+        depth: &u32,
+        texture_format: &wgpu::TextureFormat,
+        label: Option<&str>,
+    ) -> Result<Texture, Error> {
+        let size = wgpu::Extent3d {
+            width: *width,
+            height: *height,
+            depth_or_array_layers: *depth,
+        };
+        if texture_format.is_compressed() {
+            return Err(Error::UnsupportedTextureFormat(*texture_format));
+        }
+        let layout = self.image_layout(*texture_format, size)?;
+        layout.check_data(data, true)?;
+        self.check_texture_size(&size, wgpu::TextureDimension::D3)?;
+
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D3,
+            format: *texture_format,
+            view_formats: &[*texture_format],
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
+        });
+
+        self.queue.write_texture(
+            wgpu::ImageCopyTexture {
+                aspect: wgpu::TextureAspect::All,
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
             },
+            data,
+            layout.data_layout(0),
             size,
         );
 
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let sampler = self.device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
-            mipmap_filter: wgpu::FilterMode::Linear,
+        let sampler = self.texture_sampler();
+        #[cfg(feature = "tracing")]
+        tracing::debug!("created texture");
+
+        Ok(Texture::from_raw(texture, view, sampler)
+            .with_view_dimension(wgpu::TextureViewDimension::D3))
+    }
+
+    /// A 2D array texture for terrain splat layers or sprite sheets, with
+    /// one `width` x `height` layer per slice of `layers`, viewed as a
+    /// `D2Array` and sampled with clamped linear filtering.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, layers),
+            fields(width = *width, height = *height, layers = layers.len()),
+            err
+        )
+    )]
+    pub fn texture_array_with_data(
+        &self,
+        layers: &[&[u8]],
+        width: &u32,
+        height: &u32,
+        texture_format: &wgpu::TextureFormat,
+        label: Option<&str>,
+    ) -> Result<Texture, Error> {
+        let size = wgpu::Extent3d {
+            width: *width,
+            height: *height,
+            depth_or_array_layers: layers.len() as u32,
+        };
+        let layout = self.image_layout(*texture_format, size)?;
+        if layers.is_empty() {
+            return Err(Error::TextureCreationFailed);
+        }
+        for layer in layers {
+            layout.check_data(layer, false)?;
+        }
+        self.check_texture_size(&size, wgpu::TextureDimension::D2)?;
+
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: *texture_format,
+            view_formats: &[*texture_format],
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
+        });
+
+        for (index, layer) in layers.iter().enumerate() {
+            self.queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    aspect: wgpu::TextureAspect::All,
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: index as u32,
+                    },
+                },
+                layer,
+                layout.data_layout(0),
+                wgpu::Extent3d {
+                    depth_or_array_layers: 1,
+                    ..size
+                },
+            );
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
             ..Default::default()
         });
+        let sampler = self.texture_sampler();
+        #[cfg(feature = "tracing")]
+        tracing::debug!("created texture");
+
+        Ok(Texture::from_raw(texture, view, sampler)
+            .with_view_dimension(wgpu::TextureViewDimension::D2Array))
+    }
 
-        Ok(Texture {
-            texture,
-            view,
-            sampler,
+    /// Creates a texture to render into offscreen, which can then be
+    /// sampled, copied or read back (`RENDER_ATTACHMENT`,
+    /// `TEXTURE_BINDING` and `COPY_SRC` usage). `RenderTargetBuilder`
+    /// creates multisampled and transient targets.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self),
+            fields(width = *width, height = *height),
+            err
+        )
+    )]
+    pub fn render_target(
+        &self,
+        width: &u32,
+        height: &u32,
+        texture_format: &wgpu::TextureFormat,
+        label: Option<&str>,
+    ) -> Result<Texture, Error> {
+        let mut builder = RenderTargetBuilder::new(*width, *height, *texture_format);
+        if let Some(label) = label {
+            builder = builder.label(label);
+        }
+        builder.build(self)
+    }
+
+    /// The best block-compressed format the device can sample, for
+    /// transcoding universal textures such as Basis Universal: BC7, then
+    /// ASTC 4x4, then ETC2 with or without `alpha`. `None` if the device
+    /// has no compression feature, in which case `Rgba8Unorm` (or
+    /// `Rgba8UnormSrgb`) is the usual fallback.
+    pub fn preferred_compressed_format(
+        &self,
+        alpha: bool,
+        srgb: bool,
+    ) -> Option<wgpu::TextureFormat> {
+        use wgpu::TextureFormat as F;
+        let candidates = [
+            match srgb {
+                true => F::Bc7RgbaUnormSrgb,
+                false => F::Bc7RgbaUnorm,
+            },
+            F::Astc {
+                block: wgpu::AstcBlock::B4x4,
+                channel: match srgb {
+                    true => wgpu::AstcChannel::UnormSrgb,
+                    false => wgpu::AstcChannel::Unorm,
+                },
+            },
+            match (alpha, srgb) {
+                (true, true) => F::Etc2Rgba8UnormSrgb,
+                (true, false) => F::Etc2Rgba8Unorm,
+                (false, true) => F::Etc2Rgb8UnormSrgb,
+                (false, false) => F::Etc2Rgb8Unorm,
+            },
+        ];
+        candidates.into_iter().find(|format| {
+            self.device.features().contains(format.required_features())
+                && self
+                    .adapter
+                    .get_texture_format_features(*format)
+                    .allowed_usages
+                    .contains(wgpu::TextureUsages::TEXTURE_BINDING)
         })
     }
 
+    /// `ImageLayout::new`, failing also for formats whose features the
+    /// device lacks.
+    pub(crate) fn image_layout(
+        &self,
+        format: wgpu::TextureFormat,
+        size: wgpu::Extent3d,
+    ) -> Result<ImageLayout, Error> {
+        if !self.device.features().contains(format.required_features()) {
+            return Err(Error::UnsupportedTextureFormat(format));
+        }
+        ImageLayout::new(format, size)
+    }
+
+    /// Creates a texture from `image`, converted to 8-bit RGBA, or to half
+    /// or single floats for `Rgba16Float` and `Rgba32Float`, which keeps the
+    /// values above 1 of `.hdr` and `.exr` images.
+    #[cfg(feature = "image")]
     pub fn texture_from_image(
         &self,
         image: &DynamicImage,
@@ -189,10 +662,76 @@ impl Context {
         label: Option<&str>,
     ) -> Result<Texture, Error> {
         let (width, height) = image.dimensions();
-        let data = image.to_rgba8();
+        let data = image_data(image, texture_format);
         self.texture_with_data(&data, &width, &height, texture_format, label)
     }
 
+    /// Creates an 8-bit RGBA texture from `image`, whose pixels are in
+    /// `color_space`, in the format of `ColorSpace::rgba8_format`, so that
+    /// shaders sample linear values.
+    #[cfg(feature = "image")]
+    pub fn color_texture_from_image(
+        &self,
+        image: &DynamicImage,
+        color_space: ColorSpace,
+        label: Option<&str>,
+    ) -> Result<Texture, Error> {
+        self.texture_from_image(image, &color_space.rgba8_format(), label)
+    }
+
+    /// Like `texture_from_image`, with a full mip chain.
+    #[cfg(feature = "image")]
+    pub fn texture_from_image_mipmapped(
+        &self,
+        image: &DynamicImage,
+        texture_format: &wgpu::TextureFormat,
+        label: Option<&str>,
+    ) -> Result<Texture, Error> {
+        let (width, height) = image.dimensions();
+        let data = image_data(image, texture_format);
+        self.texture_with_data_mipmapped(&data, &width, &height, texture_format, label)
+    }
+
+    /// `texture_array_with_data` from images of the same size, one layer
+    /// each.
+    #[cfg(feature = "image")]
+    pub fn texture_array_from_images(
+        &self,
+        images: &[DynamicImage],
+        texture_format: &wgpu::TextureFormat,
+        label: Option<&str>,
+    ) -> Result<Texture, Error> {
+        let (width, height) = images
+            .first()
+            .map(|image| image.dimensions())
+            .ok_or(Error::TextureCreationFailed)?;
+        let expected = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        for image in images {
+            let (image_width, image_height) = image.dimensions();
+            if (image_width, image_height) != (width, height) {
+                return Err(Error::TextureSizeMismatch {
+                    expected,
+                    actual: wgpu::Extent3d {
+                        width: image_width,
+                        height: image_height,
+                        depth_or_array_layers: 1,
+                    },
+                });
+            }
+        }
+        let data: Vec<_> = images
+            .iter()
+            .map(|image| image_data(image, texture_format))
+            .collect();
+        let layers: Vec<&[u8]> = data.iter().map(Vec::as_slice).collect();
+        self.texture_array_with_data(&layers, &width, &height, texture_format, label)
+    }
+
+    #[cfg(feature = "image")]
     pub fn texture_from_image_data(
         &self,
         data: &[u8],
@@ -203,19 +742,97 @@ impl Context {
         self.texture_from_image(&image, texture_format, label)
     }
 
-    pub fn texture_from_color(
+    /// Like `texture_from_image_data`, with a full mip chain.
+    #[cfg(feature = "image")]
+    pub fn texture_from_image_data_mipmapped(
         &self,
-        color: &SVector<f32, 4>,
+        data: &[u8],
         texture_format: &wgpu::TextureFormat,
         label: Option<&str>,
     ) -> Result<Texture, Error> {
-        let data: [u8; 4] = [
-            (color.x * 255_f32) as u8,
-            (color.y * 255_f32) as u8,
-            (color.z * 255_f32) as u8,
-            (color.w * 255_f32) as u8,
-        ];
+        let image = image::load_from_memory(data)?;
+        self.texture_from_image_mipmapped(&image, texture_format, label)
+    }
+
+    /// Loads an `.hdr`, `.exr` or any other image into an `Rgba16Float`
+    /// texture, keeping values above 1.
+    #[cfg(feature = "image")]
+    pub fn load_hdr_texture(
+        &self,
+        path: impl AsRef<std::path::Path>,
+        label: Option<&str>,
+    ) -> Result<Texture, Error> {
+        let image = image::open(path)?;
+        self.texture_from_image(&image, &wgpu::TextureFormat::Rgba16Float, label)
+    }
+
+    /// A 1x1 texture of the linear `color`, encoded for `texture_format`,
+    /// one of the 8- and 16-bit unorm or float color formats. sRGB formats
+    /// store it sRGB encoded, so that it samples as `color`.
+    pub fn texture_from_rgba(
+        &self,
+        color: &[f32; 4],
+        texture_format: &wgpu::TextureFormat,
+        label: Option<&str>,
+    ) -> Result<Texture, Error> {
+        let data = texel_from_color(*texture_format, *color)?;
         let (width, height) = (1, 1);
         self.texture_with_data(&data, &width, &height, texture_format, label)
     }
+
+    #[cfg(feature = "nalgebra")]
+    pub fn texture_from_color(
+        &self,
+        color: &SVector<f32, 4>,
+        texture_format: &wgpu::TextureFormat,
+        label: Option<&str>,
+    ) -> Result<Texture, Error> {
+        self.texture_from_rgba(&(*color).into(), texture_format, label)
+    }
+}
+
+impl From<Context> for (wgpu::Instance, wgpu::Adapter, wgpu::Device, wgpu::Queue) {
+    fn from(context: Context) -> Self {
+        (
+            context.instance,
+            context.adapter,
+            context.device,
+            context.queue,
+        )
+    }
+}
+
+/// The pixels of `image` for a texture of `texture_format`: half or single
+/// floats for `Rgba16Float` and `Rgba32Float`, grayscale for single-channel
+/// formats, 16-bit for 16-bit unorm formats, BGRA for BGRA formats and
+/// 8-bit RGBA otherwise.
+#[cfg(feature = "image")]
+pub(crate) fn image_data(image: &DynamicImage, texture_format: &wgpu::TextureFormat) -> Vec<u8> {
+    match texture_format {
+        wgpu::TextureFormat::R8Unorm => image.to_luma8().into_raw(),
+        wgpu::TextureFormat::R16Unorm => bytemuck::cast_slice(image.to_luma16().as_raw()).to_vec(),
+        wgpu::TextureFormat::Rgba16Unorm => {
+            bytemuck::cast_slice(image.to_rgba16().as_raw()).to_vec()
+        }
+        wgpu::TextureFormat::R32Float => bytemuck::cast_slice(image.to_luma32f().as_raw()).to_vec(),
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => {
+            let mut data = image.to_rgba8().into_raw();
+            for pixel in data.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+            data
+        }
+        wgpu::TextureFormat::Rgba32Float => {
+            bytemuck::cast_slice(image.to_rgba32f().as_raw()).to_vec()
+        }
+        wgpu::TextureFormat::Rgba16Float => {
+            let data: Vec<half::f16> = image
+                .to_rgba32f()
+                .iter()
+                .map(|&value| half::f16::from_f32(value))
+                .collect();
+            bytemuck::cast_slice(&data).to_vec()
+        }
+        _ => image.to_rgba8().into_raw(),
+    }
 }
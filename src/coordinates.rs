@@ -0,0 +1,286 @@
+use super::mesh::Mesh;
+#[cfg(feature = "scene")]
+use super::scene::{Light, MeshSource, SceneDescription};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum UpAxis {
+    #[default]
+    Y,
+    Z,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Handedness {
+    #[default]
+    Right,
+    Left,
+}
+
+/// Axis conventions of 3D assets. X points right in all of them, the
+/// third axis follows from the up axis and the handedness.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CoordinateSystem {
+    pub up: UpAxis,
+    pub handedness: Handedness,
+}
+
+impl CoordinateSystem {
+    /// glTF, USD by default, OpenGL, and this crate's cameras and meshes.
+    pub const Y_UP_RIGHT_HANDED: Self = Self::new(UpAxis::Y, Handedness::Right);
+    /// Unity and Direct3D.
+    pub const Y_UP_LEFT_HANDED: Self = Self::new(UpAxis::Y, Handedness::Left);
+    /// Blender, 3ds Max, USD with `upAxis = "Z"` and most CAD formats.
+    pub const Z_UP_RIGHT_HANDED: Self = Self::new(UpAxis::Z, Handedness::Right);
+    pub const Z_UP_LEFT_HANDED: Self = Self::new(UpAxis::Z, Handedness::Left);
+
+    pub const fn new(up: UpAxis, handedness: Handedness) -> Self {
+        Self { up, handedness }
+    }
+
+    /// Where the X, Y and Z axes point in right, up, back coordinates.
+    fn basis(&self) -> [[f32; 3]; 3] {
+        let right = [1.0, 0.0, 0.0];
+        let up = [0.0, 1.0, 0.0];
+        let back = [0.0, 0.0, 1.0];
+        let forward = [0.0, 0.0, -1.0];
+        match (self.up, self.handedness) {
+            (UpAxis::Y, Handedness::Right) => [right, up, back],
+            (UpAxis::Y, Handedness::Left) => [right, up, forward],
+            (UpAxis::Z, Handedness::Right) => [right, forward, up],
+            (UpAxis::Z, Handedness::Left) => [right, back, up],
+        }
+    }
+
+    /// The conversion of coordinates in this system to `target`, a signed
+    /// permutation of the axes.
+    pub fn conversion_to(&self, target: &CoordinateSystem) -> CoordinateConversion {
+        let source = self.basis();
+        let target = target.basis();
+        // The target basis is orthonormal, so its inverse is its transpose.
+        let matrix = std::array::from_fn(|column| {
+            std::array::from_fn(|row| (0..3).map(|k| target[row][k] * source[column][k]).sum())
+        });
+        CoordinateConversion { matrix }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CoordinateConversion {
+    /// Column-major.
+    pub matrix: [[f32; 3]; 3],
+}
+
+impl CoordinateConversion {
+    pub fn is_identity(&self) -> bool {
+        (0..3).all(|column| {
+            (0..3).all(|row| self.matrix[column][row] == (column == row) as u8 as f32)
+        })
+    }
+
+    /// Whether the conversion mirrors, which turns front faces into back
+    /// faces unless the winding is reversed.
+    pub fn flips_winding(&self) -> bool {
+        let [a, b, c] = self.matrix;
+        let determinant = a[0] * (b[1] * c[2] - b[2] * c[1]) - b[0] * (a[1] * c[2] - a[2] * c[1])
+            + c[0] * (a[1] * b[2] - a[2] * b[1]);
+        determinant < 0.0
+    }
+
+    /// Converts points, directions and normals alike, since the matrix is
+    /// orthogonal.
+    pub fn convert(&self, v: [f32; 3]) -> [f32; 3] {
+        std::array::from_fn(|row| (0..3).map(|k| self.matrix[k][row] * v[k]).sum())
+    }
+
+    /// Converts a rotation quaternion `[x, y, z, w]` by conjugating it with
+    /// the conversion.
+    pub fn convert_rotation(&self, rotation: [f32; 4]) -> [f32; 4] {
+        // A mirror conjugates rotations like its negation, a proper rotation.
+        let sign = if self.flips_winding() { -1.0 } else { 1.0 };
+        let [x, y, z, w] = rotation;
+        let axis = self.convert([x, y, z]).map(|c| c * sign);
+        [axis[0], axis[1], axis[2], w]
+    }
+
+    /// Permutes per-axis scale factors along with the axes.
+    pub fn convert_scale(&self, scale: [f32; 3]) -> [f32; 3] {
+        std::array::from_fn(|row| (0..3).map(|k| self.matrix[k][row].abs() * scale[k]).sum())
+    }
+}
+
+impl Mesh {
+    /// Converts positions and normals, and reverses the winding of mirrored
+    /// meshes so that their front faces stay front faces.
+    pub fn convert_coordinates(&mut self, source: &CoordinateSystem, target: &CoordinateSystem) {
+        let conversion = source.conversion_to(target);
+        if conversion.is_identity() {
+            return;
+        }
+        for vertex in &mut self.vertices {
+            vertex.position = conversion.convert(vertex.position);
+            vertex.normal = conversion.convert(vertex.normal);
+        }
+        if conversion.flips_winding() {
+            for triangle in self.indices.chunks_exact_mut(3) {
+                triangle.swap(1, 2);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "scene")]
+impl SceneDescription {
+    /// Converts inline meshes, object transforms, lights and the camera.
+    /// Primitive meshes are symmetric enough to be left as they are.
+    pub fn convert_coordinates(&mut self, source: &CoordinateSystem, target: &CoordinateSystem) {
+        let conversion = source.conversion_to(target);
+        if conversion.is_identity() {
+            return;
+        }
+        for mesh in &mut self.meshes {
+            if let MeshSource::Inline(mesh) = &mut mesh.source {
+                mesh.convert_coordinates(source, target);
+            }
+        }
+        for object in &mut self.objects {
+            object.translation = conversion.convert(object.translation);
+            object.rotation = conversion.convert_rotation(object.rotation);
+            object.scale = conversion.convert_scale(object.scale);
+        }
+        for light in &mut self.lights {
+            match light {
+                Light::Directional { direction, .. } => *direction = conversion.convert(*direction),
+                Light::Point { position, .. } => *position = conversion.convert(*position),
+            }
+        }
+        let camera = &mut self.camera;
+        camera.position = conversion.convert(camera.position);
+        camera.target = conversion.convert(camera.target);
+        camera.up = conversion.convert(camera.up);
+    }
+}
+
+/// Length units of imported assets.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum LengthUnit {
+    #[default]
+    Meters,
+    Centimeters,
+    Millimeters,
+    Inches,
+    Feet,
+    /// Any other unit, by its size in meters.
+    MetersPerUnit(f64),
+}
+
+impl LengthUnit {
+    pub fn meters_per_unit(&self) -> f64 {
+        match self {
+            LengthUnit::Meters => 1.0,
+            LengthUnit::Centimeters => 0.01,
+            LengthUnit::Millimeters => 0.001,
+            LengthUnit::Inches => 0.0254,
+            LengthUnit::Feet => 0.3048,
+            LengthUnit::MetersPerUnit(meters) => *meters,
+        }
+    }
+
+    /// The named unit of that size if there is one, as USD's
+    /// `metersPerUnit` declares them.
+    pub fn from_meters_per_unit(meters: f64) -> Self {
+        [
+            LengthUnit::Meters,
+            LengthUnit::Centimeters,
+            LengthUnit::Millimeters,
+            LengthUnit::Inches,
+            LengthUnit::Feet,
+        ]
+        .into_iter()
+        .find(|unit| (unit.meters_per_unit() - meters).abs() <= meters.abs() * 1e-6)
+        .unwrap_or(LengthUnit::MetersPerUnit(meters))
+    }
+
+    /// The factor taking lengths in this unit to `target`.
+    pub fn scale_to(&self, target: &LengthUnit) -> f32 {
+        (self.meters_per_unit() / target.meters_per_unit()) as f32
+    }
+}
+
+impl Mesh {
+    /// Scales positions uniformly, for unit conversion.
+    pub fn scale(&mut self, factor: f32) {
+        for vertex in &mut self.vertices {
+            vertex.position = vertex.position.map(|c| c * factor);
+        }
+    }
+}
+
+#[cfg(feature = "scene")]
+impl SceneDescription {
+    /// Scales meshes, object translations, light positions and ranges, and
+    /// the camera, for unit conversion. Object scales are left as they are.
+    pub fn scale(&mut self, factor: f32) {
+        for mesh in &mut self.meshes {
+            match &mut mesh.source {
+                MeshSource::Cube { size } | MeshSource::Plane { size } => *size *= factor,
+                MeshSource::Sphere { radius, .. } => *radius *= factor,
+                MeshSource::Inline(mesh) => mesh.scale(factor),
+            }
+        }
+        for object in &mut self.objects {
+            object.translation = object.translation.map(|c| c * factor);
+        }
+        for light in &mut self.lights {
+            if let Light::Point {
+                position, range, ..
+            } = light
+            {
+                *position = position.map(|c| c * factor);
+                *range = range.map(|range| range * factor);
+            }
+        }
+        let camera = &mut self.camera;
+        camera.position = camera.position.map(|c| c * factor);
+        camera.target = camera.target.map(|c| c * factor);
+        camera.near *= factor;
+        camera.far *= factor;
+    }
+}
+
+/// Coordinate conventions and units for the `*_with` loaders.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ImportOptions {
+    /// Convention of the file, `None` for the one it declares, like USD's
+    /// `upAxis`, or else the target's.
+    pub source: Option<CoordinateSystem>,
+    /// Convention to convert to.
+    pub target: CoordinateSystem,
+    /// Units of the file, `None` for the ones it declares, like USD's
+    /// `metersPerUnit`, or else the target's. STL and PLY declare none.
+    pub source_units: Option<LengthUnit>,
+    /// Units to scale to.
+    pub target_units: LengthUnit,
+}
+
+impl ImportOptions {
+    pub fn new(source: CoordinateSystem) -> Self {
+        Self {
+            source: Some(source),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_units(mut self, source_units: LengthUnit) -> Self {
+        self.source_units = Some(source_units);
+        self
+    }
+
+    pub(crate) fn convert_mesh(&self, mesh: &mut Mesh) {
+        if let Some(source) = &self.source {
+            mesh.convert_coordinates(source, &self.target);
+        }
+        if let Some(units) = &self.source_units {
+            mesh.scale(units.scale_to(&self.target_units));
+        }
+    }
+}
@@ -0,0 +1,328 @@
+#[cfg(feature = "image")]
+use super::context::image_data;
+use super::context::Context;
+use super::diagnostics::{describe_texture, PassRecord};
+use super::error::Error;
+use super::texture::Texture;
+#[cfg(feature = "image")]
+use image::{DynamicImage, GenericImageView};
+use wgpu::util::DeviceExt;
+
+impl Context {
+    /// Creates a cube texture from six square faces of `size` pixels in
+    /// layer order +X, -X, +Y, -Y, +Z, -Z, viewed as a cube and sampled
+    /// with clamped linear filtering.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self, faces), fields(size = *size), err)
+    )]
+    pub fn cubemap_with_data(
+        &self,
+        faces: [&[u8]; 6],
+        size: &u32,
+        texture_format: &wgpu::TextureFormat,
+        label: Option<&str>,
+    ) -> Result<Texture, Error> {
+        let extent = wgpu::Extent3d {
+            width: *size,
+            height: *size,
+            depth_or_array_layers: 6,
+        };
+        let layout = self.image_layout(*texture_format, extent)?;
+        for face in faces {
+            layout.check_data(face, false)?;
+        }
+        self.check_texture_size(&extent, wgpu::TextureDimension::D2)?;
+
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size: extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: *texture_format,
+            view_formats: &[*texture_format],
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
+        });
+
+        for (layer, face) in faces.iter().enumerate() {
+            self.queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    aspect: wgpu::TextureAspect::All,
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: layer as u32,
+                    },
+                },
+                face,
+                layout.data_layout(0),
+                wgpu::Extent3d {
+                    depth_or_array_layers: 1,
+                    ..extent
+                },
+            );
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::Cube),
+            ..Default::default()
+        });
+        let sampler = self.texture_sampler();
+        #[cfg(feature = "tracing")]
+        tracing::debug!("created texture");
+
+        Ok(Texture::from_raw(texture, view, sampler)
+            .with_view_dimension(wgpu::TextureViewDimension::Cube))
+    }
+
+    /// `cubemap_with_data` from six square images of the same size.
+    #[cfg(feature = "image")]
+    pub fn cubemap_from_images(
+        &self,
+        faces: &[DynamicImage; 6],
+        texture_format: &wgpu::TextureFormat,
+        label: Option<&str>,
+    ) -> Result<Texture, Error> {
+        let width = faces[0].width();
+        let expected = wgpu::Extent3d {
+            width,
+            height: width,
+            depth_or_array_layers: 1,
+        };
+        for face in faces {
+            let (face_width, face_height) = face.dimensions();
+            if face_width != width || face_height != width {
+                return Err(Error::TextureSizeMismatch {
+                    expected,
+                    actual: wgpu::Extent3d {
+                        width: face_width,
+                        height: face_height,
+                        depth_or_array_layers: 1,
+                    },
+                });
+            }
+        }
+        let data = faces
+            .each_ref()
+            .map(|face| image_data(face, texture_format));
+        self.cubemap_with_data(
+            data.each_ref().map(Vec::as_slice),
+            &width,
+            texture_format,
+            label,
+        )
+    }
+
+    /// Renders an equirectangular panorama into a new cube texture with
+    /// faces of `size` pixels in the panorama's format, which must be a
+    /// renderable float format. -Z looks at the panorama's center and +Y at
+    /// its top row; longitude wraps around and texels are filtered
+    /// bilinearly.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self, equirectangular), fields(size = *size), err)
+    )]
+    pub fn cubemap_from_equirectangular(
+        &self,
+        equirectangular: &Texture,
+        size: &u32,
+        label: Option<&str>,
+    ) -> Result<Texture, Error> {
+        let format = equirectangular.format();
+        if equirectangular.dimension() != wgpu::TextureDimension::D2
+            || !equirectangular
+                .usage()
+                .contains(wgpu::TextureUsages::TEXTURE_BINDING)
+        {
+            return Err(Error::TextureCreationFailed);
+        }
+        if !matches!(
+            format.sample_type(None, None),
+            Some(wgpu::TextureSampleType::Float { .. })
+        ) {
+            return Err(Error::UnsupportedTextureFormat(format));
+        }
+        let cubemap = self.cubemap_target(size, &format, 1, label)?;
+
+        let device = &self.device;
+        let module = device.create_shader_module(wgpu::include_wgsl!("shaders/cubemap.wgsl"));
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Equirectangular Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Equirectangular Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Equirectangular Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vs_main",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fs_main",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                targets: &[Some(format.into())],
+            }),
+            multiview: None,
+        });
+        let source = equirectangular
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor {
+                dimension: Some(wgpu::TextureViewDimension::D2),
+                mip_level_count: Some(1),
+                ..Default::default()
+            });
+
+        self.diagnostics.record_pass(|| {
+            PassRecord::new(
+                "Equirectangular to Cubemap",
+                "Equirectangular Pipeline",
+                vec![
+                    describe_texture("source", &equirectangular.texture),
+                    describe_texture("target", &cubemap.texture),
+                ],
+            )
+        });
+        let mut command_encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Equirectangular Encoder"),
+        });
+        for face in 0..6u32 {
+            let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Equirectangular Face"),
+                contents: &face.to_le_bytes(),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Equirectangular Bind Group"),
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: params.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&source),
+                    },
+                ],
+            });
+            let target = cubemap.face_view(face);
+            let mut pass = command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Equirectangular Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_pipeline(&pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
+        self.submit(command_encoder);
+        Ok(cubemap)
+    }
+
+    /// An empty cube texture to render the faces of, through `face_view`.
+    pub(crate) fn cubemap_target(
+        &self,
+        size: &u32,
+        texture_format: &wgpu::TextureFormat,
+        mip_level_count: u32,
+        label: Option<&str>,
+    ) -> Result<Texture, Error> {
+        let extent = wgpu::Extent3d {
+            width: *size,
+            height: *size,
+            depth_or_array_layers: 6,
+        };
+        if *size == 0 {
+            return Err(Error::TextureCreationFailed);
+        }
+        let features = self.adapter.get_texture_format_features(*texture_format);
+        if !features
+            .allowed_usages
+            .contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+        {
+            return Err(Error::UnsupportedTextureFormat(*texture_format));
+        }
+        self.check_texture_size(&extent, wgpu::TextureDimension::D2)?;
+
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size: extent,
+            mip_level_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: *texture_format,
+            view_formats: &[*texture_format],
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::Cube),
+            ..Default::default()
+        });
+        let sampler = self.texture_sampler();
+
+        Ok(Texture::from_raw(texture, view, sampler)
+            .with_view_dimension(wgpu::TextureViewDimension::Cube))
+    }
+}
+
+impl Texture {
+    /// A 2D view of mip level 0 of cube face (or array layer) `face`.
+    pub(crate) fn face_view(&self, face: u32) -> wgpu::TextureView {
+        self.texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Cube Face View"),
+            dimension: Some(wgpu::TextureViewDimension::D2),
+            base_array_layer: face,
+            array_layer_count: Some(1),
+            mip_level_count: Some(1),
+            ..Default::default()
+        })
+    }
+}
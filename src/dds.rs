@@ -0,0 +1,564 @@
+use super::context::Context;
+use super::error::Error;
+use super::texture::Texture;
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"DDS ";
+const HEADER_SIZE: usize = 128;
+const DX10_HEADER_SIZE: usize = 20;
+
+const HEADER_CAPS: u32 = 0x1;
+const HEADER_HEIGHT: u32 = 0x2;
+const HEADER_WIDTH: u32 = 0x4;
+const HEADER_PIXEL_FORMAT: u32 = 0x1000;
+const HEADER_MIP_MAP_COUNT: u32 = 0x2_0000;
+const CAPS_COMPLEX: u32 = 0x8;
+const CAPS_TEXTURE: u32 = 0x1000;
+const CAPS_MIP_MAP: u32 = 0x40_0000;
+const PIXEL_FORMAT_ALPHA_PIXELS: u32 = 0x1;
+const PIXEL_FORMAT_FOURCC: u32 = 0x4;
+const PIXEL_FORMAT_RGB: u32 = 0x40;
+const PIXEL_FORMAT_LUMINANCE: u32 = 0x20000;
+const CAPS2_CUBEMAP: u32 = 0x200;
+const CAPS2_CUBEMAP_ALL_FACES: u32 = 0xFC00;
+const CAPS2_VOLUME: u32 = 0x20_0000;
+const DX10_RESOURCE_TEXTURE1D: u32 = 2;
+const DX10_RESOURCE_TEXTURE2D: u32 = 3;
+const DX10_RESOURCE_TEXTURE3D: u32 = 4;
+const DX10_MISC_TEXTURECUBE: u32 = 0x4;
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, Error> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or_else(|| Error::InvalidDds("truncated file".to_string()))
+}
+
+fn fourcc(code: &[u8; 4]) -> u32 {
+    u32::from_le_bytes(*code)
+}
+
+/// The `wgpu::TextureFormat` of a `DXGI_FORMAT`, for the formats that have
+/// one.
+fn dxgi_format(format: u32) -> Option<wgpu::TextureFormat> {
+    use wgpu::TextureFormat as F;
+    Some(match format {
+        2 => F::Rgba32Float,
+        3 => F::Rgba32Uint,
+        4 => F::Rgba32Sint,
+        10 => F::Rgba16Float,
+        11 => F::Rgba16Unorm,
+        12 => F::Rgba16Uint,
+        13 => F::Rgba16Snorm,
+        14 => F::Rgba16Sint,
+        16 => F::Rg32Float,
+        17 => F::Rg32Uint,
+        18 => F::Rg32Sint,
+        24 => F::Rgb10a2Unorm,
+        25 => F::Rgb10a2Uint,
+        26 => F::Rg11b10Float,
+        28 => F::Rgba8Unorm,
+        29 => F::Rgba8UnormSrgb,
+        30 => F::Rgba8Uint,
+        31 => F::Rgba8Snorm,
+        32 => F::Rgba8Sint,
+        34 => F::Rg16Float,
+        35 => F::Rg16Unorm,
+        36 => F::Rg16Uint,
+        37 => F::Rg16Snorm,
+        38 => F::Rg16Sint,
+        41 => F::R32Float,
+        42 => F::R32Uint,
+        43 => F::R32Sint,
+        49 => F::Rg8Unorm,
+        50 => F::Rg8Uint,
+        51 => F::Rg8Snorm,
+        52 => F::Rg8Sint,
+        54 => F::R16Float,
+        56 => F::R16Unorm,
+        57 => F::R16Uint,
+        58 => F::R16Snorm,
+        59 => F::R16Sint,
+        61 => F::R8Unorm,
+        62 => F::R8Uint,
+        63 => F::R8Snorm,
+        64 => F::R8Sint,
+        67 => F::Rgb9e5Ufloat,
+        71 => F::Bc1RgbaUnorm,
+        72 => F::Bc1RgbaUnormSrgb,
+        74 => F::Bc2RgbaUnorm,
+        75 => F::Bc2RgbaUnormSrgb,
+        77 => F::Bc3RgbaUnorm,
+        78 => F::Bc3RgbaUnormSrgb,
+        80 => F::Bc4RUnorm,
+        81 => F::Bc4RSnorm,
+        83 => F::Bc5RgUnorm,
+        84 => F::Bc5RgSnorm,
+        87 => F::Bgra8Unorm,
+        91 => F::Bgra8UnormSrgb,
+        95 => F::Bc6hRgbUfloat,
+        96 => F::Bc6hRgbFloat,
+        98 => F::Bc7RgbaUnorm,
+        99 => F::Bc7RgbaUnormSrgb,
+        _ => return None,
+    })
+}
+
+/// The `wgpu::TextureFormat` of a legacy pixel format, from its FourCC or
+/// its bit count and channel masks.
+fn legacy_format(data: &[u8]) -> Result<Option<wgpu::TextureFormat>, Error> {
+    use wgpu::TextureFormat as F;
+    let flags = read_u32(data, 80)?;
+    let code = read_u32(data, 84)?;
+    if flags & PIXEL_FORMAT_FOURCC != 0 {
+        return Ok(match code {
+            c if c == fourcc(b"DXT1") => Some(F::Bc1RgbaUnorm),
+            c if c == fourcc(b"DXT2") || c == fourcc(b"DXT3") => Some(F::Bc2RgbaUnorm),
+            c if c == fourcc(b"DXT4") || c == fourcc(b"DXT5") => Some(F::Bc3RgbaUnorm),
+            c if c == fourcc(b"ATI1") || c == fourcc(b"BC4U") => Some(F::Bc4RUnorm),
+            c if c == fourcc(b"BC4S") => Some(F::Bc4RSnorm),
+            c if c == fourcc(b"ATI2") || c == fourcc(b"BC5U") => Some(F::Bc5RgUnorm),
+            c if c == fourcc(b"BC5S") => Some(F::Bc5RgSnorm),
+            // D3DFORMAT values stored in place of a FourCC.
+            36 => Some(F::Rgba16Unorm),
+            111 => Some(F::R16Float),
+            112 => Some(F::Rg16Float),
+            113 => Some(F::Rgba16Float),
+            114 => Some(F::R32Float),
+            115 => Some(F::Rg32Float),
+            116 => Some(F::Rgba32Float),
+            _ => None,
+        });
+    }
+    let bits = read_u32(data, 88)?;
+    let masks = [
+        read_u32(data, 92)?,
+        read_u32(data, 96)?,
+        read_u32(data, 100)?,
+        match flags & PIXEL_FORMAT_ALPHA_PIXELS {
+            0 => 0,
+            _ => read_u32(data, 104)?,
+        },
+    ];
+    Ok(
+        match (
+            flags & (PIXEL_FORMAT_RGB | PIXEL_FORMAT_LUMINANCE),
+            bits,
+            masks,
+        ) {
+            (PIXEL_FORMAT_RGB, 32, [0xFF, 0xFF00, 0xFF_0000, _]) => Some(F::Rgba8Unorm),
+            (PIXEL_FORMAT_RGB, 32, [0xFF_0000, 0xFF00, 0xFF, _]) => Some(F::Bgra8Unorm),
+            (PIXEL_FORMAT_RGB, 32, [0x3FF, 0xF_FC00, 0x3FF0_0000, _]) => Some(F::Rgb10a2Unorm),
+            (PIXEL_FORMAT_RGB, 32, [0xFFFF, 0xFFFF_0000, 0, 0]) => Some(F::Rg16Unorm),
+            (PIXEL_FORMAT_LUMINANCE, 8, [0xFF, 0, 0, 0]) => Some(F::R8Unorm),
+            (PIXEL_FORMAT_LUMINANCE, 16, [0xFFFF, 0, 0, 0]) => Some(F::R16Unorm),
+            (PIXEL_FORMAT_LUMINANCE, 16, [0xFF, 0, 0, 0xFF00]) => Some(F::Rg8Unorm),
+            _ => None,
+        },
+    )
+}
+
+/// The parts of a DDS header (with its DX10 extension) needed to upload
+/// the texture.
+#[derive(Debug, PartialEq)]
+struct Header {
+    format: wgpu::TextureFormat,
+    dimension: wgpu::TextureDimension,
+    view_dimension: wgpu::TextureViewDimension,
+    size: wgpu::Extent3d,
+    mip_level_count: u32,
+    /// Offset of the texel data.
+    offset: usize,
+}
+
+impl Header {
+    fn parse(data: &[u8]) -> Result<Self, Error> {
+        if !data.starts_with(MAGIC) || read_u32(data, 4)? != 124 {
+            return Err(Error::InvalidDds("missing DDS header".to_string()));
+        }
+        let height = read_u32(data, 12)?;
+        let width = read_u32(data, 16)?;
+        let depth = read_u32(data, 24)?;
+        let mip_level_count = read_u32(data, 28)?.max(1);
+        let caps2 = read_u32(data, 112)?;
+
+        use wgpu::{TextureDimension, TextureViewDimension};
+        let dx10 = read_u32(data, 84)? == fourcc(b"DX10")
+            && read_u32(data, 80)? & PIXEL_FORMAT_FOURCC != 0;
+        let (format, offset, dimension, cube, array_layers) = match dx10 {
+            true => {
+                let dxgi = read_u32(data, HEADER_SIZE)?;
+                let format = dxgi_format(dxgi)
+                    .ok_or_else(|| Error::InvalidDds(format!("unsupported DXGI format {dxgi}")))?;
+                let dimension = match read_u32(data, HEADER_SIZE + 4)? {
+                    DX10_RESOURCE_TEXTURE1D => TextureDimension::D1,
+                    DX10_RESOURCE_TEXTURE3D => TextureDimension::D3,
+                    _ => TextureDimension::D2,
+                };
+                let cube = read_u32(data, HEADER_SIZE + 8)? & DX10_MISC_TEXTURECUBE != 0;
+                let array_size = read_u32(data, HEADER_SIZE + 12)?.max(1);
+                let offset = HEADER_SIZE + DX10_HEADER_SIZE;
+                (format, offset, dimension, cube, array_size)
+            }
+            false => {
+                let format = legacy_format(data)?
+                    .ok_or_else(|| Error::InvalidDds("unsupported pixel format".to_string()))?;
+                let dimension = match caps2 & CAPS2_VOLUME {
+                    0 => TextureDimension::D2,
+                    _ => TextureDimension::D3,
+                };
+                let cube = caps2 & CAPS2_CUBEMAP != 0;
+                if cube && caps2 & CAPS2_CUBEMAP_ALL_FACES != CAPS2_CUBEMAP_ALL_FACES {
+                    return Err(Error::InvalidDds("cube map with missing faces".to_string()));
+                }
+                (format, HEADER_SIZE, dimension, cube, 1)
+            }
+        };
+        if format.is_compressed() && dimension != TextureDimension::D2 {
+            return Err(Error::UnsupportedTextureFormat(format));
+        }
+
+        let faces = if cube { 6 } else { 1 };
+        let layers = array_layers.saturating_mul(faces);
+        let view_dimension = match (dimension, cube, array_layers) {
+            (TextureDimension::D1, _, 1) => TextureViewDimension::D1,
+            (TextureDimension::D3, false, 1) => TextureViewDimension::D3,
+            (TextureDimension::D2, false, 1) => TextureViewDimension::D2,
+            (TextureDimension::D2, false, _) => TextureViewDimension::D2Array,
+            (TextureDimension::D2, true, 1) => TextureViewDimension::Cube,
+            (TextureDimension::D2, true, _) => TextureViewDimension::CubeArray,
+            _ => {
+                return Err(Error::InvalidDds(
+                    "unsupported combination of dimension, cube and array size".to_string(),
+                ))
+            }
+        };
+        let size = wgpu::Extent3d {
+            width,
+            height: match dimension {
+                TextureDimension::D1 => 1,
+                _ => height,
+            },
+            depth_or_array_layers: match dimension {
+                TextureDimension::D3 => depth,
+                _ => layers,
+            },
+        };
+        if size.width == 0 || size.height == 0 || size.depth_or_array_layers == 0 {
+            return Err(Error::InvalidDds("zero-sized texture".to_string()));
+        }
+        let (block_width, block_height) = format.block_dimensions();
+        if !size.width.is_multiple_of(block_width) || !size.height.is_multiple_of(block_height) {
+            return Err(Error::InvalidDds(format!(
+                "{width}x{height} is not a multiple of the {block_width}x{block_height} blocks of {format:?}"
+            )));
+        }
+        if mip_level_count > size.max_mips(dimension) {
+            return Err(Error::InvalidDds(format!(
+                "{mip_level_count} mip levels for a {width}x{height} texture"
+            )));
+        }
+        Ok(Self {
+            format,
+            dimension,
+            view_dimension,
+            size,
+            mip_level_count,
+            offset,
+        })
+    }
+}
+
+impl Context {
+    /// Creates a texture from a DDS file, with the mip chain, array layers
+    /// and cube faces it holds, in its native (including BCn-compressed)
+    /// format. Both legacy headers and the DX10 extension are read. Cube
+    /// maps are viewed as cubes (or cube arrays), arrays as 2D arrays and
+    /// volumes in 3D. Only complete cube maps are supported.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self, data), fields(bytes = data.len()), err)
+    )]
+    pub fn texture_from_dds(&self, data: &[u8], label: Option<&str>) -> Result<Texture, Error> {
+        let Header {
+            format,
+            dimension,
+            view_dimension,
+            size,
+            mip_level_count,
+            offset,
+        } = Header::parse(data)?;
+        if !self.device.features().contains(format.required_features()) {
+            return Err(Error::UnsupportedTextureFormat(format));
+        }
+        self.check_texture_size(&size, dimension)?;
+
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size,
+            mip_level_count,
+            sample_count: 1,
+            dimension,
+            format,
+            view_formats: &[format],
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
+        });
+
+        // Each layer (or face) holds its whole mip chain before the next.
+        let (block_width, block_height) = format.block_dimensions();
+        let block_size = format
+            .block_copy_size(None)
+            .ok_or(Error::UnsupportedTextureFormat(format))?;
+        let surfaces = match dimension {
+            wgpu::TextureDimension::D3 => 1,
+            _ => size.depth_or_array_layers,
+        };
+        let mut offset = offset;
+        for layer in 0..surfaces {
+            for level in 0..mip_level_count {
+                let level_size = size.mip_level_size(level, dimension);
+                let bytes_per_row = level_size.width.div_ceil(block_width) * block_size;
+                let rows_per_image = level_size.height.div_ceil(block_height);
+                let depth = match dimension {
+                    wgpu::TextureDimension::D3 => level_size.depth_or_array_layers,
+                    _ => 1,
+                };
+                let length = bytes_per_row as usize * rows_per_image as usize * depth as usize;
+                let level_data = offset
+                    .checked_add(length)
+                    .and_then(|end| data.get(offset..end))
+                    .ok_or_else(|| {
+                        Error::InvalidDds(format!("layer {layer} level {level} is truncated"))
+                    })?;
+                offset += length;
+                self.queue.write_texture(
+                    wgpu::ImageCopyTexture {
+                        aspect: wgpu::TextureAspect::All,
+                        texture: &texture,
+                        mip_level: level,
+                        origin: wgpu::Origin3d {
+                            x: 0,
+                            y: 0,
+                            z: layer,
+                        },
+                    },
+                    level_data,
+                    wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: Some(bytes_per_row),
+                        rows_per_image: Some(rows_per_image),
+                    },
+                    wgpu::Extent3d {
+                        depth_or_array_layers: depth,
+                        ..level_size
+                    }
+                    .physical_size(format),
+                );
+            }
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(view_dimension),
+            ..Default::default()
+        });
+        let sampler = self.texture_sampler();
+        #[cfg(feature = "tracing")]
+        tracing::debug!("created texture");
+
+        Ok(Texture::from_raw(texture, view, sampler).with_view_dimension(view_dimension))
+    }
+
+    pub fn load_dds(&self, path: impl AsRef<Path>, label: Option<&str>) -> Result<Texture, Error> {
+        self.texture_from_dds(&std::fs::read(path)?, label)
+    }
+
+    /// Encodes the first layer of a 2D texture with all its mip levels as a
+    /// DDS file with a DX10 header, in the texture's format, which must
+    /// have a `DXGI_FORMAT`. The texture needs `COPY_SRC` usage.
+    pub fn encode_dds(&self, texture: &Texture) -> Result<Vec<u8>, Error> {
+        let format = texture.format();
+        let dxgi = (0..=132)
+            .find(|&code| dxgi_format(code) == Some(format))
+            .ok_or(Error::UnsupportedTextureFormat(format))?;
+        if texture.dimension() != wgpu::TextureDimension::D2 {
+            return Err(Error::InvalidDds(
+                "only 2D textures can be written".to_string(),
+            ));
+        }
+        let mip_level_count = texture.mip_level_count();
+        let mut caps = CAPS_TEXTURE;
+        if mip_level_count > 1 {
+            caps |= CAPS_COMPLEX | CAPS_MIP_MAP;
+        }
+
+        let mut header = [0u32; (HEADER_SIZE + DX10_HEADER_SIZE) / 4];
+        header[0] = fourcc(MAGIC);
+        header[1] = 124;
+        header[2] =
+            HEADER_CAPS | HEADER_HEIGHT | HEADER_WIDTH | HEADER_PIXEL_FORMAT | HEADER_MIP_MAP_COUNT;
+        header[3] = texture.height();
+        header[4] = texture.width();
+        header[7] = mip_level_count;
+        header[19] = 32;
+        header[20] = PIXEL_FORMAT_FOURCC;
+        header[21] = fourcc(b"DX10");
+        header[27] = caps;
+        header[32] = dxgi;
+        header[33] = DX10_RESOURCE_TEXTURE2D;
+        header[35] = 1;
+
+        let mut data: Vec<u8> = header.iter().flat_map(|word| word.to_le_bytes()).collect();
+        for level in 0..mip_level_count {
+            data.extend(self.read_texture_level(texture, level)?);
+        }
+        Ok(data)
+    }
+
+    /// Writes `encode_dds` of `texture` to `path`.
+    pub fn save_dds(&self, texture: &Texture, path: impl AsRef<Path>) -> Result<(), Error> {
+        std::fs::write(path, self.encode_dds(texture)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wgpu::{TextureDimension as D, TextureFormat as F, TextureViewDimension as V};
+
+    /// A legacy header with the FourCC `code`, or an RGB pixel format with
+    /// `masks` if `code` is `None`.
+    fn legacy(code: Option<&[u8; 4]>, masks: [u32; 4], size: [u32; 3], caps2: u32) -> Vec<u8> {
+        let mut header = [0u32; HEADER_SIZE / 4];
+        header[0] = fourcc(MAGIC);
+        header[1] = 124;
+        [header[3], header[4], header[6]] = [size[1], size[0], size[2]];
+        header[7] = 1;
+        match code {
+            Some(code) => [header[20], header[21]] = [PIXEL_FORMAT_FOURCC, fourcc(code)],
+            None => {
+                header[20] = PIXEL_FORMAT_RGB | PIXEL_FORMAT_ALPHA_PIXELS;
+                header[22] = 32;
+                header[23..27].copy_from_slice(&masks);
+            }
+        }
+        header[28] = caps2;
+        header.iter().flat_map(|word| word.to_le_bytes()).collect()
+    }
+
+    fn dx10(dxgi: u32, resource: u32, misc: u32, array_size: u32, size: [u32; 3]) -> Vec<u8> {
+        let mut data = legacy(Some(b"DX10"), [0; 4], size, 0);
+        for word in [dxgi, resource, misc, array_size, 0] {
+            data.extend(word.to_le_bytes());
+        }
+        data
+    }
+
+    fn with_mips(mut data: Vec<u8>, mip_level_count: u32) -> Vec<u8> {
+        data[28..32].copy_from_slice(&mip_level_count.to_le_bytes());
+        data
+    }
+
+    fn invalid(data: &[u8]) -> String {
+        match Header::parse(data) {
+            Err(Error::InvalidDds(message)) => message,
+            other => panic!("expected an invalid DDS file, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn legacy_fourcc_headers() {
+        let header =
+            Header::parse(&with_mips(legacy(Some(b"DXT1"), [0; 4], [16, 8, 0], 0), 4)).unwrap();
+        assert_eq!(
+            header,
+            Header {
+                format: F::Bc1RgbaUnorm,
+                dimension: D::D2,
+                view_dimension: V::D2,
+                size: wgpu::Extent3d {
+                    width: 16,
+                    height: 8,
+                    depth_or_array_layers: 1
+                },
+                mip_level_count: 4,
+                offset: HEADER_SIZE,
+            }
+        );
+        let formats = [
+            (b"DXT5", F::Bc3RgbaUnorm),
+            (b"ATI2", F::Bc5RgUnorm),
+            (b"BC4S", F::Bc4RSnorm),
+        ];
+        for (code, format) in formats {
+            let header = Header::parse(&legacy(Some(code), [0; 4], [4, 4, 0], 0)).unwrap();
+            assert_eq!(header.format, format);
+        }
+        assert!(invalid(&legacy(Some(b"ETC1"), [0; 4], [4, 4, 0], 0)).contains("pixel format"));
+    }
+
+    #[test]
+    fn legacy_mask_headers() {
+        let bgra = [0xFF_0000, 0xFF00, 0xFF, 0xFF00_0000];
+        let header = Header::parse(&legacy(None, bgra, [3, 5, 0], 0)).unwrap();
+        assert_eq!(header.format, F::Bgra8Unorm);
+        let rgba = [0xFF, 0xFF00, 0xFF_0000, 0xFF00_0000];
+        let header = Header::parse(&legacy(None, rgba, [3, 5, 0], 0)).unwrap();
+        assert_eq!(header.format, F::Rgba8Unorm);
+    }
+
+    #[test]
+    fn dx10_headers() {
+        let header = Header::parse(&dx10(99, DX10_RESOURCE_TEXTURE2D, 0, 3, [8, 8, 0])).unwrap();
+        assert_eq!(header.format, F::Bc7RgbaUnormSrgb);
+        assert_eq!(header.view_dimension, V::D2Array);
+        assert_eq!(header.size.depth_or_array_layers, 3);
+        assert_eq!(header.offset, HEADER_SIZE + DX10_HEADER_SIZE);
+
+        let header = Header::parse(&dx10(10, DX10_RESOURCE_TEXTURE3D, 0, 1, [4, 4, 7])).unwrap();
+        assert_eq!((header.dimension, header.view_dimension), (D::D3, V::D3));
+        assert_eq!(header.size.depth_or_array_layers, 7);
+
+        let header = Header::parse(&dx10(61, DX10_RESOURCE_TEXTURE1D, 0, 1, [9, 4, 0])).unwrap();
+        assert_eq!((header.dimension, header.size.height), (D::D1, 1));
+
+        assert!(invalid(&dx10(1, DX10_RESOURCE_TEXTURE2D, 0, 1, [4, 4, 0])).contains("DXGI"));
+        assert!(matches!(
+            Header::parse(&dx10(71, DX10_RESOURCE_TEXTURE3D, 0, 1, [4, 4, 4])),
+            Err(Error::UnsupportedTextureFormat(F::Bc1RgbaUnorm))
+        ));
+    }
+
+    #[test]
+    fn cube_headers() {
+        let caps2 = CAPS2_CUBEMAP | CAPS2_CUBEMAP_ALL_FACES;
+        let header = Header::parse(&legacy(Some(b"DXT1"), [0; 4], [8, 8, 0], caps2)).unwrap();
+        assert_eq!(header.view_dimension, V::Cube);
+        assert_eq!(header.size.depth_or_array_layers, 6);
+        let missing = legacy(Some(b"DXT1"), [0; 4], [8, 8, 0], CAPS2_CUBEMAP | 0x400);
+        assert!(invalid(&missing).contains("missing faces"));
+
+        let header = Header::parse(&dx10(
+            2,
+            DX10_RESOURCE_TEXTURE2D,
+            DX10_MISC_TEXTURECUBE,
+            2,
+            [4, 4, 0],
+        ))
+        .unwrap();
+        assert_eq!(header.view_dimension, V::CubeArray);
+        assert_eq!(header.size.depth_or_array_layers, 12);
+    }
+
+    #[test]
+    fn malformed_headers() {
+        assert!(invalid(b"DDS ").contains("truncated"));
+        assert!(invalid(&[0; 128]).contains("missing DDS header"));
+        assert!(invalid(&legacy(Some(b"DXT1"), [0; 4], [0, 4, 0], 0)).contains("zero-sized"));
+        assert!(invalid(&legacy(Some(b"DXT1"), [0; 4], [6, 8, 0], 0)).contains("4x4 blocks"));
+        assert!(invalid(&legacy(Some(b"DXT5"), [0; 4], [8, 2, 0], 0)).contains("4x4 blocks"));
+        let mips = with_mips(legacy(Some(b"DXT1"), [0; 4], [8, 8, 0], 0), 5);
+        assert!(invalid(&mips).contains("mip levels"));
+    }
+}
@@ -0,0 +1,520 @@
+use super::aov::AovTargets;
+use super::context::Context;
+use super::depth::Colormap;
+use super::diagnostics::{describe_texture, PassRecord};
+use super::error::Error;
+use super::math::normal_matrix;
+use super::mesh::{GpuMesh, Vertex};
+use super::pass_ops::PassOps;
+#[cfg(feature = "scene")]
+use super::scene::{Camera, Scene};
+use super::texture::Texture;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use wgpu::util::DeviceExt;
+
+const SRGB_TARGET: u32 = 1;
+const HAS_TEXTURE: u32 = 2;
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    view_projection: [[f32; 4]; 4],
+    model: [[f32; 4]; 4],
+    normal_matrix: [[f32; 4]; 4],
+    previous_view_projection: [[f32; 4]; 4],
+    base_color: [f32; 4],
+    mode: u32,
+    colormap: u32,
+    flags: u32,
+    value: f32,
+    object_id: f32,
+    _padding: [f32; 3],
+}
+
+/// One mesh of a `DebugMaterialRenderer` pass.
+struct Draw<'a> {
+    mesh: &'a GpuMesh,
+    model: [[f32; 4]; 4],
+    /// The base color texture and factor of the mesh's own material.
+    texture: Option<&'a Texture>,
+    base_color: [f32; 4],
+    object_id: u32,
+}
+
+/// What `DebugMaterialRenderer` draws in place of the materials.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DebugMaterial {
+    /// A checkerboard of `density` squares per UV unit along each axis,
+    /// tinted red along U and green along V, which shows stretching, seams
+    /// and flipped islands.
+    UvChecker { density: f32 },
+    /// The base color texture tinted through the colormap by its
+    /// resolution, from `DebugMaterialRenderer::resolution_range`, which
+    /// shows oversized and undersized textures. Untextured surfaces are
+    /// gray.
+    TextureResolution,
+}
+
+impl Default for DebugMaterial {
+    fn default() -> Self {
+        Self::UvChecker { density: 8.0 }
+    }
+}
+
+/// Renders meshes with a debug material instead of their own, for
+/// checking UV layouts and texture budgets.
+pub struct DebugMaterialRenderer {
+    module: wgpu::ShaderModule,
+    layout: wgpu::BindGroupLayout,
+    pipeline_layout: wgpu::PipelineLayout,
+    #[allow(clippy::type_complexity)]
+    pipelines: Mutex<
+        HashMap<
+            (
+                Vec<Option<wgpu::TextureFormat>>,
+                Option<(wgpu::TextureFormat, wgpu::CompareFunction)>,
+            ),
+            wgpu::RenderPipeline,
+        >,
+    >,
+    /// Bound for untextured surfaces.
+    blank: Texture,
+    pub material: DebugMaterial,
+    pub colormap: Colormap,
+    /// Texture sizes, along the longer side, mapped to the ends of the
+    /// colormap on a log scale.
+    pub resolution_range: [u32; 2],
+    /// Loads and stores of the target and depth, by default drawing over
+    /// their contents.
+    pub ops: PassOps,
+}
+
+impl DebugMaterialRenderer {
+    pub fn new(context: &Context) -> Result<Self, Error> {
+        let device = &context.device;
+        let source = include_str!("shaders/colormap.wgsl").to_string()
+            + include_str!("shaders/debug_material.wgsl");
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("debug_material.wgsl"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Debug Material Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Debug Material Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        Ok(Self {
+            module,
+            layout,
+            pipeline_layout,
+            pipelines: Mutex::new(HashMap::new()),
+            blank: context.texture_from_rgba(
+                &[1.0; 4],
+                &wgpu::TextureFormat::Rgba8Unorm,
+                Some("Debug Material Blank"),
+            )?,
+            material: DebugMaterial::default(),
+            colormap: Colormap::default(),
+            resolution_range: [64, 4096],
+            ops: PassOps::default(),
+        })
+    }
+
+    /// Draws `mesh`, transformed by `model` and `view_projection`, into
+    /// `target` with the debug material, `texture` being the base color
+    /// texture of its own material. With `depth`, surfaces are depth tested
+    /// and written with the context's depth state.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_mesh(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        mesh: &GpuMesh,
+        view_projection: &[[f32; 4]; 4],
+        model: &[[f32; 4]; 4],
+        texture: Option<&Texture>,
+        target: &Texture,
+        depth: Option<&Texture>,
+    ) -> Result<(), Error> {
+        let draw = Draw {
+            mesh,
+            model: *model,
+            texture,
+            base_color: [1.0; 4],
+            object_id: 0,
+        };
+        self.draw_all(
+            context,
+            encoder,
+            &[draw],
+            view_projection,
+            view_projection,
+            target,
+            depth,
+            None,
+        )
+    }
+
+    /// Draws every object of `scene` seen by `camera` with the debug
+    /// material in place of its own into `target` and `depth`, in a single
+    /// render pass.
+    #[cfg(feature = "scene")]
+    pub fn draw_scene(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        scene: &Scene,
+        camera: &Camera,
+        target: &Texture,
+        depth: Option<&Texture>,
+    ) -> Result<(), Error> {
+        let view_projection =
+            camera.view_projection(target.width() as f32 / target.height() as f32);
+        self.draw_all(
+            context,
+            encoder,
+            &scene_draws(scene),
+            &view_projection,
+            &view_projection,
+            target,
+            depth,
+            None,
+        )
+    }
+
+    /// Like `draw_scene`, also writing `aovs`, of `target`'s size, in the
+    /// same pass. Motion is measured from the view of `previous_camera`,
+    /// and zero without it. The AOVs are cleared to zero first if `ops`
+    /// clears the target.
+    #[cfg(feature = "scene")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_scene_aovs(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        scene: &Scene,
+        camera: &Camera,
+        previous_camera: Option<&Camera>,
+        target: &Texture,
+        depth: Option<&Texture>,
+        aovs: &AovTargets,
+    ) -> Result<(), Error> {
+        let aspect = target.width() as f32 / target.height() as f32;
+        let view_projection = camera.view_projection(aspect);
+        let previous_view_projection =
+            previous_camera.map_or(view_projection, |previous| previous.view_projection(aspect));
+        self.draw_all(
+            context,
+            encoder,
+            &scene_draws(scene),
+            &view_projection,
+            &previous_view_projection,
+            target,
+            depth,
+            Some(aovs),
+        )
+    }
+
+    // Records the draws into one pass, so that the attachments are loaded
+    // and stored once rather than per mesh.
+    #[allow(clippy::too_many_arguments)]
+    fn draw_all(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        draws: &[Draw],
+        view_projection: &[[f32; 4]; 4],
+        previous_view_projection: &[[f32; 4]; 4],
+        target: &Texture,
+        depth: Option<&Texture>,
+        aovs: Option<&AovTargets>,
+    ) -> Result<(), Error> {
+        let aov_targets: Vec<_> = aovs.into_iter().flat_map(AovTargets::iter).collect();
+        if std::iter::once(target)
+            .chain(depth)
+            .chain(aov_targets.iter().map(|(_, texture)| *texture))
+            .any(|texture| {
+                !texture
+                    .usage()
+                    .contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+            })
+        {
+            return Err(Error::TextureCreationFailed);
+        }
+        if let Some((_, texture)) = aov_targets
+            .iter()
+            .find(|(_, texture)| texture.size() != target.size())
+        {
+            return Err(Error::TextureSizeMismatch {
+                expected: target.size(),
+                actual: texture.size(),
+            });
+        }
+        for texture in draws.iter().filter_map(|draw| draw.texture) {
+            let format = texture.format();
+            if format.sample_type(None, None)
+                != Some(wgpu::TextureSampleType::Float { filterable: true })
+            {
+                return Err(Error::UnsupportedTextureFormat(format));
+            }
+        }
+        let format = target.format();
+        let groups = match aovs {
+            Some(aovs) => aovs.attachment_groups(context, target)?,
+            None => vec![vec![(0, target)]],
+        };
+        let device = &context.device;
+        let bind_groups: Vec<_> = draws
+            .iter()
+            .map(|draw| {
+                let (model, texture) = (draw.model, draw.texture);
+                let mut flags = match format.is_srgb() {
+                    true => SRGB_TARGET,
+                    false => 0,
+                };
+                if texture.is_some() {
+                    flags |= HAS_TEXTURE;
+                }
+                let (mode, value) = match self.material {
+                    DebugMaterial::UvChecker { density } => (0, density),
+                    DebugMaterial::TextureResolution => {
+                        let size =
+                            texture.map_or(1, |texture| texture.width().max(texture.height()));
+                        let [low, high] = self
+                            .resolution_range
+                            .map(|size| (size.max(1) as f32).log2());
+                        let t = ((size as f32).log2() - low) / (high - low).max(f32::EPSILON);
+                        (1, t)
+                    }
+                };
+                let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Debug Material Params"),
+                    contents: bytemuck::bytes_of(&Params {
+                        view_projection: *view_projection,
+                        model,
+                        normal_matrix: normal_matrix(&model),
+                        previous_view_projection: *previous_view_projection,
+                        base_color: draw.base_color,
+                        mode,
+                        colormap: self.colormap.index(),
+                        flags,
+                        value,
+                        object_id: draw.object_id as f32,
+                        _padding: [0.0; 3],
+                    }),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                });
+                let material_texture = texture.unwrap_or(&self.blank);
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Debug Material Bind Group"),
+                    layout: &self.layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: params.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::TextureView(&material_texture.view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 2,
+                            resource: wgpu::BindingResource::Sampler(&material_texture.sampler),
+                        },
+                    ],
+                })
+            })
+            .collect();
+        context.diagnostics.record_pass(|| {
+            let mut resources: Vec<_> = draws
+                .iter()
+                .map(|draw| {
+                    describe_texture("texture", &draw.texture.unwrap_or(&self.blank).texture)
+                })
+                .collect();
+            resources.push(describe_texture("target", &target.texture));
+            if let Some(depth) = depth {
+                resources.push(describe_texture("depth", &depth.texture));
+            }
+            for (aov, texture) in &aov_targets {
+                resources.push(describe_texture(aov.name(), &texture.texture));
+            }
+            PassRecord::new("Debug Material", "fs_material", resources)
+        });
+
+        // Attachments beyond the device's limit go into further passes,
+        // which only shade the surfaces the first one kept.
+        let keys: Vec<_> = groups
+            .iter()
+            .enumerate()
+            .map(|(index, group)| {
+                let mut formats = vec![None; group.last().map_or(1, |(location, _)| location + 1)];
+                for (location, texture) in group {
+                    formats[*location] = Some(texture.format());
+                }
+                let compare = match index {
+                    0 => context.depth_compare_function(),
+                    _ => wgpu::CompareFunction::Equal,
+                };
+                (formats, depth.map(|depth| (depth.format(), compare)))
+            })
+            .collect();
+        let mut pipelines = self
+            .pipelines
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        for key in &keys {
+            pipelines
+                .entry(key.clone())
+                .or_insert_with(|| self.create_pipeline(context, &key.0, key.1));
+        }
+        let aov_load = match self.ops.clear_color {
+            Some(_) => wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+            None => wgpu::LoadOp::Load,
+        };
+        for (index, (group, key)) in groups.iter().zip(&keys).enumerate() {
+            let mut color_attachments = vec![None; key.0.len()];
+            for &(location, texture) in group {
+                let ops = match location {
+                    0 => self.ops.color(),
+                    _ => wgpu::Operations {
+                        load: aov_load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                };
+                color_attachments[location] = Some(wgpu::RenderPassColorAttachment {
+                    view: &texture.view,
+                    resolve_target: None,
+                    ops,
+                });
+            }
+            let mut depth_ops = self.ops.depth();
+            if index > 0 {
+                depth_ops.load = wgpu::LoadOp::Load;
+            }
+            if index + 1 < groups.len() {
+                depth_ops.store = wgpu::StoreOp::Store;
+            }
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Debug Material"),
+                color_attachments: &color_attachments,
+                depth_stencil_attachment: depth.map(|depth| {
+                    wgpu::RenderPassDepthStencilAttachment {
+                        view: &depth.view,
+                        depth_ops: Some(depth_ops),
+                        stencil_ops: None,
+                    }
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_pipeline(&pipelines[key]);
+            for (draw, bind_group) in draws.iter().zip(&bind_groups) {
+                pass.set_bind_group(0, bind_group, &[]);
+                pass.set_vertex_buffer(0, draw.mesh.vertex_buffer.slice(..));
+                pass.set_index_buffer(draw.mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                pass.draw_indexed(0..draw.mesh.index_count, 0, 0..1);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn create_pipeline(
+        &self,
+        context: &Context,
+        formats: &[Option<wgpu::TextureFormat>],
+        depth_state: Option<(wgpu::TextureFormat, wgpu::CompareFunction)>,
+    ) -> wgpu::RenderPipeline {
+        let entry_point = match formats {
+            [Some(_)] => "fs_material",
+            _ => "fs_aovs",
+        };
+        let targets: Vec<_> = formats
+            .iter()
+            .map(|format| format.map(wgpu::ColorTargetState::from))
+            .collect();
+        context
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Debug Material"),
+                layout: Some(&self.pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &self.module,
+                    entry_point: "vs_mesh",
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    buffers: &[Vertex::layout()],
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: depth_state.map(|(format, compare)| wgpu::DepthStencilState {
+                    depth_compare: compare,
+                    depth_write_enabled: compare != wgpu::CompareFunction::Equal,
+                    ..context.depth_stencil_state(format)
+                }),
+                multisample: wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &self.module,
+                    entry_point,
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    targets: &targets,
+                }),
+                multiview: None,
+            })
+    }
+}
+
+/// The objects of `scene` with their own materials' base colors, and IDs
+/// counting from one.
+#[cfg(feature = "scene")]
+fn scene_draws(scene: &Scene) -> Vec<Draw<'_>> {
+    scene
+        .description
+        .objects
+        .iter()
+        .enumerate()
+        .map(|(index, object)| {
+            let material = scene.material(object);
+            Draw {
+                mesh: scene.object_mesh(object),
+                model: object.transform(),
+                texture: material
+                    .and_then(|material| material.base_color_texture.as_ref())
+                    .and_then(|path| scene.textures.get(path)),
+                base_color: material.map_or([1.0; 4], |material| material.base_color),
+                object_id: index as u32 + 1,
+            }
+        })
+        .collect()
+}
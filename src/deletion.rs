@@ -0,0 +1,138 @@
+use super::context::Context;
+use super::texture::Texture;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// A resource waiting in a `DeletionQueue`.
+#[derive(Debug)]
+pub enum Retired {
+    Buffer(wgpu::Buffer),
+    Texture(wgpu::Texture),
+    BindGroup(wgpu::BindGroup),
+}
+
+impl Retired {
+    fn destroy(self) {
+        match self {
+            Retired::Buffer(buffer) => buffer.destroy(),
+            Retired::Texture(texture) => texture.destroy(),
+            Retired::BindGroup(_) => {}
+        }
+    }
+}
+
+impl From<wgpu::Buffer> for Retired {
+    fn from(buffer: wgpu::Buffer) -> Self {
+        Retired::Buffer(buffer)
+    }
+}
+
+impl From<wgpu::Texture> for Retired {
+    fn from(texture: wgpu::Texture) -> Self {
+        Retired::Texture(texture)
+    }
+}
+
+impl From<Texture> for Retired {
+    fn from(texture: Texture) -> Self {
+        Retired::Texture(texture.texture)
+    }
+}
+
+impl From<wgpu::BindGroup> for Retired {
+    fn from(bind_group: wgpu::BindGroup) -> Self {
+        Retired::BindGroup(bind_group)
+    }
+}
+
+struct Batch {
+    index: wgpu::SubmissionIndex,
+    done: Arc<AtomicBool>,
+    resources: Vec<Retired>,
+}
+
+/// Delays destroying replaced buffers and textures until the submissions
+/// that may still use them have completed.
+///
+/// `retire` resources as they are replaced, then call `submitted` with the
+/// index of the next submission (the last one that can reference them).
+/// `collect` destroys the batches whose submissions finished.
+#[derive(Default)]
+pub struct DeletionQueue {
+    pending: Vec<Retired>,
+    in_flight: VecDeque<Batch>,
+}
+
+impl DeletionQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn retire(&mut self, resource: impl Into<Retired>) {
+        self.pending.push(resource.into());
+    }
+
+    /// Ties the resources retired since the last call to `index`.
+    pub fn submitted(&mut self, context: &Context, index: wgpu::SubmissionIndex) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let done = Arc::new(AtomicBool::new(false));
+        let signal = done.clone();
+        context
+            .queue
+            .on_submitted_work_done(move || signal.store(true, Ordering::Release));
+        self.in_flight.push_back(Batch {
+            index,
+            done,
+            resources: std::mem::take(&mut self.pending),
+        });
+    }
+
+    /// Destroys the resources of completed submissions without blocking
+    /// and returns how many were destroyed.
+    pub fn collect(&mut self, context: &Context) -> usize {
+        context.device.poll(wgpu::Maintain::Poll);
+        let mut destroyed = 0;
+        while let Some(batch) = self.in_flight.front() {
+            if !batch.done.load(Ordering::Acquire) {
+                break;
+            }
+            if let Some(batch) = self.in_flight.pop_front() {
+                destroyed += batch.resources.len();
+                batch.resources.into_iter().for_each(Retired::destroy);
+            }
+        }
+        #[cfg(feature = "tracing")]
+        if destroyed > 0 {
+            tracing::debug!(destroyed, "destroyed retired resources");
+        }
+        destroyed
+    }
+
+    /// Waits for all tracked submissions and destroys their resources.
+    /// Resources retired after the last `submitted` call stay queued.
+    pub fn flush(&mut self, context: &Context) -> usize {
+        if let Some(batch) = self.in_flight.back() {
+            context
+                .device
+                .poll(wgpu::Maintain::WaitForSubmissionIndex(batch.index.clone()));
+        }
+        self.collect(context)
+    }
+
+    /// Number of resources not destroyed yet, including unsubmitted ones.
+    pub fn len(&self) -> usize {
+        self.pending.len()
+            + self
+                .in_flight
+                .iter()
+                .map(|batch| batch.resources.len())
+                .sum::<usize>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -0,0 +1,433 @@
+use super::context::Context;
+use super::depth::{aspect_view, DepthRange};
+use super::diagnostics::{describe_texture, PassRecord};
+use super::error::Error;
+use super::texture::Texture;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use wgpu::util::DeviceExt;
+
+const INTERMEDIATE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+const REVERSE_Z: u32 = 1;
+const INFINITE_FAR: u32 = 2;
+const LOGARITHMIC: u32 = 4;
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    near: f32,
+    far: f32,
+    flags: u32,
+    step: i32,
+    luminance_sensitivity: f32,
+    normal_power: f32,
+    depth_sensitivity: f32,
+    temporal_alpha: f32,
+}
+
+/// The noisy image and the surfaces it shows, all of the size the
+/// `Denoiser` was created for.
+pub struct DenoiseInputs<'a> {
+    /// E.g. path-traced lighting or stochastic ambient occlusion.
+    pub color: &'a Texture,
+    /// Normals in the RGB channels, signed and in any space (e.g. in
+    /// `Rgba16Float`), zero where there is no geometry.
+    pub normal: &'a Texture,
+    pub depth: &'a Texture,
+    /// The last frame, for accumulating the noise over time.
+    pub history: Option<DenoiseHistory<'a>>,
+}
+
+/// The last frame of a `Denoiser`, e.g. from `TemporalResources`.
+pub struct DenoiseHistory<'a> {
+    /// Screen-space motion of each pixel since the last frame, in UV units.
+    pub motion: &'a Texture,
+    /// The last denoised output.
+    pub color: &'a Texture,
+    /// The last depth, written with the same `DepthRange`.
+    pub depth: &'a Texture,
+}
+
+/// Edge-aware à-trous denoiser (a lighter SVGF) for ray-traced and other
+/// stochastic effects: optionally blends the noisy input with the
+/// reprojected last output, then runs `iterations` wavelet filter passes of
+/// growing radius that avoid blurring across luminance, normal and depth
+/// edges.
+pub struct Denoiser {
+    targets: [Texture; 2],
+    module: wgpu::ShaderModule,
+    atrous_layout: wgpu::BindGroupLayout,
+    temporal_layout: wgpu::BindGroupLayout,
+    atrous_pipeline_layout: wgpu::PipelineLayout,
+    temporal_pipeline_layout: wgpu::PipelineLayout,
+    #[allow(clippy::type_complexity)]
+    pipelines: Mutex<HashMap<(&'static str, wgpu::TextureFormat), wgpu::RenderPipeline>>,
+    /// Filter passes, at least one, with a radius doubling each pass.
+    pub iterations: u32,
+    /// How quickly neighbors lose weight with their luminance difference.
+    pub luminance_sensitivity: f32,
+    /// Exponent of the cosine between normals weighting neighbors.
+    pub normal_power: f32,
+    /// How quickly neighbors lose weight with the relative difference of
+    /// their view distance, per pixel of the filter step.
+    pub depth_sensitivity: f32,
+    /// Weight of the new frame against the reprojected history, lower for
+    /// smoother but laggier results.
+    pub temporal_alpha: f32,
+}
+
+impl Denoiser {
+    /// A denoiser for `width` x `height` inputs.
+    pub fn new(context: &Context, width: &u32, height: &u32) -> Result<Self, Error> {
+        let device = &context.device;
+        let module = device.create_shader_module(wgpu::include_wgsl!("shaders/denoise.wgsl"));
+        let params = wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let texture = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let atrous_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Denoise A-Trous Bind Group Layout"),
+            entries: &[params, texture(1), texture(2), texture(3)],
+        });
+        let temporal_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Denoise Temporal Bind Group Layout"),
+            entries: &[
+                params,
+                texture(1),
+                texture(3),
+                texture(4),
+                texture(5),
+                texture(6),
+            ],
+        });
+        let pipeline_layout = |label, layout| {
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some(label),
+                bind_group_layouts: &[layout],
+                push_constant_ranges: &[],
+            })
+        };
+        let atrous_pipeline_layout =
+            pipeline_layout("Denoise A-Trous Pipeline Layout", &atrous_layout);
+        let temporal_pipeline_layout =
+            pipeline_layout("Denoise Temporal Pipeline Layout", &temporal_layout);
+
+        Ok(Self {
+            targets: Self::targets(context, width, height)?,
+            module,
+            atrous_layout,
+            temporal_layout,
+            atrous_pipeline_layout,
+            temporal_pipeline_layout,
+            pipelines: Mutex::new(HashMap::new()),
+            iterations: 4,
+            luminance_sensitivity: 4.0,
+            normal_power: 64.0,
+            depth_sensitivity: 16.0,
+            temporal_alpha: 0.2,
+        })
+    }
+
+    fn targets(context: &Context, width: &u32, height: &u32) -> Result<[Texture; 2], Error> {
+        Ok([
+            context.render_target(
+                width,
+                height,
+                &INTERMEDIATE_FORMAT,
+                Some("Denoise Target 0"),
+            )?,
+            context.render_target(
+                width,
+                height,
+                &INTERMEDIATE_FORMAT,
+                Some("Denoise Target 1"),
+            )?,
+        ])
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.targets[0].width(), self.targets[0].height())
+    }
+
+    /// Recreates the intermediate targets for `width` x `height` inputs if
+    /// the size changed.
+    pub fn resize(&mut self, context: &Context, width: &u32, height: &u32) -> Result<(), Error> {
+        if self.size() != (*width, *height) {
+            self.targets = Self::targets(context, width, height)?;
+        }
+        Ok(())
+    }
+
+    /// Draws the denoised `inputs.color` into `output`, with depths written
+    /// with `range`.
+    pub fn denoise(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        inputs: &DenoiseInputs,
+        range: &DepthRange,
+        output: &Texture,
+    ) -> Result<(), Error> {
+        if !output
+            .usage()
+            .contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+        {
+            return Err(Error::TextureCreationFailed);
+        }
+        let expected = self.targets[0].size();
+        let mut textures = vec![inputs.color, inputs.normal, inputs.depth, output];
+        if let Some(history) = &inputs.history {
+            textures.extend([history.motion, history.color, history.depth]);
+        }
+        if let Some(texture) = textures.iter().find(|texture| texture.size() != expected) {
+            return Err(Error::TextureSizeMismatch {
+                expected,
+                actual: texture.size(),
+            });
+        }
+        let mut colors = vec![inputs.color, inputs.normal];
+        if let Some(history) = &inputs.history {
+            colors.extend([history.motion, history.color]);
+        }
+        for texture in colors {
+            if !matches!(
+                texture
+                    .format()
+                    .sample_type(None, Some(context.device.features())),
+                Some(wgpu::TextureSampleType::Float { .. })
+            ) {
+                return Err(Error::UnsupportedTextureFormat(texture.format()));
+            }
+        }
+        let depth_view = aspect_view(inputs.depth, wgpu::TextureAspect::DepthOnly)?;
+        let history_depth_view = match &inputs.history {
+            Some(history) => Some(aspect_view(history.depth, wgpu::TextureAspect::DepthOnly)?),
+            None => None,
+        };
+
+        let iterations = self.iterations.max(1);
+        let format = output.format();
+        let mut keys = vec![("fs_atrous", format)];
+        if iterations > 1 {
+            keys.push(("fs_atrous", INTERMEDIATE_FORMAT));
+        }
+        if inputs.history.is_some() {
+            keys.push(("fs_temporal", INTERMEDIATE_FORMAT));
+        }
+        let mut pipelines = self
+            .pipelines
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        for key in keys {
+            pipelines
+                .entry(key)
+                .or_insert_with(|| self.create_pipeline(context, key));
+        }
+
+        context.diagnostics.record_pass(|| {
+            PassRecord::new(
+                "Denoise",
+                "fs_atrous",
+                vec![
+                    describe_texture("color", &inputs.color.texture),
+                    describe_texture("normal", &inputs.normal.texture),
+                    describe_texture("depth", &inputs.depth.texture),
+                    describe_texture("output", &output.texture),
+                ],
+            )
+        });
+        let mut source = &inputs.color.view;
+        let mut next = 0;
+        if let (Some(history), Some(history_depth_view)) = (&inputs.history, &history_depth_view) {
+            let params = self.params(context, range, 0);
+            let bind_group = context
+                .device
+                .create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Denoise Temporal Bind Group"),
+                    layout: &self.temporal_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: params.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::TextureView(source),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 3,
+                            resource: wgpu::BindingResource::TextureView(&depth_view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 4,
+                            resource: wgpu::BindingResource::TextureView(&history.motion.view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 5,
+                            resource: wgpu::BindingResource::TextureView(&history.color.view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 6,
+                            resource: wgpu::BindingResource::TextureView(history_depth_view),
+                        },
+                    ],
+                });
+            let target = &self.targets[next].view;
+            draw(
+                encoder,
+                "Denoise Temporal",
+                &pipelines[&("fs_temporal", INTERMEDIATE_FORMAT)],
+                &bind_group,
+                target,
+            );
+            source = target;
+            next ^= 1;
+        }
+        for iteration in 0..iterations {
+            let params = self.params(context, range, 1 << iteration);
+            let bind_group = context
+                .device
+                .create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Denoise A-Trous Bind Group"),
+                    layout: &self.atrous_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: params.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::TextureView(source),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 2,
+                            resource: wgpu::BindingResource::TextureView(&inputs.normal.view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 3,
+                            resource: wgpu::BindingResource::TextureView(&depth_view),
+                        },
+                    ],
+                });
+            let (target, target_format) = match iteration + 1 == iterations {
+                true => (&output.view, format),
+                false => (&self.targets[next].view, INTERMEDIATE_FORMAT),
+            };
+            draw(
+                encoder,
+                "Denoise A-Trous",
+                &pipelines[&("fs_atrous", target_format)],
+                &bind_group,
+                target,
+            );
+            source = target;
+            next ^= 1;
+        }
+        Ok(())
+    }
+
+    fn create_pipeline(
+        &self,
+        context: &Context,
+        (entry_point, format): (&'static str, wgpu::TextureFormat),
+    ) -> wgpu::RenderPipeline {
+        let layout = match entry_point {
+            "fs_temporal" => &self.temporal_pipeline_layout,
+            _ => &self.atrous_pipeline_layout,
+        };
+        context
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Denoise"),
+                layout: Some(layout),
+                vertex: wgpu::VertexState {
+                    module: &self.module,
+                    entry_point: "vs_main",
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    buffers: &[],
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &self.module,
+                    entry_point,
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    targets: &[Some(format.into())],
+                }),
+                multiview: None,
+            })
+    }
+
+    fn params(&self, context: &Context, range: &DepthRange, step: i32) -> wgpu::Buffer {
+        let mut flags = 0;
+        for (enabled, flag) in [
+            (range.reverse_z, REVERSE_Z),
+            (range.far.is_infinite(), INFINITE_FAR),
+            (range.logarithmic, LOGARITHMIC),
+        ] {
+            if enabled {
+                flags |= flag;
+            }
+        }
+        context
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Denoise Params"),
+                contents: bytemuck::bytes_of(&Params {
+                    near: range.near,
+                    far: range.far,
+                    flags,
+                    step,
+                    luminance_sensitivity: self.luminance_sensitivity,
+                    normal_power: self.normal_power,
+                    depth_sensitivity: self.depth_sensitivity,
+                    temporal_alpha: self.temporal_alpha,
+                }),
+                usage: wgpu::BufferUsages::UNIFORM,
+            })
+    }
+}
+
+fn draw(
+    encoder: &mut wgpu::CommandEncoder,
+    label: &str,
+    pipeline: &wgpu::RenderPipeline,
+    bind_group: &wgpu::BindGroup,
+    target: &wgpu::TextureView,
+) {
+    let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some(label),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: target,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+    pass.set_pipeline(pipeline);
+    pass.set_bind_group(0, bind_group, &[]);
+    pass.draw(0..3, 0..1);
+}
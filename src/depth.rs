@@ -0,0 +1,633 @@
+use super::context::Context;
+use super::diagnostics::{describe_texture, PassRecord};
+use super::error::Error;
+#[cfg(feature = "scene")]
+use super::scene::Camera;
+use super::texture::Texture;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use wgpu::util::DeviceExt;
+
+const SRGB_TARGET: u32 = 1;
+const REVERSE_Z: u32 = 2;
+const INFINITE_FAR: u32 = 4;
+const LOGARITHMIC: u32 = 8;
+/// Visible distance range for an infinite far plane, relative to `near`.
+const INFINITE_VISIBLE_RANGE: f32 = 1000.0;
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    near: f32,
+    far: f32,
+    visible_min: f32,
+    visible_max: f32,
+    colormap: u32,
+    flags: u32,
+    pixel: [u32; 2],
+}
+
+/// WGSL helpers for logarithmic depth, which keeps precision from
+/// centimeters to planetary distances. Write the result to
+/// `@builtin(frag_depth)` from the clip-space w the vertex shader passes
+/// along, and use the reversed variant with reverse-Z. `far` must be
+/// finite. Writing depth disables early depth testing.
+pub const LOGARITHMIC_DEPTH_WGSL: &str =
+    "fn logarithmic_depth(view_distance: f32, far: f32) -> f32 {
+    return log2(max(view_distance, 1e-6) + 1.0) / log2(far + 1.0);
+}
+
+fn reverse_logarithmic_depth(view_distance: f32, far: f32) -> f32 {
+    return 1.0 - logarithmic_depth(view_distance, far);
+}
+";
+
+/// How a perspective projection writes depth: from 0 at the near plane to
+/// 1 at the far plane, or from 1 to 0 with reverse-Z. `far` may be
+/// infinite, except with logarithmic depth.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DepthRange {
+    pub near: f32,
+    pub far: f32,
+    pub reverse_z: bool,
+    /// Depth written by the `LOGARITHMIC_DEPTH_WGSL` helpers.
+    pub logarithmic: bool,
+}
+
+impl DepthRange {
+    pub fn new(near: f32, far: f32) -> Self {
+        Self {
+            near,
+            far,
+            reverse_z: false,
+            logarithmic: false,
+        }
+    }
+
+    pub fn infinite(near: f32) -> Self {
+        Self::new(near, f32::INFINITY)
+    }
+
+    pub fn with_reverse_z(mut self, reverse_z: bool) -> Self {
+        self.reverse_z = reverse_z;
+        self
+    }
+
+    pub fn with_logarithmic(mut self, logarithmic: bool) -> Self {
+        self.logarithmic = logarithmic;
+        self
+    }
+
+    /// View distance of a depth buffer value.
+    pub fn linearize(&self, depth: f32) -> f32 {
+        let (near, far) = (self.near, self.far);
+        if self.logarithmic {
+            let depth = if self.reverse_z { 1.0 - depth } else { depth };
+            return (far + 1.0).powf(depth) - 1.0;
+        }
+        match (self.reverse_z, far.is_infinite()) {
+            (false, false) => near * far / (far - depth * (far - near)),
+            (true, false) => near * far / (near + depth * (far - near)),
+            (false, true) => near / (1.0 - depth),
+            (true, true) => near / depth,
+        }
+    }
+
+    /// Depth buffer value of a view distance.
+    pub fn depth(&self, distance: f32) -> f32 {
+        let (near, far) = (self.near, self.far);
+        if self.logarithmic {
+            let depth = (distance.max(1e-6) + 1.0).log2() / (far + 1.0).log2();
+            return if self.reverse_z { 1.0 - depth } else { depth };
+        }
+        match (self.reverse_z, far.is_infinite()) {
+            (false, false) => far * (distance - near) / (distance * (far - near)),
+            (true, false) => near * (far - distance) / (distance * (far - near)),
+            (false, true) => 1.0 - near / distance,
+            (true, true) => near / distance,
+        }
+    }
+}
+
+#[cfg(feature = "scene")]
+impl From<&Camera> for DepthRange {
+    fn from(camera: &Camera) -> Self {
+        let far = if camera.infinite_far {
+            f32::INFINITY
+        } else {
+            camera.far
+        };
+        Self::new(camera.near, far)
+            .with_reverse_z(camera.reverse_z)
+            .with_logarithmic(camera.logarithmic_depth)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Colormap {
+    Grayscale,
+    #[default]
+    Turbo,
+    Viridis,
+}
+
+impl Colormap {
+    pub(crate) fn index(self) -> u32 {
+        match self {
+            Self::Grayscale => 0,
+            Self::Turbo => 1,
+            Self::Viridis => 2,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum View {
+    Depth,
+    Normals,
+    Stencil,
+}
+
+impl View {
+    fn entry_point(self) -> &'static str {
+        match self {
+            Self::Depth => "fs_depth",
+            Self::Normals => "fs_normals",
+            Self::Stencil => "fs_stencil",
+        }
+    }
+}
+
+/// Debug views of depth, normal and stencil buffers drawn into any
+/// renderable color target, and depth readback at a pixel.
+pub struct DepthVisualizer {
+    module: wgpu::ShaderModule,
+    layouts: HashMap<View, (wgpu::BindGroupLayout, wgpu::PipelineLayout)>,
+    pipelines: Mutex<HashMap<(View, wgpu::TextureFormat), wgpu::RenderPipeline>>,
+    read_layout: wgpu::BindGroupLayout,
+    read_depth: wgpu::ComputePipeline,
+    pub colormap: Colormap,
+    /// View distances mapped to the ends of the colormap, the whole depth
+    /// range if `None`, or up to 1000 times `near` for an infinite far
+    /// plane.
+    pub visible_range: Option<[f32; 2]>,
+}
+
+impl DepthVisualizer {
+    pub fn new(context: &Context) -> Self {
+        let device = &context.device;
+        let source =
+            include_str!("shaders/colormap.wgsl").to_string() + include_str!("shaders/depth.wgsl");
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("depth.wgsl"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let entry = |binding, visibility, ty| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility,
+            ty,
+            count: None,
+        };
+        let params = |visibility| {
+            entry(
+                0,
+                visibility,
+                wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+            )
+        };
+        let texture = |binding, visibility, sample_type| {
+            entry(
+                binding,
+                visibility,
+                wgpu::BindingType::Texture {
+                    sample_type,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+            )
+        };
+        let layouts = [
+            (
+                View::Depth,
+                1,
+                wgpu::TextureSampleType::Float { filterable: false },
+            ),
+            (
+                View::Normals,
+                2,
+                wgpu::TextureSampleType::Float { filterable: false },
+            ),
+            (View::Stencil, 3, wgpu::TextureSampleType::Uint),
+        ]
+        .into_iter()
+        .map(|(view, binding, sample_type)| {
+            let fragment = wgpu::ShaderStages::FRAGMENT;
+            let bind_group_layout =
+                device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("Depth Visualizer Bind Group Layout"),
+                    entries: &[params(fragment), texture(binding, fragment, sample_type)],
+                });
+            let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Depth Visualizer Pipeline Layout"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+            (view, (bind_group_layout, pipeline_layout))
+        })
+        .collect();
+
+        let compute = wgpu::ShaderStages::COMPUTE;
+        let read_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Depth Readback Bind Group Layout"),
+            entries: &[
+                params(compute),
+                texture(
+                    1,
+                    compute,
+                    wgpu::TextureSampleType::Float { filterable: false },
+                ),
+                entry(
+                    4,
+                    compute,
+                    wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                ),
+            ],
+        });
+        let read_depth = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("read_depth"),
+            layout: Some(
+                &device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: Some("Depth Readback Pipeline Layout"),
+                    bind_group_layouts: &[&read_layout],
+                    push_constant_ranges: &[],
+                }),
+            ),
+            module: &module,
+            entry_point: "read_depth",
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        });
+
+        Self {
+            module,
+            layouts,
+            pipelines: Mutex::new(HashMap::new()),
+            read_layout,
+            read_depth,
+            colormap: Colormap::default(),
+            visible_range: None,
+        }
+    }
+
+    /// Draws the view distance of each depth texel of `depth` through the
+    /// colormap into `target`, resampling to the target size.
+    pub fn visualize_depth(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        depth: &Texture,
+        target: &Texture,
+        range: &DepthRange,
+    ) -> Result<(), Error> {
+        let view = aspect_view(depth, wgpu::TextureAspect::DepthOnly)?;
+        let far = if range.far.is_infinite() {
+            range.near * INFINITE_VISIBLE_RANGE
+        } else {
+            range.far
+        };
+        let [visible_min, visible_max] = self.visible_range.unwrap_or([range.near, far]);
+        let mut flags = 0;
+        for (enabled, flag) in [
+            (range.reverse_z, REVERSE_Z),
+            (range.far.is_infinite(), INFINITE_FAR),
+            (range.logarithmic, LOGARITHMIC),
+        ] {
+            if enabled {
+                flags |= flag;
+            }
+        }
+        let params = Params {
+            near: range.near,
+            far: range.far,
+            visible_min,
+            visible_max,
+            colormap: self.colormap.index(),
+            flags,
+            ..Default::default()
+        };
+        self.draw(context, encoder, View::Depth, params, &view, depth, target)
+    }
+
+    /// Draws the signed normals in the xyz of `normals` remapped from
+    /// [-1, 1] to colors into `target`.
+    pub fn visualize_normals(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        normals: &Texture,
+        target: &Texture,
+    ) -> Result<(), Error> {
+        if !matches!(
+            normals.format().sample_type(None, None),
+            Some(wgpu::TextureSampleType::Float { .. })
+        ) || normals.view_dimension() != wgpu::TextureViewDimension::D2
+        {
+            return Err(Error::UnsupportedTextureFormat(normals.format()));
+        }
+        let params = Params::default();
+        self.draw(
+            context,
+            encoder,
+            View::Normals,
+            params,
+            &normals.view,
+            normals,
+            target,
+        )
+    }
+
+    /// Draws each stencil reference value of `stencil` in its own color
+    /// into `target`, with zero in black.
+    pub fn visualize_stencil(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        stencil: &Texture,
+        target: &Texture,
+    ) -> Result<(), Error> {
+        let view = aspect_view(stencil, wgpu::TextureAspect::StencilOnly)?;
+        self.draw(
+            context,
+            encoder,
+            View::Stencil,
+            Params::default(),
+            &view,
+            stencil,
+            target,
+        )
+    }
+
+    /// Reads back the depth buffer value at `pixel`, waiting for the GPU.
+    /// Pass it to [`DepthRange::linearize`] for the view distance.
+    pub fn read_depth(
+        &self,
+        context: &Context,
+        depth: &Texture,
+        pixel: [u32; 2],
+    ) -> Result<f32, Error> {
+        let (width, height) = (depth.width(), depth.height());
+        if pixel[0] >= width || pixel[1] >= height {
+            return Err(Error::PixelOutOfBounds {
+                pixel,
+                width,
+                height,
+            });
+        }
+        let view = aspect_view(depth, wgpu::TextureAspect::DepthOnly)?;
+
+        let device = &context.device;
+        let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Depth Readback Params"),
+            contents: bytemuck::bytes_of(&Params {
+                pixel,
+                ..Default::default()
+            }),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let output = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Depth Readback"),
+            size: 4,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Depth Readback Bind Group"),
+            layout: &self.read_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: output.as_entire_binding(),
+                },
+            ],
+        });
+        context.diagnostics.record_pass(|| {
+            PassRecord::new(
+                "Depth Readback",
+                "read_depth",
+                vec![describe_texture("depth", &depth.texture)],
+            )
+        });
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Depth Readback Encoder"),
+        });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Depth Readback"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.read_depth);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(1, 1, 1);
+        }
+        context.submit(encoder);
+
+        let data = context.read_buffer(&output)?;
+        Ok(bytemuck::pod_read_unaligned(&data))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn draw(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        view: View,
+        mut params: Params,
+        source_view: &wgpu::TextureView,
+        source: &Texture,
+        target: &Texture,
+    ) -> Result<(), Error> {
+        if !target
+            .usage()
+            .contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+        {
+            return Err(Error::TextureCreationFailed);
+        }
+        let format = target.format();
+        if format.is_srgb() {
+            params.flags |= SRGB_TARGET;
+        }
+
+        let device = &context.device;
+        let (bind_group_layout, pipeline_layout) = &self.layouts[&view];
+        let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Depth Visualizer Params"),
+            contents: bytemuck::bytes_of(&params),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let binding = match view {
+            View::Depth => 1,
+            View::Normals => 2,
+            View::Stencil => 3,
+        };
+        let entries = [
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: params.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding,
+                resource: wgpu::BindingResource::TextureView(source_view),
+            },
+        ];
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Depth Visualizer Bind Group"),
+            layout: bind_group_layout,
+            entries: &entries,
+        });
+        context.diagnostics.record_pass(|| {
+            PassRecord::new(
+                "Depth Visualizer",
+                view.entry_point(),
+                vec![
+                    describe_texture("source", &source.texture),
+                    describe_texture("target", &target.texture),
+                ],
+            )
+        });
+
+        let mut pipelines = self
+            .pipelines
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let pipeline = pipelines.entry((view, format)).or_insert_with(|| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(view.entry_point()),
+                layout: Some(pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &self.module,
+                    entry_point: "vs_main",
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    buffers: &[],
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &self.module,
+                    entry_point: view.entry_point(),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    targets: &[Some(format.into())],
+                }),
+                multiview: None,
+            })
+        });
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Depth Visualizer"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &target.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+
+        Ok(())
+    }
+}
+
+impl Context {
+    /// Whether depth textures and the depth state helpers below use
+    /// reverse-Z, which spreads float depth precision evenly over distance
+    /// when the projection maps the near plane to 1.0.
+    pub fn reverse_z(&self) -> bool {
+        self.reverse_z
+    }
+
+    /// Affects depth textures created afterwards.
+    pub fn set_reverse_z(&mut self, reverse_z: bool) {
+        self.reverse_z = reverse_z;
+    }
+
+    /// The far plane's depth, to clear depth attachments to.
+    pub fn depth_clear_value(&self) -> f32 {
+        if self.reverse_z {
+            0.0
+        } else {
+            1.0
+        }
+    }
+
+    /// Passes fragments nearer than the stored depth.
+    pub fn depth_compare_function(&self) -> wgpu::CompareFunction {
+        if self.reverse_z {
+            wgpu::CompareFunction::GreaterEqual
+        } else {
+            wgpu::CompareFunction::LessEqual
+        }
+    }
+
+    /// Default depth test and write state for render pipelines.
+    pub fn depth_stencil_state(&self, format: wgpu::TextureFormat) -> wgpu::DepthStencilState {
+        wgpu::DepthStencilState {
+            format,
+            depth_write_enabled: true,
+            depth_compare: self.depth_compare_function(),
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }
+    }
+}
+
+/// A view of the depth or stencil aspect alone, which is all a shader can
+/// bind of a combined depth-stencil texture.
+pub(crate) fn aspect_view(
+    texture: &Texture,
+    aspect: wgpu::TextureAspect,
+) -> Result<wgpu::TextureView, Error> {
+    let format = texture.format();
+    let supported = match aspect {
+        wgpu::TextureAspect::DepthOnly => format.has_depth_aspect(),
+        _ => format.has_stencil_aspect(),
+    };
+    if !supported || texture.sample_count() > 1 || texture.dimension() != wgpu::TextureDimension::D2
+    {
+        return Err(Error::UnsupportedTextureFormat(format));
+    }
+    if !texture
+        .usage()
+        .contains(wgpu::TextureUsages::TEXTURE_BINDING)
+    {
+        return Err(Error::TextureCreationFailed);
+    }
+    Ok(texture.texture.create_view(&wgpu::TextureViewDescriptor {
+        label: Some("Depth Visualizer Aspect View"),
+        dimension: Some(wgpu::TextureViewDimension::D2),
+        aspect,
+        base_array_layer: 0,
+        array_layer_count: Some(1),
+        ..Default::default()
+    }))
+}
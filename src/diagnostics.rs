@@ -0,0 +1,200 @@
+use super::context::Context;
+use super::error::Error;
+use std::collections::VecDeque;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PassRecord {
+    pub name: String,
+    pub pipeline: Option<String>,
+    pub resources: Vec<String>,
+}
+
+impl PassRecord {
+    pub(crate) fn new(name: &str, pipeline: &str, resources: Vec<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            pipeline: Some(pipeline.to_string()),
+            resources,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SubmissionRecord {
+    /// Number of the submission since diagnostics were enabled.
+    pub index: u64,
+    pub passes: Vec<PassRecord>,
+}
+
+#[derive(Default)]
+struct DiagnosticState {
+    capacity: usize,
+    path: Option<PathBuf>,
+    submitted: u64,
+    recording: Vec<PassRecord>,
+    submissions: VecDeque<SubmissionRecord>,
+}
+
+/// Ring buffer of the passes recorded by the crate (and the application
+/// through `Context::record_pass`) in the last submissions. Disabled, and
+/// free apart from a lock per pass, until `Context::enable_diagnostics`.
+#[derive(Clone, Default)]
+pub(crate) struct Diagnostics(Arc<Mutex<DiagnosticState>>);
+
+impl Diagnostics {
+    fn state(&self) -> std::sync::MutexGuard<'_, DiagnosticState> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub(crate) fn record_pass(&self, pass: impl FnOnce() -> PassRecord) {
+        let mut state = self.state();
+        if state.capacity > 0 {
+            state.recording.push(pass());
+        }
+    }
+
+    pub(crate) fn submitted(&self) {
+        let mut state = self.state();
+        if state.capacity == 0 {
+            return;
+        }
+        let passes = std::mem::take(&mut state.recording);
+        let index = state.submitted;
+        state.submitted += 1;
+        if state.submissions.len() == state.capacity {
+            state.submissions.pop_front();
+        }
+        state
+            .submissions
+            .push_back(SubmissionRecord { index, passes });
+    }
+
+    fn submissions(&self) -> Vec<SubmissionRecord> {
+        self.state().submissions.iter().cloned().collect()
+    }
+
+    fn dump(&self, adapter: &wgpu::AdapterInfo, reason: &str) {
+        let path = self.state().path.clone();
+        if let Some(path) = path {
+            let report = DiagnosticReport {
+                adapter,
+                reason,
+                submissions: &self.submissions(),
+            };
+            // Nothing sensible to do if this fails while the device is lost.
+            let _ = std::fs::write(path, report.to_string());
+        }
+    }
+}
+
+struct DiagnosticReport<'a> {
+    adapter: &'a wgpu::AdapterInfo,
+    reason: &'a str,
+    submissions: &'a [SubmissionRecord],
+}
+
+impl fmt::Display for DiagnosticReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Adapter: {} ({:?})",
+            self.adapter.name, self.adapter.backend
+        )?;
+        writeln!(f, "Reason: {}", self.reason)?;
+        for submission in self.submissions {
+            writeln!(f, "Submission {}:", submission.index)?;
+            for pass in &submission.passes {
+                match &pass.pipeline {
+                    Some(pipeline) => writeln!(f, "  {} [{pipeline}]", pass.name)?,
+                    None => writeln!(f, "  {}", pass.name)?,
+                }
+                for resource in &pass.resources {
+                    writeln!(f, "    {resource}")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Context {
+    /// Keeps the passes of the last `submissions` submissions and writes
+    /// them to `path` on an uncaptured validation error or device loss.
+    ///
+    /// This replaces the device's uncaptured error handler; the installed
+    /// one still panics after writing the dump, like wgpu's default.
+    pub fn enable_diagnostics(&self, submissions: usize, path: impl AsRef<Path>) {
+        {
+            let mut state = self.diagnostics.state();
+            state.capacity = submissions.max(1);
+            state.path = Some(path.as_ref().to_path_buf());
+            while state.submissions.len() > state.capacity {
+                state.submissions.pop_front();
+            }
+        }
+
+        let adapter = self.adapter.get_info();
+        let (diagnostics, error_adapter) = (self.diagnostics.clone(), adapter.clone());
+        self.device.on_uncaptured_error(Box::new(move |error| {
+            diagnostics.dump(&error_adapter, &error.to_string());
+            panic!("wgpu error: {error}\n");
+        }));
+        let diagnostics = self.diagnostics.clone();
+        self.device
+            .set_device_lost_callback(move |reason, message| {
+                // Destroying or dropping the device and replacing this
+                // callback report a loss as well.
+                if reason == wgpu::DeviceLostReason::Unknown {
+                    diagnostics.dump(&adapter, &format!("device lost ({reason:?}): {message}"));
+                }
+            });
+    }
+
+    /// Adds a pass to the submission being recorded. `resources` describes
+    /// what the pass binds, e.g. `"texture 512x512 Rgba8Unorm"`.
+    pub fn record_pass(&self, name: &str, pipeline: Option<&str>, resources: &[&str]) {
+        self.diagnostics.record_pass(|| PassRecord {
+            name: name.to_string(),
+            pipeline: pipeline.map(str::to_string),
+            resources: resources.iter().map(|r| r.to_string()).collect(),
+        });
+    }
+
+    /// The recorded submissions, oldest first.
+    pub fn recorded_submissions(&self) -> Vec<SubmissionRecord> {
+        self.diagnostics.submissions()
+    }
+
+    /// Writes the recorded submissions to `path`, e.g. after a failed
+    /// error scope.
+    pub fn write_diagnostics(&self, path: impl AsRef<Path>, reason: &str) -> Result<(), Error> {
+        let report = DiagnosticReport {
+            adapter: &self.adapter.get_info(),
+            reason,
+            submissions: &self.diagnostics.submissions(),
+        };
+        std::fs::write(path, report.to_string())?;
+        Ok(())
+    }
+}
+
+pub(crate) fn describe_texture(name: &str, texture: &wgpu::Texture) -> String {
+    format!(
+        "{name}: texture {}x{}x{} {:?}",
+        texture.width(),
+        texture.height(),
+        texture.depth_or_array_layers(),
+        texture.format()
+    )
+}
+
+pub(crate) fn describe_buffer(name: &str, buffer: &wgpu::Buffer) -> String {
+    format!("{name}: buffer {} bytes", buffer.size())
+}
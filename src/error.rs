@@ -1,11 +1,106 @@
+use super::fft::FftShape;
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("requesting adapter failed")]
     RequestingAdapterFailed,
     #[error(transparent)]
     RequestingDeviceFailed(#[from] wgpu::RequestDeviceError),
+    #[cfg(feature = "image")]
     #[error(transparent)]
     ImageError(#[from] image::ImageError),
     #[error("texture creation failed")]
     TextureCreationFailed,
+    #[error("invalid texture: {0}")]
+    InvalidTexture(String),
+    #[error("unsupported FFT shape {0:?}")]
+    UnsupportedFftShape(FftShape),
+    #[error("buffer of {size} bytes is too small, {required} bytes required")]
+    BufferTooSmall { size: u64, required: u64 },
+    #[error("buffer offset {offset} is not a multiple of {alignment}")]
+    UnalignedBufferOffset { offset: u64, alignment: u64 },
+    #[error("buffer write of {size} bytes is not a multiple of {alignment}")]
+    UnalignedBufferSize { size: u64, alignment: u64 },
+    #[error("no workgroup size candidate fits the device limits")]
+    NoWorkgroupSizeCandidate,
+    #[error(transparent)]
+    BufferMappingFailed(#[from] wgpu::BufferAsyncError),
+    #[error("{name} of {limit} exceeded, {requested} requested")]
+    ExceedsDeviceLimit {
+        name: &'static str,
+        limit: u64,
+        requested: u64,
+    },
+    #[error("data of {actual} bytes does not match the {expected} bytes of the texture")]
+    DataSizeMismatch { expected: u64, actual: u64 },
+    #[error("unsupported texture format {0:?}")]
+    UnsupportedTextureFormat(wgpu::TextureFormat),
+    #[error("{format:?} does not support {sample_count} samples per pixel")]
+    UnsupportedSampleCount {
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    },
+    #[error("unsupported backend {0:?}")]
+    UnsupportedBackend(wgpu::Backend),
+    #[error("shader compilation failed: {0}")]
+    ShaderCompilationFailed(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[cfg(feature = "scene")]
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("invalid scene: {0}")]
+    InvalidScene(String),
+    #[error("invalid mesh: {0}")]
+    InvalidMesh(String),
+    #[error("seed {seed:?} is outside the {width}x{height} texture")]
+    SeedOutOfBounds {
+        seed: [u32; 2],
+        width: u32,
+        height: u32,
+    },
+    #[error("pixel {pixel:?} is outside the {width}x{height} texture")]
+    PixelOutOfBounds {
+        pixel: [u32; 2],
+        width: u32,
+        height: u32,
+    },
+    #[error("region of {extent:?} at {origin:?} is outside the {size:?} texture")]
+    RegionOutOfBounds {
+        origin: wgpu::Origin3d,
+        extent: wgpu::Extent3d,
+        size: wgpu::Extent3d,
+    },
+    #[error("{kind} {index} is out of range, the texture has {count}")]
+    SubresourceOutOfRange {
+        kind: &'static str,
+        index: u32,
+        count: u32,
+    },
+    #[error("texture size {actual:?} does not match {expected:?}")]
+    TextureSizeMismatch {
+        expected: wgpu::Extent3d,
+        actual: wgpu::Extent3d,
+    },
+    #[error("invalid animation: {0}")]
+    InvalidAnimation(String),
+    #[error("invalid heightmap: {0}")]
+    InvalidHeightmap(String),
+    #[error("invalid KTX2 file: {0}")]
+    InvalidKtx2(String),
+    #[error("invalid DDS file: {0}")]
+    InvalidDds(String),
+    #[error("invalid sampler: {0}")]
+    InvalidSampler(String),
+    #[error("invalid video frame: {0}")]
+    InvalidVideoFrame(String),
+    #[error("no room for a {width}x{height} image in the atlas")]
+    AtlasFull { width: u32, height: u32 },
+    #[error("no atlas entry {0}")]
+    UnknownAtlasEntry(usize),
+    #[error("operation cancelled")]
+    Cancelled,
+    #[cfg(feature = "geotiff")]
+    #[error(transparent)]
+    Tiff(#[from] tiff::TiffError),
 }
@@ -0,0 +1,116 @@
+use super::context::Context;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Signaled once the GPU has finished the work submitted before it was
+/// created, for knowing when readback buffers can be mapped or resources
+/// reused without blocking on `device.poll` loops. Clones share the signal.
+#[derive(Clone, Debug)]
+pub struct GpuFence {
+    index: wgpu::SubmissionIndex,
+    signaled: Arc<AtomicBool>,
+}
+
+impl GpuFence {
+    /// Whether the fenced work has finished, polling the device without
+    /// blocking.
+    pub fn is_signaled(&self, context: &Context) -> bool {
+        if !self.signaled.load(Ordering::Acquire) {
+            context.device.poll(wgpu::Maintain::Poll);
+        }
+        self.signaled.load(Ordering::Acquire)
+    }
+
+    /// Blocks until the fenced work has finished. Does not block on the
+    /// web, where `is_signaled` has to be checked from the event loop
+    /// instead.
+    pub fn wait(&self, context: &Context) {
+        if !self.signaled.load(Ordering::Acquire) {
+            context
+                .device
+                .poll(wgpu::Maintain::WaitForSubmissionIndex(self.index.clone()));
+        }
+    }
+
+    /// The submission the fence waits for.
+    pub fn submission_index(&self) -> &wgpu::SubmissionIndex {
+        &self.index
+    }
+}
+
+impl Context {
+    /// A fence signaled once everything submitted so far has finished.
+    pub fn fence(&self) -> GpuFence {
+        let signaled = Arc::new(AtomicBool::new(false));
+        let signal = signaled.clone();
+        self.queue
+            .on_submitted_work_done(move || signal.store(true, Ordering::Release));
+        // An empty submission returns the index of the latest one.
+        let index = self.queue.submit([]);
+        GpuFence { index, signaled }
+    }
+}
+
+/// Caps the number of frames the CPU records ahead of the GPU, so that
+/// per-frame resources can be reused round-robin and latency stays bounded.
+///
+/// Call `begin_frame` before recording a frame, which blocks until fewer
+/// than `max_frames_in_flight` frames are in flight, and `end_frame` after
+/// its last submission. The returned fence tells when the frame's
+/// resources are free again.
+#[derive(Debug)]
+pub struct FrameLimiter {
+    max_frames_in_flight: usize,
+    in_flight: VecDeque<GpuFence>,
+    frame: u64,
+}
+
+impl FrameLimiter {
+    /// A limiter of at least one frame in flight.
+    pub fn new(max_frames_in_flight: usize) -> Self {
+        Self {
+            max_frames_in_flight: max_frames_in_flight.max(1),
+            in_flight: VecDeque::new(),
+            frame: 0,
+        }
+    }
+
+    pub fn max_frames_in_flight(&self) -> usize {
+        self.max_frames_in_flight
+    }
+
+    /// Frames ended whose work has not been seen to finish yet.
+    pub fn frames_in_flight(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    /// Drops the finished frames and waits for the oldest ones until a new
+    /// frame can start. Returns the index of the slot the frame can use for
+    /// its per-frame resources, cycling through `0..max_frames_in_flight`.
+    pub fn begin_frame(&mut self, context: &Context) -> usize {
+        self.in_flight.retain(|fence| !fence.is_signaled(context));
+        while self.in_flight.len() >= self.max_frames_in_flight {
+            if let Some(fence) = self.in_flight.pop_front() {
+                fence.wait(context);
+            }
+        }
+        (self.frame % self.max_frames_in_flight as u64) as usize
+    }
+
+    /// Ends the frame after its submissions, returning its fence.
+    pub fn end_frame(&mut self, context: &Context) -> GpuFence {
+        let fence = context.fence();
+        self.in_flight.push_back(fence.clone());
+        self.frame += 1;
+        fence
+    }
+
+    /// Waits for all frames in flight.
+    pub fn wait_idle(&mut self, context: &Context) {
+        if let Some(fence) = self.in_flight.back() {
+            fence.wait(context);
+        }
+        self.in_flight.clear();
+    }
+}
@@ -0,0 +1,289 @@
+//! C interface, see `include/wgpu_tools.h`. Objects are returned as opaque
+//! pointers owned by the caller and released with the matching `_destroy`
+//! function. Failures return null (or a negative value) and leave a message
+//! for `wgpu_tools_last_error`. Panics are caught at the boundary and
+//! reported the same way.
+
+use super::context::Context;
+use super::error::Error;
+#[cfg(feature = "image")]
+use super::format::ColorSpace;
+use super::format::ImageLayout;
+use super::texture::Texture;
+use std::cell::RefCell;
+use std::ffi::{c_char, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: impl ToString) {
+    let message = CString::new(message.to_string().replace('\0', " ")).ok();
+    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+}
+
+/// Runs `body`, turning a panic into `failed` and a message, as unwinding
+/// across the C ABI is undefined behaviour.
+fn guard<T>(failed: T, body: impl FnOnce() -> T) -> T {
+    catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        set_last_error(format!("panic: {message}"));
+        failed
+    })
+}
+
+fn into_raw<T>(result: Result<T, Error>) -> *mut T {
+    match result {
+        Ok(value) => Box::into_raw(Box::new(value)),
+        Err(error) => {
+            set_last_error(error);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+fn texture_format(format: u32) -> Option<wgpu::TextureFormat> {
+    Some(match format {
+        0 => wgpu::TextureFormat::Rgba8Unorm,
+        1 => wgpu::TextureFormat::Rgba8UnormSrgb,
+        2 => wgpu::TextureFormat::Bgra8Unorm,
+        3 => wgpu::TextureFormat::Bgra8UnormSrgb,
+        4 => wgpu::TextureFormat::Rgba16Float,
+        5 => wgpu::TextureFormat::Rgba32Float,
+        _ => return None,
+    })
+}
+
+/// The message of the last failed call on this thread, or null. Valid until
+/// the next call into the library.
+#[no_mangle]
+pub extern "C" fn wgpu_tools_last_error() -> *const c_char {
+    guard(std::ptr::null(), || {
+        LAST_ERROR.with(|last| {
+            last.borrow()
+                .as_ref()
+                .map_or(std::ptr::null(), |message| message.as_ptr())
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn wgpu_tools_context_create() -> *mut Context {
+    guard(std::ptr::null_mut(), || {
+        into_raw(pollster::block_on(Context::default()))
+    })
+}
+
+/// # Safety
+///
+/// `context` must be null or come from `wgpu_tools_context_create`, and all
+/// textures created with it must be destroyed first.
+#[no_mangle]
+pub unsafe extern "C" fn wgpu_tools_context_destroy(context: *mut Context) {
+    guard((), || {
+        if !context.is_null() {
+            drop(unsafe { Box::from_raw(context) });
+        }
+    })
+}
+
+/// Creates a texture from tightly packed pixels of `format`.
+///
+/// # Safety
+///
+/// `context` must be a live context and `data` must point to `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn wgpu_tools_texture_create(
+    context: *const Context,
+    data: *const u8,
+    len: usize,
+    width: u32,
+    height: u32,
+    format: u32,
+) -> *mut Texture {
+    guard(std::ptr::null_mut(), || {
+        let (Some(context), false) = (unsafe { context.as_ref() }, data.is_null()) else {
+            set_last_error("null argument");
+            return std::ptr::null_mut();
+        };
+        let Some(format) = texture_format(format) else {
+            set_last_error(format!("unknown texture format {format}"));
+            return std::ptr::null_mut();
+        };
+        let data = unsafe { std::slice::from_raw_parts(data, len) };
+        into_raw(context.texture_with_data(data, &width, &height, &format, None))
+    })
+}
+
+/// Loads an image file as an RGBA8 texture.
+///
+/// # Safety
+///
+/// `context` must be a live context and `path` a NUL-terminated string.
+#[cfg(feature = "image")]
+#[no_mangle]
+pub unsafe extern "C" fn wgpu_tools_texture_load(
+    context: *const Context,
+    path: *const c_char,
+    srgb: bool,
+) -> *mut Texture {
+    guard(std::ptr::null_mut(), || {
+        let (Some(context), false) = (unsafe { context.as_ref() }, path.is_null()) else {
+            set_last_error("null argument");
+            return std::ptr::null_mut();
+        };
+        let path = unsafe { std::ffi::CStr::from_ptr(path) }.to_string_lossy();
+        let color_space = match srgb {
+            true => ColorSpace::Srgb,
+            false => ColorSpace::Linear,
+        };
+        into_raw(
+            image::open(path.as_ref())
+                .map_err(Error::from)
+                .and_then(|image| {
+                    context.color_texture_from_image(&image, color_space, Some(&path))
+                }),
+        )
+    })
+}
+
+/// Creates a texture that can be rendered to and read back.
+///
+/// # Safety
+///
+/// `context` must be a live context.
+#[no_mangle]
+pub unsafe extern "C" fn wgpu_tools_render_target_create(
+    context: *const Context,
+    width: u32,
+    height: u32,
+    format: u32,
+) -> *mut Texture {
+    guard(std::ptr::null_mut(), || {
+        let Some(context) = (unsafe { context.as_ref() }) else {
+            set_last_error("null argument");
+            return std::ptr::null_mut();
+        };
+        let Some(format) = texture_format(format) else {
+            set_last_error(format!("unknown texture format {format}"));
+            return std::ptr::null_mut();
+        };
+        into_raw(context.render_target(&width, &height, &format, None))
+    })
+}
+
+/// # Safety
+///
+/// `texture` must be a live texture.
+#[no_mangle]
+pub unsafe extern "C" fn wgpu_tools_texture_width(texture: *const Texture) -> u32 {
+    guard(0, || {
+        unsafe { texture.as_ref() }.map_or(0, |texture| texture.width())
+    })
+}
+
+/// # Safety
+///
+/// `texture` must be a live texture.
+#[no_mangle]
+pub unsafe extern "C" fn wgpu_tools_texture_height(texture: *const Texture) -> u32 {
+    guard(0, || {
+        unsafe { texture.as_ref() }.map_or(0, |texture| texture.height())
+    })
+}
+
+/// The number of bytes `wgpu_tools_texture_read` writes.
+///
+/// # Safety
+///
+/// `texture` must be a live texture.
+#[no_mangle]
+pub unsafe extern "C" fn wgpu_tools_texture_byte_size(texture: *const Texture) -> usize {
+    guard(0, || {
+        unsafe { texture.as_ref() }.map_or(0, |texture| {
+            let size = wgpu::Extent3d {
+                depth_or_array_layers: 1,
+                ..texture.size()
+            };
+            ImageLayout::new(texture.format(), size)
+                .map_or(0, |layout| layout.bytes_per_image() as usize)
+        })
+    })
+}
+
+/// Reads the texture back into `out` with tightly packed rows. Returns the
+/// number of bytes written, or -1 on failure.
+///
+/// # Safety
+///
+/// `context` and `texture` must be live and `out` must point to `len`
+/// writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn wgpu_tools_texture_read(
+    context: *const Context,
+    texture: *const Texture,
+    out: *mut u8,
+    len: usize,
+) -> i64 {
+    guard(-1, || {
+        let (Some(context), Some(texture), false) = (
+            unsafe { context.as_ref() },
+            unsafe { texture.as_ref() },
+            out.is_null(),
+        ) else {
+            set_last_error("null argument");
+            return -1;
+        };
+        let data = match context.read_texture(texture) {
+            Ok(data) => data,
+            Err(error) => {
+                set_last_error(error);
+                return -1;
+            }
+        };
+        if data.len() > len {
+            set_last_error(format!(
+                "output of {len} bytes is too small, {} bytes required",
+                data.len()
+            ));
+            return -1;
+        }
+        unsafe { std::ptr::copy_nonoverlapping(data.as_ptr(), out, data.len()) };
+        data.len() as i64
+    })
+}
+
+/// # Safety
+///
+/// `texture` must be null or a texture created by this library.
+#[no_mangle]
+pub unsafe extern "C" fn wgpu_tools_texture_destroy(texture: *mut Texture) {
+    guard((), || {
+        if !texture.is_null() {
+            drop(unsafe { Box::from_raw(texture) });
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    #[test]
+    fn panics_become_errors() {
+        assert_eq!(guard(-1, || panic!("validation failed")), -1);
+        let message = unsafe { CStr::from_ptr(wgpu_tools_last_error()) };
+        assert_eq!(message.to_str().unwrap(), "panic: validation failed");
+        let texture =
+            unsafe { wgpu_tools_texture_create(std::ptr::null(), std::ptr::null(), 0, 1, 1, 0) };
+        assert!(texture.is_null());
+        let message = unsafe { CStr::from_ptr(wgpu_tools_last_error()) };
+        assert_eq!(message.to_str().unwrap(), "null argument");
+    }
+}
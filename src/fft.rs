@@ -0,0 +1,682 @@
+use super::context::Context;
+use super::diagnostics::{describe_buffer, describe_texture, PassRecord};
+use super::error::Error;
+use super::texture::Texture;
+use wgpu::util::DeviceExt;
+
+const WORKGROUP_SIZE: u32 = 64;
+const TEXTURE_WORKGROUP_SIZE: u32 = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FftDirection {
+    Forward,
+    Inverse,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FftShape {
+    D1 { len: u32, batch: u32 },
+    D2 { width: u32, height: u32 },
+}
+
+impl FftShape {
+    pub fn element_count(&self) -> u64 {
+        match *self {
+            FftShape::D1 { len, batch } => len as u64 * batch as u64,
+            FftShape::D2 { width, height } => width as u64 * height as u64,
+        }
+    }
+
+    pub fn buffer_size(&self) -> u64 {
+        self.element_count() * std::mem::size_of::<[f32; 2]>() as u64
+    }
+
+    fn axes(&self) -> Vec<Axis> {
+        match *self {
+            FftShape::D1 { len, batch } => vec![Axis {
+                len,
+                batch,
+                element_stride: 1,
+                batch_stride: len,
+            }],
+            FftShape::D2 { width, height } => vec![
+                Axis {
+                    len: width,
+                    batch: height,
+                    element_stride: 1,
+                    batch_stride: width,
+                },
+                Axis {
+                    len: height,
+                    batch: width,
+                    element_stride: width,
+                    batch_stride: 1,
+                },
+            ],
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    len: u32,
+    stride: u32,
+    element_stride: u32,
+    batch_stride: u32,
+    batch: u32,
+    sign: f32,
+    scale: f32,
+    channel: u32,
+}
+
+struct Axis {
+    len: u32,
+    batch: u32,
+    element_stride: u32,
+    batch_stride: u32,
+}
+
+struct Stage {
+    radix: u32,
+    offset: u32,
+    workgroups: (u32, u32),
+}
+
+pub struct Fft {
+    shape: FftShape,
+    radix2: wgpu::ComputePipeline,
+    radix4: wgpu::ComputePipeline,
+    pack_real: wgpu::ComputePipeline,
+    unpack_real: wgpu::ComputePipeline,
+    load_texture: wgpu::ComputePipeline,
+    store_texture: wgpu::ComputePipeline,
+    stage_layout: wgpu::BindGroupLayout,
+    pack_layout: wgpu::BindGroupLayout,
+    unpack_layout: wgpu::BindGroupLayout,
+    load_layout: wgpu::BindGroupLayout,
+    store_layout: wgpu::BindGroupLayout,
+    params: wgpu::Buffer,
+    forward: Vec<Stage>,
+    inverse: Vec<Stage>,
+    scratch: wgpu::Buffer,
+}
+
+impl Fft {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(context), err)
+    )]
+    pub fn new(context: &Context, shape: FftShape) -> Result<Self, Error> {
+        let max_workgroups = context.device.limits().max_compute_workgroups_per_dimension;
+        let axes = shape.axes();
+        for axis in &axes {
+            if axis.len < 2 || !axis.len.is_power_of_two() || axis.batch > max_workgroups {
+                return Err(Error::UnsupportedFftShape(shape));
+            }
+        }
+
+        context.check_buffer_size(shape.buffer_size())?;
+        context.check_binding_size(shape.buffer_size(), true)?;
+
+        let device = &context.device;
+        let module = device.create_shader_module(wgpu::include_wgsl!("shaders/fft.wgsl"));
+
+        let stage_layout = bind_group_layout(
+            context,
+            "FFT Stage Bind Group Layout",
+            &[(1, storage_entry(true)), (2, storage_entry(false))],
+        );
+        let pack_layout = bind_group_layout(
+            context,
+            "FFT Pack Bind Group Layout",
+            &[(2, storage_entry(false)), (3, storage_entry(false))],
+        );
+        let unpack_layout = bind_group_layout(
+            context,
+            "FFT Unpack Bind Group Layout",
+            &[(1, storage_entry(true)), (3, storage_entry(false))],
+        );
+        let load_layout = bind_group_layout(
+            context,
+            "FFT Load Texture Bind Group Layout",
+            &[
+                (2, storage_entry(false)),
+                (
+                    4,
+                    wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                ),
+            ],
+        );
+        let store_layout = bind_group_layout(
+            context,
+            "FFT Store Texture Bind Group Layout",
+            &[
+                (1, storage_entry(true)),
+                (
+                    5,
+                    wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: wgpu::TextureFormat::Rgba32Float,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                ),
+            ],
+        );
+
+        let pipeline = |layout: &wgpu::BindGroupLayout, entry_point: &str| {
+            let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("FFT Pipeline Layout"),
+                bind_group_layouts: &[layout],
+                push_constant_ranges: &[],
+            });
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &module,
+                entry_point,
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            })
+        };
+
+        let alignment = device.limits().min_uniform_buffer_offset_alignment as usize;
+        let mut params = Vec::new();
+        let forward = plan(&axes, FftDirection::Forward, alignment, &mut params);
+        let inverse = plan(&axes, FftDirection::Inverse, alignment, &mut params);
+        let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("FFT Params"),
+            contents: &params,
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let scratch = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("FFT Scratch"),
+            size: shape.buffer_size(),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        Ok(Self {
+            shape,
+            radix2: pipeline(&stage_layout, "radix2"),
+            radix4: pipeline(&stage_layout, "radix4"),
+            pack_real: pipeline(&pack_layout, "pack_real"),
+            unpack_real: pipeline(&unpack_layout, "unpack_real"),
+            load_texture: pipeline(&load_layout, "load_texture"),
+            store_texture: pipeline(&store_layout, "store_texture"),
+            stage_layout,
+            pack_layout,
+            unpack_layout,
+            load_layout,
+            store_layout,
+            params,
+            forward,
+            inverse,
+            scratch,
+        })
+    }
+
+    pub fn shape(&self) -> FftShape {
+        self.shape
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "trace",
+            skip(self, context, encoder, buffer),
+            fields(shape = ?self.shape),
+            err
+        )
+    )]
+    pub fn process(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        buffer: &wgpu::Buffer,
+        direction: FftDirection,
+    ) -> Result<(), Error> {
+        self.check_buffer(buffer, self.shape.buffer_size())?;
+
+        let bind_group = |src: &wgpu::Buffer, dst: &wgpu::Buffer| {
+            context
+                .device
+                .create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("FFT Stage Bind Group"),
+                    layout: &self.stage_layout,
+                    entries: &[
+                        self.params_entry(),
+                        buffer_entry(1, src),
+                        buffer_entry(2, dst),
+                    ],
+                })
+        };
+        let bind_groups = [
+            bind_group(buffer, &self.scratch),
+            bind_group(&self.scratch, buffer),
+        ];
+
+        let stages = match direction {
+            FftDirection::Forward => &self.forward,
+            FftDirection::Inverse => &self.inverse,
+        };
+
+        context.diagnostics.record_pass(|| {
+            let radix = if stages.iter().any(|stage| stage.radix == 4) {
+                "radix4"
+            } else {
+                "radix2"
+            };
+            PassRecord::new(
+                "FFT",
+                radix,
+                vec![
+                    describe_buffer("data", buffer),
+                    describe_buffer("scratch", &self.scratch),
+                ],
+            )
+        });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("FFT"),
+                timestamp_writes: None,
+            });
+            for (index, stage) in stages.iter().enumerate() {
+                let pipeline = match stage.radix {
+                    4 => &self.radix4,
+                    _ => &self.radix2,
+                };
+                pass.set_pipeline(pipeline);
+                pass.set_bind_group(0, &bind_groups[index % 2], &[stage.offset]);
+                pass.dispatch_workgroups(stage.workgroups.0, stage.workgroups.1, 1);
+            }
+        }
+
+        if stages.len() % 2 == 1 {
+            encoder.copy_buffer_to_buffer(&self.scratch, 0, buffer, 0, self.shape.buffer_size());
+        }
+
+        Ok(())
+    }
+
+    pub fn forward_real(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        real_input: &wgpu::Buffer,
+        complex_output: &wgpu::Buffer,
+    ) -> Result<(), Error> {
+        self.check_buffer(real_input, self.real_buffer_size())?;
+        self.check_buffer(complex_output, self.shape.buffer_size())?;
+
+        let bind_group = context
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("FFT Pack Bind Group"),
+                layout: &self.pack_layout,
+                entries: &[
+                    self.params_entry(),
+                    buffer_entry(2, complex_output),
+                    buffer_entry(3, real_input),
+                ],
+            });
+        context.diagnostics.record_pass(|| {
+            PassRecord::new(
+                "FFT Real Conversion",
+                "pack_real",
+                vec![
+                    describe_buffer("real", real_input),
+                    describe_buffer("complex", complex_output),
+                ],
+            )
+        });
+        self.dispatch_linear(encoder, &self.pack_real, &bind_group);
+
+        self.process(context, encoder, complex_output, FftDirection::Forward)
+    }
+
+    /// Inverse transform of `complex_input` into the real parts in
+    /// `real_output`. The transform runs in place, so `complex_input` holds
+    /// the complex inverse afterwards; copy it first to keep the spectrum.
+    pub fn inverse_real(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        complex_input: &wgpu::Buffer,
+        real_output: &wgpu::Buffer,
+    ) -> Result<(), Error> {
+        self.check_buffer(real_output, self.real_buffer_size())?;
+        self.process(context, encoder, complex_input, FftDirection::Inverse)?;
+
+        let bind_group = context
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("FFT Unpack Bind Group"),
+                layout: &self.unpack_layout,
+                entries: &[
+                    self.params_entry(),
+                    buffer_entry(1, complex_input),
+                    buffer_entry(3, real_output),
+                ],
+            });
+        context.diagnostics.record_pass(|| {
+            PassRecord::new(
+                "FFT Real Conversion",
+                "unpack_real",
+                vec![
+                    describe_buffer("complex", complex_input),
+                    describe_buffer("real", real_output),
+                ],
+            )
+        });
+        self.dispatch_linear(encoder, &self.unpack_real, &bind_group);
+
+        Ok(())
+    }
+
+    pub fn load_texture(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &Texture,
+        channel: u32,
+        complex_output: &wgpu::Buffer,
+    ) -> Result<(), Error> {
+        self.check_texture(texture)?;
+        self.check_buffer(complex_output, self.shape.buffer_size())?;
+
+        let params = context
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("FFT Load Texture Params"),
+                contents: bytemuck::bytes_of(&Params {
+                    channel: channel.min(3),
+                    ..Default::default()
+                }),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+        let bind_group = context
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("FFT Load Texture Bind Group"),
+                layout: &self.load_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                            buffer: &params,
+                            offset: 0,
+                            size: wgpu::BufferSize::new(std::mem::size_of::<Params>() as u64),
+                        }),
+                    },
+                    buffer_entry(2, complex_output),
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: wgpu::BindingResource::TextureView(&texture.view),
+                    },
+                ],
+            });
+        context.diagnostics.record_pass(|| {
+            PassRecord::new(
+                "FFT Texture Conversion",
+                "load_texture",
+                vec![
+                    describe_texture("texture", &texture.texture),
+                    describe_buffer("complex", complex_output),
+                ],
+            )
+        });
+        self.dispatch_texture(encoder, &self.load_texture, &bind_group, 0);
+
+        Ok(())
+    }
+
+    /// Writes the 2D signal in `complex_input` to a 2D `Rgba32Float`
+    /// texture with `STORAGE_BINDING` usage.
+    pub fn store_texture(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        complex_input: &wgpu::Buffer,
+        texture: &Texture,
+    ) -> Result<(), Error> {
+        if texture.format() != wgpu::TextureFormat::Rgba32Float {
+            return Err(Error::UnsupportedTextureFormat(texture.format()));
+        }
+        if !texture
+            .usage()
+            .contains(wgpu::TextureUsages::STORAGE_BINDING)
+        {
+            return Err(Error::InvalidTexture(
+                "FFT output textures need STORAGE_BINDING usage".into(),
+            ));
+        }
+        if texture.view_dimension() != wgpu::TextureViewDimension::D2 {
+            return Err(Error::InvalidTexture(format!(
+                "FFT output textures need a D2 view, not {:?}",
+                texture.view_dimension()
+            )));
+        }
+        self.check_texture(texture)?;
+        self.check_buffer(complex_input, self.shape.buffer_size())?;
+
+        let bind_group = context
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("FFT Store Texture Bind Group"),
+                layout: &self.store_layout,
+                entries: &[
+                    self.params_entry(),
+                    buffer_entry(1, complex_input),
+                    wgpu::BindGroupEntry {
+                        binding: 5,
+                        resource: wgpu::BindingResource::TextureView(&texture.view),
+                    },
+                ],
+            });
+        context.diagnostics.record_pass(|| {
+            PassRecord::new(
+                "FFT Texture Conversion",
+                "store_texture",
+                vec![
+                    describe_buffer("complex", complex_input),
+                    describe_texture("texture", &texture.texture),
+                ],
+            )
+        });
+        self.dispatch_texture(
+            encoder,
+            &self.store_texture,
+            &bind_group,
+            self.forward[0].offset,
+        );
+
+        Ok(())
+    }
+
+    fn real_buffer_size(&self) -> u64 {
+        self.shape.element_count() * std::mem::size_of::<f32>() as u64
+    }
+
+    fn params_entry(&self) -> wgpu::BindGroupEntry<'_> {
+        wgpu::BindGroupEntry {
+            binding: 0,
+            resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                buffer: &self.params,
+                offset: 0,
+                size: wgpu::BufferSize::new(std::mem::size_of::<Params>() as u64),
+            }),
+        }
+    }
+
+    fn check_buffer(&self, buffer: &wgpu::Buffer, required: u64) -> Result<(), Error> {
+        if buffer.size() < required {
+            return Err(Error::BufferTooSmall {
+                size: buffer.size(),
+                required,
+            });
+        }
+        Ok(())
+    }
+
+    fn check_texture(&self, texture: &Texture) -> Result<(), Error> {
+        let size = texture.size();
+        match self.shape {
+            FftShape::D2 { width, height } if size.width == width && size.height == height => {
+                Ok(())
+            }
+            _ => Err(Error::UnsupportedFftShape(self.shape)),
+        }
+    }
+
+    fn dispatch_linear(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        pipeline: &wgpu::ComputePipeline,
+        bind_group: &wgpu::BindGroup,
+    ) {
+        // The first forward stage describes the whole signal (len * batch),
+        // which is all the conversion kernels need.
+        let axis = &self.shape.axes()[0];
+        let groups = (axis.len as u64 * axis.batch as u64).div_ceil(WORKGROUP_SIZE as u64);
+        let x = groups.min(u16::MAX as u64) as u32;
+        let y = groups.div_ceil(x as u64) as u32;
+
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("FFT Real Conversion"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, bind_group, &[self.forward[0].offset]);
+        pass.dispatch_workgroups(x, y, 1);
+    }
+
+    fn dispatch_texture(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        pipeline: &wgpu::ComputePipeline,
+        bind_group: &wgpu::BindGroup,
+        offset: u32,
+    ) {
+        let FftShape::D2 { width, height } = self.shape else {
+            return;
+        };
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("FFT Texture Conversion"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, bind_group, &[offset]);
+        pass.dispatch_workgroups(
+            width.div_ceil(TEXTURE_WORKGROUP_SIZE),
+            height.div_ceil(TEXTURE_WORKGROUP_SIZE),
+            1,
+        );
+    }
+}
+
+fn plan(
+    axes: &[Axis],
+    direction: FftDirection,
+    alignment: usize,
+    params: &mut Vec<u8>,
+) -> Vec<Stage> {
+    let sign = match direction {
+        FftDirection::Forward => -1.0,
+        FftDirection::Inverse => 1.0,
+    };
+
+    let mut stages = Vec::new();
+    for axis in axes {
+        let log2 = axis.len.trailing_zeros();
+        let mut radices = vec![4; (log2 / 2) as usize];
+        if log2 % 2 == 1 {
+            radices.push(2);
+        }
+
+        let mut stride = 1;
+        for (index, radix) in radices.iter().enumerate() {
+            let last = index + 1 == radices.len();
+            let scale = match direction {
+                FftDirection::Inverse if last => 1.0 / axis.len as f32,
+                _ => 1.0,
+            };
+
+            let offset = params.len();
+            params.extend_from_slice(bytemuck::bytes_of(&Params {
+                len: axis.len,
+                stride,
+                element_stride: axis.element_stride,
+                batch_stride: axis.batch_stride,
+                batch: axis.batch,
+                sign,
+                scale,
+                channel: 0,
+            }));
+            params.resize(offset + alignment.max(std::mem::size_of::<Params>()), 0);
+
+            stages.push(Stage {
+                radix: *radix,
+                offset: offset as u32,
+                workgroups: ((axis.len / radix).div_ceil(WORKGROUP_SIZE), axis.batch),
+            });
+            stride *= radix;
+        }
+    }
+    stages
+}
+
+fn storage_entry(read_only: bool) -> wgpu::BindingType {
+    wgpu::BindingType::Buffer {
+        ty: wgpu::BufferBindingType::Storage { read_only },
+        has_dynamic_offset: false,
+        min_binding_size: None,
+    }
+}
+
+fn bind_group_layout(
+    context: &Context,
+    label: &str,
+    entries: &[(u32, wgpu::BindingType)],
+) -> wgpu::BindGroupLayout {
+    let params = wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: true,
+            min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<Params>() as u64),
+        },
+        count: None,
+    };
+    let entries: Vec<_> = std::iter::once(params)
+        .chain(
+            entries
+                .iter()
+                .map(|(binding, ty)| wgpu::BindGroupLayoutEntry {
+                    binding: *binding,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: *ty,
+                    count: None,
+                }),
+        )
+        .collect();
+
+    context
+        .device
+        .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some(label),
+            entries: &entries,
+        })
+}
+
+fn buffer_entry(binding: u32, buffer: &wgpu::Buffer) -> wgpu::BindGroupEntry<'_> {
+    wgpu::BindGroupEntry {
+        binding,
+        resource: buffer.as_entire_binding(),
+    }
+}
@@ -0,0 +1,153 @@
+use super::blit::BlitPipeline;
+use super::context::Context;
+use super::error::Error;
+use super::texture::Texture;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScalingMode {
+    /// Largest whole-number scale that fits the window; falls back to
+    /// `AspectFit` when the window is smaller than the internal resolution.
+    Integer,
+    AspectFit,
+    Stretch,
+}
+
+impl ScalingMode {
+    /// The rectangle (x, y, width, height), in target pixels, that a
+    /// `width` x `height` image is scaled into, centered in the target.
+    pub fn viewport(
+        &self,
+        width: u32,
+        height: u32,
+        target_width: u32,
+        target_height: u32,
+    ) -> [f32; 4] {
+        let (width, height) = (width as f32, height as f32);
+        let (target_width, target_height) = (target_width as f32, target_height as f32);
+        let fit = (target_width / width).min(target_height / height);
+
+        let (scale_x, scale_y) = match self {
+            ScalingMode::Stretch => (target_width / width, target_height / height),
+            ScalingMode::Integer if fit >= 1.0 => (fit.floor(), fit.floor()),
+            _ => (fit, fit),
+        };
+        let (scaled_width, scaled_height) = (width * scale_x, height * scale_y);
+        [
+            ((target_width - scaled_width) / 2.0).floor(),
+            ((target_height - scaled_height) / 2.0).floor(),
+            scaled_width,
+            scaled_height,
+        ]
+    }
+}
+
+/// Renders at a fixed internal resolution and presents the result scaled
+/// into the window, letterboxed with `clear_color`.
+pub struct FixedResolution {
+    target: Texture,
+    mode: ScalingMode,
+    sampler: wgpu::Sampler,
+    blit: BlitPipeline,
+    pub clear_color: wgpu::Color,
+}
+
+impl FixedResolution {
+    pub fn new(
+        context: &Context,
+        width: &u32,
+        height: &u32,
+        texture_format: &wgpu::TextureFormat,
+        mode: ScalingMode,
+    ) -> Result<Self, Error> {
+        let target = context.render_target(
+            width,
+            height,
+            texture_format,
+            Some("Fixed Resolution Target"),
+        )?;
+        let filter = match mode {
+            ScalingMode::Integer => wgpu::FilterMode::Nearest,
+            _ => wgpu::FilterMode::Linear,
+        };
+        let sampler = context.device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Fixed Resolution Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: filter,
+            min_filter: filter,
+            ..Default::default()
+        });
+
+        Ok(Self {
+            target,
+            mode,
+            sampler,
+            blit: BlitPipeline::new(context),
+            clear_color: wgpu::Color::BLACK,
+        })
+    }
+
+    /// The internal render target to draw the scene into.
+    pub fn texture(&self) -> &Texture {
+        &self.target
+    }
+
+    pub fn mode(&self) -> ScalingMode {
+        self.mode
+    }
+
+    /// The presented rectangle (x, y, width, height) in window pixels.
+    pub fn viewport(&self, window_width: u32, window_height: u32) -> [f32; 4] {
+        self.mode.viewport(
+            self.target.width(),
+            self.target.height(),
+            window_width,
+            window_height,
+        )
+    }
+
+    /// Maps a window position to internal pixel coordinates, or `None` if it
+    /// falls on the letterbox.
+    pub fn window_to_internal(
+        &self,
+        position: (f32, f32),
+        window_width: u32,
+        window_height: u32,
+    ) -> Option<(f32, f32)> {
+        let [x, y, width, height] = self.viewport(window_width, window_height);
+        let u = (position.0 - x) / width;
+        let v = (position.1 - y) / height;
+        if !(0.0..1.0).contains(&u) || !(0.0..1.0).contains(&v) {
+            return None;
+        }
+        Some((
+            u * self.target.width() as f32,
+            v * self.target.height() as f32,
+        ))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip(self, context, encoder, target))
+    )]
+    pub fn present(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        target_format: wgpu::TextureFormat,
+        window_width: u32,
+        window_height: u32,
+    ) {
+        self.blit.draw(
+            context,
+            encoder,
+            &self.target.view,
+            &self.sampler,
+            target,
+            target_format,
+            Some(self.viewport(window_width, window_height)),
+            Some(self.clear_color),
+        );
+    }
+}
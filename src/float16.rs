@@ -0,0 +1,84 @@
+use super::context::Context;
+use super::error::Error;
+use super::texture::Texture;
+use half::f16;
+
+/// WGSL helpers for half-precision data stored in `u32` words. naga's WGSL
+/// frontend has no `f16` type yet, so storage buffers of halves are bound as
+/// `array<u32>` (two halves per word) and unpacked to `f32` in the kernel.
+pub const PACKED_F16_WGSL: &str = "fn unpack_f16x2(word: u32) -> vec2<f32> {
+    return unpack2x16float(word);
+}
+
+fn pack_f16x2(value: vec2<f32>) -> u32 {
+    return pack2x16float(value);
+}
+
+fn unpack_f16x4(words: vec2<u32>) -> vec4<f32> {
+    return vec4<f32>(unpack2x16float(words.x), unpack2x16float(words.y));
+}
+
+fn pack_f16x4(value: vec4<f32>) -> vec2<u32> {
+    return vec2<u32>(pack2x16float(value.xy), pack2x16float(value.zw));
+}
+";
+
+impl Context {
+    pub fn supports_shader_f16(&self) -> bool {
+        self.device.features().contains(wgpu::Features::SHADER_F16)
+    }
+
+    pub fn buffer_with_f16_data(
+        &self,
+        data: &[f16],
+        usage: wgpu::BufferUsages,
+        label: Option<&str>,
+    ) -> Result<wgpu::Buffer, Error> {
+        self.buffer_with_data(bytemuck::cast_slice(data), usage, label)
+    }
+
+    pub fn buffer_with_f32_as_f16(
+        &self,
+        data: &[f32],
+        usage: wgpu::BufferUsages,
+        label: Option<&str>,
+    ) -> Result<wgpu::Buffer, Error> {
+        let halves: Vec<f16> = data.iter().copied().map(f16::from_f32).collect();
+        self.buffer_with_f16_data(&halves, usage, label)
+    }
+
+    pub fn read_buffer_f16(&self, buffer: &wgpu::Buffer) -> Result<Vec<f16>, Error> {
+        let data = self.read_buffer(buffer)?;
+        Ok(bytemuck::pod_collect_to_vec(&data))
+    }
+
+    pub fn read_buffer_f16_as_f32(&self, buffer: &wgpu::Buffer) -> Result<Vec<f32>, Error> {
+        let halves = self.read_buffer_f16(buffer)?;
+        Ok(halves.into_iter().map(f16::to_f32).collect())
+    }
+
+    pub fn texture_with_f16_data(
+        &self,
+        data: &[f16],
+        width: &u32,
+        height: &u32,
+        label: Option<&str>,
+    ) -> Result<Texture, Error> {
+        self.texture_with_data(
+            bytemuck::cast_slice(data),
+            width,
+            height,
+            &wgpu::TextureFormat::Rgba16Float,
+            label,
+        )
+    }
+
+    pub fn read_texture_f16(&self, texture: &Texture) -> Result<Vec<f16>, Error> {
+        let format = texture.format();
+        if format != wgpu::TextureFormat::Rgba16Float {
+            return Err(Error::UnsupportedTextureFormat(format));
+        }
+        let data = self.read_texture(texture)?;
+        Ok(bytemuck::pod_collect_to_vec(&data))
+    }
+}
@@ -0,0 +1,458 @@
+use super::context::Context;
+use super::diagnostics::{describe_texture, PassRecord};
+use super::error::Error;
+use super::texture::Texture;
+use wgpu::util::DeviceExt;
+
+const WORKGROUP_SIZE: u32 = 8;
+const MAX_BLOCK_SIZE: u32 = 16;
+const FLOW_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba32Float;
+const DIFFERENCE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R32Float;
+const FLOW_VISUALIZATION_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    size: [u32; 2],
+    flow_size: [u32; 2],
+    block_size: u32,
+    search_radius: u32,
+    max_magnitude: f32,
+    _padding: u32,
+}
+
+/// Frame differencing and block-matching optical flow between two frames
+/// of the same size.
+pub struct OpticalFlow {
+    difference_layout: wgpu::BindGroupLayout,
+    estimate_layout: wgpu::BindGroupLayout,
+    visualize_layout: wgpu::BindGroupLayout,
+    difference: wgpu::ComputePipeline,
+    estimate: wgpu::ComputePipeline,
+    visualize: wgpu::ComputePipeline,
+    /// Side of the square blocks in pixels, clamped to 1..=16. The flow
+    /// field has one texel per block.
+    pub block_size: u32,
+    /// Largest displacement searched in each direction, in pixels.
+    pub search_radius: u32,
+}
+
+impl OpticalFlow {
+    pub fn new(context: &Context) -> Self {
+        let device = &context.device;
+        let module = device.create_shader_module(wgpu::include_wgsl!("shaders/flow.wgsl"));
+        let entry = |binding, ty| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty,
+            count: None,
+        };
+        let params = wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        };
+        let texture = wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+        };
+        let storage = |format| wgpu::BindingType::StorageTexture {
+            access: wgpu::StorageTextureAccess::WriteOnly,
+            format,
+            view_dimension: wgpu::TextureViewDimension::D2,
+        };
+        let layout = |label, entries: &[wgpu::BindGroupLayoutEntry]| {
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some(label),
+                entries,
+            })
+        };
+        let difference_layout = layout(
+            "Frame Difference Bind Group Layout",
+            &[
+                entry(0, params),
+                entry(1, texture),
+                entry(2, texture),
+                entry(4, storage(DIFFERENCE_FORMAT)),
+            ],
+        );
+        let estimate_layout = layout(
+            "Optical Flow Bind Group Layout",
+            &[
+                entry(0, params),
+                entry(1, texture),
+                entry(2, texture),
+                entry(3, storage(FLOW_FORMAT)),
+            ],
+        );
+        let visualize_layout = layout(
+            "Flow Visualization Bind Group Layout",
+            &[
+                entry(0, params),
+                entry(5, texture),
+                entry(6, storage(FLOW_VISUALIZATION_FORMAT)),
+            ],
+        );
+        let pipeline = |entry_point: &str, bind_group_layout: &wgpu::BindGroupLayout| {
+            let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some(entry_point),
+                bind_group_layouts: &[bind_group_layout],
+                push_constant_ranges: &[],
+            });
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&layout),
+                module: &module,
+                entry_point,
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            })
+        };
+
+        Self {
+            difference: pipeline("difference", &difference_layout),
+            estimate: pipeline("estimate", &estimate_layout),
+            visualize: pipeline("visualize", &visualize_layout),
+            difference_layout,
+            estimate_layout,
+            visualize_layout,
+            block_size: 8,
+            search_radius: 8,
+        }
+    }
+
+    /// Size of the flow field for frames of the given size.
+    pub fn flow_size(&self, width: &u32, height: &u32) -> (u32, u32) {
+        let block_size = self.clamped_block_size();
+        (width.div_ceil(block_size), height.div_ceil(block_size))
+    }
+
+    /// Creates an `Rgba32Float` flow field texture for frames of the given size.
+    pub fn create_flow_texture(
+        &self,
+        context: &Context,
+        width: &u32,
+        height: &u32,
+    ) -> Result<Texture, Error> {
+        let (width, height) = self.flow_size(width, height);
+        storage_texture(context, width, height, FLOW_FORMAT, "Optical Flow")
+    }
+
+    /// Creates an `R32Float` texture for [`OpticalFlow::difference`].
+    pub fn create_difference_texture(
+        &self,
+        context: &Context,
+        width: &u32,
+        height: &u32,
+    ) -> Result<Texture, Error> {
+        storage_texture(
+            context,
+            *width,
+            *height,
+            DIFFERENCE_FORMAT,
+            "Frame Difference",
+        )
+    }
+
+    /// Writes the absolute luminance difference between the frames into
+    /// `output`, which threshold into a motion mask, for example with
+    /// `ConnectedComponents::label`.
+    pub fn difference(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        previous: &Texture,
+        current: &Texture,
+        output: &Texture,
+    ) -> Result<(), Error> {
+        check_frames(previous, current)?;
+        check_output(
+            output,
+            DIFFERENCE_FORMAT,
+            previous.width(),
+            previous.height(),
+        )?;
+
+        let params = self.params(context, previous);
+        let bind_group = context
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Frame Difference Bind Group"),
+                layout: &self.difference_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: params.as_entire_binding(),
+                    },
+                    view_entry(1, previous),
+                    view_entry(2, current),
+                    view_entry(4, output),
+                ],
+            });
+        context.diagnostics.record_pass(|| {
+            PassRecord::new(
+                "Frame Difference",
+                "difference",
+                vec![
+                    describe_texture("previous", &previous.texture),
+                    describe_texture("current", &current.texture),
+                    describe_texture("output", &output.texture),
+                ],
+            )
+        });
+        dispatch(
+            encoder,
+            &self.difference,
+            &bind_group,
+            output.width().div_ceil(WORKGROUP_SIZE),
+            output.height().div_ceil(WORKGROUP_SIZE),
+        );
+
+        Ok(())
+    }
+
+    /// Estimates per block of `current` the offset in pixels to the best
+    /// matching block of `previous`, by the sum of absolute luminance
+    /// differences, and writes it to the xy of `flow` with the mean
+    /// difference of the match in z. Sampling the previous frame at a pixel
+    /// plus its block's flow reprojects it into the current frame.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, context, encoder, previous, current, flow),
+            fields(width = current.width(), height = current.height()),
+            err
+        )
+    )]
+    pub fn estimate(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        previous: &Texture,
+        current: &Texture,
+        flow: &Texture,
+    ) -> Result<(), Error> {
+        check_frames(previous, current)?;
+        let (width, height) = self.flow_size(&previous.width(), &previous.height());
+        check_output(flow, FLOW_FORMAT, width, height)?;
+
+        let params = self.params(context, previous);
+        let bind_group = context
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Optical Flow Bind Group"),
+                layout: &self.estimate_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: params.as_entire_binding(),
+                    },
+                    view_entry(1, previous),
+                    view_entry(2, current),
+                    view_entry(3, flow),
+                ],
+            });
+        context.diagnostics.record_pass(|| {
+            PassRecord::new(
+                "Optical Flow",
+                "estimate",
+                vec![
+                    describe_texture("previous", &previous.texture),
+                    describe_texture("current", &current.texture),
+                    describe_texture("flow", &flow.texture),
+                ],
+            )
+        });
+        dispatch(encoder, &self.estimate, &bind_group, width, height);
+
+        Ok(())
+    }
+
+    /// Renders `flow` into the `Rgba8Unorm` storage texture `output` of
+    /// any size, with the direction as hue and the magnitude as saturation,
+    /// fully saturated at `max_magnitude` pixels.
+    pub fn visualize(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        flow: &Texture,
+        output: &Texture,
+        max_magnitude: f32,
+    ) -> Result<(), Error> {
+        if flow.format() != FLOW_FORMAT {
+            return Err(Error::UnsupportedTextureFormat(flow.format()));
+        }
+        check_output(
+            output,
+            FLOW_VISUALIZATION_FORMAT,
+            output.width(),
+            output.height(),
+        )?;
+
+        let params = context
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Flow Visualization Params"),
+                contents: bytemuck::bytes_of(&Params {
+                    flow_size: [flow.width(), flow.height()],
+                    max_magnitude: max_magnitude.max(f32::EPSILON),
+                    ..Default::default()
+                }),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+        let bind_group = context
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Flow Visualization Bind Group"),
+                layout: &self.visualize_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: params.as_entire_binding(),
+                    },
+                    view_entry(5, flow),
+                    view_entry(6, output),
+                ],
+            });
+        context.diagnostics.record_pass(|| {
+            PassRecord::new(
+                "Flow Visualization",
+                "visualize",
+                vec![
+                    describe_texture("flow", &flow.texture),
+                    describe_texture("output", &output.texture),
+                ],
+            )
+        });
+        dispatch(
+            encoder,
+            &self.visualize,
+            &bind_group,
+            output.width().div_ceil(WORKGROUP_SIZE),
+            output.height().div_ceil(WORKGROUP_SIZE),
+        );
+
+        Ok(())
+    }
+
+    fn clamped_block_size(&self) -> u32 {
+        self.block_size.clamp(1, MAX_BLOCK_SIZE)
+    }
+
+    fn params(&self, context: &Context, frame: &Texture) -> wgpu::Buffer {
+        let (width, height) = self.flow_size(&frame.width(), &frame.height());
+        context
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Optical Flow Params"),
+                contents: bytemuck::bytes_of(&Params {
+                    size: [frame.width(), frame.height()],
+                    flow_size: [width, height],
+                    block_size: self.clamped_block_size(),
+                    search_radius: self.search_radius,
+                    ..Default::default()
+                }),
+                usage: wgpu::BufferUsages::UNIFORM,
+            })
+    }
+}
+
+fn check_frames(previous: &Texture, current: &Texture) -> Result<(), Error> {
+    for frame in [previous, current] {
+        if !matches!(
+            frame.format().sample_type(None, None),
+            Some(wgpu::TextureSampleType::Float { .. })
+        ) || frame.view_dimension() != wgpu::TextureViewDimension::D2
+        {
+            return Err(Error::UnsupportedTextureFormat(frame.format()));
+        }
+    }
+    if previous.size() != current.size() {
+        return Err(Error::TextureSizeMismatch {
+            expected: previous.size(),
+            actual: current.size(),
+        });
+    }
+    Ok(())
+}
+
+fn check_output(
+    output: &Texture,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+) -> Result<(), Error> {
+    if output.format() != format {
+        return Err(Error::UnsupportedTextureFormat(output.format()));
+    }
+    if !output
+        .usage()
+        .contains(wgpu::TextureUsages::STORAGE_BINDING)
+    {
+        return Err(Error::TextureCreationFailed);
+    }
+    let expected = wgpu::Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+    };
+    if output.size() != expected {
+        return Err(Error::TextureSizeMismatch {
+            expected,
+            actual: output.size(),
+        });
+    }
+    Ok(())
+}
+
+fn storage_texture(
+    context: &Context,
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+    label: &str,
+) -> Result<Texture, Error> {
+    let size = wgpu::Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+    };
+    context.check_texture_size(&size, wgpu::TextureDimension::D2)?;
+    let texture = context.device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        view_formats: &[],
+        usage: wgpu::TextureUsages::STORAGE_BINDING
+            | wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::COPY_SRC,
+    });
+    Ok(Texture::from_wgpu(context, texture))
+}
+
+fn view_entry(binding: u32, texture: &Texture) -> wgpu::BindGroupEntry<'_> {
+    wgpu::BindGroupEntry {
+        binding,
+        resource: wgpu::BindingResource::TextureView(&texture.view),
+    }
+}
+
+fn dispatch(
+    encoder: &mut wgpu::CommandEncoder,
+    pipeline: &wgpu::ComputePipeline,
+    bind_group: &wgpu::BindGroup,
+    x: u32,
+    y: u32,
+) {
+    let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+        label: Some("Optical Flow"),
+        timestamp_writes: None,
+    });
+    pass.set_pipeline(pipeline);
+    pass.set_bind_group(0, bind_group, &[]);
+    pass.dispatch_workgroups(x, y, 1);
+}
@@ -0,0 +1,275 @@
+use super::error::Error;
+
+/// Texel block of a texture format: `width` x `height` texels stored in
+/// `size` bytes, a single texel for uncompressed formats.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockInfo {
+    pub width: u32,
+    pub height: u32,
+    pub size: u32,
+}
+
+impl BlockInfo {
+    /// The block of `format`, `None` for formats without a defined copy
+    /// size: `Depth24Plus`, combined depth-stencil and multi-planar formats.
+    pub fn of(format: wgpu::TextureFormat) -> Option<Self> {
+        let (width, height) = format.block_dimensions();
+        Some(Self {
+            width,
+            height,
+            size: format.block_copy_size(None)?,
+        })
+    }
+}
+
+/// Byte layout of tightly packed texel data of a format, with rows of
+/// blocks for block-compressed formats, as uploaded by
+/// `Context::texture_with_data` and returned by `Context::read_texture`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ImageLayout {
+    pub block: BlockInfo,
+    pub bytes_per_row: u32,
+    /// Rows of blocks per image.
+    pub rows_per_image: u32,
+    /// Images, that is depth slices or array layers.
+    pub images: u32,
+}
+
+impl ImageLayout {
+    /// The layout of `size` texels of `format`, which must be whole blocks.
+    pub fn new(format: wgpu::TextureFormat, size: wgpu::Extent3d) -> Result<Self, Error> {
+        let block = BlockInfo::of(format).ok_or(Error::UnsupportedTextureFormat(format))?;
+        if !size.width.is_multiple_of(block.width) || !size.height.is_multiple_of(block.height) {
+            return Err(Error::TextureCreationFailed);
+        }
+        Ok(Self {
+            block,
+            bytes_per_row: size.width / block.width * block.size,
+            rows_per_image: size.height / block.height,
+            images: size.depth_or_array_layers,
+        })
+    }
+
+    pub fn bytes_per_image(&self) -> u64 {
+        self.bytes_per_row as u64 * self.rows_per_image as u64
+    }
+
+    /// Bytes of all images.
+    pub fn byte_size(&self) -> u64 {
+        self.bytes_per_image() * self.images as u64
+    }
+
+    /// `bytes_per_row` rounded up to the alignment of texture to buffer
+    /// copies.
+    pub fn padded_bytes_per_row(&self) -> u32 {
+        self.bytes_per_row
+            .next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+    }
+
+    /// Checks that `data` holds exactly one image, or all of them with
+    /// `all_images`.
+    pub fn check_data(&self, data: &[u8], all_images: bool) -> Result<(), Error> {
+        let expected = match all_images {
+            true => self.byte_size(),
+            false => self.bytes_per_image(),
+        };
+        if expected == 0 {
+            return Err(Error::TextureCreationFailed);
+        }
+        if data.len() as u64 != expected {
+            return Err(Error::DataSizeMismatch {
+                expected,
+                actual: data.len() as u64,
+            });
+        }
+        Ok(())
+    }
+
+    /// The `ImageDataLayout` of tightly packed data at `offset`.
+    pub fn data_layout(&self, offset: u64) -> wgpu::ImageDataLayout {
+        wgpu::ImageDataLayout {
+            offset,
+            bytes_per_row: Some(self.bytes_per_row),
+            rows_per_image: Some(self.rows_per_image),
+        }
+    }
+}
+
+/// How 8-bit color data is encoded: sRGB, as photos and albedo textures
+/// usually are, or linear, as normal maps, masks and data textures are.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ColorSpace {
+    #[default]
+    Srgb,
+    Linear,
+}
+
+impl ColorSpace {
+    /// `Rgba8UnormSrgb` or `Rgba8Unorm`, so that shaders sample linear
+    /// values either way.
+    pub fn rgba8_format(self) -> wgpu::TextureFormat {
+        match self {
+            ColorSpace::Srgb => wgpu::TextureFormat::Rgba8UnormSrgb,
+            ColorSpace::Linear => wgpu::TextureFormat::Rgba8Unorm,
+        }
+    }
+}
+
+/// The sRGB encoding of a linear value in [0, 1].
+fn linear_to_srgb(value: f32) -> f32 {
+    match value <= 0.0031308 {
+        true => value * 12.92,
+        false => 1.055 * value.powf(1.0 / 2.4) - 0.055,
+    }
+}
+
+/// The bytes of one texel of `format` with the linear value `color`, whose
+/// channels beyond the format's are dropped. The color channels are
+/// encoded to sRGB for sRGB formats, which decode them when sampled.
+/// Supports the 8- and 16-bit unorm and the float color formats.
+pub(crate) fn texel_from_color(
+    format: wgpu::TextureFormat,
+    color: [f32; 4],
+) -> Result<Vec<u8>, Error> {
+    use wgpu::TextureFormat as F;
+    let unorm = |value: f32, max: f32| (value.clamp(0.0, 1.0) * max).round();
+    let unorm8 = |channels: &[f32]| channels.iter().map(|&c| unorm(c, 255.0) as u8).collect();
+    let unorm16 = |channels: &[f32]| {
+        let values: Vec<u16> = channels.iter().map(|&c| unorm(c, 65535.0) as u16).collect();
+        bytemuck::cast_slice(&values).to_vec()
+    };
+    let float16 = |channels: &[f32]| {
+        let values: Vec<half::f16> = channels.iter().map(|&c| half::f16::from_f32(c)).collect();
+        bytemuck::cast_slice(&values).to_vec()
+    };
+    let float32 = |channels: &[f32]| bytemuck::cast_slice(channels).to_vec();
+    let [r, g, b, a] = color;
+    let [sr, sg, sb] = [r, g, b].map(|c| linear_to_srgb(c.clamp(0.0, 1.0)));
+    Ok(match format {
+        F::R8Unorm => unorm8(&color[..1]),
+        F::Rg8Unorm => unorm8(&color[..2]),
+        F::Rgba8Unorm => unorm8(&color),
+        F::Rgba8UnormSrgb => unorm8(&[sr, sg, sb, a]),
+        F::Bgra8Unorm => unorm8(&[b, g, r, a]),
+        F::Bgra8UnormSrgb => unorm8(&[sb, sg, sr, a]),
+        F::R16Unorm => unorm16(&color[..1]),
+        F::Rg16Unorm => unorm16(&color[..2]),
+        F::Rgba16Unorm => unorm16(&color),
+        F::R16Float => float16(&color[..1]),
+        F::Rg16Float => float16(&color[..2]),
+        F::Rgba16Float => float16(&color),
+        F::R32Float => float32(&color[..1]),
+        F::Rg32Float => float32(&color[..2]),
+        F::Rgba32Float => float32(&color),
+        _ => return Err(Error::UnsupportedTextureFormat(format)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wgpu::TextureFormat as F;
+
+    fn size(width: u32, height: u32, images: u32) -> wgpu::Extent3d {
+        wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: images,
+        }
+    }
+
+    fn layout(format: F, width: u32, height: u32, images: u32) -> ImageLayout {
+        ImageLayout::new(format, size(width, height, images)).unwrap()
+    }
+
+    #[test]
+    fn uncompressed_layouts() {
+        let r8 = layout(F::R8Unorm, 5, 3, 1);
+        assert_eq!((r8.bytes_per_row, r8.rows_per_image), (5, 3));
+        assert_eq!(r8.byte_size(), 15);
+        assert_eq!(r8.padded_bytes_per_row(), 256);
+
+        let rg16 = layout(F::Rg16Float, 7, 2, 3);
+        assert_eq!((rg16.bytes_per_row, rg16.rows_per_image), (28, 2));
+        assert_eq!(rg16.bytes_per_image(), 56);
+        assert_eq!(rg16.byte_size(), 168);
+
+        let rgba32 = layout(F::Rgba32Float, 64, 4, 1);
+        assert_eq!((rgba32.bytes_per_row, rgba32.rows_per_image), (1024, 4));
+        assert_eq!(rgba32.padded_bytes_per_row(), 1024);
+        assert_eq!(rgba32.byte_size(), 4096);
+    }
+
+    #[test]
+    fn block_compressed_layouts() {
+        let bc1 = layout(F::Bc1RgbaUnorm, 16, 8, 2);
+        assert_eq!(
+            bc1.block,
+            BlockInfo {
+                width: 4,
+                height: 4,
+                size: 8
+            }
+        );
+        assert_eq!((bc1.bytes_per_row, bc1.rows_per_image), (32, 2));
+        assert_eq!(bc1.byte_size(), 128);
+
+        let astc = F::Astc {
+            block: wgpu::AstcBlock::B8x6,
+            channel: wgpu::AstcChannel::Unorm,
+        };
+        let astc = layout(astc, 24, 12, 1);
+        assert_eq!((astc.bytes_per_row, astc.rows_per_image), (48, 2));
+        assert_eq!(astc.byte_size(), 96);
+    }
+
+    #[test]
+    fn partial_blocks_are_rejected() {
+        assert!(ImageLayout::new(F::Bc1RgbaUnorm, size(6, 8, 1)).is_err());
+        assert!(ImageLayout::new(F::Bc1RgbaUnorm, size(8, 2, 1)).is_err());
+        assert!(matches!(
+            ImageLayout::new(F::Depth24Plus, size(4, 4, 1)),
+            Err(Error::UnsupportedTextureFormat(F::Depth24Plus))
+        ));
+    }
+
+    #[test]
+    fn sub_block_mip_levels_take_a_whole_block() {
+        let base = size(8, 8, 1);
+        let dimension = wgpu::TextureDimension::D2;
+        let sizes: Vec<_> = (0..base.max_mips(dimension))
+            .map(|level| {
+                let level_size = base
+                    .mip_level_size(level, dimension)
+                    .physical_size(F::Bc3RgbaUnorm);
+                ImageLayout::new(F::Bc3RgbaUnorm, level_size)
+                    .unwrap()
+                    .byte_size()
+            })
+            .collect();
+        assert_eq!(sizes, [64, 16, 16, 16]);
+    }
+
+    #[test]
+    fn check_data_sizes() {
+        let rgba8 = layout(F::Rgba8Unorm, 2, 2, 3);
+        assert!(rgba8.check_data(&[0; 16], false).is_ok());
+        assert!(rgba8.check_data(&[0; 48], true).is_ok());
+        assert!(matches!(
+            rgba8.check_data(&[0; 48], false),
+            Err(Error::DataSizeMismatch {
+                expected: 16,
+                actual: 48
+            })
+        ));
+        assert!(matches!(
+            rgba8.check_data(&[0; 15], true),
+            Err(Error::DataSizeMismatch {
+                expected: 48,
+                actual: 15
+            })
+        ));
+        let empty = layout(F::Rgba8Unorm, 0, 2, 1);
+        assert!(empty.check_data(&[], true).is_err());
+    }
+}
@@ -0,0 +1,388 @@
+use super::error::Error;
+use super::mesh::Mesh;
+use super::scene::{Light, MeshDescription, MeshSource, ObjectDescription, SceneDescription};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+#[derive(Default)]
+struct Buffer {
+    data: Vec<u8>,
+    views: Vec<Value>,
+    accessors: Vec<Value>,
+}
+
+impl Buffer {
+    fn accessor(
+        &mut self,
+        data: &[u8],
+        count: usize,
+        kind: &str,
+        component: u32,
+        target: u32,
+    ) -> usize {
+        self.data.resize(self.data.len().next_multiple_of(4), 0);
+        self.views.push(json!({
+            "buffer": 0,
+            "byteOffset": self.data.len(),
+            "byteLength": data.len(),
+            "target": target,
+        }));
+        self.data.extend_from_slice(data);
+        self.accessors.push(json!({
+            "bufferView": self.views.len() - 1,
+            "componentType": component,
+            "count": count,
+            "type": kind,
+        }));
+        self.accessors.len() - 1
+    }
+
+    /// Writes the vertex attributes and indices of `mesh`, returning the
+    /// primitive's `attributes` and `indices`.
+    fn mesh(&mut self, mesh: &Mesh) -> (Value, usize) {
+        let count = mesh.vertices.len();
+        let positions: Vec<[f32; 3]> = mesh.vertices.iter().map(|v| v.position).collect();
+        let normals: Vec<[f32; 3]> = mesh.vertices.iter().map(|v| v.normal).collect();
+        let uvs: Vec<[f32; 2]> = mesh.vertices.iter().map(|v| v.uv).collect();
+
+        let position = self.accessor(
+            bytemuck::cast_slice(&positions),
+            count,
+            "VEC3",
+            FLOAT,
+            ARRAY_BUFFER,
+        );
+        let (min, max) = positions
+            .iter()
+            .fold(([f32::MAX; 3], [f32::MIN; 3]), |(min, max), p| {
+                (
+                    [0, 1, 2].map(|i| min[i].min(p[i])),
+                    [0, 1, 2].map(|i| max[i].max(p[i])),
+                )
+            });
+        if count > 0 {
+            self.accessors[position]["min"] = json!(min);
+            self.accessors[position]["max"] = json!(max);
+        }
+        let mut attributes = json!({
+            "POSITION": position,
+            "NORMAL": self.accessor(bytemuck::cast_slice(&normals), count, "VEC3", FLOAT, ARRAY_BUFFER),
+            "TEXCOORD_0": self.accessor(bytemuck::cast_slice(&uvs), count, "VEC2", FLOAT, ARRAY_BUFFER),
+        });
+        if mesh.colors.len() == count && count > 0 {
+            attributes["COLOR_0"] = json!(self.accessor(
+                bytemuck::cast_slice(&mesh.colors),
+                count,
+                "VEC4",
+                FLOAT,
+                ARRAY_BUFFER
+            ));
+        }
+        if mesh.uv2.len() == count && count > 0 {
+            attributes["TEXCOORD_1"] = json!(self.accessor(
+                bytemuck::cast_slice(&mesh.uv2),
+                count,
+                "VEC2",
+                FLOAT,
+                ARRAY_BUFFER
+            ));
+        }
+        let indices = self.accessor(
+            bytemuck::cast_slice(&mesh.indices),
+            mesh.indices.len(),
+            "SCALAR",
+            UNSIGNED_INT,
+            ELEMENT_ARRAY_BUFFER,
+        );
+        (attributes, indices)
+    }
+}
+
+/// Rotation (x, y, z, w) turning -Z towards `forward` with +Y as close to
+/// `up` as possible, as glTF cameras and lights look down -Z.
+fn look_rotation(forward: [f32; 3], up: [f32; 3]) -> [f32; 4] {
+    let normalize = |v: [f32; 3]| {
+        let length = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+        v.map(|c| if length > 0.0 { c / length } else { 0.0 })
+    };
+    let cross = |a: [f32; 3], b: [f32; 3]| {
+        [
+            a[1] * b[2] - a[2] * b[1],
+            a[2] * b[0] - a[0] * b[2],
+            a[0] * b[1] - a[1] * b[0],
+        ]
+    };
+    let f = normalize(forward);
+    let mut right = normalize(cross(f, up));
+    if right == [0.0; 3] {
+        let up = if f[1].abs() < 0.9 {
+            [0.0, 1.0, 0.0]
+        } else {
+            [1.0, 0.0, 0.0]
+        };
+        right = normalize(cross(f, up));
+    }
+    let (x, y, z) = (right, cross(right, f), f.map(|c| -c));
+    // Columns x, y, z of the rotation matrix to a quaternion.
+    let trace = x[0] + y[1] + z[2];
+    if trace > 0.0 {
+        let s = (trace + 1.0).sqrt() * 2.0;
+        [
+            (y[2] - z[1]) / s,
+            (z[0] - x[2]) / s,
+            (x[1] - y[0]) / s,
+            s / 4.0,
+        ]
+    } else if x[0] > y[1] && x[0] > z[2] {
+        let s = (1.0 + x[0] - y[1] - z[2]).sqrt() * 2.0;
+        [
+            s / 4.0,
+            (y[0] + x[1]) / s,
+            (z[0] + x[2]) / s,
+            (y[2] - z[1]) / s,
+        ]
+    } else if y[1] > z[2] {
+        let s = (1.0 + y[1] - x[0] - z[2]).sqrt() * 2.0;
+        [
+            (y[0] + x[1]) / s,
+            s / 4.0,
+            (z[1] + y[2]) / s,
+            (z[0] - x[2]) / s,
+        ]
+    } else {
+        let s = (1.0 + z[2] - x[0] - y[1]).sqrt() * 2.0;
+        [
+            (z[0] + x[2]) / s,
+            (z[1] + y[2]) / s,
+            s / 4.0,
+            (x[1] - y[0]) / s,
+        ]
+    }
+}
+
+/// The glTF JSON document and binary buffer of `scene`.
+fn document(
+    scene: &SceneDescription,
+    buffer_uri: Option<&str>,
+    camera: bool,
+) -> Result<(Value, Vec<u8>), Error> {
+    scene.validate()?;
+    let mut buffer = Buffer::default();
+    let primitives: HashMap<&str, (Value, usize)> = scene
+        .meshes
+        .iter()
+        .map(|mesh| (mesh.name.as_str(), buffer.mesh(&mesh.source.to_mesh())))
+        .collect();
+
+    let (mut images, mut textures, mut materials) = (Vec::new(), Vec::new(), Vec::new());
+    for material in &scene.materials {
+        let mut pbr = json!({
+            "baseColorFactor": material.base_color,
+            "metallicFactor": material.metallic,
+            "roughnessFactor": material.roughness,
+        });
+        if let Some(texture) = &material.base_color_texture {
+            images.push(json!({ "uri": texture.to_string_lossy().replace('\\', "/") }));
+            textures.push(json!({ "source": images.len() - 1 }));
+            pbr["baseColorTexture"] = json!({ "index": textures.len() - 1 });
+        }
+        let mut value = json!({
+            "name": material.name,
+            "pbrMetallicRoughness": pbr,
+            "emissiveFactor": material.emissive,
+        });
+        if material.base_color[3] < 1.0 {
+            value["alphaMode"] = json!("BLEND");
+        }
+        materials.push(value);
+    }
+
+    // glTF assigns materials per primitive, so every mesh and material
+    // pair becomes a glTF mesh.
+    let mut meshes = Vec::new();
+    let mut mesh_indices = HashMap::new();
+    let mut nodes = Vec::new();
+    for object in &scene.objects {
+        let key = (object.mesh.as_str(), object.material.as_deref());
+        let mesh = *mesh_indices.entry(key).or_insert_with(|| {
+            let (attributes, indices) = &primitives[object.mesh.as_str()];
+            let mut primitive = json!({ "attributes": attributes, "indices": indices, "mode": 4 });
+            if let Some(material) = &object.material {
+                primitive["material"] =
+                    json!(scene.materials.iter().position(|m| &m.name == material));
+            }
+            meshes.push(json!({ "name": object.mesh, "primitives": [primitive] }));
+            meshes.len() - 1
+        });
+        nodes.push(json!({
+            "name": object.mesh,
+            "mesh": mesh,
+            "translation": object.translation,
+            "rotation": object.rotation,
+            "scale": object.scale,
+        }));
+    }
+
+    let mut cameras = Vec::new();
+    if camera {
+        let camera = &scene.camera;
+        let forward = [0, 1, 2].map(|i| camera.target[i] - camera.position[i]);
+        cameras.push(json!({
+            "type": "perspective",
+            "perspective": {
+                "yfov": camera.fov_y_degrees.to_radians(),
+                "znear": camera.near,
+                "zfar": camera.far,
+            },
+        }));
+        nodes.push(json!({
+            "name": "Camera",
+            "camera": 0,
+            "translation": camera.position,
+            "rotation": look_rotation(forward, camera.up),
+        }));
+    }
+
+    let mut lights = Vec::new();
+    for light in &scene.lights {
+        let (node, value) = match light {
+            Light::Directional {
+                direction,
+                color,
+                intensity,
+            } => (
+                json!({ "rotation": look_rotation(*direction, [0.0, 1.0, 0.0]) }),
+                json!({ "type": "directional", "color": color, "intensity": intensity }),
+            ),
+            Light::Point {
+                position,
+                color,
+                intensity,
+                range,
+            } => {
+                let mut value = json!({ "type": "point", "color": color, "intensity": intensity });
+                if let Some(range) = range {
+                    value["range"] = json!(range);
+                }
+                (json!({ "translation": position }), value)
+            }
+        };
+        lights.push(value);
+        let mut node = node;
+        node["name"] = json!(format!("Light {}", lights.len() - 1));
+        node["extensions"] = json!({ "KHR_lights_punctual": { "light": lights.len() - 1 } });
+        nodes.push(node);
+    }
+
+    let mut document = json!({
+        "asset": { "version": "2.0", "generator": "wgpu-tools" },
+        "scene": 0,
+        "scenes": [{ "nodes": (0..nodes.len()).collect::<Vec<_>>() }],
+        "nodes": nodes,
+        "meshes": meshes,
+        "materials": materials,
+    });
+    if !cameras.is_empty() {
+        document["cameras"] = json!(cameras);
+    }
+    if !buffer.data.is_empty() {
+        let mut value = json!({ "byteLength": buffer.data.len() });
+        if let Some(uri) = buffer_uri {
+            value["uri"] = json!(uri);
+        }
+        document["buffers"] = json!([value]);
+        document["bufferViews"] = json!(buffer.views);
+        document["accessors"] = json!(buffer.accessors);
+    }
+    if !images.is_empty() {
+        document["images"] = json!(images);
+        document["textures"] = json!(textures);
+    }
+    if !lights.is_empty() {
+        document["extensionsUsed"] = json!(["KHR_lights_punctual"]);
+        document["extensions"] = json!({ "KHR_lights_punctual": { "lights": lights } });
+    }
+    Ok((document, buffer.data))
+}
+
+fn write_gltf(scene: &SceneDescription, path: &Path, camera: bool) -> Result<(), Error> {
+    let buffer_path = path.with_extension("bin");
+    let uri = buffer_path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+    let (document, data) = document(scene, Some(&uri), camera)?;
+    std::fs::write(path, serde_json::to_vec_pretty(&document)?)?;
+    if !data.is_empty() {
+        std::fs::write(buffer_path, data)?;
+    }
+    Ok(())
+}
+
+fn write_glb(scene: &SceneDescription, path: &Path, camera: bool) -> Result<(), Error> {
+    let (document, mut data) = document(scene, None, camera)?;
+    let mut json = serde_json::to_vec(&document)?;
+    json.resize(json.len().next_multiple_of(4), b' ');
+    data.resize(data.len().next_multiple_of(4), 0);
+
+    let mut length = 12 + 8 + json.len();
+    if !data.is_empty() {
+        length += 8 + data.len();
+    }
+    let mut glb = Vec::with_capacity(length);
+    glb.extend_from_slice(b"glTF");
+    glb.extend_from_slice(&2u32.to_le_bytes());
+    glb.extend_from_slice(&(length as u32).to_le_bytes());
+    glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    glb.extend_from_slice(b"JSON");
+    glb.extend_from_slice(&json);
+    if !data.is_empty() {
+        glb.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        glb.extend_from_slice(b"BIN\0");
+        glb.extend_from_slice(&data);
+    }
+    std::fs::write(path, glb)?;
+    Ok(())
+}
+
+impl SceneDescription {
+    /// Writes a glTF 2.0 file and its buffer next to it as `<name>.bin`.
+    /// Lights use `KHR_lights_punctual`, texture paths are written as they
+    /// are in the scene, and the environment is not exported.
+    pub fn save_gltf(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        write_gltf(self, path.as_ref(), true)
+    }
+
+    /// Like `save_gltf` with the JSON and buffer in a single binary file.
+    pub fn save_glb(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        write_glb(self, path.as_ref(), true)
+    }
+}
+
+/// A scene with `mesh` as its only object.
+fn mesh_scene(mesh: &Mesh) -> SceneDescription {
+    SceneDescription {
+        meshes: vec![MeshDescription {
+            name: "Mesh".to_string(),
+            source: MeshSource::Inline(mesh.clone()),
+        }],
+        objects: vec![ObjectDescription::new("Mesh")],
+        ..Default::default()
+    }
+}
+
+impl Mesh {
+    pub fn save_gltf(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        write_gltf(&mesh_scene(self), path.as_ref(), false)
+    }
+
+    pub fn save_glb(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        write_glb(&mesh_scene(self), path.as_ref(), false)
+    }
+}
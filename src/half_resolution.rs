@@ -0,0 +1,384 @@
+use super::context::Context;
+use super::depth::{aspect_view, DepthRange};
+use super::diagnostics::{describe_texture, PassRecord};
+use super::error::Error;
+use super::texture::Texture;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use wgpu::util::DeviceExt;
+
+const HALF_DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R32Float;
+
+const REVERSE_Z: u32 = 1;
+const INFINITE_FAR: u32 = 2;
+const LOGARITHMIC: u32 = 4;
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    near: f32,
+    far: f32,
+    flags: u32,
+    depth_sensitivity: f32,
+}
+
+/// Half resolution targets for expensive effects such as SSAO or
+/// volumetrics, composited back at full resolution by a depth-aware
+/// bilateral upsample.
+///
+/// Each frame, `downsample_depth` fills `depth` with the view distances of
+/// the scene depth, the effect renders into `target` (reading `depth` if it
+/// needs them), and `upsample` draws the result into a full resolution
+/// target.
+pub struct HalfResolution {
+    target: Texture,
+    depth: Texture,
+    module: wgpu::ShaderModule,
+    downsample_layout: wgpu::BindGroupLayout,
+    upsample_layout: wgpu::BindGroupLayout,
+    downsample: wgpu::RenderPipeline,
+    upsample_pipeline_layout: wgpu::PipelineLayout,
+    #[allow(clippy::type_complexity)]
+    upsample_pipelines:
+        Mutex<HashMap<(wgpu::TextureFormat, Option<wgpu::BlendState>), wgpu::RenderPipeline>>,
+    /// How quickly half resolution texels lose weight with the relative
+    /// difference of their view distance from a pixel's.
+    pub depth_sensitivity: f32,
+    /// Blending of the upsampled effect with the target, e.g. multiplying
+    /// for ambient occlusion, or replacing it if `None`.
+    pub blend: Option<wgpu::BlendState>,
+}
+
+impl HalfResolution {
+    /// Targets for an effect of `texture_format` over a `width` x `height`
+    /// (full resolution) frame.
+    pub fn new(
+        context: &Context,
+        width: &u32,
+        height: &u32,
+        texture_format: &wgpu::TextureFormat,
+    ) -> Result<Self, Error> {
+        let device = &context.device;
+        let module =
+            device.create_shader_module(wgpu::include_wgsl!("shaders/half_resolution.wgsl"));
+        let entry = |binding, ty| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty,
+            count: None,
+        };
+        let params = entry(
+            0,
+            wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+        );
+        let texture = |binding| {
+            entry(
+                binding,
+                wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+            )
+        };
+        let downsample_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Half Resolution Downsample Bind Group Layout"),
+            entries: &[params, texture(1)],
+        });
+        let upsample_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Half Resolution Upsample Bind Group Layout"),
+            entries: &[params, texture(1), texture(2), texture(3)],
+        });
+        let downsample_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Half Resolution Downsample Pipeline Layout"),
+                bind_group_layouts: &[&downsample_layout],
+                push_constant_ranges: &[],
+            });
+        let upsample_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Half Resolution Upsample Pipeline Layout"),
+                bind_group_layouts: &[&upsample_layout],
+                push_constant_ranges: &[],
+            });
+        let downsample = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Half Resolution Downsample"),
+            layout: Some(&downsample_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vs_main",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fs_downsample",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                targets: &[Some(HALF_DEPTH_FORMAT.into())],
+            }),
+            multiview: None,
+        });
+        let (target, depth) = Self::targets(context, *width, *height, *texture_format)?;
+
+        Ok(Self {
+            target,
+            depth,
+            module,
+            downsample_layout,
+            upsample_layout,
+            downsample,
+            upsample_pipeline_layout,
+            upsample_pipelines: Mutex::new(HashMap::new()),
+            depth_sensitivity: 8.0,
+            blend: None,
+        })
+    }
+
+    fn targets(
+        context: &Context,
+        width: u32,
+        height: u32,
+        texture_format: wgpu::TextureFormat,
+    ) -> Result<(Texture, Texture), Error> {
+        let (width, height) = (width.div_ceil(2), height.div_ceil(2));
+        Ok((
+            context.render_target(
+                &width,
+                &height,
+                &texture_format,
+                Some("Half Resolution Target"),
+            )?,
+            context.render_target(
+                &width,
+                &height,
+                &HALF_DEPTH_FORMAT,
+                Some("Half Resolution Depth"),
+            )?,
+        ))
+    }
+
+    /// The half resolution target the effect renders into.
+    pub fn target(&self) -> &Texture {
+        &self.target
+    }
+
+    /// View distances (`R32Float`) of the nearest full resolution texel of
+    /// each half resolution texel, written by `downsample_depth`.
+    pub fn depth(&self) -> &Texture {
+        &self.depth
+    }
+
+    pub fn half_size(&self) -> (u32, u32) {
+        (self.target.width(), self.target.height())
+    }
+
+    /// Recreates the targets for a `width` x `height` frame if its half
+    /// size changed.
+    pub fn resize(&mut self, context: &Context, width: &u32, height: &u32) -> Result<(), Error> {
+        if self.half_size() != (width.div_ceil(2), height.div_ceil(2)) {
+            (self.target, self.depth) =
+                Self::targets(context, *width, *height, self.target.format())?;
+        }
+        Ok(())
+    }
+
+    /// Fills `depth` from the full resolution `depth`, written with
+    /// `range`.
+    pub fn downsample_depth(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        depth: &Texture,
+        range: &DepthRange,
+    ) -> Result<(), Error> {
+        let depth_view = aspect_view(depth, wgpu::TextureAspect::DepthOnly)?;
+        let params = self.params(context, range);
+        let bind_group = context
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Half Resolution Downsample Bind Group"),
+                layout: &self.downsample_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: params.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&depth_view),
+                    },
+                ],
+            });
+        context.diagnostics.record_pass(|| {
+            PassRecord::new(
+                "Half Resolution Downsample",
+                "fs_downsample",
+                vec![
+                    describe_texture("depth", &depth.texture),
+                    describe_texture("half depth", &self.depth.texture),
+                ],
+            )
+        });
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Half Resolution Downsample"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &self.depth.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(&self.downsample);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+        Ok(())
+    }
+
+    /// Draws `target()` upsampled into `output`, of the full resolution
+    /// `depth`'s size, with `blend`.
+    pub fn upsample(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        depth: &Texture,
+        range: &DepthRange,
+        output: &Texture,
+    ) -> Result<(), Error> {
+        if !output
+            .usage()
+            .contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+        {
+            return Err(Error::TextureCreationFailed);
+        }
+        if output.size() != depth.size() {
+            return Err(Error::TextureSizeMismatch {
+                expected: depth.size(),
+                actual: output.size(),
+            });
+        }
+        let depth_view = aspect_view(depth, wgpu::TextureAspect::DepthOnly)?;
+        let params = self.params(context, range);
+        let device = &context.device;
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Half Resolution Upsample Bind Group"),
+            layout: &self.upsample_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&depth_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&self.depth.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(&self.target.view),
+                },
+            ],
+        });
+        context.diagnostics.record_pass(|| {
+            PassRecord::new(
+                "Half Resolution Upsample",
+                "fs_upsample",
+                vec![
+                    describe_texture("depth", &depth.texture),
+                    describe_texture("half depth", &self.depth.texture),
+                    describe_texture("half target", &self.target.texture),
+                    describe_texture("output", &output.texture),
+                ],
+            )
+        });
+
+        let format = output.format();
+        let mut pipelines = self
+            .upsample_pipelines
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let pipeline = pipelines.entry((format, self.blend)).or_insert_with(|| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Half Resolution Upsample"),
+                layout: Some(&self.upsample_pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &self.module,
+                    entry_point: "vs_main",
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    buffers: &[],
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &self.module,
+                    entry_point: "fs_upsample",
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend: self.blend,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                multiview: None,
+            })
+        });
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Half Resolution Upsample"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &output.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+        Ok(())
+    }
+
+    fn params(&self, context: &Context, range: &DepthRange) -> wgpu::Buffer {
+        let mut flags = 0;
+        for (enabled, flag) in [
+            (range.reverse_z, REVERSE_Z),
+            (range.far.is_infinite(), INFINITE_FAR),
+            (range.logarithmic, LOGARITHMIC),
+        ] {
+            if enabled {
+                flags |= flag;
+            }
+        }
+        context
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Half Resolution Params"),
+                contents: bytemuck::bytes_of(&Params {
+                    near: range.near,
+                    far: range.far,
+                    flags,
+                    depth_sensitivity: self.depth_sensitivity,
+                }),
+                usage: wgpu::BufferUsages::UNIFORM,
+            })
+    }
+}
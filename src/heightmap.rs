@@ -0,0 +1,219 @@
+use super::context::Context;
+use super::error::Error;
+use super::texture::Texture;
+use std::path::Path;
+
+/// Heights in the units of the source, row-major from the top-left
+/// sample.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Heightmap {
+    pub width: u32,
+    pub height: u32,
+    pub heights: Vec<f32>,
+    /// Range of the valid heights.
+    pub min: f32,
+    pub max: f32,
+    /// Value of missing samples, excluded from `min` and `max`.
+    pub nodata: Option<f32>,
+    /// Ground size of a sample in x and y, from the GeoTIFF model pixel
+    /// scale.
+    pub pixel_size: Option<[f64; 2]>,
+    /// Model coordinates of the top-left corner, from the GeoTIFF tie
+    /// point.
+    pub origin: Option<[f64; 2]>,
+}
+
+impl Heightmap {
+    pub fn new(
+        width: u32,
+        height: u32,
+        heights: Vec<f32>,
+        nodata: Option<f32>,
+    ) -> Result<Self, Error> {
+        if heights.len() != width as usize * height as usize {
+            return Err(Error::InvalidHeightmap(format!(
+                "{} heights for {width}x{height} samples",
+                heights.len()
+            )));
+        }
+        let (mut min, mut max) = (f32::INFINITY, f32::NEG_INFINITY);
+        for &h in &heights {
+            if !h.is_nan() && Some(h) != nodata {
+                (min, max) = (min.min(h), max.max(h));
+            }
+        }
+        if min > max {
+            (min, max) = (0.0, 0.0);
+        }
+        Ok(Self {
+            width,
+            height,
+            heights,
+            min,
+            max,
+            nodata,
+            pixel_size: None,
+            origin: None,
+        })
+    }
+
+    /// Reads headerless 16-bit unsigned samples, as written by most terrain
+    /// tools. The heights are the raw sample values.
+    pub fn from_raw16(
+        data: &[u8],
+        width: u32,
+        height: u32,
+        big_endian: bool,
+    ) -> Result<Self, Error> {
+        if data.len() as u64 != width as u64 * height as u64 * 2 {
+            return Err(Error::InvalidHeightmap(format!(
+                "{} bytes for {width}x{height} 16-bit samples",
+                data.len()
+            )));
+        }
+        let heights = data
+            .chunks_exact(2)
+            .map(|b| {
+                let bytes = [b[0], b[1]];
+                (if big_endian {
+                    u16::from_be_bytes(bytes)
+                } else {
+                    u16::from_le_bytes(bytes)
+                }) as f32
+            })
+            .collect();
+        Self::new(width, height, heights, None)
+    }
+
+    pub fn load_raw16(
+        path: impl AsRef<Path>,
+        width: u32,
+        height: u32,
+        big_endian: bool,
+    ) -> Result<Self, Error> {
+        Self::from_raw16(&std::fs::read(path)?, width, height, big_endian)
+    }
+
+    /// Reads the first image of a single-channel GeoTIFF with integer or
+    /// float samples, its pixel scale and tie point, and the GDAL nodata
+    /// value.
+    #[cfg(feature = "geotiff")]
+    pub fn from_geotiff(reader: impl std::io::Read + std::io::Seek) -> Result<Self, Error> {
+        use tiff::decoder::{Decoder, DecodingResult};
+        use tiff::tags::Tag;
+
+        let mut decoder = Decoder::new(reader)?;
+        let (width, height) = decoder.dimensions()?;
+        if !matches!(decoder.colortype()?, tiff::ColorType::Gray(_)) {
+            return Err(Error::InvalidHeightmap(
+                "geotiff has more than one channel".to_string(),
+            ));
+        }
+        let heights: Vec<f32> = match decoder.read_image()? {
+            DecodingResult::U8(v) => v.into_iter().map(f32::from).collect(),
+            DecodingResult::U16(v) => v.into_iter().map(f32::from).collect(),
+            DecodingResult::U32(v) => v.into_iter().map(|h| h as f32).collect(),
+            DecodingResult::U64(v) => v.into_iter().map(|h| h as f32).collect(),
+            DecodingResult::I8(v) => v.into_iter().map(f32::from).collect(),
+            DecodingResult::I16(v) => v.into_iter().map(f32::from).collect(),
+            DecodingResult::I32(v) => v.into_iter().map(|h| h as f32).collect(),
+            DecodingResult::I64(v) => v.into_iter().map(|h| h as f32).collect(),
+            DecodingResult::F16(v) => v.into_iter().map(|h| h.to_f32()).collect(),
+            DecodingResult::F32(v) => v,
+            DecodingResult::F64(v) => v.into_iter().map(|h| h as f32).collect(),
+        };
+        let nodata = decoder
+            .find_tag(Tag::GdalNodata)?
+            .map(|value| value.into_string())
+            .transpose()?
+            .and_then(|value| value.trim_end_matches('\0').trim().parse().ok());
+
+        let mut heightmap = Self::new(width, height, heights, nodata)?;
+        let scale = decoder
+            .find_tag(Tag::ModelPixelScaleTag)?
+            .map(|value| value.into_f64_vec())
+            .transpose()?;
+        let tie_point = decoder
+            .find_tag(Tag::ModelTiepointTag)?
+            .map(|value| value.into_f64_vec())
+            .transpose()?;
+        if let Some([sx, sy, ..]) = scale.as_deref() {
+            heightmap.pixel_size = Some([*sx, *sy]);
+            // Raster (i, j) maps to model (x, y); y grows upwards.
+            if let Some([i, j, _, x, y, ..]) = tie_point.as_deref() {
+                heightmap.origin = Some([x - i * sx, y + j * sy]);
+            }
+        }
+        Ok(heightmap)
+    }
+
+    #[cfg(feature = "geotiff")]
+    pub fn load_geotiff(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::from_geotiff(std::io::BufReader::new(std::fs::File::open(path)?))
+    }
+
+    /// Heights mapped from `min..max` to `0..1`, with missing samples at 0.
+    pub fn normalized(&self) -> Vec<f32> {
+        let range = self.max - self.min;
+        self.heights
+            .iter()
+            .map(|&h| {
+                if h.is_nan() || Some(h) == self.nodata || range <= 0.0 {
+                    0.0
+                } else {
+                    ((h - self.min) / range).clamp(0.0, 1.0)
+                }
+            })
+            .collect()
+    }
+}
+
+impl Context {
+    /// Uploads `heightmap` as `R32Float` heights, or as `R16Unorm` samples
+    /// `s` for heights `min + s * (max - min)` (which needs
+    /// `TEXTURE_FORMAT_16BIT_NORM`). Missing samples become `min`.
+    pub fn heightmap_texture(
+        &self,
+        heightmap: &Heightmap,
+        texture_format: &wgpu::TextureFormat,
+        label: Option<&str>,
+    ) -> Result<Texture, Error> {
+        let data: Vec<u8> = match texture_format {
+            wgpu::TextureFormat::R32Float => {
+                let heights: Vec<f32> = heightmap
+                    .heights
+                    .iter()
+                    .map(|&h| {
+                        if h.is_nan() || Some(h) == heightmap.nodata {
+                            heightmap.min
+                        } else {
+                            h
+                        }
+                    })
+                    .collect();
+                bytemuck::cast_slice(&heights).to_vec()
+            }
+            wgpu::TextureFormat::R16Unorm
+                if self
+                    .device
+                    .features()
+                    .contains(wgpu::Features::TEXTURE_FORMAT_16BIT_NORM) =>
+            {
+                let samples: Vec<u16> = heightmap
+                    .normalized()
+                    .iter()
+                    .map(|&s| (s * u16::MAX as f32).round() as u16)
+                    .collect();
+                bytemuck::cast_slice(&samples).to_vec()
+            }
+            _ => return Err(Error::UnsupportedTextureFormat(*texture_format)),
+        };
+        self.texture_with_data(
+            &data,
+            &heightmap.width,
+            &heightmap.height,
+            texture_format,
+            label,
+        )
+    }
+}
@@ -0,0 +1,98 @@
+use super::context::Context;
+use super::error::Error;
+use super::texture::Texture;
+use wgpu::core::hal_api::HalApi;
+
+// wgpu can't enable the external memory extensions itself, so importing a
+// DMA-BUF, IOSurface or DXGI shared handle is done by the caller on the raw
+// device (see `with_hal_device`), and the resulting hal texture is wrapped
+// here. Exporting works the other way around through `with_hal_texture`.
+
+impl Context {
+    /// Wraps a texture created through the hal backend `A`, e.g. a Vulkan
+    /// image bound to imported DMA-BUF memory or a Metal texture backed by an
+    /// IOSurface.
+    ///
+    /// # Safety
+    ///
+    /// `hal_texture` must have been created from this context's hal device
+    /// and must match `descriptor`.
+    pub unsafe fn texture_from_hal<A: HalApi>(
+        &self,
+        hal_texture: A::Texture,
+        descriptor: &wgpu::TextureDescriptor<'_>,
+    ) -> Result<Texture, Error> {
+        self.check_texture_size(&descriptor.size, descriptor.dimension)?;
+        let texture = unsafe {
+            self.device
+                .create_texture_from_hal::<A>(hal_texture, descriptor)
+        };
+        Ok(Texture::from_wgpu(self, texture))
+    }
+
+    /// # Safety
+    ///
+    /// `hal_buffer` must have been created from this context's hal device
+    /// and must match `descriptor`.
+    pub unsafe fn buffer_from_hal<A: HalApi>(
+        &self,
+        hal_buffer: A::Buffer,
+        descriptor: &wgpu::BufferDescriptor<'_>,
+    ) -> Result<wgpu::Buffer, Error> {
+        self.check_buffer_size(descriptor.size)?;
+        Ok(unsafe {
+            self.device
+                .create_buffer_from_hal::<A>(hal_buffer, descriptor)
+        })
+    }
+
+    /// Runs `operation` with the raw device, or returns `None` if the
+    /// context doesn't run on backend `A`.
+    ///
+    /// # Safety
+    ///
+    /// The raw device must not be destroyed, and any state changed through
+    /// it must stay consistent with what wgpu expects.
+    pub unsafe fn with_hal_device<A, F, R>(&self, operation: F) -> Option<R>
+    where
+        A: HalApi,
+        F: FnOnce(&A::Device) -> R,
+    {
+        if !self.is_backend::<A>() {
+            return None;
+        }
+        unsafe {
+            self.device
+                .as_hal::<A, _, _>(|device| device.map(operation))
+        }
+        .flatten()
+    }
+
+    /// Runs `operation` with the raw texture, e.g. to export its memory to
+    /// another process, or returns `None` if the context doesn't run on
+    /// backend `A`.
+    ///
+    /// # Safety
+    ///
+    /// `texture` must belong to this context, the raw texture must not be
+    /// destroyed, and the caller must synchronize external access with the
+    /// queue.
+    pub unsafe fn with_hal_texture<A, F, R>(&self, texture: &Texture, operation: F) -> Option<R>
+    where
+        A: HalApi,
+        F: FnOnce(&A::Texture) -> R,
+    {
+        if !self.is_backend::<A>() {
+            return None;
+        }
+        unsafe {
+            texture
+                .texture
+                .as_hal::<A, _, _>(|texture| texture.map(operation))
+        }
+    }
+
+    fn is_backend<A: HalApi>(&self) -> bool {
+        self.adapter.get_info().backend == A::VARIANT
+    }
+}
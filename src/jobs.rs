@@ -0,0 +1,244 @@
+use super::context::Context;
+use super::debug_material::DebugMaterialRenderer;
+use super::error::Error;
+use super::format::ImageLayout;
+use super::progress::CancellationToken;
+use super::readback::to_image;
+use super::scene::{Camera, Scene};
+use super::texture::Texture;
+use std::path::PathBuf;
+
+/// A frame of a `RenderJob` written to disk.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JobProgress {
+    pub frame: usize,
+    pub frames: usize,
+    pub path: PathBuf,
+}
+
+/// Renders a scene from a list of cameras headlessly and writes every frame
+/// to an image file, keeping up to `frames_in_flight` frames on the GPU
+/// while earlier ones are read back and encoded.
+pub struct RenderJob<'a> {
+    pub scene: &'a Scene,
+    pub cameras: Vec<Camera>,
+    pub width: u32,
+    pub height: u32,
+    pub format: wgpu::TextureFormat,
+    /// Path of each frame, with `{name}` replaced by `name` and `{frame}`
+    /// by the frame index padded to four digits. The extension picks the
+    /// image format, which has to hold `format`: PNG or TIFF for 8-bit
+    /// targets, EXR for float ones.
+    pub output: String,
+    pub name: String,
+    pub frames_in_flight: usize,
+    /// Stops the job with `Error::Cancelled` before its next frame, after
+    /// writing the frames in flight.
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl<'a> RenderJob<'a> {
+    /// A job rendering `scene` from `cameras` into `Rgba8UnormSrgb` frames,
+    /// two in flight at a time.
+    pub fn new(
+        scene: &'a Scene,
+        cameras: Vec<Camera>,
+        width: &u32,
+        height: &u32,
+        output: &str,
+    ) -> Self {
+        Self {
+            scene,
+            cameras,
+            width: *width,
+            height: *height,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            output: output.to_string(),
+            name: "frame".to_string(),
+            frames_in_flight: 2,
+            cancellation: None,
+        }
+    }
+
+    /// The path of `frame`, expanded from `output`.
+    pub fn path(&self, frame: usize) -> PathBuf {
+        PathBuf::from(
+            self.output
+                .replace("{name}", &self.name)
+                .replace("{frame}", &format!("{frame:04}")),
+        )
+    }
+
+    /// Renders the frames with `renderer`, whose `ops` should clear the
+    /// target and depth, calling `progress` after each frame is written.
+    pub fn run(
+        &self,
+        context: &Context,
+        renderer: &DebugMaterialRenderer,
+        progress: impl FnMut(&JobProgress),
+    ) -> Result<Vec<PathBuf>, Error> {
+        self.run_with(
+            context,
+            |encoder, scene, camera, target, depth| {
+                renderer.draw_scene(context, encoder, scene, camera, target, Some(depth))
+            },
+            progress,
+        )
+    }
+
+    /// Like `run`, with `render` recording each frame of the scene from the
+    /// camera into the target, with a `Texture::DEPTH_FORMAT` depth texture
+    /// of the same size. Targets are reused across frames, so `render` has
+    /// to clear them.
+    pub fn run_with(
+        &self,
+        context: &Context,
+        mut render: impl FnMut(
+            &mut wgpu::CommandEncoder,
+            &Scene,
+            &Camera,
+            &Texture,
+            &Texture,
+        ) -> Result<(), Error>,
+        mut progress: impl FnMut(&JobProgress),
+    ) -> Result<Vec<PathBuf>, Error> {
+        let size = wgpu::Extent3d {
+            width: self.width,
+            height: self.height,
+            depth_or_array_layers: 1,
+        };
+        let layout = ImageLayout::new(self.format, size)?;
+        let slots = (0..self.frames_in_flight.clamp(1, self.cameras.len().max(1)))
+            .map(|_| Slot::new(context, self, &layout))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let frames = self.cameras.len();
+        let mut paths = Vec::with_capacity(frames);
+        let mut finish = |slot: &Slot, (frame, submission)| -> Result<(), Error> {
+            let data = slot.read(context, &layout, submission)?;
+            let image = to_image(data, self.width, self.height, self.format)?;
+            let path = self.path(frame);
+            if let Some(parent) = path
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+            {
+                std::fs::create_dir_all(parent)?;
+            }
+            image.save(&path)?;
+            progress(&JobProgress {
+                frame,
+                frames,
+                path: path.clone(),
+            });
+            paths.push(path);
+            Ok(())
+        };
+
+        let mut pending: Vec<_> = slots.iter().map(|_| None).collect();
+        let mut cancelled = false;
+        for (frame, camera) in self.cameras.iter().enumerate() {
+            let index = frame % slots.len();
+            let slot = &slots[index];
+            if let Some(previous) = pending[index].take() {
+                finish(slot, previous)?;
+            }
+            cancelled = self
+                .cancellation
+                .as_ref()
+                .is_some_and(CancellationToken::is_cancelled);
+            if cancelled {
+                break;
+            }
+            let mut encoder =
+                context
+                    .device
+                    .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                        label: Some("Render Job Encoder"),
+                    });
+            render(&mut encoder, self.scene, camera, &slot.target, &slot.depth)?;
+            slot.copy(&mut encoder, &layout, size);
+            pending[index] = Some((frame, context.submit(encoder)));
+        }
+        for offset in 0..slots.len() {
+            let index = (frames + offset) % slots.len();
+            if let Some(previous) = pending[index].take() {
+                finish(&slots[index], previous)?;
+            }
+        }
+        match cancelled {
+            true => Err(Error::Cancelled),
+            false => Ok(paths),
+        }
+    }
+}
+
+/// The target, depth and readback buffer of one frame in flight.
+struct Slot {
+    target: Texture,
+    depth: Texture,
+    staging: wgpu::Buffer,
+}
+
+impl Slot {
+    fn new(context: &Context, job: &RenderJob, layout: &ImageLayout) -> Result<Self, Error> {
+        Ok(Self {
+            target: context.render_target(
+                &job.width,
+                &job.height,
+                &job.format,
+                Some("Render Job Target"),
+            )?,
+            depth: context.depth_texture(&job.width, &job.height, "Render Job Depth")?,
+            staging: context.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Render Job Readback Buffer"),
+                size: layout.padded_bytes_per_row() as u64 * layout.rows_per_image as u64,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+        })
+    }
+
+    fn copy(&self, encoder: &mut wgpu::CommandEncoder, layout: &ImageLayout, size: wgpu::Extent3d) {
+        encoder.copy_texture_to_buffer(
+            self.target.texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &self.staging,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(layout.padded_bytes_per_row()),
+                    rows_per_image: Some(layout.rows_per_image),
+                },
+            },
+            size,
+        );
+    }
+
+    /// Waits for the `submission` copying the slot's frame and reads it
+    /// back with rows tightly packed.
+    fn read(
+        &self,
+        context: &Context,
+        layout: &ImageLayout,
+        submission: wgpu::SubmissionIndex,
+    ) -> Result<Vec<u8>, Error> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let slice = self.staging.slice(..);
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        context
+            .device
+            .poll(wgpu::Maintain::WaitForSubmissionIndex(submission));
+        receiver
+            .recv()
+            .map_err(|_| Error::BufferMappingFailed(wgpu::BufferAsyncError))??;
+        let data = slice
+            .get_mapped_range()
+            .chunks(layout.padded_bytes_per_row() as usize)
+            .flat_map(|row| &row[..layout.bytes_per_row as usize])
+            .copied()
+            .collect();
+        self.staging.unmap();
+        Ok(data)
+    }
+}
@@ -0,0 +1,506 @@
+use super::context::Context;
+use super::error::Error;
+use super::mipmap::full_mip_level_count;
+use super::texture::Texture;
+use std::ops::Range;
+use std::path::Path;
+
+const IDENTIFIER: [u8; 12] = [
+    0xAB, b'K', b'T', b'X', b' ', b'2', b'0', 0xBB, b'\r', b'\n', 0x1A, b'\n',
+];
+const LEVEL_INDEX_OFFSET: usize = 80;
+
+/// The fields of a KTX2 header that describe the texture.
+struct Header {
+    vk_format: u32,
+    width: u32,
+    height: u32,
+    depth: u32,
+    layers: u32,
+    faces: u32,
+    levels: u32,
+    supercompression: u32,
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, Error> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or_else(|| Error::InvalidKtx2("truncated file".to_string()))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Result<u64, Error> {
+    data.get(offset..offset + 8)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or_else(|| Error::InvalidKtx2("truncated file".to_string()))
+}
+
+impl Header {
+    fn parse(data: &[u8]) -> Result<Self, Error> {
+        if !data.starts_with(&IDENTIFIER) {
+            return Err(Error::InvalidKtx2("missing KTX2 identifier".to_string()));
+        }
+        let field = |index: usize| read_u32(data, IDENTIFIER.len() + 4 * index);
+        Ok(Self {
+            vk_format: field(0)?,
+            width: field(2)?,
+            height: field(3)?,
+            depth: field(4)?,
+            layers: field(5)?,
+            faces: field(6)?,
+            levels: field(7)?,
+            supercompression: field(8)?,
+        })
+    }
+}
+
+/// The `wgpu::TextureFormat` of a Vulkan `VkFormat`, for the formats that
+/// have one.
+fn texture_format(vk_format: u32) -> Option<wgpu::TextureFormat> {
+    use wgpu::{AstcBlock, AstcChannel, TextureFormat as F};
+    let astc = |block, srgb| F::Astc {
+        block,
+        channel: match srgb {
+            true => AstcChannel::UnormSrgb,
+            false => AstcChannel::Unorm,
+        },
+    };
+    Some(match vk_format {
+        9 => F::R8Unorm,
+        10 => F::R8Snorm,
+        13 => F::R8Uint,
+        14 => F::R8Sint,
+        16 => F::Rg8Unorm,
+        17 => F::Rg8Snorm,
+        20 => F::Rg8Uint,
+        21 => F::Rg8Sint,
+        37 => F::Rgba8Unorm,
+        38 => F::Rgba8Snorm,
+        41 => F::Rgba8Uint,
+        42 => F::Rgba8Sint,
+        43 => F::Rgba8UnormSrgb,
+        44 => F::Bgra8Unorm,
+        50 => F::Bgra8UnormSrgb,
+        64 => F::Rgb10a2Unorm,
+        68 => F::Rgb10a2Uint,
+        70 => F::R16Unorm,
+        71 => F::R16Snorm,
+        74 => F::R16Uint,
+        75 => F::R16Sint,
+        76 => F::R16Float,
+        77 => F::Rg16Unorm,
+        78 => F::Rg16Snorm,
+        81 => F::Rg16Uint,
+        82 => F::Rg16Sint,
+        83 => F::Rg16Float,
+        91 => F::Rgba16Unorm,
+        92 => F::Rgba16Snorm,
+        95 => F::Rgba16Uint,
+        96 => F::Rgba16Sint,
+        97 => F::Rgba16Float,
+        98 => F::R32Uint,
+        99 => F::R32Sint,
+        100 => F::R32Float,
+        101 => F::Rg32Uint,
+        102 => F::Rg32Sint,
+        103 => F::Rg32Float,
+        107 => F::Rgba32Uint,
+        108 => F::Rgba32Sint,
+        109 => F::Rgba32Float,
+        122 => F::Rg11b10Float,
+        123 => F::Rgb9e5Ufloat,
+        // BC1 without alpha decodes the same as with it.
+        131 | 133 => F::Bc1RgbaUnorm,
+        132 | 134 => F::Bc1RgbaUnormSrgb,
+        135 => F::Bc2RgbaUnorm,
+        136 => F::Bc2RgbaUnormSrgb,
+        137 => F::Bc3RgbaUnorm,
+        138 => F::Bc3RgbaUnormSrgb,
+        139 => F::Bc4RUnorm,
+        140 => F::Bc4RSnorm,
+        141 => F::Bc5RgUnorm,
+        142 => F::Bc5RgSnorm,
+        143 => F::Bc6hRgbUfloat,
+        144 => F::Bc6hRgbFloat,
+        145 => F::Bc7RgbaUnorm,
+        146 => F::Bc7RgbaUnormSrgb,
+        147 => F::Etc2Rgb8Unorm,
+        148 => F::Etc2Rgb8UnormSrgb,
+        149 => F::Etc2Rgb8A1Unorm,
+        150 => F::Etc2Rgb8A1UnormSrgb,
+        151 => F::Etc2Rgba8Unorm,
+        152 => F::Etc2Rgba8UnormSrgb,
+        153 => F::EacR11Unorm,
+        154 => F::EacR11Snorm,
+        155 => F::EacRg11Unorm,
+        156 => F::EacRg11Snorm,
+        157..=184 => {
+            let block = [
+                AstcBlock::B4x4,
+                AstcBlock::B5x4,
+                AstcBlock::B5x5,
+                AstcBlock::B6x5,
+                AstcBlock::B6x6,
+                AstcBlock::B8x5,
+                AstcBlock::B8x6,
+                AstcBlock::B8x8,
+                AstcBlock::B10x5,
+                AstcBlock::B10x6,
+                AstcBlock::B10x8,
+                AstcBlock::B10x10,
+                AstcBlock::B12x10,
+                AstcBlock::B12x12,
+            ][(vk_format - 157) as usize / 2];
+            astc(block, vk_format.is_multiple_of(2))
+        }
+        _ => return None,
+    })
+}
+
+/// Where a stored mip level lies in the file and how its rows are laid out.
+#[derive(Debug, PartialEq)]
+struct Level {
+    range: Range<usize>,
+    bytes_per_row: u32,
+    rows_per_image: u32,
+}
+
+/// The texture a KTX2 file describes, checked against wgpu's rules and the
+/// file's length before any GPU object is created.
+#[derive(Debug, PartialEq)]
+struct Layout {
+    format: wgpu::TextureFormat,
+    dimension: wgpu::TextureDimension,
+    view_dimension: wgpu::TextureViewDimension,
+    size: wgpu::Extent3d,
+    /// The stored levels, one even when the file asks for generated mipmaps.
+    levels: Vec<Level>,
+    /// Whether the level count is 0, which asks for generated mipmaps.
+    generate_mipmaps: bool,
+}
+
+impl Layout {
+    fn parse(data: &[u8]) -> Result<Self, Error> {
+        let header = Header::parse(data)?;
+        if header.supercompression != 0 {
+            return Err(Error::InvalidKtx2(format!(
+                "unsupported supercompression scheme {}",
+                header.supercompression
+            )));
+        }
+        let format = texture_format(header.vk_format).ok_or_else(|| {
+            Error::InvalidKtx2(format!("unsupported vkFormat {}", header.vk_format))
+        })?;
+        if format.is_compressed() && header.depth > 0 {
+            return Err(Error::UnsupportedTextureFormat(format));
+        }
+
+        use wgpu::{TextureDimension, TextureViewDimension};
+        let (dimension, view_dimension) =
+            match (header.height, header.depth, header.faces, header.layers) {
+                (0, 0, 1, 0) => (TextureDimension::D1, TextureViewDimension::D1),
+                (1.., 0, 1, 0) => (TextureDimension::D2, TextureViewDimension::D2),
+                (1.., 0, 1, _) => (TextureDimension::D2, TextureViewDimension::D2Array),
+                (1.., 0, 6, 0) => (TextureDimension::D2, TextureViewDimension::Cube),
+                (1.., 0, 6, _) => (TextureDimension::D2, TextureViewDimension::CubeArray),
+                (1.., 1.., 1, 0) => (TextureDimension::D3, TextureViewDimension::D3),
+                _ => {
+                    return Err(Error::InvalidKtx2(
+                        "unsupported combination of dimensions, faces and layers".to_string(),
+                    ))
+                }
+            };
+        let size = wgpu::Extent3d {
+            width: header.width,
+            height: header.height.max(1),
+            depth_or_array_layers: match dimension {
+                TextureDimension::D3 => header.depth,
+                _ => header.layers.max(1).saturating_mul(header.faces),
+            },
+        };
+        let (width, height) = (size.width, size.height);
+        if width == 0 {
+            return Err(Error::InvalidKtx2("zero-sized texture".to_string()));
+        }
+        if header.faces == 6 && width != height {
+            return Err(Error::InvalidKtx2(format!(
+                "cube map faces of {width}x{height} are not square"
+            )));
+        }
+        if dimension == TextureDimension::D1 && (format.is_compressed() || header.levels > 1) {
+            return Err(Error::InvalidKtx2(
+                "1D textures cannot be compressed or have mip levels".to_string(),
+            ));
+        }
+        let (block_width, block_height) = format.block_dimensions();
+        if !width.is_multiple_of(block_width) || !height.is_multiple_of(block_height) {
+            return Err(Error::InvalidKtx2(format!(
+                "{width}x{height} is not a multiple of the {block_width}x{block_height} blocks of {format:?}"
+            )));
+        }
+        let stored_levels = header.levels.max(1);
+        if stored_levels > size.max_mips(dimension) {
+            return Err(Error::InvalidKtx2(format!(
+                "{stored_levels} mip levels for a {width}x{height} texture"
+            )));
+        }
+
+        let block_size = format
+            .block_copy_size(None)
+            .ok_or(Error::UnsupportedTextureFormat(format))?;
+        let levels = (0..stored_levels)
+            .map(|level| {
+                let entry = LEVEL_INDEX_OFFSET + 24 * level as usize;
+                let offset = read_u64(data, entry)?;
+                let length = read_u64(data, entry + 8)?;
+                let level_size = size.mip_level_size(level, dimension);
+                let bytes_per_row = level_size.width.div_ceil(block_width) * block_size;
+                let rows_per_image = level_size.height.div_ceil(block_height);
+                let required = bytes_per_row as u64
+                    * rows_per_image as u64
+                    * level_size.depth_or_array_layers as u64;
+                let range = offset
+                    .checked_add(required)
+                    .filter(|&end| length >= required && end <= data.len() as u64)
+                    .map(|end| offset as usize..end as usize)
+                    .ok_or_else(|| {
+                        Error::InvalidKtx2(format!("level {level} is out of bounds or too small"))
+                    })?;
+                Ok(Level {
+                    range,
+                    bytes_per_row,
+                    rows_per_image,
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(Self {
+            format,
+            dimension,
+            view_dimension,
+            size,
+            levels,
+            generate_mipmaps: header.levels == 0,
+        })
+    }
+}
+
+impl Context {
+    /// Creates a texture from a KTX2 container, with every mip level, array
+    /// layer and cube face it holds, in the format given by its `vkFormat`.
+    /// Cube maps are viewed as cubes (or cube arrays), arrays as 2D arrays
+    /// and volumes in 3D. A level count of 0 asks for generated mipmaps,
+    /// which needs a renderable, filterable format. Supercompressed files
+    /// (Basis Universal, zstd) are not supported.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self, data), fields(bytes = data.len()), err)
+    )]
+    pub fn texture_from_ktx2(&self, data: &[u8], label: Option<&str>) -> Result<Texture, Error> {
+        let Layout {
+            format,
+            dimension,
+            view_dimension,
+            size,
+            levels,
+            generate_mipmaps,
+        } = Layout::parse(data)?;
+        if !self.device.features().contains(format.required_features()) {
+            return Err(Error::UnsupportedTextureFormat(format));
+        }
+        self.check_texture_size(&size, dimension)?;
+
+        let generate_mipmaps = generate_mipmaps
+            && dimension == wgpu::TextureDimension::D2
+            && self.check_mipmap_format(format).is_ok();
+        let mip_level_count = match generate_mipmaps {
+            true => full_mip_level_count(size.width, size.height),
+            false => levels.len() as u32,
+        };
+        let usage = wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::COPY_DST
+            | wgpu::TextureUsages::COPY_SRC;
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size,
+            mip_level_count,
+            sample_count: 1,
+            dimension,
+            format,
+            view_formats: &[format],
+            usage: match generate_mipmaps {
+                true => usage | wgpu::TextureUsages::RENDER_ATTACHMENT,
+                false => usage,
+            },
+        });
+
+        for (level, layout) in (0..).zip(levels) {
+            self.queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    aspect: wgpu::TextureAspect::All,
+                    texture: &texture,
+                    mip_level: level,
+                    origin: wgpu::Origin3d::ZERO,
+                },
+                &data[layout.range],
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(layout.bytes_per_row),
+                    rows_per_image: Some(layout.rows_per_image),
+                },
+                size.mip_level_size(level, dimension).physical_size(format),
+            );
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(view_dimension),
+            ..Default::default()
+        });
+        let sampler = self.texture_sampler();
+        #[cfg(feature = "tracing")]
+        tracing::debug!("created texture");
+
+        let texture = Texture::from_raw(texture, view, sampler).with_view_dimension(view_dimension);
+        if generate_mipmaps {
+            texture.generate_mipmaps(self)?;
+        }
+        Ok(texture)
+    }
+
+    pub fn load_ktx2(&self, path: impl AsRef<Path>, label: Option<&str>) -> Result<Texture, Error> {
+        self.texture_from_ktx2(&std::fs::read(path)?, label)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wgpu::{TextureDimension as D, TextureFormat as F, TextureViewDimension as V};
+
+    const RGBA8: u32 = 37;
+    const BC1: u32 = 131;
+    const BC7: u32 = 145;
+
+    /// A file with `levels` stored back to back after the level index, each
+    /// of the given length.
+    fn ktx2(vk_format: u32, size: [u32; 3], layers: u32, faces: u32, levels: &[u64]) -> Vec<u8> {
+        let mut data = IDENTIFIER.to_vec();
+        let fields = [vk_format, 1, size[0], size[1], size[2], layers, faces];
+        for word in fields.into_iter().chain([levels.len() as u32, 0]) {
+            data.extend(word.to_le_bytes());
+        }
+        data.resize(LEVEL_INDEX_OFFSET, 0);
+        let mut offset = (LEVEL_INDEX_OFFSET + 24 * levels.len()) as u64;
+        for &length in levels {
+            for word in [offset, length, length] {
+                data.extend(word.to_le_bytes());
+            }
+            offset += length;
+        }
+        data.resize(offset as usize, 0);
+        data
+    }
+
+    fn invalid(data: &[u8]) -> String {
+        match Layout::parse(data) {
+            Err(Error::InvalidKtx2(message)) => message,
+            other => panic!("expected an invalid KTX2 file, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn headers() {
+        let layout = Layout::parse(&ktx2(BC1, [16, 8, 0], 0, 1, &[64, 16, 8])).unwrap();
+        assert_eq!(
+            (layout.format, layout.dimension, layout.view_dimension),
+            (F::Bc1RgbaUnorm, D::D2, V::D2)
+        );
+        assert_eq!(
+            layout.size,
+            wgpu::Extent3d {
+                width: 16,
+                height: 8,
+                depth_or_array_layers: 1
+            }
+        );
+        assert_eq!(layout.levels.len(), 3);
+        assert!(!layout.generate_mipmaps);
+
+        // A level count of 0 stores level 0 and asks for the rest.
+        let mut generated = ktx2(RGBA8, [4, 4, 0], 3, 1, &[192]);
+        generated[40..44].copy_from_slice(&0u32.to_le_bytes());
+        let layout = Layout::parse(&generated).unwrap();
+        assert_eq!(layout.view_dimension, V::D2Array);
+        assert_eq!(layout.size.depth_or_array_layers, 3);
+        assert!(layout.generate_mipmaps);
+
+        let layout = Layout::parse(&ktx2(RGBA8, [4, 4, 0], 0, 6, &[384])).unwrap();
+        assert_eq!(layout.view_dimension, V::Cube);
+        let layout = Layout::parse(&ktx2(RGBA8, [4, 4, 0], 2, 6, &[768])).unwrap();
+        assert_eq!(layout.view_dimension, V::CubeArray);
+        assert_eq!(layout.size.depth_or_array_layers, 12);
+
+        let layout = Layout::parse(&ktx2(RGBA8, [4, 4, 2], 0, 1, &[128])).unwrap();
+        assert_eq!((layout.dimension, layout.view_dimension), (D::D3, V::D3));
+        let layout = Layout::parse(&ktx2(RGBA8, [5, 0, 0], 0, 1, &[20])).unwrap();
+        assert_eq!((layout.dimension, layout.size.height), (D::D1, 1));
+    }
+
+    #[test]
+    fn level_index() {
+        let layout = Layout::parse(&ktx2(RGBA8, [4, 2, 0], 0, 1, &[32, 8, 4])).unwrap();
+        let start = LEVEL_INDEX_OFFSET + 72;
+        assert_eq!(
+            layout.levels,
+            [
+                Level {
+                    range: start..start + 32,
+                    bytes_per_row: 16,
+                    rows_per_image: 2
+                },
+                Level {
+                    range: start + 32..start + 40,
+                    bytes_per_row: 8,
+                    rows_per_image: 1
+                },
+                Level {
+                    range: start + 40..start + 44,
+                    bytes_per_row: 4,
+                    rows_per_image: 1
+                },
+            ]
+        );
+
+        let short = ktx2(RGBA8, [4, 2, 0], 0, 1, &[31]);
+        assert!(invalid(&short).contains("level 0"));
+        let mut truncated = ktx2(RGBA8, [4, 2, 0], 0, 1, &[32, 8]);
+        truncated.pop();
+        assert!(invalid(&truncated).contains("level 1"));
+        let mut overflowing = ktx2(RGBA8, [4, 2, 0], 0, 1, &[32]);
+        overflowing[LEVEL_INDEX_OFFSET..LEVEL_INDEX_OFFSET + 8]
+            .copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(invalid(&overflowing).contains("level 0"));
+        let mut missing_index = ktx2(RGBA8, [4, 2, 0], 0, 1, &[32]);
+        missing_index.truncate(LEVEL_INDEX_OFFSET + 4);
+        assert_eq!(invalid(&missing_index), "truncated file");
+    }
+
+    #[test]
+    fn malformed_headers() {
+        assert!(invalid(b"KTX 20").contains("identifier"));
+        let mut supercompressed = ktx2(RGBA8, [4, 4, 0], 0, 1, &[64]);
+        supercompressed[44..48].copy_from_slice(&2u32.to_le_bytes());
+        assert!(invalid(&supercompressed).contains("supercompression"));
+        assert!(invalid(&ktx2(1, [4, 4, 0], 0, 1, &[64])).contains("vkFormat"));
+        assert!(invalid(&ktx2(RGBA8, [0, 4, 0], 0, 1, &[0])).contains("zero-sized"));
+        assert!(invalid(&ktx2(RGBA8, [4, 4, 0], 0, 4, &[64])).contains("combination"));
+        assert!(invalid(&ktx2(RGBA8, [8, 4, 0], 0, 6, &[768])).contains("not square"));
+        assert!(invalid(&ktx2(BC1, [30, 30, 0], 0, 1, &[512])).contains("4x4 blocks"));
+        assert!(invalid(&ktx2(BC7, [1, 1, 0], 0, 1, &[16])).contains("4x4 blocks"));
+        assert!(invalid(&ktx2(BC1, [8, 0, 0], 0, 1, &[16])).contains("1D"));
+        assert!(invalid(&ktx2(RGBA8, [8, 0, 0], 0, 1, &[32, 16])).contains("1D"));
+        assert!(invalid(&ktx2(RGBA8, [4, 4, 0], 0, 1, &[64, 16, 4, 4])).contains("mip levels"));
+        assert!(matches!(
+            Layout::parse(&ktx2(BC1, [4, 4, 4], 0, 1, &[32])),
+            Err(Error::UnsupportedTextureFormat(F::Bc1RgbaUnorm))
+        ));
+    }
+}
@@ -0,0 +1,402 @@
+use super::atomics::{atomic_u64_wgsl, read_atomic_u64, ATOMIC_U64_WGSL};
+use super::context::Context;
+use super::diagnostics::{describe_buffer, describe_texture, PassRecord};
+use super::error::Error;
+use super::texture::Texture;
+use wgpu::util::DeviceExt;
+
+const WORKGROUP_SIZE: u32 = 8;
+const DIAGONAL: u32 = 1;
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    size: [u32; 2],
+    threshold: f32,
+    flags: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct ComponentStats {
+    area: u32,
+    root: u32,
+    inverted_min_x: u32,
+    inverted_min_y: u32,
+    max_x: u32,
+    max_y: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Component {
+    /// Value of the component's pixels in the label texture.
+    pub label: u32,
+    /// Pixel count.
+    pub area: u32,
+    /// Inclusive bounding box corners.
+    pub min: [u32; 2],
+    pub max: [u32; 2],
+    /// Mean of the pixel coordinates.
+    pub centroid: [f32; 2],
+}
+
+pub struct ComponentLabels {
+    /// `R32Uint` labels, 0 for the background and 1 to the component count
+    /// in the order of each component's first pixel in rows from the top.
+    pub labels: Texture,
+    pub components: Vec<Component>,
+}
+
+/// Connected-component labeling of binary masks with a GPU union-find:
+/// neighbors are linked with atomic minimums, paths are compressed, and
+/// the roots are numbered.
+pub struct ConnectedComponents {
+    link_layout: wgpu::BindGroupLayout,
+    stats_layout: wgpu::BindGroupLayout,
+    init: wgpu::ComputePipeline,
+    link: wgpu::ComputePipeline,
+    compress: wgpu::ComputePipeline,
+    enumerate: wgpu::ComputePipeline,
+    accumulate: wgpu::ComputePipeline,
+    relabel: wgpu::ComputePipeline,
+}
+
+impl ConnectedComponents {
+    pub fn new(context: &Context) -> Self {
+        let device = &context.device;
+        let source = ATOMIC_U64_WGSL.to_owned()
+            + &atomic_u64_wgsl("sums")
+            + include_str!("shaders/labeling.wgsl");
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("labeling.wgsl"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let entry = |binding, ty| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty,
+            count: None,
+        };
+        let storage = |read_only| wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        };
+        let uniform = wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        };
+        let link_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Labeling Link Bind Group Layout"),
+            entries: &[
+                entry(0, uniform),
+                entry(
+                    1,
+                    wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                ),
+                entry(2, storage(false)),
+                entry(3, storage(false)),
+                entry(4, storage(false)),
+            ],
+        });
+        let stats_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Labeling Stats Bind Group Layout"),
+            entries: &[
+                entry(0, uniform),
+                entry(2, storage(false)),
+                entry(3, storage(false)),
+                entry(5, storage(false)),
+                entry(6, storage(false)),
+                entry(7, storage(true)),
+                entry(
+                    8,
+                    wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: wgpu::TextureFormat::R32Uint,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                ),
+            ],
+        });
+        let pipeline = |layout: &wgpu::BindGroupLayout, entry_point: &str| {
+            let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Labeling Pipeline Layout"),
+                bind_group_layouts: &[layout],
+                push_constant_ranges: &[],
+            });
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &module,
+                entry_point,
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            })
+        };
+
+        Self {
+            init: pipeline(&link_layout, "init"),
+            link: pipeline(&link_layout, "link"),
+            compress: pipeline(&link_layout, "compress"),
+            enumerate: pipeline(&link_layout, "enumerate"),
+            accumulate: pipeline(&stats_layout, "accumulate"),
+            relabel: pipeline(&stats_layout, "relabel"),
+            link_layout,
+            stats_layout,
+        }
+    }
+
+    /// Labels the pixels of the 2D float `mask` whose red channel is above
+    /// `threshold`, connecting edge neighbors and, if `diagonal`, corner
+    /// neighbors. Waits for the GPU twice, for the component count and the
+    /// statistics.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, context, mask),
+            fields(width = mask.width(), height = mask.height()),
+            err
+        )
+    )]
+    pub fn label(
+        &self,
+        context: &Context,
+        mask: &Texture,
+        threshold: f32,
+        diagonal: bool,
+    ) -> Result<ComponentLabels, Error> {
+        if !matches!(
+            mask.format().sample_type(None, None),
+            Some(wgpu::TextureSampleType::Float { .. })
+        ) || mask.view_dimension() != wgpu::TextureViewDimension::D2
+        {
+            return Err(Error::UnsupportedTextureFormat(mask.format()));
+        }
+        let (width, height) = (mask.width(), mask.height());
+        let pixel_buffer_size = width as u64 * height as u64 * 4;
+        context.check_buffer_size(pixel_buffer_size)?;
+        context.check_binding_size(pixel_buffer_size, true)?;
+
+        let device = &context.device;
+        let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Labeling Params"),
+            contents: bytemuck::bytes_of(&Params {
+                size: [width, height],
+                threshold,
+                flags: if diagonal { DIAGONAL } else { 0 },
+            }),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let storage = |label, size: u64, usage| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size,
+                usage: wgpu::BufferUsages::STORAGE | usage,
+                mapped_at_creation: false,
+            })
+        };
+        let parents = storage(
+            "Labeling Parents",
+            pixel_buffer_size,
+            wgpu::BufferUsages::empty(),
+        );
+        let slots = storage(
+            "Labeling Slots",
+            pixel_buffer_size,
+            wgpu::BufferUsages::empty(),
+        );
+        let count = storage(
+            "Labeling Component Count",
+            4,
+            wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+        );
+        let link_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Labeling Link Bind Group"),
+            layout: &self.link_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&mask.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: parents.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: slots.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: count.as_entire_binding(),
+                },
+            ],
+        });
+        let encoder = || {
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Labeling Encoder"),
+            })
+        };
+        let dispatch = |encoder: &mut wgpu::CommandEncoder,
+                        bind_group: &wgpu::BindGroup,
+                        pipelines: &[&wgpu::ComputePipeline]| {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Labeling"),
+                timestamp_writes: None,
+            });
+            pass.set_bind_group(0, bind_group, &[]);
+            for pipeline in pipelines {
+                pass.set_pipeline(pipeline);
+                pass.dispatch_workgroups(
+                    width.div_ceil(WORKGROUP_SIZE),
+                    height.div_ceil(WORKGROUP_SIZE),
+                    1,
+                );
+            }
+        };
+
+        context.diagnostics.record_pass(|| {
+            PassRecord::new(
+                "Labeling",
+                "link",
+                vec![
+                    describe_texture("mask", &mask.texture),
+                    describe_buffer("parents", &parents),
+                ],
+            )
+        });
+        let mut command_encoder = encoder();
+        dispatch(
+            &mut command_encoder,
+            &link_group,
+            &[&self.init, &self.link, &self.compress, &self.enumerate],
+        );
+        context.submit(command_encoder);
+        let component_count: u32 = bytemuck::pod_read_unaligned(&context.read_buffer(&count)?);
+
+        // Zero-sized bindings are invalid, so keep room for one component.
+        let slot_count = component_count.max(1) as u64;
+        let stats_size = slot_count * std::mem::size_of::<ComponentStats>() as u64;
+        context.check_buffer_size(stats_size)?;
+        let stats = storage("Labeling Stats", stats_size, wgpu::BufferUsages::COPY_SRC);
+        let sums = storage(
+            "Labeling Coordinate Sums",
+            slot_count * 16,
+            wgpu::BufferUsages::COPY_SRC,
+        );
+        let remap = storage(
+            "Labeling Remap",
+            slot_count * 4,
+            wgpu::BufferUsages::COPY_DST,
+        );
+        let labels = Texture::from_wgpu(
+            context,
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Component Labels"),
+                size: mask.size(),
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::R32Uint,
+                view_formats: &[],
+                usage: wgpu::TextureUsages::STORAGE_BINDING
+                    | wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::COPY_SRC,
+            }),
+        );
+        let stats_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Labeling Stats Bind Group"),
+            layout: &self.stats_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: parents.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: slots.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: stats.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 6,
+                    resource: sums.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 7,
+                    resource: remap.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 8,
+                    resource: wgpu::BindingResource::TextureView(&labels.view),
+                },
+            ],
+        });
+
+        let mut command_encoder = encoder();
+        dispatch(&mut command_encoder, &stats_group, &[&self.accumulate]);
+        context.submit(command_encoder);
+        let raw_stats: Vec<ComponentStats> =
+            bytemuck::pod_collect_to_vec(&context.read_buffer(&stats)?);
+        let raw_sums = read_atomic_u64(&bytemuck::pod_collect_to_vec::<u8, u32>(
+            &context.read_buffer(&sums)?,
+        ));
+
+        // Number the components in scanline order of their roots, which are
+        // their first pixels.
+        let mut order: Vec<usize> = (0..component_count as usize).collect();
+        order.sort_unstable_by_key(|&slot| raw_stats[slot].root);
+        let mut remap_data = vec![0u32; slot_count as usize];
+        let components = order
+            .iter()
+            .enumerate()
+            .map(|(index, &slot)| {
+                let label = index as u32 + 1;
+                remap_data[slot] = label;
+                let stats = &raw_stats[slot];
+                let area = stats.area.max(1) as f64;
+                Component {
+                    label,
+                    area: stats.area,
+                    min: [!stats.inverted_min_x, !stats.inverted_min_y],
+                    max: [stats.max_x, stats.max_y],
+                    centroid: [
+                        (raw_sums[2 * slot] as f64 / area) as f32,
+                        (raw_sums[2 * slot + 1] as f64 / area) as f32,
+                    ],
+                }
+            })
+            .collect();
+        context
+            .queue
+            .write_buffer(&remap, 0, bytemuck::cast_slice(&remap_data));
+
+        context.diagnostics.record_pass(|| {
+            PassRecord::new(
+                "Labeling",
+                "relabel",
+                vec![describe_texture("labels", &labels.texture)],
+            )
+        });
+        let mut command_encoder = encoder();
+        dispatch(&mut command_encoder, &stats_group, &[&self.relabel]);
+        context.submit(command_encoder);
+
+        Ok(ComponentLabels { labels, components })
+    }
+}
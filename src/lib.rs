@@ -1,7 +1,227 @@
+mod ambient_occlusion;
+#[cfg(feature = "image")]
+mod animated_texture;
+mod animation;
+mod aov;
+mod arena;
+mod atlas;
+mod atomics;
+mod bench;
+mod blit;
+mod budget;
+mod buffer;
+mod builder;
+mod bvh;
+#[cfg(feature = "scene")]
+mod camera_path;
+#[cfg(feature = "image")]
+mod capture;
+mod chunking;
+mod clock;
+mod compare;
 mod context;
+mod coordinates;
+mod cubemap;
+#[cfg(feature = "dds")]
+mod dds;
+mod debug_material;
+mod deletion;
+mod denoise;
+mod depth;
+mod diagnostics;
 mod error;
+mod fence;
+#[cfg(feature = "ffi")]
+mod ffi;
+mod fft;
+mod fixed_resolution;
+mod float16;
+mod flow;
+mod format;
+#[cfg(feature = "scene")]
+mod gltf;
+mod half_resolution;
+mod heightmap;
+#[cfg(all(feature = "interop", not(target_arch = "wasm32")))]
+mod interop;
+#[cfg(feature = "scene")]
+mod jobs;
+#[cfg(feature = "ktx2")]
+mod ktx2;
+mod labeling;
+#[cfg(feature = "scene")]
+mod lightmap;
+mod limits;
+mod math;
+mod mesh;
+mod mesh_validation;
+mod mip_visualization;
+mod mipmap;
+mod msaa;
+mod normal_lines;
+mod normal_map;
+mod obj;
+mod paint;
+mod pass_ops;
+mod ply;
+mod poller;
+mod probe;
+mod progress;
+#[cfg(feature = "python")]
+mod python;
+mod readback;
+mod reduced;
+mod render_target;
+mod report;
+mod runtime;
+mod sampler;
+#[cfg(feature = "scene")]
+mod scene;
+mod screen;
+mod selection;
+#[cfg(feature = "shadertoy")]
+mod shadertoy;
+mod stencil;
+mod stl;
+mod subgroup;
+mod temporal;
+mod texel_bake;
 mod texture;
+mod texture_builder;
+mod texture_pool;
+mod thread_safety;
+mod transfer;
+mod tuning;
+mod tween;
+mod unwrap;
+mod upload;
+mod upscale;
+#[cfg(feature = "usd")]
+mod usd;
+mod uv_inspection;
+mod video;
+mod viewport;
+#[cfg(all(
+    feature = "openxr",
+    any(
+        windows,
+        all(
+            unix,
+            not(target_os = "emscripten"),
+            not(target_os = "ios"),
+            not(target_os = "macos")
+        )
+    )
+))]
+mod xr;
 
+pub use wgpu;
+
+pub use half;
+#[cfg(feature = "image")]
+pub use image;
+#[cfg(feature = "nalgebra")]
+pub use nalgebra;
+
+pub use ambient_occlusion::{AmbientOcclusionBaker, AmbientOcclusionSettings};
+#[cfg(feature = "image")]
+pub use animated_texture::AnimatedTexture;
+pub use animation::{
+    AnimationClip, AnimationEvent, AnimationPlayer, Channel, ChannelValue, Interpolation,
+    Keyframes, PlaybackMode, Pose, Property,
+};
+pub use aov::{Aov, AovTargets};
+pub use arena::{ArenaAllocation, BufferArena};
+pub use atlas::{AtlasRegion, TextureAtlas};
+pub use atomics::{atomic_u64_wgsl, read_atomic_u64, ATOMIC_U64_WGSL};
+pub use bench::{BenchReport, GpuBench, TimingSource};
+pub use blit::Blitter;
+pub use budget::{BudgetPolicy, Downgrade};
+pub use builder::ContextBuilder;
+#[cfg(feature = "scene")]
+pub use camera_path::CameraPath;
+#[cfg(feature = "image")]
+pub use capture::{CaptureDiff, FrameCapture, PassDiff};
+pub use chunking::{BufferChunk, ChunkedBuffer, DispatchChunk};
+pub use clock::Clock;
+pub use compare::{ComparisonReport, TextureComparer};
 pub use context::Context;
+pub use coordinates::{
+    CoordinateConversion, CoordinateSystem, Handedness, ImportOptions, LengthUnit, UpAxis,
+};
+pub use debug_material::{DebugMaterial, DebugMaterialRenderer};
+pub use deletion::{DeletionQueue, Retired};
+pub use denoise::{DenoiseHistory, DenoiseInputs, Denoiser};
+pub use depth::{Colormap, DepthRange, DepthVisualizer, LOGARITHMIC_DEPTH_WGSL};
+pub use diagnostics::{PassRecord, SubmissionRecord};
 pub use error::Error;
+pub use fence::{FrameLimiter, GpuFence};
+pub use fft::{Fft, FftDirection, FftShape};
+pub use fixed_resolution::{FixedResolution, ScalingMode};
+pub use float16::PACKED_F16_WGSL;
+pub use flow::OpticalFlow;
+pub use format::{BlockInfo, ColorSpace, ImageLayout};
+pub use half_resolution::HalfResolution;
+pub use heightmap::Heightmap;
+#[cfg(feature = "scene")]
+pub use jobs::{JobProgress, RenderJob};
+pub use labeling::{Component, ComponentLabels, ConnectedComponents};
+#[cfg(feature = "scene")]
+pub use lightmap::{LightmapBaker, LightmapSettings};
+pub use math::{Aabb, Frustum, Plane, Ray};
+pub use mesh::{GpuMesh, Mesh, Vertex};
+pub use mesh_validation::MeshReport;
+pub use mip_visualization::{MipView, MipVisualizer, MIP_LEVEL_WGSL};
+pub use normal_lines::{NormalLineSettings, NormalVisualizer, TangentFrames};
+pub use normal_map::NormalMapGenerator;
+pub use paint::{BlendMode, Brush, TexturePainter};
+pub use pass_ops::PassOps;
+pub use ply::PlyFormat;
+#[cfg(not(target_arch = "wasm32"))]
+pub use poller::BackgroundPoller;
+pub use poller::PollingStrategy;
+pub use probe::{EnvironmentProbe, ProbeFace, ENVIRONMENT_PROBE_WGSL};
+pub use progress::{CancellationToken, Progress, ProgressUpdate};
+pub use reduced::{ReducedRendering, ReducedRenderingMode, REDUCED_RENDERING_WGSL};
+pub use render_target::RenderTargetBuilder;
+pub use report::{ContextReport, FormatReport};
+pub use runtime::spawn_blocking;
+pub use sampler::SamplerBuilder;
+#[cfg(feature = "scene")]
+pub use scene::{
+    Camera, Environment, Light, MaterialDescription, MeshDescription, MeshSource,
+    ObjectDescription, Scene, SceneDescription,
+};
+pub use screen::{ResizeRegistry, ScreenTexture};
+pub use selection::{MagicWand, SelectionOptions};
+#[cfg(feature = "shadertoy")]
+pub use shadertoy::{ShaderToy, ShaderToyInputs, ShaderToySource};
+pub use stencil::{StencilMask, StencilTest};
+pub use stl::StlFormat;
+pub use subgroup::{ReduceOp, SubgroupSupport, WgslScalar, WorkgroupPrimitives};
+pub use temporal::TemporalResources;
 pub use texture::Texture;
+pub use texture_builder::TextureBuilder;
+pub use texture_pool::TexturePool;
+pub use tuning::{with_workgroup_size, WorkgroupTuner};
+pub use tween::{Easing, Rotation, SrgbColor, Tween, Tweenable};
+pub use upload::{UploadHeap, UploadStrategy};
+pub use upscale::SpatialUpscaler;
+#[cfg(feature = "usd")]
+pub use usd::UsdImport;
+pub use uv_inspection::{UvChannel, UvInspector};
+pub use video::{VideoTexture, YuvFormat, YuvMatrix, YuvPlane, YuvRange};
+pub use viewport::Viewport;
+#[cfg(all(
+    feature = "openxr",
+    any(
+        windows,
+        all(
+            unix,
+            not(target_os = "emscripten"),
+            not(target_os = "ios"),
+            not(target_os = "macos")
+        )
+    )
+))]
+pub use xr::{xr_swapchain_format, xr_vk_format};
@@ -0,0 +1,335 @@
+use super::bvh::Bvh;
+use super::context::Context;
+use super::diagnostics::{describe_buffer, PassRecord};
+use super::error::Error;
+use super::mesh::Mesh;
+use super::progress::Progress;
+use super::scene::Light;
+use super::texel_bake::{TexelBake, TexelPost};
+use super::texture::Texture;
+use wgpu::util::DeviceExt;
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    size: [u32; 2],
+    sample_index: u32,
+    bounces: u32,
+    light_count: u32,
+    bias: f32,
+    _padding: [u32; 2],
+    sky: [f32; 4],
+    albedo: [f32; 4],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct GpuLight {
+    position: [f32; 4],
+    color: [f32; 4],
+}
+
+impl From<&Light> for GpuLight {
+    fn from(light: &Light) -> Self {
+        match light {
+            Light::Directional {
+                direction,
+                color,
+                intensity,
+            } => GpuLight {
+                position: [direction[0], direction[1], direction[2], 0.0],
+                color: [
+                    color[0] * intensity,
+                    color[1] * intensity,
+                    color[2] * intensity,
+                    0.0,
+                ],
+            },
+            Light::Point {
+                position,
+                color,
+                intensity,
+                range,
+            } => GpuLight {
+                position: [position[0], position[1], position[2], 1.0],
+                color: [
+                    color[0] * intensity,
+                    color[1] * intensity,
+                    color[2] * intensity,
+                    range.unwrap_or(0.0),
+                ],
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LightmapSettings {
+    /// Width and height of the lightmap in texels.
+    pub resolution: u32,
+    /// Paths traced per texel.
+    pub samples: u32,
+    /// Diffuse bounces after the first hit, 0 for direct and sky light only.
+    pub bounces: u32,
+    /// Linear diffuse color of every surface for the bounces.
+    pub albedo: [f32; 3],
+    /// Linear radiance of rays that escape the scene.
+    pub sky_color: [f32; 3],
+    /// Texels between charts when unwrapping.
+    pub padding: u32,
+    /// Texels the charts are grown by to hide seams when filtering.
+    pub dilation: u32,
+}
+
+impl Default for LightmapSettings {
+    fn default() -> Self {
+        Self {
+            resolution: 256,
+            samples: 64,
+            bounces: 1,
+            albedo: [0.5; 3],
+            sky_color: [0.0; 3],
+            padding: 2,
+            dilation: 2,
+        }
+    }
+}
+
+/// Offline lightmap baking with a GPU path tracer over a BVH of the scene.
+/// Texels are rasterized on the CPU in `uv2` space, each sample pass
+/// traces one path per texel, and the result is dilated over chart borders.
+pub struct LightmapBaker {
+    trace_layout: wgpu::BindGroupLayout,
+    bvh_layout: wgpu::BindGroupLayout,
+    trace: wgpu::ComputePipeline,
+    post: TexelPost,
+}
+
+impl LightmapBaker {
+    pub fn new(context: &Context) -> Self {
+        let device = &context.device;
+        let source = include_str!("shaders/raytracing.wgsl").to_string()
+            + include_str!("shaders/lightmap.wgsl");
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("lightmap.wgsl"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let entry = |binding, ty| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty,
+            count: None,
+        };
+        let storage = |read_only| wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        };
+        let uniform = wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        };
+        let trace_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Lightmap Trace Bind Group Layout"),
+            entries: &[
+                entry(0, uniform),
+                entry(1, storage(true)),
+                entry(2, storage(true)),
+                entry(3, storage(true)),
+                entry(4, storage(false)),
+            ],
+        });
+        let bvh_layout = Bvh::bind_group_layout(context);
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Lightmap Pipeline Layout"),
+            bind_group_layouts: &[&trace_layout, &bvh_layout],
+            push_constant_ranges: &[],
+        });
+
+        Self {
+            trace: device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("trace"),
+                layout: Some(&pipeline_layout),
+                module: &module,
+                entry_point: "trace",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            post: TexelPost::new(context),
+            trace_layout,
+            bvh_layout,
+        }
+    }
+
+    /// Bakes the light reaching `mesh` into an `Rgba16Float` lightmap over
+    /// its `uv2`, which is generated with `Mesh::unwrap_lightmap_uvs` if the
+    /// mesh has none. `occluders` cast shadows and bounce light along with
+    /// the mesh itself, all in the same world space. The lightmap holds
+    /// irradiance over π, so a diffuse surface's outgoing radiance is its
+    /// albedo times the lightmap, and alpha is 1 where it is covered.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(resolution = settings.resolution, samples = settings.samples),
+            err
+        )
+    )]
+    pub fn bake(
+        &self,
+        context: &Context,
+        mesh: &mut Mesh,
+        occluders: &[&Mesh],
+        lights: &[Light],
+        settings: &LightmapSettings,
+    ) -> Result<Texture, Error> {
+        self.bake_with_progress(
+            context,
+            mesh,
+            occluders,
+            lights,
+            settings,
+            &mut Progress::new(),
+        )
+    }
+
+    /// Like `bake`, reporting the "bvh" build and the "lightmap" samples
+    /// finished on the GPU to `progress`, and stopping with
+    /// `Error::Cancelled` once its token is cancelled.
+    pub fn bake_with_progress(
+        &self,
+        context: &Context,
+        mesh: &mut Mesh,
+        occluders: &[&Mesh],
+        lights: &[Light],
+        settings: &LightmapSettings,
+        progress: &mut Progress,
+    ) -> Result<Texture, Error> {
+        let bake = TexelBake::new(
+            context,
+            &self.bvh_layout,
+            mesh,
+            occluders,
+            settings.resolution,
+            settings.padding,
+            progress,
+        )?;
+        let device = &context.device;
+        // Zero-sized bindings are invalid, so keep room for one light.
+        let mut gpu_lights: Vec<GpuLight> = lights.iter().map(GpuLight::from).collect();
+        if gpu_lights.is_empty() {
+            gpu_lights.push(GpuLight::default());
+        }
+        let light_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Lightmap Lights"),
+            contents: bytemuck::cast_slice(&gpu_lights),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        let samples = settings.samples.max(1);
+        let params = |sample_index| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Lightmap Params"),
+                contents: bytemuck::bytes_of(&Params {
+                    size: [bake.resolution; 2],
+                    sample_index,
+                    bounces: settings.bounces,
+                    light_count: lights.len() as u32,
+                    bias: bake.bias,
+                    _padding: [0; 2],
+                    sky: [
+                        settings.sky_color[0],
+                        settings.sky_color[1],
+                        settings.sky_color[2],
+                        0.0,
+                    ],
+                    albedo: [
+                        settings.albedo[0],
+                        settings.albedo[1],
+                        settings.albedo[2],
+                        0.0,
+                    ],
+                }),
+                usage: wgpu::BufferUsages::UNIFORM,
+            })
+        };
+        let workgroups = bake.workgroups();
+
+        context.diagnostics.record_pass(|| {
+            PassRecord::new(
+                "Lightmap Bake",
+                "trace",
+                vec![describe_buffer("accumulation", &bake.accumulation)],
+            )
+        });
+        // One submission per sample keeps each one short, and waiting for
+        // the previous one before recording the next keeps progress honest
+        // and cancellation prompt.
+        let mut previous = None;
+        for sample_index in 0..samples {
+            let params = params(sample_index);
+            let trace_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Lightmap Trace Bind Group"),
+                layout: &self.trace_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: params.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: bake.positions.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: bake.normals.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: light_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: bake.accumulation.as_entire_binding(),
+                    },
+                ],
+            });
+            let mut command_encoder =
+                device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Lightmap Encoder"),
+                });
+            {
+                let mut pass = command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some("Lightmap Trace"),
+                    timestamp_writes: None,
+                });
+                pass.set_pipeline(&self.trace);
+                pass.set_bind_group(0, &trace_group, &[]);
+                pass.set_bind_group(1, &bake.bvh_group, &[]);
+                pass.dispatch_workgroups(workgroups, workgroups, 1);
+            }
+            let index = context.submit(command_encoder);
+            if let Some(previous) = previous.replace(index) {
+                context
+                    .device
+                    .poll(wgpu::Maintain::WaitForSubmissionIndex(previous));
+            }
+            progress.report("lightmap", sample_index as u64, samples as u64)?;
+        }
+        if let Some(previous) = previous {
+            context
+                .device
+                .poll(wgpu::Maintain::WaitForSubmissionIndex(previous));
+        }
+        progress.report("lightmap", samples as u64, samples as u64)?;
+
+        Ok(self.post.finish(
+            context,
+            &bake,
+            1.0 / (samples as f32 * std::f32::consts::PI),
+            settings.dilation,
+            "Lightmap",
+        ))
+    }
+}
@@ -0,0 +1,66 @@
+use super::context::Context;
+use super::error::Error;
+
+impl Context {
+    pub fn check_texture_size(
+        &self,
+        size: &wgpu::Extent3d,
+        dimension: wgpu::TextureDimension,
+    ) -> Result<(), Error> {
+        let limits = self.device.limits();
+        match dimension {
+            wgpu::TextureDimension::D1 => check_limit(
+                "max_texture_dimension_1d",
+                limits.max_texture_dimension_1d,
+                size.width,
+            ),
+            wgpu::TextureDimension::D2 => {
+                check_limit(
+                    "max_texture_dimension_2d",
+                    limits.max_texture_dimension_2d,
+                    size.width,
+                )?;
+                check_limit(
+                    "max_texture_dimension_2d",
+                    limits.max_texture_dimension_2d,
+                    size.height,
+                )?;
+                check_limit(
+                    "max_texture_array_layers",
+                    limits.max_texture_array_layers,
+                    size.depth_or_array_layers,
+                )
+            }
+            wgpu::TextureDimension::D3 => {
+                let max = limits.max_texture_dimension_3d;
+                check_limit("max_texture_dimension_3d", max, size.width)?;
+                check_limit("max_texture_dimension_3d", max, size.height)?;
+                check_limit("max_texture_dimension_3d", max, size.depth_or_array_layers)
+            }
+        }
+    }
+
+    pub fn check_buffer_size(&self, size: u64) -> Result<(), Error> {
+        check_limit(
+            "max_buffer_size",
+            self.device.limits().max_buffer_size,
+            size,
+        )
+    }
+}
+
+pub(crate) fn check_limit<T: Into<u64>>(
+    name: &'static str,
+    limit: T,
+    requested: T,
+) -> Result<(), Error> {
+    let (limit, requested) = (limit.into(), requested.into());
+    if requested > limit {
+        return Err(Error::ExceedsDeviceLimit {
+            name,
+            limit,
+            requested,
+        });
+    }
+    Ok(())
+}
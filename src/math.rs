@@ -0,0 +1,428 @@
+//! Geometry shared by culling, picking and debug drawing. Matrices are
+//! column-major `[[f32; 4]; 4]` as `ObjectDescription::transform` returns,
+//! with clip-space depth in [0, 1].
+
+pub(crate) fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub(crate) fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+pub(crate) fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [0, 1, 2].map(|i| a[i] - b[i])
+}
+
+pub(crate) fn length(v: [f32; 3]) -> f32 {
+    dot(v, v).sqrt()
+}
+
+/// `v` scaled to unit length, or unchanged if it has none.
+pub(crate) fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let length = length(v);
+    if length > 0.0 {
+        v.map(|c| c / length)
+    } else {
+        v
+    }
+}
+
+pub(crate) fn lerp<const N: usize>(a: [f32; N], b: [f32; N], t: f32) -> [f32; N] {
+    std::array::from_fn(|i| a[i] + (b[i] - a[i]) * t)
+}
+
+/// Spherical interpolation of unit quaternions `[x, y, z, w]` along the
+/// shorter arc.
+pub(crate) fn slerp(a: [f32; 4], b: [f32; 4], t: f32) -> [f32; 4] {
+    let mut cos = (0..4).map(|i| a[i] * b[i]).sum::<f32>();
+    let b = if cos < 0.0 {
+        cos = -cos;
+        b.map(|c| -c)
+    } else {
+        b
+    };
+    let (wa, wb) = if cos > 0.9995 {
+        // Nearly parallel: lerp, normalized below, avoids dividing by ~0.
+        (1.0 - t, t)
+    } else {
+        let angle = cos.acos();
+        let sin = angle.sin();
+        (((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+    };
+    let q: [f32; 4] = std::array::from_fn(|i| a[i] * wa + b[i] * wb);
+    let length = q.iter().map(|c| c * c).sum::<f32>().sqrt();
+    if length > 0.0 {
+        q.map(|c| c / length)
+    } else {
+        a
+    }
+}
+
+/// `a * b` of column-major matrices.
+pub(crate) fn multiply(a: &[[f32; 4]; 4], b: &[[f32; 4]; 4]) -> [[f32; 4]; 4] {
+    std::array::from_fn(|column| {
+        std::array::from_fn(|row| (0..4).map(|k| a[k][row] * b[column][k]).sum())
+    })
+}
+
+/// `matrix * [point, 1]` without the perspective divide.
+pub(crate) fn transform_point(matrix: &[[f32; 4]; 4], point: [f32; 3]) -> [f32; 4] {
+    [0, 1, 2, 3].map(|row| {
+        matrix[0][row] * point[0]
+            + matrix[1][row] * point[1]
+            + matrix[2][row] * point[2]
+            + matrix[3][row]
+    })
+}
+
+/// Inverse of a 4x4 matrix by cofactor expansion, `None` if it is
+/// singular.
+pub(crate) fn invert(matrix: &[[f32; 4]; 4]) -> Option<[[f32; 4]; 4]> {
+    let m: Vec<f32> = matrix.iter().flatten().copied().collect();
+    let mut inv = [0.0; 16];
+    inv[0] = m[5] * m[10] * m[15] - m[5] * m[11] * m[14] - m[9] * m[6] * m[15]
+        + m[9] * m[7] * m[14]
+        + m[13] * m[6] * m[11]
+        - m[13] * m[7] * m[10];
+    inv[4] = -m[4] * m[10] * m[15] + m[4] * m[11] * m[14] + m[8] * m[6] * m[15]
+        - m[8] * m[7] * m[14]
+        - m[12] * m[6] * m[11]
+        + m[12] * m[7] * m[10];
+    inv[8] = m[4] * m[9] * m[15] - m[4] * m[11] * m[13] - m[8] * m[5] * m[15]
+        + m[8] * m[7] * m[13]
+        + m[12] * m[5] * m[11]
+        - m[12] * m[7] * m[9];
+    inv[12] = -m[4] * m[9] * m[14] + m[4] * m[10] * m[13] + m[8] * m[5] * m[14]
+        - m[8] * m[6] * m[13]
+        - m[12] * m[5] * m[10]
+        + m[12] * m[6] * m[9];
+    inv[1] = -m[1] * m[10] * m[15] + m[1] * m[11] * m[14] + m[9] * m[2] * m[15]
+        - m[9] * m[3] * m[14]
+        - m[13] * m[2] * m[11]
+        + m[13] * m[3] * m[10];
+    inv[5] = m[0] * m[10] * m[15] - m[0] * m[11] * m[14] - m[8] * m[2] * m[15]
+        + m[8] * m[3] * m[14]
+        + m[12] * m[2] * m[11]
+        - m[12] * m[3] * m[10];
+    inv[9] = -m[0] * m[9] * m[15] + m[0] * m[11] * m[13] + m[8] * m[1] * m[15]
+        - m[8] * m[3] * m[13]
+        - m[12] * m[1] * m[11]
+        + m[12] * m[3] * m[9];
+    inv[13] = m[0] * m[9] * m[14] - m[0] * m[10] * m[13] - m[8] * m[1] * m[14]
+        + m[8] * m[2] * m[13]
+        + m[12] * m[1] * m[10]
+        - m[12] * m[2] * m[9];
+    inv[2] = m[1] * m[6] * m[15] - m[1] * m[7] * m[14] - m[5] * m[2] * m[15]
+        + m[5] * m[3] * m[14]
+        + m[13] * m[2] * m[7]
+        - m[13] * m[3] * m[6];
+    inv[6] = -m[0] * m[6] * m[15] + m[0] * m[7] * m[14] + m[4] * m[2] * m[15]
+        - m[4] * m[3] * m[14]
+        - m[12] * m[2] * m[7]
+        + m[12] * m[3] * m[6];
+    inv[10] = m[0] * m[5] * m[15] - m[0] * m[7] * m[13] - m[4] * m[1] * m[15]
+        + m[4] * m[3] * m[13]
+        + m[12] * m[1] * m[7]
+        - m[12] * m[3] * m[5];
+    inv[14] = -m[0] * m[5] * m[14] + m[0] * m[6] * m[13] + m[4] * m[1] * m[14]
+        - m[4] * m[2] * m[13]
+        - m[12] * m[1] * m[6]
+        + m[12] * m[2] * m[5];
+    inv[3] = -m[1] * m[6] * m[11] + m[1] * m[7] * m[10] + m[5] * m[2] * m[11]
+        - m[5] * m[3] * m[10]
+        - m[9] * m[2] * m[7]
+        + m[9] * m[3] * m[6];
+    inv[7] = m[0] * m[6] * m[11] - m[0] * m[7] * m[10] - m[4] * m[2] * m[11]
+        + m[4] * m[3] * m[10]
+        + m[8] * m[2] * m[7]
+        - m[8] * m[3] * m[6];
+    inv[11] = -m[0] * m[5] * m[11] + m[0] * m[7] * m[9] + m[4] * m[1] * m[11]
+        - m[4] * m[3] * m[9]
+        - m[8] * m[1] * m[7]
+        + m[8] * m[3] * m[5];
+    inv[15] = m[0] * m[5] * m[10] - m[0] * m[6] * m[9] - m[4] * m[1] * m[10]
+        + m[4] * m[2] * m[9]
+        + m[8] * m[1] * m[6]
+        - m[8] * m[2] * m[5];
+    let determinant = m[0] * inv[0] + m[1] * inv[4] + m[2] * inv[8] + m[3] * inv[12];
+    if determinant == 0.0 || !determinant.is_finite() {
+        return None;
+    }
+    Some(std::array::from_fn(|column| {
+        std::array::from_fn(|row| inv[column * 4 + row] / determinant)
+    }))
+}
+
+/// Inverse transpose of the upper 3x3 of `model`, which keeps normals
+/// perpendicular to the surface under non-uniform scaling.
+pub(crate) fn normal_matrix(model: &[[f32; 4]; 4]) -> [[f32; 4]; 4] {
+    let [c0, c1, c2] = [0, 1, 2].map(|i| [model[i][0], model[i][1], model[i][2]]);
+    let cofactors = [cross(c1, c2), cross(c2, c0), cross(c0, c1)];
+    let determinant = dot(c0, cofactors[0]);
+    let scale = if determinant != 0.0 {
+        1.0 / determinant
+    } else {
+        1.0
+    };
+    let mut matrix = [[0.0; 4]; 4];
+    for (column, cofactor) in matrix.iter_mut().zip(cofactors) {
+        *column = [cofactor[0], cofactor[1], cofactor[2], 0.0].map(|c| c * scale);
+    }
+    matrix[3][3] = 1.0;
+    matrix
+}
+
+/// The points `p` with `dot(normal, p) + distance == 0`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Plane {
+    pub normal: [f32; 3],
+    pub distance: f32,
+}
+
+impl Plane {
+    pub fn new(normal: [f32; 3], distance: f32) -> Self {
+        Self { normal, distance }
+    }
+
+    pub fn from_point_normal(point: [f32; 3], normal: [f32; 3]) -> Self {
+        let normal = normalize(normal);
+        Self::new(normal, -dot(normal, point))
+    }
+
+    /// The plane through a counter-clockwise triangle, facing its front.
+    pub fn from_points(a: [f32; 3], b: [f32; 3], c: [f32; 3]) -> Self {
+        Self::from_point_normal(a, cross(sub(b, a), sub(c, a)))
+    }
+
+    /// Scales the equation so that the normal has unit length, which makes
+    /// `signed_distance` a euclidean distance.
+    pub fn normalized(self) -> Self {
+        let length = length(self.normal);
+        if length > 0.0 {
+            Self::new(self.normal.map(|c| c / length), self.distance / length)
+        } else {
+            self
+        }
+    }
+
+    /// Positive on the side the normal points to.
+    pub fn signed_distance(&self, point: [f32; 3]) -> f32 {
+        dot(self.normal, point) + self.distance
+    }
+}
+
+/// Axis-aligned bounding box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Aabb {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+impl Aabb {
+    pub fn new(min: [f32; 3], max: [f32; 3]) -> Self {
+        Self { min, max }
+    }
+
+    /// The smallest box containing the points, `None` if there are none.
+    pub fn from_points(points: impl IntoIterator<Item = [f32; 3]>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(Self::new(first, first), |aabb, point| {
+            aabb.expanded_to(point)
+        }))
+    }
+
+    pub fn center(&self) -> [f32; 3] {
+        [0, 1, 2].map(|i| (self.min[i] + self.max[i]) * 0.5)
+    }
+
+    pub fn size(&self) -> [f32; 3] {
+        sub(self.max, self.min)
+    }
+
+    /// Half the size.
+    pub fn extents(&self) -> [f32; 3] {
+        self.size().map(|c| c * 0.5)
+    }
+
+    pub fn corners(&self) -> [[f32; 3]; 8] {
+        std::array::from_fn(|i| {
+            [0, 1, 2].map(|axis| {
+                if i & (1 << axis) == 0 {
+                    self.min[axis]
+                } else {
+                    self.max[axis]
+                }
+            })
+        })
+    }
+
+    pub fn contains_point(&self, point: [f32; 3]) -> bool {
+        (0..3).all(|i| self.min[i] <= point[i] && point[i] <= self.max[i])
+    }
+
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        (0..3).all(|i| self.min[i] <= other.max[i] && other.min[i] <= self.max[i])
+    }
+
+    pub fn expanded_to(&self, point: [f32; 3]) -> Self {
+        Self::new(
+            [0, 1, 2].map(|i| self.min[i].min(point[i])),
+            [0, 1, 2].map(|i| self.max[i].max(point[i])),
+        )
+    }
+
+    pub fn union(&self, other: &Aabb) -> Self {
+        self.expanded_to(other.min).expanded_to(other.max)
+    }
+
+    /// The box containing this one after an affine transform.
+    pub fn transformed(&self, matrix: &[[f32; 4]; 4]) -> Self {
+        let corners = self.corners().map(|corner| {
+            let [x, y, z, _] = transform_point(matrix, corner);
+            [x, y, z]
+        });
+        Self::from_points(corners).unwrap_or(*self)
+    }
+}
+
+/// The six planes bounding a view volume, with normals pointing inside.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frustum {
+    /// Left, right, bottom, top, near and far. The far plane of an infinite
+    /// projection has a zero normal and contains everything.
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// Extracts the planes of a view-projection matrix. With reverse-Z the
+    /// near and far planes trade places.
+    pub fn from_view_projection(matrix: &[[f32; 4]; 4]) -> Self {
+        let row = |i: usize| [matrix[0][i], matrix[1][i], matrix[2][i], matrix[3][i]];
+        let [x, y, z, w] = [row(0), row(1), row(2), row(3)];
+        let plane = |a: [f32; 4], sign: f32, b: [f32; 4]| {
+            let v: [f32; 4] = std::array::from_fn(|i| a[i] + sign * b[i]);
+            Plane::new([v[0], v[1], v[2]], v[3]).normalized()
+        };
+        Self {
+            planes: [
+                plane(w, 1.0, x),
+                plane(w, -1.0, x),
+                plane(w, 1.0, y),
+                plane(w, -1.0, y),
+                plane(z, 0.0, z),
+                plane(w, -1.0, z),
+            ],
+        }
+    }
+
+    pub fn contains_point(&self, point: [f32; 3]) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(point) >= 0.0)
+    }
+
+    /// Conservative: spheres near the frustum's corners may pass without
+    /// intersecting it.
+    pub fn intersects_sphere(&self, center: [f32; 3], radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(center) >= -radius)
+    }
+
+    /// Conservative in the same way as `intersects_sphere`.
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            // The corner furthest along the normal.
+            let corner = [0, 1, 2].map(|i| {
+                if plane.normal[i] >= 0.0 {
+                    aabb.max[i]
+                } else {
+                    aabb.min[i]
+                }
+            });
+            plane.signed_distance(corner) >= 0.0
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    pub origin: [f32; 3],
+    /// Unit length, so that ray parameters are distances.
+    pub direction: [f32; 3],
+}
+
+impl Ray {
+    pub fn new(origin: [f32; 3], direction: [f32; 3]) -> Self {
+        Self {
+            origin,
+            direction: normalize(direction),
+        }
+    }
+
+    /// The ray through a point in normalized device coordinates, from the
+    /// near plane away from the camera, for picking. Works for reverse-Z
+    /// and infinite projections too.
+    pub fn from_ndc(ndc: [f32; 2], inverse_view_projection: &[[f32; 4]; 4]) -> Self {
+        let unproject = |depth| transform_point(inverse_view_projection, [ndc[0], ndc[1], depth]);
+        let point = |p: [f32; 4]| [p[0] / p[3], p[1] / p[3], p[2] / p[3]];
+        // The near plane has the larger w, the far plane may be at w = 0.
+        let (a, b) = (unproject(0.0), unproject(1.0));
+        let near = if a[3].abs() >= b[3].abs() { a } else { b };
+        let origin = point(near);
+        Self::new(origin, sub(point(unproject(0.5)), origin))
+    }
+
+    pub fn at(&self, t: f32) -> [f32; 3] {
+        [0, 1, 2].map(|i| self.origin[i] + self.direction[i] * t)
+    }
+
+    /// Distance to the plane, `None` if parallel or behind the origin.
+    pub fn intersect_plane(&self, plane: &Plane) -> Option<f32> {
+        let denominator = dot(plane.normal, self.direction);
+        if denominator.abs() <= f32::EPSILON {
+            return None;
+        }
+        let t = -plane.signed_distance(self.origin) / denominator;
+        (t >= 0.0).then_some(t)
+    }
+
+    /// Distance to the box entry, zero if the origin is inside, by the slab
+    /// method.
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let mut near = 0.0f32;
+        let mut far = f32::INFINITY;
+        for i in 0..3 {
+            let inverse = 1.0 / self.direction[i];
+            let t0 = (aabb.min[i] - self.origin[i]) * inverse;
+            let t1 = (aabb.max[i] - self.origin[i]) * inverse;
+            // NaN from a parallel ray on a slab boundary leaves the range.
+            near = near.max(t0.min(t1));
+            far = far.min(t0.max(t1));
+        }
+        (near <= far).then_some(near)
+    }
+
+    /// Distance to the first sphere hit in front of the origin, zero if the
+    /// origin is inside.
+    pub fn intersect_sphere(&self, center: [f32; 3], radius: f32) -> Option<f32> {
+        let offset = sub(self.origin, center);
+        let b = dot(offset, self.direction);
+        let c = dot(offset, offset) - radius * radius;
+        if c <= 0.0 {
+            return Some(0.0);
+        }
+        let discriminant = b * b - c;
+        if discriminant < 0.0 || b > 0.0 {
+            return None;
+        }
+        Some(-b - discriminant.sqrt())
+    }
+}
@@ -0,0 +1,312 @@
+use super::context::Context;
+use super::error::Error;
+use super::math::{cross, dot, normalize, sub, Aabb};
+use std::f32::consts::PI;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Vertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub uv: [f32; 2],
+}
+
+impl Vertex {
+    pub const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x2];
+
+    pub fn layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Vertex>() as u64,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+/// An indexed triangle list on the CPU.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Mesh {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+    /// RGBA per vertex in 0..1, or empty.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub colors: Vec<[f32; 4]>,
+    /// Second UV set per vertex for lightmaps, or empty.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub uv2: Vec<[f32; 2]>,
+}
+
+impl Mesh {
+    pub const COLOR_ATTRIBUTES: [wgpu::VertexAttribute; 1] =
+        wgpu::vertex_attr_array![3 => Float32x4];
+    pub const UV2_ATTRIBUTES: [wgpu::VertexAttribute; 1] = wgpu::vertex_attr_array![4 => Float32x2];
+
+    pub fn new(vertices: Vec<Vertex>, indices: Vec<u32>) -> Self {
+        Self {
+            vertices,
+            indices,
+            colors: Vec::new(),
+            uv2: Vec::new(),
+        }
+    }
+
+    /// Layout of the vertex colors, bound to slot 1 by `GpuMesh::draw`.
+    pub fn color_layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<[f32; 4]>() as u64,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::COLOR_ATTRIBUTES,
+        }
+    }
+
+    /// Layout of the lightmap UVs, bound to slot 2 by `GpuMesh::draw`.
+    pub fn uv2_layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<[f32; 2]>() as u64,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::UV2_ATTRIBUTES,
+        }
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    pub fn triangles(&self) -> impl Iterator<Item = [u32; 3]> + '_ {
+        self.indices.chunks_exact(3).map(|t| [t[0], t[1], t[2]])
+    }
+
+    /// Axis-aligned cube centered at the origin with `size` long edges and
+    /// per-face normals and UVs.
+    pub fn cube(size: f32) -> Self {
+        let h = size * 0.5;
+        let faces: [([f32; 3], [f32; 3], [f32; 3]); 6] = [
+            ([1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]),
+            ([-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+            ([0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]),
+            ([0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+            ([0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ([0.0, 0.0, -1.0], [-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+        ];
+        let mut mesh = Self::default();
+        for (normal, u, v) in faces {
+            let base = mesh.vertices.len() as u32;
+            for (su, sv) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+                let position = [0, 1, 2].map(|i| (normal[i] + u[i] * su + v[i] * sv) * h);
+                mesh.vertices.push(Vertex {
+                    position,
+                    normal,
+                    uv: [(su + 1.0) * 0.5, (1.0 - sv) * 0.5],
+                });
+            }
+            mesh.indices
+                .extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+        }
+        mesh
+    }
+
+    /// Square in the XZ plane facing +Y.
+    pub fn plane(size: f32) -> Self {
+        let h = size * 0.5;
+        let vertex = |x: f32, z: f32, u: f32, v: f32| Vertex {
+            position: [x, 0.0, z],
+            normal: [0.0, 1.0, 0.0],
+            uv: [u, v],
+        };
+        Self::new(
+            vec![
+                vertex(-h, h, 0.0, 1.0),
+                vertex(h, h, 1.0, 1.0),
+                vertex(h, -h, 1.0, 0.0),
+                vertex(-h, -h, 0.0, 0.0),
+            ],
+            vec![0, 1, 2, 0, 2, 3],
+        )
+    }
+
+    /// UV sphere with `segments` around the Y axis and `rings` from pole to
+    /// pole.
+    pub fn sphere(radius: f32, segments: u32, rings: u32) -> Self {
+        let (segments, rings) = (segments.max(3), rings.max(2));
+        let mut mesh = Self::default();
+        for ring in 0..=rings {
+            let v = ring as f32 / rings as f32;
+            let theta = v * PI;
+            for segment in 0..=segments {
+                let u = segment as f32 / segments as f32;
+                let phi = u * 2.0 * PI;
+                let normal = [
+                    theta.sin() * phi.cos(),
+                    theta.cos(),
+                    -theta.sin() * phi.sin(),
+                ];
+                mesh.vertices.push(Vertex {
+                    position: normal.map(|n| n * radius),
+                    normal,
+                    uv: [u, v],
+                });
+            }
+        }
+        let stride = segments + 1;
+        for ring in 0..rings {
+            for segment in 0..segments {
+                let a = ring * stride + segment;
+                let b = a + stride;
+                mesh.indices.extend([a, b, a + 1, a + 1, b, b + 1]);
+            }
+        }
+        mesh
+    }
+
+    /// Bounds of the vertex positions, `None` for an empty mesh.
+    pub fn bounds(&self) -> Option<Aabb> {
+        Aabb::from_points(self.vertices.iter().map(|vertex| vertex.position))
+    }
+
+    /// Replaces the normals with area-weighted averages of the adjacent
+    /// face normals.
+    pub fn compute_normals(&mut self) {
+        let mut normals = vec![[0.0f32; 3]; self.vertices.len()];
+        for [a, b, c] in self.triangles() {
+            let [pa, pb, pc] = [a, b, c].map(|i| self.vertices[i as usize].position);
+            let e1 = [0, 1, 2].map(|i| pb[i] - pa[i]);
+            let e2 = [0, 1, 2].map(|i| pc[i] - pa[i]);
+            let n = [
+                e1[1] * e2[2] - e1[2] * e2[1],
+                e1[2] * e2[0] - e1[0] * e2[2],
+                e1[0] * e2[1] - e1[1] * e2[0],
+            ];
+            for index in [a, b, c] {
+                let normal = &mut normals[index as usize];
+                *normal = [0, 1, 2].map(|i| normal[i] + n[i]);
+            }
+        }
+        for (vertex, n) in self.vertices.iter_mut().zip(normals) {
+            let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+            if length > 0.0 {
+                vertex.normal = n.map(|c| c / length);
+            }
+        }
+    }
+
+    /// Per-vertex tangents along increasing U, orthogonalized against the
+    /// normals, with the handedness of the bitangent `cross(normal,
+    /// tangent) * w` along increasing V in `w`. Vertices whose triangles
+    /// have no UV area get an arbitrary tangent perpendicular to the normal.
+    pub fn compute_tangents(&self) -> Vec<[f32; 4]> {
+        let mut tangents = vec![[0.0f32; 3]; self.vertices.len()];
+        let mut bitangents = vec![[0.0f32; 3]; self.vertices.len()];
+        for [a, b, c] in self.triangles() {
+            let [va, vb, vc] = [a, b, c].map(|i| self.vertices[i as usize]);
+            let e1 = sub(vb.position, va.position);
+            let e2 = sub(vc.position, va.position);
+            let (du1, dv1) = (vb.uv[0] - va.uv[0], vb.uv[1] - va.uv[1]);
+            let (du2, dv2) = (vc.uv[0] - va.uv[0], vc.uv[1] - va.uv[1]);
+            let determinant = du1 * dv2 - du2 * dv1;
+            if determinant == 0.0 {
+                continue;
+            }
+            let r = 1.0 / determinant;
+            let t = [0, 1, 2].map(|i| (e1[i] * dv2 - e2[i] * dv1) * r);
+            let s = [0, 1, 2].map(|i| (e2[i] * du1 - e1[i] * du2) * r);
+            for index in [a, b, c] {
+                let (tangent, bitangent) = (
+                    &mut tangents[index as usize],
+                    &mut bitangents[index as usize],
+                );
+                *tangent = [0, 1, 2].map(|i| tangent[i] + t[i]);
+                *bitangent = [0, 1, 2].map(|i| bitangent[i] + s[i]);
+            }
+        }
+        self.vertices
+            .iter()
+            .zip(tangents.iter().zip(&bitangents))
+            .map(|(vertex, (&t, &s))| {
+                let n = vertex.normal;
+                let mut tangent = normalize(sub(t, n.map(|c| c * dot(n, t))));
+                if tangent == [0.0; 3] {
+                    let axis = match n.map(f32::abs) {
+                        [x, y, z] if x <= y && x <= z => [1.0, 0.0, 0.0],
+                        [_, y, z] if y <= z => [0.0, 1.0, 0.0],
+                        _ => [0.0, 0.0, 1.0],
+                    };
+                    tangent = normalize(cross(axis, n));
+                }
+                let w = if dot(cross(n, tangent), s) < 0.0 {
+                    -1.0
+                } else {
+                    1.0
+                };
+                [tangent[0], tangent[1], tangent[2], w]
+            })
+            .collect()
+    }
+}
+
+/// A `Mesh` uploaded to vertex and index buffers.
+pub struct GpuMesh {
+    pub vertex_buffer: wgpu::Buffer,
+    pub index_buffer: wgpu::Buffer,
+    pub index_count: u32,
+    pub color_buffer: Option<wgpu::Buffer>,
+    pub uv2_buffer: Option<wgpu::Buffer>,
+}
+
+impl GpuMesh {
+    pub fn draw<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>, instances: std::ops::Range<u32>) {
+        pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        if let Some(colors) = &self.color_buffer {
+            pass.set_vertex_buffer(1, colors.slice(..));
+        }
+        if let Some(uv2) = &self.uv2_buffer {
+            pass.set_vertex_buffer(2, uv2.slice(..));
+        }
+        pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        pass.draw_indexed(0..self.index_count, 0, instances);
+    }
+}
+
+impl Context {
+    pub fn upload_mesh(&self, mesh: &Mesh, label: Option<&str>) -> Result<GpuMesh, Error> {
+        Ok(GpuMesh {
+            vertex_buffer: self.buffer_with_data(
+                bytemuck::cast_slice(&mesh.vertices),
+                wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                label,
+            )?,
+            index_buffer: self.buffer_with_data(
+                bytemuck::cast_slice(&mesh.indices),
+                wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+                label,
+            )?,
+            index_count: mesh.indices.len() as u32,
+            color_buffer: if mesh.colors.is_empty() {
+                None
+            } else {
+                Some(self.buffer_with_data(
+                    bytemuck::cast_slice(&mesh.colors),
+                    wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                    label,
+                )?)
+            },
+            uv2_buffer: if mesh.uv2.is_empty() {
+                None
+            } else {
+                Some(self.buffer_with_data(
+                    bytemuck::cast_slice(&mesh.uv2),
+                    wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                    label,
+                )?)
+            },
+        })
+    }
+}
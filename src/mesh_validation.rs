@@ -0,0 +1,202 @@
+use super::math::{cross, length, sub, Aabb};
+use super::mesh::Mesh;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Statistics and problems of a `Mesh`, from `Mesh::validate`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MeshReport {
+    pub vertex_count: usize,
+    pub triangle_count: usize,
+    /// Bounds of the vertex positions, `None` for an empty mesh.
+    pub bounds: Option<Aabb>,
+    /// Indices left over after the last whole triangle.
+    pub trailing_indices: usize,
+    /// Triangles referencing vertices that do not exist.
+    pub out_of_range_triangles: Vec<usize>,
+    /// Vertices with a NaN or infinite position, normal or UV.
+    pub non_finite_vertices: Vec<usize>,
+    /// Triangles with repeated vertices or no area.
+    pub degenerate_triangles: Vec<usize>,
+    /// Edges, as pairs of vertices, shared by more than two triangles once
+    /// vertices at the same position are welded.
+    pub non_manifold_edges: Vec<[u32; 2]>,
+    /// Edges used by a single triangle once vertices at the same position
+    /// are welded, 0 for a closed mesh.
+    pub boundary_edges: usize,
+    /// Vertices identical in every attribute to an earlier one.
+    pub duplicate_vertices: usize,
+    /// Vertices whose normal has no length.
+    pub missing_normals: usize,
+    /// Whether any vertex has a UV other than (0, 0).
+    pub has_uvs: bool,
+    /// Triangles whose UVs have no area, so that no tangent frame can be
+    /// derived for them.
+    pub degenerate_uv_triangles: usize,
+    pub has_uv2: bool,
+    pub has_colors: bool,
+    /// Names of the per-vertex attributes, `colors` or `uv2`, that are
+    /// neither empty nor one per vertex.
+    pub mismatched_attributes: Vec<String>,
+}
+
+impl MeshReport {
+    /// Whether the mesh can be drawn without out-of-range indices, NaNs or
+    /// mismatched attribute lengths. Degenerate triangles, open edges and
+    /// duplicates are reported but allowed.
+    pub fn is_valid(&self) -> bool {
+        self.trailing_indices == 0
+            && self.out_of_range_triangles.is_empty()
+            && self.non_finite_vertices.is_empty()
+            && self.mismatched_attributes.is_empty()
+    }
+}
+
+impl fmt::Display for MeshReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Vertices: {}, triangles: {}",
+            self.vertex_count, self.triangle_count
+        )?;
+        match &self.bounds {
+            Some(bounds) => writeln!(f, "Bounds: {:?} to {:?}", bounds.min, bounds.max)?,
+            None => writeln!(f, "Bounds: empty")?,
+        }
+        writeln!(
+            f,
+            "Attributes: normals{}, UVs {}, UV2 {}, colors {}",
+            match self.missing_normals {
+                0 => String::new(),
+                missing => format!(" ({missing} missing)"),
+            },
+            if self.has_uvs { "yes" } else { "no" },
+            if self.has_uv2 { "yes" } else { "no" },
+            if self.has_colors { "yes" } else { "no" },
+        )?;
+        if !self.mismatched_attributes.is_empty() {
+            writeln!(
+                f,
+                "Mismatched attributes: {}",
+                self.mismatched_attributes.join(", ")
+            )?;
+        }
+        writeln!(f, "Trailing indices: {}", self.trailing_indices)?;
+        writeln!(
+            f,
+            "Out-of-range triangles: {}",
+            self.out_of_range_triangles.len()
+        )?;
+        writeln!(f, "Non-finite vertices: {}", self.non_finite_vertices.len())?;
+        writeln!(
+            f,
+            "Degenerate triangles: {}",
+            self.degenerate_triangles.len()
+        )?;
+        writeln!(
+            f,
+            "Degenerate UV triangles: {}",
+            self.degenerate_uv_triangles
+        )?;
+        writeln!(f, "Non-manifold edges: {}", self.non_manifold_edges.len())?;
+        writeln!(f, "Boundary edges: {}", self.boundary_edges)?;
+        writeln!(f, "Duplicate vertices: {}", self.duplicate_vertices)
+    }
+}
+
+impl Mesh {
+    /// Checks the mesh for problems common in imported assets. Colors and
+    /// `uv2` are checked only for their length.
+    pub fn validate(&self) -> MeshReport {
+        let vertex_count = self.vertices.len();
+        let finite = |values: &[f32]| values.iter().all(|value| value.is_finite());
+        let non_finite_vertices = self
+            .vertices
+            .iter()
+            .enumerate()
+            .filter(|(_, vertex)| {
+                !finite(&vertex.position) || !finite(&vertex.normal) || !finite(&vertex.uv)
+            })
+            .map(|(index, _)| index)
+            .collect();
+
+        let distinct: HashSet<&[u8]> = self.vertices.iter().map(bytemuck::bytes_of).collect();
+        let duplicate_vertices = vertex_count - distinct.len();
+
+        // Topology over vertices welded by position.
+        let mut welded = HashMap::new();
+        let weld: Vec<u32> = self
+            .vertices
+            .iter()
+            .enumerate()
+            .map(|(index, vertex)| {
+                *welded
+                    .entry(vertex.position.map(f32::to_bits))
+                    .or_insert(index as u32)
+            })
+            .collect();
+
+        let mut out_of_range_triangles = Vec::new();
+        let mut degenerate_triangles = Vec::new();
+        let mut degenerate_uv_triangles = 0;
+        let mut edges: HashMap<[u32; 2], usize> = HashMap::new();
+        for (triangle, indices) in self.triangles().enumerate() {
+            if indices.iter().any(|&index| index as usize >= vertex_count) {
+                out_of_range_triangles.push(triangle);
+                continue;
+            }
+            let [a, b, c] = indices.map(|index| self.vertices[index as usize]);
+            let area = length(cross(
+                sub(b.position, a.position),
+                sub(c.position, a.position),
+            ));
+            let [wa, wb, wc] = indices.map(|index| weld[index as usize]);
+            if wa == wb || wb == wc || wc == wa || area == 0.0 {
+                degenerate_triangles.push(triangle);
+                continue;
+            }
+            let uv_area = (b.uv[0] - a.uv[0]) * (c.uv[1] - a.uv[1])
+                - (b.uv[1] - a.uv[1]) * (c.uv[0] - a.uv[0]);
+            if uv_area == 0.0 {
+                degenerate_uv_triangles += 1;
+            }
+            for [p, q] in [[wa, wb], [wb, wc], [wc, wa]] {
+                *edges.entry([p.min(q), p.max(q)]).or_default() += 1;
+            }
+        }
+        let mut non_manifold_edges: Vec<[u32; 2]> = edges
+            .iter()
+            .filter(|(_, &count)| count > 2)
+            .map(|(&edge, _)| edge)
+            .collect();
+        non_manifold_edges.sort_unstable();
+
+        MeshReport {
+            vertex_count,
+            triangle_count: self.triangle_count(),
+            bounds: self.bounds(),
+            trailing_indices: self.indices.len() % 3,
+            out_of_range_triangles,
+            non_finite_vertices,
+            degenerate_triangles,
+            non_manifold_edges,
+            boundary_edges: edges.values().filter(|&&count| count == 1).count(),
+            duplicate_vertices,
+            missing_normals: self
+                .vertices
+                .iter()
+                .filter(|vertex| vertex.normal == [0.0; 3])
+                .count(),
+            has_uvs: self.vertices.iter().any(|vertex| vertex.uv != [0.0; 2]),
+            degenerate_uv_triangles,
+            has_uv2: !self.uv2.is_empty(),
+            has_colors: !self.colors.is_empty(),
+            mismatched_attributes: [("colors", self.colors.len()), ("uv2", self.uv2.len())]
+                .into_iter()
+                .filter(|&(_, len)| len != 0 && len != vertex_count)
+                .map(|(name, _)| name.to_string())
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use super::context::Context;
+use super::depth::Colormap;
+use super::diagnostics::{describe_texture, PassRecord};
+use super::error::Error;
+use super::mesh::{GpuMesh, Vertex};
+use super::pass_ops::PassOps;
+use super::texture::Texture;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use wgpu::util::DeviceExt;
+
+const SRGB_TARGET: u32 = 1;
+
+/// WGSL helpers for a fragment shader's texture level of detail, from the
+/// derivatives of `texel`, the texture coordinates times the texture size.
+/// `mip_level` is the level a sampler with an anisotropy clamp of
+/// `max_anisotropy` reads, or would read without its LOD clamps, and
+/// `anisotropy_ratio` the ratio of the pixel footprint's axes it filters
+/// along. Both have to be called in uniform control flow.
+pub const MIP_LEVEL_WGSL: &str = "fn texel_footprint(texel: vec2<f32>) -> vec2<f32> {
+    let x = length(dpdx(texel));
+    let y = length(dpdy(texel));
+    return vec2<f32>(max(x, y), min(x, y));
+}
+
+fn anisotropy_ratio(texel: vec2<f32>, max_anisotropy: f32) -> f32 {
+    let footprint = texel_footprint(texel);
+    return clamp(footprint.x / max(footprint.y, 1e-6), 1.0, max(max_anisotropy, 1.0));
+}
+
+fn mip_level(texel: vec2<f32>, max_anisotropy: f32) -> f32 {
+    let footprint = texel_footprint(texel);
+    let ratio = clamp(footprint.x / max(footprint.y, 1e-6), 1.0, max(max_anisotropy, 1.0));
+    return max(log2(footprint.x / ratio), 0.0);
+}
+";
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    view_projection: [[f32; 4]; 4],
+    model: [[f32; 4]; 4],
+    size: [f32; 2],
+    mip_level_count: f32,
+    max_anisotropy: f32,
+    view: u32,
+    colormap: u32,
+    flags: u32,
+    _padding: u32,
+}
+
+/// What `MipVisualizer` colors surfaces by.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum MipView {
+    /// The mip level sampled, from 0 to the texture's last level.
+    #[default]
+    MipLevel,
+    /// The anisotropy of the pixel footprints, from 1 to 16 on a log scale,
+    /// limited by the sampler's clamp.
+    Anisotropy,
+}
+
+/// Debug view coloring a mesh by the mip level of a texture mapped over
+/// its UVs, or by the anisotropy of the sampling, for tuning texture
+/// resolutions and sampler settings. The shader functions are available
+/// for other shaders as `MIP_LEVEL_WGSL`.
+pub struct MipVisualizer {
+    module: wgpu::ShaderModule,
+    layout: wgpu::BindGroupLayout,
+    pipeline_layout: wgpu::PipelineLayout,
+    #[allow(clippy::type_complexity)]
+    pipelines: Mutex<
+        HashMap<
+            (
+                wgpu::TextureFormat,
+                Option<(wgpu::TextureFormat, wgpu::CompareFunction)>,
+            ),
+            wgpu::RenderPipeline,
+        >,
+    >,
+    pub view: MipView,
+    pub colormap: Colormap,
+    /// Anisotropy clamp of the sampler being tuned, 1 for trilinear.
+    pub max_anisotropy: u16,
+    /// Loads and stores of the target and depth, by default drawing over
+    /// their contents.
+    pub ops: PassOps,
+}
+
+impl MipVisualizer {
+    pub fn new(context: &Context) -> Self {
+        let device = &context.device;
+        let source = MIP_LEVEL_WGSL.to_string()
+            + include_str!("shaders/colormap.wgsl")
+            + include_str!("shaders/mip_visualization.wgsl");
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("mip_visualization.wgsl"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Mip Visualization Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Mip Visualization Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        Self {
+            module,
+            layout,
+            pipeline_layout,
+            pipelines: Mutex::new(HashMap::new()),
+            view: MipView::default(),
+            colormap: Colormap::default(),
+            max_anisotropy: 1,
+            ops: PassOps::default(),
+        }
+    }
+
+    /// Draws `mesh`, transformed by `model` and `view_projection`, into
+    /// `target` colored by how `texture` would be sampled over its UVs.
+    /// With `depth`, surfaces are depth tested and written with the
+    /// context's depth state, so that several meshes can be drawn.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        mesh: &GpuMesh,
+        view_projection: &[[f32; 4]; 4],
+        model: &[[f32; 4]; 4],
+        texture: &Texture,
+        target: &Texture,
+        depth: Option<&Texture>,
+    ) -> Result<(), Error> {
+        if std::iter::once(target).chain(depth).any(|texture| {
+            !texture
+                .usage()
+                .contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+        }) {
+            return Err(Error::TextureCreationFailed);
+        }
+        let format = target.format();
+        let device = &context.device;
+        let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Mip Visualization Params"),
+            contents: bytemuck::bytes_of(&Params {
+                view_projection: *view_projection,
+                model: *model,
+                size: [texture.width() as f32, texture.height() as f32],
+                mip_level_count: texture.mip_level_count() as f32,
+                max_anisotropy: self.max_anisotropy as f32,
+                view: self.view as u32,
+                colormap: self.colormap.index(),
+                flags: match format.is_srgb() {
+                    true => SRGB_TARGET,
+                    false => 0,
+                },
+                ..Default::default()
+            }),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Mip Visualization Bind Group"),
+            layout: &self.layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: params.as_entire_binding(),
+            }],
+        });
+        context.diagnostics.record_pass(|| {
+            let mut resources = vec![describe_texture("target", &target.texture)];
+            if let Some(depth) = depth {
+                resources.push(describe_texture("depth", &depth.texture));
+            }
+            PassRecord::new("Mip Visualization", "fs_mip", resources)
+        });
+
+        let depth_state = depth.map(|depth| (depth.format(), context.depth_compare_function()));
+        let mut pipelines = self
+            .pipelines
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let pipeline = pipelines.entry((format, depth_state)).or_insert_with(|| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Mip Visualization"),
+                layout: Some(&self.pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &self.module,
+                    entry_point: "vs_mesh",
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    buffers: &[Vertex::layout()],
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: depth_state.map(|(format, _)| context.depth_stencil_state(format)),
+                multisample: wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &self.module,
+                    entry_point: "fs_mip",
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    targets: &[Some(format.into())],
+                }),
+                multiview: None,
+            })
+        });
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Mip Visualization"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &target.view,
+                resolve_target: None,
+                ops: self.ops.color(),
+            })],
+            depth_stencil_attachment: depth.map(|depth| wgpu::RenderPassDepthStencilAttachment {
+                view: &depth.view,
+                depth_ops: Some(self.ops.depth()),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+        pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        pass.draw_indexed(0..mesh.index_count, 0, 0..1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+use super::blit::BlitPipeline;
+use super::context::Context;
+use super::error::Error;
+use super::texture::Texture;
+
+/// Levels of a full mip chain of a `width`x`height` texture, down to 1x1.
+pub(crate) fn full_mip_level_count(width: u32, height: u32) -> u32 {
+    u32::BITS - width.max(height).max(1).leading_zeros()
+}
+
+impl Context {
+    /// Mipmaps are generated by rendering, so the format must be renderable
+    /// and filterable.
+    pub(crate) fn check_mipmap_format(&self, format: wgpu::TextureFormat) -> Result<(), Error> {
+        let features = self.adapter.get_texture_format_features(format);
+        if format.is_depth_stencil_format()
+            || !features
+                .allowed_usages
+                .contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+            || !features
+                .flags
+                .contains(wgpu::TextureFormatFeatureFlags::FILTERABLE)
+        {
+            return Err(Error::UnsupportedTextureFormat(format));
+        }
+        Ok(())
+    }
+}
+
+impl Texture {
+    /// Fills mip levels 1 and up of every layer by downsampling each level
+    /// into the next with linear filtering. The texture must be 2D, with
+    /// `TEXTURE_BINDING` and `RENDER_ATTACHMENT` usage and a renderable,
+    /// filterable format; sRGB formats are filtered in linear space.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(levels = self.mip_level_count(), format = ?self.format()),
+            err
+        )
+    )]
+    pub fn generate_mipmaps(&self, context: &Context) -> Result<(), Error> {
+        if self.mip_level_count() <= 1 {
+            return Ok(());
+        }
+        let required =
+            wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT;
+        if self.dimension() != wgpu::TextureDimension::D2
+            || self.sample_count() > 1
+            || !self.usage().contains(required)
+        {
+            return Err(Error::TextureCreationFailed);
+        }
+        let format = self.format();
+        context.check_mipmap_format(format)?;
+
+        let blit = BlitPipeline::new(context);
+        let sampler = context.linear_sampler();
+        let mut command_encoder =
+            context
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Mipmap Encoder"),
+                });
+        for layer in 0..self.depth_or_array_layers() {
+            let mut source = self.subresource_view(0, layer)?;
+            for level in 1..self.mip_level_count() {
+                let target = self.subresource_view(level, layer)?;
+                blit.draw(
+                    context,
+                    &mut command_encoder,
+                    source,
+                    &sampler,
+                    target,
+                    format,
+                    None,
+                    None,
+                );
+                source = target;
+            }
+        }
+        context.submit(command_encoder);
+        Ok(())
+    }
+}
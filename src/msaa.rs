@@ -0,0 +1,127 @@
+use super::context::Context;
+use super::error::Error;
+use super::render_target::RenderTargetBuilder;
+use super::texture::Texture;
+
+impl Context {
+    /// Sample counts `format` can be rendered with on this device, 1
+    /// included. Counts other than 1 and 4 need the device feature
+    /// `TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES`.
+    pub fn supported_sample_counts(&self, format: wgpu::TextureFormat) -> Vec<u32> {
+        let features = self.adapter.get_texture_format_features(format);
+        if !features
+            .allowed_usages
+            .contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+        {
+            return vec![1];
+        }
+        let adapter_specific = self
+            .device
+            .features()
+            .contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+        features
+            .flags
+            .supported_sample_counts()
+            .into_iter()
+            .filter(|&count| adapter_specific || count == 1 || count == 4)
+            .collect()
+    }
+
+    /// Creates a multisampled color or depth attachment. Its samples are
+    /// resolved into a single-sampled texture of the same size and format,
+    /// see `Texture::color_attachment` and `Context::resolve`. The texture
+    /// is transient, usable only as an attachment.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self),
+            fields(width = *width, height = *height),
+            err
+        )
+    )]
+    pub fn msaa_texture(
+        &self,
+        width: &u32,
+        height: &u32,
+        sample_count: u32,
+        texture_format: &wgpu::TextureFormat,
+        label: Option<&str>,
+    ) -> Result<Texture, Error> {
+        let mut builder = RenderTargetBuilder::new(*width, *height, *texture_format)
+            .sample_count(sample_count)
+            .transient(true);
+        if let Some(label) = label {
+            builder = builder.label(label);
+        }
+        builder.build(self)
+    }
+
+    /// Resolves the samples of `msaa` into `target` with an otherwise empty
+    /// render pass, for passes recorded without a resolve target. The
+    /// samples are kept for further passes.
+    pub fn resolve(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        msaa: &Texture,
+        target: &Texture,
+    ) -> Result<(), Error> {
+        check_resolve(msaa, target)?;
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("MSAA Resolve"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &msaa.view,
+                resolve_target: Some(&target.view),
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        Ok(())
+    }
+}
+
+fn check_resolve(msaa: &Texture, target: &Texture) -> Result<(), Error> {
+    if msaa.sample_count() == 1 || target.sample_count() != 1 {
+        return Err(Error::TextureCreationFailed);
+    }
+    if msaa.format() != target.format() {
+        return Err(Error::UnsupportedTextureFormat(target.format()));
+    }
+    if msaa.size() != target.size() {
+        return Err(Error::TextureSizeMismatch {
+            expected: msaa.size(),
+            actual: target.size(),
+        });
+    }
+    Ok(())
+}
+
+impl Texture {
+    /// A color attachment of this texture for `RenderPassDescriptor`,
+    /// loaded with `load`. With a `resolve_target`, the samples are
+    /// resolved into it at the end of the pass and not stored, which saves
+    /// bandwidth on tiled GPUs; load a multisampled texture only when its
+    /// samples were stored by an earlier pass without a resolve target.
+    pub fn color_attachment<'a>(
+        &'a self,
+        resolve_target: Option<&'a Texture>,
+        load: wgpu::LoadOp<wgpu::Color>,
+    ) -> wgpu::RenderPassColorAttachment<'a> {
+        wgpu::RenderPassColorAttachment {
+            view: &self.view,
+            resolve_target: resolve_target.map(|target| &target.view),
+            ops: wgpu::Operations {
+                load,
+                store: match resolve_target {
+                    Some(_) => wgpu::StoreOp::Discard,
+                    None => wgpu::StoreOp::Store,
+                },
+            },
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use super::context::Context;
+use super::diagnostics::{describe_buffer, describe_texture, PassRecord};
+use super::error::Error;
+use super::math::normal_matrix;
+use super::mesh::Mesh;
+use super::pass_ops::PassOps;
+use super::texture::Texture;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use wgpu::util::DeviceExt;
+
+const NORMAL: u32 = 0;
+const TANGENT: u32 = 1;
+const BITANGENT: u32 = 2;
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    view_projection: [[f32; 4]; 4],
+    model: [[f32; 4]; 4],
+    normal_matrix: [[f32; 4]; 4],
+    kinds: [u32; 4],
+    length: f32,
+    _padding: [u32; 3],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct Frame {
+    position: [f32; 3],
+    normal: [f32; 3],
+    tangent: [f32; 4],
+}
+
+const FRAME_ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+    wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x4];
+
+fn frame_layout() -> wgpu::VertexBufferLayout<'static> {
+    wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<Frame>() as u64,
+        step_mode: wgpu::VertexStepMode::Instance,
+        attributes: &FRAME_ATTRIBUTES,
+    }
+}
+
+/// Positions, normals and `Mesh::compute_tangents` tangents of a mesh's
+/// vertices on the GPU, drawn by `NormalVisualizer`.
+pub struct TangentFrames {
+    pub buffer: wgpu::Buffer,
+    pub count: u32,
+}
+
+/// Which lines `NormalVisualizer::draw` draws for a mesh and where.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NormalLineSettings {
+    /// Object-to-world transform of the mesh.
+    pub model: [[f32; 4]; 4],
+    /// Length of the lines in world units.
+    pub length: f32,
+    /// Normals, in blue.
+    pub normals: bool,
+    /// Tangents, in red.
+    pub tangents: bool,
+    /// Bitangents, in green.
+    pub bitangents: bool,
+}
+
+impl Default for NormalLineSettings {
+    fn default() -> Self {
+        Self {
+            model: [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+            length: 0.1,
+            normals: true,
+            tangents: true,
+            bitangents: true,
+        }
+    }
+}
+
+impl Context {
+    /// Uploads the tangent frames of `mesh` for `NormalVisualizer`.
+    pub fn upload_tangent_frames(
+        &self,
+        mesh: &Mesh,
+        label: Option<&str>,
+    ) -> Result<TangentFrames, Error> {
+        if mesh.vertices.is_empty() {
+            return Err(Error::InvalidMesh("no vertices".to_string()));
+        }
+        let frames: Vec<Frame> = mesh
+            .vertices
+            .iter()
+            .zip(mesh.compute_tangents())
+            .map(|(vertex, tangent)| Frame {
+                position: vertex.position,
+                normal: vertex.normal,
+                tangent,
+            })
+            .collect();
+        Ok(TangentFrames {
+            buffer: self.buffer_with_data(
+                bytemuck::cast_slice(&frames),
+                wgpu::BufferUsages::VERTEX,
+                label,
+            )?,
+            count: frames.len() as u32,
+        })
+    }
+}
+
+/// Debug lines along the normals, tangents and bitangents of a mesh's
+/// vertices, expanded from one instance per vertex on the GPU, for
+/// diagnosing normal map and shading artifacts.
+pub struct NormalVisualizer {
+    module: wgpu::ShaderModule,
+    layout: wgpu::BindGroupLayout,
+    pipeline_layout: wgpu::PipelineLayout,
+    #[allow(clippy::type_complexity)]
+    pipelines: Mutex<
+        HashMap<
+            (
+                wgpu::TextureFormat,
+                Option<(wgpu::TextureFormat, wgpu::CompareFunction)>,
+            ),
+            wgpu::RenderPipeline,
+        >,
+    >,
+    /// Loads and stores of the target and depth, by default drawing over
+    /// their contents.
+    pub ops: PassOps,
+}
+
+impl NormalVisualizer {
+    pub fn new(context: &Context) -> Self {
+        let device = &context.device;
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("normal_lines.wgsl"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/normal_lines.wgsl").into()),
+        });
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Normal Lines Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Normal Lines Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        Self {
+            module,
+            layout,
+            pipeline_layout,
+            pipelines: Mutex::new(HashMap::new()),
+            ops: PassOps::default(),
+        }
+    }
+
+    /// Draws the lines chosen by `settings` for `frames` into `target`.
+    /// With `depth`, the mesh's depth buffer, lines are hidden
+    /// behind nearer surfaces using the context's depth comparison, without
+    /// writing depth.
+    #[allow(clippy::too_many_arguments)]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(vertices = frames.count))
+    )]
+    pub fn draw(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        frames: &TangentFrames,
+        view_projection: &[[f32; 4]; 4],
+        settings: &NormalLineSettings,
+        target: &Texture,
+        depth: Option<&Texture>,
+    ) -> Result<(), Error> {
+        if std::iter::once(target).chain(depth).any(|texture| {
+            !texture
+                .usage()
+                .contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+        }) {
+            return Err(Error::TextureCreationFailed);
+        }
+        let enabled = [
+            (NORMAL, settings.normals),
+            (TANGENT, settings.tangents),
+            (BITANGENT, settings.bitangents),
+        ];
+        let mut kinds = [0; 4];
+        let mut kind_count = 0;
+        for (kind, _) in enabled.into_iter().filter(|&(_, enabled)| enabled) {
+            kinds[kind_count] = kind;
+            kind_count += 1;
+        }
+        if kind_count == 0 {
+            return Ok(());
+        }
+
+        let device = &context.device;
+        let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Normal Lines Params"),
+            contents: bytemuck::bytes_of(&Params {
+                view_projection: *view_projection,
+                model: settings.model,
+                normal_matrix: normal_matrix(&settings.model),
+                kinds,
+                length: settings.length,
+                ..Default::default()
+            }),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Normal Lines Bind Group"),
+            layout: &self.layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: params.as_entire_binding(),
+            }],
+        });
+        context.diagnostics.record_pass(|| {
+            let mut resources = vec![
+                describe_buffer("frames", &frames.buffer),
+                describe_texture("target", &target.texture),
+            ];
+            if let Some(depth) = depth {
+                resources.push(describe_texture("depth", &depth.texture));
+            }
+            PassRecord::new("Normal Lines", "vs_lines", resources)
+        });
+
+        let format = target.format();
+        let depth_state = depth.map(|depth| (depth.format(), context.depth_compare_function()));
+        let mut pipelines = self
+            .pipelines
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let pipeline = pipelines.entry((format, depth_state)).or_insert_with(|| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Normal Lines"),
+                layout: Some(&self.pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &self.module,
+                    entry_point: "vs_lines",
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    buffers: &[frame_layout()],
+                },
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::LineList,
+                    ..Default::default()
+                },
+                depth_stencil: depth_state.map(|(format, depth_compare)| wgpu::DepthStencilState {
+                    format,
+                    depth_write_enabled: false,
+                    depth_compare,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &self.module,
+                    entry_point: "fs_lines",
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    targets: &[Some(format.into())],
+                }),
+                multiview: None,
+            })
+        });
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Normal Lines"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &target.view,
+                resolve_target: None,
+                ops: self.ops.color(),
+            })],
+            depth_stencil_attachment: depth.map(|depth| wgpu::RenderPassDepthStencilAttachment {
+                view: &depth.view,
+                depth_ops: Some(self.ops.depth()),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.set_vertex_buffer(0, frames.buffer.slice(..));
+        pass.draw(0..kind_count as u32 * 2, 0..frames.count);
+
+        Ok(())
+    }
+}
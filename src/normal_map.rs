@@ -0,0 +1,236 @@
+use super::context::Context;
+use super::diagnostics::{describe_texture, PassRecord};
+use super::error::Error;
+use super::texture::Texture;
+use super::texture_builder::TextureBuilder;
+use wgpu::util::DeviceExt;
+
+const WORKGROUP_SIZE: u32 = 8;
+const NORMAL_MAP_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    size: [u32; 2],
+    strength: f32,
+    wrap: u32,
+}
+
+/// Generates tangent-space normal maps from height textures with a Sobel
+/// filter, for baking bump and terrain heights into normal maps.
+pub struct NormalMapGenerator {
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::ComputePipeline,
+    /// Multiplier of the height slopes, with heights in texels: at 1, a
+    /// height difference of 1 between neighbors is a 45 degree slope.
+    pub strength: f32,
+    /// Wraps around the edges instead of clamping, for tiling textures.
+    pub wrap: bool,
+}
+
+impl NormalMapGenerator {
+    pub fn new(context: &Context) -> Self {
+        let device = &context.device;
+        let module = device.create_shader_module(wgpu::include_wgsl!("shaders/normal_map.wgsl"));
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Normal Map Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: NORMAL_MAP_FORMAT,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Normal Map Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Normal Map Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &module,
+            entry_point: "generate",
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        });
+
+        Self {
+            bind_group_layout,
+            pipeline,
+            strength: 1.0,
+            wrap: false,
+        }
+    }
+
+    /// Creates an `Rgba8Unorm` storage texture for normal maps of the given
+    /// size.
+    pub fn create_normal_map(
+        &self,
+        context: &Context,
+        width: &u32,
+        height: &u32,
+        label: Option<&str>,
+    ) -> Result<Texture, Error> {
+        TextureBuilder::new(*width, *height, NORMAL_MAP_FORMAT)
+            .label(label.unwrap_or("Normal Map"))
+            .usage(
+                wgpu::TextureUsages::STORAGE_BINDING
+                    | wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::COPY_SRC,
+            )
+            .build(context)
+    }
+
+    /// Writes the normals of `heights`, read from the red channel of a
+    /// single-sampled float texture, into `output` of the same size. The
+    /// normals are encoded into [0, 1] with +y up (OpenGL convention), and
+    /// the heights are kept in alpha for parallax mapping.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, context, encoder, heights, output),
+            fields(width = heights.width(), height = heights.height()),
+            err
+        )
+    )]
+    pub fn generate(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        heights: &Texture,
+        output: &Texture,
+    ) -> Result<(), Error> {
+        if !matches!(
+            heights.format().sample_type(None, None),
+            Some(wgpu::TextureSampleType::Float { .. })
+        ) || heights.view_dimension() != wgpu::TextureViewDimension::D2
+            || heights.sample_count() != 1
+        {
+            return Err(Error::UnsupportedTextureFormat(heights.format()));
+        }
+        if output.format() != NORMAL_MAP_FORMAT {
+            return Err(Error::UnsupportedTextureFormat(output.format()));
+        }
+        if !heights
+            .usage()
+            .contains(wgpu::TextureUsages::TEXTURE_BINDING)
+            || !output
+                .usage()
+                .contains(wgpu::TextureUsages::STORAGE_BINDING)
+        {
+            return Err(Error::TextureCreationFailed);
+        }
+        if output.size() != heights.size() {
+            return Err(Error::TextureSizeMismatch {
+                expected: heights.size(),
+                actual: output.size(),
+            });
+        }
+
+        let params = context
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Normal Map Params"),
+                contents: bytemuck::bytes_of(&Params {
+                    size: [heights.width(), heights.height()],
+                    strength: self.strength,
+                    wrap: self.wrap as u32,
+                }),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+        let bind_group = context
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Normal Map Bind Group"),
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: params.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&heights.view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::TextureView(&output.view),
+                    },
+                ],
+            });
+        context.diagnostics.record_pass(|| {
+            PassRecord::new(
+                "Normal Map",
+                "generate",
+                vec![
+                    describe_texture("heights", &heights.texture),
+                    describe_texture("output", &output.texture),
+                ],
+            )
+        });
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Normal Map"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.dispatch_workgroups(
+            heights.width().div_ceil(WORKGROUP_SIZE),
+            heights.height().div_ceil(WORKGROUP_SIZE),
+            1,
+        );
+
+        Ok(())
+    }
+}
+
+impl Context {
+    /// Generates the normal map of `heights` with `strength`, as
+    /// `NormalMapGenerator::generate`.
+    pub fn normal_map_from_height(
+        &self,
+        heights: &Texture,
+        strength: f32,
+        label: Option<&str>,
+    ) -> Result<Texture, Error> {
+        let mut generator = NormalMapGenerator::new(self);
+        generator.strength = strength;
+        let output =
+            generator.create_normal_map(self, &heights.width(), &heights.height(), label)?;
+        let mut command_encoder =
+            self.device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Normal Map Encoder"),
+                });
+        generator.generate(self, &mut command_encoder, heights, &output)?;
+        self.submit(command_encoder);
+        Ok(output)
+    }
+}
@@ -0,0 +1,134 @@
+use super::error::Error;
+use super::mesh::Mesh;
+#[cfg(feature = "scene")]
+use super::scene::SceneDescription;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Writes `mesh` with vertex indices starting at `first` (OBJ indices are
+/// global and 1-based), transforming positions and normals by the
+/// column-major `transform`.
+fn write_mesh(
+    writer: &mut impl Write,
+    mesh: &Mesh,
+    transform: Option<&[[f32; 4]; 4]>,
+    first: usize,
+) -> std::io::Result<()> {
+    // The columns divided by their squared lengths are the inverse
+    // transpose of a rotation and scale.
+    let normal_matrix = transform.map(|m| {
+        [0, 1, 2].map(|c| {
+            let length = m[c][0] * m[c][0] + m[c][1] * m[c][1] + m[c][2] * m[c][2];
+            [0, 1, 2].map(|r| if length > 0.0 { m[c][r] / length } else { 0.0 })
+        })
+    });
+    for (i, vertex) in mesh.vertices.iter().enumerate() {
+        let [x, y, z] = match transform {
+            Some(m) => [0, 1, 2]
+                .map(|r| (0..3).map(|c| m[c][r] * vertex.position[c]).sum::<f32>() + m[3][r]),
+            None => vertex.position,
+        };
+        match mesh.colors.get(i) {
+            Some([r, g, b, _]) => writeln!(writer, "v {x} {y} {z} {r} {g} {b}")?,
+            None => writeln!(writer, "v {x} {y} {z}")?,
+        }
+        let [u, v] = vertex.uv;
+        writeln!(writer, "vt {u} {}", 1.0 - v)?;
+        let mut normal = match normal_matrix {
+            Some(m) => [0, 1, 2].map(|r| (0..3).map(|c| m[c][r] * vertex.normal[c]).sum::<f32>()),
+            None => vertex.normal,
+        };
+        let length = normal.iter().map(|c| c * c).sum::<f32>().sqrt();
+        if length > 0.0 {
+            normal = normal.map(|c| c / length);
+        }
+        let [nx, ny, nz] = normal;
+        writeln!(writer, "vn {nx} {ny} {nz}")?;
+    }
+    for triangle in mesh.triangles() {
+        let [a, b, c] = triangle.map(|i| i as usize + first);
+        writeln!(writer, "f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}")?;
+    }
+    Ok(())
+}
+
+impl Mesh {
+    pub fn save_obj(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        self.write_obj(BufWriter::new(File::create(path)?))
+    }
+
+    /// Writes positions (followed by RGB if the mesh has vertex colors),
+    /// texture coordinates with a bottom-left origin, normals and
+    /// triangles.
+    pub fn write_obj(&self, mut writer: impl Write) -> Result<(), Error> {
+        writeln!(writer, "# wgpu-tools")?;
+        write_mesh(&mut writer, self, None, 1)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// OBJ names end at whitespace.
+#[cfg(feature = "scene")]
+fn obj_name(name: &str) -> String {
+    name.replace(char::is_whitespace, "_")
+}
+
+#[cfg(feature = "scene")]
+impl SceneDescription {
+    /// Writes every object with its transform applied to `path`, and the
+    /// materials to a `.mtl` file next to it. Texture paths are written as
+    /// they are in the scene, and lights, camera and environment are not
+    /// exported.
+    pub fn save_obj(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        self.validate()?;
+        let path = path.as_ref();
+        let library = path.with_extension("mtl");
+        let library_name = library.file_name().unwrap_or_default().to_string_lossy();
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "# wgpu-tools\nmtllib {library_name}")?;
+        let meshes: Vec<_> = self
+            .meshes
+            .iter()
+            .map(|m| (&m.name, m.source.to_mesh()))
+            .collect();
+        let mut first = 1;
+        for (index, object) in self.objects.iter().enumerate() {
+            let Some((_, mesh)) = meshes.iter().find(|(name, _)| **name == object.mesh) else {
+                continue;
+            };
+            writeln!(writer, "o {}_{index}", obj_name(&object.mesh))?;
+            if let Some(material) = &object.material {
+                writeln!(writer, "usemtl {}", obj_name(material))?;
+            }
+            write_mesh(&mut writer, mesh, Some(&object.transform()), first)?;
+            first += mesh.vertices.len();
+        }
+        writer.flush()?;
+
+        let mut writer = BufWriter::new(File::create(&library)?);
+        writeln!(writer, "# wgpu-tools")?;
+        for material in &self.materials {
+            let [r, g, b, a] = material.base_color;
+            let [er, eg, eb] = material.emissive;
+            writeln!(writer, "\nnewmtl {}", obj_name(&material.name))?;
+            writeln!(writer, "Kd {r} {g} {b}\nd {a}\nKe {er} {eg} {eb}")?;
+            writeln!(
+                writer,
+                "Pr {}\nPm {}",
+                material.roughness, material.metallic
+            )?;
+            if let Some(texture) = &material.base_color_texture {
+                writeln!(
+                    writer,
+                    "map_Kd {}",
+                    texture.to_string_lossy().replace('\\', "/")
+                )?;
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
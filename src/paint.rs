@@ -0,0 +1,762 @@
+use super::context::Context;
+use super::diagnostics::{describe_texture, PassRecord};
+use super::error::Error;
+use super::selection::{MagicWand, SelectionOptions};
+use super::texture::Texture;
+use std::collections::HashMap;
+use wgpu::util::DeviceExt;
+
+/// Edge length of the undo snapshot tiles.
+const TILE_SIZE: u32 = 64;
+
+const CANVAS_USAGE: wgpu::TextureUsages = wgpu::TextureUsages::RENDER_ATTACHMENT
+    .union(wgpu::TextureUsages::TEXTURE_BINDING)
+    .union(wgpu::TextureUsages::COPY_SRC)
+    .union(wgpu::TextureUsages::COPY_DST);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    /// Paints over the canvas.
+    #[default]
+    Normal,
+    /// Adds the brush color.
+    Add,
+    /// Multiplies the canvas color by the brush color.
+    Multiply,
+    /// Lowers the canvas alpha by the brush alpha.
+    Erase,
+}
+
+impl BlendMode {
+    const ALL: [Self; 4] = [Self::Normal, Self::Add, Self::Multiply, Self::Erase];
+
+    /// Blending of the premultiplied stamp color.
+    fn blend_state(&self) -> wgpu::BlendState {
+        let component = |src_factor, dst_factor| wgpu::BlendComponent {
+            src_factor,
+            dst_factor,
+            operation: wgpu::BlendOperation::Add,
+        };
+        use wgpu::BlendFactor::{Dst, One, OneMinusSrcAlpha, Zero};
+        let (color, alpha) = match self {
+            Self::Normal => (
+                component(One, OneMinusSrcAlpha),
+                component(One, OneMinusSrcAlpha),
+            ),
+            Self::Add => (component(One, One), component(One, OneMinusSrcAlpha)),
+            Self::Multiply => (component(Dst, OneMinusSrcAlpha), component(Zero, One)),
+            Self::Erase => (
+                component(Zero, OneMinusSrcAlpha),
+                component(Zero, OneMinusSrcAlpha),
+            ),
+        };
+        wgpu::BlendState { color, alpha }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Brush {
+    /// Diameter in pixels at full pressure.
+    pub size: f32,
+    /// Fraction of the radius painted at full strength before the edge
+    /// falls off.
+    pub hardness: f32,
+    /// Linear RGBA color.
+    pub color: [f32; 4],
+    /// Alpha of a single stamp at full pressure.
+    pub opacity: f32,
+    /// Distance between stamps as a fraction of the size.
+    pub spacing: f32,
+    /// How much pressure scales the size, from 0 (not at all) to 1.
+    pub size_pressure: f32,
+    /// How much pressure scales the opacity, from 0 (not at all) to 1.
+    pub opacity_pressure: f32,
+    pub blend_mode: BlendMode,
+}
+
+impl Default for Brush {
+    fn default() -> Self {
+        Self {
+            size: 16.0,
+            hardness: 0.8,
+            color: [0.0, 0.0, 0.0, 1.0],
+            opacity: 1.0,
+            spacing: 0.1,
+            size_pressure: 1.0,
+            opacity_pressure: 0.0,
+            blend_mode: BlendMode::Normal,
+        }
+    }
+}
+
+impl Brush {
+    fn radius(&self, pressure: f32) -> f32 {
+        0.5 * self.size * (1.0 + (pressure - 1.0) * self.size_pressure)
+    }
+
+    fn stamp(&self, center: [f32; 2], pressure: f32) -> Stamp {
+        let [r, g, b, a] = self.color;
+        let opacity = self.opacity * (1.0 + (pressure - 1.0) * self.opacity_pressure);
+        Stamp {
+            center,
+            radius: self.radius(pressure),
+            hardness: self.hardness.clamp(0.0, 1.0),
+            color: [r, g, b, a * opacity.clamp(0.0, 1.0)],
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct Stamp {
+    center: [f32; 2],
+    radius: f32,
+    hardness: f32,
+    color: [f32; 4],
+}
+
+impl Stamp {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 4] =
+        wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32, 2 => Float32, 3 => Float32x4];
+}
+
+/// Canvas contents of one tile before an edit.
+struct Tile {
+    origin: [u32; 2],
+    texture: wgpu::Texture,
+}
+
+#[derive(Default)]
+struct Stroke {
+    tiles: Vec<Tile>,
+    last: Option<([f32; 2], f32)>,
+    /// Distance along the stroke to the next stamp.
+    next_stamp: f32,
+}
+
+/// Brush painting into a canvas texture it owns. The canvas holds
+/// premultiplied alpha, which is the same as straight alpha where it is
+/// opaque. Each stroke is one undo step, which keeps copies of the tiles
+/// the stroke touched.
+pub struct TexturePainter {
+    canvas: Texture,
+    canvas_size: wgpu::Buffer,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    pipelines: HashMap<(BlendMode, bool), wgpu::RenderPipeline>,
+    fill_layout: wgpu::BindGroupLayout,
+    fill_pipelines: HashMap<BlendMode, wgpu::RenderPipeline>,
+    has_tip: bool,
+    stroke: Option<Stroke>,
+    undo: Vec<Vec<Tile>>,
+    redo: Vec<Vec<Tile>>,
+    /// Oldest steps are dropped beyond this count.
+    pub max_undo_steps: usize,
+}
+
+impl TexturePainter {
+    /// Paints on a transparent canvas of `width` x `height`.
+    pub fn new(
+        context: &Context,
+        width: &u32,
+        height: &u32,
+        texture_format: &wgpu::TextureFormat,
+    ) -> Result<Self, Error> {
+        let size = wgpu::Extent3d {
+            width: *width,
+            height: *height,
+            depth_or_array_layers: 1,
+        };
+        if size.width == 0 || size.height == 0 {
+            return Err(Error::TextureCreationFailed);
+        }
+        context.check_texture_size(&size, wgpu::TextureDimension::D2)?;
+        let texture = context.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Paint Canvas"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: *texture_format,
+            view_formats: &[*texture_format],
+            usage: CANVAS_USAGE,
+        });
+        Self::with_canvas(context, Texture::from_wgpu(context, texture))
+    }
+
+    /// Paints on `texture`, or on a copy of it if it lacks the
+    /// `RENDER_ATTACHMENT`, `COPY_SRC` or `COPY_DST` usage, in which case it
+    /// needs `COPY_SRC`.
+    pub fn from_texture(context: &Context, texture: Texture) -> Result<Self, Error> {
+        if texture
+            .usage()
+            .contains(CANVAS_USAGE - wgpu::TextureUsages::TEXTURE_BINDING)
+        {
+            return Self::with_canvas(context, texture);
+        }
+        if !texture.usage().contains(wgpu::TextureUsages::COPY_SRC)
+            || texture.dimension() != wgpu::TextureDimension::D2
+        {
+            return Err(Error::TextureCreationFailed);
+        }
+        let painter = Self::new(
+            context,
+            &texture.width(),
+            &texture.height(),
+            &texture.format(),
+        )?;
+        context.schedule(|encoder| {
+            encoder.copy_texture_to_texture(
+                texture.texture.as_image_copy(),
+                painter.canvas.texture.as_image_copy(),
+                wgpu::Extent3d {
+                    depth_or_array_layers: 1,
+                    ..texture.size()
+                },
+            )
+        });
+        Ok(painter)
+    }
+
+    fn with_canvas(context: &Context, canvas: Texture) -> Result<Self, Error> {
+        let device = &context.device;
+        let module = device.create_shader_module(wgpu::include_wgsl!("shaders/paint.wgsl"));
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Paint Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let fill_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Paint Fill Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline = |layout: &wgpu::BindGroupLayout,
+                        vertex: wgpu::VertexState,
+                        fragment_entry_point: &str,
+                        blend_mode: BlendMode| {
+            let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Paint Pipeline Layout"),
+                bind_group_layouts: &[layout],
+                push_constant_ranges: &[],
+            });
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Paint Pipeline"),
+                layout: Some(&layout),
+                vertex,
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleStrip,
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &module,
+                    entry_point: fragment_entry_point,
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: canvas.format(),
+                        blend: Some(blend_mode.blend_state()),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                multiview: None,
+            })
+        };
+        let stamp_buffers = [wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Stamp>() as u64,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Stamp::ATTRIBUTES,
+        }];
+        let (mut pipelines, mut fill_pipelines) = (HashMap::new(), HashMap::new());
+        for blend_mode in BlendMode::ALL {
+            for (has_tip, entry_point) in [(false, "fs_round"), (true, "fs_tip")] {
+                let vertex = wgpu::VertexState {
+                    module: &module,
+                    entry_point: "vs_main",
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    buffers: &stamp_buffers,
+                };
+                pipelines.insert(
+                    (blend_mode, has_tip),
+                    pipeline(&bind_group_layout, vertex, entry_point, blend_mode),
+                );
+            }
+            let vertex = wgpu::VertexState {
+                module: &module,
+                entry_point: "vs_fill",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                buffers: &[],
+            };
+            fill_pipelines.insert(
+                blend_mode,
+                pipeline(&fill_layout, vertex, "fs_fill", blend_mode),
+            );
+        }
+
+        let canvas_size = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Paint Canvas Size"),
+            contents: bytemuck::cast_slice(&[
+                canvas.width() as f32,
+                canvas.height() as f32,
+                0.0,
+                0.0,
+            ]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let white = context.texture_from_rgba(
+            &[1.0; 4],
+            &wgpu::TextureFormat::Rgba8Unorm,
+            Some("Paint Tip"),
+        )?;
+        let bind_group = Self::create_bind_group(context, &bind_group_layout, &canvas_size, &white);
+
+        Ok(Self {
+            canvas,
+            canvas_size,
+            bind_group_layout,
+            bind_group,
+            pipelines,
+            fill_layout,
+            fill_pipelines,
+            has_tip: false,
+            stroke: None,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            max_undo_steps: 32,
+        })
+    }
+
+    fn create_bind_group(
+        context: &Context,
+        layout: &wgpu::BindGroupLayout,
+        canvas_size: &wgpu::Buffer,
+        tip: &Texture,
+    ) -> wgpu::BindGroup {
+        context
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Paint Bind Group"),
+                layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: canvas_size.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&tip.view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::Sampler(&tip.sampler),
+                    },
+                ],
+            })
+    }
+
+    pub fn canvas(&self) -> &Texture {
+        &self.canvas
+    }
+
+    pub fn into_canvas(self) -> Texture {
+        self.canvas
+    }
+
+    /// Stamps the red channel times the alpha of `tip`, stretched over the
+    /// brush size, instead of a round brush. The tip needs a filterable
+    /// float format.
+    pub fn set_tip(&mut self, context: &Context, tip: Option<&Texture>) -> Result<(), Error> {
+        let white;
+        let texture = match tip {
+            Some(tip) => tip,
+            None => {
+                white = context.texture_from_rgba(
+                    &[1.0; 4],
+                    &wgpu::TextureFormat::Rgba8Unorm,
+                    Some("Paint Tip"),
+                )?;
+                &white
+            }
+        };
+        self.bind_group =
+            Self::create_bind_group(context, &self.bind_group_layout, &self.canvas_size, texture);
+        self.has_tip = tip.is_some();
+        Ok(())
+    }
+
+    pub fn is_painting(&self) -> bool {
+        self.stroke.is_some()
+    }
+
+    /// Continues the current stroke to `position` in pixels, starting one
+    /// if there is none, and stamps `brush` along the way. Pressure is
+    /// interpolated from the previous point.
+    pub fn stroke_to(
+        &mut self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        brush: &Brush,
+        position: [f32; 2],
+        pressure: f32,
+    ) {
+        let pressure = pressure.clamp(0.0, 1.0);
+        let stroke = self.stroke.get_or_insert_with(Stroke::default);
+        let spacing = |pressure: f32| (brush.spacing * 2.0 * brush.radius(pressure)).max(1.0);
+        let mut stamps = Vec::new();
+        match stroke.last {
+            None => {
+                stamps.push(brush.stamp(position, pressure));
+                stroke.next_stamp = spacing(pressure);
+            }
+            Some((from, from_pressure)) => {
+                let delta = [position[0] - from[0], position[1] - from[1]];
+                let length = (delta[0] * delta[0] + delta[1] * delta[1]).sqrt();
+                let mut distance = stroke.next_stamp;
+                while distance <= length {
+                    let t = distance / length;
+                    let point = [from[0] + delta[0] * t, from[1] + delta[1] * t];
+                    let point_pressure = from_pressure + (pressure - from_pressure) * t;
+                    stamps.push(brush.stamp(point, point_pressure));
+                    distance += spacing(point_pressure);
+                }
+                stroke.next_stamp = distance - length;
+            }
+        }
+        stroke.last = Some((position, pressure));
+        self.paint(context, encoder, brush.blend_mode, &stamps);
+    }
+
+    /// Ends the current stroke, making it one undo step.
+    pub fn end_stroke(&mut self) {
+        let Some(stroke) = self.stroke.take() else {
+            return;
+        };
+        self.push_undo(stroke.tiles);
+    }
+
+    fn push_undo(&mut self, step: Vec<Tile>) {
+        if step.is_empty() {
+            return;
+        }
+        self.undo.push(step);
+        if self.undo.len() > self.max_undo_steps {
+            let excess = self.undo.len() - self.max_undo_steps;
+            self.undo.drain(..excess);
+        }
+    }
+
+    /// Paints `color` scaled by the red channel of `mask`, a float texture
+    /// of the canvas size such as a `MagicWand` selection, as one undo
+    /// step.
+    pub fn fill(
+        &mut self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        mask: &Texture,
+        color: [f32; 4],
+        blend_mode: BlendMode,
+    ) -> Result<(), Error> {
+        if (mask.width(), mask.height()) != (self.canvas.width(), self.canvas.height()) {
+            return Err(Error::TextureSizeMismatch {
+                expected: self.canvas.size(),
+                actual: mask.size(),
+            });
+        }
+        self.end_stroke();
+        let (width, height) = (self.canvas.width(), self.canvas.height());
+        let step = (0..height.div_ceil(TILE_SIZE))
+            .flat_map(|y| (0..width.div_ceil(TILE_SIZE)).map(move |x| [x, y]))
+            .map(|[x, y]| {
+                snapshot(
+                    context,
+                    encoder,
+                    &self.canvas,
+                    [x * TILE_SIZE, y * TILE_SIZE],
+                )
+            })
+            .collect();
+        self.push_undo(step);
+        self.redo.clear();
+
+        let color = context
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Paint Fill Color"),
+                contents: bytemuck::cast_slice(&color),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+        let bind_group = context
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Paint Fill Bind Group"),
+                layout: &self.fill_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: wgpu::BindingResource::TextureView(&mask.view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: color.as_entire_binding(),
+                    },
+                ],
+            });
+        context.diagnostics.record_pass(|| {
+            PassRecord::new(
+                "Paint Pass",
+                "fs_fill",
+                vec![
+                    describe_texture("mask", &mask.texture),
+                    describe_texture("canvas", &self.canvas.texture),
+                ],
+            )
+        });
+        let mut pass = self.begin_pass(encoder);
+        pass.set_pipeline(&self.fill_pipelines[&blend_mode]);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+        Ok(())
+    }
+
+    /// Selects the pixels around `seed` on the canvas, as of the last
+    /// submission, fills them and submits.
+    pub fn flood_fill(
+        &mut self,
+        context: &Context,
+        wand: &MagicWand,
+        seed: [u32; 2],
+        options: &SelectionOptions,
+        color: [f32; 4],
+        blend_mode: BlendMode,
+    ) -> Result<(), Error> {
+        let mask = wand.select(context, &self.canvas, seed, options)?;
+        let mut command_encoder =
+            context
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Flood Fill Encoder"),
+                });
+        self.fill(context, &mut command_encoder, &mask, color, blend_mode)?;
+        context.submit(command_encoder);
+        Ok(())
+    }
+
+    fn begin_pass<'a>(&'a self, encoder: &'a mut wgpu::CommandEncoder) -> wgpu::RenderPass<'a> {
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Paint Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &self.canvas.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        })
+    }
+
+    fn paint(
+        &mut self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        blend_mode: BlendMode,
+        stamps: &[Stamp],
+    ) {
+        if stamps.is_empty() {
+            return;
+        }
+        // Save the tiles under the stamps the first time the stroke
+        // touches them.
+        let (width, height) = (self.canvas.width(), self.canvas.height());
+        let tile_range = |low: f32, high: f32, size: u32| {
+            let low = (low.floor().max(0.0) as u32).min(size) / TILE_SIZE;
+            let high = (high.ceil().max(0.0) as u32).min(size).div_ceil(TILE_SIZE);
+            low..high
+        };
+        let mut origins = Vec::new();
+        for stamp in stamps {
+            let radius = stamp.radius + 1.0;
+            let [x, y] = stamp.center;
+            for tile_y in tile_range(y - radius, y + radius, height) {
+                for tile_x in tile_range(x - radius, x + radius, width) {
+                    origins.push([tile_x * TILE_SIZE, tile_y * TILE_SIZE]);
+                }
+            }
+        }
+        origins.sort_unstable();
+        origins.dedup();
+        let stroke = self.stroke.get_or_insert_with(Stroke::default);
+        origins.retain(|origin| !stroke.tiles.iter().any(|tile| tile.origin == *origin));
+        if !origins.is_empty() {
+            self.redo.clear();
+        }
+        for origin in origins {
+            let tile = snapshot(context, encoder, &self.canvas, origin);
+            stroke.tiles.push(tile);
+        }
+
+        let instances = context
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Paint Stamps"),
+                contents: bytemuck::cast_slice(stamps),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+        context.diagnostics.record_pass(|| {
+            PassRecord::new(
+                "Paint Pass",
+                "Paint Pipeline",
+                vec![describe_texture("canvas", &self.canvas.texture)],
+            )
+        });
+        let mut pass = self.begin_pass(encoder);
+        pass.set_pipeline(&self.pipelines[&(blend_mode, self.has_tip)]);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.set_vertex_buffer(0, instances.slice(..));
+        pass.draw(0..4, 0..stamps.len() as u32);
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.stroke
+            .as_ref()
+            .is_some_and(|stroke| !stroke.tiles.is_empty())
+            || !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Ends the current stroke and restores the canvas from before the
+    /// last one. Returns whether there was a step to undo.
+    pub fn undo(&mut self, context: &Context, encoder: &mut wgpu::CommandEncoder) -> bool {
+        self.end_stroke();
+        let Some(step) = self.undo.pop() else {
+            return false;
+        };
+        let step = self.swap(context, encoder, step);
+        self.redo.push(step);
+        true
+    }
+
+    pub fn redo(&mut self, context: &Context, encoder: &mut wgpu::CommandEncoder) -> bool {
+        self.end_stroke();
+        let Some(step) = self.redo.pop() else {
+            return false;
+        };
+        let step = self.swap(context, encoder, step);
+        self.undo.push(step);
+        true
+    }
+
+    /// Writes the tiles of `step` to the canvas and returns the tiles they
+    /// replaced.
+    fn swap(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        step: Vec<Tile>,
+    ) -> Vec<Tile> {
+        step.into_iter()
+            .map(|tile| {
+                let replaced = snapshot(context, encoder, &self.canvas, tile.origin);
+                encoder.copy_texture_to_texture(
+                    tile.texture.as_image_copy(),
+                    wgpu::ImageCopyTexture {
+                        origin: origin3d(tile.origin),
+                        ..self.canvas.texture.as_image_copy()
+                    },
+                    tile.texture.size(),
+                );
+                replaced
+            })
+            .collect()
+    }
+}
+
+fn origin3d([x, y]: [u32; 2]) -> wgpu::Origin3d {
+    wgpu::Origin3d { x, y, z: 0 }
+}
+
+/// Copies the tile of `canvas` at `origin`, clipped to the canvas.
+fn snapshot(
+    context: &Context,
+    encoder: &mut wgpu::CommandEncoder,
+    canvas: &Texture,
+    origin: [u32; 2],
+) -> Tile {
+    let size = wgpu::Extent3d {
+        width: TILE_SIZE.min(canvas.width() - origin[0]),
+        height: TILE_SIZE.min(canvas.height() - origin[1]),
+        depth_or_array_layers: 1,
+    };
+    let texture = context.device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Paint Undo Tile"),
+        size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: canvas.format(),
+        view_formats: &[],
+        usage: wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::COPY_DST,
+    });
+    encoder.copy_texture_to_texture(
+        wgpu::ImageCopyTexture {
+            origin: origin3d(origin),
+            ..canvas.texture.as_image_copy()
+        },
+        texture.as_image_copy(),
+        size,
+    );
+    Tile { origin, texture }
+}
@@ -0,0 +1,56 @@
+/// How a render pass helper loads and stores its attachments. The default
+/// draws over the existing contents and keeps everything.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PassOps {
+    /// Clears the color target to this color first instead of loading it.
+    pub clear_color: Option<wgpu::Color>,
+    /// Clears the depth attachment to this depth first instead of loading
+    /// it, e.g. `Context::depth_clear_value`.
+    pub clear_depth: Option<f32>,
+    /// Stores the depth attachment at the end of the pass. Without it, the
+    /// depth is discarded, which saves bandwidth on tiled GPUs when no later
+    /// pass needs it.
+    pub store_depth: bool,
+}
+
+impl Default for PassOps {
+    fn default() -> Self {
+        Self {
+            clear_color: None,
+            clear_depth: None,
+            store_depth: true,
+        }
+    }
+}
+
+impl PassOps {
+    /// Clears color and depth, and discards depth at the end.
+    pub fn clear(color: wgpu::Color, depth: f32) -> Self {
+        Self {
+            clear_color: Some(color),
+            clear_depth: Some(depth),
+            store_depth: false,
+        }
+    }
+
+    pub(crate) fn color(&self) -> wgpu::Operations<wgpu::Color> {
+        wgpu::Operations {
+            load: self
+                .clear_color
+                .map_or(wgpu::LoadOp::Load, wgpu::LoadOp::Clear),
+            store: wgpu::StoreOp::Store,
+        }
+    }
+
+    pub(crate) fn depth(&self) -> wgpu::Operations<f32> {
+        wgpu::Operations {
+            load: self
+                .clear_depth
+                .map_or(wgpu::LoadOp::Load, wgpu::LoadOp::Clear),
+            store: match self.store_depth {
+                true => wgpu::StoreOp::Store,
+                false => wgpu::StoreOp::Discard,
+            },
+        }
+    }
+}
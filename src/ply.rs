@@ -0,0 +1,501 @@
+use super::coordinates::ImportOptions;
+use super::error::Error;
+use super::mesh::{Mesh, Vertex};
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PlyFormat {
+    Ascii,
+    #[default]
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Result<Self, Error> {
+        Ok(match name {
+            "char" | "int8" => Scalar::I8,
+            "uchar" | "uint8" => Scalar::U8,
+            "short" | "int16" => Scalar::I16,
+            "ushort" | "uint16" => Scalar::U16,
+            "int" | "int32" => Scalar::I32,
+            "uint" | "uint32" => Scalar::U32,
+            "float" | "float32" => Scalar::F32,
+            "double" | "float64" => Scalar::F64,
+            _ => return Err(invalid(&format!("unknown type {name}"))),
+        })
+    }
+
+    /// The value mapping to 1.0 for colors.
+    fn color_scale(self) -> f64 {
+        match self {
+            Scalar::I8 => i8::MAX as f64,
+            Scalar::U8 => u8::MAX as f64,
+            Scalar::I16 => i16::MAX as f64,
+            Scalar::U16 => u16::MAX as f64,
+            Scalar::I32 => i32::MAX as f64,
+            Scalar::U32 => u32::MAX as f64,
+            Scalar::F32 | Scalar::F64 => 1.0,
+        }
+    }
+}
+
+enum PropertyType {
+    Scalar(Scalar),
+    List { count: Scalar, item: Scalar },
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<(String, PropertyType)>,
+}
+
+impl Element {
+    fn position(&self, names: &[&str]) -> Option<usize> {
+        self.properties
+            .iter()
+            .position(|(name, _)| names.contains(&name.as_str()))
+    }
+}
+
+fn invalid(message: &str) -> Error {
+    Error::InvalidMesh(format!("ply: {message}"))
+}
+
+fn header(data: &[u8]) -> Result<(PlyFormat, Vec<Element>, &[u8]), Error> {
+    let end = data
+        .windows(10)
+        .position(|w| w == b"end_header")
+        .ok_or_else(|| invalid("missing end_header"))?;
+    let body = data[end..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(&[][..], |newline| &data[end + newline + 1..]);
+    let text = std::str::from_utf8(&data[..end]).map_err(|_| invalid("header is not utf-8"))?;
+
+    let mut lines = text.lines();
+    if lines.next().map(str::trim) != Some("ply") {
+        return Err(invalid("missing ply magic"));
+    }
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    for line in lines {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words[..] {
+            ["format", name, _] => {
+                format = Some(match name {
+                    "ascii" => PlyFormat::Ascii,
+                    "binary_little_endian" => PlyFormat::BinaryLittleEndian,
+                    "binary_big_endian" => PlyFormat::BinaryBigEndian,
+                    _ => return Err(invalid(&format!("unknown format {name}"))),
+                })
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| invalid(&format!("invalid count {count}")))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, item, name] => elements
+                .last_mut()
+                .ok_or_else(|| invalid("property outside an element"))?
+                .properties
+                .push((
+                    name.to_string(),
+                    PropertyType::List {
+                        count: Scalar::parse(count)?,
+                        item: Scalar::parse(item)?,
+                    },
+                )),
+            ["property", scalar, name] => elements
+                .last_mut()
+                .ok_or_else(|| invalid("property outside an element"))?
+                .properties
+                .push((
+                    name.to_string(),
+                    PropertyType::Scalar(Scalar::parse(scalar)?),
+                )),
+            _ => {}
+        }
+    }
+    let format = format.ok_or_else(|| invalid("missing format"))?;
+    Ok((format, elements, body))
+}
+
+enum Body<'a> {
+    Ascii(std::str::SplitAsciiWhitespace<'a>),
+    Binary { data: &'a [u8], big_endian: bool },
+}
+
+impl Body<'_> {
+    fn scalar(&mut self, scalar: Scalar) -> Result<f64, Error> {
+        let truncated = || invalid("truncated data");
+        match self {
+            Body::Ascii(words) => {
+                let word = words.next().ok_or_else(truncated)?;
+                word.parse()
+                    .map_err(|_| invalid(&format!("invalid number {word}")))
+            }
+            Body::Binary { data, big_endian } => {
+                macro_rules! read {
+                    ($t:ty) => {{
+                        const SIZE: usize = std::mem::size_of::<$t>();
+                        let (bytes, rest) =
+                            data.split_first_chunk::<SIZE>().ok_or_else(truncated)?;
+                        *data = rest;
+                        (if *big_endian {
+                            <$t>::from_be_bytes(*bytes)
+                        } else {
+                            <$t>::from_le_bytes(*bytes)
+                        }) as f64
+                    }};
+                }
+                Ok(match scalar {
+                    Scalar::I8 => read!(i8),
+                    Scalar::U8 => read!(u8),
+                    Scalar::I16 => read!(i16),
+                    Scalar::U16 => read!(u16),
+                    Scalar::I32 => read!(i32),
+                    Scalar::U32 => read!(u32),
+                    Scalar::F32 => read!(f32),
+                    Scalar::F64 => read!(f64),
+                })
+            }
+        }
+    }
+
+    /// Reads a property, appending list items to `items`.
+    fn property(&mut self, property: &PropertyType, items: &mut Vec<f64>) -> Result<f64, Error> {
+        match *property {
+            PropertyType::Scalar(scalar) => self.scalar(scalar),
+            PropertyType::List { count, item } => {
+                let count = self.scalar(count)? as usize;
+                for _ in 0..count {
+                    items.push(self.scalar(item)?);
+                }
+                Ok(count as f64)
+            }
+        }
+    }
+}
+
+impl Mesh {
+    pub fn load_ply(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::read_ply(File::open(path)?)
+    }
+
+    pub fn load_ply_with(path: impl AsRef<Path>, options: &ImportOptions) -> Result<Self, Error> {
+        Self::read_ply_with(File::open(path)?, options)
+    }
+
+    /// Reads like `read_ply` and converts the mesh to `options.target` and
+    /// `options.target_units`.
+    pub fn read_ply_with(reader: impl Read, options: &ImportOptions) -> Result<Self, Error> {
+        let mut mesh = Self::read_ply(reader)?;
+        options.convert_mesh(&mut mesh);
+        Ok(mesh)
+    }
+
+    /// Reads an ASCII or binary PLY file's `vertex` and `face` elements.
+    /// Faces with more than three vertices are triangulated as fans, and
+    /// normals are computed if the file has none. Texture coordinates are
+    /// flipped to a top-left origin.
+    pub fn read_ply(mut reader: impl Read) -> Result<Self, Error> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let (format, elements, body) = header(&data)?;
+        let mut body = match format {
+            PlyFormat::Ascii => Body::Ascii(
+                std::str::from_utf8(body)
+                    .map_err(|_| invalid("ascii data is not utf-8"))?
+                    .split_ascii_whitespace(),
+            ),
+            _ => Body::Binary {
+                data: body,
+                big_endian: format == PlyFormat::BinaryBigEndian,
+            },
+        };
+
+        let mut mesh = Mesh::default();
+        let mut has_normals = false;
+        let (mut values, mut items) = (Vec::new(), Vec::new());
+        for element in &elements {
+            let position = ["x", "y", "z"].map(|name| element.position(&[name]));
+            let normal = ["nx", "ny", "nz"].map(|name| element.position(&[name]));
+            let uv = [
+                element.position(&["u", "s", "texture_u", "texture_s"]),
+                element.position(&["v", "t", "texture_v", "texture_t"]),
+            ];
+            let color = [
+                ["red", "r", "diffuse_red"],
+                ["green", "g", "diffuse_green"],
+                ["blue", "b", "diffuse_blue"],
+                ["alpha", "a", "diffuse_alpha"],
+            ]
+            .map(|names| element.position(&names));
+            let color_scale = color.map(|index| match index.map(|i| &element.properties[i].1) {
+                Some(PropertyType::Scalar(scalar)) => scalar.color_scale(),
+                _ => 1.0,
+            });
+            let indices = element.position(&["vertex_indices", "vertex_index"]);
+            has_normals |= element.name == "vertex" && normal.iter().all(Option::is_some);
+
+            for _ in 0..element.count {
+                values.clear();
+                items.clear();
+                let mut face = 0..0;
+                for (i, (_, property)) in element.properties.iter().enumerate() {
+                    let start = items.len();
+                    values.push(body.property(property, &mut items)?);
+                    if Some(i) == indices {
+                        face = start..items.len();
+                    }
+                }
+                let get = |index: Option<usize>| index.map_or(0.0, |i| values[i] as f32);
+                match element.name.as_str() {
+                    "vertex" => {
+                        mesh.vertices.push(Vertex {
+                            position: position.map(get),
+                            normal: normal.map(get),
+                            uv: [get(uv[0]), 1.0 - get(uv[1])],
+                        });
+                        if color[..3].iter().all(Option::is_some) {
+                            let mut rgba = [1.0; 4];
+                            for ((c, index), scale) in rgba.iter_mut().zip(color).zip(color_scale) {
+                                if let Some(i) = index {
+                                    *c = (values[i] / scale) as f32;
+                                }
+                            }
+                            mesh.colors.push(rgba);
+                        }
+                    }
+                    "face" => {
+                        let face = &items[face];
+                        // Negative indices would saturate to 0 as u32.
+                        if face.iter().any(|&i| i < 0.0) {
+                            return Err(invalid("face vertex index out of range"));
+                        }
+                        for i in 1..face.len().saturating_sub(1) {
+                            mesh.indices
+                                .extend([face[0], face[i], face[i + 1]].map(|i| i as u32));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if mesh
+            .indices
+            .iter()
+            .any(|&i| i as usize >= mesh.vertices.len())
+        {
+            return Err(invalid("face vertex index out of range"));
+        }
+        if !has_normals {
+            mesh.compute_normals();
+        }
+        Ok(mesh)
+    }
+
+    pub fn save_ply(&self, path: impl AsRef<Path>, format: PlyFormat) -> Result<(), Error> {
+        self.write_ply(BufWriter::new(File::create(path)?), format)
+    }
+
+    /// Writes positions, normals, texture coordinates, 8-bit colors if the
+    /// mesh has them, and triangles.
+    pub fn write_ply(&self, mut writer: impl Write, format: PlyFormat) -> Result<(), Error> {
+        let has_colors = !self.colors.is_empty();
+        let format_name = match format {
+            PlyFormat::Ascii => "ascii",
+            PlyFormat::BinaryLittleEndian => "binary_little_endian",
+            PlyFormat::BinaryBigEndian => "binary_big_endian",
+        };
+        writeln!(writer, "ply\nformat {format_name} 1.0\ncomment wgpu-tools")?;
+        writeln!(writer, "element vertex {}", self.vertices.len())?;
+        for name in ["x", "y", "z", "nx", "ny", "nz", "s", "t"] {
+            writeln!(writer, "property float {name}")?;
+        }
+        if has_colors {
+            for name in ["red", "green", "blue", "alpha"] {
+                writeln!(writer, "property uchar {name}")?;
+            }
+        }
+        writeln!(writer, "element face {}", self.triangle_count())?;
+        writeln!(
+            writer,
+            "property list uchar uint vertex_indices\nend_header"
+        )?;
+
+        for (i, vertex) in self.vertices.iter().enumerate() {
+            let [u, v] = vertex.uv;
+            let floats = [vertex.position, vertex.normal, [u, 1.0 - v, 0.0]];
+            let floats = floats.iter().flatten().take(8);
+            let color = self
+                .colors
+                .get(i)
+                .map(|c| c.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8));
+            match format {
+                PlyFormat::Ascii => {
+                    let line: Vec<String> = floats.map(f32::to_string).collect();
+                    write!(writer, "{}", line.join(" "))?;
+                    if has_colors {
+                        let [r, g, b, a] = color.unwrap_or([255; 4]);
+                        write!(writer, " {r} {g} {b} {a}")?;
+                    }
+                    writeln!(writer)?;
+                }
+                _ => {
+                    let big_endian = format == PlyFormat::BinaryBigEndian;
+                    for value in floats {
+                        writer.write_all(&if big_endian {
+                            value.to_be_bytes()
+                        } else {
+                            value.to_le_bytes()
+                        })?;
+                    }
+                    if has_colors {
+                        writer.write_all(&color.unwrap_or([255; 4]))?;
+                    }
+                }
+            }
+        }
+        for triangle in self.triangles() {
+            match format {
+                PlyFormat::Ascii => {
+                    writeln!(writer, "3 {} {} {}", triangle[0], triangle[1], triangle[2])?
+                }
+                _ => {
+                    writer.write_all(&[3])?;
+                    for index in triangle {
+                        writer.write_all(&if format == PlyFormat::BinaryBigEndian {
+                            index.to_be_bytes()
+                        } else {
+                            index.to_le_bytes()
+                        })?;
+                    }
+                }
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn colored_quad() -> Mesh {
+        let vertex = |x: f32, y: f32| Vertex {
+            position: [x, y, -1.5],
+            normal: [0.0, 0.0, 1.0],
+            uv: [x, y * 0.5],
+        };
+        let mut mesh = Mesh::new(
+            vec![
+                vertex(0.0, 0.0),
+                vertex(1.0, 0.0),
+                vertex(1.0, 2.0),
+                vertex(0.0, 2.0),
+            ],
+            vec![0, 1, 2, 0, 2, 3],
+        );
+        // Multiples of 1/255 survive the 8-bit colors.
+        mesh.colors = vec![
+            [1.0, 0.0, 0.0, 1.0],
+            [0.0, 1.0, 0.0, 0.2],
+            [0.0, 0.0, 1.0, 0.6],
+            [0.2, 0.4, 0.6, 0.8],
+        ];
+        mesh
+    }
+
+    fn round_trip(format: PlyFormat) {
+        let mesh = colored_quad();
+        let mut data = Vec::new();
+        mesh.write_ply(&mut data, format).unwrap();
+        let read = Mesh::read_ply(data.as_slice()).unwrap();
+        assert_eq!(read.vertices, mesh.vertices);
+        assert_eq!(read.indices, mesh.indices);
+        assert_eq!(read.colors, mesh.colors);
+    }
+
+    #[test]
+    fn ascii_round_trip() {
+        round_trip(PlyFormat::Ascii);
+    }
+
+    #[test]
+    fn binary_little_endian_round_trip() {
+        round_trip(PlyFormat::BinaryLittleEndian);
+    }
+
+    #[test]
+    fn binary_big_endian_round_trip() {
+        round_trip(PlyFormat::BinaryBigEndian);
+    }
+
+    const QUAD_HEADER: &str = "ply\nformat ascii 1.0\n\
+                               element vertex 4\n\
+                               property float x\nproperty float y\nproperty float z\n\
+                               element face 1\n\
+                               property list uchar int vertex_indices\nend_header\n\
+                               0 0 0\n1 0 0\n1 1 0\n0 1 0\n";
+
+    #[test]
+    fn polygons_are_triangulated_with_computed_normals() {
+        let mesh = Mesh::read_ply(format!("{QUAD_HEADER}4 0 1 2 3\n").as_bytes()).unwrap();
+        assert_eq!(mesh.indices, [0, 1, 2, 0, 2, 3]);
+        assert!(mesh.colors.is_empty());
+        assert!(mesh.vertices.iter().all(|v| v.normal == [0.0, 0.0, 1.0]));
+    }
+
+    fn error(data: &[u8]) -> String {
+        match Mesh::read_ply(data) {
+            Err(Error::InvalidMesh(message)) => message,
+            other => panic!("expected an invalid mesh, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn out_of_range_indices_are_rejected() {
+        let message = error(format!("{QUAD_HEADER}3 0 1 4\n").as_bytes());
+        assert!(message.contains("out of range"), "{message}");
+        let message = error(format!("{QUAD_HEADER}3 0 1 -1\n").as_bytes());
+        assert!(message.contains("out of range"), "{message}");
+    }
+
+    #[test]
+    fn truncated_data_is_rejected() {
+        assert!(error(format!("{QUAD_HEADER}3 0 1").as_bytes()).contains("truncated"));
+
+        let mut data = Vec::new();
+        colored_quad()
+            .write_ply(&mut data, PlyFormat::BinaryLittleEndian)
+            .unwrap();
+        assert!(error(&data[..data.len() - 2]).contains("truncated"));
+    }
+
+    #[test]
+    fn missing_end_header_is_rejected() {
+        let data = QUAD_HEADER.replace("end_header", "end");
+        assert!(error(data.as_bytes()).contains("end_header"));
+    }
+}
@@ -0,0 +1,219 @@
+use super::context::Context;
+use super::error::Error;
+use super::runtime::block_in_place;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Poll, Waker};
+
+/// How the device gets polled while waiting on the GPU, which fires buffer
+/// mapping and `on_submitted_work_done` callbacks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PollingStrategy {
+    /// Waits block in `device.poll` until the GPU is done, for headless
+    /// tools and tests. Futures such as `Context::read_buffer_async` block
+    /// the first time they are polled, through `block_in_place` on
+    /// multi-threaded tokio runtimes with the `tokio` feature.
+    #[default]
+    Blocking,
+    /// Futures poll the device without blocking and yield back to the
+    /// executor until the GPU is done, for async applications. Blocking
+    /// waits still block.
+    Async,
+    /// A `BackgroundPoller` thread polls the device, so callbacks fire
+    /// promptly while a GUI thread renders, and futures wait on their
+    /// wakers without polling.
+    Background,
+}
+
+impl Context {
+    pub fn polling_strategy(&self) -> PollingStrategy {
+        *self
+            .polling_strategy
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// `Background` is set by `BackgroundPoller::start`, which should be
+    /// used instead.
+    pub fn set_polling_strategy(&self, strategy: PollingStrategy) {
+        *self
+            .polling_strategy
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = strategy;
+    }
+
+    /// Copies `buffer` into a staging buffer and resolves to its contents
+    /// once mapped, polling the device as the `PollingStrategy` says.
+    pub async fn read_buffer_async(&self, buffer: &wgpu::Buffer) -> Result<Vec<u8>, Error> {
+        let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Readback Buffer"),
+            size: buffer.size(),
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut command_encoder =
+            self.device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Readback Encoder"),
+                });
+        command_encoder.copy_buffer_to_buffer(buffer, 0, &staging, 0, buffer.size());
+        self.submit(command_encoder);
+
+        let slice = staging.slice(..);
+        self.map_async(slice, wgpu::MapMode::Read).await?;
+        let data = slice.get_mapped_range().to_vec();
+        staging.unmap();
+        Ok(data)
+    }
+
+    /// Maps `slice` with `mode`, resolving once the mapping is done.
+    pub fn map_async<'a>(
+        &'a self,
+        slice: wgpu::BufferSlice<'a>,
+        mode: wgpu::MapMode,
+    ) -> impl Future<Output = Result<(), Error>> + 'a {
+        let state = Arc::new(Mutex::new(MapState::default()));
+        let callback_state = state.clone();
+        slice.map_async(mode, move |result| {
+            let mut state = callback_state
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            state.result = Some(result);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        });
+        MapFuture {
+            context: self,
+            state,
+        }
+    }
+}
+
+#[derive(Default)]
+struct MapState {
+    result: Option<Result<(), wgpu::BufferAsyncError>>,
+    waker: Option<Waker>,
+}
+
+struct MapFuture<'a> {
+    context: &'a Context,
+    state: Arc<Mutex<MapState>>,
+}
+
+impl MapFuture<'_> {
+    fn take_result(&self, waker: &Waker) -> Option<Result<(), wgpu::BufferAsyncError>> {
+        let mut state = self
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let result = state.result.take();
+        if result.is_none() {
+            state.waker = Some(waker.clone());
+        }
+        result
+    }
+}
+
+impl Future for MapFuture<'_> {
+    type Output = Result<(), Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
+        if let Some(result) = self.take_result(cx.waker()) {
+            return Poll::Ready(result.map_err(Error::BufferMappingFailed));
+        }
+        match self.context.polling_strategy() {
+            PollingStrategy::Blocking => {
+                block_in_place(|| self.context.device.poll(wgpu::Maintain::Wait));
+            }
+            PollingStrategy::Async => {
+                self.context.device.poll(wgpu::Maintain::Poll);
+            }
+            PollingStrategy::Background => return Poll::Pending,
+        }
+        match self.take_result(cx.waker()) {
+            Some(result) => Poll::Ready(result.map_err(Error::BufferMappingFailed)),
+            None => {
+                // Nothing else will wake the task, so yield and poll again.
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// A thread polling the device of a context every `interval`, which sets
+/// the context's `PollingStrategy` to `Background` until it is dropped.
+/// It holds the context weakly and exits once the context is gone. Dropping
+/// it waits for the submitted work, so pending futures are woken.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct BackgroundPoller {
+    context: std::sync::Weak<Context>,
+    previous: PollingStrategy,
+    stop: Arc<std::sync::atomic::AtomicBool>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl BackgroundPoller {
+    pub fn start(context: &Arc<Context>, interval: std::time::Duration) -> Self {
+        use std::sync::atomic::Ordering;
+
+        let previous = context.polling_strategy();
+        context.set_polling_strategy(PollingStrategy::Background);
+        let stop = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let weak = Arc::downgrade(context);
+        let thread = std::thread::Builder::new()
+            .name("wgpu-tools poller".into())
+            .spawn({
+                let stop = stop.clone();
+                let weak = weak.clone();
+                move || {
+                    while !stop.load(Ordering::Acquire) {
+                        let Some(context) = weak.upgrade() else {
+                            break;
+                        };
+                        context.device.poll(wgpu::Maintain::Poll);
+                        drop(context);
+                        std::thread::park_timeout(interval);
+                    }
+                }
+            })
+            .ok();
+        if thread.is_none() {
+            context.set_polling_strategy(previous);
+        }
+
+        Self {
+            context: weak,
+            previous,
+            stop,
+            thread,
+        }
+    }
+
+    /// Whether the polling thread is running.
+    pub fn is_running(&self) -> bool {
+        self.thread
+            .as_ref()
+            .is_some_and(|thread| !thread.is_finished())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Drop for BackgroundPoller {
+    fn drop(&mut self) {
+        self.stop.store(true, std::sync::atomic::Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+        if let Some(context) = self.context.upgrade() {
+            context.set_polling_strategy(self.previous);
+            // Futures that went pending in `Background` wait on their wakers,
+            // so finish the outstanding work to fire their callbacks.
+            context.device.poll(wgpu::Maintain::Wait);
+        }
+    }
+}
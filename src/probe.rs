@@ -0,0 +1,184 @@
+use super::context::Context;
+use super::error::Error;
+use super::math::{dot, multiply, Aabb};
+use super::mipmap::full_mip_level_count;
+use super::texture::Texture;
+
+/// WGSL for sampling an `EnvironmentProbe` bound as a uniform of its
+/// `params`: `probe_direction` box-projects a reflection onto the probe's
+/// influence box so that nearby reflections line up, and `probe_level`
+/// picks the mip level for a roughness.
+pub const ENVIRONMENT_PROBE_WGSL: &str = r#"
+struct EnvironmentProbe {
+    position: vec3<f32>,
+    mip_count: f32,
+    box_min: vec3<f32>,
+    box_max: vec3<f32>,
+}
+
+fn probe_direction(probe: EnvironmentProbe, position: vec3<f32>, direction: vec3<f32>) -> vec3<f32> {
+    // Axis-parallel directions would divide by zero, which WGSL leaves
+    // undefined.
+    let safe = select(direction, vec3<f32>(1e-6), abs(direction) < vec3<f32>(1e-6));
+    let first = (probe.box_max - position) / safe;
+    let second = (probe.box_min - position) / safe;
+    let furthest = max(first, second);
+    let distance = min(min(furthest.x, furthest.y), furthest.z);
+    return position + direction * distance - probe.position;
+}
+
+fn probe_level(probe: EnvironmentProbe, roughness: f32) -> f32 {
+    return clamp(roughness, 0.0, 1.0) * (probe.mip_count - 1.0);
+}
+"#;
+
+/// Forward, right and up of the cube faces in layer order +X, -X, +Y, -Y,
+/// +Z, -Z, as cube sampling lays them out.
+const FACES: [[[f32; 3]; 3]; 6] = [
+    [[1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]],
+    [[-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]],
+    [[0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]],
+    [[0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]],
+    [[0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+    [[0.0, 0.0, -1.0], [-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+];
+
+/// One face of a probe being baked, to render the scene into.
+pub struct ProbeFace<'a> {
+    pub index: u32,
+    /// Column-major view and view-projection matrices.
+    pub view: [[f32; 4]; 4],
+    pub view_projection: [[f32; 4]; 4],
+    pub color: &'a wgpu::TextureView,
+    pub depth: &'a wgpu::TextureView,
+    /// Depth to clear to, following the context's reverse-Z setting.
+    pub depth_clear_value: f32,
+}
+
+/// A reflection probe: the scene as seen from `position`, baked into a cube
+/// texture whose mip chain is prefiltered for rough reflections.
+pub struct EnvironmentProbe {
+    pub position: [f32; 3],
+    /// The region whose reflections the probe provides, and the box they
+    /// are projected onto.
+    pub influence: Aabb,
+    pub near: f32,
+    pub far: f32,
+    texture: Texture,
+    depth: Texture,
+}
+
+impl EnvironmentProbe {
+    /// The format must be renderable and filterable, such as `Rgba16Float`.
+    pub fn new(
+        context: &Context,
+        position: [f32; 3],
+        influence: Aabb,
+        size: &u32,
+        texture_format: &wgpu::TextureFormat,
+        label: Option<&str>,
+    ) -> Result<Self, Error> {
+        context.check_mipmap_format(*texture_format)?;
+        let mip_level_count = full_mip_level_count(*size, *size);
+        let texture = context.cubemap_target(size, texture_format, mip_level_count, label)?;
+        let depth = context.depth_texture(size, size, "Environment Probe Depth")?;
+        Ok(Self {
+            position,
+            influence,
+            near: 0.1,
+            far: 1000.0,
+            texture,
+            depth,
+        })
+    }
+
+    /// The cube texture with a cube view and a trilinear sampler.
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    /// Column-major view matrix of `face`. Cube faces mirror the scene, so
+    /// triangles wind the other way: render with `FrontFace::Cw` or without
+    /// culling.
+    pub fn face_view(&self, face: u32) -> [[f32; 4]; 4] {
+        let [forward, right, up] = FACES[face as usize % 6];
+        let p = self.position;
+        [
+            [right[0], up[0], -forward[0], 0.0],
+            [right[1], up[1], -forward[1], 0.0],
+            [right[2], up[2], -forward[2], 0.0],
+            [-dot(right, p), -dot(up, p), dot(forward, p), 1.0],
+        ]
+    }
+
+    /// The 90° square projection of every face, with depth in [0, 1],
+    /// reversed if `reverse_z` is set.
+    pub fn projection(&self, reverse_z: bool) -> [[f32; 4]; 4] {
+        let (near, far) = (self.near, self.far);
+        let (z_scale, z_offset) = match reverse_z {
+            false => (far / (near - far), near * far / (near - far)),
+            true => (near / (far - near), near * far / (far - near)),
+        };
+        [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, z_scale, -1.0],
+            [0.0, 0.0, z_offset, 0.0],
+        ]
+    }
+
+    /// Calls `render` to draw each face into the cube texture, then fills
+    /// the mip chain with `Texture::generate_mipmaps`, a box-filtered
+    /// stand-in for GGX prefiltering.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(position = ?self.position), err)
+    )]
+    pub fn bake(
+        &self,
+        context: &Context,
+        mut render: impl FnMut(&mut wgpu::CommandEncoder, &ProbeFace),
+    ) -> Result<(), Error> {
+        let projection = self.projection(context.reverse_z());
+        let mut command_encoder =
+            context
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Environment Probe Encoder"),
+                });
+        for index in 0..6 {
+            let view = self.face_view(index);
+            let color = self.texture.face_view(index);
+            let face = ProbeFace {
+                index,
+                view,
+                view_projection: multiply(&projection, &view),
+                color: &color,
+                depth: &self.depth.view,
+                depth_clear_value: context.depth_clear_value(),
+            };
+            render(&mut command_encoder, &face);
+        }
+        context.submit(command_encoder);
+        self.texture.generate_mipmaps(context)
+    }
+
+    /// Uniform data for `ENVIRONMENT_PROBE_WGSL`'s `EnvironmentProbe`.
+    pub fn params(&self) -> [f32; 12] {
+        let [min, max] = [self.influence.min, self.influence.max];
+        [
+            self.position[0],
+            self.position[1],
+            self.position[2],
+            self.texture.mip_level_count() as f32,
+            min[0],
+            min[1],
+            min[2],
+            0.0,
+            max[0],
+            max[1],
+            max[2],
+            0.0,
+        ]
+    }
+}
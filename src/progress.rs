@@ -0,0 +1,104 @@
+use super::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Aborts long-running operations from another thread. Clones share the
+/// flag, and an operation given one stops with `Error::Cancelled` at its
+/// next check after `cancel`.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn check(&self) -> Result<(), Error> {
+        match self.is_cancelled() {
+            true => Err(Error::Cancelled),
+            false => Ok(()),
+        }
+    }
+}
+
+/// How far a stage of an operation got, as passed to `Progress::on_update`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProgressUpdate {
+    /// What the operation is doing, such as "bvh" or "ambient occlusion".
+    pub stage: &'static str,
+    pub completed: u64,
+    pub total: u64,
+}
+
+impl ProgressUpdate {
+    /// Completed fraction of the stage in [0, 1].
+    pub fn fraction(&self) -> f32 {
+        match self.total {
+            0 => 1.0,
+            total => self.completed as f32 / total as f32,
+        }
+    }
+}
+
+/// Progress reporting and cancellation of one long-running operation, for
+/// the progress bars and abort buttons of tools. The `*_with_progress`
+/// variants of bakes and uploads report their stages to it and check its
+/// token between steps.
+#[derive(Default)]
+pub struct Progress<'a> {
+    cancellation: Option<CancellationToken>,
+    callback: Option<Box<dyn FnMut(ProgressUpdate) + Send + 'a>>,
+}
+
+impl<'a> Progress<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Calls `callback` on every step, from the thread running the
+    /// operation.
+    pub fn on_update(mut self, callback: impl FnMut(ProgressUpdate) + Send + 'a) -> Self {
+        self.callback = Some(Box::new(callback));
+        self
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// Reports a step of `stage`, then fails if the operation was
+    /// cancelled.
+    pub(crate) fn report(
+        &mut self,
+        stage: &'static str,
+        completed: u64,
+        total: u64,
+    ) -> Result<(), Error> {
+        if let Some(callback) = &mut self.callback {
+            callback(ProgressUpdate {
+                stage,
+                completed,
+                total,
+            });
+        }
+        match &self.cancellation {
+            Some(token) => token.check(),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,288 @@
+// The pymethods expansion converts every `PyResult` error into itself.
+#![allow(clippy::useless_conversion)]
+
+use super::context::Context;
+use super::diagnostics::{describe_buffer, PassRecord};
+use super::error::Error;
+use super::format::ColorSpace;
+use super::subgroup::{ReduceOp, WgslScalar, WorkgroupPrimitives};
+use super::texture::Texture;
+use numpy::{
+    IntoPyArray, PyArray1, PyArray3, PyArrayMethods, PyReadonlyArray1, PyReadonlyArray3,
+    PyUntypedArrayMethods,
+};
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+
+const WORKGROUP_SIZE: u32 = 256;
+
+fn runtime_error(error: Error) -> PyErr {
+    PyRuntimeError::new_err(error.to_string())
+}
+
+#[pyclass(name = "Texture", module = "wgpu_tools")]
+struct PyTexture(Texture);
+
+#[pymethods]
+impl PyTexture {
+    #[getter]
+    fn width(&self) -> u32 {
+        self.0.width()
+    }
+
+    #[getter]
+    fn height(&self) -> u32 {
+        self.0.height()
+    }
+
+    #[getter]
+    fn format(&self) -> String {
+        format!("{:?}", self.0.format())
+    }
+}
+
+#[pyclass(name = "Context", module = "wgpu_tools")]
+struct PyContext(Context);
+
+#[pymethods]
+impl PyContext {
+    #[new]
+    fn new() -> PyResult<Self> {
+        pollster::block_on(Context::default())
+            .map(Self)
+            .map_err(runtime_error)
+    }
+
+    #[getter]
+    fn adapter_name(&self) -> String {
+        self.0.adapter.get_info().name
+    }
+
+    #[cfg(feature = "image")]
+    #[pyo3(signature = (path, srgb = false))]
+    fn load_texture(&self, path: &str, srgb: bool) -> PyResult<PyTexture> {
+        let image = image::open(path).map_err(|error| runtime_error(error.into()))?;
+        self.0
+            .texture_from_image(&image, &rgba8(srgb), Some(path))
+            .map(PyTexture)
+            .map_err(runtime_error)
+    }
+
+    /// Creates an RGBA8 texture from a `(height, width, 4)` uint8 array.
+    #[pyo3(signature = (array, srgb = false))]
+    fn texture_from_array(&self, array: PyReadonlyArray3<u8>, srgb: bool) -> PyResult<PyTexture> {
+        let &[height, width, channels] = array.shape() else {
+            unreachable!()
+        };
+        if channels != 4 {
+            return Err(PyValueError::new_err(
+                "expected an array of shape (height, width, 4)",
+            ));
+        }
+        let data: Vec<u8> = array.as_array().iter().copied().collect();
+        self.0
+            .texture_with_data(&data, &(width as u32), &(height as u32), &rgba8(srgb), None)
+            .map(PyTexture)
+            .map_err(runtime_error)
+    }
+
+    /// Reads a texture back as a `(height, width, bytes per pixel)` uint8
+    /// array.
+    fn read_texture<'py>(
+        &self,
+        py: Python<'py>,
+        texture: &PyTexture,
+    ) -> PyResult<Bound<'py, PyArray3<u8>>> {
+        let texture = &texture.0;
+        let bytes_per_pixel = texture.format().block_copy_size(None).unwrap_or(0) as usize;
+        let data = self.0.read_texture(texture).map_err(runtime_error)?;
+        data.into_pyarray_bound(py).reshape([
+            texture.height() as usize,
+            texture.width() as usize,
+            bytes_per_pixel,
+        ])
+    }
+
+    /// Applies a WGSL expression of the f32 `x` to every element.
+    fn map<'py>(
+        &self,
+        py: Python<'py>,
+        array: PyReadonlyArray1<f32>,
+        expression: &str,
+    ) -> PyResult<Bound<'py, PyArray1<f32>>> {
+        let data: Vec<f32> = array.as_array().iter().copied().collect();
+        if data.is_empty() {
+            return Ok(data.into_pyarray_bound(py));
+        }
+        let buffer = self.storage_buffer(&data)?;
+        let source = format!(
+            "@group(0) @binding(0) var<storage, read_write> data: array<f32>;
+
+@compute @workgroup_size({WORKGROUP_SIZE})
+fn main(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+) {{
+    let i = id.x + id.y * groups.x * {WORKGROUP_SIZE}u;
+    if (i >= arrayLength(&data)) {{
+        return;
+    }}
+    let x = data[i];
+    data[i] = f32({expression});
+}}
+"
+        );
+        self.dispatch(&source, &[&buffer], data.len() as u64)?;
+
+        let bytes = self.0.read_buffer(&buffer).map_err(runtime_error)?;
+        let result: Vec<f32> = bytemuck::pod_collect_to_vec(&bytes);
+        Ok(result.into_pyarray_bound(py))
+    }
+
+    /// Reduces the array with `"add"`, `"min"` or `"max"`.
+    #[pyo3(signature = (array, op = "add"))]
+    fn reduce(&self, array: PyReadonlyArray1<f32>, op: &str) -> PyResult<f32> {
+        let (op, identity) = match op {
+            "add" => (ReduceOp::Add, "0.0"),
+            "min" => (ReduceOp::Min, "3.40282347e38"),
+            "max" => (ReduceOp::Max, "-3.40282347e38"),
+            _ => return Err(PyValueError::new_err(format!("unknown reduction {op:?}"))),
+        };
+        let data: Vec<f32> = array.as_array().iter().copied().collect();
+        if data.is_empty() {
+            return Err(PyValueError::new_err("reduction of an empty array"));
+        }
+
+        let primitives = WorkgroupPrimitives::new(&self.0.subgroup_support(), WORKGROUP_SIZE)
+            .reduce(op, WgslScalar::F32);
+        let source = format!(
+            "{primitives}
+@group(0) @binding(0) var<storage, read_write> input: array<f32>;
+@group(0) @binding(1) var<storage, read_write> output: array<f32>;
+
+@compute @workgroup_size({WORKGROUP_SIZE})
+fn main(
+    @builtin(workgroup_id) workgroup: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+    invocation: WorkgroupInvocation,
+) {{
+    let group = workgroup.x + workgroup.y * groups.x;
+    let i = group * {WORKGROUP_SIZE}u + invocation.local_index;
+    var value = {identity};
+    if (i < arrayLength(&input)) {{
+        value = input[i];
+    }}
+    let total = workgroup_reduce_{name}_f32(value, invocation);
+    if (invocation.local_index == 0u && group < arrayLength(&output)) {{
+        output[group] = total;
+    }}
+}}
+",
+            primitives = primitives.build(),
+            name = match op {
+                ReduceOp::Add => "add",
+                ReduceOp::Min => "min",
+                ReduceOp::Max => "max",
+            },
+        );
+
+        let mut input = self.storage_buffer(&data)?;
+        let mut len = data.len() as u64;
+        while len > 1 {
+            let groups = len.div_ceil(WORKGROUP_SIZE as u64);
+            let output = self.storage_buffer(&vec![0.0; groups as usize])?;
+            self.dispatch(&source, &[&input, &output], len)?;
+            (input, len) = (output, groups);
+        }
+
+        let bytes = self.0.read_buffer(&input).map_err(runtime_error)?;
+        Ok(bytemuck::pod_read_unaligned(&bytes[..4]))
+    }
+}
+
+impl PyContext {
+    fn storage_buffer(&self, data: &[f32]) -> PyResult<wgpu::Buffer> {
+        let size = std::mem::size_of_val(data) as u64;
+        self.0
+            .check_binding_size(size, true)
+            .map_err(runtime_error)?;
+        self.0
+            .buffer_with_data(
+                bytemuck::cast_slice(data),
+                wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+                None,
+            )
+            .map_err(runtime_error)
+    }
+
+    /// Runs `main` of `source` over `len` invocations with `buffers` bound in
+    /// order to group 0.
+    fn dispatch(&self, source: &str, buffers: &[&wgpu::Buffer], len: u64) -> PyResult<()> {
+        let device = &self.0.device;
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Python Compute Shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Python Compute Pipeline"),
+            layout: None,
+            module: &module,
+            entry_point: "main",
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        });
+        if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+            return Err(PyValueError::new_err(error.to_string()));
+        }
+
+        let entries: Vec<_> = buffers
+            .iter()
+            .enumerate()
+            .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                binding: binding as u32,
+                resource: buffer.as_entire_binding(),
+            })
+            .collect();
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Python Compute Bind Group"),
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &entries,
+        });
+
+        // The buffers fit a single binding, so they also fit one dispatch.
+        let workgroups = self.0.split_dispatch(len.div_ceil(WORKGROUP_SIZE as u64))[0].workgroups;
+        self.0.diagnostics.record_pass(|| {
+            let resources = buffers
+                .iter()
+                .enumerate()
+                .map(|(binding, buffer)| describe_buffer(&format!("binding {binding}"), buffer))
+                .collect();
+            PassRecord::new("Python Compute Pass", "Python Compute Pipeline", resources)
+        });
+        self.0.schedule(|encoder| {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Python Compute Pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(workgroups.0, workgroups.1, workgroups.2);
+        });
+        Ok(())
+    }
+}
+
+fn rgba8(srgb: bool) -> wgpu::TextureFormat {
+    match srgb {
+        true => ColorSpace::Srgb,
+        false => ColorSpace::Linear,
+    }
+    .rgba8_format()
+}
+
+#[pymodule]
+fn wgpu_tools(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyContext>()?;
+    module.add_class::<PyTexture>()?;
+    Ok(())
+}
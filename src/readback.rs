@@ -0,0 +1,170 @@
+use super::context::Context;
+use super::error::Error;
+use super::format::ImageLayout;
+use super::texture::{check_subresource, Texture};
+
+impl Context {
+    /// Reads back mip level 0 of the first layer with rows tightly packed,
+    /// laid out as `ImageLayout::new` describes.
+    /// The texture needs `COPY_SRC` usage.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(width = texture.width(), height = texture.height(), format = ?texture.format()),
+            err
+        )
+    )]
+    pub fn read_texture(&self, texture: &Texture) -> Result<Vec<u8>, Error> {
+        self.read_texture_level(texture, 0)
+    }
+
+    /// Like `read_texture`, for mip level `mip_level`. Levels of
+    /// block-compressed formats smaller than a block read back a whole
+    /// block.
+    pub fn read_texture_level(&self, texture: &Texture, mip_level: u32) -> Result<Vec<u8>, Error> {
+        let (staging, layout) = self.copy_to_readback_buffer(texture, mip_level)?;
+        let padded = self.map_read(&staging)?;
+        Ok(unpad_rows(&padded, &layout))
+    }
+
+    /// `read_texture` that waits for the mapping as a future, polling the
+    /// device as the `PollingStrategy` says.
+    pub async fn read_texture_async(&self, texture: &Texture) -> Result<Vec<u8>, Error> {
+        let (staging, layout) = self.copy_to_readback_buffer(texture, 0)?;
+        let slice = staging.slice(..);
+        self.map_async(slice, wgpu::MapMode::Read).await?;
+        let data = unpad_rows(&slice.get_mapped_range(), &layout);
+        staging.unmap();
+        Ok(data)
+    }
+
+    /// Submits a copy of `mip_level` of the first layer into a new staging
+    /// buffer, with rows padded to the copy alignment.
+    fn copy_to_readback_buffer(
+        &self,
+        texture: &Texture,
+        mip_level: u32,
+    ) -> Result<(wgpu::Buffer, ImageLayout), Error> {
+        check_subresource("mip level", mip_level, texture.mip_level_count())?;
+        let size = wgpu::Extent3d {
+            depth_or_array_layers: 1,
+            ..texture
+                .texture
+                .size()
+                .mip_level_size(mip_level, texture.dimension())
+                .physical_size(texture.format())
+        };
+        let layout = ImageLayout::new(texture.format(), size)?;
+        let padded_bytes_per_row = layout.padded_bytes_per_row();
+
+        let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Texture Readback Buffer"),
+            size: padded_bytes_per_row as u64 * layout.rows_per_image as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut command_encoder =
+            self.device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Texture Readback Encoder"),
+                });
+        command_encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                aspect: wgpu::TextureAspect::All,
+                texture: &texture.texture,
+                mip_level,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &staging,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(layout.rows_per_image),
+                },
+            },
+            size,
+        );
+        self.submit(command_encoder);
+        Ok((staging, layout))
+    }
+}
+
+fn unpad_rows(padded: &[u8], layout: &ImageLayout) -> Vec<u8> {
+    padded
+        .chunks(layout.padded_bytes_per_row() as usize)
+        .flat_map(|row| &row[..layout.bytes_per_row as usize])
+        .copied()
+        .collect()
+}
+
+#[cfg(feature = "image")]
+impl Texture {
+    /// Reads back mip level 0 of the first layer into the `DynamicImage`
+    /// variant that holds the format without loss: 8- and 16-bit unorm
+    /// formats as RGBA or grayscale, BGRA swizzled to RGBA, and float
+    /// formats as 32-bit float RGBA or RGB (single-channel ones as gray).
+    /// The texture needs `COPY_SRC` usage.
+    pub fn read_to_image(&self, context: &Context) -> Result<image::DynamicImage, Error> {
+        let data = context.read_texture(self)?;
+        to_image(data, self.width(), self.height(), self.format())
+    }
+}
+
+/// Tightly packed texture `data` as in `Texture::read_to_image`.
+#[cfg(feature = "image")]
+pub(crate) fn to_image(
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+) -> Result<image::DynamicImage, Error> {
+    use image::{DynamicImage, ImageBuffer};
+    use wgpu::TextureFormat as F;
+
+    let image = match format {
+        F::Rgba8Unorm | F::Rgba8UnormSrgb => {
+            ImageBuffer::from_raw(width, height, data).map(DynamicImage::ImageRgba8)
+        }
+        F::Bgra8Unorm | F::Bgra8UnormSrgb => {
+            let data = data
+                .chunks_exact(4)
+                .flat_map(|p| [p[2], p[1], p[0], p[3]])
+                .collect();
+            ImageBuffer::from_raw(width, height, data).map(DynamicImage::ImageRgba8)
+        }
+        F::R8Unorm => ImageBuffer::from_raw(width, height, data).map(DynamicImage::ImageLuma8),
+        F::R16Unorm => ImageBuffer::from_raw(width, height, bytemuck::pod_collect_to_vec(&data))
+            .map(DynamicImage::ImageLuma16),
+        F::Rgba16Unorm => ImageBuffer::from_raw(width, height, bytemuck::pod_collect_to_vec(&data))
+            .map(DynamicImage::ImageRgba16),
+        F::Rgba16Float => {
+            let data = bytemuck::pod_collect_to_vec::<u8, half::f16>(&data)
+                .into_iter()
+                .map(half::f16::to_f32)
+                .collect();
+            ImageBuffer::from_raw(width, height, data).map(DynamicImage::ImageRgba32F)
+        }
+        F::Rgba32Float => ImageBuffer::from_raw(width, height, bytemuck::pod_collect_to_vec(&data))
+            .map(DynamicImage::ImageRgba32F),
+        F::R16Float => {
+            let data = bytemuck::pod_collect_to_vec::<u8, half::f16>(&data)
+                .into_iter()
+                .flat_map(|v| [v.to_f32(); 3])
+                .collect();
+            ImageBuffer::from_raw(width, height, data).map(DynamicImage::ImageRgb32F)
+        }
+        F::R32Float => {
+            let data = bytemuck::pod_collect_to_vec::<u8, f32>(&data)
+                .into_iter()
+                .flat_map(|v| [v; 3])
+                .collect();
+            ImageBuffer::from_raw(width, height, data).map(DynamicImage::ImageRgb32F)
+        }
+        _ => return Err(Error::UnsupportedTextureFormat(format)),
+    };
+    image.ok_or(Error::TextureCreationFailed)
+}
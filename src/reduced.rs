@@ -0,0 +1,235 @@
+use super::context::Context;
+use super::diagnostics::{describe_texture, PassRecord};
+use super::error::Error;
+use super::texture::Texture;
+
+/// WGSL helpers for the scene shaders: fragments for which these return
+/// `true` should be discarded. `parity` is `ReducedRendering::parity()`.
+pub const REDUCED_RENDERING_WGSL: &str =
+    "fn checkerboard_skip(position: vec4<f32>, parity: u32) -> bool {
+    let p = vec2<u32>(position.xy);
+    return ((p.x + p.y) & 1u) != parity;
+}
+
+fn interlaced_skip(position: vec4<f32>, parity: u32) -> bool {
+    return (u32(position.y) & 1u) != parity;
+}
+";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReducedRenderingMode {
+    /// Shades every other pixel in a checkerboard pattern, alternating
+    /// every frame.
+    Checkerboard,
+    /// Shades every other row, alternating every frame.
+    Interlaced,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    mode: u32,
+    parity: u32,
+    history: u32,
+    _padding: u32,
+}
+
+/// Experimental reduced-cost rendering: the scene shades half of the pixels
+/// of `texture()` each frame and `reconstruct` fills in the rest from the
+/// previous frame. The scene pass must load rather than clear the target
+/// (see `load_op`), since the skipped pixels are the history.
+pub struct ReducedRendering {
+    target: Texture,
+    mode: ReducedRenderingMode,
+    frame: u64,
+    params: wgpu::Buffer,
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl ReducedRendering {
+    pub fn new(
+        context: &Context,
+        width: &u32,
+        height: &u32,
+        texture_format: &wgpu::TextureFormat,
+        mode: ReducedRenderingMode,
+    ) -> Result<Self, Error> {
+        let device = &context.device;
+        let target = context.render_target(
+            width,
+            height,
+            texture_format,
+            Some("Reduced Rendering Target"),
+        )?;
+
+        let module = device.create_shader_module(wgpu::include_wgsl!("shaders/reconstruct.wgsl"));
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Reconstruct Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Reconstruct Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Reconstruct Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vs_main",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fs_main",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                targets: &[Some((*texture_format).into())],
+            }),
+            multiview: None,
+        });
+        let params = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Reconstruct Params"),
+            size: std::mem::size_of::<Params>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Ok(Self {
+            target,
+            mode,
+            frame: 0,
+            params,
+            bind_group_layout,
+            pipeline,
+        })
+    }
+
+    /// The full-resolution target the scene is drawn into.
+    pub fn texture(&self) -> &Texture {
+        &self.target
+    }
+
+    pub fn mode(&self) -> ReducedRenderingMode {
+        self.mode
+    }
+
+    /// Which half of the pixels is shaded this frame, for the WGSL helpers.
+    pub fn parity(&self) -> u32 {
+        (self.frame & 1) as u32
+    }
+
+    pub fn load_op(&self) -> wgpu::LoadOp<wgpu::Color> {
+        wgpu::LoadOp::Load
+    }
+
+    /// Advances to the next frame, flipping the shaded half.
+    pub fn next_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    /// Forgets the history, e.g. after a camera cut, so the skipped pixels
+    /// are interpolated from their neighbours for one frame.
+    pub fn reset(&mut self) {
+        self.frame = 0;
+    }
+
+    /// Writes the reconstructed full image into `output`, which must have the
+    /// size and format of `texture()`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "trace",
+            skip_all,
+            fields(mode = ?self.mode, parity = self.parity())
+        )
+    )]
+    pub fn reconstruct(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        output: &wgpu::TextureView,
+    ) {
+        let params = Params {
+            mode: match self.mode {
+                ReducedRenderingMode::Checkerboard => 0,
+                ReducedRenderingMode::Interlaced => 1,
+            },
+            parity: self.parity(),
+            history: (self.frame > 0) as u32,
+            _padding: 0,
+        };
+        context
+            .queue
+            .write_buffer(&self.params, 0, bytemuck::bytes_of(&params));
+
+        let bind_group = context
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Reconstruct Bind Group"),
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: self.params.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&self.target.view),
+                    },
+                ],
+            });
+
+        context.diagnostics.record_pass(|| {
+            PassRecord::new(
+                "Reconstruct Pass",
+                "Reconstruct Pipeline",
+                vec![describe_texture("source", &self.target.texture)],
+            )
+        });
+
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Reconstruct Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: output,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,177 @@
+use super::context::Context;
+use super::error::Error;
+use super::texture::Texture;
+
+/// Settings of an offscreen render target, defaulting to what
+/// `Context::render_target` creates.
+#[derive(Clone, Debug)]
+pub struct RenderTargetBuilder<'a> {
+    label: Option<&'a str>,
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+    transient: bool,
+    usage: wgpu::TextureUsages,
+}
+
+impl<'a> RenderTargetBuilder<'a> {
+    pub fn new(width: u32, height: u32, format: wgpu::TextureFormat) -> Self {
+        Self {
+            label: None,
+            width,
+            height,
+            format,
+            sample_count: 1,
+            transient: false,
+            usage: wgpu::TextureUsages::empty(),
+        }
+    }
+
+    pub fn label(mut self, label: &'a str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    /// Samples per pixel, one of `Context::supported_sample_counts`.
+    pub fn sample_count(mut self, sample_count: u32) -> Self {
+        self.sample_count = sample_count;
+        self
+    }
+
+    /// Restricts the target to use as an attachment, without sampling or
+    /// copies, for depth that is never stored or MSAA samples that are only
+    /// resolved. Drivers of tiled GPUs can then keep it in tile memory
+    /// rather than allocating it in full, provided passes discard it with
+    /// `Texture::depth_attachment` or a resolving `Texture::color_attachment`.
+    pub fn transient(mut self, transient: bool) -> Self {
+        self.transient = transient;
+        self
+    }
+
+    /// Usages in addition to the defaults, rendering, sampling and (for a
+    /// single sample) copying from. Not allowed for transient targets.
+    pub fn usage(mut self, usage: wgpu::TextureUsages) -> Self {
+        self.usage = usage;
+        self
+    }
+
+    pub fn build(&self, context: &Context) -> Result<Texture, Error> {
+        let format = self.format;
+        if !context
+            .device
+            .features()
+            .contains(format.required_features())
+        {
+            return Err(Error::UnsupportedTextureFormat(format));
+        }
+        // Without adapter-specific features wgpu validates against the
+        // guaranteed ones.
+        let allowed_usages = match context
+            .device
+            .features()
+            .contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES)
+        {
+            true => context.adapter.get_texture_format_features(format),
+            false => format.guaranteed_format_features(context.device.features()),
+        }
+        .allowed_usages;
+        if !allowed_usages.contains(wgpu::TextureUsages::RENDER_ATTACHMENT) {
+            return Err(Error::InvalidTexture(format!(
+                "{format:?} is not renderable"
+            )));
+        }
+        if self.sample_count != 1
+            && !context
+                .supported_sample_counts(self.format)
+                .contains(&self.sample_count)
+        {
+            return Err(Error::UnsupportedSampleCount {
+                format: self.format,
+                sample_count: self.sample_count,
+            });
+        }
+        if self.transient && !self.usage.is_empty() {
+            return Err(Error::InvalidTexture(format!(
+                "transient render targets cannot have {:?} usage",
+                self.usage
+            )));
+        }
+        let size = wgpu::Extent3d {
+            width: self.width,
+            height: self.height,
+            depth_or_array_layers: 1,
+        };
+        if size.width == 0 || size.height == 0 {
+            return Err(Error::InvalidTexture(format!(
+                "zero-sized render target {size:?}"
+            )));
+        }
+        context.check_texture_size(&size, wgpu::TextureDimension::D2)?;
+
+        let usage = match (self.transient, self.sample_count) {
+            (true, _) => wgpu::TextureUsages::RENDER_ATTACHMENT,
+            (false, 1) => {
+                wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::COPY_SRC
+            }
+            (false, _) => {
+                wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING
+            }
+        };
+        let usage = usage | self.usage;
+        if !allowed_usages.contains(usage) {
+            return Err(Error::InvalidTexture(format!(
+                "{format:?} does not support {:?} usage",
+                usage - allowed_usages
+            )));
+        }
+        let texture = context.device.create_texture(&wgpu::TextureDescriptor {
+            label: self.label,
+            size,
+            mip_level_count: 1,
+            sample_count: self.sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            view_formats: &[format],
+            usage,
+        });
+        #[cfg(feature = "tracing")]
+        tracing::debug!("created texture");
+        Ok(Texture::from_wgpu(context, texture))
+    }
+}
+
+impl Texture {
+    /// Whether the texture can only be used as an attachment, so that its
+    /// contents need not outlive a pass, as for `RenderTargetBuilder::transient`
+    /// targets and `Context::msaa_texture`.
+    pub fn is_transient(&self) -> bool {
+        self.usage() == wgpu::TextureUsages::RENDER_ATTACHMENT
+    }
+
+    /// A depth (and stencil) attachment of this texture for
+    /// `RenderPassDescriptor`, with depth loaded with `load` and stencil
+    /// loaded. Both are stored unless the texture is transient.
+    pub fn depth_attachment(
+        &self,
+        load: wgpu::LoadOp<f32>,
+    ) -> wgpu::RenderPassDepthStencilAttachment<'_> {
+        let store = match self.is_transient() {
+            true => wgpu::StoreOp::Discard,
+            false => wgpu::StoreOp::Store,
+        };
+        let format = self.format();
+        wgpu::RenderPassDepthStencilAttachment {
+            view: &self.view,
+            depth_ops: format
+                .has_depth_aspect()
+                .then_some(wgpu::Operations { load, store }),
+            stencil_ops: format.has_stencil_aspect().then_some(wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store,
+            }),
+        }
+    }
+}
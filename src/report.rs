@@ -0,0 +1,163 @@
+use super::context::Context;
+use std::fmt;
+
+const REPORTED_FORMATS: &[wgpu::TextureFormat] = &[
+    wgpu::TextureFormat::R8Unorm,
+    wgpu::TextureFormat::R8Snorm,
+    wgpu::TextureFormat::R8Uint,
+    wgpu::TextureFormat::R16Unorm,
+    wgpu::TextureFormat::R16Float,
+    wgpu::TextureFormat::R32Uint,
+    wgpu::TextureFormat::R32Float,
+    wgpu::TextureFormat::Rg8Unorm,
+    wgpu::TextureFormat::Rg16Float,
+    wgpu::TextureFormat::Rg32Float,
+    wgpu::TextureFormat::Rgba8Unorm,
+    wgpu::TextureFormat::Rgba8UnormSrgb,
+    wgpu::TextureFormat::Bgra8Unorm,
+    wgpu::TextureFormat::Bgra8UnormSrgb,
+    wgpu::TextureFormat::Rgb10a2Unorm,
+    wgpu::TextureFormat::Rg11b10Float,
+    wgpu::TextureFormat::Rgba16Unorm,
+    wgpu::TextureFormat::Rgba16Float,
+    wgpu::TextureFormat::Rgba32Uint,
+    wgpu::TextureFormat::Rgba32Float,
+    wgpu::TextureFormat::Depth16Unorm,
+    wgpu::TextureFormat::Depth24Plus,
+    wgpu::TextureFormat::Depth24PlusStencil8,
+    wgpu::TextureFormat::Depth32Float,
+    wgpu::TextureFormat::Depth32FloatStencil8,
+    wgpu::TextureFormat::Bc1RgbaUnorm,
+    wgpu::TextureFormat::Bc3RgbaUnorm,
+    wgpu::TextureFormat::Bc4RUnorm,
+    wgpu::TextureFormat::Bc5RgUnorm,
+    wgpu::TextureFormat::Bc6hRgbUfloat,
+    wgpu::TextureFormat::Bc7RgbaUnorm,
+    wgpu::TextureFormat::Etc2Rgb8Unorm,
+    wgpu::TextureFormat::Etc2Rgba8Unorm,
+    wgpu::TextureFormat::Astc {
+        block: wgpu::AstcBlock::B4x4,
+        channel: wgpu::AstcChannel::Unorm,
+    },
+];
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FormatReport {
+    pub format: wgpu::TextureFormat,
+    pub allowed_usages: Vec<String>,
+    pub flags: Vec<String>,
+    pub sample_counts: Vec<u32>,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ContextReport {
+    pub adapter: wgpu::AdapterInfo,
+    pub adapter_features: Vec<String>,
+    pub device_features: Vec<String>,
+    pub limits: wgpu::Limits,
+    pub downlevel_flags: Vec<String>,
+    pub shader_model: String,
+    pub texture_formats: Vec<FormatReport>,
+    pub surface_formats: Vec<wgpu::TextureFormat>,
+    pub present_modes: Vec<wgpu::PresentMode>,
+}
+
+impl ContextReport {
+    pub fn has_device_feature(&self, feature: wgpu::Features) -> bool {
+        feature
+            .iter_names()
+            .all(|(name, _)| self.device_features.iter().any(|f| f == name))
+    }
+
+    pub fn format(&self, format: wgpu::TextureFormat) -> Option<&FormatReport> {
+        self.texture_formats.iter().find(|f| f.format == format)
+    }
+}
+
+impl Context {
+    pub fn report(&self) -> ContextReport {
+        self.report_with_surface(None)
+    }
+
+    pub fn report_with_surface(&self, surface: Option<&wgpu::Surface<'_>>) -> ContextReport {
+        let adapter_features = self.adapter.features();
+        let downlevel = self.adapter.get_downlevel_capabilities();
+        let texture_formats = REPORTED_FORMATS
+            .iter()
+            .filter(|format| adapter_features.contains(format.required_features()))
+            .map(|format| {
+                let features = self.adapter.get_texture_format_features(*format);
+                FormatReport {
+                    format: *format,
+                    allowed_usages: names(features.allowed_usages.iter_names()),
+                    flags: names(features.flags.iter_names()),
+                    sample_counts: features.flags.supported_sample_counts(),
+                }
+            })
+            .collect();
+        let capabilities = surface.map(|surface| surface.get_capabilities(&self.adapter));
+
+        ContextReport {
+            adapter: self.adapter.get_info(),
+            adapter_features: names(adapter_features.iter_names()),
+            device_features: names(self.device.features().iter_names()),
+            limits: self.device.limits(),
+            downlevel_flags: names(downlevel.flags.iter_names()),
+            shader_model: format!("{:?}", downlevel.shader_model),
+            texture_formats,
+            surface_formats: capabilities
+                .as_ref()
+                .map(|c| c.formats.clone())
+                .unwrap_or_default(),
+            present_modes: capabilities.map(|c| c.present_modes).unwrap_or_default(),
+        }
+    }
+}
+
+impl fmt::Display for ContextReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Adapter: {} ({:?})",
+            self.adapter.name, self.adapter.device_type
+        )?;
+        writeln!(f, "Backend: {:?}", self.adapter.backend)?;
+        writeln!(
+            f,
+            "Driver: {} {}",
+            self.adapter.driver, self.adapter.driver_info
+        )?;
+        writeln!(
+            f,
+            "Vendor/device: {:#06x}/{:#06x}",
+            self.adapter.vendor, self.adapter.device
+        )?;
+        writeln!(f, "Shader model: {}", self.shader_model)?;
+        writeln!(f, "Device features: {}", self.device_features.join(", "))?;
+        writeln!(f, "Adapter features: {}", self.adapter_features.join(", "))?;
+        writeln!(f, "Downlevel flags: {}", self.downlevel_flags.join(", "))?;
+        writeln!(f, "Limits: {:#?}", self.limits)?;
+        writeln!(f, "Texture formats:")?;
+        for format in &self.texture_formats {
+            writeln!(
+                f,
+                "  {:?}: usages [{}], flags [{}], samples {:?}",
+                format.format,
+                format.allowed_usages.join(", "),
+                format.flags.join(", "),
+                format.sample_counts
+            )?;
+        }
+        if !self.surface_formats.is_empty() {
+            writeln!(f, "Surface formats: {:?}", self.surface_formats)?;
+            writeln!(f, "Present modes: {:?}", self.present_modes)?;
+        }
+        Ok(())
+    }
+}
+
+fn names<'a, T>(iter: impl Iterator<Item = (&'a str, T)>) -> Vec<String> {
+    iter.map(|(name, _)| name.to_string()).collect()
+}
@@ -0,0 +1,162 @@
+#[cfg(feature = "image")]
+use super::context::{image_data, Context};
+#[cfg(feature = "image")]
+use super::error::Error;
+#[cfg(feature = "image")]
+use super::texture::Texture;
+#[cfg(not(target_arch = "wasm32"))]
+use std::future::Future;
+#[cfg(not(target_arch = "wasm32"))]
+use std::pin::Pin;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::{Arc, Mutex};
+#[cfg(not(target_arch = "wasm32"))]
+use std::task::{Poll, Waker};
+
+/// Runs CPU-bound work such as image decoding off the async executor and
+/// resolves to its result: on tokio's blocking pool with the `tokio`
+/// feature inside a tokio runtime, and on a thread of its own otherwise,
+/// which suits async-std, smol and other executors. Panics of `work` are
+/// resumed in the awaiting task. On wasm32 `work` runs in place.
+pub async fn spawn_blocking<T, F>(work: F) -> T
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    #[cfg(target_arch = "wasm32")]
+    return work();
+
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    if tokio::runtime::Handle::try_current().is_ok() {
+        return match tokio::task::spawn_blocking(work).await {
+            Ok(value) => value,
+            Err(error) => std::panic::resume_unwind(error.into_panic()),
+        };
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let state = Arc::new(Mutex::new(BlockingState::default()));
+        let thread_state = state.clone();
+        std::thread::spawn(move || {
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(work));
+            let mut state = thread_state
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            state.result = Some(result);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        });
+        match (BlockingTask { state }).await {
+            Ok(value) => value,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+type ThreadResult<T> = std::thread::Result<T>;
+
+#[cfg(not(target_arch = "wasm32"))]
+struct BlockingState<T> {
+    result: Option<ThreadResult<T>>,
+    waker: Option<Waker>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T> Default for BlockingState<T> {
+    fn default() -> Self {
+        Self {
+            result: None,
+            waker: None,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+struct BlockingTask<T> {
+    state: Arc<Mutex<BlockingState<T>>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T> Future for BlockingTask<T> {
+    type Output = ThreadResult<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
+        let mut state = self
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Blocks in `wait` without stalling the executor: through
+/// `tokio::task::block_in_place` on a multi-threaded tokio runtime, and in
+/// place otherwise.
+pub(crate) fn block_in_place<T>(wait: impl FnOnce() -> T) -> T {
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    if tokio::runtime::Handle::try_current()
+        .is_ok_and(|handle| handle.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread)
+    {
+        return tokio::task::block_in_place(wait);
+    }
+    wait()
+}
+
+#[cfg(feature = "image")]
+impl Context {
+    /// Reads and decodes the image at `path` with `spawn_blocking`, then
+    /// uploads it as `texture_from_image` does.
+    pub async fn load_texture_async(
+        &self,
+        path: impl AsRef<std::path::Path>,
+        texture_format: &wgpu::TextureFormat,
+        label: Option<&str>,
+    ) -> Result<Texture, Error> {
+        let path = path.as_ref().to_path_buf();
+        self.decode_texture(move || image::open(path), texture_format, label)
+            .await
+    }
+
+    /// Decodes encoded image `data` with `spawn_blocking`, then uploads it
+    /// as `texture_from_image_data` does.
+    pub async fn texture_from_image_data_async(
+        &self,
+        data: Vec<u8>,
+        texture_format: &wgpu::TextureFormat,
+        label: Option<&str>,
+    ) -> Result<Texture, Error> {
+        self.decode_texture(
+            move || image::load_from_memory(&data),
+            texture_format,
+            label,
+        )
+        .await
+    }
+
+    async fn decode_texture(
+        &self,
+        decode: impl FnOnce() -> image::ImageResult<image::DynamicImage> + Send + 'static,
+        texture_format: &wgpu::TextureFormat,
+        label: Option<&str>,
+    ) -> Result<Texture, Error> {
+        use image::GenericImageView;
+
+        let format = *texture_format;
+        let (data, width, height) = spawn_blocking(move || {
+            let image = decode()?;
+            let (width, height) = image.dimensions();
+            Ok::<_, Error>((image_data(&image, &format), width, height))
+        })
+        .await?;
+        self.texture_with_data(&data, &width, &height, texture_format, label)
+    }
+}
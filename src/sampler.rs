@@ -0,0 +1,247 @@
+use super::context::Context;
+use super::error::Error;
+use super::texture::Texture;
+
+/// Sampler settings, defaulting to the clamped trilinear sampler the
+/// texture constructors create.
+#[derive(Clone, Debug)]
+pub struct SamplerBuilder<'a> {
+    label: Option<&'a str>,
+    address_modes: [wgpu::AddressMode; 3],
+    mag_filter: wgpu::FilterMode,
+    min_filter: wgpu::FilterMode,
+    mipmap_filter: wgpu::FilterMode,
+    lod_min_clamp: f32,
+    lod_max_clamp: f32,
+    compare: Option<wgpu::CompareFunction>,
+    anisotropy_clamp: u16,
+    border_color: Option<wgpu::SamplerBorderColor>,
+}
+
+impl Default for SamplerBuilder<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> SamplerBuilder<'a> {
+    pub fn new() -> Self {
+        Self {
+            label: None,
+            address_modes: [wgpu::AddressMode::ClampToEdge; 3],
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            lod_min_clamp: 0.0,
+            lod_max_clamp: 32.0,
+            compare: None,
+            anisotropy_clamp: 1,
+            border_color: None,
+        }
+    }
+
+    pub fn label(mut self, label: &'a str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    /// Sets the address mode along all three axes.
+    pub fn address_mode(mut self, mode: wgpu::AddressMode) -> Self {
+        self.address_modes = [mode; 3];
+        self
+    }
+
+    pub fn address_mode_u(mut self, mode: wgpu::AddressMode) -> Self {
+        self.address_modes[0] = mode;
+        self
+    }
+
+    pub fn address_mode_v(mut self, mode: wgpu::AddressMode) -> Self {
+        self.address_modes[1] = mode;
+        self
+    }
+
+    pub fn address_mode_w(mut self, mode: wgpu::AddressMode) -> Self {
+        self.address_modes[2] = mode;
+        self
+    }
+
+    /// Sets the magnification, minification and mipmap filters.
+    pub fn filter(mut self, filter: wgpu::FilterMode) -> Self {
+        self.mag_filter = filter;
+        self.min_filter = filter;
+        self.mipmap_filter = filter;
+        self
+    }
+
+    pub fn mag_filter(mut self, filter: wgpu::FilterMode) -> Self {
+        self.mag_filter = filter;
+        self
+    }
+
+    pub fn min_filter(mut self, filter: wgpu::FilterMode) -> Self {
+        self.min_filter = filter;
+        self
+    }
+
+    pub fn mipmap_filter(mut self, filter: wgpu::FilterMode) -> Self {
+        self.mipmap_filter = filter;
+        self
+    }
+
+    /// Limits sampling to mip levels `min..=max`.
+    pub fn lod_clamp(mut self, min: f32, max: f32) -> Self {
+        self.lod_min_clamp = min;
+        self.lod_max_clamp = max;
+        self
+    }
+
+    /// Makes a comparison sampler, for `texture_depth_2d` shadow lookups.
+    pub fn compare(mut self, compare: wgpu::CompareFunction) -> Self {
+        self.compare = Some(compare);
+        self
+    }
+
+    /// Maximum anisotropy from 1 (off) to 16, which needs linear filters
+    /// and an adapter supporting anisotropic filtering
+    /// (`Context::max_anisotropy`).
+    pub fn anisotropy(mut self, clamp: u16) -> Self {
+        self.anisotropy_clamp = clamp;
+        self
+    }
+
+    /// Color outside the texture with `AddressMode::ClampToBorder`.
+    pub fn border_color(mut self, color: wgpu::SamplerBorderColor) -> Self {
+        self.border_color = Some(color);
+        self
+    }
+
+    fn descriptor(&self) -> wgpu::SamplerDescriptor<'a> {
+        let [address_mode_u, address_mode_v, address_mode_w] = self.address_modes;
+        wgpu::SamplerDescriptor {
+            label: self.label,
+            address_mode_u,
+            address_mode_v,
+            address_mode_w,
+            mag_filter: self.mag_filter,
+            min_filter: self.min_filter,
+            mipmap_filter: self.mipmap_filter,
+            lod_min_clamp: self.lod_min_clamp,
+            lod_max_clamp: self.lod_max_clamp,
+            compare: self.compare,
+            anisotropy_clamp: self.anisotropy_clamp,
+            border_color: self.border_color,
+        }
+    }
+
+    /// Creates the sampler, after checking the settings that would
+    /// otherwise fail wgpu validation.
+    pub fn build(&self, context: &Context) -> Result<wgpu::Sampler, Error> {
+        if !(1..=16).contains(&self.anisotropy_clamp) {
+            return Err(Error::InvalidSampler(format!(
+                "anisotropy {} is outside 1 to 16",
+                self.anisotropy_clamp
+            )));
+        }
+        if self.anisotropy_clamp > context.max_anisotropy() {
+            return Err(Error::InvalidSampler(format!(
+                "anisotropy {} is not supported by the adapter",
+                self.anisotropy_clamp
+            )));
+        }
+        let linear = [self.mag_filter, self.min_filter, self.mipmap_filter]
+            .iter()
+            .all(|&filter| filter == wgpu::FilterMode::Linear);
+        if self.anisotropy_clamp > 1 && !linear {
+            return Err(Error::InvalidSampler(
+                "anisotropic filtering needs linear filters".to_string(),
+            ));
+        }
+        if !(0.0 <= self.lod_min_clamp && self.lod_min_clamp <= self.lod_max_clamp) {
+            return Err(Error::InvalidSampler(format!(
+                "LOD clamp {}..{} is empty or negative",
+                self.lod_min_clamp, self.lod_max_clamp
+            )));
+        }
+        let features = context.device.features();
+        if self
+            .address_modes
+            .contains(&wgpu::AddressMode::ClampToBorder)
+            && !features.contains(wgpu::Features::ADDRESS_MODE_CLAMP_TO_BORDER)
+        {
+            return Err(Error::InvalidSampler(
+                "ClampToBorder needs the ADDRESS_MODE_CLAMP_TO_BORDER feature".to_string(),
+            ));
+        }
+        if self.border_color == Some(wgpu::SamplerBorderColor::Zero)
+            && !features.contains(wgpu::Features::ADDRESS_MODE_CLAMP_TO_ZERO)
+        {
+            return Err(Error::InvalidSampler(
+                "a zero border needs the ADDRESS_MODE_CLAMP_TO_ZERO feature".to_string(),
+            ));
+        }
+        Ok(context.device.create_sampler(&self.descriptor()))
+    }
+}
+
+impl Context {
+    pub(crate) fn linear_sampler(&self) -> wgpu::Sampler {
+        self.device
+            .create_sampler(&SamplerBuilder::new().descriptor())
+    }
+
+    /// The sampler of textures created by the texture constructors, with
+    /// the anisotropy of `set_texture_anisotropy`.
+    pub(crate) fn texture_sampler(&self) -> wgpu::Sampler {
+        self.device.create_sampler(
+            &SamplerBuilder::new()
+                .anisotropy(self.texture_anisotropy)
+                .descriptor(),
+        )
+    }
+
+    /// Largest anisotropy samplers can use: 16, or 1 on adapters without
+    /// anisotropic filtering (some GLES and WebGL ones).
+    pub fn max_anisotropy(&self) -> u16 {
+        let flags = self.adapter.get_downlevel_capabilities().flags;
+        if flags.contains(wgpu::DownlevelFlags::ANISOTROPIC_FILTERING) {
+            16
+        } else {
+            1
+        }
+    }
+
+    pub fn texture_anisotropy(&self) -> u16 {
+        self.texture_anisotropy
+    }
+
+    /// Sets the anisotropy of the samplers of textures created afterwards
+    /// by `texture_with_data`, `texture_from_image` and the other texture
+    /// constructors, for textures seen at grazing angles such as terrain
+    /// and floors. Fails outside 1 to `max_anisotropy`.
+    pub fn set_texture_anisotropy(&mut self, clamp: u16) -> Result<(), Error> {
+        if !(1..=self.max_anisotropy()).contains(&clamp) {
+            return Err(Error::InvalidSampler(format!(
+                "anisotropy {clamp} is outside 1 to {}",
+                self.max_anisotropy()
+            )));
+        }
+        self.texture_anisotropy = clamp;
+        Ok(())
+    }
+}
+
+impl Texture {
+    /// Replaces the sampler, e.g. with one from `SamplerBuilder`.
+    pub fn with_sampler(mut self, sampler: wgpu::Sampler) -> Self {
+        self.sampler = sampler;
+        self
+    }
+
+    /// Replaces the sampler with the default trilinear one with anisotropy
+    /// `clamp`, checked as `SamplerBuilder::build` does.
+    pub fn with_anisotropy(self, context: &Context, clamp: u16) -> Result<Self, Error> {
+        let sampler = SamplerBuilder::new().anisotropy(clamp).build(context)?;
+        Ok(self.with_sampler(sampler))
+    }
+}
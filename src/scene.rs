@@ -0,0 +1,405 @@
+use super::context::Context;
+use super::error::Error;
+use super::format::ColorSpace;
+use super::math::{cross, dot, multiply, normalize, sub};
+use super::mesh::{GpuMesh, Mesh};
+use super::texture::Texture;
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// JSON scene description. Paths are relative to the scene file.
+///
+/// ```json
+/// {
+///   "meshes": [{ "name": "ball", "sphere": { "radius": 1.0 } }],
+///   "materials": [{ "name": "red", "base_color": [1, 0, 0, 1] }],
+///   "objects": [{ "mesh": "ball", "material": "red", "translation": [0, 1, 0] }],
+///   "lights": [{ "directional": { "direction": [-1, -1, -1] } }],
+///   "camera": { "position": [0, 2, 5], "target": [0, 1, 0] },
+///   "environment": { "color": [0.1, 0.1, 0.1] }
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SceneDescription {
+    pub meshes: Vec<MeshDescription>,
+    pub materials: Vec<MaterialDescription>,
+    pub objects: Vec<ObjectDescription>,
+    pub lights: Vec<Light>,
+    pub camera: Camera,
+    pub environment: Environment,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MeshDescription {
+    pub name: String,
+    #[serde(flatten)]
+    pub source: MeshSource,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MeshSource {
+    Cube {
+        #[serde(default = "one")]
+        size: f32,
+    },
+    Plane {
+        #[serde(default = "one")]
+        size: f32,
+    },
+    Sphere {
+        #[serde(default = "one")]
+        radius: f32,
+        #[serde(default = "sphere_segments")]
+        segments: u32,
+        #[serde(default = "sphere_rings")]
+        rings: u32,
+    },
+    Inline(Mesh),
+}
+
+impl MeshSource {
+    pub fn to_mesh(&self) -> Mesh {
+        match self {
+            MeshSource::Cube { size } => Mesh::cube(*size),
+            MeshSource::Plane { size } => Mesh::plane(*size),
+            MeshSource::Sphere {
+                radius,
+                segments,
+                rings,
+            } => Mesh::sphere(*radius, *segments, *rings),
+            MeshSource::Inline(mesh) => mesh.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MaterialDescription {
+    pub name: String,
+    pub base_color: [f32; 4],
+    pub base_color_texture: Option<PathBuf>,
+    pub metallic: f32,
+    pub roughness: f32,
+    pub emissive: [f32; 3],
+}
+
+impl Default for MaterialDescription {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            base_color: [1.0; 4],
+            base_color_texture: None,
+            metallic: 0.0,
+            roughness: 0.5,
+            emissive: [0.0; 3],
+        }
+    }
+}
+
+/// A mesh instance. `rotation` is a unit quaternion (x, y, z, w).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ObjectDescription {
+    pub mesh: String,
+    #[serde(default)]
+    pub material: Option<String>,
+    #[serde(default)]
+    pub translation: [f32; 3],
+    #[serde(default = "identity_rotation")]
+    pub rotation: [f32; 4],
+    #[serde(default = "unit_scale")]
+    pub scale: [f32; 3],
+}
+
+impl ObjectDescription {
+    /// An untransformed instance of `mesh` without a material.
+    pub fn new(mesh: &str) -> Self {
+        Self {
+            mesh: mesh.to_string(),
+            material: None,
+            translation: [0.0; 3],
+            rotation: identity_rotation(),
+            scale: unit_scale(),
+        }
+    }
+
+    /// Column-major translation * rotation * scale matrix.
+    pub fn transform(&self) -> [[f32; 4]; 4] {
+        let [x, y, z, w] = self.rotation;
+        let [sx, sy, sz] = self.scale;
+        let [tx, ty, tz] = self.translation;
+        [
+            [
+                (1.0 - 2.0 * (y * y + z * z)) * sx,
+                2.0 * (x * y + z * w) * sx,
+                2.0 * (x * z - y * w) * sx,
+                0.0,
+            ],
+            [
+                2.0 * (x * y - z * w) * sy,
+                (1.0 - 2.0 * (x * x + z * z)) * sy,
+                2.0 * (y * z + x * w) * sy,
+                0.0,
+            ],
+            [
+                2.0 * (x * z + y * w) * sz,
+                2.0 * (y * z - x * w) * sz,
+                (1.0 - 2.0 * (x * x + y * y)) * sz,
+                0.0,
+            ],
+            [tx, ty, tz, 1.0],
+        ]
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Light {
+    Directional {
+        direction: [f32; 3],
+        #[serde(default = "white")]
+        color: [f32; 3],
+        #[serde(default = "one")]
+        intensity: f32,
+    },
+    Point {
+        position: [f32; 3],
+        #[serde(default = "white")]
+        color: [f32; 3],
+        #[serde(default = "one")]
+        intensity: f32,
+        #[serde(default)]
+        range: Option<f32>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Camera {
+    pub position: [f32; 3],
+    pub target: [f32; 3],
+    pub up: [f32; 3],
+    pub fov_y_degrees: f32,
+    pub near: f32,
+    pub far: f32,
+    /// Map the near plane to depth 1.0 and the far plane to 0.0, matching
+    /// a context with `reverse_z` set.
+    pub reverse_z: bool,
+    /// Ignore `far` and project with the far plane at infinity.
+    pub infinite_far: bool,
+    /// The scene shaders write `LOGARITHMIC_DEPTH_WGSL` depth up to `far`.
+    pub logarithmic_depth: bool,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            position: [0.0, 0.0, 5.0],
+            target: [0.0; 3],
+            up: [0.0, 1.0, 0.0],
+            fov_y_degrees: 60.0,
+            near: 0.1,
+            far: 1000.0,
+            reverse_z: false,
+            infinite_far: false,
+            logarithmic_depth: false,
+        }
+    }
+}
+
+impl Camera {
+    /// Column-major right-handed look-at view matrix.
+    pub fn view(&self) -> [[f32; 4]; 4] {
+        let forward = normalize(sub(self.target, self.position));
+        let right = normalize(cross(forward, self.up));
+        let up = cross(right, forward);
+        let eye = self.position;
+        [
+            [right[0], up[0], -forward[0], 0.0],
+            [right[1], up[1], -forward[1], 0.0],
+            [right[2], up[2], -forward[2], 0.0],
+            [-dot(right, eye), -dot(up, eye), dot(forward, eye), 1.0],
+        ]
+    }
+
+    /// `projection(aspect) * view()`, e.g. for `Frustum::from_view_projection`.
+    pub fn view_projection(&self, aspect: f32) -> [[f32; 4]; 4] {
+        multiply(&self.projection(aspect), &self.view())
+    }
+
+    /// Column-major right-handed perspective projection with depth in
+    /// [0, 1], reversed if `reverse_z` is set.
+    pub fn projection(&self, aspect: f32) -> [[f32; 4]; 4] {
+        let f = 1.0 / (self.fov_y_degrees.to_radians() * 0.5).tan();
+        let (near, far) = (self.near, self.far);
+        let (z_scale, z_offset) = match (self.reverse_z, self.infinite_far) {
+            (false, false) => (far / (near - far), near * far / (near - far)),
+            (true, false) => (near / (far - near), near * far / (far - near)),
+            (false, true) => (-1.0, -near),
+            (true, true) => (0.0, near),
+        };
+        [
+            [f / aspect, 0.0, 0.0, 0.0],
+            [0.0, f, 0.0, 0.0],
+            [0.0, 0.0, z_scale, -1.0],
+            [0.0, 0.0, z_offset, 0.0],
+        ]
+    }
+}
+
+/// Background color, or an equirectangular image if `texture` is set.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Environment {
+    pub color: [f32; 3],
+    pub texture: Option<PathBuf>,
+    pub intensity: Option<f32>,
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+fn sphere_segments() -> u32 {
+    32
+}
+
+fn sphere_rings() -> u32 {
+    16
+}
+
+fn white() -> [f32; 3] {
+    [1.0; 3]
+}
+
+fn identity_rotation() -> [f32; 4] {
+    [0.0, 0.0, 0.0, 1.0]
+}
+
+fn unit_scale() -> [f32; 3] {
+    [1.0; 3]
+}
+
+impl SceneDescription {
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let scene: Self = serde_json::from_str(json)?;
+        scene.validate()?;
+        Ok(scene)
+    }
+
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Checks that objects reference existing meshes and materials.
+    pub fn validate(&self) -> Result<(), Error> {
+        for object in &self.objects {
+            if !self.meshes.iter().any(|m| m.name == object.mesh) {
+                return Err(Error::InvalidScene(format!(
+                    "unknown mesh {:?}",
+                    object.mesh
+                )));
+            }
+            if let Some(material) = &object.material {
+                if !self.materials.iter().any(|m| &m.name == material) {
+                    return Err(Error::InvalidScene(format!(
+                        "unknown material {material:?}"
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A scene with its meshes uploaded and textures loaded.
+pub struct Scene {
+    pub description: SceneDescription,
+    pub meshes: HashMap<String, GpuMesh>,
+    /// Material textures by path as written in the scene.
+    pub textures: HashMap<PathBuf, Texture>,
+    pub environment: Option<Texture>,
+}
+
+impl Scene {
+    pub fn object_mesh(&self, object: &ObjectDescription) -> &GpuMesh {
+        &self.meshes[&object.mesh]
+    }
+
+    pub fn material(&self, object: &ObjectDescription) -> Option<&MaterialDescription> {
+        let name = object.material.as_ref()?;
+        self.description.materials.iter().find(|m| &m.name == name)
+    }
+}
+
+impl Context {
+    pub fn load_scene(&self, path: impl AsRef<Path>) -> Result<Scene, Error> {
+        let path = path.as_ref();
+        let description = SceneDescription::from_json(&std::fs::read_to_string(path)?)?;
+        self.instantiate_scene(description, path.parent().unwrap_or(Path::new("")))
+    }
+
+    /// Uploads the meshes and loads the textures of `description`, resolving
+    /// relative paths against `base`. Color textures are sRGB, the
+    /// environment is loaded as 32-bit float.
+    pub fn instantiate_scene(
+        &self,
+        description: SceneDescription,
+        base: &Path,
+    ) -> Result<Scene, Error> {
+        self.instantiate_scene_with(description, |path| Ok(image::open(base.join(path))?))
+    }
+
+    /// Like `instantiate_scene` but with images opened by `open`, e.g. from
+    /// a package.
+    pub(crate) fn instantiate_scene_with(
+        &self,
+        description: SceneDescription,
+        mut open: impl FnMut(&Path) -> Result<DynamicImage, Error>,
+    ) -> Result<Scene, Error> {
+        description.validate()?;
+        let mut meshes = HashMap::new();
+        for mesh in &description.meshes {
+            let gpu_mesh = self.upload_mesh(&mesh.source.to_mesh(), Some(&mesh.name))?;
+            meshes.insert(mesh.name.clone(), gpu_mesh);
+        }
+
+        let mut textures = HashMap::new();
+        for path in description
+            .materials
+            .iter()
+            .filter_map(|m| m.base_color_texture.as_ref())
+        {
+            if !textures.contains_key(path) {
+                let image = open(path)?;
+                let label = path.to_string_lossy();
+                let texture =
+                    self.color_texture_from_image(&image, ColorSpace::Srgb, Some(&label))?;
+                textures.insert(path.clone(), texture);
+            }
+        }
+        let environment = match &description.environment.texture {
+            Some(path) => {
+                let image = open(path)?.into_rgba32f();
+                Some(self.texture_with_data(
+                    bytemuck::cast_slice(image.as_raw()),
+                    &image.width(),
+                    &image.height(),
+                    &wgpu::TextureFormat::Rgba32Float,
+                    Some("Environment"),
+                )?)
+            }
+            None => None,
+        };
+
+        Ok(Scene {
+            description,
+            meshes,
+            textures,
+            environment,
+        })
+    }
+}
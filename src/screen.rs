@@ -0,0 +1,187 @@
+use super::context::Context;
+use super::error::Error;
+use super::texture::Texture;
+use std::collections::HashMap;
+
+/// A color target that follows the size (optionally scaled) and format of
+/// a surface configuration.
+pub struct ScreenTexture {
+    texture: Texture,
+    format: Option<wgpu::TextureFormat>,
+    scale: f32,
+    label: Option<String>,
+}
+
+impl ScreenTexture {
+    pub fn new(
+        context: &Context,
+        config: &wgpu::SurfaceConfiguration,
+        label: Option<&str>,
+    ) -> Result<Self, Error> {
+        Self::with_options(context, config, None, 1.0, label)
+    }
+
+    /// `format` overrides the surface format, `scale` multiplies the
+    /// surface size (e.g. 0.5 for half-resolution targets).
+    pub fn with_options(
+        context: &Context,
+        config: &wgpu::SurfaceConfiguration,
+        format: Option<wgpu::TextureFormat>,
+        scale: f32,
+        label: Option<&str>,
+    ) -> Result<Self, Error> {
+        let (width, height) = scaled_size(config, scale);
+        let texture =
+            context.render_target(&width, &height, &format.unwrap_or(config.format), label)?;
+        Ok(Self {
+            texture,
+            format,
+            scale,
+            label: label.map(str::to_string),
+        })
+    }
+
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Changes the scale and recreates the texture if its size changed.
+    pub fn set_scale(
+        &mut self,
+        context: &Context,
+        config: &wgpu::SurfaceConfiguration,
+        scale: f32,
+    ) -> Result<bool, Error> {
+        self.scale = scale;
+        self.resize(context, config)
+    }
+
+    /// Recreates the texture if the configuration's size or format changed.
+    /// Returns whether the texture was recreated.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(width = config.width, height = config.height),
+            err
+        )
+    )]
+    pub fn resize(
+        &mut self,
+        context: &Context,
+        config: &wgpu::SurfaceConfiguration,
+    ) -> Result<bool, Error> {
+        let (width, height) = scaled_size(config, self.scale);
+        let format = self.format.unwrap_or(config.format);
+        if self.texture.width() == width
+            && self.texture.height() == height
+            && self.texture.format() == format
+        {
+            return Ok(false);
+        }
+
+        self.texture = context.render_target(&width, &height, &format, self.label.as_deref())?;
+        Ok(true)
+    }
+}
+
+/// Named screen-sized textures that are all resized together when the
+/// surface is reconfigured.
+pub struct ResizeRegistry {
+    config: wgpu::SurfaceConfiguration,
+    textures: HashMap<String, ScreenTexture>,
+    resolution_scale: f32,
+}
+
+impl ResizeRegistry {
+    pub fn new(config: &wgpu::SurfaceConfiguration) -> Self {
+        Self {
+            config: config.clone(),
+            textures: HashMap::new(),
+            resolution_scale: 1.0,
+        }
+    }
+
+    pub fn config(&self) -> &wgpu::SurfaceConfiguration {
+        &self.config
+    }
+
+    pub fn insert(
+        &mut self,
+        context: &Context,
+        name: &str,
+        format: Option<wgpu::TextureFormat>,
+        scale: f32,
+    ) -> Result<&Texture, Error> {
+        let scale = scale * self.resolution_scale;
+        let texture =
+            ScreenTexture::with_options(context, &self.config, format, scale, Some(name))?;
+        self.textures.insert(name.to_string(), texture);
+        Ok(self.textures[name].texture())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Texture> {
+        self.textures.get(name).map(ScreenTexture::texture)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Texture> {
+        self.textures.remove(name).map(|screen| screen.texture)
+    }
+
+    /// Extra scale applied on top of every texture's own scale.
+    pub fn resolution_scale(&self) -> f32 {
+        self.resolution_scale
+    }
+
+    /// Changes the scale applied to all textures, recreating them at the
+    /// new size. Textures inserted later use it as well.
+    pub fn set_resolution_scale(&mut self, context: &Context, scale: f32) -> Result<(), Error> {
+        for texture in self.textures.values_mut() {
+            let base = texture.scale() / self.resolution_scale;
+            texture.set_scale(context, &self.config, base * scale)?;
+        }
+        self.resolution_scale = scale;
+        Ok(())
+    }
+
+    pub fn memory_size(&self) -> u64 {
+        self.textures
+            .values()
+            .map(|screen| screen.texture.memory_size())
+            .sum()
+    }
+
+    pub fn resize(
+        &mut self,
+        context: &Context,
+        config: &wgpu::SurfaceConfiguration,
+    ) -> Result<(), Error> {
+        self.config = config.clone();
+        for texture in self.textures.values_mut() {
+            texture.resize(context, config)?;
+        }
+        Ok(())
+    }
+}
+
+impl Context {
+    pub fn screen_texture(
+        &self,
+        config: &wgpu::SurfaceConfiguration,
+        label: Option<&str>,
+    ) -> Result<Texture, Error> {
+        self.render_target(&config.width, &config.height, &config.format, label)
+    }
+}
+
+fn scaled_size(config: &wgpu::SurfaceConfiguration, scale: f32) -> (u32, u32) {
+    (
+        ((config.width as f32 * scale).round() as u32).max(1),
+        ((config.height as f32 * scale).round() as u32).max(1),
+    )
+}
@@ -0,0 +1,296 @@
+use super::context::Context;
+use super::diagnostics::{describe_buffer, describe_texture, PassRecord};
+use super::error::Error;
+use super::texture::Texture;
+use wgpu::util::DeviceExt;
+
+const WORKGROUP_SIZE: u32 = 8;
+const TILE_SIZE: u32 = 16;
+/// Grow dispatches between checks whether the selection still changes.
+const GROW_DISPATCHES: u32 = 4;
+const DIAGONAL: u32 = 1;
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    seed: [u32; 2],
+    size: [u32; 2],
+    tolerance: f32,
+    flags: u32,
+    _padding: [u32; 2],
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SelectionOptions {
+    /// Largest difference from the seed color in any channel, from 0 to 1.
+    pub tolerance: f32,
+    /// Select only pixels connected to the seed, as a flood fill does,
+    /// instead of every similar pixel.
+    pub contiguous: bool,
+    /// Connect diagonal neighbors too.
+    pub diagonal: bool,
+}
+
+impl Default for SelectionOptions {
+    fn default() -> Self {
+        Self {
+            tolerance: 32.0 / 255.0,
+            contiguous: true,
+            diagonal: false,
+        }
+    }
+}
+
+/// Selects the pixels of a texture similar in color to a seed pixel.
+pub struct MagicWand {
+    bind_group_layout: wgpu::BindGroupLayout,
+    select_similar: wgpu::ComputePipeline,
+    select_seed: wgpu::ComputePipeline,
+    grow: wgpu::ComputePipeline,
+    store: wgpu::ComputePipeline,
+}
+
+impl MagicWand {
+    pub fn new(context: &Context) -> Self {
+        let device = &context.device;
+        let module = device.create_shader_module(wgpu::include_wgsl!("shaders/selection.wgsl"));
+        let entry = |binding, ty| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty,
+            count: None,
+        };
+        let storage = wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only: false },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Magic Wand Bind Group Layout"),
+            entries: &[
+                entry(
+                    0,
+                    wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                ),
+                entry(
+                    1,
+                    wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                ),
+                entry(2, storage),
+                entry(3, storage),
+                entry(
+                    4,
+                    wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: wgpu::TextureFormat::R32Float,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                ),
+            ],
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Magic Wand Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |entry_point: &str| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&layout),
+                module: &module,
+                entry_point,
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            })
+        };
+
+        Self {
+            select_similar: pipeline("select_similar"),
+            select_seed: pipeline("select_seed"),
+            grow: pipeline("grow"),
+            store: pipeline("store"),
+            bind_group_layout,
+        }
+    }
+
+    /// Returns an `R32Float` mask of the selected pixels of the 2D float
+    /// `texture`, 1.0 where selected and 0.0 elsewhere. Contiguous
+    /// selections grow from the seed on the GPU and wait for it between
+    /// rounds of dispatches.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, context, texture),
+            fields(width = texture.width(), height = texture.height()),
+            err
+        )
+    )]
+    pub fn select(
+        &self,
+        context: &Context,
+        texture: &Texture,
+        seed: [u32; 2],
+        options: &SelectionOptions,
+    ) -> Result<Texture, Error> {
+        let (width, height) = (texture.width(), texture.height());
+        if seed[0] >= width || seed[1] >= height {
+            return Err(Error::SeedOutOfBounds {
+                seed,
+                width,
+                height,
+            });
+        }
+        if !matches!(
+            texture.format().sample_type(None, None),
+            Some(wgpu::TextureSampleType::Float { .. })
+        ) || texture.view_dimension() != wgpu::TextureViewDimension::D2
+        {
+            return Err(Error::UnsupportedTextureFormat(texture.format()));
+        }
+        let mask_size = width as u64 * height as u64 * 4;
+        context.check_buffer_size(mask_size)?;
+        context.check_binding_size(mask_size, true)?;
+
+        let device = &context.device;
+        let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Magic Wand Params"),
+            contents: bytemuck::bytes_of(&Params {
+                seed,
+                size: [width, height],
+                tolerance: options.tolerance.max(0.0),
+                flags: if options.diagonal { DIAGONAL } else { 0 },
+                ..Default::default()
+            }),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let mask = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Magic Wand Mask"),
+            size: mask_size,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let changed = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Magic Wand Changed"),
+            size: 4,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let output = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Selection Mask"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R32Float,
+            view_formats: &[],
+            usage: wgpu::TextureUsages::STORAGE_BINDING
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+        });
+        let output = Texture::from_wgpu(context, output);
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Magic Wand Bind Group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: mask.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: changed.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::TextureView(&output.view),
+                },
+            ],
+        });
+        let dispatch = |encoder: &mut wgpu::CommandEncoder,
+                        pipeline: &wgpu::ComputePipeline,
+                        workgroup_size: u32,
+                        count: u32| {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Magic Wand"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            for _ in 0..count {
+                pass.dispatch_workgroups(
+                    width.div_ceil(workgroup_size),
+                    height.div_ceil(workgroup_size),
+                    1,
+                );
+            }
+        };
+        let encoder = || {
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Magic Wand Encoder"),
+            })
+        };
+
+        context.diagnostics.record_pass(|| {
+            let pipeline = if options.contiguous {
+                "grow"
+            } else {
+                "select_similar"
+            };
+            PassRecord::new(
+                "Magic Wand",
+                pipeline,
+                vec![
+                    describe_texture("texture", &texture.texture),
+                    describe_buffer("mask", &mask),
+                    describe_texture("output", &output.texture),
+                ],
+            )
+        });
+        let mut command_encoder = encoder();
+        if options.contiguous {
+            dispatch(&mut command_encoder, &self.select_seed, WORKGROUP_SIZE, 1);
+            loop {
+                command_encoder.clear_buffer(&changed, 0, None);
+                dispatch(&mut command_encoder, &self.grow, TILE_SIZE, GROW_DISPATCHES);
+                context.submit(command_encoder);
+                command_encoder = encoder();
+                if context.read_buffer(&changed)? == [0; 4] {
+                    break;
+                }
+            }
+        } else {
+            dispatch(
+                &mut command_encoder,
+                &self.select_similar,
+                WORKGROUP_SIZE,
+                1,
+            );
+        }
+        dispatch(&mut command_encoder, &self.store, WORKGROUP_SIZE, 1);
+        context.submit(command_encoder);
+
+        Ok(output)
+    }
+}
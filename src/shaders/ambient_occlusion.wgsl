@@ -0,0 +1,40 @@
+struct Params {
+    size: vec2<u32>,
+    sample_index: u32,
+    // Bit 0 set for cosine-weighted rays.
+    flags: u32,
+    distance: f32,
+    bias: f32,
+    _padding: vec2<u32>,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+// World positions of the texels, with w 1 where a triangle covers them.
+@group(0) @binding(1) var<storage, read> positions: array<vec4<f32>>;
+@group(0) @binding(2) var<storage, read> normals: array<vec4<f32>>;
+@group(0) @binding(3) var<storage, read_write> accumulation: array<vec4<f32>>;
+
+const COSINE_WEIGHTED: u32 = 1u;
+
+@compute @workgroup_size(8, 8)
+fn occlusion(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id.xy >= params.size) {
+        return;
+    }
+    let index = id.y * params.size.x + id.x;
+    let texel = positions[index];
+    if texel.w == 0.0 {
+        return;
+    }
+    var seed = hash(index ^ hash(params.sample_index));
+    let normal = normals[index].xyz;
+    var direction: vec3<f32>;
+    if (params.flags & COSINE_WEIGHTED) != 0u {
+        direction = sample_hemisphere(normal, &seed);
+    } else {
+        direction = sample_hemisphere_uniform(normal, &seed);
+    }
+    let hit = trace_ray(texel.xyz, direction, params.distance, true);
+    let visible = select(0.0, 1.0, hit.t >= params.distance);
+    accumulation[index] += vec4<f32>(vec3<f32>(visible), 0.0);
+}
@@ -0,0 +1,48 @@
+// Colormaps and sRGB decoding shared by the debug views, prepended to
+// their shaders. `map` takes the values of `Colormap::index`.
+const GRAYSCALE: u32 = 0u;
+const TURBO: u32 = 1u;
+const VIRIDIS: u32 = 2u;
+
+fn to_linear(color: vec3<f32>) -> vec3<f32> {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, color <= vec3<f32>(0.04045));
+}
+
+// Polynomial fits of the Turbo and Viridis colormaps.
+fn turbo(t: f32) -> vec3<f32> {
+    let v4 = vec4<f32>(1.0, t, t * t, t * t * t);
+    let v2 = v4.zw * v4.z;
+    return vec3<f32>(
+        dot(v4, vec4<f32>(0.13572138, 4.61539260, -42.66032258, 132.13108234)) + dot(v2, vec2<f32>(-152.94239396, 59.28637943)),
+        dot(v4, vec4<f32>(0.09140261, 2.19418839, 4.84296658, -14.18503333)) + dot(v2, vec2<f32>(4.27729857, 2.82956604)),
+        dot(v4, vec4<f32>(0.10667330, 12.64194608, -60.58204836, 110.36276771)) + dot(v2, vec2<f32>(-89.90310912, 27.34824973)),
+    );
+}
+
+fn viridis(t: f32) -> vec3<f32> {
+    let c0 = vec3<f32>(0.2777273272234177, 0.005407344544966578, 0.3340998053353061);
+    let c1 = vec3<f32>(0.1050930431085774, 1.404613529898575, 1.384590162594685);
+    let c2 = vec3<f32>(-0.3308618287255563, 0.214847559468213, 0.09509516302823659);
+    let c3 = vec3<f32>(-4.634230498983486, -5.799100973351585, -19.33244095627987);
+    let c4 = vec3<f32>(6.228269936347081, 14.17993336680509, 56.69055260068105);
+    let c5 = vec3<f32>(4.776384997670288, -13.74514537774601, -65.35303263337234);
+    let c6 = vec3<f32>(-5.435455855934631, 4.645852612178535, 26.3124352495832);
+    return c0 + t * (c1 + t * (c2 + t * (c3 + t * (c4 + t * (c5 + t * c6)))));
+}
+
+fn colormap(map: u32, t: f32) -> vec3<f32> {
+    let value = clamp(t, 0.0, 1.0);
+    switch map {
+        case TURBO: {
+            return clamp(turbo(value), vec3<f32>(0.0), vec3<f32>(1.0));
+        }
+        case VIRIDIS: {
+            return clamp(viridis(value), vec3<f32>(0.0), vec3<f32>(1.0));
+        }
+        default: {
+            return vec3<f32>(value);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+// Prefixed at runtime with `ATOMIC_U64_WGSL` and `atomic_u64_wgsl("sums")`.
+
+const PERCEPTUAL: u32 = 1u;
+const DIFFERENCE_IMAGE: u32 = 2u;
+// Fixed-point scales of the summed errors and delta E values.
+const ERROR_SCALE: f32 = 65536.0;
+const DELTA_E_SCALE: f32 = 256.0;
+
+struct Params {
+    size: vec2<u32>,
+    threshold: f32,
+    difference_gain: f32,
+    flags: u32,
+}
+
+struct Stats {
+    // Bits of non-negative floats, which order like the floats.
+    max_error: atomic<u32>,
+    max_delta_e: atomic<u32>,
+    differing_pixels: atomic<u32>,
+    _padding: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var first: texture_2d<f32>;
+@group(0) @binding(2) var second: texture_2d<f32>;
+@group(0) @binding(3) var<storage, read_write> stats: Stats;
+// Summed absolute errors, squared errors and delta E values.
+@group(0) @binding(4) var<storage, read_write> sums: array<AtomicU64>;
+@group(0) @binding(5) var difference: texture_storage_2d<rgba8unorm, write>;
+
+fn lab_f(t: f32) -> f32 {
+    if t > 0.008856 {
+        return pow(t, 1.0 / 3.0);
+    }
+    return 7.787 * t + 16.0 / 116.0;
+}
+
+// CIELAB of linear sRGB under D65.
+fn lab(rgb: vec3<f32>) -> vec3<f32> {
+    let c = clamp(rgb, vec3<f32>(0.0), vec3<f32>(1.0));
+    let x = dot(c, vec3<f32>(0.4124, 0.3576, 0.1805)) / 0.95047;
+    let y = dot(c, vec3<f32>(0.2126, 0.7152, 0.0722));
+    let z = dot(c, vec3<f32>(0.0193, 0.1192, 0.9505)) / 1.08883;
+    let f = vec3<f32>(lab_f(x), lab_f(y), lab_f(z));
+    return vec3<f32>(116.0 * f.y - 16.0, 500.0 * (f.x - f.y), 200.0 * (f.y - f.z));
+}
+
+fn add_fixed(index: u32, value: f32) {
+    sums_add(index, u32(min(value, 4294967295.0)));
+}
+
+@compute @workgroup_size(8, 8)
+fn compare(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id.xy >= params.size) {
+        return;
+    }
+    let a = textureLoad(first, id.xy, 0);
+    let b = textureLoad(second, id.xy, 0);
+    let error = abs(a - b);
+    let pixel_error = max(max(error.r, error.g), max(error.b, error.a));
+    atomicMax(&stats.max_error, bitcast<u32>(pixel_error));
+    if pixel_error > params.threshold {
+        atomicAdd(&stats.differing_pixels, 1u);
+    }
+    add_fixed(0u, dot(error, vec4<f32>(1.0)) * ERROR_SCALE);
+    add_fixed(1u, dot(error, error) * ERROR_SCALE);
+    if (params.flags & PERCEPTUAL) != 0u {
+        let delta_e = distance(lab(a.rgb), lab(b.rgb));
+        atomicMax(&stats.max_delta_e, bitcast<u32>(delta_e));
+        add_fixed(2u, delta_e * DELTA_E_SCALE);
+    }
+    if (params.flags & DIFFERENCE_IMAGE) != 0u {
+        textureStore(difference, id.xy, vec4<f32>(error.rgb * params.difference_gain, 1.0));
+    }
+}
@@ -0,0 +1,59 @@
+const PI: f32 = 3.14159265358979;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@group(0) @binding(0) var<uniform> face: u32;
+// Bound as unfilterable float and filtered by hand, so that 32-bit float
+// panoramas work without the filterable float feature.
+@group(0) @binding(1) var equirectangular: texture_2d<f32>;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    // A single triangle covering the whole viewport.
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+/// Direction through `uv` (top-left origin) of a cube face in layer order
+/// +X, -X, +Y, -Y, +Z, -Z.
+fn face_direction(face: u32, uv: vec2<f32>) -> vec3<f32> {
+    let a = uv * 2.0 - 1.0;
+    switch face {
+        case 0u: { return normalize(vec3<f32>(1.0, -a.y, -a.x)); }
+        case 1u: { return normalize(vec3<f32>(-1.0, -a.y, a.x)); }
+        case 2u: { return normalize(vec3<f32>(a.x, 1.0, a.y)); }
+        case 3u: { return normalize(vec3<f32>(a.x, -1.0, -a.y)); }
+        case 4u: { return normalize(vec3<f32>(a.x, -a.y, 1.0)); }
+        default: { return normalize(vec3<f32>(-a.x, -a.y, -1.0)); }
+    }
+}
+
+fn texel(coordinates: vec2<i32>, size: vec2<i32>) -> vec4<f32> {
+    // Longitude wraps around, latitude stops at the poles.
+    let x = ((coordinates.x % size.x) + size.x) % size.x;
+    let y = clamp(coordinates.y, 0, size.y - 1);
+    return textureLoad(equirectangular, vec2<i32>(x, y), 0);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let direction = face_direction(face, in.uv);
+    // -Z is the center of the panorama, +Y its top row.
+    let longitude = atan2(direction.x, -direction.z);
+    let latitude = acos(clamp(direction.y, -1.0, 1.0));
+    let uv = vec2<f32>(longitude / (2.0 * PI) + 0.5, latitude / PI);
+
+    let size = vec2<i32>(textureDimensions(equirectangular));
+    let position = uv * vec2<f32>(size) - 0.5;
+    let base = vec2<i32>(floor(position));
+    let f = position - floor(position);
+    let top = mix(texel(base, size), texel(base + vec2<i32>(1, 0), size), f.x);
+    let bottom = mix(texel(base + vec2<i32>(0, 1), size), texel(base + vec2<i32>(1, 1), size), f.x);
+    return mix(top, bottom, f.y);
+}
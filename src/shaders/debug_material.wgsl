@@ -0,0 +1,116 @@
+const SRGB_TARGET: u32 = 1u;
+const HAS_TEXTURE: u32 = 2u;
+const UV_CHECKER: u32 = 0u;
+const TEXTURE_RESOLUTION: u32 = 1u;
+
+struct Params {
+    view_projection: mat4x4<f32>,
+    model: mat4x4<f32>,
+    normal_matrix: mat4x4<f32>,
+    // The view-projection of the last frame, for motion vectors.
+    previous_view_projection: mat4x4<f32>,
+    // The base color factor of the object's own material.
+    base_color: vec4<f32>,
+    mode: u32,
+    colormap: u32,
+    flags: u32,
+    // Checker squares per UV unit, or the position of the texture's
+    // resolution in the colormap.
+    value: f32,
+    object_id: f32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var material_texture: texture_2d<f32>;
+@group(0) @binding(2) var material_sampler: sampler;
+
+struct VertexOutput {
+    // Invariant, so that the AOV passes after the first one can depth test
+    // for equality.
+    @builtin(position) @invariant position: vec4<f32>,
+    @location(0) normal: vec3<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) clip: vec4<f32>,
+    @location(3) previous_clip: vec4<f32>,
+}
+
+// The beauty at location 0 and the `Aov`s at 1 + their index, of which
+// only those with a target are written.
+struct AovOutput {
+    @location(0) color: vec4<f32>,
+    @location(1) albedo: vec4<f32>,
+    @location(2) normal: vec4<f32>,
+    @location(3) depth: vec4<f32>,
+    @location(4) object_id: vec4<f32>,
+    @location(5) motion: vec4<f32>,
+}
+
+@vertex
+fn vs_mesh(
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+) -> VertexOutput {
+    let world = params.model * vec4<f32>(position, 1.0);
+    var out: VertexOutput;
+    out.position = params.view_projection * world;
+    out.normal = (params.normal_matrix * vec4<f32>(normal, 0.0)).xyz;
+    out.uv = uv;
+    out.clip = out.position;
+    out.previous_clip = params.previous_view_projection * world;
+    return out;
+}
+
+// The debug material's display color, before decoding for sRGB targets.
+fn material_color(in: VertexOutput, texel: vec3<f32>) -> vec3<f32> {
+    var color: vec3<f32>;
+    if params.mode == UV_CHECKER {
+        let cell = vec2<i32>(floor(in.uv * params.value));
+        let checker = select(0.9, 0.35, ((cell.x + cell.y) & 1) != 0);
+        // Red grows along U and green along V within each UV unit, which
+        // shows flipped and rotated islands.
+        let tint = vec3<f32>(0.5 + 0.5 * fract(in.uv.x), 0.5 + 0.5 * fract(in.uv.y), 0.75);
+        color = checker * tint;
+    } else {
+        if (params.flags & HAS_TEXTURE) != 0u {
+            let luminance = dot(texel, vec3<f32>(0.2126, 0.7152, 0.0722));
+            color = colormap(params.colormap, params.value) * (0.35 + 0.65 * luminance);
+        } else {
+            color = vec3<f32>(0.5);
+        }
+    }
+    // Shading that keeps the shape readable without lights.
+    let normal = normalize(in.normal);
+    color *= 0.7 + 0.3 * (0.5 + 0.5 * normal.y);
+    if (params.flags & SRGB_TARGET) != 0u {
+        color = to_linear(color);
+    }
+    return color;
+}
+
+@fragment
+fn fs_material(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Sampled before branching, which needs uniform control flow.
+    let texel = textureSample(material_texture, material_sampler, in.uv).rgb;
+    return vec4<f32>(material_color(in, texel), 1.0);
+}
+
+fn screen_uv(clip: vec4<f32>) -> vec2<f32> {
+    return clip.xy / clip.w * vec2<f32>(0.5, -0.5) + 0.5;
+}
+
+// The beauty with the surface's albedo (the base color of its own
+// material), world normal, view depth, object ID and screen-space motion
+// since the last frame in UV units.
+@fragment
+fn fs_aovs(in: VertexOutput) -> AovOutput {
+    let texel = textureSample(material_texture, material_sampler, in.uv);
+    var out: AovOutput;
+    out.color = vec4<f32>(material_color(in, texel.rgb), 1.0);
+    out.albedo = params.base_color * select(vec4<f32>(1.0), texel, (params.flags & HAS_TEXTURE) != 0u);
+    out.normal = vec4<f32>(normalize(in.normal), 1.0);
+    out.depth = vec4<f32>(in.clip.w, 0.0, 0.0, 1.0);
+    out.object_id = vec4<f32>(params.object_id, 0.0, 0.0, 1.0);
+    out.motion = vec4<f32>(screen_uv(in.clip) - screen_uv(in.previous_clip), 0.0, 1.0);
+    return out;
+}
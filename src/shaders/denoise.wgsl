@@ -0,0 +1,124 @@
+const REVERSE_Z: u32 = 1u;
+const INFINITE_FAR: u32 = 2u;
+const LOGARITHMIC: u32 = 4u;
+
+// Relative view distance change beyond which the history of a pixel is
+// taken to show another surface.
+const DISOCCLUSION_THRESHOLD: f32 = 0.1;
+
+struct Params {
+    near: f32,
+    far: f32,
+    flags: u32,
+    step: i32,
+    luminance_sensitivity: f32,
+    normal_power: f32,
+    depth_sensitivity: f32,
+    temporal_alpha: f32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var color: texture_2d<f32>;
+@group(0) @binding(2) var normal: texture_2d<f32>;
+// Bound as unfilterable float, which unlike depth textures GLSL can load.
+@group(0) @binding(3) var depth: texture_2d<f32>;
+@group(0) @binding(4) var motion: texture_2d<f32>;
+@group(0) @binding(5) var history: texture_2d<f32>;
+@group(0) @binding(6) var history_depth: texture_2d<f32>;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+}
+
+/// View distance of a depth value, as `DepthRange::linearize`, kept finite
+/// so that distances at an infinite far plane compare equal.
+fn linearize(value: f32) -> f32 {
+    let near = params.near;
+    let far = params.far;
+    let reverse = (params.flags & REVERSE_Z) != 0u;
+    var distance: f32;
+    if (params.flags & LOGARITHMIC) != 0u {
+        distance = exp2(select(value, 1.0 - value, reverse) * log2(far + 1.0)) - 1.0;
+    } else if (params.flags & INFINITE_FAR) != 0u {
+        distance = select(near / max(1.0 - value, 1e-30), near / max(value, 1e-30), reverse);
+    } else if reverse {
+        distance = near * far / (near + value * (far - near));
+    } else {
+        distance = near * far / (far - value * (far - near));
+    }
+    return min(distance, 1e30);
+}
+
+fn luminance(value: vec4<f32>) -> f32 {
+    return dot(value.rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+// Pixels without geometry have zero normals, which only match each other.
+fn normal_weight(a: vec3<f32>, b: vec3<f32>) -> f32 {
+    let length_a = length(a);
+    let length_b = length(b);
+    if length_a < 1e-3 || length_b < 1e-3 {
+        return select(0.0, 1.0, length_a < 1e-3 && length_b < 1e-3);
+    }
+    return pow(max(dot(a / length_a, b / length_b), 0.0), params.normal_power);
+}
+
+// Blends the noisy color with the history reprojected along the motion
+// vectors, restarting the accumulation where the history falls off screen
+// or shows another surface.
+@fragment
+fn fs_temporal(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let texel = vec2<i32>(position.xy);
+    let size = vec2<f32>(textureDimensions(color));
+    let current = textureLoad(color, texel, 0);
+    let previous_uv = position.xy / size - textureLoad(motion, texel, 0).xy;
+    if any(previous_uv < vec2<f32>(0.0)) || any(previous_uv >= vec2<f32>(1.0)) {
+        return current;
+    }
+    let previous_texel = vec2<i32>(previous_uv * size);
+    let distance = linearize(textureLoad(depth, texel, 0).r);
+    let previous_distance = linearize(textureLoad(history_depth, previous_texel, 0).r);
+    if abs(previous_distance - distance) / max(distance, 1e-6) > DISOCCLUSION_THRESHOLD {
+        return current;
+    }
+    return mix(textureLoad(history, previous_texel, 0), current, params.temporal_alpha);
+}
+
+// One à-trous iteration: a 5x5 B3 spline kernel with holes of `step`
+// pixels, each tap weighted down by its luminance, normal and view
+// distance differences from the center, so that edges stay sharp.
+@fragment
+fn fs_atrous(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let texel = vec2<i32>(position.xy);
+    let size = vec2<i32>(textureDimensions(color));
+    let center = textureLoad(color, texel, 0);
+    let center_luminance = luminance(center);
+    let center_normal = textureLoad(normal, texel, 0).xyz;
+    let center_distance = linearize(textureLoad(depth, texel, 0).r);
+    var kernel = array<f32, 3>(0.375, 0.25, 0.0625);
+
+    var sum = vec4<f32>(0.0);
+    var total = 0.0;
+    for (var y = -2; y <= 2; y++) {
+        for (var x = -2; x <= 2; x++) {
+            let tap = texel + vec2<i32>(x, y) * params.step;
+            if any(tap < vec2<i32>(0)) || any(tap >= size) {
+                continue;
+            }
+            let value = textureLoad(color, tap, 0);
+            let distance = linearize(textureLoad(depth, tap, 0).r);
+            let relative = abs(distance - center_distance) / max(center_distance, 1e-6);
+            var weight = kernel[abs(x)] * kernel[abs(y)];
+            if x != 0 || y != 0 {
+                weight *= exp(-params.luminance_sensitivity * abs(luminance(value) - center_luminance))
+                    * normal_weight(textureLoad(normal, tap, 0).xyz, center_normal)
+                    * exp(-params.depth_sensitivity * relative / f32(params.step));
+            }
+            sum += weight * value;
+            total += weight;
+        }
+    }
+    return sum / total;
+}
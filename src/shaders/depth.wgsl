@@ -0,0 +1,101 @@
+const SRGB_TARGET: u32 = 1u;
+const REVERSE_Z: u32 = 2u;
+const INFINITE_FAR: u32 = 4u;
+const LOGARITHMIC: u32 = 8u;
+
+struct Params {
+    near: f32,
+    far: f32,
+    visible_min: f32,
+    visible_max: f32,
+    colormap: u32,
+    flags: u32,
+    pixel: vec2<u32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+// Bound as unfilterable float, which unlike depth textures GLSL can load.
+@group(0) @binding(1) var depth: texture_2d<f32>;
+@group(0) @binding(2) var normals: texture_2d<f32>;
+@group(0) @binding(3) var stencil: texture_2d<u32>;
+@group(0) @binding(4) var<storage, read_write> readback: array<f32>;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    // A single triangle covering the whole viewport.
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+fn texel(uv: vec2<f32>, size: vec2<u32>) -> vec2<u32> {
+    return min(vec2<u32>(uv * vec2<f32>(size)), size - 1u);
+}
+
+/// View distance of a depth value, as `DepthRange::linearize`.
+fn linearize(value: f32) -> f32 {
+    let near = params.near;
+    let far = params.far;
+    let reverse = (params.flags & REVERSE_Z) != 0u;
+    if (params.flags & LOGARITHMIC) != 0u {
+        return exp2(select(value, 1.0 - value, reverse) * log2(far + 1.0)) - 1.0;
+    }
+    if (params.flags & INFINITE_FAR) != 0u {
+        return select(near / (1.0 - value), near / value, reverse);
+    }
+    if reverse {
+        return near * far / (near + value * (far - near));
+    }
+    return near * far / (far - value * (far - near));
+}
+
+/// Encodes a display color for the target, which decodes sRGB on write.
+fn output(color: vec3<f32>) -> vec4<f32> {
+    if (params.flags & SRGB_TARGET) != 0u {
+        return vec4<f32>(to_linear(color), 1.0);
+    }
+    return vec4<f32>(color, 1.0);
+}
+
+@fragment
+fn fs_depth(in: VertexOutput) -> @location(0) vec4<f32> {
+    let value = textureLoad(depth, texel(in.uv, textureDimensions(depth)), 0).r;
+    let distance = linearize(value);
+    let t = (distance - params.visible_min) / max(params.visible_max - params.visible_min, 1e-6);
+    return output(colormap(params.colormap, t));
+}
+
+@fragment
+fn fs_normals(in: VertexOutput) -> @location(0) vec4<f32> {
+    let normal = textureLoad(normals, texel(in.uv, textureDimensions(normals)), 0).xyz;
+    let magnitude = length(normal);
+    if magnitude == 0.0 {
+        return output(vec3<f32>(0.0));
+    }
+    return output(normal / magnitude * 0.5 + 0.5);
+}
+
+@fragment
+fn fs_stencil(in: VertexOutput) -> @location(0) vec4<f32> {
+    let value = textureLoad(stencil, texel(in.uv, textureDimensions(stencil)), 0).r;
+    if value == 0u {
+        return output(vec3<f32>(0.0));
+    }
+    // Golden-ratio hues keep neighboring reference values apart.
+    let hue = fract(f32(value) * 0.618034);
+    let h = hue * 6.0;
+    let color = clamp(vec3<f32>(abs(h - 3.0) - 1.0, 2.0 - abs(h - 2.0), 2.0 - abs(h - 4.0)), vec3<f32>(0.0), vec3<f32>(1.0));
+    return output(color);
+}
+
+@compute @workgroup_size(1)
+fn read_depth() {
+    readback[0] = textureLoad(depth, params.pixel, 0).r;
+}
@@ -0,0 +1,136 @@
+struct Params {
+    len: u32,
+    stride: u32,
+    element_stride: u32,
+    batch_stride: u32,
+    batch: u32,
+    sign: f32,
+    scale: f32,
+    channel: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> src: array<vec2<f32>>;
+@group(0) @binding(2) var<storage, read_write> dst: array<vec2<f32>>;
+
+const TAU: f32 = 6.283185307179586;
+
+fn complex_mul(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
+}
+
+fn twiddle(k: u32, n: u32) -> vec2<f32> {
+    let angle = params.sign * TAU * f32(k) / f32(n);
+    return vec2<f32>(cos(angle), sin(angle));
+}
+
+fn address(batch: u32, index: u32) -> u32 {
+    return batch * params.batch_stride + index * params.element_stride;
+}
+
+// Stockham autosort stages: `stride` is the length of the sub-transforms
+// already combined by the previous stages.
+
+@compute @workgroup_size(64)
+fn radix2(@builtin(global_invocation_id) id: vec3<u32>) {
+    let half = params.len / 2u;
+    let j = id.x;
+    let b = id.y;
+    if (j >= half || b >= params.batch) {
+        return;
+    }
+
+    let ns = params.stride;
+    let k = j % ns;
+    let v0 = src[address(b, j)];
+    let v1 = complex_mul(src[address(b, j + half)], twiddle(k, ns * 2u));
+
+    let out = (j / ns) * ns * 2u + k;
+    dst[address(b, out)] = (v0 + v1) * params.scale;
+    dst[address(b, out + ns)] = (v0 - v1) * params.scale;
+}
+
+@compute @workgroup_size(64)
+fn radix4(@builtin(global_invocation_id) id: vec3<u32>) {
+    let quarter = params.len / 4u;
+    let j = id.x;
+    let b = id.y;
+    if (j >= quarter || b >= params.batch) {
+        return;
+    }
+
+    let ns = params.stride;
+    let k = j % ns;
+    let v0 = src[address(b, j)];
+    let v1 = complex_mul(src[address(b, j + quarter)], twiddle(k, ns * 4u));
+    let v2 = complex_mul(src[address(b, j + 2u * quarter)], twiddle(2u * k, ns * 4u));
+    let v3 = complex_mul(src[address(b, j + 3u * quarter)], twiddle(3u * k, ns * 4u));
+
+    let a0 = v0 + v2;
+    let a1 = v0 - v2;
+    let a2 = v1 + v3;
+    let a3 = complex_mul(v1 - v3, vec2<f32>(0.0, params.sign));
+
+    let out = (j / ns) * ns * 4u + k;
+    dst[address(b, out)] = (a0 + a2) * params.scale;
+    dst[address(b, out + ns)] = (a1 + a3) * params.scale;
+    dst[address(b, out + 2u * ns)] = (a0 - a2) * params.scale;
+    dst[address(b, out + 3u * ns)] = (a1 - a3) * params.scale;
+}
+
+// Conversions between real data and the interleaved complex layout.
+
+@group(0) @binding(3) var<storage, read_write> real: array<f32>;
+
+fn linear_index(id: vec3<u32>, groups: vec3<u32>) -> u32 {
+    return id.x + id.y * groups.x * 64u;
+}
+
+@compute @workgroup_size(64)
+fn pack_real(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+) {
+    let i = linear_index(id, groups);
+    if (i >= params.len * params.batch) {
+        return;
+    }
+    dst[i] = vec2<f32>(real[i], 0.0);
+}
+
+@compute @workgroup_size(64)
+fn unpack_real(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+) {
+    let i = linear_index(id, groups);
+    if (i >= params.len * params.batch) {
+        return;
+    }
+    real[i] = src[i].x;
+}
+
+// Conversions between textures and the complex layout of a 2D transform.
+
+@group(0) @binding(4) var input_texture: texture_2d<f32>;
+@group(0) @binding(5) var output_texture: texture_storage_2d<rgba32float, write>;
+
+@compute @workgroup_size(8, 8)
+fn load_texture(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(input_texture);
+    if (id.x >= size.x || id.y >= size.y) {
+        return;
+    }
+    let texel = textureLoad(input_texture, id.xy, 0);
+    dst[id.y * size.x + id.x] = vec2<f32>(texel[params.channel], 0.0);
+}
+
+@compute @workgroup_size(8, 8)
+fn store_texture(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(output_texture);
+    if (id.x >= size.x || id.y >= size.y) {
+        return;
+    }
+    let value = src[id.y * size.x + id.x];
+    textureStore(output_texture, id.xy, vec4<f32>(value, length(value), 1.0));
+}
@@ -0,0 +1,113 @@
+const THREADS: u32 = 64u;
+const PI: f32 = 3.14159265359;
+
+struct Params {
+    size: vec2<u32>,
+    flow_size: vec2<u32>,
+    block_size: u32,
+    search_radius: u32,
+    max_magnitude: f32,
+    _padding: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var previous: texture_2d<f32>;
+@group(0) @binding(2) var current: texture_2d<f32>;
+@group(0) @binding(3) var flow_output: texture_storage_2d<rgba32float, write>;
+@group(0) @binding(4) var difference_output: texture_storage_2d<r32float, write>;
+@group(0) @binding(5) var flow: texture_2d<f32>;
+@group(0) @binding(6) var visualization: texture_storage_2d<rgba8unorm, write>;
+
+fn luminance(color: vec4<f32>) -> f32 {
+    return dot(color.rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+fn clamp_pixel(pixel: vec2<i32>) -> vec2<i32> {
+    return clamp(pixel, vec2<i32>(0), vec2<i32>(params.size) - 1);
+}
+
+@compute @workgroup_size(8, 8)
+fn difference(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id.xy >= params.size) {
+        return;
+    }
+    let value = abs(luminance(textureLoad(current, id.xy, 0)) - luminance(textureLoad(previous, id.xy, 0)));
+    textureStore(difference_output, id.xy, vec4<f32>(value, 0.0, 0.0, 1.0));
+}
+
+var<workgroup> block: array<f32, 256>;
+var<workgroup> best_costs: array<f32, 64>;
+var<workgroup> best_offsets: array<vec2<i32>, 64>;
+
+fn better(cost: f32, offset: vec2<i32>, best_cost: f32, best_offset: vec2<i32>) -> bool {
+    // Ties go to the shorter offset, so flat regions report no motion.
+    return cost < best_cost || (cost == best_cost && dot(offset, offset) < dot(best_offset, best_offset));
+}
+
+/// One workgroup per block of `current`: each invocation scores a share of
+/// the offsets into `previous` by the sum of absolute luminance
+/// differences, and the workgroup keeps the best.
+@compute @workgroup_size(8, 8)
+fn estimate(
+    @builtin(workgroup_id) group: vec3<u32>,
+    @builtin(local_invocation_index) thread: u32,
+) {
+    let size = params.block_size;
+    let origin = vec2<i32>(group.xy * size);
+    for (var i = thread; i < size * size; i += THREADS) {
+        let pixel = clamp_pixel(origin + vec2<i32>(i32(i % size), i32(i / size)));
+        block[i] = luminance(textureLoad(current, pixel, 0));
+    }
+    workgroupBarrier();
+
+    let radius = i32(params.search_radius);
+    let side = u32(2 * radius + 1);
+    var best_cost = 3.4e38;
+    var best_offset = vec2<i32>(0);
+    for (var candidate = thread; candidate < side * side; candidate += THREADS) {
+        let offset = vec2<i32>(i32(candidate % side), i32(candidate / side)) - radius;
+        var cost = 0.0;
+        for (var i = 0u; i < size * size; i++) {
+            let pixel = clamp_pixel(origin + offset + vec2<i32>(i32(i % size), i32(i / size)));
+            cost += abs(block[i] - luminance(textureLoad(previous, pixel, 0)));
+        }
+        if better(cost, offset, best_cost, best_offset) {
+            best_cost = cost;
+            best_offset = offset;
+        }
+    }
+    best_costs[thread] = best_cost;
+    best_offsets[thread] = best_offset;
+
+    for (var stride = THREADS / 2u; stride > 0u; stride /= 2u) {
+        workgroupBarrier();
+        if thread < stride && better(best_costs[thread + stride], best_offsets[thread + stride], best_costs[thread], best_offsets[thread]) {
+            best_costs[thread] = best_costs[thread + stride];
+            best_offsets[thread] = best_offsets[thread + stride];
+        }
+    }
+    if thread == 0u {
+        let error = best_costs[0] / f32(size * size);
+        textureStore(flow_output, group.xy, vec4<f32>(vec2<f32>(best_offsets[0]), error, 1.0));
+    }
+}
+
+fn hue_to_rgb(hue: f32) -> vec3<f32> {
+    let h = fract(hue) * 6.0;
+    return clamp(vec3<f32>(abs(h - 3.0) - 1.0, 2.0 - abs(h - 2.0), 2.0 - abs(h - 4.0)), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+/// Direction as hue and magnitude as saturation over white.
+@compute @workgroup_size(8, 8)
+fn visualize(@builtin(global_invocation_id) id: vec3<u32>) {
+    let output_size = textureDimensions(visualization);
+    if any(id.xy >= output_size) {
+        return;
+    }
+    let texel = min(id.xy * params.flow_size / output_size, params.flow_size - 1u);
+    let motion = textureLoad(flow, texel, 0).xy;
+    let strength = clamp(length(motion) / params.max_magnitude, 0.0, 1.0);
+    let hue = atan2(motion.y, motion.x) / (2.0 * PI) + 0.5;
+    let color = mix(vec3<f32>(1.0), hue_to_rgb(hue), strength);
+    textureStore(visualization, id.xy, vec4<f32>(color, 1.0));
+}
@@ -0,0 +1,90 @@
+const REVERSE_Z: u32 = 1u;
+const INFINITE_FAR: u32 = 2u;
+const LOGARITHMIC: u32 = 4u;
+
+struct Params {
+    near: f32,
+    far: f32,
+    flags: u32,
+    depth_sensitivity: f32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+// Bound as unfilterable float, which unlike depth textures GLSL can load.
+@group(0) @binding(1) var full_depth: texture_2d<f32>;
+@group(0) @binding(2) var half_depth: texture_2d<f32>;
+@group(0) @binding(3) var half_color: texture_2d<f32>;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+}
+
+/// View distance of a depth value, as `DepthRange::linearize`, kept finite
+/// so that distances at an infinite far plane compare equal.
+fn linearize(value: f32) -> f32 {
+    let near = params.near;
+    let far = params.far;
+    let reverse = (params.flags & REVERSE_Z) != 0u;
+    var distance: f32;
+    if (params.flags & LOGARITHMIC) != 0u {
+        distance = exp2(select(value, 1.0 - value, reverse) * log2(far + 1.0)) - 1.0;
+    } else if (params.flags & INFINITE_FAR) != 0u {
+        distance = select(near / max(1.0 - value, 1e-30), near / max(value, 1e-30), reverse);
+    } else if reverse {
+        distance = near * far / (near + value * (far - near));
+    } else {
+        distance = near * far / (far - value * (far - near));
+    }
+    return min(distance, 1e30);
+}
+
+// The nearest view distance of each 2x2 block of full resolution depth.
+@fragment
+fn fs_downsample(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let size = vec2<i32>(textureDimensions(full_depth));
+    let base = vec2<i32>(position.xy) * 2;
+    var nearest = 1e30;
+    for (var i = 0; i < 4; i++) {
+        let texel = min(base + vec2<i32>(i & 1, i >> 1u), size - 1);
+        nearest = min(nearest, linearize(textureLoad(full_depth, texel, 0).r));
+    }
+    return vec4<f32>(nearest, 0.0, 0.0, 1.0);
+}
+
+// Bilinear upsampling of the four half resolution texels around the pixel,
+// each weighted down by how far its distance is from the pixel's, so that
+// effects do not bleed across depth edges. Falls back to the texel of the
+// closest distance where all of them lie across an edge.
+@fragment
+fn fs_upsample(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let distance = linearize(textureLoad(full_depth, vec2<i32>(position.xy), 0).r);
+    let half_size = vec2<i32>(textureDimensions(half_depth));
+    let coordinate = position.xy * 0.5 - 0.5;
+    let base = vec2<i32>(floor(coordinate));
+    let f = fract(coordinate);
+
+    var sum = vec4<f32>(0.0);
+    var total = 0.0;
+    var closest = vec4<f32>(0.0);
+    var closest_difference = 3.4e38;
+    for (var i = 0; i < 4; i++) {
+        let offset = vec2<i32>(i & 1, i >> 1u);
+        let texel = clamp(base + offset, vec2<i32>(0), half_size - 1);
+        let bilinear = select(1.0 - f.x, f.x, offset.x == 1) * select(1.0 - f.y, f.y, offset.y == 1);
+        let difference = abs(textureLoad(half_depth, texel, 0).r - distance) / max(distance, 1e-6);
+        let color = textureLoad(half_color, texel, 0);
+        let weight = max(bilinear, 1e-3) * exp(-params.depth_sensitivity * difference);
+        sum += weight * color;
+        total += weight;
+        if difference < closest_difference {
+            closest_difference = difference;
+            closest = color;
+        }
+    }
+    if total < 1e-4 {
+        return closest;
+    }
+    return sum / total;
+}
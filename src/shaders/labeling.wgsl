@@ -0,0 +1,166 @@
+// Prefixed at runtime with `ATOMIC_U64_WGSL` and `atomic_u64_wgsl("sums")`.
+
+const BACKGROUND: u32 = 0xffffffffu;
+const DIAGONAL: u32 = 1u;
+
+struct Params {
+    size: vec2<u32>,
+    threshold: f32,
+    flags: u32,
+}
+
+struct ComponentStats {
+    area: atomic<u32>,
+    root: atomic<u32>,
+    // Stored inverted so that zeroed stats start at the largest value.
+    inverted_min_x: atomic<u32>,
+    inverted_min_y: atomic<u32>,
+    max_x: atomic<u32>,
+    max_y: atomic<u32>,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var mask: texture_2d<f32>;
+@group(0) @binding(2) var<storage, read_write> parents: array<atomic<u32>>;
+@group(0) @binding(3) var<storage, read_write> slots: array<u32>;
+@group(0) @binding(4) var<storage, read_write> component_count: atomic<u32>;
+@group(0) @binding(5) var<storage, read_write> stats: array<ComponentStats>;
+@group(0) @binding(6) var<storage, read_write> sums: array<AtomicU64>;
+@group(0) @binding(7) var<storage, read> remap: array<u32>;
+@group(0) @binding(8) var labels: texture_storage_2d<r32uint, write>;
+
+fn find(start: u32) -> u32 {
+    var node = start;
+    var parent = atomicLoad(&parents[node]);
+    while parent != node {
+        node = parent;
+        parent = atomicLoad(&parents[node]);
+    }
+    return node;
+}
+
+/// Links the root of the larger index below the other, retrying when
+/// another invocation relinked it first.
+fn merge(first: u32, second: u32) {
+    var a = find(first);
+    var b = find(second);
+    loop {
+        if a == b {
+            return;
+        }
+        if a < b {
+            let swap = a;
+            a = b;
+            b = swap;
+        }
+        let old = atomicMin(&parents[a], b);
+        if old == a {
+            return;
+        }
+        a = find(old);
+        b = find(b);
+    }
+}
+
+fn foreground(x: i32, y: i32) -> bool {
+    if x < 0 || y < 0 || x >= i32(params.size.x) || y >= i32(params.size.y) {
+        return false;
+    }
+    return atomicLoad(&parents[u32(y) * params.size.x + u32(x)]) != BACKGROUND;
+}
+
+@compute @workgroup_size(8, 8)
+fn init(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id.xy >= params.size) {
+        return;
+    }
+    let index = id.y * params.size.x + id.x;
+    let value = textureLoad(mask, id.xy, 0).r;
+    atomicStore(&parents[index], select(BACKGROUND, index, value > params.threshold));
+}
+
+@compute @workgroup_size(8, 8)
+fn link(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id.xy >= params.size) {
+        return;
+    }
+    let x = i32(id.x);
+    let y = i32(id.y);
+    let index = id.y * params.size.x + id.x;
+    if !foreground(x, y) {
+        return;
+    }
+    // Earlier neighbors in scanline order; later ones link back to this.
+    if foreground(x - 1, y) {
+        merge(index, index - 1u);
+    }
+    if foreground(x, y - 1) {
+        merge(index, index - params.size.x);
+    }
+    if (params.flags & DIAGONAL) != 0u {
+        if foreground(x - 1, y - 1) {
+            merge(index, index - params.size.x - 1u);
+        }
+        if foreground(x + 1, y - 1) {
+            merge(index, index - params.size.x + 1u);
+        }
+    }
+}
+
+@compute @workgroup_size(8, 8)
+fn compress(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id.xy >= params.size) {
+        return;
+    }
+    let index = id.y * params.size.x + id.x;
+    if atomicLoad(&parents[index]) != BACKGROUND {
+        atomicStore(&parents[index], find(index));
+    }
+}
+
+@compute @workgroup_size(8, 8)
+fn enumerate(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id.xy >= params.size) {
+        return;
+    }
+    let index = id.y * params.size.x + id.x;
+    if atomicLoad(&parents[index]) == index {
+        slots[index] = atomicAdd(&component_count, 1u);
+    }
+}
+
+@compute @workgroup_size(8, 8)
+fn accumulate(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id.xy >= params.size) {
+        return;
+    }
+    let index = id.y * params.size.x + id.x;
+    let root = atomicLoad(&parents[index]);
+    if root == BACKGROUND {
+        return;
+    }
+    let slot = slots[root];
+    if root == index {
+        atomicStore(&stats[slot].root, index);
+    }
+    atomicAdd(&stats[slot].area, 1u);
+    atomicMax(&stats[slot].inverted_min_x, ~id.x);
+    atomicMax(&stats[slot].inverted_min_y, ~id.y);
+    atomicMax(&stats[slot].max_x, id.x);
+    atomicMax(&stats[slot].max_y, id.y);
+    sums_add(2u * slot, id.x);
+    sums_add(2u * slot + 1u, id.y);
+}
+
+@compute @workgroup_size(8, 8)
+fn relabel(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id.xy >= params.size) {
+        return;
+    }
+    let root = atomicLoad(&parents[id.y * params.size.x + id.x]);
+    var label = 0u;
+    if root != BACKGROUND {
+        label = remap[slots[root]];
+    }
+    textureStore(labels, id.xy, vec4<u32>(label, 0u, 0u, 0u));
+}
@@ -0,0 +1,96 @@
+struct Params {
+    size: vec2<u32>,
+    sample_index: u32,
+    bounces: u32,
+    light_count: u32,
+    bias: f32,
+    _padding: vec2<u32>,
+    sky: vec4<f32>,
+    albedo: vec4<f32>,
+}
+
+// A directional light if `position.w` is 0, with `position.xyz` the
+// direction the light travels in. `color.w` is a point light's range, 0 for
+// none.
+struct Light {
+    position: vec4<f32>,
+    color: vec4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+// World positions of the texels, with w 1 where a triangle covers them.
+@group(0) @binding(1) var<storage, read> positions: array<vec4<f32>>;
+@group(0) @binding(2) var<storage, read> normals: array<vec4<f32>>;
+@group(0) @binding(3) var<storage, read> lights: array<Light>;
+@group(0) @binding(4) var<storage, read_write> accumulation: array<vec4<f32>>;
+
+const PI: f32 = 3.14159265359;
+const MISS: f32 = 3.4e38;
+
+// Irradiance from the lights at `position`, with shadow rays.
+fn direct_light(position: vec3<f32>, normal: vec3<f32>) -> vec3<f32> {
+    var irradiance = vec3<f32>(0.0);
+    for (var i = 0u; i < params.light_count; i++) {
+        let light = lights[i];
+        var direction = -normalize(light.position.xyz);
+        var distance = MISS;
+        var color = light.color.rgb;
+        if light.position.w != 0.0 {
+            let offset = light.position.xyz - position;
+            distance = length(offset);
+            direction = offset / distance;
+            color /= max(distance * distance, 1e-4);
+            if light.color.w > 0.0 {
+                let window = clamp(1.0 - pow(distance / light.color.w, 4.0), 0.0, 1.0);
+                color *= window * window;
+            }
+        }
+        let cosine = dot(normal, direction);
+        if cosine <= 0.0 {
+            continue;
+        }
+        let t_max = distance - params.bias;
+        if trace_ray(position, direction, t_max, true).t < t_max {
+            continue;
+        }
+        irradiance += color * cosine;
+    }
+    return irradiance;
+}
+
+// One path per covered texel: the direct light, then a cosine-weighted
+// walk that picks up the direct light at every diffuse bounce and the sky
+// when it escapes. Sums irradiance.
+@compute @workgroup_size(8, 8)
+fn trace(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id.xy >= params.size) {
+        return;
+    }
+    let index = id.y * params.size.x + id.x;
+    let texel = positions[index];
+    if texel.w == 0.0 {
+        return;
+    }
+    var seed = hash(index ^ hash(params.sample_index));
+    var origin = texel.xyz;
+    var normal = normals[index].xyz;
+    var irradiance = direct_light(origin, normal);
+    var throughput = vec3<f32>(1.0);
+    for (var bounce = 0u; bounce <= params.bounces; bounce++) {
+        let direction = sample_hemisphere(normal, &seed);
+        let hit = trace_ray(origin, direction, MISS, false);
+        if hit.t >= MISS {
+            irradiance += throughput * params.sky.rgb * PI;
+            break;
+        }
+        if bounce == params.bounces {
+            break;
+        }
+        normal = triangle_normal(hit.triangle);
+        normal = select(normal, -normal, dot(normal, direction) > 0.0);
+        origin += direction * hit.t + normal * params.bias;
+        throughput *= params.albedo.rgb;
+        irradiance += throughput * direct_light(origin, normal);
+    }
+    accumulation[index] += vec4<f32>(irradiance, 0.0);
+}
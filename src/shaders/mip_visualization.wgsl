@@ -0,0 +1,52 @@
+const SRGB_TARGET: u32 = 1u;
+const MIP_LEVEL: u32 = 0u;
+const ANISOTROPY: u32 = 1u;
+
+struct Params {
+    view_projection: mat4x4<f32>,
+    model: mat4x4<f32>,
+    size: vec2<f32>,
+    mip_level_count: f32,
+    max_anisotropy: f32,
+    view: u32,
+    colormap: u32,
+    flags: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_mesh(
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = params.view_projection * params.model * vec4<f32>(position, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+@fragment
+fn fs_mip(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = in.uv * params.size;
+    let level = mip_level(texel, params.max_anisotropy);
+    let ratio = anisotropy_ratio(texel, params.max_anisotropy);
+    var t: f32;
+    if params.view == ANISOTROPY {
+        // Ratios from 1 to 16 on a log scale.
+        t = log2(ratio) / 4.0;
+    } else {
+        t = level / max(params.mip_level_count - 1.0, 1.0);
+    }
+    var color = colormap(params.colormap, t);
+    if (params.flags & SRGB_TARGET) != 0u {
+        color = to_linear(color);
+    }
+    return vec4<f32>(color, 1.0);
+}
@@ -0,0 +1,63 @@
+const NORMAL: u32 = 0u;
+const TANGENT: u32 = 1u;
+const BITANGENT: u32 = 2u;
+
+struct Params {
+    view_projection: mat4x4<f32>,
+    model: mat4x4<f32>,
+    normal_matrix: mat4x4<f32>,
+    // The kinds of line drawn per vertex, in the order of their segments.
+    kinds: vec4<u32>,
+    length: f32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+
+struct Frame {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) tangent: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+}
+
+// Expands each instance, one vertex of the mesh, into a segment per kind
+// of line starting at the vertex.
+@vertex
+fn vs_lines(@builtin(vertex_index) index: u32, frame: Frame) -> VertexOutput {
+    let kind = params.kinds[index / 2u];
+    var direction: vec3<f32>;
+    var color: vec3<f32>;
+    switch kind {
+        case NORMAL: {
+            direction = (params.normal_matrix * vec4<f32>(frame.normal, 0.0)).xyz;
+            color = vec3<f32>(0.0, 0.0, 1.0);
+        }
+        case TANGENT: {
+            direction = (params.model * vec4<f32>(frame.tangent.xyz, 0.0)).xyz;
+            color = vec3<f32>(1.0, 0.0, 0.0);
+        }
+        case BITANGENT, default: {
+            let bitangent = cross(frame.normal, frame.tangent.xyz) * frame.tangent.w;
+            direction = (params.model * vec4<f32>(bitangent, 0.0)).xyz;
+            color = vec3<f32>(0.0, 1.0, 0.0);
+        }
+    }
+    var world = (params.model * vec4<f32>(frame.position, 1.0)).xyz;
+    if index % 2u == 1u && dot(direction, direction) > 0.0 {
+        world += normalize(direction) * params.length;
+    }
+
+    var out: VertexOutput;
+    out.position = params.view_projection * vec4<f32>(world, 1.0);
+    out.color = color;
+    return out;
+}
+
+@fragment
+fn fs_lines(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(in.color, 1.0);
+}
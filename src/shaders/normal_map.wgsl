@@ -0,0 +1,41 @@
+struct Params {
+    size: vec2<u32>,
+    strength: f32,
+    wrap: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var heights: texture_2d<f32>;
+@group(0) @binding(2) var normals: texture_storage_2d<rgba8unorm, write>;
+
+fn height(pixel: vec2<i32>) -> f32 {
+    let size = vec2<i32>(params.size);
+    var p = clamp(pixel, vec2<i32>(0), size - 1);
+    if params.wrap != 0u {
+        p = (pixel % size + size) % size;
+    }
+    return textureLoad(heights, p, 0).r;
+}
+
+// Sobel gradients of the heights, with +y up in tangent space as in
+// OpenGL normal maps, encoded into [0, 1] with the height in alpha.
+@compute @workgroup_size(8, 8)
+fn generate(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id.xy >= params.size) {
+        return;
+    }
+    let p = vec2<i32>(id.xy);
+    let top_left = height(p + vec2<i32>(-1, -1));
+    let top = height(p + vec2<i32>(0, -1));
+    let top_right = height(p + vec2<i32>(1, -1));
+    let left = height(p + vec2<i32>(-1, 0));
+    let right = height(p + vec2<i32>(1, 0));
+    let bottom_left = height(p + vec2<i32>(-1, 1));
+    let bottom = height(p + vec2<i32>(0, 1));
+    let bottom_right = height(p + vec2<i32>(1, 1));
+    // Slopes per texel, with y growing down the image.
+    let dx = (top_right + 2.0 * right + bottom_right - top_left - 2.0 * left - bottom_left) / 8.0;
+    let dy = (bottom_left + 2.0 * bottom + bottom_right - top_left - 2.0 * top - top_right) / 8.0;
+    let normal = normalize(vec3<f32>(-dx * params.strength, dy * params.strength, 1.0));
+    textureStore(normals, id.xy, vec4<f32>(normal * 0.5 + 0.5, height(p)));
+}
@@ -0,0 +1,67 @@
+struct Canvas {
+    size: vec2<f32>,
+    _padding: vec2<f32>,
+}
+
+struct Stamp {
+    @location(0) center: vec2<f32>,
+    @location(1) radius: f32,
+    @location(2) hardness: f32,
+    @location(3) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) offset: vec2<f32>,
+    @location(1) feather: f32,
+    @location(2) color: vec4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> canvas: Canvas;
+@group(0) @binding(1) var tip: texture_2d<f32>;
+@group(0) @binding(2) var tip_sampler: sampler;
+@group(0) @binding(3) var fill_mask: texture_2d<f32>;
+@group(0) @binding(4) var<uniform> fill_color: vec4<f32>;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32, stamp: Stamp) -> VertexOutput {
+    // A quad around the stamp, drawn as a triangle strip.
+    let offset = vec2<f32>(f32(index & 1u), f32(index >> 1u)) * 2.0 - 1.0;
+    let pixel = stamp.center + offset * stamp.radius;
+    var out: VertexOutput;
+    out.position = vec4<f32>(pixel / canvas.size * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+    out.offset = offset;
+    // At least a pixel of falloff keeps hard brushes antialiased.
+    out.feather = max(1.0 - stamp.hardness, 1.0 / max(stamp.radius, 1.0));
+    out.color = stamp.color;
+    return out;
+}
+
+fn premultiply(color: vec4<f32>, coverage: f32) -> vec4<f32> {
+    let alpha = color.a * coverage;
+    return vec4<f32>(color.rgb * alpha, alpha);
+}
+
+@fragment
+fn fs_round(in: VertexOutput) -> @location(0) vec4<f32> {
+    let edge = clamp((1.0 - length(in.offset)) / in.feather, 0.0, 1.0);
+    return premultiply(in.color, edge * edge * (3.0 - 2.0 * edge));
+}
+
+@fragment
+fn fs_tip(in: VertexOutput) -> @location(0) vec4<f32> {
+    let sample = textureSample(tip, tip_sampler, in.offset * 0.5 + 0.5);
+    return premultiply(in.color, sample.r * sample.a);
+}
+
+@vertex
+fn vs_fill(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    // A single triangle covering the whole canvas.
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+@fragment
+fn fs_fill(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    return premultiply(fill_color, textureLoad(fill_mask, vec2<i32>(position.xy), 0).r);
+}
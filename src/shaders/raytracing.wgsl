@@ -0,0 +1,139 @@
+// Ray traversal of a `Bvh` in group 1, and sampling helpers.
+
+struct BvhNode {
+    min: vec3<f32>,
+    first: u32,
+    max: vec3<f32>,
+    count: u32,
+}
+
+struct BvhTriangle {
+    a: vec4<f32>,
+    b: vec4<f32>,
+    c: vec4<f32>,
+}
+
+struct Hit {
+    // The ray's `t_max` on a miss.
+    t: f32,
+    triangle: u32,
+}
+
+@group(1) @binding(0) var<storage, read> bvh_nodes: array<BvhNode>;
+@group(1) @binding(1) var<storage, read> bvh_triangles: array<BvhTriangle>;
+
+const BVH_STACK_SIZE: u32 = 32u;
+const EDGE_TOLERANCE: f32 = 1e-5;
+
+fn intersect_box(origin: vec3<f32>, inverse_direction: vec3<f32>, node: BvhNode, t_max: f32) -> bool {
+    let first = (node.min - origin) * inverse_direction;
+    let second = (node.max - origin) * inverse_direction;
+    let near = max(max(min(first.x, second.x), min(first.y, second.y)), min(first.z, second.z));
+    let far = min(min(max(first.x, second.x), max(first.y, second.y)), max(first.z, second.z));
+    return near <= far && far >= 0.0 && near < t_max;
+}
+
+// Two-sided Möller-Trumbore, the distance to the hit or -1. Edges are
+// widened a little so that rays don't slip between adjacent triangles.
+fn intersect_triangle(origin: vec3<f32>, direction: vec3<f32>, triangle: BvhTriangle) -> f32 {
+    let edge1 = triangle.b.xyz - triangle.a.xyz;
+    let edge2 = triangle.c.xyz - triangle.a.xyz;
+    let p = cross(direction, edge2);
+    let determinant = dot(edge1, p);
+    if abs(determinant) < 1e-12 {
+        return -1.0;
+    }
+    let inverse_determinant = 1.0 / determinant;
+    let s = origin - triangle.a.xyz;
+    let u = dot(s, p) * inverse_determinant;
+    if u < -EDGE_TOLERANCE || u > 1.0 + EDGE_TOLERANCE {
+        return -1.0;
+    }
+    let q = cross(s, edge1);
+    let v = dot(direction, q) * inverse_determinant;
+    if v < -EDGE_TOLERANCE || u + v > 1.0 + EDGE_TOLERANCE {
+        return -1.0;
+    }
+    return dot(edge2, q) * inverse_determinant;
+}
+
+// The closest hit before `t_max`, or with `any_hit` the first one found,
+// which is enough for visibility.
+fn trace_ray(origin: vec3<f32>, direction: vec3<f32>, t_max: f32, any_hit: bool) -> Hit {
+    // Axis-parallel rays would divide by zero, which WGSL leaves undefined.
+    let safe = select(vec3<f32>(1e-12), direction, abs(direction) > vec3<f32>(1e-12));
+    let inverse_direction = 1.0 / safe;
+    var hit = Hit(t_max, 0u);
+    var stack: array<u32, BVH_STACK_SIZE>;
+    var depth = 1u;
+    stack[0] = 0u;
+    while depth > 0u {
+        depth -= 1u;
+        let node = bvh_nodes[stack[depth]];
+        if !intersect_box(origin, inverse_direction, node, hit.t) {
+            continue;
+        }
+        if node.count == 0u {
+            if depth + 2u <= BVH_STACK_SIZE {
+                stack[depth] = node.first;
+                stack[depth + 1u] = node.first + 1u;
+                depth += 2u;
+            }
+            continue;
+        }
+        for (var i = node.first; i < node.first + node.count; i++) {
+            let t = intersect_triangle(origin, direction, bvh_triangles[i]);
+            if t > 0.0 && t < hit.t {
+                hit = Hit(t, i);
+                if any_hit {
+                    return hit;
+                }
+            }
+        }
+    }
+    return hit;
+}
+
+fn triangle_normal(index: u32) -> vec3<f32> {
+    let triangle = bvh_triangles[index];
+    return normalize(cross(triangle.b.xyz - triangle.a.xyz, triangle.c.xyz - triangle.a.xyz));
+}
+
+// PCG hash.
+fn hash(value: u32) -> u32 {
+    let state = value * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+fn random(seed: ptr<function, u32>) -> f32 {
+    *seed = hash(*seed);
+    return f32(*seed >> 8u) / 16777216.0;
+}
+
+// `local` in the orthonormal basis of Duff et al. around the unit vector
+// `normal`, which is its z axis.
+fn around_normal(normal: vec3<f32>, local: vec3<f32>) -> vec3<f32> {
+    let s = select(-1.0, 1.0, normal.z >= 0.0);
+    let a = -1.0 / (s + normal.z);
+    let b = normal.x * normal.y * a;
+    let tangent = vec3<f32>(1.0 + s * normal.x * normal.x * a, s * b, -s * normal.x);
+    let bitangent = vec3<f32>(b, s + normal.y * normal.y * a, -normal.y);
+    return normalize(tangent * local.x + bitangent * local.y + normal * local.z);
+}
+
+// A cosine-weighted direction around the unit vector `normal`.
+fn sample_hemisphere(normal: vec3<f32>, seed: ptr<function, u32>) -> vec3<f32> {
+    let phi = 6.28318530718 * random(seed);
+    let r2 = random(seed);
+    let r = sqrt(r2);
+    return around_normal(normal, vec3<f32>(r * cos(phi), r * sin(phi), sqrt(1.0 - r2)));
+}
+
+// A uniformly distributed direction around the unit vector `normal`.
+fn sample_hemisphere_uniform(normal: vec3<f32>, seed: ptr<function, u32>) -> vec3<f32> {
+    let phi = 6.28318530718 * random(seed);
+    let z = random(seed);
+    let r = sqrt(max(1.0 - z * z, 0.0));
+    return around_normal(normal, vec3<f32>(r * cos(phi), r * sin(phi), z));
+}
@@ -0,0 +1,63 @@
+struct Params {
+    mode: u32,
+    parity: u32,
+    history: u32,
+    _padding: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var source: texture_2d<f32>;
+
+const CHECKERBOARD: u32 = 0u;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+}
+
+// Mirrors at the borders so the neighbours of an edge texel are still
+// texels shaded this frame.
+fn load(position: vec2<i32>) -> vec4<f32> {
+    let size = vec2<i32>(textureDimensions(source));
+    let mirrored = select(position, -position, position < vec2<i32>(0));
+    let inside = select(mirrored, 2 * size - 2 - mirrored, mirrored >= size);
+    return textureLoad(source, clamp(inside, vec2<i32>(0), size - 1), 0);
+}
+
+fn rendered(position: vec2<i32>) -> bool {
+    let p = vec2<u32>(position);
+    if (params.mode == CHECKERBOARD) {
+        return ((p.x + p.y) & 1u) == params.parity;
+    }
+    return (p.y & 1u) == params.parity;
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let p = vec2<i32>(position.xy);
+    let center = load(p);
+    if (rendered(p)) {
+        return center;
+    }
+
+    // Texels skipped this frame still hold the previous frame, clamped to
+    // the range of the neighbours rendered this frame to limit ghosting.
+    let up = load(p + vec2<i32>(0, -1));
+    let down = load(p + vec2<i32>(0, 1));
+    var lo = min(up, down);
+    var hi = max(up, down);
+    var average = (up + down) * 0.5;
+    if (params.mode == CHECKERBOARD) {
+        let left = load(p + vec2<i32>(-1, 0));
+        let right = load(p + vec2<i32>(1, 0));
+        lo = min(lo, min(left, right));
+        hi = max(hi, max(left, right));
+        average = (up + down + left + right) * 0.25;
+    }
+
+    if (params.history == 0u) {
+        return average;
+    }
+    return clamp(center, lo, hi);
+}
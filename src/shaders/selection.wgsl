@@ -0,0 +1,129 @@
+const TILE: u32 = 16u;
+const DIAGONAL: u32 = 1u;
+
+struct Params {
+    seed: vec2<u32>,
+    size: vec2<u32>,
+    tolerance: f32,
+    flags: u32,
+    _padding: vec2<u32>,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var source: texture_2d<f32>;
+@group(0) @binding(2) var<storage, read_write> mask: array<u32>;
+@group(0) @binding(3) var<storage, read_write> changed: atomic<u32>;
+@group(0) @binding(4) var output: texture_storage_2d<r32float, write>;
+
+fn similar(pixel: vec2<u32>) -> bool {
+    let difference = abs(textureLoad(source, pixel, 0) - textureLoad(source, params.seed, 0));
+    return max(max(difference.r, difference.g), max(difference.b, difference.a)) <= params.tolerance;
+}
+
+fn inside(pixel: vec2<i32>) -> bool {
+    return all(pixel >= vec2<i32>(0)) && all(pixel < vec2<i32>(params.size));
+}
+
+fn neighbor_offset(index: u32) -> vec2<i32> {
+    // Four edge neighbors, then four diagonal ones.
+    var offsets = array<vec2<i32>, 8>(
+        vec2<i32>(-1, 0), vec2<i32>(1, 0), vec2<i32>(0, -1), vec2<i32>(0, 1),
+        vec2<i32>(-1, -1), vec2<i32>(1, -1), vec2<i32>(-1, 1), vec2<i32>(1, 1),
+    );
+    return offsets[index];
+}
+
+fn neighbor_count() -> u32 {
+    return select(4u, 8u, (params.flags & DIAGONAL) != 0u);
+}
+
+/// Every pixel similar to the seed, connected or not.
+@compute @workgroup_size(8, 8)
+fn select_similar(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id.xy >= params.size) {
+        return;
+    }
+    mask[id.y * params.size.x + id.x] = u32(similar(id.xy));
+}
+
+@compute @workgroup_size(8, 8)
+fn select_seed(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id.xy >= params.size) {
+        return;
+    }
+    mask[id.y * params.size.x + id.x] = u32(all(id.xy == params.seed));
+}
+
+// 0: not similar, 1: similar and not selected yet, 2: selected.
+var<workgroup> tile: array<u32, 256>;
+var<workgroup> tile_changed: u32;
+
+/// Grows the selection within each tile until it stops changing, starting
+/// from the selected pixels in and next to the tile.
+@compute @workgroup_size(16, 16)
+fn grow(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(local_invocation_id) local: vec3<u32>,
+) {
+    let pixel = vec2<i32>(id.xy);
+    let index = local.y * TILE + local.x;
+    let valid = all(id.xy < params.size);
+    let selected = valid && mask[id.y * params.size.x + id.x] != 0u;
+    var state = 0u;
+    if valid {
+        if selected {
+            state = 2u;
+        } else if similar(id.xy) {
+            state = 1u;
+            // Selected neighbors in other tiles.
+            for (var i = 0u; i < neighbor_count(); i++) {
+                let neighbor = pixel + neighbor_offset(i);
+                let neighbor_local = vec2<i32>(local.xy) + neighbor_offset(i);
+                let outside_tile = any(neighbor_local < vec2<i32>(0)) || any(neighbor_local >= vec2<i32>(i32(TILE)));
+                if outside_tile && inside(neighbor) && mask[u32(neighbor.y) * params.size.x + u32(neighbor.x)] != 0u {
+                    state = 2u;
+                }
+            }
+        }
+    }
+    tile[index] = state;
+
+    loop {
+        workgroupBarrier();
+        if index == 0u {
+            tile_changed = 0u;
+        }
+        workgroupBarrier();
+        var grows = false;
+        if state == 1u {
+            for (var i = 0u; i < neighbor_count(); i++) {
+                let neighbor = vec2<i32>(local.xy) + neighbor_offset(i);
+                if all(neighbor >= vec2<i32>(0)) && all(neighbor < vec2<i32>(i32(TILE))) && tile[u32(neighbor.y) * TILE + u32(neighbor.x)] == 2u {
+                    grows = true;
+                }
+            }
+        }
+        workgroupBarrier();
+        if grows {
+            state = 2u;
+            tile[index] = 2u;
+            tile_changed = 1u;
+        }
+        if workgroupUniformLoad(&tile_changed) == 0u {
+            break;
+        }
+    }
+
+    if state == 2u && !selected {
+        mask[id.y * params.size.x + id.x] = 1u;
+        atomicStore(&changed, 1u);
+    }
+}
+
+@compute @workgroup_size(8, 8)
+fn store(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id.xy >= params.size) {
+        return;
+    }
+    textureStore(output, id.xy, vec4<f32>(f32(mask[id.y * params.size.x + id.x]), 0.0, 0.0, 1.0));
+}
@@ -0,0 +1,7 @@
+// World-to-clip transform of the mesh being written into the mask.
+@group(0) @binding(0) var<uniform> transform: mat4x4<f32>;
+
+@vertex
+fn vs_mask(@location(0) position: vec3<f32>) -> @builtin(position) vec4<f32> {
+    return transform * vec4<f32>(position, 1.0);
+}
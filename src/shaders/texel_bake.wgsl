@@ -0,0 +1,62 @@
+// Turns the sums of baked texels into a texture.
+
+struct Params {
+    size: vec2<u32>,
+    scale: f32,
+    _padding: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+// World positions of the texels, with w 1 where a triangle covers them.
+@group(0) @binding(1) var<storage, read> positions: array<vec4<f32>>;
+@group(0) @binding(2) var<storage, read> source: array<vec4<f32>>;
+@group(0) @binding(3) var<storage, read_write> destination: array<vec4<f32>>;
+@group(0) @binding(4) var output: texture_storage_2d<rgba16float, write>;
+
+// Scales the sums, with the coverage in alpha.
+@compute @workgroup_size(8, 8)
+fn resolve(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id.xy >= params.size) {
+        return;
+    }
+    let index = id.y * params.size.x + id.x;
+    destination[index] = vec4<f32>(source[index].rgb * params.scale, positions[index].w);
+}
+
+// Fills uncovered texels with the average of their covered neighbors, so
+// that filtering across chart borders doesn't pull in black.
+@compute @workgroup_size(8, 8)
+fn dilate(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id.xy >= params.size) {
+        return;
+    }
+    let index = id.y * params.size.x + id.x;
+    var value = source[index];
+    if value.w == 0.0 {
+        var sum = vec4<f32>(0.0);
+        for (var y = -1; y <= 1; y++) {
+            for (var x = -1; x <= 1; x++) {
+                let neighbor = vec2<i32>(id.xy) + vec2<i32>(x, y);
+                if any(neighbor < vec2<i32>(0)) || any(neighbor >= vec2<i32>(params.size)) {
+                    continue;
+                }
+                let sample = source[u32(neighbor.y) * params.size.x + u32(neighbor.x)];
+                if sample.w > 0.0 {
+                    sum += vec4<f32>(sample.rgb, 1.0);
+                }
+            }
+        }
+        if sum.w > 0.0 {
+            value = vec4<f32>(sum.rgb / sum.w, 1.0);
+        }
+    }
+    destination[index] = value;
+}
+
+@compute @workgroup_size(8, 8)
+fn store(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id.xy >= params.size) {
+        return;
+    }
+    textureStore(output, id.xy, source[id.y * params.size.x + id.x]);
+}
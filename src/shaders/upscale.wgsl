@@ -0,0 +1,193 @@
+struct Params {
+    output_size: vec2<f32>,
+    sharpness: f32,
+    _padding: f32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var source: texture_2d<f32>;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+}
+
+fn load(position: vec2<i32>) -> vec3<f32> {
+    let size = vec2<i32>(textureDimensions(source));
+    return textureLoad(source, clamp(position, vec2<i32>(0), size - 1), 0).rgb;
+}
+
+fn luma(color: vec3<f32>) -> f32 {
+    return color.r * 0.5 + color.g + color.b * 0.5;
+}
+
+// Edge adaptive spatial upsampling: estimates the local gradient direction
+// and length from the 12 texels around the sample position and filters with
+// a lanczos-like kernel stretched along the edge.
+
+struct Direction {
+    dir: vec2<f32>,
+    len: f32,
+}
+
+fn accumulate(
+    acc: Direction,
+    w: f32,
+    above: f32,
+    left: f32,
+    center: f32,
+    right: f32,
+    below: f32,
+) -> Direction {
+    var out = acc;
+
+    let dir_x = right - left;
+    let len_x = saturate(abs(dir_x) / max(max(abs(right - center), abs(center - left)), 1e-5));
+    out.dir.x += dir_x * w;
+    out.len += len_x * len_x * w;
+
+    let dir_y = below - above;
+    let len_y = saturate(abs(dir_y) / max(max(abs(below - center), abs(center - above)), 1e-5));
+    out.dir.y += dir_y * w;
+    out.len += len_y * len_y * w;
+
+    return out;
+}
+
+struct Accumulator {
+    color: vec3<f32>,
+    weight: f32,
+}
+
+fn tap(
+    acc: Accumulator,
+    offset: vec2<f32>,
+    dir: vec2<f32>,
+    len: vec2<f32>,
+    lobe: f32,
+    clip: f32,
+    color: vec3<f32>,
+) -> Accumulator {
+    let v = vec2<f32>(
+        offset.x * dir.x + offset.y * dir.y,
+        offset.x * -dir.y + offset.y * dir.x,
+    ) * len;
+    let d2 = min(dot(v, v), clip);
+
+    var window = 2.0 / 5.0 * d2 - 1.0;
+    var base = lobe * d2 - 1.0;
+    window = window * window;
+    base = base * base;
+    window = 25.0 / 16.0 * window - (25.0 / 16.0 - 1.0);
+    let w = window * base;
+
+    return Accumulator(acc.color + color * w, acc.weight + w);
+}
+
+@fragment
+fn easu(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let source_size = vec2<f32>(textureDimensions(source));
+    let p = position.xy / params.output_size * source_size - 0.5;
+    let fp = floor(p);
+    let pp = p - fp;
+    let o = vec2<i32>(fp);
+
+    //    b c
+    //  e f g h
+    //  i j k l
+    //    n o
+    let b = load(o + vec2<i32>(0, -1));
+    let c = load(o + vec2<i32>(1, -1));
+    let e = load(o + vec2<i32>(-1, 0));
+    let f = load(o);
+    let g = load(o + vec2<i32>(1, 0));
+    let h = load(o + vec2<i32>(2, 0));
+    let i = load(o + vec2<i32>(-1, 1));
+    let j = load(o + vec2<i32>(0, 1));
+    let k = load(o + vec2<i32>(1, 1));
+    let l = load(o + vec2<i32>(2, 1));
+    let n = load(o + vec2<i32>(0, 2));
+    let oo = load(o + vec2<i32>(1, 2));
+
+    let lb = luma(b);
+    let lc = luma(c);
+    let le = luma(e);
+    let lf = luma(f);
+    let lg = luma(g);
+    let lh = luma(h);
+    let li = luma(i);
+    let lj = luma(j);
+    let lk = luma(k);
+    let ll = luma(l);
+    let ln = luma(n);
+    let lo = luma(oo);
+
+    var d = Direction(vec2<f32>(0.0), 0.0);
+    d = accumulate(d, (1.0 - pp.x) * (1.0 - pp.y), lb, le, lf, lg, lj);
+    d = accumulate(d, pp.x * (1.0 - pp.y), lc, lf, lg, lh, lk);
+    d = accumulate(d, (1.0 - pp.x) * pp.y, lf, li, lj, lk, ln);
+    d = accumulate(d, pp.x * pp.y, lg, lj, lk, ll, lo);
+
+    var dir = d.dir;
+    let dir_length = dot(dir, dir);
+    if (dir_length < 1.0 / 32768.0) {
+        dir = vec2<f32>(1.0, 0.0);
+    } else {
+        dir = dir * inverseSqrt(dir_length);
+    }
+
+    var len = d.len * 0.5;
+    len = len * len;
+    let stretch = dot(dir, dir) / max(abs(dir.x), abs(dir.y));
+    let len2 = vec2<f32>(1.0 + (stretch - 1.0) * len, 1.0 - 0.5 * len);
+    let lobe = 0.5 + ((1.0 / 4.0 - 0.04) - 0.5) * len;
+    let clip = 1.0 / lobe;
+
+    var acc = Accumulator(vec3<f32>(0.0), 0.0);
+    acc = tap(acc, vec2<f32>(0.0, -1.0) - pp, dir, len2, lobe, clip, b);
+    acc = tap(acc, vec2<f32>(1.0, -1.0) - pp, dir, len2, lobe, clip, c);
+    acc = tap(acc, vec2<f32>(-1.0, 0.0) - pp, dir, len2, lobe, clip, e);
+    acc = tap(acc, vec2<f32>(0.0, 0.0) - pp, dir, len2, lobe, clip, f);
+    acc = tap(acc, vec2<f32>(1.0, 0.0) - pp, dir, len2, lobe, clip, g);
+    acc = tap(acc, vec2<f32>(2.0, 0.0) - pp, dir, len2, lobe, clip, h);
+    acc = tap(acc, vec2<f32>(-1.0, 1.0) - pp, dir, len2, lobe, clip, i);
+    acc = tap(acc, vec2<f32>(0.0, 1.0) - pp, dir, len2, lobe, clip, j);
+    acc = tap(acc, vec2<f32>(1.0, 1.0) - pp, dir, len2, lobe, clip, k);
+    acc = tap(acc, vec2<f32>(2.0, 1.0) - pp, dir, len2, lobe, clip, l);
+    acc = tap(acc, vec2<f32>(0.0, 2.0) - pp, dir, len2, lobe, clip, n);
+    acc = tap(acc, vec2<f32>(1.0, 2.0) - pp, dir, len2, lobe, clip, oo);
+
+    // Deringing: clamp to the range of the four nearest texels.
+    let lo4 = min(min(f, g), min(j, k));
+    let hi4 = max(max(f, g), max(j, k));
+    let color = clamp(acc.color / acc.weight, lo4, hi4);
+    return vec4<f32>(color, 1.0);
+}
+
+// Robust contrast adaptive sharpening on a 5 texel cross, limited so it
+// never pushes a texel past the local range.
+
+const RCAS_LIMIT: f32 = 0.25 - 1.0 / 16.0;
+
+@fragment
+fn rcas(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let p = vec2<i32>(position.xy);
+    let b = load(p + vec2<i32>(0, -1));
+    let d = load(p + vec2<i32>(-1, 0));
+    let e = load(p);
+    let f = load(p + vec2<i32>(1, 0));
+    let h = load(p + vec2<i32>(0, 1));
+
+    let lo4 = min(min(b, d), min(f, h));
+    let hi4 = max(max(b, d), max(f, h));
+
+    let hit_min = min(lo4, e) / max(4.0 * hi4, vec3<f32>(1e-5));
+    let hit_max = (1.0 - max(hi4, e)) / min(4.0 * lo4 - 4.0, vec3<f32>(-1e-5));
+    let lobe_rgb = max(-hit_min, hit_max);
+    let lobe = max(-RCAS_LIMIT, min(max(lobe_rgb.r, max(lobe_rgb.g, lobe_rgb.b)), 0.0))
+        * params.sharpness;
+
+    let color = (lobe * (b + d + f + h) + e) / (4.0 * lobe + 1.0);
+    return vec4<f32>(color, 1.0);
+}
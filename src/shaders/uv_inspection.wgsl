@@ -0,0 +1,75 @@
+const SRGB_TARGET: u32 = 1u;
+
+struct Params {
+    colormap: u32,
+    flags: u32,
+    // Densities mapped to the ends of the colormap.
+    range: vec2<f32>,
+}
+
+struct UvOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) value: f32,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+// Triangles covering each texel, summed by additive blending.
+@group(0) @binding(1) var coverage: texture_2d<f32>;
+
+/// Encodes a display color for the target, which decodes sRGB on write.
+fn output(color: vec3<f32>) -> vec4<f32> {
+    if (params.flags & SRGB_TARGET) != 0u {
+        return vec4<f32>(to_linear(color), 1.0);
+    }
+    return vec4<f32>(color, 1.0);
+}
+
+// Places a triangle at its UVs, with (0, 0) at the top left of the target.
+@vertex
+fn vs_uv(@location(0) uv: vec2<f32>, @location(1) value: f32) -> UvOutput {
+    var out: UvOutput;
+    out.position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.value = value;
+    return out;
+}
+
+@fragment
+fn fs_density(in: UvOutput) -> @location(0) vec4<f32> {
+    let t = (in.value - params.range.x) / max(params.range.y - params.range.x, 1e-6);
+    return output(colormap(params.colormap, t));
+}
+
+@fragment
+fn fs_count(in: UvOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0, 0.0, 0.0, 0.0);
+}
+
+@vertex
+fn vs_fullscreen(@builtin(vertex_index) index: u32) -> VertexOutput {
+    // A single triangle covering the whole viewport.
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+// Black where no triangle covers the texel, green where one does and red
+// where several overlap.
+@fragment
+fn fs_overlaps(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = textureDimensions(coverage);
+    let count = textureLoad(coverage, min(vec2<u32>(in.uv * vec2<f32>(size)), size - 1u), 0).r;
+    if count > 1.5 {
+        return output(vec3<f32>(1.0, 0.0, 0.0));
+    }
+    if count > 0.5 {
+        return output(vec3<f32>(0.0, 0.6, 0.0));
+    }
+    return output(vec3<f32>(0.0));
+}
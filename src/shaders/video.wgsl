@@ -0,0 +1,53 @@
+const INTERLEAVED_CHROMA: u32 = 1u;
+const SRGB_TARGET: u32 = 2u;
+
+struct Params {
+    // Rows of the affine map from (Y, Cb, Cr, 1) samples to R'G'B'.
+    rows: array<vec4<f32>, 3>,
+    flags: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var luma: texture_2d<f32>;
+// Cb, or Cb and Cr interleaved in NV12.
+@group(0) @binding(2) var chroma_u: texture_2d<f32>;
+// Cr of I420, the same texture as `chroma_u` for NV12.
+@group(0) @binding(3) var chroma_v: texture_2d<f32>;
+@group(0) @binding(4) var chroma_sampler: sampler;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+}
+
+fn srgb_to_linear(value: vec3<f32>) -> vec3<f32> {
+    let low = value / 12.92;
+    let high = pow((value + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, value <= vec3<f32>(0.04045));
+}
+
+// Chroma planes are upsampled bilinearly, with their samples centered
+// between luma samples.
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let uv = position.xy / vec2<f32>(textureDimensions(luma));
+    let y = textureLoad(luma, vec2<i32>(position.xy), 0).r;
+    let u = textureSampleLevel(chroma_u, chroma_sampler, uv, 0.0);
+    let v = textureSampleLevel(chroma_v, chroma_sampler, uv, 0.0);
+    var chroma = vec2<f32>(u.r, v.r);
+    if (params.flags & INTERLEAVED_CHROMA) != 0u {
+        chroma = u.rg;
+    }
+    let yuv = vec4<f32>(y, chroma, 1.0);
+    var rgb = clamp(
+        vec3<f32>(dot(params.rows[0], yuv), dot(params.rows[1], yuv), dot(params.rows[2], yuv)),
+        vec3<f32>(0.0),
+        vec3<f32>(1.0),
+    );
+    // sRGB targets encode on write, so hand them linear values.
+    if (params.flags & SRGB_TARGET) != 0u {
+        rgb = srgb_to_linear(rgb);
+    }
+    return vec4<f32>(rgb, 1.0);
+}
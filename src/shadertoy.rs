@@ -0,0 +1,340 @@
+use super::context::Context;
+use super::diagnostics::{describe_texture, PassRecord};
+use super::error::Error;
+use super::texture::Texture;
+use std::borrow::Cow;
+
+/// Bindings and wrapper for Shadertoy GLSL. `mainImage` gets a bottom-left
+/// origin `fragCoord` like on Shadertoy.
+const GLSL_PRELUDE: &str = "#version 450
+layout(set = 0, binding = 0) uniform ShaderToy {
+    vec3 iResolution;
+    float iTime;
+    vec4 iMouse;
+    float iTimeDelta;
+    int iFrame;
+    float iFrameRate;
+    vec4 iDate;
+    vec4 iChannelResolution[4];
+};
+layout(set = 0, binding = 1) uniform texture2D iChannel0_texture;
+layout(set = 0, binding = 2) uniform sampler iChannel0_sampler;
+layout(set = 0, binding = 3) uniform texture2D iChannel1_texture;
+layout(set = 0, binding = 4) uniform sampler iChannel1_sampler;
+layout(set = 0, binding = 5) uniform texture2D iChannel2_texture;
+layout(set = 0, binding = 6) uniform sampler iChannel2_sampler;
+layout(set = 0, binding = 7) uniform texture2D iChannel3_texture;
+layout(set = 0, binding = 8) uniform sampler iChannel3_sampler;
+#define iChannel0 sampler2D(iChannel0_texture, iChannel0_sampler)
+#define iChannel1 sampler2D(iChannel1_texture, iChannel1_sampler)
+#define iChannel2 sampler2D(iChannel2_texture, iChannel2_sampler)
+#define iChannel3 sampler2D(iChannel3_texture, iChannel3_sampler)
+layout(location = 0) out vec4 wgpu_tools_color;
+";
+
+const GLSL_MAIN: &str = "
+void main() {
+    mainImage(wgpu_tools_color, vec2(gl_FragCoord.x, iResolution.y - gl_FragCoord.y));
+}
+";
+
+/// The WGSL flavour: the shader defines
+/// `fn main_image(frag_coord: vec2<f32>) -> vec4<f32>` and reads the
+/// `shadertoy` uniforms and `channel0`..`channel3` with their samplers.
+const WGSL_PRELUDE: &str = "struct ShaderToy {
+    resolution: vec3<f32>,
+    time: f32,
+    mouse: vec4<f32>,
+    time_delta: f32,
+    frame: i32,
+    frame_rate: f32,
+    date: vec4<f32>,
+    channel_resolution: array<vec4<f32>, 4>,
+}
+@group(0) @binding(0) var<uniform> shadertoy: ShaderToy;
+@group(0) @binding(1) var channel0: texture_2d<f32>;
+@group(0) @binding(2) var channel0_sampler: sampler;
+@group(0) @binding(3) var channel1: texture_2d<f32>;
+@group(0) @binding(4) var channel1_sampler: sampler;
+@group(0) @binding(5) var channel2: texture_2d<f32>;
+@group(0) @binding(6) var channel2_sampler: sampler;
+@group(0) @binding(7) var channel3: texture_2d<f32>;
+@group(0) @binding(8) var channel3_sampler: sampler;
+";
+
+const WGSL_MAIN: &str = "
+@fragment
+fn wgpu_tools_fragment(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    return main_image(vec2<f32>(position.x, shadertoy.resolution.y - position.y));
+}
+";
+
+const VERTEX: &str = "@vertex
+fn wgpu_tools_fullscreen(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+}
+";
+
+#[derive(Clone, Copy, Debug)]
+pub enum ShaderToySource<'a> {
+    /// Shadertoy GLSL defining `mainImage`, translated by naga.
+    Glsl(&'a str),
+    Wgsl(&'a str),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ShaderToyInputs {
+    pub time: f32,
+    pub time_delta: f32,
+    pub frame: i32,
+    /// Shadertoy's `iMouse`: current position while pressed in xy, click
+    /// position in zw (negative when released).
+    pub mouse: [f32; 4],
+    /// Year, month (0-based), day and seconds since midnight.
+    pub date: [f32; 4],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct Uniforms {
+    resolution: [f32; 3],
+    time: f32,
+    mouse: [f32; 4],
+    time_delta: f32,
+    frame: i32,
+    frame_rate: f32,
+    _padding: f32,
+    date: [f32; 4],
+    channel_resolution: [[f32; 4]; 4],
+}
+
+/// Runs Shadertoy-style fragment shaders with up to four input channels.
+/// Multipass shaders are built by rendering one `ShaderToy` into a render
+/// target and passing it as a channel of the next.
+pub struct ShaderToy {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    uniforms: wgpu::Buffer,
+    empty_channel: Texture,
+    target_format: wgpu::TextureFormat,
+}
+
+impl ShaderToy {
+    pub fn new(
+        context: &Context,
+        source: ShaderToySource<'_>,
+        target_format: &wgpu::TextureFormat,
+    ) -> Result<Self, Error> {
+        let device = &context.device;
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+        let (vertex, fragment, fragment_entry) = match source {
+            ShaderToySource::Glsl(code) => {
+                let vertex = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                    label: Some("ShaderToy Vertex"),
+                    source: wgpu::ShaderSource::Wgsl(VERTEX.into()),
+                });
+                let fragment = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                    label: Some("ShaderToy Fragment"),
+                    source: wgpu::ShaderSource::Glsl {
+                        shader: Cow::Owned(format!("{GLSL_PRELUDE}{code}\n{GLSL_MAIN}")),
+                        stage: wgpu::naga::ShaderStage::Fragment,
+                        defines: Default::default(),
+                    },
+                });
+                (Some(vertex), fragment, "main")
+            }
+            ShaderToySource::Wgsl(code) => {
+                let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                    label: Some("ShaderToy"),
+                    source: wgpu::ShaderSource::Wgsl(
+                        format!("{WGSL_PRELUDE}{code}\n{WGSL_MAIN}\n{VERTEX}").into(),
+                    ),
+                });
+                (None, module, "wgpu_tools_fragment")
+            }
+        };
+
+        let mut entries = vec![wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }];
+        for channel in 0..4 {
+            entries.push(wgpu::BindGroupLayoutEntry {
+                binding: 1 + channel * 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            });
+            entries.push(wgpu::BindGroupLayoutEntry {
+                binding: 2 + channel * 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            });
+        }
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("ShaderToy Bind Group Layout"),
+            entries: &entries,
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("ShaderToy Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("ShaderToy Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: vertex.as_ref().unwrap_or(&fragment),
+                entry_point: "wgpu_tools_fullscreen",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &fragment,
+                entry_point: fragment_entry,
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                targets: &[Some((*target_format).into())],
+            }),
+            multiview: None,
+        });
+
+        if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+            return Err(Error::ShaderCompilationFailed(error.to_string()));
+        }
+
+        let uniforms = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("ShaderToy Uniforms"),
+            size: std::mem::size_of::<Uniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let empty_channel = context.texture_from_rgba(
+            &[0.0, 0.0, 0.0, 1.0],
+            &wgpu::TextureFormat::Rgba8Unorm,
+            Some("ShaderToy Empty Channel"),
+        )?;
+
+        Ok(Self {
+            pipeline,
+            bind_group_layout,
+            uniforms,
+            empty_channel,
+            target_format: *target_format,
+        })
+    }
+
+    pub fn target_format(&self) -> wgpu::TextureFormat {
+        self.target_format
+    }
+
+    /// Draws one frame into `target` of `width` x `height`. Channels are
+    /// sampled with their texture's sampler; missing ones read as black.
+    /// The uniforms are written through the queue, so draws recorded before
+    /// a submit share the last `inputs`.
+    #[allow(clippy::too_many_arguments)]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "trace",
+            skip_all,
+            fields(width = *width, height = *height, frame = inputs.frame)
+        )
+    )]
+    pub fn render(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        width: &u32,
+        height: &u32,
+        inputs: &ShaderToyInputs,
+        channels: [Option<&Texture>; 4],
+    ) {
+        let channels = channels.map(|channel| channel.unwrap_or(&self.empty_channel));
+        let uniforms = Uniforms {
+            resolution: [*width as f32, *height as f32, 1.0],
+            time: inputs.time,
+            mouse: inputs.mouse,
+            time_delta: inputs.time_delta,
+            frame: inputs.frame,
+            frame_rate: match inputs.time_delta > 0.0 {
+                true => 1.0 / inputs.time_delta,
+                false => 0.0,
+            },
+            _padding: 0.0,
+            date: inputs.date,
+            channel_resolution: channels
+                .map(|channel| [channel.width() as f32, channel.height() as f32, 1.0, 0.0]),
+        };
+        context
+            .queue
+            .write_buffer(&self.uniforms, 0, bytemuck::bytes_of(&uniforms));
+
+        let mut entries = vec![wgpu::BindGroupEntry {
+            binding: 0,
+            resource: self.uniforms.as_entire_binding(),
+        }];
+        for (index, channel) in channels.iter().enumerate() {
+            entries.push(wgpu::BindGroupEntry {
+                binding: 1 + index as u32 * 2,
+                resource: wgpu::BindingResource::TextureView(&channel.view),
+            });
+            entries.push(wgpu::BindGroupEntry {
+                binding: 2 + index as u32 * 2,
+                resource: wgpu::BindingResource::Sampler(&channel.sampler),
+            });
+        }
+        let bind_group = context
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("ShaderToy Bind Group"),
+                layout: &self.bind_group_layout,
+                entries: &entries,
+            });
+
+        context.diagnostics.record_pass(|| {
+            let resources = channels
+                .iter()
+                .enumerate()
+                .map(|(index, channel)| {
+                    describe_texture(&format!("iChannel{index}"), &channel.texture)
+                })
+                .collect();
+            PassRecord::new("ShaderToy Pass", "ShaderToy Pipeline", resources)
+        });
+
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("ShaderToy Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,276 @@
+use super::context::Context;
+use super::diagnostics::{describe_texture, PassRecord};
+use super::error::Error;
+use super::math::multiply;
+use super::mesh::{GpuMesh, Vertex};
+use super::texture::Texture;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use wgpu::util::DeviceExt;
+
+/// Which side of a stencil mask a pass draws on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum StencilTest {
+    /// Where the stencil equals the pass's stencil reference.
+    #[default]
+    Inside,
+    /// Where the stencil differs from the pass's stencil reference.
+    Outside,
+}
+
+impl Context {
+    /// Creates a `Texture::DEPTH_STENCIL_FORMAT` attachment for stencil
+    /// masks, which can also be bound to `DepthVisualizer`.
+    pub fn depth_stencil_texture(
+        &self,
+        width: &u32,
+        height: &u32,
+        label: Option<&str>,
+    ) -> Result<Texture, Error> {
+        let size = wgpu::Extent3d {
+            width: *width,
+            height: *height,
+            depth_or_array_layers: 1,
+        };
+        if size.width == 0 || size.height == 0 {
+            return Err(Error::TextureCreationFailed);
+        }
+        self.check_texture_size(&size, wgpu::TextureDimension::D2)?;
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: Texture::DEPTH_STENCIL_FORMAT,
+            view_formats: &[Texture::DEPTH_STENCIL_FORMAT],
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        });
+        Ok(Texture::from_wgpu(self, texture))
+    }
+
+    /// Depth and stencil state for pipelines restricted to one side of a
+    /// stencil mask, with the default depth test of `depth_stencil_state`.
+    /// Set the mask's value with `RenderPass::set_stencil_reference`.
+    pub fn stencil_test_state(
+        &self,
+        format: wgpu::TextureFormat,
+        test: StencilTest,
+    ) -> wgpu::DepthStencilState {
+        let face = wgpu::StencilFaceState {
+            compare: match test {
+                StencilTest::Inside => wgpu::CompareFunction::Equal,
+                StencilTest::Outside => wgpu::CompareFunction::NotEqual,
+            },
+            fail_op: wgpu::StencilOperation::Keep,
+            depth_fail_op: wgpu::StencilOperation::Keep,
+            pass_op: wgpu::StencilOperation::Keep,
+        };
+        wgpu::DepthStencilState {
+            stencil: wgpu::StencilState {
+                front: face,
+                back: face,
+                read_mask: 0xff,
+                write_mask: 0,
+            },
+            ..self.depth_stencil_state(format)
+        }
+    }
+
+    /// Depth and stencil state for pipelines writing the stencil reference
+    /// wherever they draw, regardless of depth, and leaving depth as is.
+    pub fn stencil_write_state(&self, format: wgpu::TextureFormat) -> wgpu::DepthStencilState {
+        let face = wgpu::StencilFaceState {
+            compare: wgpu::CompareFunction::Always,
+            fail_op: wgpu::StencilOperation::Keep,
+            depth_fail_op: wgpu::StencilOperation::Keep,
+            pass_op: wgpu::StencilOperation::Replace,
+        };
+        wgpu::DepthStencilState {
+            format,
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::Always,
+            stencil: wgpu::StencilState {
+                front: face,
+                back: face,
+                read_mask: 0xff,
+                write_mask: 0xff,
+            },
+            bias: wgpu::DepthBiasState::default(),
+        }
+    }
+}
+
+impl Texture {
+    /// A view of the stencil aspect alone, for binding stencil values as
+    /// `texture_2d<u32>` (or `texture_multisampled_2d<u32>`) in stencil-only
+    /// passes. Fails for formats without stencil.
+    pub fn stencil_view(&self) -> Result<wgpu::TextureView, Error> {
+        let format = self.format();
+        if !format.has_stencil_aspect() || self.dimension() != wgpu::TextureDimension::D2 {
+            return Err(Error::UnsupportedTextureFormat(format));
+        }
+        if !self.usage().contains(wgpu::TextureUsages::TEXTURE_BINDING) {
+            return Err(Error::TextureCreationFailed);
+        }
+        Ok(self.texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Stencil View"),
+            dimension: Some(wgpu::TextureViewDimension::D2),
+            aspect: wgpu::TextureAspect::StencilOnly,
+            base_array_layer: 0,
+            array_layer_count: Some(1),
+            ..Default::default()
+        }))
+    }
+}
+
+/// Writes the silhouettes of meshes into the stencil aspect of a depth
+/// stencil texture, for passes built with `Context::stencil_test_state` to
+/// draw only inside or outside of.
+pub struct StencilMask {
+    module: wgpu::ShaderModule,
+    layout: wgpu::BindGroupLayout,
+    pipeline_layout: wgpu::PipelineLayout,
+    pipelines: Mutex<HashMap<wgpu::TextureFormat, wgpu::RenderPipeline>>,
+}
+
+impl StencilMask {
+    pub fn new(context: &Context) -> Self {
+        let device = &context.device;
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("stencil_mask.wgsl"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/stencil_mask.wgsl").into()),
+        });
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Stencil Mask Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Stencil Mask Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        Self {
+            module,
+            layout,
+            pipeline_layout,
+            pipelines: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Writes `reference` into the stencil of `stencil` wherever one of
+    /// `meshes`, each with its model matrix, covers, after clearing the
+    /// stencil to 0 if `clear` is set. Depth is kept.
+    #[allow(clippy::too_many_arguments)]
+    pub fn write(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        meshes: &[(&GpuMesh, [[f32; 4]; 4])],
+        view_projection: &[[f32; 4]; 4],
+        stencil: &Texture,
+        reference: u32,
+        clear: bool,
+    ) -> Result<(), Error> {
+        let format = stencil.format();
+        if !format.has_stencil_aspect() {
+            return Err(Error::UnsupportedTextureFormat(format));
+        }
+        if !stencil
+            .usage()
+            .contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+        {
+            return Err(Error::TextureCreationFailed);
+        }
+        let device = &context.device;
+        let bind_groups: Vec<wgpu::BindGroup> = meshes
+            .iter()
+            .map(|(_, model)| {
+                let transform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Stencil Mask Transform"),
+                    contents: bytemuck::bytes_of(&multiply(view_projection, model)),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                });
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Stencil Mask Bind Group"),
+                    layout: &self.layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: transform.as_entire_binding(),
+                    }],
+                })
+            })
+            .collect();
+        context.diagnostics.record_pass(|| {
+            PassRecord::new(
+                "Stencil Mask",
+                "vs_mask",
+                vec![describe_texture("stencil", &stencil.texture)],
+            )
+        });
+
+        let mut pipelines = self
+            .pipelines
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let pipeline = pipelines.entry(format).or_insert_with(|| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Stencil Mask"),
+                layout: Some(&self.pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &self.module,
+                    entry_point: "vs_mask",
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    buffers: &[Vertex::layout()],
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: Some(context.stencil_write_state(format)),
+                multisample: wgpu::MultisampleState {
+                    count: stencil.sample_count(),
+                    ..Default::default()
+                },
+                fragment: None,
+                multiview: None,
+            })
+        });
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Stencil Mask"),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &stencil.view,
+                depth_ops: format.has_depth_aspect().then_some(wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: Some(wgpu::Operations {
+                    load: match clear {
+                        true => wgpu::LoadOp::Clear(0),
+                        false => wgpu::LoadOp::Load,
+                    },
+                    store: wgpu::StoreOp::Store,
+                }),
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(pipeline);
+        pass.set_stencil_reference(reference);
+        for ((mesh, _), bind_group) in meshes.iter().zip(&bind_groups) {
+            pass.set_bind_group(0, bind_group, &[]);
+            pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+            pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+            pass.draw_indexed(0..mesh.index_count, 0, 0..1);
+        }
+
+        Ok(())
+    }
+}
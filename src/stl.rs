@@ -0,0 +1,241 @@
+use super::coordinates::ImportOptions;
+use super::error::Error;
+use super::mesh::{Mesh, Vertex};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StlFormat {
+    Ascii,
+    #[default]
+    Binary,
+}
+
+fn invalid(message: &str) -> Error {
+    Error::InvalidMesh(format!("stl: {message}"))
+}
+
+fn face_normal([a, b, c]: &[[f32; 3]; 3]) -> [f32; 3] {
+    let e1 = [0, 1, 2].map(|i| b[i] - a[i]);
+    let e2 = [0, 1, 2].map(|i| c[i] - a[i]);
+    let n = [
+        e1[1] * e2[2] - e1[2] * e2[1],
+        e1[2] * e2[0] - e1[0] * e2[2],
+        e1[0] * e2[1] - e1[1] * e2[0],
+    ];
+    let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+    // Adding zero turns -0.0 into 0.0 so equal normals share vertices.
+    n.map(|c| if length > 0.0 { c / length + 0.0 } else { 0.0 })
+}
+
+impl Mesh {
+    pub fn load_stl(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::read_stl(File::open(path)?)
+    }
+
+    pub fn load_stl_with(path: impl AsRef<Path>, options: &ImportOptions) -> Result<Self, Error> {
+        Self::read_stl_with(File::open(path)?, options)
+    }
+
+    /// Reads like `read_stl` and converts the mesh to `options.target` and
+    /// `options.target_units`.
+    pub fn read_stl_with(reader: impl Read, options: &ImportOptions) -> Result<Self, Error> {
+        let mut mesh = Self::read_stl(reader)?;
+        options.convert_mesh(&mut mesh);
+        Ok(mesh)
+    }
+
+    /// Reads an ASCII or binary STL file. Vertices are shared between
+    /// facets with the same position and normal, so the mesh stays flat
+    /// shaded. Facets without a normal get the one of their winding.
+    pub fn read_stl(mut reader: impl Read) -> Result<Self, Error> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let mut facets = Vec::new();
+
+        // Some binary files start with "solid" too, so check the size first.
+        let binary_count = data
+            .get(80..84)
+            .map(|count| u32::from_le_bytes([count[0], count[1], count[2], count[3]]) as usize);
+        let binary_size = binary_count.and_then(|count| count.checked_mul(50)?.checked_add(84));
+        if binary_size == Some(data.len()) {
+            for facet in data[84..].chunks_exact(50) {
+                let floats: Vec<f32> = bytemuck::pod_collect_to_vec(&facet[..48]);
+                let vector = |i: usize| [floats[i], floats[i + 1], floats[i + 2]];
+                facets.push((vector(0), [vector(3), vector(6), vector(9)]));
+            }
+        } else if data.starts_with(b"solid") {
+            let text =
+                std::str::from_utf8(&data).map_err(|_| invalid("ascii data is not utf-8"))?;
+            let mut words = text.split_ascii_whitespace();
+            let vector = |words: &mut std::str::SplitAsciiWhitespace| -> Result<[f32; 3], Error> {
+                let mut v = [0.0; 3];
+                for c in &mut v {
+                    let word = words.next().ok_or_else(|| invalid("truncated data"))?;
+                    *c = word
+                        .parse()
+                        .map_err(|_| invalid(&format!("invalid number {word}")))?;
+                }
+                Ok(v)
+            };
+            let (mut normal, mut vertices) = ([0.0; 3], Vec::new());
+            while let Some(word) = words.next() {
+                match word {
+                    "normal" => normal = vector(&mut words)?,
+                    "vertex" => vertices.push(vector(&mut words)?),
+                    "endfacet" => {
+                        for i in 1..vertices.len().saturating_sub(1) {
+                            facets.push((normal, [vertices[0], vertices[i], vertices[i + 1]]));
+                        }
+                        (normal, vertices) = ([0.0; 3], Vec::new());
+                    }
+                    _ => {}
+                }
+            }
+        } else {
+            return Err(invalid("neither ascii nor binary"));
+        }
+
+        let mut mesh = Mesh::default();
+        let mut lookup = HashMap::new();
+        for (normal, positions) in facets {
+            let length =
+                (normal[0] * normal[0] + normal[1] * normal[1] + normal[2] * normal[2]).sqrt();
+            let normal = if length > 0.0 {
+                normal.map(|c| c / length + 0.0)
+            } else {
+                face_normal(&positions)
+            };
+            for position in positions {
+                let vertex = Vertex {
+                    position,
+                    normal,
+                    uv: [0.0; 2],
+                };
+                let key: [u32; 8] = bytemuck::cast(vertex);
+                let index = *lookup.entry(key).or_insert_with(|| {
+                    mesh.vertices.push(vertex);
+                    mesh.vertices.len() as u32 - 1
+                });
+                mesh.indices.push(index);
+            }
+        }
+        Ok(mesh)
+    }
+
+    pub fn save_stl(&self, path: impl AsRef<Path>, format: StlFormat) -> Result<(), Error> {
+        self.write_stl(BufWriter::new(File::create(path)?), format)
+    }
+
+    /// Writes the triangles with normals computed from their winding.
+    pub fn write_stl(&self, mut writer: impl Write, format: StlFormat) -> Result<(), Error> {
+        let triangles = self
+            .triangles()
+            .map(|triangle| triangle.map(|i| self.vertices[i as usize].position));
+        match format {
+            StlFormat::Ascii => {
+                writeln!(writer, "solid wgpu-tools")?;
+                for triangle in triangles {
+                    let [nx, ny, nz] = face_normal(&triangle);
+                    writeln!(writer, "  facet normal {nx} {ny} {nz}\n    outer loop")?;
+                    for [x, y, z] in triangle {
+                        writeln!(writer, "      vertex {x} {y} {z}")?;
+                    }
+                    writeln!(writer, "    endloop\n  endfacet")?;
+                }
+                writeln!(writer, "endsolid wgpu-tools")?;
+            }
+            StlFormat::Binary => {
+                // The header must not start with "solid".
+                let mut header = [0u8; 80];
+                header[..10].copy_from_slice(b"wgpu-tools");
+                writer.write_all(&header)?;
+                writer.write_all(&(self.triangle_count() as u32).to_le_bytes())?;
+                for triangle in triangles {
+                    let floats = std::iter::once(face_normal(&triangle)).chain(triangle);
+                    for value in floats.flatten() {
+                        writer.write_all(&value.to_le_bytes())?;
+                    }
+                    writer.write_all(&[0, 0])?;
+                }
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quad() -> Mesh {
+        let vertex = |x: f32, y: f32| Vertex {
+            position: [x, y, 0.5],
+            normal: [0.0, 0.0, 1.0],
+            uv: [0.0; 2],
+        };
+        Mesh::new(
+            vec![
+                vertex(0.0, 0.0),
+                vertex(1.0, 0.0),
+                vertex(1.0, 2.0),
+                vertex(0.0, 2.0),
+            ],
+            vec![0, 1, 2, 0, 2, 3],
+        )
+    }
+
+    fn round_trip(format: StlFormat) -> Mesh {
+        let mut data = Vec::new();
+        quad().write_stl(&mut data, format).unwrap();
+        Mesh::read_stl(data.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let mesh = round_trip(StlFormat::Binary);
+        assert_eq!(mesh.vertices, quad().vertices);
+        assert_eq!(mesh.indices, quad().indices);
+    }
+
+    #[test]
+    fn ascii_round_trip() {
+        let mesh = round_trip(StlFormat::Ascii);
+        assert_eq!(mesh.vertices, quad().vertices);
+        assert_eq!(mesh.indices, quad().indices);
+    }
+
+    #[test]
+    fn ascii_facets_without_normals_use_their_winding() {
+        let text = "solid t\nfacet normal 0 0 0\nouter loop\n\
+                    vertex 0 0 0\nvertex 0 1 0\nvertex 1 0 0\n\
+                    endloop\nendfacet\nendsolid t\n";
+        let mesh = Mesh::read_stl(text.as_bytes()).unwrap();
+        assert_eq!(mesh.indices, [0, 1, 2]);
+        assert!(mesh.vertices.iter().all(|v| v.normal == [0.0, 0.0, -1.0]));
+    }
+
+    #[test]
+    fn truncated_data_is_rejected() {
+        let mut data = Vec::new();
+        quad().write_stl(&mut data, StlFormat::Binary).unwrap();
+        assert!(Mesh::read_stl(&data[..data.len() - 1]).is_err());
+        assert!(Mesh::read_stl(&data[..40]).is_err());
+
+        let text = "solid t\nfacet normal 0 0 1\nouter loop\nvertex 0 0";
+        assert!(matches!(
+            Mesh::read_stl(text.as_bytes()),
+            Err(Error::InvalidMesh(message)) if message.contains("truncated")
+        ));
+    }
+
+    #[test]
+    fn huge_facet_counts_are_rejected() {
+        let mut data = vec![0u8; 84];
+        data[80..84].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(Mesh::read_stl(data.as_slice()).is_err());
+    }
+}
@@ -0,0 +1,303 @@
+use super::context::Context;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SubgroupSupport {
+    pub compute: bool,
+    pub vertex: bool,
+    pub barrier: bool,
+    pub min_size: u32,
+    pub max_size: u32,
+}
+
+impl SubgroupSupport {
+    pub fn from_features(features: wgpu::Features, limits: &wgpu::Limits) -> Self {
+        Self {
+            compute: features.contains(wgpu::Features::SUBGROUP),
+            vertex: features.contains(wgpu::Features::SUBGROUP_VERTEX),
+            barrier: features.contains(wgpu::Features::SUBGROUP_BARRIER),
+            min_size: limits.min_subgroup_size,
+            max_size: limits.max_subgroup_size,
+        }
+    }
+
+    pub fn none() -> Self {
+        Self::default()
+    }
+}
+
+impl Context {
+    /// Subgroup support of the device. Adapters may support more than this
+    /// if the features were not requested when the context was created.
+    pub fn subgroup_support(&self) -> SubgroupSupport {
+        SubgroupSupport::from_features(self.device.features(), &self.device.limits())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ReduceOp {
+    Add,
+    Min,
+    Max,
+}
+
+impl ReduceOp {
+    fn name(&self) -> &'static str {
+        match self {
+            ReduceOp::Add => "add",
+            ReduceOp::Min => "min",
+            ReduceOp::Max => "max",
+        }
+    }
+
+    fn subgroup_reduce(&self) -> &'static str {
+        match self {
+            ReduceOp::Add => "subgroupAdd",
+            ReduceOp::Min => "subgroupMin",
+            ReduceOp::Max => "subgroupMax",
+        }
+    }
+
+    fn combine(&self, a: &str, b: &str) -> String {
+        match self {
+            ReduceOp::Add => format!("({a} + {b})"),
+            ReduceOp::Min => format!("min({a}, {b})"),
+            ReduceOp::Max => format!("max({a}, {b})"),
+        }
+    }
+
+    fn identity(&self, scalar: WgslScalar) -> &'static str {
+        match (self, scalar) {
+            (ReduceOp::Add, WgslScalar::F32) => "0.0",
+            (ReduceOp::Add, WgslScalar::U32) => "0u",
+            (ReduceOp::Add, WgslScalar::I32) => "0i",
+            (ReduceOp::Min, WgslScalar::F32) => "3.40282347e38",
+            (ReduceOp::Min, WgslScalar::U32) => "4294967295u",
+            (ReduceOp::Min, WgslScalar::I32) => "2147483647i",
+            (ReduceOp::Max, WgslScalar::F32) => "-3.40282347e38",
+            (ReduceOp::Max, WgslScalar::U32) => "0u",
+            (ReduceOp::Max, WgslScalar::I32) => "(-2147483647i - 1i)",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WgslScalar {
+    F32,
+    U32,
+    I32,
+}
+
+impl WgslScalar {
+    fn name(&self) -> &'static str {
+        match self {
+            WgslScalar::F32 => "f32",
+            WgslScalar::U32 => "u32",
+            WgslScalar::I32 => "i32",
+        }
+    }
+}
+
+/// Generates WGSL workgroup reductions and inclusive scans, using subgroup
+/// operations when the device supports them and shared memory otherwise.
+///
+/// The generated source declares a `WorkgroupInvocation` struct that the
+/// entry point must take as an argument and pass to every
+/// `workgroup_reduce_<op>_<scalar>` / `workgroup_scan_<op>_<scalar>` call.
+/// All invocations of the workgroup must call these functions uniformly,
+/// and the entry point must use `workgroup_size()` invocations.
+pub struct WorkgroupPrimitives {
+    subgroups: bool,
+    workgroup_size: u32,
+    functions: Vec<String>,
+}
+
+impl WorkgroupPrimitives {
+    pub fn new(support: &SubgroupSupport, workgroup_size: u32) -> Self {
+        Self {
+            subgroups: support.compute,
+            workgroup_size: workgroup_size.next_power_of_two(),
+            functions: Vec::new(),
+        }
+    }
+
+    pub fn shared_memory(workgroup_size: u32) -> Self {
+        Self::new(&SubgroupSupport::none(), workgroup_size)
+    }
+
+    pub fn uses_subgroups(&self) -> bool {
+        self.subgroups
+    }
+
+    pub fn workgroup_size(&self) -> u32 {
+        self.workgroup_size
+    }
+
+    pub fn reduce(mut self, op: ReduceOp, scalar: WgslScalar) -> Self {
+        let function = if self.subgroups {
+            self.subgroup_reduce(op, scalar)
+        } else {
+            self.shared_reduce(op, scalar)
+        };
+        self.functions.push(function);
+        self
+    }
+
+    pub fn scan(mut self, op: ReduceOp, scalar: WgslScalar) -> Self {
+        // Subgroup scans only exist for addition.
+        let function = if self.subgroups && op == ReduceOp::Add {
+            self.subgroup_scan(op, scalar)
+        } else {
+            self.shared_scan(op, scalar)
+        };
+        self.functions.push(function);
+        self
+    }
+
+    pub fn build(&self) -> String {
+        let invocation = if self.subgroups {
+            "struct WorkgroupInvocation {
+    @builtin(local_invocation_index) local_index: u32,
+    @builtin(subgroup_invocation_id) lane: u32,
+    @builtin(subgroup_size) lane_count: u32,
+    @builtin(subgroup_id) subgroup: u32,
+    @builtin(num_subgroups) subgroup_count: u32,
+}
+"
+        } else {
+            "struct WorkgroupInvocation {
+    @builtin(local_invocation_index) local_index: u32,
+}
+"
+        };
+
+        let mut source = String::from(invocation);
+        for function in &self.functions {
+            source.push('\n');
+            source.push_str(function);
+        }
+        source
+    }
+
+    fn subgroup_reduce(&self, op: ReduceOp, scalar: WgslScalar) -> String {
+        let (name, ty, size) = (
+            self.name("reduce", op, scalar),
+            scalar.name(),
+            self.workgroup_size,
+        );
+        let identity = op.identity(scalar);
+        let combine = op.combine("total", &format!("{name}_scratch[i]"));
+        let subgroup_reduce = op.subgroup_reduce();
+        format!(
+            "var<workgroup> {name}_scratch: array<{ty}, {size}>;
+
+fn {name}(value: {ty}, invocation: WorkgroupInvocation) -> {ty} {{
+    let partial = {subgroup_reduce}(value);
+    if (invocation.lane == 0u) {{
+        {name}_scratch[invocation.subgroup] = partial;
+    }}
+    workgroupBarrier();
+    var total: {ty} = {identity};
+    for (var i = 0u; i < invocation.subgroup_count; i++) {{
+        total = {combine};
+    }}
+    workgroupBarrier();
+    return total;
+}}
+"
+        )
+    }
+
+    fn shared_reduce(&self, op: ReduceOp, scalar: WgslScalar) -> String {
+        let (name, ty, size) = (
+            self.name("reduce", op, scalar),
+            scalar.name(),
+            self.workgroup_size,
+        );
+        let combine = op.combine(
+            &format!("{name}_scratch[i]"),
+            &format!("{name}_scratch[i + stride]"),
+        );
+        format!(
+            "var<workgroup> {name}_scratch: array<{ty}, {size}>;
+
+fn {name}(value: {ty}, invocation: WorkgroupInvocation) -> {ty} {{
+    let i = invocation.local_index;
+    {name}_scratch[i] = value;
+    workgroupBarrier();
+    for (var stride = {size}u / 2u; stride > 0u; stride = stride / 2u) {{
+        if (i < stride) {{
+            {name}_scratch[i] = {combine};
+        }}
+        workgroupBarrier();
+    }}
+    let total = {name}_scratch[0];
+    workgroupBarrier();
+    return total;
+}}
+"
+        )
+    }
+
+    fn subgroup_scan(&self, op: ReduceOp, scalar: WgslScalar) -> String {
+        let (name, ty, size) = (
+            self.name("scan", op, scalar),
+            scalar.name(),
+            self.workgroup_size,
+        );
+        let identity = op.identity(scalar);
+        format!(
+            "var<workgroup> {name}_scratch: array<{ty}, {size}>;
+
+fn {name}(value: {ty}, invocation: WorkgroupInvocation) -> {ty} {{
+    let prefix = subgroupInclusiveAdd(value);
+    if (invocation.lane == invocation.lane_count - 1u) {{
+        {name}_scratch[invocation.subgroup] = prefix;
+    }}
+    workgroupBarrier();
+    var offset: {ty} = {identity};
+    for (var i = 0u; i < invocation.subgroup; i++) {{
+        offset += {name}_scratch[i];
+    }}
+    workgroupBarrier();
+    return prefix + offset;
+}}
+"
+        )
+    }
+
+    fn shared_scan(&self, op: ReduceOp, scalar: WgslScalar) -> String {
+        let (name, ty, size) = (
+            self.name("scan", op, scalar),
+            scalar.name(),
+            self.workgroup_size,
+        );
+        let identity = op.identity(scalar);
+        let combine = op.combine(&format!("{name}_scratch[i]"), "addend");
+        format!(
+            "var<workgroup> {name}_scratch: array<{ty}, {size}>;
+
+fn {name}(value: {ty}, invocation: WorkgroupInvocation) -> {ty} {{
+    let i = invocation.local_index;
+    {name}_scratch[i] = value;
+    workgroupBarrier();
+    for (var offset = 1u; offset < {size}u; offset = offset * 2u) {{
+        var addend: {ty} = {identity};
+        if (i >= offset) {{
+            addend = {name}_scratch[i - offset];
+        }}
+        workgroupBarrier();
+        {name}_scratch[i] = {combine};
+        workgroupBarrier();
+    }}
+    let result = {name}_scratch[i];
+    workgroupBarrier();
+    return result;
+}}
+"
+        )
+    }
+
+    fn name(&self, kind: &str, op: ReduceOp, scalar: WgslScalar) -> String {
+        format!("workgroup_{kind}_{}_{}", op.name(), scalar.name())
+    }
+}
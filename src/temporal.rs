@@ -0,0 +1,198 @@
+use super::context::Context;
+use super::error::Error;
+use super::math::{invert, multiply};
+use super::texture::Texture;
+
+const MOTION_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rg16Float;
+
+/// Jitter sequence length, enough samples for a well-distributed pattern
+/// without visible cycling.
+const JITTER_PHASES: u64 = 16;
+
+const IDENTITY: [[f32; 4]; 4] = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+/// History targets shared by temporal techniques such as TAA, temporal
+/// upscaling and temporal denoisers: the color and depth of the current and
+/// the previous frame, the current motion vectors, and the matrices to
+/// reproject between the frames.
+///
+/// Each frame, call `begin_frame` with the unjittered view-projection,
+/// render into `color`, `depth` and `motion`, and read the previous frame
+/// through `history_color` and `history_depth`. These are `None` on the
+/// first frame and after `resize` or `invalidate`, e.g. on camera cuts.
+pub struct TemporalResources {
+    colors: [Texture; 2],
+    depths: [Texture; 2],
+    motion: Texture,
+    current: usize,
+    frame_index: u64,
+    has_history: bool,
+    invalidated: bool,
+    view_projection: [[f32; 4]; 4],
+    previous_view_projection: [[f32; 4]; 4],
+}
+
+impl TemporalResources {
+    /// Targets for `width` x `height` frames with color in
+    /// `texture_format`, depth in `Texture::DEPTH_FORMAT` and motion
+    /// vectors in `Rg16Float`.
+    pub fn new(
+        context: &Context,
+        width: &u32,
+        height: &u32,
+        texture_format: &wgpu::TextureFormat,
+    ) -> Result<Self, Error> {
+        let (colors, depths, motion) = Self::targets(context, width, height, texture_format)?;
+        Ok(Self {
+            colors,
+            depths,
+            motion,
+            current: 0,
+            frame_index: 0,
+            has_history: false,
+            invalidated: true,
+            view_projection: IDENTITY,
+            previous_view_projection: IDENTITY,
+        })
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn targets(
+        context: &Context,
+        width: &u32,
+        height: &u32,
+        texture_format: &wgpu::TextureFormat,
+    ) -> Result<([Texture; 2], [Texture; 2], Texture), Error> {
+        let target = |format, label| context.render_target(width, height, format, Some(label));
+        Ok((
+            [
+                target(texture_format, "Temporal Color 0")?,
+                target(texture_format, "Temporal Color 1")?,
+            ],
+            [
+                target(&Texture::DEPTH_FORMAT, "Temporal Depth 0")?,
+                target(&Texture::DEPTH_FORMAT, "Temporal Depth 1")?,
+            ],
+            target(&MOTION_FORMAT, "Temporal Motion")?,
+        ))
+    }
+
+    /// Starts a frame seen through `view_projection`, making the targets of
+    /// the last frame the history.
+    pub fn begin_frame(&mut self, view_projection: &[[f32; 4]; 4]) {
+        self.current ^= 1;
+        self.has_history = !self.invalidated;
+        self.invalidated = false;
+        self.previous_view_projection = self.view_projection;
+        self.view_projection = *view_projection;
+        self.frame_index += 1;
+    }
+
+    /// Drops the history, so that the next frame starts without one, for
+    /// camera cuts and teleports.
+    pub fn invalidate(&mut self) {
+        self.invalidated = true;
+    }
+
+    /// Recreates the targets for `width` x `height` frames, dropping the
+    /// history, if the size changed.
+    pub fn resize(&mut self, context: &Context, width: &u32, height: &u32) -> Result<(), Error> {
+        if self.size() != (*width, *height) {
+            (self.colors, self.depths, self.motion) =
+                Self::targets(context, width, height, &self.colors[0].format())?;
+            self.has_history = false;
+            self.invalidated = true;
+        }
+        Ok(())
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.motion.width(), self.motion.height())
+    }
+
+    /// Frames started so far.
+    pub fn frame_index(&self) -> u64 {
+        self.frame_index
+    }
+
+    pub fn color(&self) -> &Texture {
+        &self.colors[self.current]
+    }
+
+    pub fn depth(&self) -> &Texture {
+        &self.depths[self.current]
+    }
+
+    /// Screen-space motion of each pixel since the last frame, in UV units.
+    pub fn motion(&self) -> &Texture {
+        &self.motion
+    }
+
+    pub fn has_history(&self) -> bool {
+        self.has_history
+    }
+
+    /// The color of the last frame, if it is a valid history.
+    pub fn history_color(&self) -> Option<&Texture> {
+        self.has_history.then(|| &self.colors[self.current ^ 1])
+    }
+
+    /// The depth of the last frame, if it is a valid history.
+    pub fn history_depth(&self) -> Option<&Texture> {
+        self.has_history.then(|| &self.depths[self.current ^ 1])
+    }
+
+    pub fn view_projection(&self) -> [[f32; 4]; 4] {
+        self.view_projection
+    }
+
+    pub fn previous_view_projection(&self) -> [[f32; 4]; 4] {
+        self.previous_view_projection
+    }
+
+    /// Maps clip-space positions of this frame to the clip space of the
+    /// last frame, for reprojecting from depth alone (static geometry).
+    /// `None` without a history or for a singular view-projection.
+    pub fn reprojection(&self) -> Option<[[f32; 4]; 4]> {
+        if !self.has_history {
+            return None;
+        }
+        Some(multiply(
+            &self.previous_view_projection,
+            &invert(&self.view_projection)?,
+        ))
+    }
+
+    /// This frame's subpixel jitter, in pixels within (-0.5, 0.5), from a
+    /// Halton (2, 3) sequence.
+    pub fn jitter(&self) -> [f32; 2] {
+        let index = self.frame_index % JITTER_PHASES + 1;
+        [halton(index, 2) - 0.5, halton(index, 3) - 0.5]
+    }
+
+    /// `view_projection` offset by `jitter`, to render the frame with.
+    pub fn jittered(&self, view_projection: &[[f32; 4]; 4]) -> [[f32; 4]; 4] {
+        let (width, height) = self.size();
+        let [x, y] = self.jitter();
+        let mut offset = IDENTITY;
+        offset[3][0] = 2.0 * x / width as f32;
+        offset[3][1] = -2.0 * y / height as f32;
+        multiply(&offset, view_projection)
+    }
+}
+
+fn halton(mut index: u64, base: u64) -> f32 {
+    let mut result = 0.0;
+    let mut fraction = 1.0;
+    while index > 0 {
+        fraction /= base as f32;
+        result += fraction * (index % base) as f32;
+        index /= base;
+    }
+    result
+}
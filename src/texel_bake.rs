@@ -0,0 +1,348 @@
+use super::bvh::Bvh;
+use super::context::Context;
+use super::error::Error;
+use super::math::{cross, dot, normalize, sub};
+use super::mesh::Mesh;
+use super::progress::Progress;
+use super::texture::Texture;
+use wgpu::util::DeviceExt;
+
+pub(crate) const WORKGROUP_SIZE: u32 = 8;
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    size: [u32; 2],
+    scale: f32,
+    _padding: u32,
+}
+
+/// The texels of a mesh's `uv2` space and a BVH of the scene around it,
+/// uploaded for a bake that sums one value per texel into `accumulation`.
+pub(crate) struct TexelBake {
+    pub(crate) resolution: u32,
+    /// Distance rays start off the surface.
+    pub(crate) bias: f32,
+    pub(crate) positions: wgpu::Buffer,
+    pub(crate) normals: wgpu::Buffer,
+    pub(crate) accumulation: wgpu::Buffer,
+    pub(crate) bvh_group: wgpu::BindGroup,
+}
+
+impl TexelBake {
+    /// Unwraps `mesh` with `padding` texels between charts if it has no
+    /// `uv2`, and rasterizes it at `resolution`.
+    pub(crate) fn new(
+        context: &Context,
+        bvh_layout: &wgpu::BindGroupLayout,
+        mesh: &mut Mesh,
+        occluders: &[&Mesh],
+        resolution: u32,
+        padding: u32,
+        progress: &mut Progress,
+    ) -> Result<Self, Error> {
+        if resolution == 0 {
+            return Err(Error::TextureCreationFailed);
+        }
+        if mesh.indices.is_empty() {
+            return Err(Error::InvalidMesh("no triangles to bake".to_string()));
+        }
+        if mesh.uv2.is_empty() {
+            mesh.unwrap_lightmap_uvs(resolution, padding);
+        }
+        if mesh.uv2.len() != mesh.vertices.len() {
+            return Err(Error::InvalidMesh(format!(
+                "{} lightmap UVs for {} vertices",
+                mesh.uv2.len(),
+                mesh.vertices.len()
+            )));
+        }
+        context.check_texture_size(
+            &wgpu::Extent3d {
+                width: resolution,
+                height: resolution,
+                depth_or_array_layers: 1,
+            },
+            wgpu::TextureDimension::D2,
+        )?;
+        let texel_buffer_size = resolution as u64 * resolution as u64 * 16;
+        context.check_buffer_size(texel_buffer_size)?;
+        context.check_binding_size(texel_buffer_size, true)?;
+
+        let bvh = Bvh::new(
+            std::iter::once(&*mesh).chain(occluders.iter().copied()),
+            progress,
+        )?;
+        let bias = mesh
+            .bounds()
+            .map_or(0.0, |bounds| {
+                let size = bounds.size();
+                dot(size, size).sqrt()
+            })
+            .max(1e-3)
+            * 1e-4;
+        let (positions, normals) = rasterize(mesh, resolution, bias);
+
+        let device = &context.device;
+        let storage = |label, contents: &[u8]| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents,
+                usage: wgpu::BufferUsages::STORAGE,
+            })
+        };
+        Ok(Self {
+            resolution,
+            bias,
+            positions: storage("Bake Positions", bytemuck::cast_slice(&positions)),
+            normals: storage("Bake Normals", bytemuck::cast_slice(&normals)),
+            accumulation: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Bake Accumulation"),
+                size: texel_buffer_size,
+                usage: wgpu::BufferUsages::STORAGE,
+                mapped_at_creation: false,
+            }),
+            bvh_group: bvh.bind_group(context, bvh_layout)?,
+        })
+    }
+
+    pub(crate) fn workgroups(&self) -> u32 {
+        self.resolution.div_ceil(WORKGROUP_SIZE)
+    }
+}
+
+/// World positions, offset by `bias` off the surface, and normals of the
+/// texel centers covered by the mesh in `uv2` space.
+fn rasterize(mesh: &Mesh, resolution: u32, bias: f32) -> (Vec<[f32; 4]>, Vec<[f32; 4]>) {
+    let texels = resolution as usize * resolution as usize;
+    let mut positions = vec![[0.0; 4]; texels];
+    let mut normals = vec![[0.0; 4]; texels];
+    let edge = |a: [f32; 2], b: [f32; 2], p: [f32; 2]| {
+        (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+    };
+    for [a, b, c] in mesh.triangles() {
+        let [va, vb, vc] = [a, b, c].map(|i| mesh.vertices[i as usize]);
+        let [ta, tb, tc] = [a, b, c].map(|i| mesh.uv2[i as usize].map(|t| t * resolution as f32));
+        let area = edge(ta, tb, tc);
+        if area.abs() < 1e-12 {
+            continue;
+        }
+        let mut geometric = normalize(cross(
+            sub(vb.position, va.position),
+            sub(vc.position, va.position),
+        ));
+        let lower = |i: usize| (ta[i].min(tb[i]).min(tc[i]) - 0.5).max(0.0) as u32;
+        let upper = |i: usize| {
+            ((ta[i].max(tb[i]).max(tc[i]) - 0.5).ceil().max(0.0) as u32).min(resolution - 1)
+        };
+        for y in lower(1)..=upper(1) {
+            for x in lower(0)..=upper(0) {
+                let p = [x as f32 + 0.5, y as f32 + 0.5];
+                let weights = [edge(tb, tc, p), edge(tc, ta, p), edge(ta, tb, p)].map(|w| w / area);
+                if weights.iter().any(|&w| w < -1e-4) {
+                    continue;
+                }
+                let blend = |values: [[f32; 3]; 3]| {
+                    [0, 1, 2].map(|i| {
+                        values[0][i] * weights[0]
+                            + values[1][i] * weights[1]
+                            + values[2][i] * weights[2]
+                    })
+                };
+                let position = blend([va.position, vb.position, vc.position]);
+                let mut normal = normalize(blend([va.normal, vb.normal, vc.normal]));
+                if normal == [0.0; 3] {
+                    normal = geometric;
+                }
+                if dot(geometric, normal) < 0.0 {
+                    geometric = geometric.map(|g| -g);
+                }
+                let index = y as usize * resolution as usize + x as usize;
+                positions[index] = [
+                    position[0] + geometric[0] * bias,
+                    position[1] + geometric[1] * bias,
+                    position[2] + geometric[2] * bias,
+                    1.0,
+                ];
+                normals[index] = [normal[0], normal[1], normal[2], 0.0];
+            }
+        }
+    }
+    (positions, normals)
+}
+
+/// The passes after the samples: scaling the sums, dilating charts and
+/// storing the result into an `Rgba16Float` texture.
+pub(crate) struct TexelPost {
+    layout: wgpu::BindGroupLayout,
+    resolve: wgpu::ComputePipeline,
+    dilate: wgpu::ComputePipeline,
+    store: wgpu::ComputePipeline,
+}
+
+impl TexelPost {
+    pub(crate) fn new(context: &Context) -> Self {
+        let device = &context.device;
+        let module = device.create_shader_module(wgpu::include_wgsl!("shaders/texel_bake.wgsl"));
+        let entry = |binding, ty| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty,
+            count: None,
+        };
+        let storage = |read_only| wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        };
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Texel Bake Bind Group Layout"),
+            entries: &[
+                entry(
+                    0,
+                    wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                ),
+                entry(1, storage(true)),
+                entry(2, storage(true)),
+                entry(3, storage(false)),
+                entry(
+                    4,
+                    wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: wgpu::TextureFormat::Rgba16Float,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                ),
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Texel Bake Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &module,
+                entry_point,
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            })
+        };
+        Self {
+            resolve: pipeline("resolve"),
+            dilate: pipeline("dilate"),
+            store: pipeline("store"),
+            layout,
+        }
+    }
+
+    /// Scales the sums of `bake` by `scale`, grows the charts by
+    /// `dilation` texels and returns them with the coverage in alpha.
+    pub(crate) fn finish(
+        &self,
+        context: &Context,
+        bake: &TexelBake,
+        scale: f32,
+        dilation: u32,
+        label: &str,
+    ) -> Texture {
+        let device = &context.device;
+        let resolution = bake.resolution;
+        let texture = Texture::from_wgpu(
+            context,
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width: resolution,
+                    height: resolution,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba16Float,
+                view_formats: &[],
+                usage: wgpu::TextureUsages::STORAGE_BINDING
+                    | wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::COPY_SRC,
+            }),
+        );
+        let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Texel Bake Params"),
+            contents: bytemuck::bytes_of(&Params {
+                size: [resolution, resolution],
+                scale,
+                _padding: 0,
+            }),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let texel_buffer = |label| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: bake.accumulation.size(),
+                usage: wgpu::BufferUsages::STORAGE,
+                mapped_at_creation: false,
+            })
+        };
+        let ping = texel_buffer("Texel Bake Ping");
+        let pong = texel_buffer("Texel Bake Pong");
+        let bind_group = |source: &wgpu::Buffer, destination: &wgpu::Buffer| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Texel Bake Bind Group"),
+                layout: &self.layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: params.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: bake.positions.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: source.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: destination.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: wgpu::BindingResource::TextureView(&texture.view),
+                    },
+                ],
+            })
+        };
+        let mut passes = vec![(&self.resolve, bind_group(&bake.accumulation, &ping))];
+        let (mut source, mut destination) = (&ping, &pong);
+        for _ in 0..dilation {
+            passes.push((&self.dilate, bind_group(source, destination)));
+            std::mem::swap(&mut source, &mut destination);
+        }
+        passes.push((&self.store, bind_group(source, destination)));
+
+        let mut command_encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Texel Bake Encoder"),
+        });
+        {
+            let mut pass = command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Texel Bake"),
+                timestamp_writes: None,
+            });
+            let workgroups = bake.workgroups();
+            for (pipeline, bind_group) in &passes {
+                pass.set_pipeline(pipeline);
+                pass.set_bind_group(0, bind_group, &[]);
+                pass.dispatch_workgroups(workgroups, workgroups, 1);
+            }
+        }
+        context.submit(command_encoder);
+        texture
+    }
+}
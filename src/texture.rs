@@ -1,10 +1,366 @@
+use super::context::Context;
+use super::error::Error;
+use std::sync::OnceLock;
+
 #[derive(Debug)]
 pub struct Texture {
     pub texture: wgpu::Texture,
     pub view: wgpu::TextureView,
     pub sampler: wgpu::Sampler,
+    view_dimension: wgpu::TextureViewDimension,
+    views: SubresourceViews,
+}
+
+type ViewTable = OnceLock<Box<[OnceLock<wgpu::TextureView>]>>;
+
+/// Views of single mip levels and layers, created on first use.
+#[derive(Debug, Default)]
+struct SubresourceViews {
+    mips: ViewTable,
+    layers: ViewTable,
+    subresources: ViewTable,
+    storage: ViewTable,
 }
 
 impl Texture {
     pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+    pub const DEPTH_STENCIL_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth24PlusStencil8;
+
+    pub fn from_raw(
+        texture: wgpu::Texture,
+        view: wgpu::TextureView,
+        sampler: wgpu::Sampler,
+    ) -> Self {
+        let view_dimension =
+            default_view_dimension(texture.dimension(), texture.depth_or_array_layers());
+        Self {
+            texture,
+            view,
+            sampler,
+            view_dimension,
+            views: SubresourceViews::default(),
+        }
+    }
+
+    /// Wraps an externally created texture with a default view and the
+    /// crate's default linear sampler.
+    pub fn from_wgpu(context: &Context, texture: wgpu::Texture) -> Self {
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = context.linear_sampler();
+        Self::from_raw(texture, view, sampler)
+    }
+
+    pub fn size(&self) -> wgpu::Extent3d {
+        self.texture.size()
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.texture.format()
+    }
+
+    pub fn mip_level_count(&self) -> u32 {
+        self.texture.mip_level_count()
+    }
+
+    pub fn width(&self) -> u32 {
+        self.texture.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.texture.height()
+    }
+
+    pub fn depth_or_array_layers(&self) -> u32 {
+        self.texture.depth_or_array_layers()
+    }
+
+    pub fn dimension(&self) -> wgpu::TextureDimension {
+        self.texture.dimension()
+    }
+
+    pub fn usage(&self) -> wgpu::TextureUsages {
+        self.texture.usage()
+    }
+
+    pub fn sample_count(&self) -> u32 {
+        self.texture.sample_count()
+    }
+
+    pub fn view_dimension(&self) -> wgpu::TextureViewDimension {
+        self.view_dimension
+    }
+
+    /// Estimated memory of all mip levels, layers and samples. Formats
+    /// without a defined texel size (`Depth24Plus`) are counted as 4 bytes.
+    pub fn memory_size(&self) -> u64 {
+        let (format, dimension, size) = (self.format(), self.dimension(), self.size());
+        let (block_width, block_height) = format.block_dimensions();
+        let block_size = format.block_copy_size(None).unwrap_or(4) as u64;
+        let blocks: u64 = (0..self.mip_level_count())
+            .map(|level| {
+                let mip = size.mip_level_size(level, dimension).physical_size(format);
+                (mip.width / block_width) as u64
+                    * (mip.height / block_height) as u64
+                    * mip.depth_or_array_layers as u64
+            })
+            .sum();
+        blocks * block_size * self.sample_count() as u64
+    }
+
+    /// Swaps in `texture` with a default view in the current view
+    /// dimension, dropping the cached subresource views of the old one.
+    pub(crate) fn replace(&mut self, texture: wgpu::Texture) {
+        self.view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(self.view_dimension),
+            ..Default::default()
+        });
+        self.texture = texture;
+        self.views = SubresourceViews::default();
+    }
+
+    pub fn with_view_dimension(mut self, view_dimension: wgpu::TextureViewDimension) -> Self {
+        self.view_dimension = view_dimension;
+        self.views = SubresourceViews::default();
+        self
+    }
+
+    /// A view of mip level `level` with all layers, in the texture's view
+    /// dimension, for sampling one level of a mip chain.
+    pub fn mip_view(&self, level: u32) -> Result<&wgpu::TextureView, Error> {
+        let levels = self.mip_level_count();
+        check_subresource("mip level", level, levels)?;
+        Ok(self.cached_view(&self.views.mips, levels, level, || {
+            wgpu::TextureViewDescriptor {
+                label: Some("Mip View"),
+                dimension: Some(self.view_dimension),
+                base_mip_level: level,
+                mip_level_count: Some(1),
+                ..Default::default()
+            }
+        }))
+    }
+
+    /// A 2D view of layer `layer` with all mip levels, such as a face of a
+    /// cubemap or a slice of a 2D array. Fails for 1D and 3D textures.
+    pub fn layer_view(&self, layer: u32) -> Result<&wgpu::TextureView, Error> {
+        let layers = self.layer_count()?;
+        check_subresource("layer", layer, layers)?;
+        Ok(self.cached_view(&self.views.layers, layers, layer, || {
+            wgpu::TextureViewDescriptor {
+                label: Some("Layer View"),
+                dimension: Some(wgpu::TextureViewDimension::D2),
+                base_array_layer: layer,
+                array_layer_count: Some(1),
+                ..Default::default()
+            }
+        }))
+    }
+
+    /// A 2D view of mip level `level` of layer `layer`, for rendering into
+    /// one level of a face or slice. Fails for 1D and 3D textures.
+    pub fn subresource_view(&self, level: u32, layer: u32) -> Result<&wgpu::TextureView, Error> {
+        let (levels, layers) = (self.mip_level_count(), self.layer_count()?);
+        check_subresource("mip level", level, levels)?;
+        check_subresource("layer", layer, layers)?;
+        Ok(self.cached_view(
+            &self.views.subresources,
+            levels * layers,
+            layer * levels + level,
+            || wgpu::TextureViewDescriptor {
+                label: Some("Subresource View"),
+                dimension: Some(wgpu::TextureViewDimension::D2),
+                base_mip_level: level,
+                mip_level_count: Some(1),
+                base_array_layer: layer,
+                array_layer_count: Some(1),
+                ..Default::default()
+            },
+        ))
+    }
+
+    /// A view of mip level `level` with all layers for binding as a storage
+    /// texture, which takes a single level. Cubemaps are viewed as 2D
+    /// arrays of their faces. The texture needs `STORAGE_BINDING` usage.
+    pub fn storage_view(&self, level: u32) -> Result<&wgpu::TextureView, Error> {
+        if !self.usage().contains(wgpu::TextureUsages::STORAGE_BINDING) {
+            return Err(Error::InvalidTexture(
+                "storage views need STORAGE_BINDING usage".into(),
+            ));
+        }
+        let levels = self.mip_level_count();
+        check_subresource("mip level", level, levels)?;
+        let dimension = match self.view_dimension {
+            wgpu::TextureViewDimension::Cube | wgpu::TextureViewDimension::CubeArray => {
+                wgpu::TextureViewDimension::D2Array
+            }
+            dimension => dimension,
+        };
+        Ok(self.cached_view(&self.views.storage, levels, level, || {
+            wgpu::TextureViewDescriptor {
+                label: Some("Storage View"),
+                dimension: Some(dimension),
+                base_mip_level: level,
+                mip_level_count: Some(1),
+                ..Default::default()
+            }
+        }))
+    }
+
+    /// Layers of 2D textures, which 1D and 3D textures have none of.
+    fn layer_count(&self) -> Result<u32, Error> {
+        match self.dimension() {
+            wgpu::TextureDimension::D2 => Ok(self.depth_or_array_layers()),
+            dimension => Err(Error::InvalidTexture(format!(
+                "{dimension:?} textures have no layers"
+            ))),
+        }
+    }
+
+    fn cached_view<'a>(
+        &'a self,
+        table: &'a ViewTable,
+        len: u32,
+        index: u32,
+        descriptor: impl FnOnce() -> wgpu::TextureViewDescriptor<'static>,
+    ) -> &'a wgpu::TextureView {
+        table.get_or_init(|| (0..len).map(|_| OnceLock::new()).collect())[index as usize]
+            .get_or_init(|| self.texture.create_view(&descriptor()))
+    }
+
+    /// Uploads the tightly packed rows of `data` into the `extent` region
+    /// of mip level 0 at `origin`, for dynamic atlases and streamed tiles
+    /// that change a part of the texture. The region has to lie inside the
+    /// texture and, for block-compressed formats, start and end on block
+    /// boundaries. The texture needs `COPY_DST` usage.
+    pub fn write_region(
+        &self,
+        context: &Context,
+        origin: wgpu::Origin3d,
+        extent: wgpu::Extent3d,
+        data: &[u8],
+    ) -> Result<(), Error> {
+        let size = self.size();
+        let inside = |origin: u32, extent: u32, size: u32| {
+            origin.checked_add(extent).is_some_and(|end| end <= size)
+        };
+        if !inside(origin.x, extent.width, size.width)
+            || !inside(origin.y, extent.height, size.height)
+            || !inside(
+                origin.z,
+                extent.depth_or_array_layers,
+                size.depth_or_array_layers,
+            )
+        {
+            return Err(Error::RegionOutOfBounds {
+                origin,
+                extent,
+                size,
+            });
+        }
+        if !self.usage().contains(wgpu::TextureUsages::COPY_DST) {
+            return Err(Error::InvalidTexture(
+                "region writes need COPY_DST usage".into(),
+            ));
+        }
+        if self.sample_count() > 1 {
+            return Err(Error::InvalidTexture(
+                "multisampled textures cannot be written".into(),
+            ));
+        }
+        let format = self.format();
+        let (block_width, block_height) = format.block_dimensions();
+        let aligned = |value: u32, block: u32| value.is_multiple_of(block);
+        if !aligned(origin.x, block_width)
+            || !aligned(origin.y, block_height)
+            || !aligned(extent.width, block_width)
+            || !aligned(extent.height, block_height)
+        {
+            return Err(Error::InvalidTexture(format!(
+                "region of {}x{} at {}x{} is not aligned to the {block_width}x{block_height} blocks of {format:?}",
+                extent.width, extent.height, origin.x, origin.y
+            )));
+        }
+        let layout = context.image_layout(format, extent)?;
+        layout.check_data(data, true)?;
+        context.queue.write_texture(
+            wgpu::ImageCopyTexture {
+                aspect: wgpu::TextureAspect::All,
+                texture: &self.texture,
+                mip_level: 0,
+                origin,
+            },
+            data,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(layout.bytes_per_row),
+                rows_per_image: Some(layout.rows_per_image),
+            },
+            extent,
+        );
+        Ok(())
+    }
+}
+
+pub(crate) fn check_subresource(kind: &'static str, index: u32, count: u32) -> Result<(), Error> {
+    match index < count {
+        true => Ok(()),
+        false => Err(Error::SubresourceOutOfRange { kind, index, count }),
+    }
+}
+
+/// The view dimension wgpu picks for a default view of a texture.
+pub(crate) fn default_view_dimension(
+    dimension: wgpu::TextureDimension,
+    depth_or_array_layers: u32,
+) -> wgpu::TextureViewDimension {
+    match dimension {
+        wgpu::TextureDimension::D1 => wgpu::TextureViewDimension::D1,
+        wgpu::TextureDimension::D2 if depth_or_array_layers > 1 => {
+            wgpu::TextureViewDimension::D2Array
+        }
+        wgpu::TextureDimension::D2 => wgpu::TextureViewDimension::D2,
+        wgpu::TextureDimension::D3 => wgpu::TextureViewDimension::D3,
+    }
+}
+
+impl From<Texture> for wgpu::Texture {
+    fn from(texture: Texture) -> Self {
+        texture.texture
+    }
+}
+
+impl From<Texture> for (wgpu::Texture, wgpu::TextureView, wgpu::Sampler) {
+    fn from(texture: Texture) -> Self {
+        (texture.texture, texture.view, texture.sampler)
+    }
+}
+
+impl AsRef<wgpu::Texture> for Texture {
+    fn as_ref(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+}
+
+impl AsRef<wgpu::TextureView> for Texture {
+    fn as_ref(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+}
+
+impl AsRef<wgpu::Sampler> for Texture {
+    fn as_ref(&self) -> &wgpu::Sampler {
+        &self.sampler
+    }
+}
+
+impl<'a> From<&'a Texture> for wgpu::BindingResource<'a> {
+    fn from(texture: &'a Texture) -> Self {
+        wgpu::BindingResource::TextureView(&texture.view)
+    }
+}
+
+impl<'a> From<&'a Texture> for wgpu::ImageCopyTexture<'a> {
+    fn from(texture: &'a Texture) -> Self {
+        texture.texture.as_image_copy()
+    }
 }
@@ -0,0 +1,257 @@
+use super::context::Context;
+use super::error::Error;
+use super::sampler::SamplerBuilder;
+use super::texture::{default_view_dimension, Texture};
+
+/// Full control over a texture's descriptor, for textures the other
+/// constructors do not cover, such as storage textures, 1D textures,
+/// mip chains filled by hand or textures viewed in other formats. Builds
+/// a `Texture` with a view of all levels and layers and a sampler.
+#[derive(Clone, Debug)]
+pub struct TextureBuilder<'a> {
+    label: Option<&'a str>,
+    size: wgpu::Extent3d,
+    format: wgpu::TextureFormat,
+    dimension: wgpu::TextureDimension,
+    mip_level_count: Option<u32>,
+    sample_count: u32,
+    usage: wgpu::TextureUsages,
+    view_formats: &'a [wgpu::TextureFormat],
+    view_dimension: Option<wgpu::TextureViewDimension>,
+    sampler: Option<SamplerBuilder<'a>>,
+}
+
+impl<'a> TextureBuilder<'a> {
+    /// A single-sample 2D texture with one level, sampled and written by
+    /// uploads (`TEXTURE_BINDING` and `COPY_DST` usage).
+    pub fn new(width: u32, height: u32, format: wgpu::TextureFormat) -> Self {
+        Self {
+            label: None,
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            format,
+            dimension: wgpu::TextureDimension::D2,
+            mip_level_count: Some(1),
+            sample_count: 1,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+            view_dimension: None,
+            sampler: None,
+        }
+    }
+
+    pub fn label(mut self, label: &'a str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    /// Depth of 3D textures or layers of 2D array and cube textures.
+    pub fn depth_or_array_layers(mut self, depth_or_array_layers: u32) -> Self {
+        self.size.depth_or_array_layers = depth_or_array_layers;
+        self
+    }
+
+    pub fn dimension(mut self, dimension: wgpu::TextureDimension) -> Self {
+        self.dimension = dimension;
+        self
+    }
+
+    pub fn mip_level_count(mut self, mip_level_count: u32) -> Self {
+        self.mip_level_count = Some(mip_level_count);
+        self
+    }
+
+    /// Mip levels down to 1x1.
+    pub fn full_mip_chain(mut self) -> Self {
+        self.mip_level_count = None;
+        self
+    }
+
+    /// Samples per pixel, one of `Context::supported_sample_counts`, for
+    /// single-level 2D textures.
+    pub fn sample_count(mut self, sample_count: u32) -> Self {
+        self.sample_count = sample_count;
+        self
+    }
+
+    /// Replaces the default usages.
+    pub fn usage(mut self, usage: wgpu::TextureUsages) -> Self {
+        self.usage = usage;
+        self
+    }
+
+    /// Formats the texture can also be viewed in, which may differ from its
+    /// format only in being sRGB or not, on devices with the
+    /// `VIEW_FORMATS` downlevel flag (not on GL).
+    pub fn view_formats(mut self, view_formats: &'a [wgpu::TextureFormat]) -> Self {
+        self.view_formats = view_formats;
+        self
+    }
+
+    /// Dimension of the texture's view, by default the one wgpu picks,
+    /// `D2Array` for layered 2D textures, so cubemaps need `Cube`.
+    pub fn view_dimension(mut self, view_dimension: wgpu::TextureViewDimension) -> Self {
+        self.view_dimension = Some(view_dimension);
+        self
+    }
+
+    /// Sampler of the texture instead of the crate's clamped trilinear one.
+    pub fn sampler(mut self, sampler: SamplerBuilder<'a>) -> Self {
+        self.sampler = Some(sampler);
+        self
+    }
+
+    /// Creates the texture, after checking the settings that would
+    /// otherwise fail wgpu validation.
+    pub fn build(&self, context: &Context) -> Result<Texture, Error> {
+        let (size, format, dimension) = (self.size, self.format, self.dimension);
+        if size.width == 0 || size.height == 0 || size.depth_or_array_layers == 0 {
+            return Err(Error::InvalidTexture(format!(
+                "zero-sized texture {size:?}"
+            )));
+        }
+        if dimension == wgpu::TextureDimension::D1
+            && (size.height != 1 || size.depth_or_array_layers != 1)
+        {
+            return Err(Error::InvalidTexture(format!(
+                "1D texture of height {} and {} layers, both have to be 1",
+                size.height, size.depth_or_array_layers
+            )));
+        }
+        context.check_texture_size(&size, dimension)?;
+
+        if !context
+            .device
+            .features()
+            .contains(format.required_features())
+            || !context
+                .adapter
+                .get_texture_format_features(format)
+                .allowed_usages
+                .contains(self.usage)
+        {
+            return Err(Error::UnsupportedTextureFormat(format));
+        }
+        if self.usage.is_empty() {
+            return Err(Error::InvalidTexture("no usages".into()));
+        }
+        let (block_width, block_height) = format.block_dimensions();
+        if !size.width.is_multiple_of(block_width) || !size.height.is_multiple_of(block_height) {
+            return Err(Error::InvalidTexture(format!(
+                "{}x{} is not a multiple of the {block_width}x{block_height} blocks of {format:?}",
+                size.width, size.height
+            )));
+        }
+        let compatible = |view_format: &&wgpu::TextureFormat| {
+            view_format.remove_srgb_suffix() == format.remove_srgb_suffix()
+        };
+        if let Some(view_format) = self.view_formats.iter().find(|f| !compatible(f)) {
+            return Err(Error::InvalidTexture(format!(
+                "view format {view_format:?} is incompatible with {format:?}"
+            )));
+        }
+        if self
+            .view_formats
+            .iter()
+            .any(|&view_format| view_format != format)
+            && !context
+                .adapter
+                .get_downlevel_capabilities()
+                .flags
+                .contains(wgpu::DownlevelFlags::VIEW_FORMATS)
+        {
+            return Err(Error::InvalidTexture(
+                "other view formats need the VIEW_FORMATS downlevel flag".into(),
+            ));
+        }
+
+        let mip_level_count = self
+            .mip_level_count
+            .unwrap_or_else(|| size.max_mips(dimension));
+        if mip_level_count == 0 || mip_level_count > size.max_mips(dimension) {
+            return Err(Error::InvalidTexture(format!(
+                "{mip_level_count} mip levels, the texture has 1 to {}",
+                size.max_mips(dimension)
+            )));
+        }
+        if self.sample_count != 1 {
+            if !context
+                .supported_sample_counts(format)
+                .contains(&self.sample_count)
+            {
+                return Err(Error::UnsupportedSampleCount {
+                    format,
+                    sample_count: self.sample_count,
+                });
+            }
+            if dimension != wgpu::TextureDimension::D2
+                || size.depth_or_array_layers != 1
+                || mip_level_count != 1
+            {
+                return Err(Error::InvalidTexture(
+                    "multisampled textures have to be single-level 2D textures".into(),
+                ));
+            }
+        }
+
+        let layers = size.depth_or_array_layers;
+        let view_dimension = self
+            .view_dimension
+            .unwrap_or_else(|| default_view_dimension(dimension, layers));
+        let valid_view = match view_dimension {
+            wgpu::TextureViewDimension::D1 => dimension == wgpu::TextureDimension::D1,
+            wgpu::TextureViewDimension::D2 => {
+                dimension == wgpu::TextureDimension::D2 && layers == 1
+            }
+            wgpu::TextureViewDimension::D2Array => dimension == wgpu::TextureDimension::D2,
+            wgpu::TextureViewDimension::Cube => {
+                dimension == wgpu::TextureDimension::D2 && layers == 6
+            }
+            wgpu::TextureViewDimension::CubeArray => {
+                dimension == wgpu::TextureDimension::D2 && layers.is_multiple_of(6)
+            }
+            wgpu::TextureViewDimension::D3 => dimension == wgpu::TextureDimension::D3,
+        };
+        if !valid_view {
+            return Err(Error::InvalidTexture(format!(
+                "view dimension {view_dimension:?} does not fit a {dimension:?} texture with {layers} layers"
+            )));
+        }
+        let texture = context.device.create_texture(&wgpu::TextureDescriptor {
+            label: self.label,
+            size,
+            mip_level_count,
+            sample_count: self.sample_count,
+            dimension,
+            format,
+            usage: self.usage,
+            view_formats: self.view_formats,
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(view_dimension),
+            ..Default::default()
+        });
+        let sampler = match &self.sampler {
+            Some(sampler) => sampler.build(context)?,
+            None => context.texture_sampler(),
+        };
+        #[cfg(feature = "tracing")]
+        tracing::debug!("created texture");
+        Ok(Texture::from_raw(texture, view, sampler).with_view_dimension(view_dimension))
+    }
+}
+
+impl Context {
+    /// A `TextureBuilder` for a `width` x `height` texture of `format`.
+    pub fn texture_builder<'a>(
+        &self,
+        width: &u32,
+        height: &u32,
+        format: &wgpu::TextureFormat,
+    ) -> TextureBuilder<'a> {
+        TextureBuilder::new(*width, *height, *format)
+    }
+}
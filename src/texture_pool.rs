@@ -0,0 +1,158 @@
+use super::context::Context;
+use super::error::Error;
+use super::render_target::RenderTargetBuilder;
+use super::texture::Texture;
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct PoolKey {
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+    usage: wgpu::TextureUsages,
+}
+
+struct Pooled {
+    key: PoolKey,
+    texture: Arc<Texture>,
+    /// The frame the texture was last handed out in.
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct PoolState {
+    frame: u64,
+    in_use: Vec<Pooled>,
+    free: Vec<Pooled>,
+}
+
+/// Recycles the short-lived render targets of post-processing chains
+/// across frames instead of reallocating them.
+///
+/// `acquire` hands out a target of the requested size, format and usage,
+/// free since the last `recall` or newly created. `recall` at the end of a
+/// frame takes back the targets whose handles were dropped, and `collect`
+/// destroys those unused for a number of frames. `Context::texture_pool`
+/// is shared by everything using the context.
+#[derive(Default)]
+pub struct TexturePool {
+    state: Mutex<PoolState>,
+}
+
+impl TexturePool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, PoolState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// A `RenderTargetBuilder` target with `usage` in addition to the
+    /// defaults, not handed out since the last `recall`.
+    pub fn acquire(
+        &self,
+        context: &Context,
+        width: &u32,
+        height: &u32,
+        texture_format: &wgpu::TextureFormat,
+        usage: wgpu::TextureUsages,
+    ) -> Result<Arc<Texture>, Error> {
+        let key = PoolKey {
+            width: *width,
+            height: *height,
+            format: *texture_format,
+            usage,
+        };
+        let mut state = self.state();
+        let frame = state.frame;
+        let mut pooled = match state.free.iter().position(|pooled| pooled.key == key) {
+            Some(index) => state.free.swap_remove(index),
+            None => Pooled {
+                key,
+                texture: Arc::new(
+                    RenderTargetBuilder::new(key.width, key.height, key.format)
+                        .label("Pooled Texture")
+                        .usage(usage)
+                        .build(context)?,
+                ),
+                last_used: frame,
+            },
+        };
+        pooled.last_used = frame;
+        let texture = pooled.texture.clone();
+        state.in_use.push(pooled);
+        Ok(texture)
+    }
+
+    /// Ends the frame, returning the targets no longer held outside of the
+    /// pool to it. Targets still held stay in use until a later `recall`.
+    pub fn recall(&self) {
+        let mut state = self.state();
+        let (returned, kept) = std::mem::take(&mut state.in_use)
+            .into_iter()
+            .partition(|pooled| Arc::strong_count(&pooled.texture) == 1);
+        state.in_use = kept;
+        state.free.extend::<Vec<_>>(returned);
+        state.frame += 1;
+    }
+
+    /// Destroys the free targets last handed out more than
+    /// `max_unused_frames` frames ago, returning how many were destroyed.
+    pub fn collect(&self, max_unused_frames: u64) -> usize {
+        let mut state = self.state();
+        let frame = state.frame;
+        let count = state.free.len();
+        state
+            .free
+            .retain(|pooled| frame - pooled.last_used <= max_unused_frames);
+        count - state.free.len()
+    }
+
+    /// Destroys all free targets.
+    pub fn clear(&self) {
+        self.state().free.clear();
+    }
+
+    /// Number of targets handed out since the last `recall` or still held.
+    pub fn in_use(&self) -> usize {
+        self.state().in_use.len()
+    }
+
+    /// Number of targets waiting to be handed out again.
+    pub fn free(&self) -> usize {
+        self.state().free.len()
+    }
+
+    /// Bytes of all the targets of the pool, as `Texture::memory_size`.
+    pub fn memory_size(&self) -> u64 {
+        let state = self.state();
+        state
+            .in_use
+            .iter()
+            .chain(&state.free)
+            .map(|pooled| pooled.texture.memory_size())
+            .sum()
+    }
+}
+
+impl Context {
+    /// The `TexturePool` of this context.
+    pub fn texture_pool(&self) -> &TexturePool {
+        &self.texture_pool
+    }
+
+    /// `TexturePool::acquire` from the context's pool.
+    pub fn pooled_texture(
+        &self,
+        width: &u32,
+        height: &u32,
+        texture_format: &wgpu::TextureFormat,
+        usage: wgpu::TextureUsages,
+    ) -> Result<Arc<Texture>, Error> {
+        self.texture_pool
+            .acquire(self, width, height, texture_format, usage)
+    }
+}
@@ -0,0 +1,189 @@
+//! Compile-time checks that the crate's types can be used from several
+//! threads: a `Context` shared through an `Arc`, with textures, buffers and
+//! renderers created on worker threads and used for rendering on another.
+//! Types holding user callbacks or a channel receiver are only `Send`.
+//! wgpu's types are not `Send` on wasm32, so neither are the crate's there.
+#![cfg(not(target_arch = "wasm32"))]
+
+use super::*;
+
+const fn assert_send<T: Send>() {}
+
+const fn assert_send_sync<T: Send + Sync>() {}
+
+const _: () = {
+    assert_send_sync::<AmbientOcclusionBaker>();
+    assert_send_sync::<AmbientOcclusionSettings>();
+    #[cfg(feature = "image")]
+    assert_send_sync::<AnimatedTexture>();
+    assert_send_sync::<AnimationClip>();
+    assert_send_sync::<AnimationEvent>();
+    assert_send_sync::<AnimationPlayer>();
+    assert_send_sync::<Channel>();
+    assert_send_sync::<ChannelValue>();
+    assert_send_sync::<Interpolation>();
+    assert_send_sync::<Keyframes>();
+    assert_send_sync::<PlaybackMode>();
+    assert_send_sync::<Pose>();
+    assert_send_sync::<Property>();
+    assert_send_sync::<Aov>();
+    assert_send_sync::<AovTargets>();
+    assert_send_sync::<ArenaAllocation>();
+    assert_send_sync::<BufferArena>();
+    assert_send_sync::<AtlasRegion>();
+    assert_send_sync::<TextureAtlas>();
+    assert_send_sync::<BenchReport>();
+    assert_send_sync::<GpuBench>();
+    assert_send_sync::<TimingSource>();
+    assert_send_sync::<Blitter>();
+    assert_send::<BudgetPolicy>();
+    assert_send_sync::<Downgrade>();
+    assert_send_sync::<ContextBuilder>();
+    #[cfg(feature = "scene")]
+    assert_send_sync::<CameraPath>();
+    #[cfg(feature = "image")]
+    assert_send_sync::<CaptureDiff>();
+    #[cfg(feature = "image")]
+    assert_send_sync::<FrameCapture>();
+    #[cfg(feature = "image")]
+    assert_send_sync::<PassDiff>();
+    assert_send_sync::<BufferChunk>();
+    assert_send_sync::<ChunkedBuffer>();
+    assert_send_sync::<DispatchChunk>();
+    assert_send_sync::<Clock>();
+    assert_send_sync::<ComparisonReport>();
+    assert_send_sync::<TextureComparer>();
+    assert_send_sync::<Context>();
+    assert_send_sync::<CoordinateConversion>();
+    assert_send_sync::<CoordinateSystem>();
+    assert_send_sync::<Handedness>();
+    assert_send_sync::<ImportOptions>();
+    assert_send_sync::<LengthUnit>();
+    assert_send_sync::<UpAxis>();
+    assert_send_sync::<DebugMaterial>();
+    assert_send_sync::<DebugMaterialRenderer>();
+    assert_send_sync::<DeletionQueue>();
+    assert_send_sync::<Retired>();
+    assert_send_sync::<DenoiseHistory>();
+    assert_send_sync::<DenoiseInputs>();
+    assert_send_sync::<Denoiser>();
+    assert_send_sync::<Colormap>();
+    assert_send_sync::<DepthRange>();
+    assert_send_sync::<DepthVisualizer>();
+    assert_send_sync::<PassRecord>();
+    assert_send_sync::<SubmissionRecord>();
+    assert_send_sync::<Error>();
+    assert_send_sync::<FrameLimiter>();
+    assert_send_sync::<GpuFence>();
+    assert_send_sync::<Fft>();
+    assert_send_sync::<FftDirection>();
+    assert_send_sync::<FftShape>();
+    assert_send_sync::<FixedResolution>();
+    assert_send_sync::<ScalingMode>();
+    assert_send_sync::<OpticalFlow>();
+    assert_send_sync::<BlockInfo>();
+    assert_send_sync::<ColorSpace>();
+    assert_send_sync::<ImageLayout>();
+    assert_send_sync::<HalfResolution>();
+    assert_send_sync::<Heightmap>();
+    #[cfg(feature = "scene")]
+    assert_send_sync::<JobProgress>();
+    #[cfg(feature = "scene")]
+    assert_send_sync::<RenderJob>();
+    assert_send_sync::<Component>();
+    assert_send_sync::<ComponentLabels>();
+    assert_send_sync::<ConnectedComponents>();
+    #[cfg(feature = "scene")]
+    assert_send_sync::<LightmapBaker>();
+    #[cfg(feature = "scene")]
+    assert_send_sync::<LightmapSettings>();
+    assert_send_sync::<Aabb>();
+    assert_send_sync::<Frustum>();
+    assert_send_sync::<Plane>();
+    assert_send_sync::<Ray>();
+    assert_send_sync::<GpuMesh>();
+    assert_send_sync::<Mesh>();
+    assert_send_sync::<Vertex>();
+    assert_send_sync::<MeshReport>();
+    assert_send_sync::<MipView>();
+    assert_send_sync::<MipVisualizer>();
+    assert_send_sync::<NormalLineSettings>();
+    assert_send_sync::<NormalVisualizer>();
+    assert_send_sync::<TangentFrames>();
+    assert_send_sync::<NormalMapGenerator>();
+    assert_send_sync::<BlendMode>();
+    assert_send_sync::<Brush>();
+    assert_send_sync::<TexturePainter>();
+    assert_send_sync::<PassOps>();
+    assert_send_sync::<PlyFormat>();
+    assert_send_sync::<BackgroundPoller>();
+    assert_send_sync::<PollingStrategy>();
+    assert_send_sync::<EnvironmentProbe>();
+    assert_send_sync::<ProbeFace>();
+    assert_send_sync::<CancellationToken>();
+    assert_send::<Progress<'static>>();
+    assert_send_sync::<ProgressUpdate>();
+    assert_send_sync::<ReducedRendering>();
+    assert_send_sync::<ReducedRenderingMode>();
+    assert_send_sync::<RenderTargetBuilder>();
+    assert_send_sync::<ContextReport>();
+    assert_send_sync::<FormatReport>();
+    assert_send_sync::<SamplerBuilder>();
+    #[cfg(feature = "scene")]
+    assert_send_sync::<Camera>();
+    #[cfg(feature = "scene")]
+    assert_send_sync::<Environment>();
+    #[cfg(feature = "scene")]
+    assert_send_sync::<Light>();
+    #[cfg(feature = "scene")]
+    assert_send_sync::<MaterialDescription>();
+    #[cfg(feature = "scene")]
+    assert_send_sync::<MeshDescription>();
+    #[cfg(feature = "scene")]
+    assert_send_sync::<MeshSource>();
+    #[cfg(feature = "scene")]
+    assert_send_sync::<ObjectDescription>();
+    #[cfg(feature = "scene")]
+    assert_send_sync::<Scene>();
+    #[cfg(feature = "scene")]
+    assert_send_sync::<SceneDescription>();
+    assert_send_sync::<ResizeRegistry>();
+    assert_send_sync::<ScreenTexture>();
+    assert_send_sync::<MagicWand>();
+    assert_send_sync::<SelectionOptions>();
+    #[cfg(feature = "shadertoy")]
+    assert_send_sync::<ShaderToy>();
+    #[cfg(feature = "shadertoy")]
+    assert_send_sync::<ShaderToyInputs>();
+    #[cfg(feature = "shadertoy")]
+    assert_send_sync::<ShaderToySource>();
+    assert_send_sync::<StencilMask>();
+    assert_send_sync::<StencilTest>();
+    assert_send_sync::<StlFormat>();
+    assert_send_sync::<ReduceOp>();
+    assert_send_sync::<SubgroupSupport>();
+    assert_send_sync::<WgslScalar>();
+    assert_send_sync::<WorkgroupPrimitives>();
+    assert_send_sync::<TemporalResources>();
+    assert_send_sync::<Texture>();
+    assert_send_sync::<TextureBuilder>();
+    assert_send_sync::<TexturePool>();
+    assert_send_sync::<WorkgroupTuner>();
+    assert_send_sync::<Easing>();
+    assert_send_sync::<Rotation>();
+    assert_send_sync::<SrgbColor>();
+    assert_send_sync::<Tween<f32>>();
+    assert_send::<UploadHeap>();
+    assert_send_sync::<UploadStrategy>();
+    assert_send_sync::<SpatialUpscaler>();
+    #[cfg(feature = "usd")]
+    assert_send_sync::<UsdImport>();
+    assert_send_sync::<UvChannel>();
+    assert_send_sync::<UvInspector>();
+    assert_send_sync::<VideoTexture>();
+    assert_send_sync::<YuvFormat>();
+    assert_send_sync::<YuvMatrix>();
+    assert_send_sync::<YuvPlane>();
+    assert_send_sync::<YuvRange>();
+    assert_send_sync::<Viewport>();
+};
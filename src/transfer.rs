@@ -0,0 +1,85 @@
+use super::context::Context;
+use super::error::Error;
+use super::texture::Texture;
+
+// Resources can't be shared between devices, so transfers between contexts
+// on different adapters go through CPU memory.
+
+impl Context {
+    pub fn is_same_adapter(&self, other: &Context) -> bool {
+        let (a, b) = (self.adapter.get_info(), other.adapter.get_info());
+        a.name == b.name && a.vendor == b.vendor && a.device == b.device && a.backend == b.backend
+    }
+
+    /// Copies `buffer` (which needs `COPY_SRC` usage) into a new buffer on
+    /// `destination`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, buffer, destination),
+            fields(bytes = buffer.size()),
+            err
+        )
+    )]
+    pub fn transfer_buffer(
+        &self,
+        buffer: &wgpu::Buffer,
+        destination: &Context,
+        usage: wgpu::BufferUsages,
+        label: Option<&str>,
+    ) -> Result<wgpu::Buffer, Error> {
+        let data = self.read_buffer(buffer)?;
+        destination.buffer_with_data(&data, usage, label)
+    }
+
+    /// Copies `source` into the existing `target` buffer on `destination`,
+    /// which needs `COPY_DST` usage.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(bytes = source.size()), err)
+    )]
+    pub fn transfer_buffer_into(
+        &self,
+        source: &wgpu::Buffer,
+        destination: &Context,
+        target: &wgpu::Buffer,
+    ) -> Result<(), Error> {
+        if target.size() < source.size() {
+            return Err(Error::BufferTooSmall {
+                size: target.size(),
+                required: source.size(),
+            });
+        }
+        let data = self.read_buffer(source)?;
+        destination.queue.write_buffer(target, 0, &data);
+        Ok(())
+    }
+
+    /// Copies mip level 0 of the first layer of `texture` into a new
+    /// texture with the same size and format on `destination`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self, texture, destination),
+            fields(width = texture.width(), height = texture.height()),
+            err
+        )
+    )]
+    pub fn transfer_texture(
+        &self,
+        texture: &Texture,
+        destination: &Context,
+        label: Option<&str>,
+    ) -> Result<Texture, Error> {
+        let data = self.read_texture(texture)?;
+        destination.texture_with_data(
+            &data,
+            &texture.width(),
+            &texture.height(),
+            &texture.format(),
+            label,
+        )
+    }
+}
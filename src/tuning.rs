@@ -0,0 +1,159 @@
+use super::context::Context;
+use super::error::Error;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+pub struct WorkgroupTuner {
+    iterations: u32,
+    cache: Mutex<HashMap<String, [u32; 3]>>,
+}
+
+impl WorkgroupTuner {
+    pub const CANDIDATES_1D: [[u32; 3]; 5] = [
+        [32, 1, 1],
+        [64, 1, 1],
+        [128, 1, 1],
+        [256, 1, 1],
+        [512, 1, 1],
+    ];
+    pub const CANDIDATES_2D: [[u32; 3]; 5] =
+        [[8, 4, 1], [8, 8, 1], [16, 8, 1], [16, 16, 1], [32, 8, 1]];
+
+    pub fn new() -> Self {
+        Self::with_iterations(8)
+    }
+
+    pub fn with_iterations(iterations: u32) -> Self {
+        Self {
+            iterations: iterations.max(1),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn cached(&self, context: &Context, key: &str) -> Option<[u32; 3]> {
+        self.cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(&cache_key(context, key))
+            .copied()
+    }
+
+    pub fn insert(&self, context: &Context, key: &str, workgroup_size: [u32; 3]) {
+        self.cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(cache_key(context, key), workgroup_size);
+    }
+
+    pub fn clear(&self) {
+        self.cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clear();
+    }
+
+    /// Times every candidate that fits the device limits and caches the
+    /// fastest one under `key` for the current adapter. `prepare` builds
+    /// the pipeline (or whatever state a candidate needs) outside of the
+    /// timed region, `run` records the workload for one iteration.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self, context, prepare, run), err)
+    )]
+    pub fn tune<P, B, R>(
+        &self,
+        context: &Context,
+        key: &str,
+        candidates: &[[u32; 3]],
+        mut prepare: B,
+        mut run: R,
+    ) -> Result<[u32; 3], Error>
+    where
+        B: FnMut([u32; 3]) -> P,
+        R: FnMut(&mut wgpu::CommandEncoder, &P, [u32; 3]),
+    {
+        if let Some(workgroup_size) = self.cached(context, key) {
+            return Ok(workgroup_size);
+        }
+
+        let limits = context.device.limits();
+        let mut best: Option<([u32; 3], Duration)> = None;
+        for &candidate in candidates.iter().filter(|c| fits_limits(c, &limits)) {
+            let prepared = prepare(candidate);
+
+            // Warm up once so pipeline compilation and first-use costs don't
+            // skew the measurement.
+            self.submit(context, 1, &prepared, candidate, &mut run);
+            let elapsed = self.submit(context, self.iterations, &prepared, candidate, &mut run);
+
+            if best.is_none_or(|(_, fastest)| elapsed < fastest) {
+                best = Some((candidate, elapsed));
+            }
+        }
+
+        let (workgroup_size, _) = best.ok_or(Error::NoWorkgroupSizeCandidate)?;
+        self.insert(context, key, workgroup_size);
+        Ok(workgroup_size)
+    }
+
+    fn submit<P, R>(
+        &self,
+        context: &Context,
+        iterations: u32,
+        prepared: &P,
+        candidate: [u32; 3],
+        run: &mut R,
+    ) -> Duration
+    where
+        R: FnMut(&mut wgpu::CommandEncoder, &P, [u32; 3]),
+    {
+        let start = Instant::now();
+        let mut command_encoder =
+            context
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Workgroup Tuning Encoder"),
+                });
+        for _ in 0..iterations {
+            run(&mut command_encoder, prepared, candidate);
+        }
+        let index = context.submit(command_encoder);
+        context
+            .device
+            .poll(wgpu::Maintain::WaitForSubmissionIndex(index));
+        start.elapsed()
+    }
+}
+
+impl Default for WorkgroupTuner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn with_workgroup_size(source: &str, workgroup_size: [u32; 3]) -> String {
+    source.replace(
+        "WORKGROUP_SIZE",
+        &format!(
+            "{}, {}, {}",
+            workgroup_size[0], workgroup_size[1], workgroup_size[2]
+        ),
+    )
+}
+
+fn fits_limits(candidate: &[u32; 3], limits: &wgpu::Limits) -> bool {
+    let [x, y, z] = *candidate;
+    x > 0
+        && y > 0
+        && z > 0
+        && x <= limits.max_compute_workgroup_size_x
+        && y <= limits.max_compute_workgroup_size_y
+        && z <= limits.max_compute_workgroup_size_z
+        && x * y * z <= limits.max_compute_invocations_per_workgroup
+}
+
+fn cache_key(context: &Context, key: &str) -> String {
+    let info = context.adapter.get_info();
+    format!("{}:{:?}:{}", info.name, info.backend, key)
+}
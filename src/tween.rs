@@ -0,0 +1,276 @@
+use super::clock::Clock;
+#[cfg(feature = "scene")]
+use super::math::normalize;
+use super::math::{lerp, slerp};
+#[cfg(feature = "scene")]
+use super::scene::Camera;
+use std::f32::consts::PI;
+use std::time::Duration;
+
+/// Easing curves mapping progress in [0, 1] to an interpolation factor,
+/// 0 at the start and 1 at the end. `Back` and `Elastic` overshoot.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Easing {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    SineIn,
+    SineOut,
+    SineInOut,
+    ExpoIn,
+    ExpoOut,
+    ExpoInOut,
+    BackIn,
+    BackOut,
+    BackInOut,
+    ElasticOut,
+    BounceOut,
+    /// Hermite smoothstep, `3t² - 2t³`.
+    Smoothstep,
+}
+
+impl Easing {
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        // Overshoot of the back curves, the common 10% one.
+        const BACK: f32 = 1.70158;
+        let in_out = |ease_in: fn(f32) -> f32, t: f32| {
+            if t < 0.5 {
+                ease_in(2.0 * t) * 0.5
+            } else {
+                1.0 - ease_in(2.0 - 2.0 * t) * 0.5
+            }
+        };
+        let out = |ease_in: fn(f32) -> f32, t: f32| 1.0 - ease_in(1.0 - t);
+        let quad = |t: f32| t * t;
+        let cubic = |t: f32| t * t * t;
+        let sine = |t: f32| 1.0 - (t * PI * 0.5).cos();
+        let expo = |t: f32| {
+            if t <= 0.0 {
+                0.0
+            } else {
+                (10.0 * (t - 1.0)).exp2()
+            }
+        };
+        let back = |t: f32| t * t * ((BACK + 1.0) * t - BACK);
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => quad(t),
+            Easing::QuadOut => out(quad, t),
+            Easing::QuadInOut => in_out(quad, t),
+            Easing::CubicIn => cubic(t),
+            Easing::CubicOut => out(cubic, t),
+            Easing::CubicInOut => in_out(cubic, t),
+            Easing::SineIn => sine(t),
+            Easing::SineOut => out(sine, t),
+            Easing::SineInOut => in_out(sine, t),
+            Easing::ExpoIn => expo(t),
+            Easing::ExpoOut => out(expo, t),
+            Easing::ExpoInOut => in_out(expo, t),
+            Easing::BackIn => back(t),
+            Easing::BackOut => out(back, t),
+            Easing::BackInOut => in_out(back, t),
+            Easing::ElasticOut if t <= 0.0 || t >= 1.0 => t,
+            Easing::ElasticOut => {
+                (-10.0 * t).exp2() * ((t * 10.0 - 0.75) * 2.0 * PI / 3.0).sin() + 1.0
+            }
+            Easing::BounceOut => bounce_out(t),
+            Easing::Smoothstep => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+fn bounce_out(t: f32) -> f32 {
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+/// Values a `Tween` can interpolate, `self` at 0 and `other` at 1.
+pub trait Tweenable: Clone {
+    fn interpolate(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Tweenable for f32 {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl<const N: usize> Tweenable for [f32; N] {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        lerp(*self, *other, t)
+    }
+}
+
+/// A unit quaternion `[x, y, z, w]`, interpolated spherically.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rotation(pub [f32; 4]);
+
+impl Tweenable for Rotation {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        Rotation(slerp(self.0, other.0, t))
+    }
+}
+
+/// An sRGB-encoded RGBA color, interpolated in linear light so that
+/// midpoints don't darken. Alpha is interpolated as it is.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SrgbColor(pub [f32; 4]);
+
+impl Tweenable for SrgbColor {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        let decode = |c: f32| match c <= 0.04045 {
+            true => c / 12.92,
+            false => ((c + 0.055) / 1.055).powf(2.4),
+        };
+        let encode = |c: f32| match c <= 0.0031308 {
+            true => c * 12.92,
+            false => 1.055 * c.powf(1.0 / 2.4) - 0.055,
+        };
+        let [a, b] = [self.0, other.0];
+        SrgbColor(std::array::from_fn(|i| match i {
+            3 => a[3] + (b[3] - a[3]) * t,
+            _ => encode(decode(a[i]) + (decode(b[i]) - decode(a[i])) * t),
+        }))
+    }
+}
+
+/// Linear like all `wgpu::Color`s.
+impl Tweenable for wgpu::Color {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        let t = t as f64;
+        wgpu::Color {
+            r: self.r + (other.r - self.r) * t,
+            g: self.g + (other.g - self.g) * t,
+            b: self.b + (other.b - self.b) * t,
+            a: self.a + (other.a - self.a) * t,
+        }
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl<const N: usize> Tweenable for nalgebra::SVector<f32, N> {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self.lerp(other, t)
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl Tweenable for nalgebra::UnitQuaternion<f32> {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self.try_slerp(other, t, 1e-6)
+            .unwrap_or_else(|| self.nlerp(other, t))
+    }
+}
+
+/// Moves the eye and the target in straight lines, and blends the field
+/// of view and clip planes. Projection flags switch at the midpoint.
+#[cfg(feature = "scene")]
+impl Tweenable for Camera {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        let flags = if t < 0.5 { self } else { other };
+        Camera {
+            position: lerp(self.position, other.position, t),
+            target: lerp(self.target, other.target, t),
+            up: normalize(lerp(self.up, other.up, t)),
+            fov_y_degrees: self.fov_y_degrees.interpolate(&other.fov_y_degrees, t),
+            near: self.near.interpolate(&other.near, t),
+            far: self.far.interpolate(&other.far, t),
+            ..flags.clone()
+        }
+    }
+}
+
+/// A transition from one value to another over a duration, advanced by a
+/// `Clock` or by explicit deltas.
+#[derive(Clone, Debug)]
+pub struct Tween<T> {
+    from: T,
+    to: T,
+    duration: Duration,
+    elapsed: Duration,
+    pub easing: Easing,
+}
+
+impl<T: Tweenable> Tween<T> {
+    pub fn new(from: T, to: T, duration: Duration, easing: Easing) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            elapsed: Duration::ZERO,
+            easing,
+        }
+    }
+
+    pub fn from(&self) -> &T {
+        &self.from
+    }
+
+    pub fn to(&self) -> &T {
+        &self.to
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Linear progress in [0, 1], before easing.
+    pub fn progress(&self) -> f32 {
+        match self.duration.is_zero() {
+            true => 1.0,
+            false => (self.elapsed.as_secs_f32() / self.duration.as_secs_f32()).min(1.0),
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    pub fn value(&self) -> T {
+        self.from
+            .interpolate(&self.to, self.easing.apply(self.progress()))
+    }
+
+    /// Advances by `delta` and returns the new value.
+    pub fn advance(&mut self, delta: Duration) -> T {
+        self.elapsed = (self.elapsed + delta).min(self.duration);
+        self.value()
+    }
+
+    /// Advances by the clock's frame delta, so pausing the clock pauses the
+    /// tween.
+    pub fn update(&mut self, clock: &Clock) -> T {
+        self.advance(clock.delta())
+    }
+
+    /// Starts over from the current value towards `to`, which keeps
+    /// interrupted transitions continuous.
+    pub fn retarget(&mut self, to: T, duration: Duration) {
+        self.from = self.value();
+        self.to = to;
+        self.duration = duration;
+        self.elapsed = Duration::ZERO;
+    }
+
+    pub fn restart(&mut self) {
+        self.elapsed = Duration::ZERO;
+    }
+}
@@ -0,0 +1,184 @@
+use super::math::{cross, dot, normalize, sub};
+use super::mesh::Mesh;
+use std::collections::HashMap;
+
+/// Cosine of the largest angle between a chart's first face and the
+/// others, 45°.
+const CHART_COSINE: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+/// Triangles of a chart, and their projection onto its plane.
+struct Chart {
+    triangles: Vec<usize>,
+    tangent: [f32; 3],
+    bitangent: [f32; 3],
+    min: [f32; 2],
+    size: [f32; 2],
+}
+
+impl Mesh {
+    /// Generates `uv2` for lightmaps. Edge-connected triangles facing within
+    /// 45° of each other form charts, each chart is projected onto its
+    /// plane, and the charts are packed into the unit square with the same
+    /// texel density and `padding` texels between them at `resolution`.
+    /// Vertices on chart borders are split, and vertex colors follow them.
+    pub fn unwrap_lightmap_uvs(&mut self, resolution: u32, padding: u32) {
+        let triangles: Vec<[u32; 3]> = self.triangles().collect();
+        let position = |index: u32| self.vertices[index as usize].position;
+        let normals: Vec<[f32; 3]> = triangles
+            .iter()
+            .map(|&[a, b, c]| {
+                normalize(cross(
+                    sub(position(b), position(a)),
+                    sub(position(c), position(a)),
+                ))
+            })
+            .collect();
+
+        let mut edges: HashMap<(u32, u32), Vec<usize>> = HashMap::new();
+        for (triangle, indices) in triangles.iter().enumerate() {
+            for i in 0..3 {
+                let (a, b) = (indices[i], indices[(i + 1) % 3]);
+                edges
+                    .entry((a.min(b), a.max(b)))
+                    .or_default()
+                    .push(triangle);
+            }
+        }
+
+        let mut chart_of = vec![usize::MAX; triangles.len()];
+        let mut charts = Vec::new();
+        for seed in 0..triangles.len() {
+            if chart_of[seed] != usize::MAX {
+                continue;
+            }
+            let normal = normals[seed];
+            chart_of[seed] = charts.len();
+            let mut members = vec![seed];
+            let mut next = 0;
+            while let Some(&triangle) = members.get(next) {
+                next += 1;
+                let indices = triangles[triangle];
+                for i in 0..3 {
+                    let (a, b) = (indices[i], indices[(i + 1) % 3]);
+                    for &neighbor in &edges[&(a.min(b), a.max(b))] {
+                        if chart_of[neighbor] == usize::MAX
+                            && dot(normals[neighbor], normal) >= CHART_COSINE
+                        {
+                            chart_of[neighbor] = charts.len();
+                            members.push(neighbor);
+                        }
+                    }
+                }
+            }
+
+            // Any axis not along the normal completes a basis of the plane.
+            let axis = match normal.map(f32::abs) {
+                [x, y, z] if x <= y && x <= z => [1.0, 0.0, 0.0],
+                [_, y, z] if y <= z => [0.0, 1.0, 0.0],
+                _ => [0.0, 0.0, 1.0],
+            };
+            let tangent = normalize(cross(axis, normal));
+            let bitangent = cross(normal, tangent);
+            let (mut min, mut max) = ([f32::MAX; 2], [f32::MIN; 2]);
+            for &triangle in &members {
+                for index in triangles[triangle] {
+                    let p = position(index);
+                    let uv = [dot(p, tangent), dot(p, bitangent)];
+                    min = [min[0].min(uv[0]), min[1].min(uv[1])];
+                    max = [max[0].max(uv[0]), max[1].max(uv[1])];
+                }
+            }
+            charts.push(Chart {
+                triangles: members,
+                tangent,
+                bitangent,
+                min,
+                size: [max[0] - min[0], max[1] - min[1]],
+            });
+        }
+
+        // Shelf packing of the tallest charts first, at the largest texel
+        // density that fits.
+        let mut order: Vec<usize> = (0..charts.len()).collect();
+        order.sort_by(|&a, &b| charts[b].size[1].total_cmp(&charts[a].size[1]));
+        let resolution = resolution.max(1) as f32;
+        let padding = padding as f32;
+        let pack = |scale: f32| -> Option<Vec<[f32; 2]>> {
+            let mut offsets = vec![[0.0; 2]; charts.len()];
+            let (mut x, mut y, mut shelf_height) = (padding, padding, 0.0f32);
+            for &chart in &order {
+                let [width, height] = charts[chart].size.map(|s| (s * scale).ceil().max(1.0));
+                if x + width + padding > resolution {
+                    x = padding;
+                    y += shelf_height + padding;
+                    shelf_height = 0.0;
+                }
+                if x + width + padding > resolution || y + height + padding > resolution {
+                    return None;
+                }
+                offsets[chart] = [x, y];
+                x += width + padding;
+                shelf_height = shelf_height.max(height);
+            }
+            Some(offsets)
+        };
+        let area: f32 = charts
+            .iter()
+            .map(|chart| chart.size[0] * chart.size[1])
+            .sum();
+        let largest = charts
+            .iter()
+            .map(|chart| chart.size[0].max(chart.size[1]))
+            .fold(0.0, f32::max);
+        let mut high = match area > 0.0 {
+            true => resolution / area.sqrt(),
+            false => resolution / largest.max(f32::MIN_POSITIVE),
+        };
+        let mut low = 0.0;
+        for _ in 0..24 {
+            let scale = 0.5 * (low + high);
+            match pack(scale) {
+                Some(_) => low = scale,
+                None => high = scale,
+            }
+        }
+        // With more charts than fit even at one texel each, they overlap.
+        let offsets = pack(low).unwrap_or_else(|| vec![[padding; 2]; charts.len()]);
+
+        let mut vertices = Vec::new();
+        let mut colors = Vec::new();
+        let mut uv2 = Vec::new();
+        let mut split: HashMap<(u32, usize), u32> = HashMap::new();
+        let mut indices = Vec::with_capacity(self.indices.len());
+        for (index, chart) in charts.iter().enumerate() {
+            for &triangle in &chart.triangles {
+                for old in triangles[triangle] {
+                    let new = *split.entry((old, index)).or_insert_with(|| {
+                        let vertex = self.vertices[old as usize];
+                        let p = vertex.position;
+                        let projected = [
+                            dot(p, chart.tangent) - chart.min[0],
+                            dot(p, chart.bitangent) - chart.min[1],
+                        ];
+                        vertices.push(vertex);
+                        if let Some(color) = self.colors.get(old as usize) {
+                            colors.push(*color);
+                        }
+                        uv2.push(
+                            [0, 1].map(|i| (offsets[index][i] + projected[i] * low) / resolution),
+                        );
+                        vertices.len() as u32 - 1
+                    });
+                    indices.push(new);
+                }
+            }
+        }
+        if colors.len() != vertices.len() {
+            colors.clear();
+        }
+        self.vertices = vertices;
+        self.colors = colors;
+        self.uv2 = uv2;
+        self.indices = indices;
+    }
+}
@@ -0,0 +1,247 @@
+use super::context::Context;
+use super::error::Error;
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UploadStrategy {
+    /// `Queue::write_buffer`, staged by wgpu.
+    QueueWrite,
+    /// Mapped staging chunks owned by the `UploadHeap`, copied on the GPU.
+    MappedHeap,
+}
+
+impl UploadStrategy {
+    /// Browsers (WebGPU and WebGL) proxy mapped memory through extra
+    /// copies and only remap a frame late, so `write_buffer` is preferred
+    /// there. Use `fastest` to measure on a specific device.
+    pub fn preferred(context: &Context) -> Self {
+        let backend = context.adapter.get_info().backend;
+        if cfg!(target_arch = "wasm32") || backend == wgpu::Backend::BrowserWebGpu {
+            UploadStrategy::QueueWrite
+        } else {
+            UploadStrategy::MappedHeap
+        }
+    }
+
+    /// Measures both strategies uploading `bytes` per frame and returns the
+    /// faster one.
+    pub fn fastest(context: &Context, bytes: u64, iterations: u32) -> Result<Self, Error> {
+        let results = UploadHeap::benchmark(context, bytes, iterations)?;
+        Ok(results
+            .into_iter()
+            .min_by_key(|(_, duration)| *duration)
+            .map_or(UploadStrategy::QueueWrite, |(strategy, _)| strategy))
+    }
+}
+
+struct Chunk {
+    buffer: Arc<wgpu::Buffer>,
+    offset: u64,
+}
+
+/// Streams frequently changing data (UI vertices, debug lines) into GPU
+/// buffers through a pool of mappable staging chunks.
+///
+/// Per frame: `write` any number of times, `finish` before submitting the
+/// encoder, then `recall` after the submit. A chunk becomes writable again
+/// when its remap completes, which wgpu only allows once the submissions
+/// reading it have finished, so regions in flight are never overwritten.
+pub struct UploadHeap {
+    strategy: UploadStrategy,
+    chunk_size: u64,
+    active: Vec<Chunk>,
+    closed: Vec<Chunk>,
+    free: Vec<Chunk>,
+    sender: mpsc::Sender<Chunk>,
+    receiver: mpsc::Receiver<Chunk>,
+}
+
+impl UploadHeap {
+    pub fn new(strategy: UploadStrategy, chunk_size: u64) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            strategy,
+            chunk_size: chunk_size.next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT),
+            active: Vec::new(),
+            closed: Vec::new(),
+            free: Vec::new(),
+            sender,
+            receiver,
+        }
+    }
+
+    pub fn with_preferred_strategy(context: &Context, chunk_size: u64) -> Self {
+        Self::new(UploadStrategy::preferred(context), chunk_size)
+    }
+
+    pub fn strategy(&self) -> UploadStrategy {
+        self.strategy
+    }
+
+    /// Number of staging chunks, in use or not.
+    pub fn chunk_count(&self) -> usize {
+        self.active.len() + self.closed.len() + self.free.len()
+    }
+
+    /// Uploads `data` to `target` at `offset`, which must be 4-byte aligned.
+    /// Unaligned tails are padded with zeroes. `target` needs `COPY_DST`.
+    pub fn write(
+        &mut self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::Buffer,
+        offset: u64,
+        data: &[u8],
+    ) -> Result<(), Error> {
+        if !offset.is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT) {
+            return Err(Error::UnalignedBufferOffset {
+                offset,
+                alignment: wgpu::COPY_BUFFER_ALIGNMENT,
+            });
+        }
+        let size = (data.len() as u64).next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT);
+        let required = offset.saturating_add(size);
+        if required > target.size() {
+            return Err(Error::BufferTooSmall {
+                size: target.size(),
+                required,
+            });
+        }
+        if size == 0 {
+            return Ok(());
+        }
+
+        if self.strategy == UploadStrategy::QueueWrite {
+            context.write_buffer_padded(target, offset, data);
+            return Ok(());
+        }
+
+        let index = match self
+            .active
+            .iter()
+            .position(|chunk| chunk.offset + size <= chunk.buffer.size())
+        {
+            Some(index) => index,
+            None => {
+                let chunk = self.take_chunk(context, size)?;
+                self.active.push(chunk);
+                self.active.len() - 1
+            }
+        };
+        let chunk = &mut self.active[index];
+        {
+            let mut mapped = chunk
+                .buffer
+                .slice(chunk.offset..chunk.offset + size)
+                .get_mapped_range_mut();
+            mapped[..data.len()].copy_from_slice(data);
+            mapped[data.len()..].fill(0);
+        }
+        encoder.copy_buffer_to_buffer(&chunk.buffer, chunk.offset, target, offset, size);
+        // Mapped ranges have to start at `MAP_ALIGNMENT`.
+        chunk.offset = (chunk.offset + size).next_multiple_of(wgpu::MAP_ALIGNMENT);
+        Ok(())
+    }
+
+    /// Unmaps the chunks written this frame. Call before submitting the
+    /// encoders passed to `write`.
+    pub fn finish(&mut self) {
+        for chunk in self.active.drain(..) {
+            chunk.buffer.unmap();
+            self.closed.push(chunk);
+        }
+    }
+
+    /// Remaps the chunks of submitted frames and reclaims those whose
+    /// mapping completed. Call after submitting.
+    pub fn recall(&mut self, context: &Context) {
+        for mut chunk in self.closed.drain(..) {
+            let sender = self.sender.clone();
+            let buffer = chunk.buffer.clone();
+            chunk.offset = 0;
+            buffer
+                .slice(..)
+                .map_async(wgpu::MapMode::Write, move |result| {
+                    if result.is_ok() {
+                        let _ = sender.send(chunk);
+                    }
+                });
+        }
+        context.device.poll(wgpu::Maintain::Poll);
+        self.free.extend(self.receiver.try_iter());
+    }
+
+    fn take_chunk(&mut self, context: &Context, size: u64) -> Result<Chunk, Error> {
+        if let Some(index) = self
+            .free
+            .iter()
+            .position(|chunk| chunk.buffer.size() >= size)
+        {
+            return Ok(self.free.swap_remove(index));
+        }
+
+        let chunk_size = self.chunk_size.max(size);
+        context.check_buffer_size(chunk_size)?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(chunk_size, "created upload heap chunk");
+        Ok(Chunk {
+            buffer: Arc::new(context.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Upload Heap Chunk"),
+                size: chunk_size,
+                usage: wgpu::BufferUsages::MAP_WRITE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: true,
+            })),
+            offset: 0,
+        })
+    }
+
+    /// Average wall clock time per frame of uploading `bytes` with each
+    /// strategy, including the submit and waiting for the GPU.
+    pub fn benchmark(
+        context: &Context,
+        bytes: u64,
+        iterations: u32,
+    ) -> Result<Vec<(UploadStrategy, Duration)>, Error> {
+        let bytes = bytes.next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT);
+        context.check_buffer_size(bytes)?;
+        let target = context.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Upload Benchmark Buffer"),
+            size: bytes,
+            usage: wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let data = vec![0u8; bytes as usize];
+
+        [UploadStrategy::QueueWrite, UploadStrategy::MappedHeap]
+            .into_iter()
+            .map(|strategy| {
+                let mut heap = UploadHeap::new(strategy, bytes);
+                let mut frame = || -> Result<(), Error> {
+                    let mut command_encoder =
+                        context
+                            .device
+                            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                                label: Some("Upload Benchmark Encoder"),
+                            });
+                    heap.write(context, &mut command_encoder, &target, 0, &data)?;
+                    heap.finish();
+                    let index = context.submit(command_encoder);
+                    heap.recall(context);
+                    context
+                        .device
+                        .poll(wgpu::Maintain::WaitForSubmissionIndex(index));
+                    Ok(())
+                };
+                // Warm up so chunk creation isn't measured.
+                frame()?;
+                let start = Instant::now();
+                for _ in 0..iterations {
+                    frame()?;
+                }
+                Ok((strategy, start.elapsed() / iterations.max(1)))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,241 @@
+use super::context::Context;
+use super::diagnostics::{describe_texture, PassRecord};
+use super::error::Error;
+use super::texture::Texture;
+
+const INTERMEDIATE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    output_size: [f32; 2],
+    sharpness: f32,
+    _padding: f32,
+}
+
+/// FSR 1.0-style spatial upscaler: an edge adaptive upsampling pass (EASU)
+/// followed by contrast adaptive sharpening (RCAS) at the output resolution.
+pub struct SpatialUpscaler {
+    intermediate: Texture,
+    output_format: wgpu::TextureFormat,
+    params: wgpu::Buffer,
+    bind_group_layout: wgpu::BindGroupLayout,
+    easu: wgpu::RenderPipeline,
+    rcas: wgpu::RenderPipeline,
+    /// Sharpening strength in stops, 0.0 is the strongest.
+    pub sharpness: f32,
+}
+
+impl SpatialUpscaler {
+    pub fn new(
+        context: &Context,
+        width: &u32,
+        height: &u32,
+        output_format: &wgpu::TextureFormat,
+    ) -> Result<Self, Error> {
+        let device = &context.device;
+        let module = device.create_shader_module(wgpu::include_wgsl!("shaders/upscale.wgsl"));
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Upscale Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Upscale Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |entry_point: &str, format: wgpu::TextureFormat| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Upscale Pipeline"),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &module,
+                    entry_point: "vs_main",
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    buffers: &[],
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &module,
+                    entry_point,
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    targets: &[Some(format.into())],
+                }),
+                multiview: None,
+            })
+        };
+        let easu = pipeline("easu", INTERMEDIATE_FORMAT);
+        let rcas = pipeline("rcas", *output_format);
+
+        let params = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Upscale Params"),
+            size: std::mem::size_of::<Params>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let intermediate = context.render_target(
+            width,
+            height,
+            &INTERMEDIATE_FORMAT,
+            Some("Upscale Intermediate"),
+        )?;
+
+        Ok(Self {
+            intermediate,
+            output_format: *output_format,
+            params,
+            bind_group_layout,
+            easu,
+            rcas,
+            sharpness: 0.2,
+        })
+    }
+
+    pub fn output_size(&self) -> (u32, u32) {
+        (self.intermediate.width(), self.intermediate.height())
+    }
+
+    pub fn output_format(&self) -> wgpu::TextureFormat {
+        self.output_format
+    }
+
+    pub fn resize(&mut self, context: &Context, width: &u32, height: &u32) -> Result<(), Error> {
+        if self.output_size() != (*width, *height) {
+            self.intermediate = context.render_target(
+                width,
+                height,
+                &INTERMEDIATE_FORMAT,
+                Some("Upscale Intermediate"),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Upscales `input` into `target`, which must have the output size and
+    /// format. The parameters are written through the queue, so all
+    /// upscales recorded before a submit share the last `sharpness`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "trace",
+            skip_all,
+            fields(input_width = input.width(), input_height = input.height())
+        )
+    )]
+    pub fn upscale(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        input: &Texture,
+        target: &wgpu::TextureView,
+    ) {
+        let (width, height) = self.output_size();
+        let params = Params {
+            output_size: [width as f32, height as f32],
+            sharpness: (-self.sharpness.max(0.0)).exp2(),
+            _padding: 0.0,
+        };
+        context
+            .queue
+            .write_buffer(&self.params, 0, bytemuck::bytes_of(&params));
+
+        context.diagnostics.record_pass(|| {
+            PassRecord::new(
+                "Upscale Pass",
+                "easu",
+                vec![
+                    describe_texture("input", &input.texture),
+                    describe_texture("intermediate", &self.intermediate.texture),
+                ],
+            )
+        });
+        self.pass(
+            context,
+            encoder,
+            &self.easu,
+            &input.view,
+            &self.intermediate.view,
+        );
+        context.diagnostics.record_pass(|| {
+            PassRecord::new(
+                "Upscale Pass",
+                "rcas",
+                vec![describe_texture("intermediate", &self.intermediate.texture)],
+            )
+        });
+        self.pass(
+            context,
+            encoder,
+            &self.rcas,
+            &self.intermediate.view,
+            target,
+        );
+    }
+
+    fn pass(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        pipeline: &wgpu::RenderPipeline,
+        source: &wgpu::TextureView,
+        target: &wgpu::TextureView,
+    ) {
+        let bind_group = context
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Upscale Bind Group"),
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: self.params.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(source),
+                    },
+                ],
+            });
+
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Upscale Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,1382 @@
+use super::context::Context;
+use super::coordinates::{CoordinateSystem, ImportOptions, LengthUnit, UpAxis};
+use super::error::Error;
+use super::mesh::{Mesh, Vertex};
+use super::scene::{
+    MaterialDescription, MeshDescription, MeshSource, ObjectDescription, Scene, SceneDescription,
+};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+/// Meshes, transforms and `UsdPreviewSurface` materials read from a USDA
+/// layer or a USDZ package whose root layer is USDA.
+///
+/// Only `def` prims of the root layer are imported: references, payloads,
+/// variants and binary (`usdc`) layers are not supported, and time-sampled
+/// attributes take their first sample. Transforms are baked into the mesh
+/// vertices, and every `Mesh` prim becomes one mesh and one object named by
+/// its prim path.
+pub struct UsdImport {
+    pub scene: SceneDescription,
+    /// `"Y"` or `"Z"`. Nothing is converted unless opened with `open_with`.
+    pub up_axis: String,
+    pub meters_per_unit: f64,
+    /// Files of the package by path, empty for a loose layer.
+    files: HashMap<PathBuf, Vec<u8>>,
+    base: PathBuf,
+}
+
+impl UsdImport {
+    /// Opens a `.usdz` package or a `.usda` layer.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let data = std::fs::read(path)?;
+        let mut import = if path.extension().is_some_and(|e| e == "usdz") {
+            Self::from_usdz(&data)?
+        } else {
+            Self::from_usda(layer_text(&data)?, Path::new(""))?
+        };
+        import.base = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        Ok(import)
+    }
+
+    /// Opens like `open` and converts the scene to `options.target` and
+    /// `options.target_units`, from the layer's `upAxis` and
+    /// `metersPerUnit` unless the options set the source.
+    pub fn open_with(path: impl AsRef<Path>, options: &ImportOptions) -> Result<Self, Error> {
+        let mut import = Self::open(path)?;
+        let source = options.source.unwrap_or(match import.up_axis.as_str() {
+            "Z" => CoordinateSystem::Z_UP_RIGHT_HANDED,
+            _ => CoordinateSystem::Y_UP_RIGHT_HANDED,
+        });
+        import.scene.convert_coordinates(&source, &options.target);
+        import.up_axis = match options.target.up {
+            UpAxis::Y => "Y",
+            UpAxis::Z => "Z",
+        }
+        .to_string();
+        let units = options
+            .source_units
+            .unwrap_or(LengthUnit::from_meters_per_unit(import.meters_per_unit));
+        import.scene.scale(units.scale_to(&options.target_units));
+        import.meters_per_unit = options.target_units.meters_per_unit();
+        Ok(import)
+    }
+
+    /// Parses a USDZ package. Its first file is the root layer and asset
+    /// paths resolve to files in the package.
+    pub fn from_usdz(data: &[u8]) -> Result<Self, Error> {
+        let entries = unzip(data)?;
+        let (root, layer) = entries
+            .first()
+            .ok_or_else(|| Error::InvalidScene("empty usdz package".to_string()))?;
+        let directory = Path::new(root).parent().unwrap_or(Path::new(""));
+        let mut import = Self::from_usda(layer_text(layer)?, directory)?;
+        import.files = entries
+            .iter()
+            .map(|(name, data)| (PathBuf::from(name), data.to_vec()))
+            .collect();
+        Ok(import)
+    }
+
+    /// Parses a USDA layer. Asset paths are resolved against `directory`.
+    pub fn from_usda(text: &str, directory: &Path) -> Result<Self, Error> {
+        let (metadata, prims) = Parser::new(text)?.layer()?;
+        let mut importer = Importer {
+            prims: HashMap::new(),
+            directory,
+            scene: SceneDescription::default(),
+        };
+        for prim in &prims {
+            importer.index(prim);
+        }
+        for prim in &prims {
+            importer.walk(prim, &IDENTITY, None)?;
+        }
+        Ok(Self {
+            scene: importer.scene,
+            up_axis: metadata
+                .get("upAxis")
+                .and_then(Value::as_str)
+                .unwrap_or("Y")
+                .to_string(),
+            meters_per_unit: metadata
+                .get("metersPerUnit")
+                .and_then(|v| v.numbers().first().copied())
+                .unwrap_or(0.01),
+            files: HashMap::new(),
+            base: PathBuf::new(),
+        })
+    }
+}
+
+impl Context {
+    pub fn load_usd(&self, path: impl AsRef<Path>) -> Result<Scene, Error> {
+        self.instantiate_usd(&UsdImport::open(path)?)
+    }
+
+    pub fn instantiate_usd(&self, import: &UsdImport) -> Result<Scene, Error> {
+        self.instantiate_scene_with(import.scene.clone(), |path| {
+            Ok(match import.files.get(path) {
+                Some(data) => image::load_from_memory(data)?,
+                None => image::open(import.base.join(path))?,
+            })
+        })
+    }
+}
+
+fn layer_text(data: &[u8]) -> Result<&str, Error> {
+    if data.starts_with(b"PXR-USDC") {
+        return Err(Error::InvalidScene(
+            "binary usdc layers are not supported, convert them with usdcat".to_string(),
+        ));
+    }
+    std::str::from_utf8(data).map_err(|_| Error::InvalidScene("layer is not utf-8".to_string()))
+}
+
+/// Entries of a zip archive in order. USDZ packages store files
+/// uncompressed, so compressed entries are rejected.
+fn unzip(data: &[u8]) -> Result<Vec<(String, &[u8])>, Error> {
+    let invalid = || Error::InvalidScene("invalid usdz archive".to_string());
+    let bytes = |offset: usize, len: usize| data.get(offset..offset + len).ok_or_else(invalid);
+    let u16_at = |offset| bytes(offset, 2).map(|b| u16::from_le_bytes([b[0], b[1]]) as usize);
+    let u32_at =
+        |offset| bytes(offset, 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize);
+
+    let end = (0..=data.len().saturating_sub(22))
+        .rev()
+        .take(65536 + 22)
+        .find(|&offset| data[offset..].starts_with(&[0x50, 0x4b, 0x05, 0x06]))
+        .ok_or_else(invalid)?;
+    let mut entry = u32_at(end + 16)?;
+    let mut entries = Vec::new();
+    for _ in 0..u16_at(end + 10)? {
+        if u32_at(entry)? != 0x0201_4b50 {
+            return Err(invalid());
+        }
+        let name_len = u16_at(entry + 28)?;
+        let name = String::from_utf8_lossy(bytes(entry + 46, name_len)?).into_owned();
+        if u16_at(entry + 10)? != 0 {
+            return Err(Error::InvalidScene(format!(
+                "usdz entry {name} is compressed"
+            )));
+        }
+        let size = u32_at(entry + 20)?;
+        let local = u32_at(entry + 42)?;
+        if u32_at(local)? != 0x0403_4b50 {
+            return Err(invalid());
+        }
+        let start = local + 30 + u16_at(local + 26)? + u16_at(local + 28)?;
+        entries.push((name, bytes(start, size)?));
+        entry += 46 + name_len + u16_at(entry + 30)? + u16_at(entry + 32)?;
+    }
+    Ok(entries)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    String(String),
+    Asset(String),
+    Path(String),
+    Punct(char),
+    Newline,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Number(f64),
+    /// Strings, tokens, asset and prim paths.
+    String(String),
+    /// Tuples and arrays.
+    List(Vec<Value>),
+}
+
+impl Value {
+    fn numbers(&self) -> Vec<f64> {
+        match self {
+            Value::Number(n) => vec![*n],
+            Value::List(values) => values.iter().flat_map(Value::numbers).collect(),
+            Value::String(_) => Vec::new(),
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            Value::List(values) if values.len() == 1 => values[0].as_str(),
+            _ => None,
+        }
+    }
+
+    fn strings(&self) -> Vec<&str> {
+        match self {
+            Value::List(values) => values.iter().filter_map(Value::as_str).collect(),
+            _ => self.as_str().into_iter().collect(),
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, Error> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let (mut i, mut line) = (0, 1);
+    let error = |line, message: &str| Error::InvalidScene(format!("usda line {line}: {message}"));
+    while let Some(&c) = chars.get(i) {
+        let start_line = line;
+        let token = match c {
+            '\n' => {
+                line += 1;
+                i += 1;
+                Token::Newline
+            }
+            _ if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '#' => {
+                while chars.get(i).is_some_and(|&c| c != '\n') {
+                    i += 1;
+                }
+                continue;
+            }
+            '"' | '\'' => {
+                let triple = chars.get(i + 1) == Some(&c) && chars.get(i + 2) == Some(&c);
+                i += if triple { 3 } else { 1 };
+                let mut string = String::new();
+                loop {
+                    match chars.get(i) {
+                        None => return Err(error(start_line, "unterminated string")),
+                        Some(&q) if q == c && !triple => {
+                            i += 1;
+                            break;
+                        }
+                        Some(&q)
+                            if q == c
+                                && chars.get(i + 1) == Some(&c)
+                                && chars.get(i + 2) == Some(&c) =>
+                        {
+                            i += 3;
+                            break;
+                        }
+                        Some('\\') => {
+                            string.push(match chars.get(i + 1) {
+                                Some('n') => '\n',
+                                Some('t') => '\t',
+                                Some(&e) => e,
+                                None => return Err(error(line, "unterminated string")),
+                            });
+                            i += 2;
+                        }
+                        Some(&ch) => {
+                            line += (ch == '\n') as usize;
+                            string.push(ch);
+                            i += 1;
+                        }
+                    }
+                }
+                Token::String(string)
+            }
+            '@' | '<' => {
+                let close = if c == '@' { '@' } else { '>' };
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|&ch| ch == close)
+                    .ok_or_else(|| error(line, "unterminated asset or path"))?;
+                let string = chars[i + 1..i + 1 + end].iter().collect();
+                i += end + 2;
+                if c == '@' {
+                    Token::Asset(string)
+                } else {
+                    Token::Path(string)
+                }
+            }
+            _ if c.is_ascii_digit()
+                || matches!(c, '-' | '+' | '.')
+                    && chars
+                        .get(i + 1)
+                        .is_some_and(|n| n.is_ascii_alphanumeric() || *n == '.') =>
+            {
+                let start = i;
+                i += 1;
+                while chars
+                    .get(i)
+                    .is_some_and(|&ch| ch.is_ascii_alphanumeric() || ch == '.')
+                    || (chars.get(i).is_some_and(|&ch| ch == '-' || ch == '+')
+                        && matches!(chars[i - 1], 'e' | 'E'))
+                {
+                    i += 1;
+                }
+                let number: String = chars[start..i].iter().collect();
+                Token::Number(
+                    number
+                        .parse()
+                        .map_err(|_| error(line, &format!("invalid number {number}")))?,
+                )
+            }
+            _ if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while chars
+                    .get(i)
+                    .is_some_and(|&ch| ch.is_alphanumeric() || matches!(ch, '_' | ':' | '.'))
+                {
+                    i += 1;
+                }
+                Token::Ident(chars[start..i].iter().collect())
+            }
+            _ => {
+                i += 1;
+                Token::Punct(c)
+            }
+        };
+        tokens.push((token, start_line));
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug)]
+struct Property {
+    name: String,
+    type_name: String,
+    value: Option<Value>,
+    time_sampled: bool,
+    metadata: HashMap<String, Value>,
+}
+
+#[derive(Debug)]
+struct Prim {
+    specifier: String,
+    type_name: String,
+    path: String,
+    metadata: HashMap<String, Value>,
+    properties: Vec<Property>,
+    children: Vec<Prim>,
+}
+
+impl Prim {
+    fn property(&self, name: &str) -> Option<&Property> {
+        // Time samples win over the default value.
+        self.properties
+            .iter()
+            .filter(|p| p.name == name && p.value.is_some())
+            .max_by_key(|p| p.time_sampled)
+    }
+
+    fn value(&self, name: &str) -> Option<&Value> {
+        self.property(name).and_then(|p| p.value.as_ref())
+    }
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+}
+
+impl Parser {
+    fn new(text: &str) -> Result<Self, Error> {
+        Ok(Self {
+            tokens: tokenize(text)?,
+            position: 0,
+        })
+    }
+
+    fn error(&self, message: &str) -> Error {
+        let line = self
+            .tokens
+            .get(self.position.min(self.tokens.len().saturating_sub(1)))
+            .map_or(0, |(_, line)| *line);
+        Error::InvalidScene(format!("usda line {line}: {message}"))
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .map(|(token, _)| token.clone());
+        self.position += 1;
+        token
+    }
+
+    fn skip_newlines(&mut self) {
+        while self.peek() == Some(&Token::Newline) {
+            self.position += 1;
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let found = self.peek() == Some(&Token::Punct(c));
+        self.position += found as usize;
+        found
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), Error> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{c}'")))
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, Error> {
+        match self.next() {
+            Some(Token::Ident(ident)) => Ok(ident),
+            _ => {
+                self.position -= 1;
+                Err(self.error("expected an identifier"))
+            }
+        }
+    }
+
+    fn layer(&mut self) -> Result<(HashMap<String, Value>, Vec<Prim>), Error> {
+        self.skip_newlines();
+        let metadata = if self.eat('(') {
+            self.metadata()?
+        } else {
+            HashMap::new()
+        };
+        let mut prims = Vec::new();
+        loop {
+            self.skip_newlines();
+            match self.peek() {
+                None => return Ok((metadata, prims)),
+                Some(Token::Ident(word)) if matches!(word.as_str(), "def" | "over" | "class") => {
+                    prims.push(self.prim("")?)
+                }
+                _ => return Err(self.error("expected a prim")),
+            }
+        }
+    }
+
+    /// Parses `key = value` entries up to the closing parenthesis.
+    fn metadata(&mut self) -> Result<HashMap<String, Value>, Error> {
+        let mut metadata = HashMap::new();
+        loop {
+            self.skip_newlines();
+            if self.eat(')') {
+                return Ok(metadata);
+            }
+            match self.next() {
+                Some(Token::String(doc)) => {
+                    metadata.insert("doc".to_string(), Value::String(doc));
+                }
+                Some(Token::Ident(mut key)) => {
+                    if matches!(
+                        key.as_str(),
+                        "prepend" | "append" | "add" | "delete" | "reorder"
+                    ) {
+                        key = self.ident()?;
+                    }
+                    if self.eat('=') {
+                        metadata.insert(key, self.value()?);
+                    }
+                    self.eat(';');
+                }
+                _ => {
+                    self.position -= 1;
+                    return Err(self.error("expected metadata"));
+                }
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, Error> {
+        self.skip_newlines();
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Value::Number(n)),
+            Some(Token::String(s) | Token::Ident(s) | Token::Path(s)) => Ok(Value::String(s)),
+            Some(Token::Asset(asset)) => {
+                // A reference's target prim.
+                if matches!(self.peek(), Some(Token::Path(_))) {
+                    self.position += 1;
+                }
+                Ok(Value::String(asset))
+            }
+            Some(Token::Punct(open @ ('(' | '['))) => {
+                let close = if open == '(' { ')' } else { ']' };
+                let mut values = Vec::new();
+                loop {
+                    self.skip_newlines();
+                    if self.eat(close) {
+                        return Ok(Value::List(values));
+                    }
+                    values.push(self.value()?);
+                    self.skip_newlines();
+                    if !self.eat(',') {
+                        self.skip_newlines();
+                        self.expect(close)?;
+                        return Ok(Value::List(values));
+                    }
+                }
+            }
+            Some(Token::Punct('{')) => {
+                // Dictionaries are skipped.
+                self.position -= 1;
+                self.skip_block()?;
+                Ok(Value::List(Vec::new()))
+            }
+            _ => {
+                self.position -= 1;
+                Err(self.error("expected a value"))
+            }
+        }
+    }
+
+    fn skip_block(&mut self) -> Result<(), Error> {
+        self.expect('{')?;
+        let mut depth = 1;
+        while depth > 0 {
+            match self.next() {
+                Some(Token::Punct('{')) => depth += 1,
+                Some(Token::Punct('}')) => depth -= 1,
+                Some(_) => {}
+                None => return Err(self.error("unterminated block")),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the value of the first time sample.
+    fn time_samples(&mut self) -> Result<Option<Value>, Error> {
+        self.skip_newlines();
+        self.expect('{')?;
+        let mut first = None;
+        loop {
+            self.skip_newlines();
+            if self.eat('}') {
+                return Ok(first);
+            }
+            self.value()?;
+            self.expect(':')?;
+            let value = self.value()?;
+            first.get_or_insert(value);
+            self.skip_newlines();
+            self.eat(',');
+        }
+    }
+
+    fn prim(&mut self, parent: &str) -> Result<Prim, Error> {
+        let specifier = self.ident()?;
+        let type_name = match self.peek() {
+            Some(Token::Ident(_)) => self.ident()?,
+            _ => String::new(),
+        };
+        let Some(Token::String(name)) = self.next() else {
+            self.position -= 1;
+            return Err(self.error("expected a prim name"));
+        };
+        self.skip_newlines();
+        let metadata = if self.eat('(') {
+            self.metadata()?
+        } else {
+            HashMap::new()
+        };
+        self.skip_newlines();
+        self.expect('{')?;
+
+        let mut prim = Prim {
+            specifier,
+            type_name,
+            path: format!("{parent}/{name}"),
+            metadata,
+            properties: Vec::new(),
+            children: Vec::new(),
+        };
+        loop {
+            self.skip_newlines();
+            match self.peek() {
+                None => return Err(self.error("unterminated prim")),
+                Some(Token::Punct('}')) => {
+                    self.position += 1;
+                    return Ok(prim);
+                }
+                Some(Token::Ident(word)) if matches!(word.as_str(), "def" | "over" | "class") => {
+                    let child = self.prim(&prim.path)?;
+                    prim.children.push(child);
+                }
+                Some(Token::Ident(word)) if word == "variantSet" => {
+                    while !matches!(self.peek(), Some(Token::Punct('{')) | None) {
+                        self.position += 1;
+                    }
+                    self.skip_block()?;
+                }
+                Some(Token::Ident(word)) if word == "reorder" => {
+                    while !matches!(self.peek(), Some(Token::Newline) | None) {
+                        self.position += 1;
+                    }
+                }
+                _ => prim.properties.push(self.property()?),
+            }
+        }
+    }
+
+    fn property(&mut self) -> Result<Property, Error> {
+        let mut type_name = self.ident()?;
+        while matches!(
+            type_name.as_str(),
+            "custom" | "uniform" | "varying" | "config" | "prepend" | "append" | "add" | "delete"
+        ) {
+            type_name = self.ident()?;
+        }
+        if self.eat('[') {
+            self.expect(']')?;
+            type_name.push_str("[]");
+        }
+        let mut name = self.ident()?;
+        let mut value = None;
+        let time_sampled = name.ends_with(".timeSamples");
+        if self.eat('=') {
+            if time_sampled {
+                name.truncate(name.len() - ".timeSamples".len());
+                value = self.time_samples()?;
+            } else {
+                value = Some(self.value()?);
+            }
+        }
+        let metadata = if self.eat('(') {
+            self.metadata()?
+        } else {
+            HashMap::new()
+        };
+        Ok(Property {
+            name,
+            type_name,
+            value,
+            time_sampled,
+            metadata,
+        })
+    }
+}
+
+/// Column-major, for column vectors.
+type Matrix = [[f64; 4]; 4];
+
+const IDENTITY: Matrix = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    let mut m = [[0.0; 4]; 4];
+    for (column, b_column) in m.iter_mut().zip(b) {
+        for (row, value) in column.iter_mut().enumerate() {
+            *value = (0..4).map(|k| a[k][row] * b_column[k]).sum();
+        }
+    }
+    m
+}
+
+fn rotation(axis: usize, degrees: f64) -> Matrix {
+    let (s, c) = degrees.to_radians().sin_cos();
+    let (i, j) = ((axis + 1) % 3, (axis + 2) % 3);
+    let mut m = IDENTITY;
+    m[i][i] = c;
+    m[i][j] = s;
+    m[j][i] = -s;
+    m[j][j] = c;
+    m
+}
+
+/// Cross products of the columns of the upper 3x3, i.e. its cofactor
+/// matrix, and its determinant.
+fn cofactors(m: &Matrix) -> ([[f64; 3]; 3], f64) {
+    let cross = |a: &[f64; 4], b: &[f64; 4]| {
+        [
+            a[1] * b[2] - a[2] * b[1],
+            a[2] * b[0] - a[0] * b[2],
+            a[0] * b[1] - a[1] * b[0],
+        ]
+    };
+    let cofactors = [
+        cross(&m[1], &m[2]),
+        cross(&m[2], &m[0]),
+        cross(&m[0], &m[1]),
+    ];
+    let determinant = (0..3).map(|i| m[0][i] * cofactors[0][i]).sum();
+    (cofactors, determinant)
+}
+
+fn affine_inverse(m: &Matrix) -> Option<Matrix> {
+    let (cofactors, determinant) = cofactors(m);
+    if determinant == 0.0 {
+        return None;
+    }
+    let mut inverse = IDENTITY;
+    for (column, values) in inverse.iter_mut().take(3).enumerate() {
+        for (row, value) in values.iter_mut().take(3).enumerate() {
+            *value = cofactors[row][column] / determinant;
+        }
+    }
+    let translation = [0, 1, 2].map(|row| -(0..3).map(|k| inverse[k][row] * m[3][k]).sum::<f64>());
+    inverse[3][..3].copy_from_slice(&translation);
+    Some(inverse)
+}
+
+/// The local transform of an xformable prim and whether it resets the
+/// parent transform.
+fn local_transform(prim: &Prim) -> Result<(Matrix, bool), Error> {
+    let Some(order) = prim.value("xformOpOrder") else {
+        return Ok((IDENTITY, false));
+    };
+    let (mut matrix, mut reset) = (IDENTITY, false);
+    for op in order.strings() {
+        if op == "!resetXformStack!" {
+            (matrix, reset) = (IDENTITY, true);
+            continue;
+        }
+        let (invert, name) = match op.strip_prefix("!invert!") {
+            Some(name) => (true, name),
+            None => (false, op),
+        };
+        let invalid =
+            |message: &str| Error::InvalidScene(format!("{}: {name} {message}", prim.path));
+        let values = prim
+            .value(name)
+            .ok_or_else(|| invalid("has no value"))?
+            .numbers();
+        let kind = name
+            .strip_prefix("xformOp:")
+            .and_then(|name| name.split(':').next())
+            .unwrap_or_default();
+        let expected = match kind {
+            "rotateX" | "rotateY" | "rotateZ" => 1,
+            "orient" => 4,
+            "transform" => 16,
+            _ => 3,
+        };
+        if values.len() < expected {
+            return Err(invalid("has too few values"));
+        }
+        let axis = |c: u8| (c - b'X') as usize;
+        let op_matrix = match kind {
+            "translate" => {
+                let mut m = IDENTITY;
+                m[3][..3].copy_from_slice(&values[..3]);
+                m
+            }
+            "scale" => {
+                let mut m = IDENTITY;
+                (0..3).for_each(|i| m[i][i] = values[i]);
+                m
+            }
+            "rotateX" | "rotateY" | "rotateZ" => rotation(axis(kind.as_bytes()[6]), values[0]),
+            _ if kind.len() == 9
+                && kind.starts_with("rotate")
+                && kind[6..].bytes().all(|c| matches!(c, b'X' | b'Y' | b'Z')) =>
+            {
+                // The first axis is applied first.
+                kind[6..]
+                    .bytes()
+                    .zip(&values)
+                    .fold(IDENTITY, |m, (c, &degrees)| {
+                        multiply(&rotation(axis(c), degrees), &m)
+                    })
+            }
+            "orient" => {
+                let [w, x, y, z] = [values[0], values[1], values[2], values[3]];
+                [
+                    [
+                        1.0 - 2.0 * (y * y + z * z),
+                        2.0 * (x * y + z * w),
+                        2.0 * (x * z - y * w),
+                        0.0,
+                    ],
+                    [
+                        2.0 * (x * y - z * w),
+                        1.0 - 2.0 * (x * x + z * z),
+                        2.0 * (y * z + x * w),
+                        0.0,
+                    ],
+                    [
+                        2.0 * (x * z + y * w),
+                        2.0 * (y * z - x * w),
+                        1.0 - 2.0 * (x * x + y * y),
+                        0.0,
+                    ],
+                    [0.0, 0.0, 0.0, 1.0],
+                ]
+            }
+            // USD matrices are row-major for row vectors, which is the
+            // column-major layout for column vectors.
+            "transform" => {
+                let mut m = IDENTITY;
+                for (i, value) in values.iter().take(16).enumerate() {
+                    m[i / 4][i % 4] = *value;
+                }
+                m
+            }
+            _ => return Err(invalid("is not a supported transform operation")),
+        };
+        let op_matrix = if invert {
+            affine_inverse(&op_matrix).ok_or_else(|| invalid("is not invertible"))?
+        } else {
+            op_matrix
+        };
+        matrix = multiply(&matrix, &op_matrix);
+    }
+    Ok((matrix, reset))
+}
+
+/// A primvar (or the `normals` attribute) with its interpolation resolved.
+struct Primvar {
+    name: String,
+    values: Vec<f64>,
+    components: usize,
+    interpolation: String,
+    indices: Option<Vec<f64>>,
+}
+
+impl Primvar {
+    fn new(prim: &Prim, name: &str, components: usize, counts: [usize; 3]) -> Option<Self> {
+        let property = prim.property(name)?;
+        let values = property.value.as_ref()?.numbers();
+        let elements = values.len() / components;
+        let [points, faces, corners] = counts;
+        let interpolation = match property
+            .metadata
+            .get("interpolation")
+            .and_then(Value::as_str)
+        {
+            Some(interpolation) => interpolation.to_string(),
+            None if elements == points => "vertex".to_string(),
+            None if elements == corners => "faceVarying".to_string(),
+            None if elements == faces => "uniform".to_string(),
+            None => "constant".to_string(),
+        };
+        Some(Self {
+            name: name.to_string(),
+            values,
+            components,
+            interpolation,
+            indices: prim.value(&format!("{name}:indices")).map(Value::numbers),
+        })
+    }
+
+    fn get(&self, prim: &Prim, face: usize, corner: usize, point: usize) -> Result<&[f64], Error> {
+        let element = match self.interpolation.as_str() {
+            "constant" => 0,
+            "uniform" => face,
+            "vertex" | "varying" => point,
+            _ => corner,
+        };
+        let element = match &self.indices {
+            Some(indices) => indices
+                .get(element)
+                .filter(|&&i| i >= 0.0)
+                .map(|&i| i as usize),
+            None => Some(element),
+        };
+        element
+            .and_then(|e| {
+                self.values
+                    .get(e * self.components..(e + 1) * self.components)
+            })
+            .ok_or_else(|| {
+                Error::InvalidScene(format!("{}: {} has too few values", prim.path, self.name))
+            })
+    }
+}
+
+struct Importer<'a> {
+    prims: HashMap<&'a str, &'a Prim>,
+    directory: &'a Path,
+    scene: SceneDescription,
+}
+
+impl<'a> Importer<'a> {
+    fn index(&mut self, prim: &'a Prim) {
+        self.prims.insert(&prim.path, prim);
+        for child in &prim.children {
+            self.index(child);
+        }
+    }
+
+    fn walk(&mut self, prim: &Prim, parent: &Matrix, binding: Option<&str>) -> Result<(), Error> {
+        let active = prim.metadata.get("active").and_then(Value::as_str) != Some("false");
+        if prim.specifier != "def" || !active {
+            return Ok(());
+        }
+        let (local, reset) = local_transform(prim)?;
+        let world = if reset {
+            local
+        } else {
+            multiply(parent, &local)
+        };
+        let binding = ["material:binding", "material:binding:preview"]
+            .iter()
+            .find_map(|name| prim.value(name).and_then(Value::as_str))
+            .or(binding);
+        if prim.type_name == "Mesh" {
+            self.mesh(prim, &world, binding)?;
+        }
+        for child in &prim.children {
+            self.walk(child, &world, binding)?;
+        }
+        Ok(())
+    }
+
+    fn mesh(&mut self, prim: &Prim, world: &Matrix, binding: Option<&str>) -> Result<(), Error> {
+        let numbers = |name| prim.value(name).map(Value::numbers).unwrap_or_default();
+        let points = numbers("points");
+        let counts: Vec<usize> = numbers("faceVertexCounts")
+            .iter()
+            .map(|&c| c as usize)
+            .collect();
+        // Negative indices map out of range rather than saturating to 0.
+        let indices: Vec<usize> = numbers("faceVertexIndices")
+            .iter()
+            .map(|&i| if i < 0.0 { usize::MAX } else { i as usize })
+            .collect();
+        if points.is_empty() || counts.is_empty() {
+            return Ok(());
+        }
+        let invalid = |message: &str| Error::InvalidScene(format!("{}: {message}", prim.path));
+        let corners = counts
+            .iter()
+            .try_fold(0usize, |sum, &count| sum.checked_add(count));
+        if corners != Some(indices.len()) {
+            return Err(invalid("faceVertexCounts do not match faceVertexIndices"));
+        }
+        if indices.iter().any(|&i| i >= points.len() / 3) {
+            return Err(invalid("face vertex index out of range"));
+        }
+
+        let sizes = [points.len() / 3, counts.len(), indices.len()];
+        let normals = ["primvars:normals", "normals"]
+            .iter()
+            .find_map(|name| Primvar::new(prim, name, 3, sizes));
+        let uv_name = prim
+            .properties
+            .iter()
+            .filter(|p| {
+                p.name.starts_with("primvars:")
+                    && !p.name.ends_with(":indices")
+                    && p.type_name == "texCoord2f[]"
+            })
+            .map(|p| p.name.as_str())
+            .find(|&name| name == "primvars:st")
+            .or_else(|| {
+                prim.properties
+                    .iter()
+                    .find(|p| p.type_name == "texCoord2f[]" && !p.name.ends_with(":indices"))
+                    .map(|p| p.name.as_str())
+            });
+        let uvs = uv_name.and_then(|name| Primvar::new(prim, name, 2, sizes));
+
+        let (cofactors, determinant) = cofactors(world);
+        let left_handed = prim.value("orientation").and_then(Value::as_str) == Some("leftHanded");
+        let flip = left_handed != (determinant < 0.0);
+
+        let mut mesh = Mesh::default();
+        let mut lookup = HashMap::new();
+        let mut corner = 0;
+        for (face, &count) in counts.iter().enumerate() {
+            let mut face_vertices = Vec::with_capacity(count);
+            for (k, &point) in indices[corner..corner + count].iter().enumerate() {
+                let p = &points[point * 3..point * 3 + 3];
+                let position = [0, 1, 2]
+                    .map(|row| (0..3).map(|k| world[k][row] * p[k]).sum::<f64>() + world[3][row]);
+                let normal = match &normals {
+                    Some(normals) => {
+                        let n = normals.get(prim, face, corner + k, point)?;
+                        let n = [0, 1, 2].map(|row| {
+                            (0..3).map(|k| cofactors[k][row] * n[k]).sum::<f64>()
+                                * determinant.signum()
+                        });
+                        let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+                        n.map(|c| if length > 0.0 { c / length } else { 0.0 })
+                    }
+                    None => [0.0; 3],
+                };
+                // USD texture coordinates start at the bottom left.
+                let uv = match &uvs {
+                    Some(uvs) => {
+                        let uv = uvs.get(prim, face, corner + k, point)?;
+                        [uv[0], 1.0 - uv[1]]
+                    }
+                    None => [0.0; 2],
+                };
+                let vertex = Vertex {
+                    position: position.map(|v| v as f32),
+                    normal: normal.map(|v| v as f32),
+                    uv: uv.map(|v| v as f32),
+                };
+                let key: [u32; 8] = bytemuck::cast(vertex);
+                let index = *lookup.entry(key).or_insert_with(|| {
+                    mesh.vertices.push(vertex);
+                    mesh.vertices.len() as u32 - 1
+                });
+                face_vertices.push(index);
+            }
+            for i in 1..count.saturating_sub(1) {
+                let (a, b, c) = (face_vertices[0], face_vertices[i], face_vertices[i + 1]);
+                mesh.indices
+                    .extend(if flip { [a, c, b] } else { [a, b, c] });
+            }
+            corner += count;
+        }
+        if normals.is_none() {
+            mesh.compute_normals();
+        }
+
+        let material = binding.and_then(|path| self.material(path));
+        self.scene.meshes.push(MeshDescription {
+            name: prim.path.clone(),
+            source: MeshSource::Inline(mesh),
+        });
+        self.scene.objects.push(ObjectDescription {
+            material,
+            ..ObjectDescription::new(&prim.path)
+        });
+        Ok(())
+    }
+
+    /// Adds the material at `path` from its `UsdPreviewSurface` shader,
+    /// returning its name.
+    fn material(&mut self, path: &str) -> Option<String> {
+        if self.scene.materials.iter().any(|m| m.name == path) {
+            return Some(path.to_string());
+        }
+        let material = *self.prims.get(path)?;
+        let shader = material
+            .value("outputs:surface.connect")
+            .and_then(|target| self.connected(target))
+            .filter(|shader| is_shader(shader, "UsdPreviewSurface"))
+            .or_else(|| find_shader(material, "UsdPreviewSurface"));
+
+        let mut description = MaterialDescription {
+            name: path.to_string(),
+            base_color: [0.18, 0.18, 0.18, 1.0],
+            ..Default::default()
+        };
+        if let Some(shader) = shader {
+            let number = |name| shader.value(name).map(Value::numbers).unwrap_or_default();
+            let texture = shader
+                .value("inputs:diffuseColor.connect")
+                .and_then(|target| self.connected(target))
+                .filter(|texture| is_shader(texture, "UsdUVTexture"))
+                .and_then(|texture| texture.value("inputs:file"))
+                .and_then(Value::as_str);
+            match texture {
+                Some(file) => {
+                    description.base_color_texture = Some(normalize(&self.directory.join(file)));
+                    description.base_color[..3].fill(1.0);
+                }
+                None => {
+                    if let [r, g, b, ..] = number("inputs:diffuseColor")[..] {
+                        description.base_color[..3]
+                            .copy_from_slice(&[r as f32, g as f32, b as f32]);
+                    }
+                }
+            }
+            if let Some(&opacity) = number("inputs:opacity").first() {
+                description.base_color[3] = opacity as f32;
+            }
+            if let Some(&metallic) = number("inputs:metallic").first() {
+                description.metallic = metallic as f32;
+            }
+            if let Some(&roughness) = number("inputs:roughness").first() {
+                description.roughness = roughness as f32;
+            }
+            if let [r, g, b, ..] = number("inputs:emissiveColor")[..] {
+                description.emissive = [r as f32, g as f32, b as f32];
+            }
+        }
+        self.scene.materials.push(description);
+        Some(path.to_string())
+    }
+
+    /// The prim of a connection target such as `</Material/Tex.outputs:rgb>`.
+    fn connected(&self, target: &Value) -> Option<&'a Prim> {
+        let target = target.as_str()?;
+        let path = target.split_once('.').map_or(target, |(path, _)| path);
+        self.prims.get(path).copied()
+    }
+}
+
+fn is_shader(prim: &Prim, id: &str) -> bool {
+    prim.value("info:id").and_then(Value::as_str) == Some(id)
+}
+
+fn find_shader<'a>(prim: &'a Prim, id: &str) -> Option<&'a Prim> {
+    prim.children
+        .iter()
+        .find(|child| is_shader(child, id))
+        .or_else(|| {
+            prim.children
+                .iter()
+                .find_map(|child| find_shader(child, id))
+        })
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn import(text: &str) -> Result<UsdImport, Error> {
+        UsdImport::from_usda(text, Path::new(""))
+    }
+
+    fn meshes(text: &str) -> Vec<(String, Mesh)> {
+        import(text)
+            .unwrap()
+            .scene
+            .meshes
+            .into_iter()
+            .map(|description| match description.source {
+                MeshSource::Inline(mesh) => (description.name, mesh),
+                other => panic!("expected an inline mesh, got {other:?}"),
+            })
+            .collect()
+    }
+
+    fn positions(mesh: &Mesh) -> Vec<[f32; 3]> {
+        mesh.indices
+            .iter()
+            .map(|&i| mesh.vertices[i as usize].position)
+            .collect()
+    }
+
+    /// A layer with a one-triangle mesh in an `Xform` with `parent_ops`,
+    /// the mesh having `mesh_ops` and extra `attributes`.
+    fn layer(parent_ops: &str, mesh_ops: &str, attributes: &str) -> String {
+        format!(
+            r#"#usda 1.0
+(
+    upAxis = "Z"
+    metersPerUnit = 1
+)
+
+def Xform "Root"
+{{
+    {parent_ops}
+    def Mesh "Triangle"
+    {{
+        point3f[] points = [(0, 0, 0), (1, 0, 0), (0, 1, 0)]
+        int[] faceVertexCounts = [3]
+        int[] faceVertexIndices = [0, 1, 2]
+        {mesh_ops}
+        {attributes}
+    }}
+}}
+"#
+        )
+    }
+
+    #[test]
+    fn tokenizes_literals() {
+        let tokens: Vec<Token> =
+            tokenize("def \"A\" # comment\n  asset a = @./t.png@ </B/C> -1.5e-2 '''x\ny''' [1,2]")
+                .unwrap()
+                .into_iter()
+                .map(|(token, _)| token)
+                .collect();
+        assert_eq!(
+            tokens,
+            [
+                Token::Ident("def".to_string()),
+                Token::String("A".to_string()),
+                Token::Newline,
+                Token::Ident("asset".to_string()),
+                Token::Ident("a".to_string()),
+                Token::Punct('='),
+                Token::Asset("./t.png".to_string()),
+                Token::Path("/B/C".to_string()),
+                Token::Number(-0.015),
+                Token::String("x\ny".to_string()),
+                Token::Punct('['),
+                Token::Number(1.0),
+                Token::Punct(','),
+                Token::Number(2.0),
+                Token::Punct(']'),
+            ]
+        );
+        assert!(tokenize("\"open").is_err());
+        assert!(tokenize("<open").is_err());
+    }
+
+    #[test]
+    fn parses_layers() {
+        let text = r#"#usda 1.0
+(
+    doc = "layer"
+    upAxis = "Z"
+)
+def Xform "A" (kind = "component")
+{
+    custom uniform token[] names = ["x", "y"]
+    float size.timeSamples = {
+        1: 2.5,
+        2: 4,
+    }
+    float size = 1
+    over "B" {}
+}
+"#;
+        let (metadata, prims) = Parser::new(text).unwrap().layer().unwrap();
+        assert_eq!(metadata["upAxis"], Value::String("Z".to_string()));
+        assert_eq!(metadata["doc"], Value::String("layer".to_string()));
+        let a = &prims[0];
+        assert_eq!((a.path.as_str(), a.type_name.as_str()), ("/A", "Xform"));
+        assert_eq!(a.metadata["kind"], Value::String("component".to_string()));
+        assert_eq!(a.value("names").unwrap().strings(), ["x", "y"]);
+        assert_eq!(a.value("size"), Some(&Value::Number(2.5)));
+        assert_eq!(a.children[0].path, "/A/B");
+        assert_eq!(a.children[0].specifier, "over");
+
+        let error = Parser::new("def Xform \"A\" {").unwrap().layer();
+        assert!(matches!(error, Err(Error::InvalidScene(message)) if message.contains("line 1")));
+    }
+
+    #[test]
+    fn reads_layer_metadata() {
+        let layer = import(&layer("", "", "")).unwrap();
+        assert_eq!((layer.up_axis.as_str(), layer.meters_per_unit), ("Z", 1.0));
+        let defaults = import("def Xform \"A\" {}").unwrap();
+        assert_eq!(
+            (defaults.up_axis.as_str(), defaults.meters_per_unit),
+            ("Y", 0.01)
+        );
+    }
+
+    #[test]
+    fn composes_xform_ops_in_order() {
+        // Scale first, then translate, as the ops apply right to left.
+        let mesh_ops = r#"double3 xformOp:translate = (10, 0, 0)
+        float3 xformOp:scale = (2, 2, 2)
+        uniform token[] xformOpOrder = ["xformOp:translate", "xformOp:scale"]"#;
+        let parent_ops = r#"float xformOp:rotateZ = 90
+        uniform token[] xformOpOrder = ["xformOp:rotateZ"]"#;
+        let (name, mesh) = meshes(&layer(parent_ops, mesh_ops, "")).remove(0);
+        assert_eq!(name, "/Root/Triangle");
+        let expected = [[0.0, 10.0, 0.0], [0.0, 12.0, 0.0], [-2.0, 10.0, 0.0]];
+        for (position, expected) in positions(&mesh).iter().zip(expected) {
+            for (a, b) in position.iter().zip(expected) {
+                assert!((a - b).abs() < 1e-5, "{position:?} != {expected:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn inverted_ops_cancel_out() {
+        let mesh_ops = r#"double3 xformOp:translate:pivot = (3, 4, 5)
+        float xformOp:rotateXYZ = (30, 40, 50)
+        uniform token[] xformOpOrder = ["xformOp:translate:pivot", "xformOp:rotateXYZ", "!invert!xformOp:rotateXYZ", "!invert!xformOp:translate:pivot"]"#;
+        let (_, mesh) = meshes(&layer("", mesh_ops, "")).remove(0);
+        let expected = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+        for (position, expected) in positions(&mesh).iter().zip(expected) {
+            for (a, b) in position.iter().zip(expected) {
+                assert!((a - b).abs() < 1e-5, "{position:?} != {expected:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn reset_xform_stack_ignores_parents() {
+        let parent_ops = r#"double3 xformOp:translate = (100, 0, 0)
+        uniform token[] xformOpOrder = ["xformOp:translate"]"#;
+        let mesh_ops = r#"double3 xformOp:translate = (0, 0, 1)
+        uniform token[] xformOpOrder = ["!resetXformStack!", "xformOp:translate"]"#;
+        let (_, mesh) = meshes(&layer(parent_ops, mesh_ops, "")).remove(0);
+        assert_eq!(
+            positions(&mesh),
+            [[0.0, 0.0, 1.0], [1.0, 0.0, 1.0], [0.0, 1.0, 1.0]]
+        );
+    }
+
+    #[test]
+    fn missing_and_singular_ops_are_errors() {
+        let missing = r#"uniform token[] xformOpOrder = ["xformOp:translate"]"#;
+        assert!(matches!(
+            import(&layer("", missing, "")),
+            Err(Error::InvalidScene(_))
+        ));
+        let singular = r#"float3 xformOp:scale = (0, 1, 1)
+        uniform token[] xformOpOrder = ["!invert!xformOp:scale"]"#;
+        assert!(matches!(
+            import(&layer("", singular, "")),
+            Err(Error::InvalidScene(message)) if message.contains("not invertible")
+        ));
+    }
+
+    #[test]
+    fn resolves_primvar_interpolation_and_indices() {
+        // faceVarying texture coordinates through indices, and one uniform
+        // normal for the face.
+        let attributes = r#"texCoord2f[] primvars:st = [(0, 0), (1, 0), (0, 1)] (
+            interpolation = "faceVarying"
+        )
+        int[] primvars:st:indices = [2, 1, 0]
+        normal3f[] normals = [(0, 0, -1)] (
+            interpolation = "uniform"
+        )"#;
+        let (_, mesh) = meshes(&layer("", "", attributes)).remove(0);
+        let uvs: Vec<[f32; 2]> = mesh
+            .indices
+            .iter()
+            .map(|&i| mesh.vertices[i as usize].uv)
+            .collect();
+        // Flipped to a top-left origin.
+        assert_eq!(uvs, [[0.0, 0.0], [1.0, 1.0], [0.0, 1.0]]);
+        assert!(mesh.vertices.iter().all(|v| v.normal == [0.0, 0.0, -1.0]));
+
+        // Without interpolation metadata it follows from the value count.
+        let attributes = "normal3f[] primvars:normals = [(1, 0, 0), (0, 1, 0), (0, 0, 1)]";
+        let (_, mesh) = meshes(&layer("", "", attributes)).remove(0);
+        let normals: Vec<[f32; 3]> = mesh
+            .indices
+            .iter()
+            .map(|&i| mesh.vertices[i as usize].normal)
+            .collect();
+        assert_eq!(normals, [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+    }
+
+    #[test]
+    fn out_of_range_primvar_indices_are_errors() {
+        for indices in ["[0, 1, 3]", "[0, 1, -1]"] {
+            let attributes = format!(
+                "texCoord2f[] primvars:st = [(0, 0), (1, 0), (0, 1)] (interpolation = \"faceVarying\")
+        int[] primvars:st:indices = {indices}"
+            );
+            assert!(matches!(
+                import(&layer("", "", &attributes)),
+                Err(Error::InvalidScene(message)) if message.contains("too few values")
+            ));
+        }
+    }
+
+    #[test]
+    fn invalid_topology_is_rejected() {
+        let overflowing =
+            layer("", "", "").replace("[3]", "[18446744073709551615, 18446744073709551615, 2]");
+        assert!(matches!(
+            import(&overflowing),
+            Err(Error::InvalidScene(message)) if message.contains("faceVertexCounts")
+        ));
+        let negative = layer("", "", "").replace("[0, 1, 2]", "[0, 1, -2]");
+        assert!(matches!(
+            import(&negative),
+            Err(Error::InvalidScene(message)) if message.contains("out of range")
+        ));
+    }
+}
@@ -0,0 +1,593 @@
+use super::context::Context;
+use super::depth::Colormap;
+use super::diagnostics::{describe_texture, PassRecord};
+use super::error::Error;
+use super::math::{cross, length, sub};
+use super::mesh::Mesh;
+use super::texture::Texture;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use wgpu::util::DeviceExt;
+
+const SRGB_TARGET: u32 = 1;
+/// Format of the texture counting the triangles over each texel.
+const COVERAGE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R16Float;
+/// Overlap along an axis, in UV units, below which triangles only touch.
+const OVERLAP_TOLERANCE: f32 = 1e-6;
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    colormap: u32,
+    flags: u32,
+    range: [f32; 2],
+}
+
+/// Which UV set of a mesh to inspect.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum UvChannel {
+    /// `Vertex::uv`.
+    #[default]
+    Uv,
+    /// `Mesh::uv2`.
+    Uv2,
+}
+
+impl Mesh {
+    fn channel_uvs(&self, channel: UvChannel) -> Result<Vec<[f32; 2]>, Error> {
+        match channel {
+            UvChannel::Uv => Ok(self.vertices.iter().map(|vertex| vertex.uv).collect()),
+            UvChannel::Uv2 if self.uv2.len() == self.vertices.len() => Ok(self.uv2.clone()),
+            UvChannel::Uv2 => Err(Error::InvalidMesh(format!(
+                "{} lightmap UVs for {} vertices",
+                self.uv2.len(),
+                self.vertices.len()
+            ))),
+        }
+    }
+
+    /// Texels per world unit of each triangle, for a `width` by `height`
+    /// texture mapped over `channel`. Triangles without area in world space
+    /// have a density of 0.
+    pub fn texel_densities(
+        &self,
+        channel: UvChannel,
+        width: &u32,
+        height: &u32,
+    ) -> Result<Vec<f32>, Error> {
+        let uvs = self.channel_uvs(channel)?;
+        let texels = *width as f32 * *height as f32;
+        Ok(self
+            .triangles()
+            .map(|[a, b, c]| {
+                let [pa, pb, pc] = [a, b, c].map(|i| self.vertices[i as usize].position);
+                let world_area = 0.5 * length(cross(sub(pb, pa), sub(pc, pa)));
+                let uv_area = triangle_area([a, b, c].map(|i| uvs[i as usize])).abs();
+                match world_area > 0.0 {
+                    true => (uv_area * texels / world_area).sqrt(),
+                    false => 0.0,
+                }
+            })
+            .collect())
+    }
+
+    /// Triangles grouped into the islands of `channel`: triangles sharing an
+    /// edge, with the same positions and UVs on both sides, belong to the
+    /// same island.
+    pub fn uv_islands(&self, channel: UvChannel) -> Result<Vec<Vec<usize>>, Error> {
+        let uvs = self.channel_uvs(channel)?;
+        let triangles: Vec<[u32; 3]> = self.triangles().collect();
+        let key = |index: u32| {
+            let [x, y, z] = self.vertices[index as usize].position.map(f32::to_bits);
+            let [u, v] = uvs[index as usize].map(f32::to_bits);
+            [x, y, z, u, v]
+        };
+        let mut parent: Vec<usize> = (0..triangles.len()).collect();
+        fn root(parent: &mut [usize], mut node: usize) -> usize {
+            while parent[node] != node {
+                parent[node] = parent[parent[node]];
+                node = parent[node];
+            }
+            node
+        }
+        let mut edges: HashMap<([u32; 5], [u32; 5]), usize> = HashMap::new();
+        for (triangle, indices) in triangles.iter().enumerate() {
+            for i in 0..3 {
+                let (a, b) = (key(indices[i]), key(indices[(i + 1) % 3]));
+                match edges.entry((a.min(b), a.max(b))) {
+                    Entry::Occupied(other) => {
+                        let (x, y) = (root(&mut parent, *other.get()), root(&mut parent, triangle));
+                        parent[x] = y;
+                    }
+                    Entry::Vacant(entry) => {
+                        entry.insert(triangle);
+                    }
+                }
+            }
+        }
+        let mut islands: Vec<Vec<usize>> = Vec::new();
+        let mut island_of = HashMap::new();
+        for triangle in 0..triangles.len() {
+            let island = *island_of
+                .entry(root(&mut parent, triangle))
+                .or_insert_with(|| {
+                    islands.push(Vec::new());
+                    islands.len() - 1
+                });
+            islands[island].push(triangle);
+        }
+        Ok(islands)
+    }
+
+    /// Pairs of indices into `uv_islands` whose triangles overlap in UV
+    /// space, with the smaller index first. An island folded over itself
+    /// pairs with itself. Triangles that only touch do not overlap.
+    pub fn overlapping_uv_islands(&self, channel: UvChannel) -> Result<Vec<[usize; 2]>, Error> {
+        let uvs = self.channel_uvs(channel)?;
+        let islands = self.uv_islands(channel)?;
+        let triangles: Vec<[[f32; 2]; 3]> = self
+            .triangles()
+            .map(|triangle| triangle.map(|i| uvs[i as usize]))
+            .collect();
+        let mut island_of = vec![0; triangles.len()];
+        for (island, members) in islands.iter().enumerate() {
+            for &triangle in members {
+                island_of[triangle] = island;
+            }
+        }
+
+        // Bucket triangles into a grid over their UV bounds, about one
+        // triangle per cell, and test the pairs sharing a cell.
+        let bounds = |triangle: &[[f32; 2]; 3]| {
+            let min = [0, 1].map(|i| triangle[0][i].min(triangle[1][i]).min(triangle[2][i]));
+            let max = [0, 1].map(|i| triangle[0][i].max(triangle[1][i]).max(triangle[2][i]));
+            (min, max)
+        };
+        let (mut min, mut max) = ([f32::MAX; 2], [f32::MIN; 2]);
+        for triangle in &triangles {
+            let (low, high) = bounds(triangle);
+            min = [min[0].min(low[0]), min[1].min(low[1])];
+            max = [max[0].max(high[0]), max[1].max(high[1])];
+        }
+        let cells = (triangles.len() as f32).sqrt().ceil().clamp(1.0, 1024.0) as usize;
+        let cell = |value: f32, axis: usize| {
+            let extent = (max[axis] - min[axis]).max(f32::MIN_POSITIVE);
+            (((value - min[axis]) / extent * cells as f32) as usize).min(cells - 1)
+        };
+        let mut grid: HashMap<[usize; 2], Vec<usize>> = HashMap::new();
+        for (index, triangle) in triangles.iter().enumerate() {
+            let (low, high) = bounds(triangle);
+            for y in cell(low[1], 1)..=cell(high[1], 1) {
+                for x in cell(low[0], 0)..=cell(high[0], 0) {
+                    grid.entry([x, y]).or_default().push(index);
+                }
+            }
+        }
+
+        let mut tested = HashSet::new();
+        let mut overlaps = HashSet::new();
+        for members in grid.values() {
+            for (i, &a) in members.iter().enumerate() {
+                for &b in &members[i + 1..] {
+                    let pair = [
+                        island_of[a].min(island_of[b]),
+                        island_of[a].max(island_of[b]),
+                    ];
+                    if overlaps.contains(&pair) || !tested.insert((a.min(b), a.max(b))) {
+                        continue;
+                    }
+                    if triangles_overlap(&triangles[a], &triangles[b]) {
+                        overlaps.insert(pair);
+                    }
+                }
+            }
+        }
+        let mut overlaps: Vec<[usize; 2]> = overlaps.into_iter().collect();
+        overlaps.sort_unstable();
+        Ok(overlaps)
+    }
+}
+
+/// Signed area of a 2D triangle, positive for counter-clockwise vertices.
+fn triangle_area([a, b, c]: [[f32; 2]; 3]) -> f32 {
+    0.5 * ((b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]))
+}
+
+/// Whether two 2D triangles share more than an edge or a point, by the
+/// separating axis theorem over their edge normals.
+fn triangles_overlap(a: &[[f32; 2]; 3], b: &[[f32; 2]; 3]) -> bool {
+    if triangle_area(*a) == 0.0 || triangle_area(*b) == 0.0 {
+        return false;
+    }
+    let project = |triangle: &[[f32; 2]; 3], axis: [f32; 2]| {
+        triangle
+            .iter()
+            .map(|p| p[0] * axis[0] + p[1] * axis[1])
+            .fold((f32::MAX, f32::MIN), |(low, high), d| {
+                (low.min(d), high.max(d))
+            })
+    };
+    [a, b].into_iter().all(|triangle| {
+        (0..3).all(|i| {
+            let (p, q) = (triangle[i], triangle[(i + 1) % 3]);
+            let edge = [q[0] - p[0], q[1] - p[1]];
+            let scale = (edge[0] * edge[0] + edge[1] * edge[1]).sqrt();
+            let axis = [-edge[1] / scale, edge[0] / scale];
+            let (a_min, a_max) = project(a, axis);
+            let (b_min, b_max) = project(b, axis);
+            a_max.min(b_max) - a_min.max(b_min) > OVERLAP_TOLERANCE
+        })
+    })
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum View {
+    Density,
+    Overlaps,
+}
+
+/// Debug views of a mesh's UV layout for asset checks: texel density
+/// through a colormap, and texels covered by more than one triangle.
+/// Both draw the unit square of UV space over the whole target, with
+/// (0, 0) at the top left.
+pub struct UvInspector {
+    module: wgpu::ShaderModule,
+    density_layout: wgpu::BindGroupLayout,
+    overlap_layout: wgpu::BindGroupLayout,
+    density_pipeline_layout: wgpu::PipelineLayout,
+    overlap_pipeline_layout: wgpu::PipelineLayout,
+    count: wgpu::RenderPipeline,
+    pipelines: Mutex<HashMap<(View, wgpu::TextureFormat), wgpu::RenderPipeline>>,
+    pub colormap: Colormap,
+    /// Texels per world unit mapped to the ends of the colormap, the range
+    /// of the mesh's densities if `None`.
+    pub density_range: Option<[f32; 2]>,
+}
+
+const UV_ATTRIBUTES: [wgpu::VertexAttribute; 2] =
+    wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32];
+
+fn uv_layout() -> wgpu::VertexBufferLayout<'static> {
+    wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<[f32; 3]>() as u64,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &UV_ATTRIBUTES,
+    }
+}
+
+impl UvInspector {
+    pub fn new(context: &Context) -> Self {
+        let device = &context.device;
+        let source = include_str!("shaders/colormap.wgsl").to_string()
+            + include_str!("shaders/uv_inspection.wgsl");
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("uv_inspection.wgsl"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let params = wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let density_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("UV Density Bind Group Layout"),
+            entries: &[params],
+        });
+        let overlap_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("UV Overlap Bind Group Layout"),
+            entries: &[
+                params,
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = |layouts: &[&wgpu::BindGroupLayout]| {
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("UV Inspector Pipeline Layout"),
+                bind_group_layouts: layouts,
+                push_constant_ranges: &[],
+            })
+        };
+        let count = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("fs_count"),
+            layout: Some(&pipeline_layout(&[])),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vs_uv",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                buffers: &[uv_layout()],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fs_count",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: COVERAGE_FORMAT,
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::One,
+                            dst_factor: wgpu::BlendFactor::One,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                        alpha: wgpu::BlendComponent::REPLACE,
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        });
+
+        Self {
+            density_pipeline_layout: pipeline_layout(&[&density_layout]),
+            overlap_pipeline_layout: pipeline_layout(&[&overlap_layout]),
+            module,
+            density_layout,
+            overlap_layout,
+            count,
+            pipelines: Mutex::new(HashMap::new()),
+            colormap: Colormap::default(),
+            density_range: None,
+        }
+    }
+
+    /// Clears `target` and draws the triangles of `mesh` at their `channel`
+    /// UVs, colored by `Mesh::texel_densities` for a `width` by `height`
+    /// texture.
+    #[allow(clippy::too_many_arguments)]
+    pub fn visualize_texel_density(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        mesh: &Mesh,
+        channel: UvChannel,
+        width: &u32,
+        height: &u32,
+        target: &Texture,
+    ) -> Result<(), Error> {
+        let densities = mesh.texel_densities(channel, width, height)?;
+        let range = self.density_range.unwrap_or_else(|| {
+            densities
+                .iter()
+                .filter(|&&density| density > 0.0)
+                .fold([f32::MAX, 0.0], |[low, high], &density| {
+                    [low.min(density), high.max(density)]
+                })
+        });
+        let vertices = uv_vertices(context, mesh, channel, &densities)?;
+        self.draw(
+            context,
+            encoder,
+            View::Density,
+            range,
+            &vertices,
+            &target.view,
+            target,
+        )
+    }
+
+    /// Clears `target` and marks the texels of the `channel` UV layout of
+    /// `mesh` covered by one triangle in green and by several in red, which
+    /// shows overlapping islands and folded charts. The coverage is counted
+    /// at the target's resolution.
+    pub fn visualize_uv_overlaps(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        mesh: &Mesh,
+        channel: UvChannel,
+        target: &Texture,
+    ) -> Result<(), Error> {
+        let vertices = uv_vertices(context, mesh, channel, &[])?;
+        let coverage = context.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("UV Coverage"),
+            size: wgpu::Extent3d {
+                depth_or_array_layers: 1,
+                ..target.size()
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: COVERAGE_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let coverage_view = coverage.create_view(&wgpu::TextureViewDescriptor::default());
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("UV Coverage"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &coverage_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_pipeline(&self.count);
+            pass.set_vertex_buffer(0, vertices.slice(..));
+            pass.draw(0..vertices.size() as u32 / 12, 0..1);
+        }
+        self.draw(
+            context,
+            encoder,
+            View::Overlaps,
+            [0.0; 2],
+            &vertices,
+            &coverage_view,
+            target,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn draw(
+        &self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        view: View,
+        range: [f32; 2],
+        vertices: &wgpu::Buffer,
+        coverage: &wgpu::TextureView,
+        target: &Texture,
+    ) -> Result<(), Error> {
+        if !target
+            .usage()
+            .contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+        {
+            return Err(Error::TextureCreationFailed);
+        }
+        let format = target.format();
+        let device = &context.device;
+        let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("UV Inspector Params"),
+            contents: bytemuck::bytes_of(&Params {
+                colormap: self.colormap.index(),
+                flags: match format.is_srgb() {
+                    true => SRGB_TARGET,
+                    false => 0,
+                },
+                range,
+            }),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let params_entry = wgpu::BindGroupEntry {
+            binding: 0,
+            resource: params.as_entire_binding(),
+        };
+        let bind_group = match view {
+            View::Density => device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("UV Density Bind Group"),
+                layout: &self.density_layout,
+                entries: &[params_entry],
+            }),
+            View::Overlaps => device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("UV Overlap Bind Group"),
+                layout: &self.overlap_layout,
+                entries: &[
+                    params_entry,
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(coverage),
+                    },
+                ],
+            }),
+        };
+        let entry_point = match view {
+            View::Density => "fs_density",
+            View::Overlaps => "fs_overlaps",
+        };
+        context.diagnostics.record_pass(|| {
+            PassRecord::new(
+                "UV Inspector",
+                entry_point,
+                vec![describe_texture("target", &target.texture)],
+            )
+        });
+
+        let mut pipelines = self
+            .pipelines
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let pipeline = pipelines.entry((view, format)).or_insert_with(|| {
+            let (layout, vertex_entry_point, buffers) = match view {
+                View::Density => (&self.density_pipeline_layout, "vs_uv", vec![uv_layout()]),
+                View::Overlaps => (&self.overlap_pipeline_layout, "vs_fullscreen", vec![]),
+            };
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(layout),
+                vertex: wgpu::VertexState {
+                    module: &self.module,
+                    entry_point: vertex_entry_point,
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    buffers: &buffers,
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &self.module,
+                    entry_point,
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    targets: &[Some(format.into())],
+                }),
+                multiview: None,
+            })
+        });
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("UV Inspector"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &target.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        match view {
+            View::Density => {
+                pass.set_vertex_buffer(0, vertices.slice(..));
+                pass.draw(0..vertices.size() as u32 / 12, 0..1);
+            }
+            View::Overlaps => pass.draw(0..3, 0..1),
+        }
+
+        Ok(())
+    }
+}
+
+/// The triangles of `mesh` unindexed, each vertex its UV followed by the
+/// triangle's value, or 0 without values.
+fn uv_vertices(
+    context: &Context,
+    mesh: &Mesh,
+    channel: UvChannel,
+    values: &[f32],
+) -> Result<wgpu::Buffer, Error> {
+    let uvs = mesh.channel_uvs(channel)?;
+    let vertices: Vec<[f32; 3]> = mesh
+        .triangles()
+        .enumerate()
+        .flat_map(|(triangle, indices)| {
+            let value = values.get(triangle).copied().unwrap_or(0.0);
+            indices.map(|i| {
+                let [u, v] = uvs[i as usize];
+                [u, v, value]
+            })
+        })
+        .collect();
+    if vertices.is_empty() {
+        return Err(Error::InvalidMesh("no triangles to inspect".to_string()));
+    }
+    context.buffer_with_data(
+        bytemuck::cast_slice(&vertices),
+        wgpu::BufferUsages::VERTEX,
+        Some("UV Inspector Vertices"),
+    )
+}
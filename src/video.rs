@@ -0,0 +1,387 @@
+use super::context::Context;
+use super::diagnostics::{describe_texture, PassRecord};
+use super::error::Error;
+use super::texture::Texture;
+use super::texture_builder::TextureBuilder;
+
+const INTERLEAVED_CHROMA: u32 = 1;
+const SRGB_TARGET: u32 = 2;
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    rows: [[f32; 4]; 3],
+    flags: u32,
+    _padding: [u32; 3],
+}
+
+/// Plane layout of 8-bit 4:2:0 YUV frames, as decoders and cameras
+/// produce them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum YuvFormat {
+    /// A luma plane followed by one plane of interleaved Cb and Cr.
+    Nv12,
+    /// Luma, Cb and Cr planes.
+    I420,
+}
+
+impl YuvFormat {
+    fn plane_count(self) -> usize {
+        match self {
+            YuvFormat::Nv12 => 2,
+            YuvFormat::I420 => 3,
+        }
+    }
+}
+
+/// The matrix coefficients YUV frames were encoded with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum YuvMatrix {
+    /// Standard definition video.
+    Bt601,
+    /// HD video.
+    #[default]
+    Bt709,
+    /// UHD and HDR video.
+    Bt2020,
+}
+
+impl YuvMatrix {
+    /// The luma weights of red and blue.
+    fn coefficients(self) -> (f32, f32) {
+        match self {
+            YuvMatrix::Bt601 => (0.299, 0.114),
+            YuvMatrix::Bt709 => (0.2126, 0.0722),
+            YuvMatrix::Bt2020 => (0.2627, 0.0593),
+        }
+    }
+}
+
+/// The code values YUV frames use.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum YuvRange {
+    /// Luma in [16, 235] and chroma in [16, 240], as most video is.
+    #[default]
+    Limited,
+    /// The whole [0, 255] range, as JPEG and many cameras use.
+    Full,
+}
+
+/// One plane of a YUV frame, with rows `bytes_per_row` apart, which may
+/// be more than the plane's width to allow for decoder padding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct YuvPlane<'a> {
+    pub data: &'a [u8],
+    pub bytes_per_row: u32,
+}
+
+impl<'a> YuvPlane<'a> {
+    pub fn new(data: &'a [u8], bytes_per_row: u32) -> Self {
+        Self {
+            data,
+            bytes_per_row,
+        }
+    }
+}
+
+/// Uploads the planes of YUV video frames or camera feeds into plane
+/// textures and converts them into an RGBA `Texture` with a fullscreen
+/// pass, frame after frame.
+pub struct VideoTexture {
+    format: YuvFormat,
+    planes: Vec<Texture>,
+    output: Texture,
+    params: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    pipeline: wgpu::RenderPipeline,
+    /// Matrix of the frames, BT.709 by default.
+    pub matrix: YuvMatrix,
+    /// Range of the frames, limited by default.
+    pub range: YuvRange,
+}
+
+impl VideoTexture {
+    /// A `width` x `height` video texture of `format` frames, converted
+    /// into a render target of `output_format`. Chroma planes of odd sizes
+    /// are rounded up.
+    pub fn new(
+        context: &Context,
+        width: &u32,
+        height: &u32,
+        format: YuvFormat,
+        output_format: &wgpu::TextureFormat,
+    ) -> Result<Self, Error> {
+        let (chroma_width, chroma_height) = (width.div_ceil(2), height.div_ceil(2));
+        let plane = |width, height, format, label| {
+            TextureBuilder::new(width, height, format)
+                .label(label)
+                .build(context)
+        };
+        let mut planes = vec![plane(
+            *width,
+            *height,
+            wgpu::TextureFormat::R8Unorm,
+            "Video Luma Plane",
+        )?];
+        match format {
+            YuvFormat::Nv12 => planes.push(plane(
+                chroma_width,
+                chroma_height,
+                wgpu::TextureFormat::Rg8Unorm,
+                "Video Chroma Plane",
+            )?),
+            YuvFormat::I420 => {
+                for label in ["Video Cb Plane", "Video Cr Plane"] {
+                    planes.push(plane(
+                        chroma_width,
+                        chroma_height,
+                        wgpu::TextureFormat::R8Unorm,
+                        label,
+                    )?);
+                }
+            }
+        }
+        let output = context.render_target(width, height, output_format, Some("Video Texture"))?;
+
+        let device = &context.device;
+        let module = device.create_shader_module(wgpu::include_wgsl!("shaders/video.wgsl"));
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Video Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                texture_entry(1),
+                texture_entry(2),
+                texture_entry(3),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Video Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Video Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vs_main",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fs_main",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                targets: &[Some((*output_format).into())],
+            }),
+            multiview: None,
+        });
+        let params = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Video Params"),
+            size: std::mem::size_of::<Params>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let sampler = context.linear_sampler();
+        // NV12 binds its interleaved chroma plane twice.
+        let chroma_v = &planes[planes.len() - 1];
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Video Bind Group"),
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&planes[0].view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&planes[1].view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(&chroma_v.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        Ok(Self {
+            format,
+            planes,
+            output,
+            params,
+            bind_group,
+            pipeline,
+            matrix: YuvMatrix::default(),
+            range: YuvRange::default(),
+        })
+    }
+
+    pub fn format(&self) -> YuvFormat {
+        self.format
+    }
+
+    /// The converted RGBA frame.
+    pub fn texture(&self) -> &Texture {
+        &self.output
+    }
+
+    /// The luma plane followed by the chroma planes, as last uploaded.
+    pub fn planes(&self) -> &[Texture] {
+        &self.planes
+    }
+
+    /// Uploads a frame, luma first and then the chroma planes of the
+    /// format, without converting it.
+    pub fn upload(&self, context: &Context, planes: &[YuvPlane]) -> Result<(), Error> {
+        if planes.len() != self.format.plane_count() {
+            return Err(Error::InvalidVideoFrame(format!(
+                "{} planes for {:?}, which has {}",
+                planes.len(),
+                self.format,
+                self.format.plane_count()
+            )));
+        }
+        for (plane, texture) in planes.iter().zip(&self.planes) {
+            let size = texture.size();
+            let row_bytes = size.width * texture.format().block_copy_size(None).unwrap_or(1);
+            if plane.bytes_per_row < row_bytes {
+                return Err(Error::InvalidVideoFrame(format!(
+                    "rows of {} bytes for a plane {row_bytes} bytes wide",
+                    plane.bytes_per_row
+                )));
+            }
+            let expected = plane.bytes_per_row as u64 * (size.height - 1) as u64 + row_bytes as u64;
+            if (plane.data.len() as u64) < expected {
+                return Err(Error::DataSizeMismatch {
+                    expected,
+                    actual: plane.data.len() as u64,
+                });
+            }
+            context.queue.write_texture(
+                texture.texture.as_image_copy(),
+                plane.data,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(plane.bytes_per_row),
+                    rows_per_image: Some(size.height),
+                },
+                size,
+            );
+        }
+        Ok(())
+    }
+
+    /// Records the conversion of the uploaded frame into `texture`.
+    pub fn convert(&self, context: &Context, encoder: &mut wgpu::CommandEncoder) {
+        let mut flags = 0;
+        if self.format == YuvFormat::Nv12 {
+            flags |= INTERLEAVED_CHROMA;
+        }
+        if self.output.format().is_srgb() {
+            flags |= SRGB_TARGET;
+        }
+        context.queue.write_buffer(
+            &self.params,
+            0,
+            bytemuck::bytes_of(&Params {
+                rows: conversion_rows(self.matrix, self.range),
+                flags,
+                _padding: [0; 3],
+            }),
+        );
+
+        context.diagnostics.record_pass(|| {
+            PassRecord::new(
+                "Video Conversion",
+                "Video Pipeline",
+                vec![describe_texture("output", &self.output.texture)],
+            )
+        });
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Video Conversion"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &self.output.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+
+    /// Uploads and converts a frame, submitting the conversion.
+    pub fn update(&self, context: &Context, planes: &[YuvPlane]) -> Result<(), Error> {
+        self.upload(context, planes)?;
+        context.schedule(|encoder| self.convert(context, encoder));
+        Ok(())
+    }
+}
+
+/// Rows of the affine map from normalized (Y, Cb, Cr, 1) samples to R'G'B'.
+fn conversion_rows(matrix: YuvMatrix, range: YuvRange) -> [[f32; 4]; 3] {
+    let (kr, kb) = matrix.coefficients();
+    let kg = 1.0 - kr - kb;
+    let chroma_offset = 128.0 / 255.0;
+    let (luma_offset, luma_scale, chroma_scale) = match range {
+        YuvRange::Limited => (16.0 / 255.0, 255.0 / 219.0, 255.0 / 224.0),
+        YuvRange::Full => (0.0, 1.0, 1.0),
+    };
+    // Weights of Cb and Cr in R, G and B.
+    let weights = [
+        [0.0, 2.0 * (1.0 - kr)],
+        [-2.0 * kb * (1.0 - kb) / kg, -2.0 * kr * (1.0 - kr) / kg],
+        [2.0 * (1.0 - kb), 0.0],
+    ];
+    weights.map(|[cb, cr]| {
+        let (cb, cr) = (cb * chroma_scale, cr * chroma_scale);
+        [
+            luma_scale,
+            cb,
+            cr,
+            -luma_scale * luma_offset - (cb + cr) * chroma_offset,
+        ]
+    })
+}
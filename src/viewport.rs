@@ -0,0 +1,74 @@
+use super::texture::Texture;
+
+/// A rectangle of a render target to draw into, for split-screen views,
+/// minimaps and 3D views embedded in a UI. Set it on a render pass before
+/// each draw with `apply` or `apply_scissor`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Viewport {
+    /// x, y, width and height in pixels from the top left.
+    Pixels([f32; 4]),
+    /// x, y, width and height as fractions of the target size, from the
+    /// top left, `[0.0, 0.0, 1.0, 1.0]` being the whole target.
+    Normalized([f32; 4]),
+}
+
+impl Viewport {
+    /// The whole target.
+    pub const FULL: Self = Self::Normalized([0.0, 0.0, 1.0, 1.0]);
+
+    /// Cell (`column`, `row`) of a split screen of `columns` by `rows`
+    /// equal views.
+    pub fn grid(columns: u32, rows: u32, column: u32, row: u32) -> Self {
+        let (width, height) = (1.0 / columns.max(1) as f32, 1.0 / rows.max(1) as f32);
+        Self::Normalized([column as f32 * width, row as f32 * height, width, height])
+    }
+
+    /// x, y, width and height in pixels of a `width` by `height` target.
+    pub fn pixels(&self, width: u32, height: u32) -> [f32; 4] {
+        match *self {
+            Self::Pixels(rect) => rect,
+            Self::Normalized([x, y, w, h]) => {
+                let (width, height) = (width as f32, height as f32);
+                [x * width, y * height, w * width, h * height]
+            }
+        }
+    }
+
+    /// Width over height, for the projection of the view drawn into it.
+    pub fn aspect(&self, width: u32, height: u32) -> f32 {
+        let [_, _, width, height] = self.pixels(width, height);
+        width / height
+    }
+
+    /// The pixels covered, rounded outwards and clipped to the target, as
+    /// a scissor rectangle (x, y, width, height). Empty outside the target.
+    pub fn scissor_rect(&self, width: u32, height: u32) -> [u32; 4] {
+        let [x, y, w, h] = self.pixels(width, height);
+        let clip = |value: f32, size: u32| (value.max(0.0) as u32).min(size);
+        let (left, top) = (clip(x.floor(), width), clip(y.floor(), height));
+        let (right, bottom) = (clip((x + w).ceil(), width), clip((y + h).ceil(), height));
+        [
+            left,
+            top,
+            right.saturating_sub(left),
+            bottom.saturating_sub(top),
+        ]
+    }
+
+    /// Maps the following draws of `pass` into the viewport of `target`,
+    /// with the full depth range, and restricts them to it with the
+    /// scissor rectangle. The viewport has to lie within the target.
+    pub fn apply(&self, pass: &mut wgpu::RenderPass<'_>, target: &Texture) {
+        let [x, y, width, height] = self.pixels(target.width(), target.height());
+        pass.set_viewport(x, y, width, height, 0.0, 1.0);
+        self.apply_scissor(pass, target);
+    }
+
+    /// Restricts the following draws of `pass` to the viewport of `target`
+    /// without changing how they are mapped, e.g. for a clear-colored
+    /// fullscreen draw behind one view.
+    pub fn apply_scissor(&self, pass: &mut wgpu::RenderPass<'_>, target: &Texture) {
+        let [x, y, width, height] = self.scissor_rect(target.width(), target.height());
+        pass.set_scissor_rect(x, y, width, height);
+    }
+}
@@ -0,0 +1,143 @@
+use super::context::Context;
+use super::error::Error;
+use super::texture::Texture;
+use ash::vk::{self, Handle};
+use wgpu::hal::api::Vulkan;
+
+// OpenXR reports swapchain formats as raw `VkFormat` values when the session
+// uses the Vulkan graphics binding.
+const FORMATS: [(vk::Format, wgpu::TextureFormat); 12] = [
+    (vk::Format::R8G8B8A8_UNORM, wgpu::TextureFormat::Rgba8Unorm),
+    (
+        vk::Format::R8G8B8A8_SRGB,
+        wgpu::TextureFormat::Rgba8UnormSrgb,
+    ),
+    (vk::Format::B8G8R8A8_UNORM, wgpu::TextureFormat::Bgra8Unorm),
+    (
+        vk::Format::B8G8R8A8_SRGB,
+        wgpu::TextureFormat::Bgra8UnormSrgb,
+    ),
+    (
+        vk::Format::A2B10G10R10_UNORM_PACK32,
+        wgpu::TextureFormat::Rgb10a2Unorm,
+    ),
+    (
+        vk::Format::B10G11R11_UFLOAT_PACK32,
+        wgpu::TextureFormat::Rg11b10Float,
+    ),
+    (
+        vk::Format::R16G16B16A16_SFLOAT,
+        wgpu::TextureFormat::Rgba16Float,
+    ),
+    (
+        vk::Format::R32G32B32A32_SFLOAT,
+        wgpu::TextureFormat::Rgba32Float,
+    ),
+    (vk::Format::D16_UNORM, wgpu::TextureFormat::Depth16Unorm),
+    (vk::Format::D32_SFLOAT, wgpu::TextureFormat::Depth32Float),
+    (
+        vk::Format::D24_UNORM_S8_UINT,
+        wgpu::TextureFormat::Depth24PlusStencil8,
+    ),
+    (
+        vk::Format::D32_SFLOAT_S8_UINT,
+        wgpu::TextureFormat::Depth32FloatStencil8,
+    ),
+];
+
+/// The texture format for a `VkFormat` offered by an OpenXR runtime.
+pub fn xr_swapchain_format(vk_format: i64) -> Option<wgpu::TextureFormat> {
+    FORMATS
+        .iter()
+        .find(|(format, _)| format.as_raw() as i64 == vk_format)
+        .map(|(_, format)| *format)
+}
+
+/// The `VkFormat` to request from an OpenXR runtime for `texture_format`.
+pub fn xr_vk_format(texture_format: wgpu::TextureFormat) -> Option<i64> {
+    FORMATS
+        .iter()
+        .find(|(_, format)| *format == texture_format)
+        .map(|(format, _)| format.as_raw() as i64)
+}
+
+impl Context {
+    /// Wraps the `VkImage` handles of an OpenXR swapchain as render targets.
+    /// Multiview swapchains (`array_size > 1`) get `D2Array` views. The
+    /// images stay owned by the runtime and are not destroyed with the
+    /// textures.
+    ///
+    /// # Safety
+    ///
+    /// The context must run on the Vulkan device the OpenXR session was
+    /// created with, and the swapchain must have been created with
+    /// `width`, `height`, `array_size` and `texture_format`, a sample count
+    /// of 1 and the color (or depth/stencil) attachment and sampled usages.
+    pub unsafe fn textures_from_xr_swapchain(
+        &self,
+        images: &[u64],
+        width: &u32,
+        height: &u32,
+        array_size: u32,
+        texture_format: &wgpu::TextureFormat,
+    ) -> Result<Vec<Texture>, Error> {
+        let backend = self.adapter.get_info().backend;
+        if backend != wgpu::Backend::Vulkan {
+            return Err(Error::UnsupportedBackend(backend));
+        }
+        let size = wgpu::Extent3d {
+            width: *width,
+            height: *height,
+            depth_or_array_layers: array_size,
+        };
+        self.check_texture_size(&size, wgpu::TextureDimension::D2)?;
+
+        let attachment = if texture_format.has_depth_aspect() || texture_format.has_stencil_aspect()
+        {
+            wgpu::hal::TextureUses::DEPTH_STENCIL_READ | wgpu::hal::TextureUses::DEPTH_STENCIL_WRITE
+        } else {
+            wgpu::hal::TextureUses::COLOR_TARGET
+        };
+        let hal_descriptor = wgpu::hal::TextureDescriptor {
+            label: Some("XR Swapchain Image"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: *texture_format,
+            usage: attachment | wgpu::hal::TextureUses::RESOURCE,
+            memory_flags: wgpu::hal::MemoryFlags::empty(),
+            view_formats: Vec::new(),
+        };
+        let descriptor = wgpu::TextureDescriptor {
+            label: Some("XR Swapchain Image"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: *texture_format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        };
+
+        let textures = images
+            .iter()
+            .map(|&image| {
+                // A drop guard keeps hal from destroying the runtime's image.
+                let hal_texture = unsafe {
+                    wgpu::hal::vulkan::Device::texture_from_raw(
+                        vk::Image::from_raw(image),
+                        &hal_descriptor,
+                        Some(Box::new(())),
+                    )
+                };
+                let texture = unsafe {
+                    self.device
+                        .create_texture_from_hal::<Vulkan>(hal_texture, &descriptor)
+                };
+                Texture::from_wgpu(self, texture)
+            })
+            .collect();
+        Ok(textures)
+    }
+}
@@ -0,0 +1,93 @@
+//! Buffer writes and reads that fail with an error instead of a wgpu panic.
+
+mod common;
+
+use wgpu_tools::{ChunkedBuffer, Context, Error};
+
+fn buffer(context: &Context, size: u64) -> wgpu::Buffer {
+    context.device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    })
+}
+
+#[test]
+fn chunked_writes_reject_unaligned_ranges() {
+    let Some(context) = common::context() else {
+        return;
+    };
+    let target = buffer(&context, 16);
+    context
+        .write_buffer_chunked(&target, 4, &[1, 2, 3, 4, 5, 6, 7, 8])
+        .unwrap();
+    assert_eq!(
+        context.read_buffer_range(&target, 4, 8).unwrap(),
+        [1, 2, 3, 4, 5, 6, 7, 8]
+    );
+    let result = context.write_buffer_chunked(&target, 2, &[0; 4]);
+    assert!(matches!(
+        result,
+        Err(Error::UnalignedBufferOffset { offset: 2, .. })
+    ));
+    let result = context.write_buffer_chunked(&target, 0, &[0; 3]);
+    assert!(matches!(
+        result,
+        Err(Error::UnalignedBufferSize { size: 3, .. })
+    ));
+    let result = context.write_buffer_chunked(&target, 12, &[0; 8]);
+    assert!(matches!(
+        result,
+        Err(Error::BufferTooSmall { required: 20, .. })
+    ));
+    // The bytes after a rejected write are untouched.
+    assert_eq!(
+        context.read_buffer_range(&target, 8, 4).unwrap(),
+        [5, 6, 7, 8]
+    );
+}
+
+#[test]
+fn reads_stay_inside_the_buffer() {
+    let Some(context) = common::context() else {
+        return;
+    };
+    let source = buffer(&context, 16);
+    assert_eq!(context.read_buffer_range(&source, 12, 3).unwrap().len(), 3);
+    let result = context.read_buffer_range(&source, 6, 4);
+    assert!(matches!(
+        result,
+        Err(Error::UnalignedBufferOffset { offset: 6, .. })
+    ));
+    let result = context.read_buffer_range(&source, 12, 5);
+    assert!(matches!(
+        result,
+        Err(Error::BufferTooSmall { required: 20, .. })
+    ));
+}
+
+#[test]
+fn chunked_buffers_only_end_unaligned() {
+    let Some(context) = common::context() else {
+        return;
+    };
+    let usage = wgpu::BufferUsages::STORAGE;
+    let buffer = ChunkedBuffer::new(&context, 10, 4, usage, None).unwrap();
+    buffer.write(&context, 0, &[1; 4]).unwrap();
+    buffer.write(&context, 4, &[2; 6]).unwrap();
+    assert_eq!(
+        buffer.read(&context).unwrap(),
+        [1, 1, 1, 1, 2, 2, 2, 2, 2, 2]
+    );
+    let result = buffer.write(&context, 0, &[3; 5]);
+    assert!(matches!(
+        result,
+        Err(Error::UnalignedBufferSize { size: 5, .. })
+    ));
+    let result = buffer.write(&context, 1, &[3; 4]);
+    assert!(matches!(
+        result,
+        Err(Error::UnalignedBufferOffset { offset: 1, .. })
+    ));
+}
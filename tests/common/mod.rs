@@ -0,0 +1,15 @@
+//! Fixtures shared by the integration tests.
+
+use wgpu_tools::Context;
+
+/// The default context, or `None` with a note when there is no adapter, so
+/// that tests needing a GPU pass on machines without one.
+pub fn context() -> Option<Context> {
+    match pollster::block_on(Context::default()) {
+        Ok(context) => Some(context),
+        Err(error) => {
+            eprintln!("skipping, no adapter: {error}");
+            None
+        }
+    }
+}
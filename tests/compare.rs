@@ -0,0 +1,38 @@
+//! Golden-image comparisons of known textures.
+
+mod common;
+
+use wgpu_tools::TextureComparer;
+
+const SIZE: u32 = 8;
+
+#[test]
+fn identical_and_one_pixel_differences() {
+    let Some(context) = common::context() else {
+        return;
+    };
+    let format = wgpu::TextureFormat::Rgba8Unorm;
+    let mut data = vec![64; (SIZE * SIZE * 4) as usize];
+    let first = context
+        .texture_with_data(&data, &SIZE, &SIZE, &format, None)
+        .unwrap();
+    let comparer = TextureComparer::new(&context);
+
+    let report = comparer.compare(&context, &first, &first).unwrap();
+    assert!(report.is_match(), "{report}");
+    assert_eq!(report.max_error, 0.0);
+    assert_eq!(report.psnr(), f32::INFINITY);
+
+    // Red of pixel (3, 5) goes from 64 to 192.
+    data[((5 * SIZE + 3) * 4) as usize] = 192;
+    let second = context
+        .texture_with_data(&data, &SIZE, &SIZE, &format, None)
+        .unwrap();
+    let report = comparer.compare(&context, &first, &second).unwrap();
+    assert!(!report.is_match());
+    assert_eq!(report.differing_pixels, 1);
+    assert!((report.max_error - 128.0 / 255.0).abs() < 1e-3, "{report}");
+    let channels = (SIZE * SIZE * 4) as f32;
+    assert!((report.mean_error - 128.0 / 255.0 / channels).abs() < 1e-4);
+    assert!(report.psnr().is_finite());
+}
@@ -0,0 +1,52 @@
+//! Futures waiting on a `BackgroundPoller` when it goes away.
+
+mod common;
+
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Poll, Wake, Waker};
+use std::time::Duration;
+use wgpu_tools::{BackgroundPoller, PollingStrategy};
+
+#[derive(Default)]
+struct Flag(AtomicBool);
+
+impl Wake for Flag {
+    fn wake(self: Arc<Self>) {
+        self.0.store(true, Ordering::Release);
+    }
+}
+
+#[test]
+fn dropping_the_poller_wakes_pending_futures() {
+    let Some(context) = common::context().map(Arc::new) else {
+        return;
+    };
+    let source = context.device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: 64,
+        usage: wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    });
+    // Long enough that the thread polls only once, when it starts.
+    let poller = BackgroundPoller::start(&context, Duration::from_secs(3600));
+    assert_eq!(context.polling_strategy(), PollingStrategy::Background);
+
+    let flag = Arc::new(Flag::default());
+    let waker = Waker::from(flag.clone());
+    let mut cx = std::task::Context::from_waker(&waker);
+    let mut read = Box::pin(context.read_buffer_async(&source));
+    if let Poll::Ready(data) = read.as_mut().poll(&mut cx) {
+        assert_eq!(data.unwrap().len(), 64);
+        return;
+    }
+
+    drop(poller);
+    assert_eq!(context.polling_strategy(), PollingStrategy::Blocking);
+    assert!(flag.0.load(Ordering::Acquire), "pending read was not woken");
+    match read.as_mut().poll(&mut cx) {
+        Poll::Ready(data) => assert_eq!(data.unwrap(), [0; 64]),
+        Poll::Pending => panic!("woken read is still pending"),
+    }
+}
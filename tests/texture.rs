@@ -0,0 +1,212 @@
+//! Texture validation that fails with an error instead of a wgpu panic.
+
+mod common;
+
+use wgpu::{TextureDimension, TextureFormat, TextureUsages, TextureViewDimension};
+use wgpu_tools::{Context, ContextBuilder, Error, RenderTargetBuilder, TextureBuilder};
+
+fn invalid(context: &Context, builder: TextureBuilder) -> String {
+    match builder.build(context) {
+        Err(Error::InvalidTexture(message)) => message,
+        other => panic!("expected an invalid texture, got {other:?}"),
+    }
+}
+
+#[test]
+fn builder_rejects_invalid_textures() {
+    let Some(context) = common::context() else {
+        return;
+    };
+    let format = TextureFormat::Rgba8Unorm;
+    let builder = || TextureBuilder::new(16, 16, format);
+    builder().build(&context).unwrap();
+
+    let message = invalid(&context, TextureBuilder::new(0, 16, format));
+    assert!(message.contains("zero-sized"), "{message}");
+    let one_d = builder().dimension(TextureDimension::D1);
+    assert!(invalid(&context, one_d).contains("1D texture of height 16"));
+    let unused = builder().usage(TextureUsages::empty());
+    assert_eq!(invalid(&context, unused), "no usages");
+    let message = invalid(&context, builder().mip_level_count(6));
+    assert!(message.contains("6 mip levels"), "{message}");
+    let cube = builder().view_dimension(TextureViewDimension::Cube);
+    assert!(invalid(&context, cube).contains("view dimension Cube"));
+
+    let views = [TextureFormat::Bgra8Unorm];
+    let message = invalid(&context, builder().view_formats(&views));
+    assert!(message.contains("incompatible"), "{message}");
+    let views = [TextureFormat::Rgba8UnormSrgb];
+    let srgb = builder().view_formats(&views).build(&context);
+    let flags = context.adapter.get_downlevel_capabilities().flags;
+    match flags.contains(wgpu::DownlevelFlags::VIEW_FORMATS) {
+        true => assert!(srgb.is_ok()),
+        false => {
+            assert!(matches!(srgb, Err(Error::InvalidTexture(m)) if m.contains("VIEW_FORMATS")))
+        }
+    }
+
+    let bc1 = TextureFormat::Bc1RgbaUnorm;
+    if context.device.features().contains(bc1.required_features()) {
+        let message = invalid(&context, TextureBuilder::new(6, 8, bc1));
+        assert!(message.contains("4x4 blocks"), "{message}");
+    }
+}
+
+#[test]
+fn views_reject_missing_usage_and_layers() {
+    let Some(context) = common::context() else {
+        return;
+    };
+    let format = TextureFormat::Rgba8Unorm;
+    let sampled = TextureBuilder::new(16, 16, format).build(&context).unwrap();
+    let message = match sampled.storage_view(0) {
+        Err(Error::InvalidTexture(message)) => message,
+        other => panic!("expected an invalid texture, got {other:?}"),
+    };
+    assert!(message.contains("STORAGE_BINDING"), "{message}");
+    assert!(sampled.layer_view(0).is_ok());
+
+    let volume = TextureBuilder::new(4, 4, format)
+        .dimension(TextureDimension::D3)
+        .depth_or_array_layers(4)
+        .build(&context)
+        .unwrap();
+    for result in [volume.layer_view(0), volume.subresource_view(0, 0)] {
+        assert!(
+            matches!(result, Err(Error::InvalidTexture(m)) if m == "D3 textures have no layers")
+        );
+    }
+}
+
+#[test]
+fn write_region_rejects_bad_regions() {
+    let Some(context) = common::context() else {
+        return;
+    };
+    let format = TextureFormat::Rgba8Unorm;
+    let texture = TextureBuilder::new(8, 8, format).build(&context).unwrap();
+    let extent = |width, height| wgpu::Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+    };
+    let origin = |x, y| wgpu::Origin3d { x, y, z: 0 };
+    let data = vec![0; 4 * 4 * 4];
+    texture
+        .write_region(&context, origin(4, 4), extent(4, 4), &data)
+        .unwrap();
+    for (at, size) in [((5, 4), (4, 4)), ((0, 0), (9, 1)), ((u32::MAX, 0), (4, 4))] {
+        let result =
+            texture.write_region(&context, origin(at.0, at.1), extent(size.0, size.1), &data);
+        assert!(
+            matches!(result, Err(Error::RegionOutOfBounds { .. })),
+            "{result:?}"
+        );
+    }
+
+    let unwritable = TextureBuilder::new(8, 8, format)
+        .usage(TextureUsages::TEXTURE_BINDING)
+        .build(&context)
+        .unwrap();
+    let result = unwritable.write_region(&context, origin(0, 0), extent(4, 4), &data);
+    assert!(matches!(result, Err(Error::InvalidTexture(m)) if m.contains("COPY_DST")));
+
+    let bc1 = TextureFormat::Bc1RgbaUnorm;
+    let features = wgpu::Features::TEXTURE_COMPRESSION_BC;
+    let Ok(context) = pollster::block_on(ContextBuilder::new().features(features).build()) else {
+        eprintln!("skipping misaligned regions, no BC compression");
+        return;
+    };
+    let texture = TextureBuilder::new(8, 8, bc1).build(&context).unwrap();
+    let data = vec![0; 8];
+    texture
+        .write_region(&context, origin(4, 0), extent(4, 4), &data)
+        .unwrap();
+    for (at, size) in [((2, 0), (4, 4)), ((0, 4), (4, 2))] {
+        let result =
+            texture.write_region(&context, origin(at.0, at.1), extent(size.0, size.1), &data);
+        assert!(
+            matches!(&result, Err(Error::InvalidTexture(m)) if m.contains("4x4 blocks")),
+            "{result:?}"
+        );
+    }
+}
+
+#[test]
+fn replaced_textures_drop_cached_views() {
+    let Some(context) = common::context() else {
+        return;
+    };
+    let mut texture = TextureBuilder::new(16, 16, TextureFormat::Rgba8Unorm)
+        .mip_level_count(3)
+        .usage(TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_SRC)
+        .build(&context)
+        .unwrap();
+    texture.mip_view(2).unwrap();
+    texture.layer_view(0).unwrap();
+    context.drop_top_mips(&mut texture, 1).unwrap();
+    assert_eq!((texture.width(), texture.mip_level_count()), (8, 2));
+    texture.mip_view(1).unwrap();
+    texture.subresource_view(1, 0).unwrap();
+    assert!(matches!(
+        texture.mip_view(2),
+        Err(Error::SubresourceOutOfRange {
+            index: 2,
+            count: 2,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn drop_top_mips_reports_its_reason() {
+    let Some(context) = common::context() else {
+        return;
+    };
+    let format = TextureFormat::Rgba8Unorm;
+    let mut unreadable = TextureBuilder::new(16, 16, format)
+        .mip_level_count(3)
+        .build(&context)
+        .unwrap();
+    let result = context.drop_top_mips(&mut unreadable, 1);
+    assert!(matches!(result, Err(Error::InvalidTexture(m)) if m.contains("COPY_SRC")));
+    let result = context.drop_top_mips(&mut unreadable, 3);
+    assert!(
+        matches!(result, Err(Error::InvalidTexture(m)) if m == "cannot drop 3 of 3 mip levels")
+    );
+
+    let features = wgpu::Features::TEXTURE_COMPRESSION_BC;
+    let Ok(context) = pollster::block_on(ContextBuilder::new().features(features).build()) else {
+        eprintln!("skipping block-compressed mips, no BC compression");
+        return;
+    };
+    let mut texture = TextureBuilder::new(12, 12, TextureFormat::Bc1RgbaUnorm)
+        .mip_level_count(2)
+        .usage(TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_SRC)
+        .build(&context)
+        .unwrap();
+    let result = context.drop_top_mips(&mut texture, 1);
+    assert!(matches!(result, Err(Error::InvalidTexture(m)) if m.contains("4x4 blocks")));
+}
+
+#[test]
+fn render_targets_report_their_reason() {
+    let Some(context) = common::context() else {
+        return;
+    };
+    let invalid = |builder: RenderTargetBuilder| match builder.build(&context) {
+        Err(Error::InvalidTexture(message)) => message,
+        other => panic!("expected an invalid render target, got {other:?}"),
+    };
+    RenderTargetBuilder::new(8, 8, TextureFormat::Rgba8Unorm)
+        .build(&context)
+        .unwrap();
+    let snorm = RenderTargetBuilder::new(8, 8, TextureFormat::Rgba8Snorm);
+    assert_eq!(invalid(snorm), "Rgba8Snorm is not renderable");
+    let empty = RenderTargetBuilder::new(0, 8, TextureFormat::Rgba8Unorm);
+    assert!(invalid(empty).contains("zero-sized"));
+    let transient = RenderTargetBuilder::new(8, 8, TextureFormat::Rgba8Unorm)
+        .transient(true)
+        .usage(TextureUsages::COPY_DST);
+    assert!(invalid(transient).contains("transient"));
+}
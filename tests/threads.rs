@@ -0,0 +1,92 @@
+//! Creates resources on worker threads while the main thread renders with
+//! the same `Arc<Context>`.
+
+mod common;
+
+use std::sync::Arc;
+
+const SIZE: u32 = 16;
+const WORKERS: usize = 4;
+const FRAMES: usize = 32;
+
+#[test]
+fn resources_on_worker_threads_while_rendering() {
+    let Some(context) = common::context().map(Arc::new) else {
+        return;
+    };
+    let format = wgpu::TextureFormat::Rgba8Unorm;
+    // Only written on a validation error, named per process so parallel
+    // runs do not share it.
+    let dump = format!("wgpu-tools-threads-{}.json", std::process::id());
+    context.enable_diagnostics(4, std::env::temp_dir().join(dump));
+
+    let workers: Vec<_> = (0..WORKERS)
+        .map(|worker| {
+            let context = context.clone();
+            std::thread::spawn(move || {
+                let value = worker as u8 + 1;
+                let mut results = Vec::new();
+                for _ in 0..FRAMES / 4 {
+                    let data = vec![value; (SIZE * SIZE * 4) as usize];
+                    let texture = context
+                        .texture_with_data(&data, &SIZE, &SIZE, &format, Some("worker texture"))
+                        .unwrap();
+                    let buffer = context
+                        .buffer_with_data(
+                            &data,
+                            wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::STORAGE,
+                            Some("worker buffer"),
+                        )
+                        .unwrap();
+                    let pooled = context
+                        .pooled_texture(&SIZE, &SIZE, &format, wgpu::TextureUsages::COPY_DST)
+                        .unwrap();
+                    context.record_pass("worker", None, &["texture", "buffer"]);
+                    results.push((texture, buffer, pooled));
+                }
+                (value, results)
+            })
+        })
+        .collect();
+
+    let target = context
+        .render_target(&SIZE, &SIZE, &format, Some("main target"))
+        .unwrap();
+    for frame in 0..FRAMES {
+        context.record_pass("clear", None, &["main target"]);
+        let green = frame as f64 / (FRAMES - 1) as f64;
+        context.schedule(|encoder| {
+            encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("clear"),
+                color_attachments: &[Some(target.color_attachment(
+                    None,
+                    wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 1.0,
+                        g: green,
+                        b: 0.0,
+                        a: 1.0,
+                    }),
+                ))],
+                ..Default::default()
+            });
+        });
+    }
+
+    for worker in workers {
+        let (value, results) = worker.join().unwrap();
+        for (texture, buffer, pooled) in &results {
+            let expected = vec![value; (SIZE * SIZE * 4) as usize];
+            assert_eq!(context.read_texture(texture).unwrap(), expected);
+            assert_eq!(context.read_buffer(buffer).unwrap(), expected);
+            assert_eq!((pooled.width(), pooled.height()), (SIZE, SIZE));
+        }
+    }
+    let pixels = context.read_texture(&target).unwrap();
+    assert!(pixels.chunks(4).all(|pixel| pixel == [255, 255, 0, 255]));
+
+    assert_eq!(context.texture_pool().in_use(), WORKERS * FRAMES / 4);
+    context.texture_pool().recall();
+    assert_eq!(context.texture_pool().in_use(), 0);
+    assert_eq!(context.texture_pool().free(), WORKERS * FRAMES / 4);
+    assert!(!context.recorded_submissions().is_empty());
+}